@@ -0,0 +1,285 @@
+//! Block structure of each source line from a full CommonMark parse
+//!
+//! The line renderers recognise blocks from the line itself (and a little
+//! state: open fences, list nesting), which misses whatever CommonMark
+//! decides from context: indented code blocks, lazy continuation lines of
+//! quotes and list items, setext headings. [`classify`] runs pulldown-cmark
+//! over the whole document and maps its block events back to the lines
+//! they cover, so a renderer can still work one source line at a time.
+
+use crate::config::MarkdownFlavor;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use ropey::Rope;
+use std::ops::Range;
+
+/// The leaf block a line belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeafKind {
+    /// Blank, or only container markers (`>`)
+    #[default]
+    Blank,
+    Paragraph,
+    /// An ATX heading, or the text of a setext heading
+    Heading(u8),
+    /// The `===`/`---` line under a setext heading
+    HeadingUnderline(u8),
+    /// The opening or closing line of a fenced code block
+    Fence,
+    /// Inside a fenced code block
+    Code,
+    /// A line of an indented code block
+    IndentedCode,
+    Html,
+    Table,
+    Rule,
+}
+
+/// Where a line sits in the document's block structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineBlock {
+    pub leaf: LeafKind,
+    /// Number of block quotes around the line
+    pub quote_depth: usize,
+    /// Number of list items around the line (1 = top-level item)
+    pub list_depth: usize,
+    /// The line holds the marker of the innermost list item
+    pub item_start: bool,
+    /// Number of an ordered item, counted from its list's start
+    pub item_number: Option<u64>,
+    /// Column where the innermost list item's content starts, counted
+    /// after the quote markers
+    pub list_indent: usize,
+}
+
+/// Block-level parser extensions of a Markdown flavor
+pub fn parser_options(flavor: MarkdownFlavor) -> Options {
+    match flavor {
+        MarkdownFlavor::Gfm => {
+            Options::ENABLE_TABLES
+                | Options::ENABLE_FOOTNOTES
+                | Options::ENABLE_STRIKETHROUGH
+                | Options::ENABLE_WIKILINKS
+        }
+        MarkdownFlavor::CommonMark => Options::ENABLE_FOOTNOTES,
+    }
+}
+
+/// Block structure of every line of `text` (one entry per line, including
+/// the empty line after a trailing newline)
+pub fn classify(text: &str, flavor: MarkdownFlavor) -> Vec<LineBlock> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut lines = vec![LineBlock::default(); line_starts.len()];
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+    // Lines covered by a range; an empty range still covers its line
+    let span =
+        |range: &Range<usize>| line_of(range.start)..=line_of(range.end.max(range.start + 1) - 1);
+
+    // Numbers of the ordered lists open around the current event
+    let mut list_numbers: Vec<Option<u64>> = Vec::new();
+    // The item whose content column is taken from its first child
+    let mut pending_item: Option<(Range<usize>, usize)> = None;
+
+    for (event, range) in Parser::new_ext(text, parser_options(flavor)).into_offset_iter() {
+        if let Some((item, marker)) = pending_item.take() {
+            let first = line_of(item.start);
+            let column = if line_of(range.start) == first && range.start > item.start {
+                range.start - line_starts[first]
+            } else {
+                // Empty item, or content starting on a later line
+                marker
+            };
+            let indent = column.saturating_sub(quote_prefix_len(
+                line_text(text, &line_starts, first),
+                lines[first].quote_depth,
+            ));
+            for line in span(&item) {
+                lines[line].list_indent = indent;
+            }
+        }
+
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => {
+                for line in span(&range) {
+                    lines[line].quote_depth += 1;
+                }
+            }
+            Event::Start(Tag::List(start)) => list_numbers.push(start),
+            Event::End(pulldown_cmark::TagEnd::List(_)) => {
+                list_numbers.pop();
+            }
+            Event::Start(Tag::Item) => {
+                let first = line_of(range.start);
+                let number = list_numbers.last_mut().and_then(|n| {
+                    let current = *n;
+                    *n = n.map(|value| value + 1);
+                    current
+                });
+                for line in span(&range) {
+                    lines[line].list_depth += 1;
+                    lines[line].item_start = false;
+                }
+                lines[first].item_start = true;
+                lines[first].item_number = number;
+                let marker_end = text[range.clone()]
+                    .find(char::is_whitespace)
+                    .map_or(range.len(), |i| i + 1);
+                pending_item = Some((range.clone(), range.start + marker_end - line_starts[first]));
+            }
+            Event::Start(Tag::Paragraph) => set_leaf(&mut lines, span(&range), LeafKind::Paragraph),
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = level as u8;
+                let lines_of = span(&range);
+                let (first, last) = (*lines_of.start(), *lines_of.end());
+                set_leaf(&mut lines, lines_of, LeafKind::Heading(level));
+                let atx = text[range.clone()].trim_start().starts_with('#');
+                if !atx && last > first {
+                    lines[last].leaf = LeafKind::HeadingUnderline(level);
+                }
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Indented)) => {
+                set_leaf(&mut lines, span(&range), LeafKind::IndentedCode)
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(_))) => {
+                let lines_of = span(&range);
+                let (first, last) = (*lines_of.start(), *lines_of.end());
+                set_leaf(&mut lines, lines_of, LeafKind::Code);
+                lines[first].leaf = LeafKind::Fence;
+                let closing = line_text(text, &line_starts, last);
+                let closing = closing[quote_prefix_len(closing, lines[last].quote_depth)..].trim();
+                if last > first && is_closing_fence(closing) {
+                    lines[last].leaf = LeafKind::Fence;
+                }
+            }
+            Event::Start(Tag::HtmlBlock) => set_leaf(&mut lines, span(&range), LeafKind::Html),
+            Event::Start(Tag::Table(_)) => set_leaf(&mut lines, span(&range), LeafKind::Table),
+            Event::Rule => set_leaf(&mut lines, span(&range), LeafKind::Rule),
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Source lines of each GitHub Flavored Markdown table, header to last
+/// row, so a `|` in prose doesn't make a table
+pub fn extract_tables(rope: &Rope) -> Vec<Range<usize>> {
+    let text = rope.to_string();
+    Parser::new_ext(&text, Options::ENABLE_TABLES)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::Table(_))))
+        .map(|(_, range)| {
+            let last = rope.byte_to_line(range.end.saturating_sub(1).max(range.start));
+            rope.byte_to_line(range.start)..last + 1
+        })
+        .collect()
+}
+
+fn set_leaf(lines: &mut [LineBlock], range: std::ops::RangeInclusive<usize>, leaf: LeafKind) {
+    for line in range {
+        lines[line].leaf = leaf;
+    }
+}
+
+fn line_text<'a>(text: &'a str, line_starts: &[usize], line: usize) -> &'a str {
+    let end = line_starts.get(line + 1).copied().unwrap_or(text.len());
+    text[line_starts[line]..end].trim_end_matches(['\n', '\r'])
+}
+
+fn is_closing_fence(line: &str) -> bool {
+    let Some(c) = line.chars().next().filter(|c| *c == '`' || *c == '~') else {
+        return false;
+    };
+    line.len() >= 3 && line.chars().all(|d| d == c)
+}
+
+/// Length of the quote markers (`> > `) in front of a line inside
+/// `depth` block quotes; lazy continuation lines have none
+pub fn quote_prefix_len(line: &str, depth: usize) -> usize {
+    let mut offset = 0;
+    for _ in 0..depth {
+        let rest = &line[offset..];
+        let spaces = rest.len() - rest.trim_start_matches(' ').len();
+        if spaces > 3 || !rest[spaces..].starts_with('>') {
+            break;
+        }
+        offset += spaces + 1;
+        if line[offset..].starts_with(' ') {
+            offset += 1;
+        }
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(text: &str) -> Vec<LeafKind> {
+        classify(text, MarkdownFlavor::Gfm)
+            .into_iter()
+            .map(|line| line.leaf)
+            .collect()
+    }
+
+    #[test]
+    fn test_indented_code_and_setext_headings() {
+        use LeafKind::*;
+        assert_eq!(
+            leaves("Title\n=====\n\n    let x = 1;\n\ntext\n    lazy\n"),
+            [
+                Heading(1),
+                HeadingUnderline(1),
+                Blank,
+                IndentedCode,
+                Blank,
+                Paragraph,
+                Paragraph,
+                Blank
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lazy_quote_continuation() {
+        let lines = classify("> quoted\nlazy line\n\nafter\n", MarkdownFlavor::Gfm);
+        let depths: Vec<_> = lines.iter().map(|l| l.quote_depth).collect();
+        assert_eq!(depths, [1, 1, 0, 0, 0]);
+        assert_eq!(lines[1].leaf, LeafKind::Paragraph);
+        assert_eq!(quote_prefix_len("> > deep", 2), 4);
+        assert_eq!(quote_prefix_len("lazy", 1), 0);
+    }
+
+    #[test]
+    fn test_list_items_and_fences() {
+        let lines = classify(
+            "1. one\n   more\n2. two\n   ```\n   code\n   ```\n- a\n  - b\n",
+            MarkdownFlavor::Gfm,
+        );
+        assert!(lines[0].item_start);
+        assert_eq!(lines[0].item_number, Some(1));
+        assert_eq!(lines[1].list_indent, 3);
+        assert!(!lines[1].item_start);
+        assert_eq!(lines[2].item_number, Some(2));
+        let leaves: Vec<_> = lines[3..6].iter().map(|l| l.leaf).collect();
+        assert_eq!(leaves, [LeafKind::Fence, LeafKind::Code, LeafKind::Fence]);
+        assert_eq!((lines[7].list_depth, lines[7].list_indent), (2, 4));
+    }
+
+    #[test]
+    fn test_tables_only_in_gfm() {
+        let text = "a | b\n--|--\n1 | 2\n\nnot | a table\n";
+        let tables = |flavor| -> Vec<bool> {
+            classify(text, flavor)
+                .iter()
+                .map(|line| line.leaf == LeafKind::Table)
+                .collect()
+        };
+        assert_eq!(
+            tables(MarkdownFlavor::Gfm),
+            [true, true, true, false, false, false]
+        );
+        assert!(!tables(MarkdownFlavor::CommonMark).contains(&true));
+        assert_eq!(extract_tables(&Rope::from(text)), vec![(0..3)]);
+    }
+}
@@ -6,6 +6,9 @@ use std::path::PathBuf;
 
 use crate::security::SecurityEvent;
 
+/// Per-project config file, looked up from the document's directory upward
+pub const PROJECT_CONFIG_FILE: &str = ".mdx.toml";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct RenderConfig {
@@ -15,10 +18,68 @@ pub struct RenderConfig {
     /// Number of visual rows preserved between pages when using PgUp/PgDn.
     /// 2 matches less/vim. Clamped to [0, page_height/2] at use time.
     pub page_overlap_rows: usize,
+    /// Lines of context kept above and below the cursor when the view
+    /// follows it, like vim's `scrolloff`. Shrinks to fit small panes.
+    pub scrolloff: usize,
+    /// Animate the scroll of page jumps (`Ctrl-d`, `PageDown`, ...) over a
+    /// few frames instead of jumping straight there
+    pub smooth_scroll: bool,
     /// When true and the viewport has scrolled past the heading of the
     /// current section, the active heading is pinned to the first content
     /// row of the pane.
     pub sticky_heading: bool,
+    /// Reorder right-to-left text (Arabic, Hebrew) into visual order and
+    /// right-align RTL rows. Disable to show text in logical order.
+    pub bidi: bool,
+    /// Render `--`/`---` as en/em dashes and straight quotes as curly
+    /// quotes in prose.
+    pub smart_punctuation: bool,
+    /// Show raw HTML lines as their text content instead of literal tags
+    pub strip_html: bool,
+    /// Cap the content column at this many columns and center it in wider
+    /// panes. 0 uses the full pane width.
+    pub max_width: usize,
+    /// Start in focus mode: sections other than the one under the cursor
+    /// are dimmed and line numbers, gutters, and the status bar are hidden
+    pub focus_mode: bool,
+    /// Show a row of tabs above the panes when more than one file is open
+    pub tab_bar: bool,
+    /// Prefix headings with section numbers (1, 1.1, 2.3.4) in the content
+    /// pane and TOC
+    pub number_headings: bool,
+    /// Number ordered list items sequentially from the list's first number
+    /// (nested lists as i, ii and a, b like GitHub) whatever the source
+    /// says, e.g. when every item is written `1.`
+    pub renumber_lists: bool,
+    /// Break long words in wrapped paragraphs at syllable boundaries
+    /// (needs the `hyphenation` build feature)
+    pub hyphenate: bool,
+    /// Widen the gaps between words so wrapped paragraph rows reach the
+    /// right edge
+    pub justify: bool,
+    /// Columns of background between the gutter and the text of a code
+    /// block
+    pub code_padding: usize,
+    /// Where a fenced code block shows its language label
+    pub code_label: CodeLabel,
+    /// Style lines by a full CommonMark parse of the document instead of
+    /// line by line, so indented code blocks, lazy continuation lines, and
+    /// nested quotes render as CommonMark reads them
+    pub commonmark: bool,
+    /// What the gutter numbers count: source lines, distance from the
+    /// cursor line (for `5j`-style motions), both, or nothing
+    pub line_numbers: LineNumbers,
+    /// Show the file or section named by a line of just
+    /// `![[file.md#section]]` or `<!-- include: file.md#section -->`
+    /// below that line, unless `security.safe_mode` is on
+    pub transclude: bool,
+    /// Show `<!-- comments -->` dimmed instead of hiding them
+    pub show_comments: bool,
+    /// Output for terminal screen readers: blank borders, plain ASCII
+    /// markers, and structure spelled out ("Heading level 2:") instead of
+    /// shown by box drawing or color alone. Set by `--a11y`.
+    pub a11y: bool,
+    pub markdown: MarkdownConfig,
 }
 
 impl Default for RenderConfig {
@@ -28,7 +89,209 @@ impl Default for RenderConfig {
             show_scrollbar: true,
             skip_front_matter: true,
             page_overlap_rows: 2,
+            scrolloff: 0,
+            smooth_scroll: false,
             sticky_heading: false,
+            bidi: true,
+            smart_punctuation: false,
+            strip_html: true,
+            max_width: 0,
+            focus_mode: false,
+            tab_bar: true,
+            number_headings: false,
+            renumber_lists: false,
+            hyphenate: false,
+            justify: false,
+            code_padding: 1,
+            code_label: CodeLabel::Right,
+            commonmark: false,
+            line_numbers: LineNumbers::Absolute,
+            transclude: true,
+            show_comments: false,
+            a11y: false,
+            markdown: MarkdownConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    /// Which Markdown extensions are recognised
+    pub flavor: MarkdownFlavor,
+}
+
+/// Markdown dialect documents are read as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum MarkdownFlavor {
+    /// GitHub Flavored Markdown: tables, `~~strikethrough~~`, and bare
+    /// URLs as links; Obsidian-style `[[wiki links]]` are recognised too
+    #[default]
+    Gfm,
+    /// Plain CommonMark, without extensions
+    CommonMark,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentConfig {
+    /// Largest file (in bytes) mdx will load. 0 disables the limit.
+    pub max_file_bytes: u64,
+}
+
+impl Default for DocumentConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: crate::doc::DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinksConfig {
+    /// Check relative links and images against the filesystem and mark
+    /// broken ones
+    pub check: bool,
+    /// Also send HTTP HEAD requests for remote URLs. Requires the
+    /// `remote-links` build feature and is disabled by safe mode.
+    pub check_remote: bool,
+    /// Root of an Obsidian vault: `[[Page]]` links not found next to the
+    /// linking file are looked up below it, by path and then by file name
+    pub vault_root: Option<PathBuf>,
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            check: true,
+            check_remote: false,
+            vault_root: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+    /// Ask the terminal for the kitty keyboard protocol, so keys that
+    /// legacy terminals send identically (Ctrl+I and Tab, Enter and
+    /// Shift+Enter) arrive as distinct keys. Terminals without it are
+    /// unaffected.
+    pub enhanced_keys: bool,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            enhanced_keys: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Move to the first match after the cursor while the query is typed;
+    /// Esc goes back to where the search started. Off, matches are only
+    /// highlighted until Enter.
+    pub incremental: bool,
+    /// Matching lines found up front. Past this many the count shows as
+    /// `N+` and `n`/`N` find the rest as they get there, so searching a
+    /// common word in a huge document stays quick. 0 finds them all.
+    pub max_matches: usize,
+    /// Match text inside HTML comments, which search skips otherwise
+    pub include_comments: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            incremental: true,
+            max_matches: 10_000,
+            include_comments: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Column `mdx fmt` and `:fmt` wrap paragraphs at. 0 keeps their line
+    /// breaks.
+    pub width: usize,
+}
+
+/// Reading positions kept between runs (see [`crate::session`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Put the cursor back where it was when a file is opened again
+    pub restore: bool,
+    /// Seconds between saves of the session while it changes, so a crash
+    /// or dropped connection loses little. 0 saves only on exit.
+    pub autosave_secs: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            restore: true,
+            autosave_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpellConfig {
+    /// Underline misspelled words in prose
+    pub enabled: bool,
+    /// Hunspell `.dic` file; the `.aff` file beside it is read too.
+    /// Empty searches the usual system locations for `language`.
+    pub dictionary: String,
+    /// Dictionary name used for the system search, e.g. `en_GB`
+    pub language: String,
+    /// Extra words to accept
+    pub words: Vec<String>,
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dictionary: String::new(),
+            language: "en_US".to_string(),
+            words: Vec::new(),
+        }
+    }
+}
+
+/// Status bar layout. Each side is a template where `{token}` is replaced
+/// by a value: `{file}`, `{modified}`, `{encoding}`, `{buffer}`, `{line}`,
+/// `{lines}`, `{column}`, `{percent}`, `{position}`, `{mode}`,
+/// `{selection}`, `{headings}`, `{theme}`, `{toc}`, `{prefix}`, `{watch}`,
+/// `{search}`, `{fold}`, `{links}`, `{spell}`, and `{git}`. Tokens with nothing to show
+/// also drop the spaces before them. `{percent}` counts source lines;
+/// `{position}` is the less/vim `Top`/`Bot`/`NN%` over wrapped rows.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusBarConfig {
+    /// Left-aligned part, truncated when the terminal is too narrow
+    pub left: String,
+    /// Right-aligned part, dropped when it doesn't fit on its own
+    pub right: String,
+    /// Finish the right side with a vim-style `line,column  position` ruler
+    pub ruler: bool,
+}
+
+impl Default for StatusBarConfig {
+    fn default() -> Self {
+        Self {
+            left: " {file}{modified} {encoding} {buffer} [{mode}] {selection} {prefix} {search} {watch} {links} {spell} {git}"
+                .to_string(),
+            right: "{toc} {line}/{lines} {position} ".to_string(),
+            ruler: false,
         }
     }
 }
@@ -41,6 +304,14 @@ pub struct Config {
     pub editor: EditorConfig,
     pub security: SecurityConfig,
     pub render: RenderConfig,
+    pub document: DocumentConfig,
+    pub links: LinksConfig,
+    pub spell: SpellConfig,
+    pub statusbar: StatusBarConfig,
+    pub input: InputConfig,
+    pub search: SearchConfig,
+    pub format: FormatConfig,
+    pub session: SessionConfig,
     #[cfg(feature = "watch")]
     pub watch: WatchConfig,
     #[cfg(feature = "git")]
@@ -53,6 +324,59 @@ pub struct Config {
 pub enum ThemeVariant {
     Dark,
     Light,
+    /// Pure black and white with bright accents, for low vision
+    HighContrast,
+    /// Solarized Dark
+    Solarized,
+    /// Shades of gray only; structure is shown with bold, italic, and
+    /// underline
+    Monochrome,
+    /// Blue/orange accents that stay apart for red-green colorblindness
+    DeuteranopiaSafe,
+}
+
+impl ThemeVariant {
+    /// Every variant, in the order `m` and the options dialog cycle
+    /// through them
+    pub const ALL: [Self; 6] = [
+        Self::Dark,
+        Self::Light,
+        Self::HighContrast,
+        Self::Solarized,
+        Self::Monochrome,
+        Self::DeuteranopiaSafe,
+    ];
+
+    /// The variant after this one in [`Self::ALL`], wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|v| *v == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Name used by `--theme` and the status bar, e.g. `high-contrast`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+            Self::HighContrast => "high-contrast",
+            Self::Solarized => "solarized",
+            Self::Monochrome => "monochrome",
+            Self::DeuteranopiaSafe => "deuteranopia-safe",
+        }
+    }
+
+    /// Parse a theme name, ignoring case, `-`, and `_`, so both
+    /// `high-contrast` and `HighContrast` work
+    pub fn from_name(name: &str) -> Option<Self> {
+        let key: String = name
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        Self::ALL
+            .into_iter()
+            .find(|v| v.name().replace('-', "") == key)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +384,10 @@ pub struct TocConfig {
     pub enabled: bool,
     pub side: TocSide,
     pub width: u16,
+    /// Show each section's size (lines, words, code blocks) after its
+    /// heading
+    #[serde(default)]
+    pub stats: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -68,6 +396,31 @@ pub enum TocSide {
     Right,
 }
 
+/// Placement of the language label on fenced code blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeLabel {
+    /// Right-aligned on the block's first row
+    Right,
+    /// On a header row of its own, in place of the opening fence
+    Left,
+    Hidden,
+}
+
+/// Numbering shown in the gutter of the content pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    /// Source line numbers
+    Absolute,
+    /// Distance from the cursor line, 0 on the cursor line
+    Relative,
+    /// Distance from the cursor line, and the source line number on the
+    /// cursor line
+    Hybrid,
+    /// No line numbers; the text starts at the gutter
+    Off,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditorConfig {
     pub command: String,
@@ -90,9 +443,12 @@ pub struct WatchConfig {
 
 #[cfg(feature = "git")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GitConfig {
     pub diff: bool,
     pub base: GitBase,
+    /// Show removed base lines as virtual lines under the diff mark
+    pub show_deleted: bool,
 }
 
 #[cfg(feature = "git")]
@@ -110,6 +466,14 @@ pub struct ImageConfig {
     pub allow_absolute: bool,
     pub allow_remote: bool,
     pub max_bytes: u64,
+    /// Memory budget for cached images in MiB, counted as decoded RGBA
+    /// size; 0 disables the cache
+    pub cache_mb: u64,
+    /// Show the title (or alt text) as a line beneath each image
+    pub caption: bool,
+    /// Start with images shown as their alt text only, without reading
+    /// the files (toggled with `I`)
+    pub alt_text: bool,
 }
 
 impl Default for Config {
@@ -120,6 +484,14 @@ impl Default for Config {
             editor: EditorConfig::default(),
             security: SecurityConfig::default(),
             render: RenderConfig::default(),
+            document: DocumentConfig::default(),
+            links: LinksConfig::default(),
+            spell: SpellConfig::default(),
+            statusbar: StatusBarConfig::default(),
+            input: InputConfig::default(),
+            search: SearchConfig::default(),
+            format: FormatConfig::default(),
+            session: SessionConfig::default(),
             #[cfg(feature = "watch")]
             watch: WatchConfig::default(),
             #[cfg(feature = "git")]
@@ -136,6 +508,7 @@ impl Default for TocConfig {
             enabled: false,
             side: TocSide::Left,
             width: 32,
+            stats: false,
         }
     }
 }
@@ -174,6 +547,7 @@ impl Default for GitConfig {
         Self {
             diff: true,
             base: GitBase::Head,
+            show_deleted: false,
         }
     }
 }
@@ -186,41 +560,55 @@ impl Default for ImageConfig {
             allow_absolute: false,
             allow_remote: false,
             max_bytes: 10 * 1024 * 1024,
+            cache_mb: 64,
+            caption: false,
+            alt_text: false,
         }
     }
 }
 
 impl Config {
+    /// Turn off the settings `render.a11y` rules out: box-drawing graphics
+    /// and the scrollbar
+    pub fn apply_a11y(&mut self) {
+        if self.render.a11y {
+            self.render.use_utf8_graphics = false;
+            self.render.show_scrollbar = false;
+        }
+    }
+
     /// Get the platform-specific config file path
     pub fn config_path() -> Option<PathBuf> {
         directories::ProjectDirs::from("", "", "mdx")
             .map(|proj_dirs| proj_dirs.config_dir().join("mdx.toml"))
     }
 
+    /// Get the log file path used by `--verbose`: the XDG state directory
+    /// where the platform has one, the local data directory elsewhere
+    pub fn log_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "mdx").map(|proj_dirs| {
+            proj_dirs
+                .state_dir()
+                .unwrap_or_else(|| proj_dirs.data_local_dir())
+                .join("mdx.log")
+        })
+    }
+
+    /// Get the session file path, next to the log file
+    pub fn session_path() -> Option<PathBuf> {
+        Self::log_path().map(|path| path.with_file_name("session.json"))
+    }
+
     /// Load configuration from file, falling back to defaults if missing
     /// Returns (Config, Vec<SecurityEvent>) where events track security-related settings
+    #[allow(clippy::needless_return)]
     pub fn load() -> Result<(Self, Vec<SecurityEvent>)> {
         let warnings = Vec::new();
         let config_path = Self::config_path();
 
         if let Some(path) = config_path {
             if path.exists() {
-                // Check config file permissions (Unix only)
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    let metadata = std::fs::metadata(&path)?;
-                    let perms = metadata.permissions();
-                    if perms.mode() & 0o002 != 0 {
-                        anyhow::bail!(
-                            "Config file {} is world-writable (insecure permissions)",
-                            path.display()
-                        );
-                    }
-                }
-
-                let content = std::fs::read_to_string(&path)
-                    .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+                let content = read_config_file(&path)?;
 
                 let config: Config = toml::from_str(&content)
                     .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
@@ -255,23 +643,9 @@ impl Config {
     }
 
     /// Load from a specific path (for testing)
+    #[allow(clippy::needless_return)]
     pub fn load_from(path: &std::path::Path) -> Result<Self> {
-        // Check config file permissions (Unix only)
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let metadata = std::fs::metadata(path)?;
-            let perms = metadata.permissions();
-            if perms.mode() & 0o002 != 0 {
-                anyhow::bail!(
-                    "Config file {} is world-writable (insecure permissions)",
-                    path.display()
-                );
-            }
-        }
-
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let content = read_config_file(path)?;
 
         let config: Config = toml::from_str(&content)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
@@ -332,11 +706,20 @@ impl Config {
 
     /// Save configuration to the default config file path
     /// Overwrites the existing file if it exists
-    pub fn save_to_file(config: &Self) -> Result<()> {
+    pub fn save_to_file(config: &Self) -> Result<PathBuf> {
         let config_path = Self::config_path().context("Could not determine config file path")?;
+        config.save_to(&config_path)?;
+        Ok(config_path)
+    }
 
+    /// Save configuration to a specific path.
+    ///
+    /// Settings are written into the existing file where possible, so
+    /// comments, key order, and keys this version doesn't know about are
+    /// kept. A file that doesn't parse is replaced.
+    pub fn save_to(&self, path: &std::path::Path) -> Result<()> {
         // Create parent directory if it doesn't exist
-        if let Some(parent) = config_path.parent() {
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).with_context(|| {
                 format!("Failed to create config directory: {}", parent.display())
             })?;
@@ -344,23 +727,245 @@ impl Config {
 
         // Serialize config to TOML
         let toml_string =
-            toml::to_string_pretty(config).context("Failed to serialize config to TOML")?;
+            toml::to_string_pretty(self).context("Failed to serialize config to TOML")?;
+        let existing = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| content.parse::<toml_edit::DocumentMut>().ok());
+        let output = match existing {
+            Some(mut doc) => {
+                let fresh: toml_edit::DocumentMut = toml_string
+                    .parse()
+                    .context("Failed to serialize config to TOML")?;
+                merge_table(doc.as_table_mut(), fresh.as_table());
+                doc.to_string()
+            }
+            None => toml_string,
+        };
 
         // Write to file
-        std::fs::write(&config_path, toml_string)
-            .with_context(|| format!("Failed to write config file: {}", config_path.display()))?;
+        std::fs::write(path, output)
+            .with_context(|| format!("Failed to write config file: {}", path.display()))?;
 
         // Set proper permissions (Unix only)
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = std::fs::metadata(&config_path)?.permissions();
+            let mut perms = std::fs::metadata(path)?.permissions();
             perms.set_mode(0o644); // rw-r--r--
-            std::fs::set_permissions(&config_path, perms)?;
+            std::fs::set_permissions(path, perms)?;
         }
 
         Ok(())
     }
+
+    /// Load the user configuration with the nearest project `.mdx.toml`
+    /// for `doc_path` merged over it. Without a path (standard input) the
+    /// search starts in the current directory.
+    pub fn load_for(doc_path: Option<&std::path::Path>) -> Result<(Self, Vec<SecurityEvent>)> {
+        let (config, mut warnings) = Self::load()?;
+        let dir = match doc_path {
+            Some(path) => std::path::absolute(path)
+                .ok()
+                .and_then(|path| path.parent().map(PathBuf::from)),
+            None => std::env::current_dir().ok(),
+        };
+        let project = dir.as_deref().and_then(Self::find_project_config);
+        let Some(project) = project else {
+            return Ok((config, warnings));
+        };
+        let (config, project_warnings) = config.with_project_file(&project)?;
+        warnings.extend(project_warnings);
+        Ok((config, warnings))
+    }
+
+    /// Nearest project config file in `dir` or one of its ancestors
+    pub fn find_project_config(dir: &std::path::Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|ancestor| ancestor.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Merge a project config file over this configuration.
+    ///
+    /// Project files come with the documents, so they are not trusted like
+    /// the user's own file: security settings can only become stricter,
+    /// and the editor command is never taken from them. Ignored settings
+    /// are reported as warnings.
+    pub fn with_project_file(&self, path: &std::path::Path) -> Result<(Self, Vec<SecurityEvent>)> {
+        let content = read_config_file(path)?;
+        let mut project: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        let mut warnings = Vec::new();
+        let source = path.display().to_string();
+        if project.remove("editor").is_some() {
+            warnings.push(SecurityEvent::warning(
+                "Ignored [editor] settings in project config",
+                source.clone(),
+            ));
+        }
+
+        let mut merged = toml::Table::try_from(self).context("Failed to serialize config")?;
+        merge_values(&mut merged, project);
+        let mut config: Config = toml::Value::Table(merged)
+            .try_into()
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        let mut refuse = |name: &str| {
+            warnings.push(SecurityEvent::warning(
+                format!("Project config cannot loosen {}", name),
+                source.clone(),
+            ));
+        };
+        if self.security.safe_mode && !config.security.safe_mode {
+            config.security.safe_mode = true;
+            refuse("security.safe_mode");
+        }
+        if self.security.no_exec && !config.security.no_exec {
+            config.security.no_exec = true;
+            refuse("security.no_exec");
+        }
+        if !self.links.check_remote && config.links.check_remote {
+            config.links.check_remote = false;
+            refuse("links.check_remote");
+        }
+        if !self.render.transclude && config.render.transclude {
+            config.render.transclude = false;
+            refuse("render.transclude");
+        }
+        if config.links.vault_root != self.links.vault_root {
+            let root = config.links.vault_root.as_deref();
+            match root.and_then(|root| inside_project(path, root)) {
+                Some(root) => config.links.vault_root = Some(root),
+                None => {
+                    config.links.vault_root = self.links.vault_root.clone();
+                    refuse("links.vault_root");
+                }
+            }
+        }
+        if looser_limit(config.document.max_file_bytes, self.document.max_file_bytes) {
+            config.document.max_file_bytes = self.document.max_file_bytes;
+            refuse("document.max_file_bytes");
+        }
+
+        #[cfg(feature = "images")]
+        {
+            if !self.images.allow_absolute && config.images.allow_absolute {
+                config.images.allow_absolute = false;
+                refuse("images.allow_absolute");
+            }
+            if !self.images.allow_remote && config.images.allow_remote {
+                config.images.allow_remote = false;
+                refuse("images.allow_remote");
+            }
+            if looser_limit(config.images.max_bytes, self.images.max_bytes) {
+                config.images.max_bytes = self.images.max_bytes;
+                refuse("images.max_bytes");
+            }
+            if config.security.safe_mode {
+                config.images.enabled = false;
+            }
+        }
+
+        Ok((config, warnings))
+    }
+
+    /// Apply the settings that differ between `before` and `after` to this
+    /// configuration, leaving everything else as it is
+    pub fn with_changes(&self, before: &Config, after: &Config) -> Result<Self> {
+        let mut base = toml::Table::try_from(self).context("Failed to serialize config")?;
+        let before = toml::Table::try_from(before).context("Failed to serialize config")?;
+        let after = toml::Table::try_from(after).context("Failed to serialize config")?;
+        apply_changes(&mut base, &before, after);
+        toml::Value::Table(base)
+            .try_into()
+            .context("Failed to apply config changes")
+    }
+}
+
+/// `path` as seen from the directory of the project file `project_file`,
+/// if it is inside that directory
+fn inside_project(project_file: &std::path::Path, path: &std::path::Path) -> Option<PathBuf> {
+    let dir = project_file.parent()?.canonicalize().ok()?;
+    let path = dir.join(path).canonicalize().ok()?;
+    path.starts_with(&dir).then_some(path)
+}
+
+/// Whether size limit `limit` allows more than `than`; 0 is no limit
+fn looser_limit(limit: u64, than: u64) -> bool {
+    than != 0 && (limit == 0 || limit > than)
+}
+
+/// Read a config file, refusing world-writable ones
+fn read_config_file(path: &std::path::Path) -> Result<String> {
+    // Check config file permissions (Unix only)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(path)?;
+        let perms = metadata.permissions();
+        if perms.mode() & 0o002 != 0 {
+            anyhow::bail!(
+                "Config file {} is world-writable (insecure permissions)",
+                path.display()
+            );
+        }
+    }
+
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))
+}
+
+/// Recursively overlay `src` onto `dst`; tables merge, other values replace
+fn merge_values(dst: &mut toml::Table, src: toml::Table) {
+    for (key, value) in src {
+        match (dst.get_mut(&key), value) {
+            (Some(toml::Value::Table(dst_table)), toml::Value::Table(src_table)) => {
+                merge_values(dst_table, src_table);
+            }
+            (_, value) => {
+                dst.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Copy into `dst` the values of `after` that differ from `before`
+fn apply_changes(dst: &mut toml::Table, before: &toml::Table, after: toml::Table) {
+    for (key, value) in after {
+        match (before.get(&key), value, dst.get_mut(&key)) {
+            (
+                Some(toml::Value::Table(before_table)),
+                toml::Value::Table(after_table),
+                Some(toml::Value::Table(dst_table)),
+            ) => apply_changes(dst_table, before_table, after_table),
+            (old, value, _) => {
+                if old != Some(&value) {
+                    dst.insert(key, value);
+                }
+            }
+        }
+    }
+}
+
+/// Copy every value from `src` into `dst`, keeping the comments and
+/// formatting already attached to keys in `dst`
+fn merge_table(dst: &mut toml_edit::Table, src: &toml_edit::Table) {
+    for (key, item) in src.iter() {
+        match (dst.get_mut(key), item) {
+            (Some(toml_edit::Item::Table(dst_table)), toml_edit::Item::Table(src_table)) => {
+                merge_table(dst_table, src_table);
+            }
+            (Some(toml_edit::Item::Value(dst_value)), toml_edit::Item::Value(src_value)) => {
+                let decor = dst_value.decor().clone();
+                *dst_value = src_value.clone();
+                *dst_value.decor_mut() = decor;
+            }
+            _ => {
+                dst.insert(key, item.clone());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -536,6 +1141,76 @@ max_bytes = 2048\n";
         }
     }
 
+    #[test]
+    fn test_document_max_file_bytes() -> Result<()> {
+        assert_eq!(
+            Config::default().document.max_file_bytes,
+            crate::doc::DEFAULT_MAX_FILE_SIZE
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[document]\nmax_file_bytes = 0\n")?;
+
+        let config = Config::load_from(file.path())?;
+        assert_eq!(config.document.max_file_bytes, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spell_config() -> Result<()> {
+        assert!(!Config::default().spell.enabled);
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[spell]\nenabled = true\nwords = [\"mdx\", \"ratatui\"]\n")?;
+
+        let config = Config::load_from(file.path())?;
+        assert!(config.spell.enabled);
+        assert_eq!(config.spell.language, "en_US");
+        assert_eq!(config.spell.words, vec!["mdx", "ratatui"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_config() -> Result<()> {
+        assert!(Config::default().input.enhanced_keys);
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[input]\nenhanced_keys = false\n")?;
+
+        let config = Config::load_from(file.path())?;
+        assert!(!config.input.enhanced_keys);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_numbers_config() -> Result<()> {
+        assert_eq!(Config::default().render.line_numbers, LineNumbers::Absolute);
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[render]\nline_numbers = \"hybrid\"\n")?;
+
+        let config = Config::load_from(file.path())?;
+        assert_eq!(config.render.line_numbers, LineNumbers::Hybrid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_statusbar_config() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[statusbar]\nleft = \" {file} {mode}\"\n")?;
+
+        // Setting one side keeps the default for the other
+        let config = Config::load_from(file.path())?;
+        assert_eq!(config.statusbar.left, " {file} {mode}");
+        assert_eq!(config.statusbar.right, StatusBarConfig::default().right);
+
+        Ok(())
+    }
+
     #[test]
     fn test_theme_variant_serialization() -> Result<()> {
         let config = Config {
@@ -551,4 +1226,214 @@ max_bytes = 2048\n";
 
         Ok(())
     }
+
+    #[test]
+    fn test_save_to_keeps_comments() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("mdx.toml");
+        std::fs::write(
+            &path,
+            "# My settings\ntheme = \"Dark\" # night owl\n\n[toc]\n# sidebar\nenabled = false\nside = \"Left\"\nwidth = 32\nextra = 1\n",
+        )?;
+
+        let mut config = Config::load_from(&path)?;
+        config.theme = ThemeVariant::Light;
+        config.toc.enabled = true;
+        config.save_to(&path)?;
+
+        let saved = std::fs::read_to_string(&path)?;
+        assert!(saved.starts_with("# My settings\ntheme = \"Light\" # night owl\n"));
+        assert!(saved.contains("# sidebar\nenabled = true\n"));
+        assert!(saved.contains("extra = 1"), "unknown keys are kept");
+        assert!(saved.contains("[render]"), "missing sections are added");
+
+        let reloaded = Config::load_from(&path)?;
+        assert_eq!(reloaded.theme, ThemeVariant::Light);
+        assert!(reloaded.toc.enabled);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_to_new_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sub").join("mdx.toml");
+        let config = Config {
+            theme: ThemeVariant::Light,
+            ..Default::default()
+        };
+        config.save_to(&path)?;
+        assert_eq!(Config::load_from(&path)?.theme, ThemeVariant::Light);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_project_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let nested = dir.path().join("docs").join("guide");
+        std::fs::create_dir_all(&nested)?;
+        assert_eq!(Config::find_project_config(&nested), None);
+
+        std::fs::write(dir.path().join(PROJECT_CONFIG_FILE), "")?;
+        assert_eq!(
+            Config::find_project_config(&nested),
+            Some(dir.path().join(PROJECT_CONFIG_FILE))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_overrides() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        std::fs::write(
+            &path,
+            "theme = \"Light\"\n[toc]\nwidth = 40\n[links]\ncheck = false\n",
+        )?;
+
+        let (config, warnings) = Config::default().with_project_file(&path)?;
+        assert_eq!(config.theme, ThemeVariant::Light);
+        assert_eq!(config.toc.width, 40);
+        // Keys the project doesn't set keep the user's value
+        assert_eq!(config.toc.side, TocSide::Left);
+        assert!(!config.links.check);
+        assert!(warnings.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_cannot_loosen_security() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        std::fs::write(
+            &path,
+            "[security]\nsafe_mode = false\nno_exec = false\n\n[links]\ncheck_remote = true\n\n[editor]\ncommand = \"evil\"\n",
+        )?;
+
+        let (config, warnings) = Config::default().with_project_file(&path)?;
+        assert!(config.security.safe_mode);
+        assert!(config.security.no_exec);
+        assert!(!config.links.check_remote);
+        assert_eq!(config.editor.command, "$EDITOR");
+        assert_eq!(warnings.len(), 4);
+
+        // Tightening is fine
+        let mut user = Config::default();
+        user.security.no_exec = false;
+        std::fs::write(&path, "[security]\nno_exec = true\n")?;
+        let (config, warnings) = user.with_project_file(&path)?;
+        assert!(config.security.no_exec);
+        assert!(warnings.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_cannot_enable_transclusion() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        std::fs::write(&path, "[render]\ntransclude = true\n")?;
+
+        let mut user = Config::default();
+        user.render.transclude = false;
+        let (config, warnings) = user.with_project_file(&path)?;
+        assert!(!config.render.transclude);
+        assert_eq!(warnings.len(), 1);
+
+        std::fs::write(&path, "[render]\ntransclude = false\n")?;
+        let (config, warnings) = Config::default().with_project_file(&path)?;
+        assert!(!config.render.transclude);
+        assert!(warnings.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_keeps_vault_root_inside_project() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("vault"))?;
+        let path = project.join(PROJECT_CONFIG_FILE);
+
+        // Found from the project directory, wherever mdx was started
+        std::fs::write(&path, "[links]\nvault_root = \"vault\"\n")?;
+        let (config, warnings) = Config::default().with_project_file(&path)?;
+        let vault = project.join("vault").canonicalize()?;
+        assert_eq!(config.links.vault_root, Some(vault));
+        assert!(warnings.is_empty());
+
+        for outside in ["..", "/"] {
+            std::fs::write(&path, format!("[links]\nvault_root = \"{}\"\n", outside))?;
+            let (config, warnings) = Config::default().with_project_file(&path)?;
+            assert_eq!(config.links.vault_root, None, "{}", outside);
+            assert_eq!(warnings.len(), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_config_cannot_raise_max_file_bytes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        let default = crate::doc::DEFAULT_MAX_FILE_SIZE;
+
+        for looser in [default * 2, 0] {
+            std::fs::write(&path, format!("[document]\nmax_file_bytes = {}\n", looser))?;
+            let (config, warnings) = Config::default().with_project_file(&path)?;
+            assert_eq!(config.document.max_file_bytes, default);
+            assert_eq!(warnings.len(), 1);
+        }
+
+        std::fs::write(&path, "[document]\nmax_file_bytes = 1024\n")?;
+        let (config, warnings) = Config::default().with_project_file(&path)?;
+        assert_eq!(config.document.max_file_bytes, 1024);
+        assert!(warnings.is_empty());
+
+        // Without a limit of the user's, any limit is tighter
+        let mut user = Config::default();
+        user.document.max_file_bytes = 0;
+        let (config, _warnings) = user.with_project_file(&path)?;
+        assert_eq!(config.document.max_file_bytes, 1024);
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_changes_only_applies_edits() -> Result<()> {
+        let mut user = Config::default();
+        user.toc.width = 50;
+
+        // The effective config has overrides the user file doesn't
+        let mut before = user.clone();
+        before.security.safe_mode = false;
+        before.theme = ThemeVariant::Light;
+        let mut after = before.clone();
+        after.toc.enabled = true;
+
+        let saved = user.with_changes(&before, &after)?;
+        assert!(saved.toc.enabled);
+        assert_eq!(saved.toc.width, 50);
+        assert!(saved.security.safe_mode);
+        assert_eq!(saved.theme, ThemeVariant::Dark);
+        Ok(())
+    }
+
+    #[test]
+    fn test_theme_variant_names_and_cycle() -> Result<()> {
+        for variant in ThemeVariant::ALL {
+            assert_eq!(ThemeVariant::from_name(variant.name()), Some(variant));
+            assert_eq!(
+                ThemeVariant::from_name(&format!("{:?}", variant)),
+                Some(variant)
+            );
+        }
+        assert_eq!(
+            ThemeVariant::from_name("High_Contrast"),
+            Some(ThemeVariant::HighContrast)
+        );
+        assert_eq!(ThemeVariant::from_name("sepia"), None);
+        assert_eq!(ThemeVariant::Dark.next(), ThemeVariant::Light);
+        assert_eq!(ThemeVariant::DeuteranopiaSafe.next(), ThemeVariant::Dark);
+
+        let config: Config = toml::from_str("theme = \"DeuteranopiaSafe\"\n")?;
+        assert_eq!(config.theme, ThemeVariant::DeuteranopiaSafe);
+        Ok(())
+    }
 }
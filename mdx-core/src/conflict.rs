@@ -0,0 +1,168 @@
+//! Merge conflict markers left in a file by git
+//!
+//! A conflict runs from a `<<<<<<<` line to a `>>>>>>>` line and is split
+//! by a `=======` line into our side and theirs. diff3-style conflicts
+//! also have a `|||||||` line before the separator, starting the merge
+//! base.
+
+use ropey::Rope;
+
+/// One conflict, as 0-based source lines of its markers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    /// The `<<<<<<<` line
+    pub start: usize,
+    /// The `|||||||` line of a diff3-style conflict
+    pub base: Option<usize>,
+    /// The `=======` line
+    pub separator: usize,
+    /// The `>>>>>>>` line
+    pub end: usize,
+}
+
+/// Which version of the text a conflict line belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictSide {
+    Ours,
+    Base,
+    Theirs,
+}
+
+impl Conflict {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    /// Whether `line` is one of the conflict's marker lines
+    pub fn is_marker(&self, line: usize) -> bool {
+        line == self.start || Some(line) == self.base || line == self.separator || line == self.end
+    }
+
+    /// The side `line` is on. A marker line goes with the side it starts,
+    /// and the `>>>>>>>` line with theirs, which it ends.
+    pub fn side(&self, line: usize) -> Option<ConflictSide> {
+        if !self.contains(line) {
+            return None;
+        }
+        Some(if line >= self.separator {
+            ConflictSide::Theirs
+        } else if self.base.is_some_and(|base| line >= base) {
+            ConflictSide::Base
+        } else {
+            ConflictSide::Ours
+        })
+    }
+}
+
+/// Whether `line` is a conflict marker made of `marker`: seven of them,
+/// then the end of the line or a space and a label (`<<<<<<< HEAD`)
+fn is_marker_line(line: &str, marker: char) -> bool {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let rest = line.trim_start_matches(marker);
+    line.len() - rest.len() == 7 && (rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+/// Complete conflicts in the document, in order. Markers that don't form
+/// a whole conflict (a lone `=======` is a setext heading underline) are
+/// left alone.
+pub fn extract_conflicts(rope: &Rope) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut open: Option<Conflict> = None;
+    for (idx, line) in rope.lines().enumerate() {
+        // Markers are short; longer lines can't be one
+        if line.len_chars() > 1024 {
+            continue;
+        }
+        let line = line.to_string();
+        if is_marker_line(&line, '<') {
+            open = Some(Conflict {
+                start: idx,
+                base: None,
+                separator: usize::MAX,
+                end: usize::MAX,
+            });
+            continue;
+        }
+        let Some(conflict) = open.as_mut() else {
+            continue;
+        };
+        let separated = conflict.separator != usize::MAX;
+        if !separated && conflict.base.is_none() && is_marker_line(&line, '|') {
+            conflict.base = Some(idx);
+        } else if !separated && line.trim_end() == "=======" {
+            conflict.separator = idx;
+        } else if separated && is_marker_line(&line, '>') {
+            conflict.end = idx;
+            conflicts.extend(open.take());
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_conflicts() {
+        let text = "# Notes\n\
+                    <<<<<<< HEAD\n\
+                    ours\n\
+                    =======\n\
+                    theirs\n\
+                    >>>>>>> feature\n\
+                    Title\n\
+                    =======\n\
+                    <<<<<<< ours\n\
+                    a\n\
+                    ||||||| base\n\
+                    b\n\
+                    =======\n\
+                    c\n\
+                    >>>>>>>\n\
+                    <<<<<<<< not a marker\n";
+        let conflicts = extract_conflicts(&Rope::from_str(text));
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    start: 1,
+                    base: None,
+                    separator: 3,
+                    end: 5
+                },
+                Conflict {
+                    start: 8,
+                    base: Some(10),
+                    separator: 12,
+                    end: 14
+                },
+            ]
+        );
+
+        let diff3 = conflicts[1];
+        let sides: Vec<_> = (7..=15).map(|line| diff3.side(line)).collect();
+        use ConflictSide::*;
+        assert_eq!(
+            sides,
+            [
+                None,
+                Some(Ours),
+                Some(Ours),
+                Some(Base),
+                Some(Base),
+                Some(Theirs),
+                Some(Theirs),
+                Some(Theirs),
+                None
+            ]
+        );
+        assert!(diff3.is_marker(10) && !diff3.is_marker(11));
+    }
+
+    #[test]
+    fn test_unfinished_conflict_is_ignored() {
+        let rope = Rope::from_str("<<<<<<< HEAD\nours\n=======\ntheirs\n");
+        assert!(extract_conflicts(&rope).is_empty());
+    }
+}
@@ -0,0 +1,409 @@
+//! Turning file bytes into text
+//!
+//! Most files are UTF-8 and stream straight into the rope. UTF-16 and
+//! Latin-1 files are transcoded, and saved back in the same encoding.
+//! Anything else still opens: invalid sequences are replaced, and binary
+//! files are shown as a hex dump and the printable strings found in them.
+
+use anyhow::Result;
+use std::fmt::Write as _;
+
+/// How many leading bytes are checked for NUL when looking for binary files
+pub const SNIFF_BYTES: usize = 8000;
+
+/// Bytes shown in the hex dump of a binary file
+const HEX_DUMP_BYTES: usize = 1024;
+
+/// Shortest run of printable characters listed as a string
+const MIN_STRING_LEN: usize = 4;
+
+/// Most strings listed for a binary file
+const MAX_STRINGS: usize = 2000;
+
+/// UTF-8 byte order mark
+pub const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A text encoding other than plain UTF-8 that files are transcoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    Utf16Le {
+        bom: bool,
+    },
+    Utf16Be {
+        bom: bool,
+    },
+    /// Latin-1, read as its Windows-1252 superset like browsers do
+    Latin1,
+}
+
+impl Encoding {
+    /// Short name for the status bar
+    pub fn label(&self) -> &'static str {
+        match self {
+            Encoding::Utf8Bom => "UTF-8 BOM",
+            Encoding::Utf16Le { .. } => "UTF-16LE",
+            Encoding::Utf16Be { .. } => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+
+    /// The encoding announced by a byte order mark at the start of `bytes`
+    pub fn from_bom(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(UTF8_BOM) {
+            Some(Encoding::Utf8Bom)
+        } else if bytes.starts_with(b"\xFF\xFE") {
+            Some(Encoding::Utf16Le { bom: true })
+        } else if bytes.starts_with(b"\xFE\xFF") {
+            Some(Encoding::Utf16Be { bom: true })
+        } else {
+            None
+        }
+    }
+
+    /// Text of `bytes` in this encoding, without any byte order mark
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match *self {
+            Encoding::Utf8Bom => {
+                String::from_utf8_lossy(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)).into_owned()
+            }
+            Encoding::Utf16Le { bom } | Encoding::Utf16Be { bom } => {
+                let bytes = if bom {
+                    &bytes[2.min(bytes.len())..]
+                } else {
+                    bytes
+                };
+                let little = matches!(self, Encoding::Utf16Le { .. });
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| {
+                        let pair = [pair[0], pair[1]];
+                        if little {
+                            u16::from_le_bytes(pair)
+                        } else {
+                            u16::from_be_bytes(pair)
+                        }
+                    })
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            Encoding::Latin1 => bytes.iter().map(|&byte| windows_1252_char(byte)).collect(),
+        }
+    }
+
+    /// `text` as bytes in this encoding, with the byte order mark it was
+    /// read with. Fails on a character the encoding can't represent.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        Ok(match *self {
+            Encoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+            Encoding::Utf16Le { bom } => {
+                let bom = bom.then_some(0xFEFF);
+                bom.into_iter()
+                    .chain(text.encode_utf16())
+                    .flat_map(u16::to_le_bytes)
+                    .collect()
+            }
+            Encoding::Utf16Be { bom } => {
+                let bom = bom.then_some(0xFEFF);
+                bom.into_iter()
+                    .chain(text.encode_utf16())
+                    .flat_map(u16::to_be_bytes)
+                    .collect()
+            }
+            Encoding::Latin1 => text
+                .chars()
+                .map(|c| {
+                    windows_1252_byte(c).ok_or_else(|| {
+                        anyhow::anyhow!("{:?} can't be saved as {}", c, self.label())
+                    })
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Bytes 0x80..=0x9F of Windows-1252; the five it leaves undefined map to
+/// the same C1 control as in Latin-1
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+fn windows_1252_byte(c: char) -> Option<u8> {
+    if let Some(index) = WINDOWS_1252_HIGH.iter().position(|&high| high == c) {
+        return Some(0x80 + index as u8);
+    }
+    match c as u32 {
+        code @ (0..=0x7F | 0xA0..=0xFF) => Some(code as u8),
+        _ => None,
+    }
+}
+
+/// UTF-16 without a byte order mark: ASCII-heavy text has a zero in
+/// nearly every other byte, on the high-byte side
+pub fn sniff_utf16(head: &[u8]) -> Option<Encoding> {
+    let pairs = head.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros_at = |offset: usize| {
+        head.chunks_exact(2)
+            .filter(|pair| pair[offset] == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let mostly = |count: usize| count * 10 >= pairs * 4;
+    let rarely = |count: usize| count * 20 < pairs;
+    if mostly(odd) && rarely(even) {
+        Some(Encoding::Utf16Le { bom: false })
+    } else if mostly(even) && rarely(odd) {
+        Some(Encoding::Utf16Be { bom: false })
+    } else {
+        None
+    }
+}
+
+/// How the text of a document was obtained from its bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Decoding {
+    /// Valid UTF-8, shown as is
+    #[default]
+    Utf8,
+    /// Converted from another encoding, and saved back in it
+    Transcoded(Encoding),
+    /// Invalid UTF-8; this many bad sequences were replaced with U+FFFD
+    Lossy { replaced: usize },
+    /// Binary data, replaced by a generated preview
+    Binary { bytes: u64 },
+}
+
+impl Decoding {
+    /// Whether saving would write back something other than the original bytes
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, Decoding::Lossy { .. } | Decoding::Binary { .. })
+    }
+
+    /// Short name of the file's encoding for the status bar, empty for
+    /// plain UTF-8
+    pub fn label(&self) -> &'static str {
+        match self {
+            Decoding::Utf8 => "",
+            Decoding::Transcoded(encoding) => encoding.label(),
+            Decoding::Lossy { .. } => "invalid UTF-8",
+            Decoding::Binary { .. } => "binary",
+        }
+    }
+
+    /// One-line explanation for a banner, `None` for text that was read
+    /// faithfully
+    pub fn notice(&self) -> Option<String> {
+        match self {
+            Decoding::Utf8 | Decoding::Transcoded(_) => None,
+            Decoding::Lossy { replaced } => Some(format!(
+                "Not valid UTF-8: {} invalid sequence{} shown as \u{FFFD}; saving is disabled",
+                replaced,
+                if *replaced == 1 { "" } else { "s" }
+            )),
+            Decoding::Binary { bytes } => Some(format!(
+                "Binary file ({} bytes): showing a hex dump and printable strings",
+                bytes
+            )),
+        }
+    }
+}
+
+/// Git's heuristic: a NUL byte near the start means binary
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_BYTES)].contains(&0)
+}
+
+/// Text for bytes that aren't plain UTF-8. A byte order mark or the
+/// UTF-16 pattern picks the encoding; binary files get a preview. Invalid
+/// UTF-8 with no valid multi-byte sequence at all is taken as Latin-1,
+/// otherwise the bad sequences are replaced.
+pub fn decode_bytes(bytes: &[u8]) -> (String, Decoding) {
+    let head = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if let Some(encoding) = Encoding::from_bom(head).or_else(|| sniff_utf16(head)) {
+        return (encoding.decode(bytes), Decoding::Transcoded(encoding));
+    }
+    if looks_binary(bytes) {
+        let decoding = Decoding::Binary {
+            bytes: bytes.len() as u64,
+        };
+        return (binary_preview(bytes), decoding);
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.to_string(), Decoding::Utf8);
+    }
+
+    let mut replaced = 0;
+    let mut multibyte = false;
+    for chunk in bytes.utf8_chunks() {
+        multibyte |= !chunk.valid().is_ascii();
+        replaced += usize::from(!chunk.invalid().is_empty());
+    }
+    if !multibyte {
+        let encoding = Encoding::Latin1;
+        return (encoding.decode(bytes), Decoding::Transcoded(encoding));
+    }
+    let text = String::from_utf8_lossy(bytes).into_owned();
+    (text, Decoding::Lossy { replaced })
+}
+
+/// Markdown standing in for a binary file: a hex dump of the start, then
+/// the printable strings, each in a fenced block
+pub fn binary_preview(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(HEX_DUMP_BYTES)];
+    let mut out = String::from("# Hex dump\n\n");
+    if shown.len() < bytes.len() {
+        let _ = writeln!(out, "First {} of {} bytes.\n", shown.len(), bytes.len());
+    }
+    out.push_str("```text\n");
+    out.push_str(&hex_dump(shown));
+    out.push_str("```\n\n# Strings\n\n");
+
+    let strings = printable_strings(bytes);
+    if strings.is_empty() {
+        out.push_str("No printable strings.\n");
+        return out;
+    }
+    if strings.len() == MAX_STRINGS {
+        let _ = writeln!(out, "The first {} strings.\n", MAX_STRINGS);
+    }
+    out.push_str("```text\n");
+    for string in strings {
+        out.push_str(&string);
+        out.push('\n');
+    }
+    out.push_str("```\n");
+    out
+}
+
+/// `hexdump -C` style lines: offset, sixteen bytes in hex, and the ASCII
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(out, "{:08x} ", row * 16);
+        for column in 0..16 {
+            if column == 8 {
+                out.push(' ');
+            }
+            match chunk.get(column) {
+                Some(byte) => {
+                    let _ = write!(out, " {:02x}", byte);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("  |");
+        out.extend(chunk.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// Runs of printable ASCII at least `MIN_STRING_LEN` long, like `strings`
+pub fn printable_strings(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|&byte| !(byte.is_ascii_graphic() || byte == b' ' || byte == b'\t'))
+        .filter(|run| run.len() >= MIN_STRING_LEN)
+        .take(MAX_STRINGS)
+        .map(|run| String::from_utf8_lossy(run).trim_end().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_lossy_counts_replacements() {
+        let (text, decoding) = decode_bytes(b"caf\xc3\xa9 \xff and na\xefve\n");
+        assert_eq!(text, "caf\u{e9} \u{FFFD} and na\u{FFFD}ve\n");
+        assert_eq!(decoding, Decoding::Lossy { replaced: 2 });
+        assert!(decoding.notice().unwrap().contains("2 invalid sequences"));
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let bytes = b"caf\xe9 \x93quoted\x94 \x80\n";
+        let (text, decoding) = decode_bytes(bytes);
+        assert_eq!(text, "caf\u{e9} \u{201C}quoted\u{201D} \u{20AC}\n");
+        assert_eq!(decoding, Decoding::Transcoded(Encoding::Latin1));
+        assert_eq!(Encoding::Latin1.encode(&text).unwrap(), bytes);
+        assert!(Encoding::Latin1.encode("\u{2192}").is_err());
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let le: Vec<u8> = "# Title\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let (text, decoding) = decode_bytes(&le);
+        assert_eq!(text, "# Title\n");
+        let encoding = Encoding::Utf16Le { bom: false };
+        assert_eq!(decoding, Decoding::Transcoded(encoding));
+        assert_eq!(encoding.encode(&text).unwrap(), le);
+
+        let be: Vec<u8> = [0xFE, 0xFF]
+            .into_iter()
+            .chain("\u{e9}t\u{e9}".encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        let (text, decoding) = decode_bytes(&be);
+        assert_eq!(text, "\u{e9}t\u{e9}");
+        let encoding = Encoding::Utf16Be { bom: true };
+        assert_eq!(decoding, Decoding::Transcoded(encoding));
+        assert_eq!(encoding.encode(&text).unwrap(), be);
+        assert!(!decoding.is_lossy());
+        assert_eq!(decoding.label(), "UTF-16BE");
+    }
+
+    #[test]
+    fn test_decode_utf8_bom() {
+        let (text, decoding) = decode_bytes(b"\xEF\xBB\xBF# Title\n");
+        assert_eq!(text, "# Title\n");
+        assert_eq!(decoding, Decoding::Transcoded(Encoding::Utf8Bom));
+        assert_eq!(
+            Encoding::Utf8Bom.encode(&text).unwrap(),
+            b"\xEF\xBB\xBF# Title\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_preview() {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        bytes.extend_from_slice(b"\0tEXtComment\0made with mdx\0");
+        assert!(looks_binary(&bytes));
+
+        let (text, decoding) = decode_bytes(&bytes);
+        assert_eq!(decoding, Decoding::Binary { bytes: 43 });
+        assert!(text.starts_with("# Hex dump\n\n```text\n00000000  89 50 4e 47"));
+        assert!(text.contains("|.PNG........IHDR|\n"));
+        assert!(text.contains("# Strings\n\n```text\nIHDR\ntEXtComment\nmade with mdx\n```\n"));
+    }
+
+    #[test]
+    fn test_hex_dump_pads_short_rows() {
+        assert_eq!(
+            hex_dump(b"abc"),
+            "00000000  61 62 63                                          |abc|\n"
+        );
+    }
+}
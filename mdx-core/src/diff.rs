@@ -9,10 +9,28 @@ pub enum DiffMark {
     DeletedAfter(u16),
 }
 
+/// What a layer of gutter marks compares the document with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffLayer {
+    /// The git HEAD or index version of the file
+    Git,
+    /// The text as last loaded or saved, so in-app edits show before
+    /// they are written
+    Saved,
+}
+
 /// Diff gutter aligned to working tree lines
 #[derive(Clone, Debug)]
 pub struct DiffGutter {
+    /// Marks of the [`DiffLayer::Git`] layer
     pub marks: Vec<DiffMark>,
+    /// Marks of the [`DiffLayer::Saved`] layer
+    pub saved_marks: Vec<DiffMark>,
+    /// Byte ranges of the changed words on each modified line
+    pub changes: Vec<Vec<std::ops::Range<usize>>>,
+    /// Base lines removed after each line, including lines of a
+    /// replacement that have no counterpart in the new text
+    pub deleted: std::collections::BTreeMap<usize, Vec<String>>,
 }
 
 impl DiffGutter {
@@ -20,12 +38,61 @@ impl DiffGutter {
     pub fn empty(line_count: usize) -> Self {
         Self {
             marks: vec![DiffMark::None; line_count],
+            saved_marks: vec![DiffMark::None; line_count],
+            changes: vec![Vec::new(); line_count],
+            deleted: std::collections::BTreeMap::new(),
         }
     }
 
-    /// Get the mark for a given line (0-indexed)
+    /// Get the git mark for a given line (0-indexed)
     pub fn get(&self, line: usize) -> DiffMark {
-        self.marks.get(line).copied().unwrap_or(DiffMark::None)
+        self.layer(DiffLayer::Git, line)
+    }
+
+    /// Get the mark of one layer for a given line (0-indexed)
+    pub fn layer(&self, layer: DiffLayer, line: usize) -> DiffMark {
+        let marks = match layer {
+            DiffLayer::Git => &self.marks,
+            DiffLayer::Saved => &self.saved_marks,
+        };
+        marks.get(line).copied().unwrap_or(DiffMark::None)
+    }
+
+    /// Fill the saved layer by comparing `current` with the text as it
+    /// was last saved
+    #[cfg(feature = "git")]
+    pub fn set_saved_layer(&mut self, saved: &str, current: &str) {
+        self.saved_marks = diff_gutter_from_text(saved, current).marks;
+    }
+
+    /// Clear the saved layer, as after a save
+    pub fn clear_saved_layer(&mut self) {
+        self.saved_marks.fill(DiffMark::None);
+    }
+
+    /// Line ranges of the hunks: runs of consecutive marked lines
+    pub fn hunks(&self) -> Vec<std::ops::Range<usize>> {
+        let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+        for (line, mark) in self.marks.iter().enumerate() {
+            if *mark == DiffMark::None {
+                continue;
+            }
+            match hunks.last_mut() {
+                Some(last) if last.end == line => last.end = line + 1,
+                _ => hunks.push(line..line + 1),
+            }
+        }
+        hunks
+    }
+
+    /// Changed words of a modified line (0-indexed), as byte ranges
+    pub fn changes(&self, line: usize) -> &[std::ops::Range<usize>] {
+        self.changes.get(line).map_or(&[], Vec::as_slice)
+    }
+
+    /// Base lines removed after a line (0-indexed)
+    pub fn deleted_lines(&self, line: usize) -> &[String] {
+        self.deleted.get(&line).map_or(&[], Vec::as_slice)
     }
 }
 
@@ -38,16 +105,17 @@ pub fn diff_gutter_from_text(base: &str, current: &str) -> DiffGutter {
 
     let current_lines = current.lines().count().max(1);
     let mut marks = vec![DiffMark::None; current_lines];
+    let mut changes = vec![Vec::new(); current_lines];
+    let mut deleted = std::collections::BTreeMap::<usize, Vec<String>>::new();
+    let base_text: Vec<&str> = base.lines().collect();
+    let current_text: Vec<&str> = current.lines().collect();
 
-    // Process grouped ops to properly distinguish modifications from pure additions/deletions
-    let mut current_line_idx = 0;
-
+    // Process grouped ops to properly distinguish modifications from pure additions/deletions.
+    // Groups skip unchanged lines, so positions come from each op's own range.
     for group in diff.grouped_ops(0) {
         for op in &group {
             match op.tag() {
-                DiffTag::Equal => {
-                    current_line_idx += op.new_range().len();
-                }
+                DiffTag::Equal => {}
                 DiffTag::Delete => {
                     // Check if this delete is part of a replacement
                     let is_replacement = group.iter().any(|o| o.tag() == DiffTag::Insert);
@@ -55,6 +123,7 @@ pub fn diff_gutter_from_text(base: &str, current: &str) -> DiffGutter {
                     if !is_replacement {
                         // Pure deletion - mark as DeletedAfter on previous line
                         let delete_count = op.old_range().len() as u16;
+                        let current_line_idx = op.new_range().start;
                         if current_line_idx > 0 {
                             let mark_idx = current_line_idx - 1;
                             if mark_idx < marks.len() {
@@ -90,7 +159,6 @@ pub fn diff_gutter_from_text(base: &str, current: &str) -> DiffGutter {
                             }
                         }
                     }
-                    current_line_idx += range.len();
                 }
                 DiffTag::Replace => {
                     // Some diff engines use Replace instead of Delete+Insert
@@ -100,13 +168,184 @@ pub fn diff_gutter_from_text(base: &str, current: &str) -> DiffGutter {
                             marks[i] = DiffMark::Modified;
                         }
                     }
-                    current_line_idx += range.len();
+                }
+            }
+        }
+
+        // Pair the removed and inserted lines of a replacement in order to
+        // find the words that changed
+        let changed = group.iter().filter(|op| op.tag() != DiffTag::Equal);
+        let old_lines: Vec<usize> = changed.clone().flat_map(|op| op.old_range()).collect();
+        let new_lines: Vec<usize> = changed.flat_map(|op| op.new_range()).collect();
+        for (&old_idx, &new_idx) in old_lines.iter().zip(&new_lines) {
+            if let (Some(old), Some(new), Some(slot)) = (
+                base_text.get(old_idx),
+                current_text.get(new_idx),
+                changes.get_mut(new_idx),
+            ) {
+                *slot = intraline_changes(old, new).1;
+            }
+        }
+
+        // Base lines left over go after the group's last line
+        let removed = old_lines.get(new_lines.len()..).unwrap_or(&[]);
+        if !removed.is_empty() {
+            let end = group.last().map_or(0, |op| op.new_range().end);
+            let after = end.saturating_sub(1).min(current_lines - 1);
+            deleted.entry(after).or_default().extend(
+                removed
+                    .iter()
+                    .filter_map(|&idx| base_text.get(idx))
+                    .map(|line| line.to_string()),
+            );
+        }
+    }
+
+    DiffGutter {
+        saved_marks: vec![DiffMark::None; marks.len()],
+        marks,
+        changes,
+        deleted,
+    }
+}
+
+/// How a row of a side-by-side diff differs between the two files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowKind {
+    Equal,
+    /// Only in the old file
+    Removed,
+    /// Only in the new file
+    Added,
+    /// Present in both, with different text
+    Changed,
+}
+
+/// One side of a side-by-side row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowSide {
+    /// 0-based line number in its file
+    pub line: usize,
+    pub text: String,
+    /// Byte ranges of `text` that differ from the other side
+    pub changes: Vec<std::ops::Range<usize>>,
+}
+
+/// A row of a side-by-side diff. A side is `None` where the other file
+/// has lines this one lacks, which keeps the two columns aligned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SideBySideRow {
+    pub kind: RowKind,
+    pub left: Option<RowSide>,
+    pub right: Option<RowSide>,
+}
+
+/// Align the lines of two texts for a side-by-side view. Replaced lines
+/// are paired up, with their changed words marked on both sides.
+#[cfg(feature = "git")]
+pub fn side_by_side(old: &str, new: &str) -> Vec<SideBySideRow> {
+    use similar::{DiffTag, TextDiff};
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let side = |lines: &[&str], line: usize| RowSide {
+        line,
+        text: lines[line].to_string(),
+        changes: Vec::new(),
+    };
+
+    let diff = TextDiff::from_lines(old, new);
+    let mut rows = Vec::new();
+    for op in diff.ops() {
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        match op.tag() {
+            DiffTag::Equal => {
+                for (o, n) in old_range.zip(new_range) {
+                    rows.push(SideBySideRow {
+                        kind: RowKind::Equal,
+                        left: Some(side(&old_lines, o)),
+                        right: Some(side(&new_lines, n)),
+                    });
+                }
+            }
+            DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                let paired = old_range.len().min(new_range.len());
+                for i in 0..old_range.len().max(new_range.len()) {
+                    let mut left =
+                        (i < old_range.len()).then(|| side(&old_lines, old_range.start + i));
+                    let mut right =
+                        (i < new_range.len()).then(|| side(&new_lines, new_range.start + i));
+                    let kind = if i < paired {
+                        RowKind::Changed
+                    } else if left.is_some() {
+                        RowKind::Removed
+                    } else {
+                        RowKind::Added
+                    };
+                    if let (Some(l), Some(r)) = (left.as_mut(), right.as_mut()) {
+                        (l.changes, r.changes) = intraline_changes(&l.text, &r.text);
+                    }
+                    rows.push(SideBySideRow { kind, left, right });
                 }
             }
         }
     }
+    rows
+}
+
+/// Byte ranges of the words that differ between two versions of a line,
+/// as (ranges in `old`, ranges in `new`). Changed words with only
+/// whitespace between them are merged into one range.
+#[cfg(feature = "git")]
+pub fn intraline_changes(
+    old: &str,
+    new: &str,
+) -> (Vec<std::ops::Range<usize>>, Vec<std::ops::Range<usize>>) {
+    use similar::{DiffTag, TextDiff};
 
-    DiffGutter { marks }
+    let diff = TextDiff::from_words(old, new);
+    let offsets = |slices: &[&str]| {
+        let mut offsets = Vec::with_capacity(slices.len() + 1);
+        let mut pos = 0;
+        offsets.push(0);
+        for slice in slices {
+            pos += slice.len();
+            offsets.push(pos);
+        }
+        offsets
+    };
+    let old_offsets = offsets(diff.old_slices());
+    let new_offsets = offsets(diff.new_slices());
+
+    // Words separated only by unchanged whitespace form one range
+    let push =
+        |ranges: &mut Vec<std::ops::Range<usize>>, text: &str, range: std::ops::Range<usize>| {
+            if range.is_empty() {
+                return;
+            }
+            match ranges.last_mut() {
+                Some(last) if text[last.end..range.start].trim().is_empty() => last.end = range.end,
+                _ => ranges.push(range),
+            }
+        };
+    let (mut old_changes, mut new_changes) = (Vec::new(), Vec::new());
+    for op in diff.ops() {
+        if op.tag() == DiffTag::Equal {
+            continue;
+        }
+        let (o, n) = (op.old_range(), op.new_range());
+        push(
+            &mut old_changes,
+            old,
+            old_offsets[o.start]..old_offsets[o.end],
+        );
+        push(
+            &mut new_changes,
+            new,
+            new_offsets[n.start]..new_offsets[n.end],
+        );
+    }
+    (old_changes, new_changes)
 }
 
 #[cfg(test)]
@@ -162,6 +401,9 @@ mod tests {
         assert_eq!(gutter.get(0), DiffMark::None);
         assert_eq!(gutter.get(1), DiffMark::Modified);
         assert_eq!(gutter.get(2), DiffMark::None);
+        assert_eq!(gutter.changes(1).len(), 1);
+        assert_eq!(gutter.changes(1)[0], 0..9);
+        assert!(gutter.changes(0).is_empty());
     }
 
     #[test]
@@ -174,6 +416,58 @@ mod tests {
         assert_eq!(gutter.marks.len(), 2);
         assert_eq!(gutter.get(0), DiffMark::DeletedAfter(2));
         assert_eq!(gutter.get(1), DiffMark::None);
+        assert_eq!(gutter.deleted_lines(0), ["line 2", "line 3"]);
+        assert!(gutter.deleted_lines(1).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_deletion_mid_file_marks_previous_line() {
+        let base = "1\n2\n3\n4\n5\n";
+        let current = "1\n2\n3\n5\n";
+
+        let gutter = diff_gutter_from_text(base, current);
+
+        assert_eq!(gutter.get(0), DiffMark::None);
+        assert_eq!(gutter.get(2), DiffMark::DeletedAfter(1));
+        assert_eq!(gutter.deleted_lines(2), ["4"]);
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_replacement_keeps_unpaired_deleted_lines() {
+        let base = "a\nold 1\nold 2\nold 3\nz\n";
+        let current = "a\nnew 1\nz\n";
+
+        let gutter = diff_gutter_from_text(base, current);
+
+        assert_eq!(gutter.get(1), DiffMark::Modified);
+        assert_eq!(gutter.deleted_lines(1), ["old 2", "old 3"]);
+    }
+
+    #[test]
+    fn test_hunks_group_consecutive_marks() {
+        let mut gutter = DiffGutter::empty(8);
+        gutter.marks[1] = DiffMark::Added;
+        gutter.marks[2] = DiffMark::Modified;
+        gutter.marks[3] = DiffMark::DeletedAfter(2);
+        gutter.marks[6] = DiffMark::Modified;
+
+        assert_eq!(gutter.hunks(), vec![1..4, 6..7]);
+        assert!(DiffGutter::empty(3).hunks().is_empty());
+    }
+
+    #[test]
+    fn test_saved_layer_is_separate_from_git() {
+        let mut gutter = diff_gutter_from_text("a\nb\n", "a\nb\nc\n");
+        gutter.set_saved_layer("a\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(gutter.get(2), DiffMark::Added);
+        assert_eq!(gutter.layer(DiffLayer::Saved, 1), DiffMark::Modified);
+        assert_eq!(gutter.layer(DiffLayer::Saved, 2), DiffMark::None);
+        assert_eq!(gutter.hunks(), vec![2..3]);
+
+        gutter.clear_saved_layer();
+        assert_eq!(gutter.layer(DiffLayer::Saved, 1), DiffMark::None);
     }
 
     #[test]
@@ -199,4 +493,43 @@ mod tests {
         assert_eq!(gutter.get(1), DiffMark::Added);
         assert_eq!(gutter.get(2), DiffMark::Added);
     }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_side_by_side_aligns_rows() {
+        let old = "same\nold words here\ngone\nend\n";
+        let new = "same\nnew words here\nend\nextra\n";
+
+        let rows = side_by_side(old, new);
+        let kinds: Vec<RowKind> = rows.iter().map(|row| row.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RowKind::Equal,
+                RowKind::Changed,
+                RowKind::Removed,
+                RowKind::Equal,
+                RowKind::Added
+            ]
+        );
+        assert!(rows[2].right.is_none());
+        assert!(rows[4].left.is_none());
+        assert_eq!(rows[4].right.as_ref().unwrap().line, 3);
+
+        let changed_left = rows[1].left.as_ref().unwrap();
+        let changed_right = rows[1].right.as_ref().unwrap();
+        assert_eq!(&changed_left.text[changed_left.changes[0].clone()], "old");
+        assert_eq!(&changed_right.text[changed_right.changes[0].clone()], "new");
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_intraline_changes_merges_adjacent_words() {
+        let (old, new) = intraline_changes("a quick brown fox", "a slow red fox");
+        assert_eq!(old, vec![2..13]);
+        assert_eq!(new, vec![2..10]);
+
+        let (old, new) = intraline_changes("same text", "same text");
+        assert!(old.is_empty() && new.is_empty());
+    }
 }
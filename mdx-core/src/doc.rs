@@ -1,20 +1,30 @@
 //! Document model with Rope-based text storage
 
+#[cfg(feature = "asciidoc")]
+pub mod asciidoc;
+pub mod csv;
+pub mod notebook;
+#[cfg(feature = "rst")]
+pub mod rst;
+
 use anyhow::{Context, Result};
 use ropey::Rope;
-use std::fs;
-use std::io::{self, Read};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::decode::{self, Decoding};
+use crate::html::{self, DetailsBlock};
+use crate::blocks;
+use crate::conflict::{self, Conflict};
+use crate::links::{self, LinkDefinitions};
 use crate::security::SecurityEvent;
 use crate::toc;
 
-/// Maximum file size that can be loaded (10MB)
-const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
-
-/// Maximum number of headings allowed in a document
-const MAX_HEADINGS: usize = 1000;
+/// Default maximum file size that can be loaded (10MB). Overridable via
+/// `[document] max_file_bytes`; 0 disables the limit.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
 /// Maximum number of images allowed in a document
 const MAX_IMAGES: usize = 100;
@@ -40,10 +50,32 @@ pub struct Document {
     pub path: PathBuf,
     pub rope: Rope,
     pub headings: Vec<Heading>,
+    /// Raw HTML `<details>` blocks, rendered as collapsible sections
+    pub details: Vec<DetailsBlock>,
+    /// Reference link definitions (`[label]: url`)
+    pub link_definitions: LinkDefinitions,
+    /// Source lines of each GitHub Flavored Markdown table
+    pub tables: Vec<std::ops::Range<usize>>,
+    /// Source lines of each HTML comment on lines of its own
+    pub comments: Vec<std::ops::Range<usize>>,
+    /// Merge conflicts left in the text by git
+    pub conflicts: Vec<Conflict>,
+    /// How the file's bytes became this text
+    pub decoding: Decoding,
     pub loaded_mtime: Option<SystemTime>,
     pub disk_mtime: Option<SystemTime>,
     pub dirty_on_disk: bool,
+    /// In-app edits not yet written to disk
+    pub modified: bool,
+    /// The text as last loaded or saved, which in-app edits are compared
+    /// with for the gutter
+    pub saved_rope: Rope,
     pub rev: u64,
+    /// Size limit in bytes applied on load and reload (0 = unlimited)
+    pub max_file_size: u64,
+    /// Git revision the text was read from instead of the working tree
+    /// (`mdx --rev`); such documents are read-only
+    pub revision: Option<String>,
     #[cfg(feature = "git")]
     pub diff_gutter: DiffGutter,
     #[cfg(feature = "images")]
@@ -54,6 +86,14 @@ impl Document {
     /// Load a document from a file path
     /// Returns (Document, Vec<SecurityEvent>) where events track security warnings
     pub fn load(path: &Path) -> Result<(Self, Vec<SecurityEvent>)> {
+        Self::load_with_limit(path, DEFAULT_MAX_FILE_SIZE)
+    }
+
+    /// Load a document from a file path with a custom size limit (0 = unlimited)
+    ///
+    /// The file is streamed into the rope in chunks rather than read into a
+    /// single `String` first, so peak memory stays close to the rope size.
+    pub fn load_with_limit(path: &Path, max_file_size: u64) -> Result<(Self, Vec<SecurityEvent>)> {
         let mut warnings = Vec::new();
 
         // Canonicalize the path to get absolute path (needed for git integration)
@@ -66,40 +106,23 @@ impl Document {
             .with_context(|| format!("Failed to read file metadata: {}", abs_path.display()))?;
 
         let file_size = metadata.len();
-        if file_size > MAX_FILE_SIZE {
-            anyhow::bail!("File exceeds maximum size of 10MB ({} bytes)", file_size);
-        }
+        check_size_limit(file_size, max_file_size, "File")?;
 
         // Warn if approaching size limit (>80%)
-        if file_size > MAX_FILE_SIZE * 8 / 10 {
+        if is_near_size_limit(file_size, max_file_size) {
             warnings.push(SecurityEvent::warning(
                 format!("Large file: {} bytes", file_size),
                 "document",
             ));
         }
 
-        let content = fs::read_to_string(&abs_path)
-            .with_context(|| format!("Failed to read file: {}", abs_path.display()))?;
-
-        let rope = Rope::from_str(&content);
+        let (rope, decoding) = read_source(&abs_path)?;
         let headings = toc::extract_headings(&rope);
-
-        // Check heading count limit
-        if headings.len() > MAX_HEADINGS {
-            anyhow::bail!(
-                "Document has too many headings ({}, max is {})",
-                headings.len(),
-                MAX_HEADINGS
-            );
-        }
-
-        // Warn if approaching heading limit (>80%)
-        if headings.len() > MAX_HEADINGS * 8 / 10 {
-            warnings.push(SecurityEvent::warning(
-                format!("Many headings: {}", headings.len()),
-                "document",
-            ));
-        }
+        let details = html::extract_details(&rope);
+        let link_definitions = links::extract_definitions(&rope);
+        let tables = blocks::extract_tables(&rope);
+        let comments = html::extract_comments(&rope);
+        let conflicts = conflict::extract_conflicts(&rope);
 
         let mtime = metadata.modified().ok();
 
@@ -135,12 +158,22 @@ impl Document {
 
         let doc = Self {
             path: abs_path,
+            saved_rope: rope.clone(),
             rope,
             headings,
+            details,
+            link_definitions,
+            tables,
+            comments,
+            conflicts,
+            decoding,
             loaded_mtime: mtime,
             disk_mtime: mtime,
             dirty_on_disk: false,
+            modified: false,
             rev: 1,
+            max_file_size,
+            revision: None,
             #[cfg(feature = "git")]
             diff_gutter,
             #[cfg(feature = "images")]
@@ -150,53 +183,89 @@ impl Document {
         Ok((doc, warnings))
     }
 
+    /// An empty stand-in for a file that couldn't be loaded, so the viewer
+    /// can still start and offer to retry
+    pub fn unloaded(path: &Path, max_file_size: u64) -> Self {
+        Self {
+            path: std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            rope: Rope::new(),
+            headings: Vec::new(),
+            details: Vec::new(),
+            link_definitions: LinkDefinitions::new(),
+            tables: Vec::new(),
+            comments: Vec::new(),
+            conflicts: Vec::new(),
+            decoding: Decoding::Utf8,
+            loaded_mtime: None,
+            disk_mtime: None,
+            dirty_on_disk: false,
+            modified: false,
+            saved_rope: Rope::new(),
+            rev: 1,
+            max_file_size,
+            revision: None,
+            #[cfg(feature = "git")]
+            diff_gutter: DiffGutter::empty(1),
+            #[cfg(feature = "images")]
+            images: Vec::new(),
+        }
+    }
+
+    /// Load the file at `path` as it was at the git revision `rev`, read-only
+    #[cfg(feature = "git")]
+    pub fn from_revision(path: &Path, rev: &str, max_file_size: u64) -> Result<Self> {
+        let text = crate::git::read_file_at_revision(path, rev)?
+            .with_context(|| format!("{} doesn't exist at {}", path.display(), rev))?;
+        check_size_limit(text.len() as u64, max_file_size, "File")?;
+
+        let mut doc = Self::unloaded(path, max_file_size);
+        doc.path = path.canonicalize().unwrap_or(doc.path);
+        doc.rope = Rope::from_str(&text);
+        doc.saved_rope = doc.rope.clone();
+        doc.diff_gutter = DiffGutter::empty(doc.rope.len_lines());
+        doc.revision = Some(rev.to_string());
+        doc.refresh_derived();
+        Ok(doc)
+    }
+
     /// Load a document from stdin
     /// Returns (Document, Vec<SecurityEvent>) where events track security warnings
     pub fn from_stdin() -> Result<(Self, Vec<SecurityEvent>)> {
+        Self::from_stdin_with_limit(DEFAULT_MAX_FILE_SIZE)
+    }
+
+    /// Load a document from stdin with a custom size limit (0 = unlimited)
+    pub fn from_stdin_with_limit(max_file_size: u64) -> Result<(Self, Vec<SecurityEvent>)> {
         let mut warnings = Vec::new();
 
-        // Read all content from stdin
-        let mut content = String::new();
-        io::stdin()
-            .read_to_string(&mut content)
-            .context("Failed to read from stdin")?;
+        // Stream stdin into the rope. Read one byte past the limit so an
+        // oversized input is detected without buffering all of it.
+        let stdin = io::stdin().lock();
+        let rope = if max_file_size == 0 {
+            Rope::from_reader(stdin)
+        } else {
+            Rope::from_reader(stdin.take(max_file_size + 1))
+        }
+        .context("Failed to read from stdin")?;
 
         // Check content size
-        let content_size = content.len() as u64;
-        if content_size > MAX_FILE_SIZE {
-            anyhow::bail!(
-                "Input exceeds maximum size of 10MB ({} bytes)",
-                content_size
-            );
-        }
+        let content_size = rope.len_bytes() as u64;
+        check_size_limit(content_size, max_file_size, "Input")?;
 
         // Warn if approaching size limit (>80%)
-        if content_size > MAX_FILE_SIZE * 8 / 10 {
+        if is_near_size_limit(content_size, max_file_size) {
             warnings.push(SecurityEvent::warning(
                 format!("Large input: {} bytes", content_size),
                 "document",
             ));
         }
 
-        let rope = Rope::from_str(&content);
         let headings = toc::extract_headings(&rope);
-
-        // Check heading count limit
-        if headings.len() > MAX_HEADINGS {
-            anyhow::bail!(
-                "Document has too many headings ({}, max is {})",
-                headings.len(),
-                MAX_HEADINGS
-            );
-        }
-
-        // Warn if approaching heading limit (>80%)
-        if headings.len() > MAX_HEADINGS * 8 / 10 {
-            warnings.push(SecurityEvent::warning(
-                format!("Many headings: {}", headings.len()),
-                "document",
-            ));
-        }
+        let details = html::extract_details(&rope);
+        let link_definitions = links::extract_definitions(&rope);
+        let tables = blocks::extract_tables(&rope);
+        let comments = html::extract_comments(&rope);
+        let conflicts = conflict::extract_conflicts(&rope);
 
         // Initialize with empty diff gutter - stdin has no git context
         #[cfg(feature = "git")]
@@ -230,12 +299,22 @@ impl Document {
 
         let doc = Self {
             path: PathBuf::from("<stdin>"),
+            saved_rope: rope.clone(),
             rope,
             headings,
+            details,
+            link_definitions,
+            tables,
+            comments,
+            conflicts,
+            decoding: Decoding::Utf8,
             loaded_mtime: None,
             disk_mtime: None,
             dirty_on_disk: false,
+            modified: false,
             rev: 1,
+            max_file_size,
+            revision: None,
             #[cfg(feature = "git")]
             diff_gutter,
             #[cfg(feature = "images")]
@@ -247,19 +326,28 @@ impl Document {
 
     /// Reload the document from disk
     pub fn reload(&mut self) -> Result<()> {
-        let content = fs::read_to_string(&self.path)
+        if let Some(rev) = &self.revision {
+            anyhow::bail!(
+                "{} is shown as of {} and can't be reloaded",
+                self.path.display(),
+                rev
+            );
+        }
+        let metadata = fs::metadata(&self.path)
             .with_context(|| format!("Failed to reload file: {}", self.path.display()))?;
+        check_size_limit(metadata.len(), self.max_file_size, "File")?;
 
-        self.rope = Rope::from_str(&content);
-        self.headings = toc::extract_headings(&self.rope);
+        (self.rope, self.decoding) = read_source(&self.path)
+            .with_context(|| format!("Failed to reload file: {}", self.path.display()))?;
 
-        let metadata = fs::metadata(&self.path).ok();
-        let mtime = metadata.and_then(|m| m.modified().ok());
+        let mtime = metadata.modified().ok();
 
         self.loaded_mtime = mtime;
         self.disk_mtime = mtime;
         self.dirty_on_disk = false;
-        self.rev += 1;
+        self.modified = false;
+        self.saved_rope = self.rope.clone();
+        self.refresh_derived();
 
         // Reset diff gutter to empty - will be computed asynchronously by worker thread
         #[cfg(feature = "git")]
@@ -268,13 +356,109 @@ impl Document {
             self.diff_gutter = DiffGutter::empty(line_count);
         }
 
+        Ok(())
+    }
+
+    /// Replace a range of characters with `text` and mark the document
+    /// modified. The diff gutter keeps its old marks until the next diff.
+    pub fn edit(&mut self, chars: std::ops::Range<usize>, text: &str) {
+        self.rope.remove(chars.clone());
+        self.rope.insert(chars.start, text);
+        self.modified = true;
+        self.refresh_derived();
+    }
+
+    /// File the document was loaded from, or `None` for standard input
+    pub fn file_path(&self) -> Option<&Path> {
+        (self.loaded_mtime.is_some() || self.path != Path::new("<stdin>")).then_some(&self.path)
+    }
+
+    /// Whether the file was changed by something else since it was
+    /// loaded or saved
+    pub fn changed_on_disk(&self) -> bool {
+        let disk_mtime = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        self.dirty_on_disk || (disk_mtime.is_some() && disk_mtime != self.loaded_mtime)
+    }
+
+    /// Write the document back to its file. Refused if the file changed on
+    /// disk since it was loaded, as the write would throw that away; see
+    /// [`Document::overwrite`].
+    pub fn save(&mut self) -> Result<()> {
+        if self.file_path().is_some() && self.revision.is_none() && self.changed_on_disk() {
+            anyhow::bail!(
+                "{} changed on disk since it was loaded (:w! overwrites it)",
+                self.path.display()
+            );
+        }
+        self.overwrite()
+    }
+
+    /// Write the document back to its file, even if it changed on disk.
+    /// The text goes to a temporary file next to it that is renamed over
+    /// the original, so a failed write leaves the original intact.
+    pub fn overwrite(&mut self) -> Result<()> {
+        if self.file_path().is_none() {
+            anyhow::bail!("Standard input has no file to save to");
+        }
+        if let Some(rev) = &self.revision {
+            anyhow::bail!("{} is shown read-only as of {}", self.path.display(), rev);
+        }
+        if is_converted_path(&self.path) {
+            anyhow::bail!(
+                "{} is shown converted to Markdown; saving would overwrite the original",
+                self.path.display()
+            );
+        }
+        if self.decoding.is_lossy() {
+            anyhow::bail!(
+                "{} isn't UTF-8 text; saving would overwrite its original bytes",
+                self.path.display()
+            );
+        }
+        // Written back in the encoding it was read in
+        let encoded = match self.decoding {
+            Decoding::Transcoded(encoding) => Some(encoding.encode(&self.rope.to_string())?),
+            _ => None,
+        };
+        // Replace the file a symlink points to, not the link
+        let target = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        let tmp = temp_path(&target);
+        let written = write_synced(&tmp, &target, |writer| match &encoded {
+            Some(bytes) => io::Write::write_all(writer, bytes),
+            None => self.rope.write_to(writer),
+        })
+        .and_then(|()| fs::rename(&tmp, &target));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&tmp);
+            return Err(e).with_context(|| format!("Failed to write file: {}", self.path.display()));
+        }
+
+        let mtime = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        self.loaded_mtime = mtime;
+        self.disk_mtime = mtime;
+        self.dirty_on_disk = false;
+        self.modified = false;
+        self.saved_rope = self.rope.clone();
+        #[cfg(feature = "git")]
+        self.diff_gutter.clear_saved_layer();
+        Ok(())
+    }
+
+    /// Re-extract everything derived from the text and bump the revision
+    fn refresh_derived(&mut self) {
+        self.headings = toc::extract_headings(&self.rope);
+        self.details = html::extract_details(&self.rope);
+        self.link_definitions = links::extract_definitions(&self.rope);
+        self.tables = blocks::extract_tables(&self.rope);
+        self.comments = html::extract_comments(&self.rope);
+        self.conflicts = conflict::extract_conflicts(&self.rope);
+        self.rev += 1;
+
         // Re-extract images from Markdown
         #[cfg(feature = "images")]
         {
             self.images = extract_images(&self.rope);
         }
-
-        Ok(())
     }
 
     /// Get the number of lines in the document
@@ -282,6 +466,11 @@ impl Document {
         self.rope.len_lines()
     }
 
+    /// Word count, reading time, and per-section statistics
+    pub fn stats(&self) -> crate::stats::DocumentStats {
+        crate::stats::compute(&self.rope, &self.headings)
+    }
+
     /// Extract lines for yank operations (inclusive range)
     pub fn get_lines(&self, start: usize, end_inclusive: usize) -> String {
         let line_count = self.line_count();
@@ -314,21 +503,127 @@ impl Document {
     }
 }
 
+/// Whether a file is shown converted to Markdown (notebooks, CSV and TSV
+/// tables, and AsciiDoc and reStructuredText with their build features)
+/// rather than as it is
+pub fn is_converted_path(path: &Path) -> bool {
+    #[cfg(feature = "asciidoc")]
+    if asciidoc::is_asciidoc_path(path) {
+        return true;
+    }
+    #[cfg(feature = "rst")]
+    if rst::is_rst_path(path) {
+        return true;
+    }
+    notebook::is_notebook_path(path) || csv::is_csv_path(path)
+}
+
+/// Read a file as Markdown, converting other input formats on the way
+fn read_source(path: &Path) -> Result<(Rope, Decoding)> {
+    let (rope, decoding) = read_rope(path)?;
+    #[cfg(feature = "asciidoc")]
+    if asciidoc::is_asciidoc_path(path) {
+        return Ok((Rope::from_str(&asciidoc::to_markdown(&rope.to_string())), decoding));
+    }
+    #[cfg(feature = "rst")]
+    if rst::is_rst_path(path) {
+        return Ok((Rope::from_str(&rst::to_markdown(&rope.to_string())), decoding));
+    }
+    if csv::is_csv_path(path) {
+        let format = crate::export::TableFormat::for_path(path);
+        return Ok((Rope::from_str(&csv::to_markdown(&rope.to_string(), format)), decoding));
+    }
+    if !notebook::is_notebook_path(path) {
+        return Ok((rope, decoding));
+    }
+    let markdown = notebook::to_markdown(&rope.to_string())
+        .with_context(|| format!("Failed to read notebook: {}", path.display()))?;
+    Ok((Rope::from_str(&markdown), decoding))
+}
+
+/// Fence for a code block around `body`: longer than any backtick run in it
+fn code_fence(body: &str) -> String {
+    let longest_run = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+/// Stream a file into a rope without materializing the whole text first
+fn read_rope(path: &Path) -> Result<(Rope, Decoding)> {
+    let context = || format!("Failed to read file: {}", path.display());
+    let mut file = File::open(path).with_context(context)?;
+
+    // UTF-8 text streams straight into the rope. Other encodings, binary
+    // files, and invalid UTF-8 are read again as bytes and decoded.
+    let mut head = Vec::with_capacity(decode::SNIFF_BYTES);
+    (&mut file)
+        .take(decode::SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .with_context(context)?;
+    let plain = decode::Encoding::from_bom(&head).is_none()
+        && decode::sniff_utf16(&head).is_none()
+        && !decode::looks_binary(&head);
+    if plain {
+        match Rope::from_reader(BufReader::new(io::Cursor::new(&head).chain(&mut file))) {
+            Ok(rope) => return Ok((rope, Decoding::Utf8)),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {}
+            Err(e) => return Err(e).with_context(context),
+        }
+    }
+    let bytes = fs::read(path).with_context(context)?;
+    let (text, decoding) = decode::decode_bytes(&bytes);
+    Ok((Rope::from_str(&text), decoding))
+}
+
+/// Fail if `size` exceeds `limit` (0 = unlimited)
+fn check_size_limit(size: u64, limit: u64, what: &str) -> Result<()> {
+    if limit > 0 && size > limit {
+        anyhow::bail!(
+            "{} exceeds maximum size of {} bytes ({} bytes); raise [document] max_file_bytes or set it to 0 to disable the limit",
+            what,
+            limit,
+            size
+        );
+    }
+    Ok(())
+}
+
+/// Whether `size` is above 80% of `limit` (never true when unlimited)
+fn is_near_size_limit(size: u64, limit: u64) -> bool {
+    limit > 0 && size > limit * 8 / 10
+}
+
 /// Extract images from Markdown text
 #[cfg(feature = "images")]
 fn extract_images(rope: &Rope) -> Vec<ImageNode> {
-    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+    use crate::image::ImageContext;
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
 
     let text: String = rope.chunks().collect();
-    let parser = Parser::new(&text);
+    let parser = Parser::new_ext(&text, Options::ENABLE_TABLES);
     let parser_with_offsets = parser.into_offset_iter();
 
     let mut images = Vec::new();
     let mut in_image = false;
     let mut current_alt = String::new();
+    let mut item_depth = 0usize;
+    let mut in_table_cell = false;
+
+    let context = |item_depth: usize, in_table_cell: bool| {
+        if in_table_cell {
+            ImageContext::TableCell
+        } else if item_depth > 0 {
+            ImageContext::ListItem
+        } else {
+            ImageContext::Paragraph
+        }
+    };
 
     for (event, range) in parser_with_offsets {
         match event {
+            Event::Start(Tag::Item) => item_depth += 1,
+            Event::End(TagEnd::Item) => item_depth = item_depth.saturating_sub(1),
+            Event::Start(Tag::TableCell) => in_table_cell = true,
+            Event::End(TagEnd::TableCell) => in_table_cell = false,
             Event::Start(Tag::Image {
                 link_type: _,
                 ref dest_url,
@@ -339,12 +634,10 @@ fn extract_images(rope: &Rope) -> Vec<ImageNode> {
                 in_image = true;
                 current_alt.clear();
 
-                // Find line number for this byte offset
-                let byte_offset = range.start.min(rope.len_bytes().saturating_sub(1));
-                let current_line = rope.byte_to_line(byte_offset);
-
                 // Create image node (will update alt text in Text event)
-                let mut img = ImageNode::new(dest_url.to_string(), String::new(), current_line);
+                let mut img = ImageNode::new(dest_url.to_string(), String::new(), 0);
+                place_image(&mut img, rope, range.clone());
+                img.context = context(item_depth, in_table_cell);
 
                 if !title.is_empty() {
                     img.title = Some(title.to_string());
@@ -353,6 +646,17 @@ fn extract_images(rope: &Rope) -> Vec<ImageNode> {
                 // Store temporarily (will be updated with alt text)
                 images.push(img);
             }
+            Event::Html(ref html) | Event::InlineHtml(ref html) => {
+                // Raw `<img>` tags feed the same pipeline as Markdown images
+                for tag in html::parse_img_tags(html) {
+                    let start = range.start + tag.offset;
+                    let mut img = ImageNode::new(tag.src, tag.alt, 0);
+                    place_image(&mut img, rope, start..start + tag.len);
+                    img.context = context(item_depth, in_table_cell);
+                    img.title = tag.title;
+                    images.push(img);
+                }
+            }
             Event::Text(ref text) if in_image => {
                 // Capture alt text
                 current_alt.push_str(text);
@@ -369,9 +673,85 @@ fn extract_images(rope: &Rope) -> Vec<ImageNode> {
         }
     }
 
+    for img in &mut images {
+        if img.context == ImageContext::TableCell {
+            img.standalone = false;
+        }
+    }
     images
 }
 
+/// Set an image's line, column, and whether it is alone on its line, from
+/// the byte range of its markup
+#[cfg(feature = "images")]
+fn place_image(img: &mut ImageNode, rope: &Rope, range: std::ops::Range<usize>) {
+    let start = range.start.min(rope.len_bytes().saturating_sub(1));
+    let line_idx = rope.byte_to_line(start);
+    let line_start = rope.line_to_byte(line_idx);
+    let line: String = rope.line(line_idx).chunks().collect();
+    let line = line.trim_end_matches(['\n', '\r']);
+    let column = (start - line_start).min(line.len());
+    let end = range.end.saturating_sub(line_start);
+
+    img.source_line = line_idx;
+    img.column = column;
+    img.standalone = end <= line.len()
+        && line.is_char_boundary(end.max(column))
+        && strip_block_markers(&line[..column]).is_empty()
+        && line[end.max(column)..].trim().is_empty();
+}
+
+/// Strip leading indentation, block quote markers, and list markers
+#[cfg(feature = "images")]
+fn strip_block_markers(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = text.strip_prefix('>') {
+            text = rest;
+            continue;
+        }
+        if let Some(rest) = text.strip_prefix(['-', '*', '+']) {
+            if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                text = rest;
+                continue;
+            }
+        }
+        let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            if let Some(rest) = text[digits..].strip_prefix(['.', ')']) {
+                if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                    text = rest;
+                    continue;
+                }
+            }
+        }
+        return text;
+    }
+}
+
+
+/// Temporary file a save of `path` is written to before the rename
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map_or_else(Default::default, |n| n.to_string_lossy());
+    path.with_file_name(format!(".{}.mdx-save", name))
+}
+
+/// Create `tmp` with the permissions of `original`, fill it with `write`
+/// and flush it to disk
+fn write_synced(
+    tmp: &Path,
+    original: &Path,
+    write: impl FnOnce(&mut io::BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let file = File::create(tmp)?;
+    if let Ok(metadata) = fs::metadata(original) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    let mut writer = io::BufWriter::new(file);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(io::IntoInnerError::into_error)?;
+    file.sync_all()
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,6 +785,164 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_load_notebook_as_markdown() -> Result<()> {
+        let mut file = tempfile::Builder::new().suffix(".ipynb").tempfile()?;
+        file.write_all(
+            br##"{"cells": [{"cell_type": "markdown", "source": ["# Results"]},
+                 {"cell_type": "code", "source": ["x = 1"], "outputs": []}],
+                 "metadata": {}, "nbformat": 4, "nbformat_minor": 5}"##,
+        )?;
+
+        let (mut doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.headings[0].text, "Results");
+        assert_eq!(doc.rope.line(4).to_string(), "```python\n");
+        assert!(doc.save().is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "asciidoc")]
+    #[test]
+    fn test_load_asciidoc_as_markdown() -> Result<()> {
+        let mut file = tempfile::Builder::new().suffix(".adoc").tempfile()?;
+        file.write_all(b"= Manual\n\n== Setup\n\n* Install\n")?;
+
+        let (doc, _warnings) = Document::load(file.path())?;
+        let titles: Vec<_> = doc.headings.iter().map(|h| (h.level, h.text.as_str())).collect();
+        assert_eq!(titles, [(1, "Manual"), (2, "Setup")]);
+        assert_eq!(doc.rope.line(4).to_string(), "- Install\n");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "rst")]
+    #[test]
+    fn test_load_rst_as_markdown() -> Result<()> {
+        let mut file = tempfile::Builder::new().suffix(".rst").tempfile()?;
+        file.write_all(b"Manual\n======\n\nSetup\n-----\n")?;
+
+        let (mut doc, _warnings) = Document::load(file.path())?;
+        let titles: Vec<_> = doc.headings.iter().map(|h| (h.level, h.text.as_str())).collect();
+        assert_eq!(titles, [(1, "Manual"), (2, "Setup")]);
+        assert!(doc.save().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_and_save() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"# Titel\n\nText\n")?;
+
+        let (mut doc, _warnings) = Document::load(file.path())?;
+        doc.edit(4..7, "tle");
+        assert!(doc.modified);
+        assert_eq!(doc.rev, 2);
+        assert_eq!(doc.headings[0].text, "Title");
+
+        doc.save()?;
+        assert!(!doc.modified);
+        assert_eq!(fs::read_to_string(file.path())?, "# Title\n\nText\n");
+        assert!(!temp_path(file.path()).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_refuses_over_changes_on_disk() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("doc.md");
+        fs::write(&path, "# Title\n")?;
+        let (mut doc, _warnings) = Document::load(&path)?;
+        doc.edit(0..0, "mine\n");
+
+        // Someone else writes the file in the meantime
+        fs::write(&path, "theirs\n")?;
+        let later = doc.loaded_mtime.unwrap() + std::time::Duration::from_secs(5);
+        File::options().write(true).open(&path)?.set_modified(later)?;
+        assert!(doc.changed_on_disk());
+        assert!(doc.save().is_err());
+        assert_eq!(fs::read_to_string(&path)?, "theirs\n");
+
+        doc.overwrite()?;
+        assert_eq!(fs::read_to_string(&path)?, "mine\n# Title\n");
+        assert!(!doc.changed_on_disk());
+        doc.edit(0..0, "again\n");
+        doc.save()?;
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_permissions_and_symlinks() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir()?;
+        let target = dir.path().join("target.md");
+        fs::write(&target, "text\n")?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640))?;
+        let link = dir.path().join("link.md");
+        std::os::unix::fs::symlink(&target, &link)?;
+
+        let (mut doc, _warnings) = Document::load(&link)?;
+        doc.edit(0..0, "more ");
+        doc.save()?;
+        assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target)?, "more text\n");
+        assert_eq!(fs::metadata(&target)?.permissions().mode() & 0o777, 0o640);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_invalid_utf8_and_binary() -> Result<()> {
+        // The bad byte sits past the sniffed head, so streaming fails late
+        let mut file = NamedTempFile::new()?;
+        let mut bytes = "Caf\u{e9}\n".repeat(2000).into_bytes();
+        bytes.extend_from_slice(b"caf\xe9\n");
+        file.write_all(&bytes)?;
+
+        let (mut doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.decoding, Decoding::Lossy { replaced: 1 });
+        assert_eq!(doc.rope.line(2000).to_string(), "caf\u{FFFD}\n");
+        doc.edit(0..0, "x");
+        assert!(doc.save().is_err());
+        assert_eq!(std::fs::read(file.path())?, bytes);
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0/lib/ld-linux.so.2\0")?;
+        let (doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.decoding, Decoding::Binary { bytes: 35 });
+        assert_eq!(doc.headings[0].text, "Hex dump");
+        assert!(doc.rope.to_string().contains("/lib/ld-linux.so.2\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_utf16_round_trip() -> Result<()> {
+        let encode = |text: &str| -> Vec<u8> {
+            [0xFF, 0xFE]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect()
+        };
+        let mut file = NamedTempFile::new()?;
+        file.write_all(&encode("# Titel\r\n\r\nText\r\n"))?;
+
+        let (mut doc, _warnings) = Document::load(file.path())?;
+        let encoding = decode::Encoding::Utf16Le { bom: true };
+        assert_eq!(doc.decoding, Decoding::Transcoded(encoding));
+        assert_eq!(doc.headings[0].text, "Titel");
+        doc.edit(4..7, "tle");
+        doc.save()?;
+        assert_eq!(std::fs::read(file.path())?, encode("# Title\r\n\r\nText\r\n"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_reload_increments_revision() -> Result<()> {
         let mut file = NamedTempFile::new()?;
@@ -478,6 +1016,22 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_extract_images_reference_style() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"![Logo][logo]\n\n[logo]: img/logo.png \"The logo\"\n")?;
+
+        let (doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.images.len(), 1);
+        assert_eq!(doc.images[0].src, "img/logo.png");
+        assert_eq!(doc.images[0].alt, "Logo");
+        assert_eq!(doc.images[0].title, Some("The logo".to_string()));
+        assert!(doc.images[0].standalone);
+
+        Ok(())
+    }
+
     #[cfg(feature = "images")]
     #[test]
     fn test_extract_images_with_title() -> Result<()> {
@@ -560,6 +1114,67 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_extract_images_from_html() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(
+            b"# Title\n\n<p align=\"center\">\n  <img src=\"logo.png\" alt=\"Logo\" width=\"80\">\n</p>\n\nText <img src=\"inline.png\"> here\n",
+        )?;
+
+        let (doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.images.len(), 2);
+        assert_eq!(doc.images[0].src, "logo.png");
+        assert_eq!(doc.images[0].alt, "Logo");
+        assert_eq!(doc.images[0].source_line, 3);
+        assert_eq!(doc.images[1].src, "inline.png");
+        assert_eq!(doc.images[1].source_line, 6);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "images")]
+    #[test]
+    fn test_extract_images_context_and_column() -> Result<()> {
+        use crate::image::ImageContext;
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(
+            b"![solo](a.png)\n\n- ![item](b.png)\n- see ![inline](c.png) here\n\n| Icon | Name |\n|------|------|\n| ![cell](d.png) | x |\n",
+        )?;
+
+        let (doc, _warnings) = Document::load(file.path())?;
+        let placed: Vec<_> = doc
+            .images
+            .iter()
+            .map(|img| (img.alt.as_str(), img.source_line, img.column, img.context, img.standalone))
+            .collect();
+        assert_eq!(
+            placed,
+            vec![
+                ("solo", 0, 0, ImageContext::Paragraph, true),
+                ("item", 2, 2, ImageContext::ListItem, true),
+                ("inline", 3, 6, ImageContext::ListItem, false),
+                ("cell", 7, 2, ImageContext::TableCell, false),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_details_blocks_extracted() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"<details>\n<summary>More</summary>\n\nHidden\n</details>\n")?;
+
+        let (doc, _warnings) = Document::load(file.path())?;
+        assert_eq!(doc.details.len(), 1);
+        assert_eq!(doc.details[0].summary, "More");
+        assert_eq!(doc.details[0].end, 4);
+
+        Ok(())
+    }
+
     #[cfg(feature = "images")]
     #[test]
     fn test_extract_images_reload_updates() -> Result<()> {
@@ -597,7 +1212,7 @@ mod tests {
         use std::io::Write;
         let mut file = NamedTempFile::new().unwrap();
 
-        // Create a file larger than MAX_FILE_SIZE (10MB)
+        // Create a file larger than DEFAULT_MAX_FILE_SIZE (10MB)
         let large_content = "x".repeat(11 * 1024 * 1024); // 11MB
         file.write_all(large_content.as_bytes()).unwrap();
         file.flush().unwrap();
@@ -608,4 +1223,69 @@ mod tests {
         let err = result.unwrap_err();
         assert!(err.to_string().contains("exceeds maximum size"));
     }
+
+    #[test]
+    fn test_load_with_custom_limit() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"# Heading\n\nSome longer body text\n")?;
+        file.flush()?;
+
+        let err = Document::load_with_limit(file.path(), 8).unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum size of 8 bytes"));
+
+        let (doc, _warnings) = Document::load_with_limit(file.path(), 1024)?;
+        assert_eq!(doc.headings.len(), 1);
+        assert_eq!(doc.max_file_size, 1024);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_unlimited_allows_large_file() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        let mut content = String::from("# Big\n");
+        while content.len() <= DEFAULT_MAX_FILE_SIZE as usize {
+            content.push_str(&"x".repeat(1023));
+            content.push('\n');
+        }
+        file.write_all(content.as_bytes())?;
+        file.flush()?;
+
+        let (doc, _warnings) = Document::load_with_limit(file.path(), 0)?;
+        assert_eq!(doc.rope.len_bytes(), content.len());
+        assert_eq!(doc.headings.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_keeps_every_heading() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        for i in 0..5000 {
+            writeln!(file, "## Entry {}\n\nChanged.\n", i)?;
+        }
+        file.flush()?;
+
+        let (doc, warnings) = Document::load(file.path())?;
+        assert_eq!(doc.headings.len(), 5000);
+        assert_eq!(doc.headings[4999].text, "Entry 4999");
+        assert!(warnings.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_respects_limit() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"small\n")?;
+        file.flush()?;
+
+        let (mut doc, _warnings) = Document::load_with_limit(file.path(), 16)?;
+        std::fs::write(file.path(), "this content is far too long\n")?;
+
+        assert!(doc.reload().is_err());
+        assert_eq!(doc.rev, 1);
+
+        Ok(())
+    }
 }
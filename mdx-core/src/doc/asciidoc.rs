@@ -0,0 +1,297 @@
+//! AsciiDoc (`.adoc`) input adapter
+//!
+//! Converts the common subset of AsciiDoc to Markdown: `=` section titles,
+//! `*`/`.` lists, `----`/`....` blocks (with `[source,lang]`), admonition
+//! paragraphs, and `link:`/`xref:`/URL/`<<id>>` links. Anything else is
+//! passed through as text.
+
+use std::path::Path;
+
+/// Whether a path names an AsciiDoc file, going by its extension
+pub fn is_asciidoc_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["adoc", "asciidoc", "asc"]
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// Convert AsciiDoc text to Markdown
+pub fn to_markdown(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut lines = text.lines();
+    // Language from a `[source,lang]` line, for the block below it
+    let mut source_lang: Option<String> = None;
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_end();
+        let lang = source_lang.take();
+
+        // Delimited blocks: listing (----), literal (....), comment (////),
+        // and quote (____)
+        if let Some(delimiter) = block_delimiter(trimmed) {
+            let body: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| l.trim_end() != trimmed)
+                .collect();
+            match delimiter {
+                '/' => {}
+                '_' => out.extend(
+                    body.iter()
+                        .map(|l| format!("> {}", inline(l)).trim_end().to_string()),
+                ),
+                _ => {
+                    let body = body.join("\n");
+                    let fence = super::code_fence(&body);
+                    out.push(format!("{}{}", fence, lang.unwrap_or_default()));
+                    if !body.is_empty() {
+                        out.push(body);
+                    }
+                    out.push(fence);
+                }
+            }
+            continue;
+        }
+
+        // Block attribute lines: keep a source language, drop the rest
+        if trimmed.starts_with('[') && trimmed.ends_with(']') && !trimmed.contains("](") {
+            let attributes: Vec<&str> = trimmed[1..trimmed.len() - 1]
+                .split(',')
+                .map(str::trim)
+                .collect();
+            if attributes.first() == Some(&"source") {
+                source_lang = Some(attributes.get(1).copied().unwrap_or_default().to_string());
+            }
+            continue;
+        }
+
+        // Comments, document attributes, list continuations, and open
+        // block delimiters
+        if trimmed.starts_with("//")
+            || is_attribute_entry(trimmed)
+            || trimmed == "+"
+            || trimmed == "--"
+        {
+            continue;
+        }
+
+        if trimmed == "'''" {
+            out.push("---".to_string());
+        } else if let Some((level, title)) = section_title(trimmed) {
+            out.push(format!("{} {}", "#".repeat(level), inline(title)));
+        } else if let Some(item) = list_item(trimmed) {
+            out.push(item);
+        } else if let Some(title) = block_title(trimmed) {
+            out.push(format!("*{}*", inline(title)));
+        } else if let Some((label, rest)) = admonition(trimmed) {
+            out.push(format!("> **{}:** {}", label, inline(rest)));
+        } else {
+            out.push(inline(trimmed));
+        }
+    }
+
+    let mut markdown = out.join("\n");
+    markdown.push('\n');
+    markdown
+}
+
+/// Character of a block delimiter line (`----`, `....`, `////`, `____`)
+fn block_delimiter(line: &str) -> Option<char> {
+    let first = line.chars().next()?;
+    (matches!(first, '-' | '.' | '/' | '_') && line.len() >= 4 && line.chars().all(|c| c == first))
+        .then_some(first)
+}
+
+/// `:name:` or `:name: value`
+fn is_attribute_entry(line: &str) -> bool {
+    line.strip_prefix(':')
+        .and_then(|rest| rest.split_once(':'))
+        .is_some_and(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '!')
+        })
+}
+
+/// `== Title` (or Markdown-style `## Title`): level and title
+fn section_title(line: &str) -> Option<(usize, &str)> {
+    let marker = line.chars().next().filter(|c| *c == '=' || *c == '#')?;
+    let level = line.chars().take_while(|c| *c == marker).count();
+    let title = line[level..].strip_prefix(' ')?;
+    (level <= 6).then_some((level, title.trim()))
+}
+
+/// `* item`, `** nested`, `- item`, `. step`, `.. nested step`
+fn list_item(line: &str) -> Option<String> {
+    let marker = line
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '*' | '.' | '-'))?;
+    let depth = line.chars().take_while(|c| *c == marker).count();
+    let text = line[depth..].strip_prefix(' ')?;
+    if marker == '-' && depth > 1 {
+        return None;
+    }
+    let item = if marker == '.' {
+        format!("{}1. {}", "   ".repeat(depth - 1), inline(text))
+    } else {
+        format!("{}- {}", "  ".repeat(depth - 1), inline(text))
+    };
+    Some(item)
+}
+
+/// `.Title` above a block
+fn block_title(line: &str) -> Option<&str> {
+    let title = line.strip_prefix('.')?;
+    title
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace() && c != '.')
+        .then_some(title)
+}
+
+/// `NOTE: text` and the other admonition paragraphs
+fn admonition(line: &str) -> Option<(&'static str, &str)> {
+    [
+        ("NOTE: ", "Note"),
+        ("TIP: ", "Tip"),
+        ("IMPORTANT: ", "Important"),
+        ("WARNING: ", "Warning"),
+        ("CAUTION: ", "Caution"),
+    ]
+    .into_iter()
+    .find_map(|(prefix, label)| line.strip_prefix(prefix).map(|rest| (label, rest)))
+}
+
+/// Convert inline markup: links, cross references, and constrained bold
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let at_word_start = !out.chars().last().is_some_and(char::is_alphanumeric);
+        if c == '`' {
+            // Code spans are copied as they are
+            let end = rest[1..].find('`').map_or(rest.len(), |i| i + 2);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some((link, used)) = at_word_start.then(|| macro_link(rest)).flatten() {
+            out.push_str(&link);
+            rest = &rest[used..];
+        } else if let Some((link, used)) = cross_reference(rest) {
+            out.push_str(&link);
+            rest = &rest[used..];
+        } else if let Some((strong, used)) = at_word_start.then(|| constrained_bold(rest)).flatten()
+        {
+            out.push_str(&strong);
+            rest = &rest[used..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// `link:target[text]`, `xref:target[text]`, or `https://url[text]`, with
+/// the number of bytes it takes
+fn macro_link(text: &str) -> Option<(String, usize)> {
+    let (target_start, keep_scheme) = [
+        ("link:", false),
+        ("xref:", false),
+        ("https://", true),
+        ("http://", true),
+        ("mailto:", true),
+    ]
+    .into_iter()
+    .find_map(|(prefix, keep)| {
+        text.starts_with(prefix)
+            .then_some((if keep { 0 } else { prefix.len() }, keep))
+    })?;
+    let target_end = text[target_start..]
+        .find(|c: char| c == '[' || c.is_whitespace())
+        .map_or(text.len(), |i| target_start + i);
+    let target = &text[target_start..target_end];
+    if !text[target_end..].starts_with('[') {
+        // A bare URL stays as it is
+        return keep_scheme.then(|| (target.to_string(), target_end));
+    }
+    let close = text[target_end..].find(']')? + target_end;
+    let label = &text[target_end + 1..close];
+    let label = if label.is_empty() { target } else { label };
+    Some((format!("[{}]({})", label, target), close + 1))
+}
+
+/// `<<id>>` or `<<id,text>>`
+fn cross_reference(text: &str) -> Option<(String, usize)> {
+    let inner = text.strip_prefix("<<")?;
+    let close = inner.find(">>")?;
+    let (id, label) = match inner[..close].split_once(',') {
+        Some((id, label)) => (id.trim(), label.trim()),
+        None => (inner[..close].trim(), inner[..close].trim()),
+    };
+    Some((format!("[{}](#{})", label, id), close + 4))
+}
+
+/// `*bold*` (AsciiDoc's single-asterisk strong) as `**bold**`
+fn constrained_bold(text: &str) -> Option<(String, usize)> {
+    let inner = text
+        .strip_prefix('*')
+        .filter(|rest| !rest.starts_with(['*', ' ']))?;
+    let close = inner.find('*')?;
+    let after = inner[close + 1..].chars().next();
+    if inner[..close].ends_with(' ') || after.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    Some((format!("**{}**", &inner[..close]), close + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asciidoc_to_markdown() {
+        let adoc = "= Guide\n:toc:\n\nIntro with *bold* and `code`.\n\n== Install\n\n\
+                    * First\n** Nested\n. Step one\n\n\
+                    [source,rust]\n----\nfn main() {}\n----\n\n\
+                    NOTE: Read https://example.com[the docs] and <<install,Install>>.\n\
+                    // a comment\n";
+        assert_eq!(
+            to_markdown(adoc),
+            "# Guide\n\nIntro with **bold** and `code`.\n\n## Install\n\n\
+             - First\n  - Nested\n1. Step one\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             > **Note:** Read [the docs](https://example.com) and [Install](#install).\n"
+        );
+    }
+
+    #[test]
+    fn test_asciidoc_links() {
+        assert_eq!(
+            inline("see link:other.adoc[Other]"),
+            "see [Other](other.adoc)"
+        );
+        assert_eq!(
+            inline("xref:api.adoc#auth[]"),
+            "[api.adoc#auth](api.adoc#auth)"
+        );
+        assert_eq!(
+            inline("plain https://example.com here"),
+            "plain https://example.com here"
+        );
+        // Not bold: a list-like star, or a star inside a word
+        assert_eq!(inline("2*3*4 and * not"), "2*3*4 and * not");
+    }
+
+    #[test]
+    fn test_asciidoc_blocks() {
+        let adoc = "....\nliteral *text*\n....\n\n____\nQuoted\n____\n\n////\nhidden\n////\n.Example\n'''\n";
+        assert_eq!(
+            to_markdown(adoc),
+            "```\nliteral *text*\n```\n\n> Quoted\n\n*Example*\n---\n"
+        );
+    }
+}
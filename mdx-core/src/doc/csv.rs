@@ -0,0 +1,182 @@
+//! CSV and TSV (`.csv`, `.tsv`) input adapter
+//!
+//! A delimited file is shown as one Markdown table: the first record is
+//! the header row and every record after it a body row. The same parser
+//! reads fenced `csv` and `tsv` blocks so the view can lay them out as
+//! tables.
+
+use crate::export::TableFormat;
+use std::path::Path;
+
+/// One record of delimited text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// 0-based line of the text the record starts on
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// Whether a path names a CSV or TSV file, going by its extension
+pub fn is_csv_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["csv", "tsv"]
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// The format a fenced block's info string names: `csv` or `tsv`
+pub fn fence_format(info: &str) -> Option<TableFormat> {
+    TableFormat::from_name(info.split_whitespace().next()?)
+}
+
+/// Records of `text`, skipping blank lines. CSV fields may be quoted as
+/// RFC 4180 describes, with `""` for a quote and line breaks kept inside
+/// the quotes; TSV fields are split at every tab.
+pub fn records(text: &str, format: TableFormat) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+    let mut line = 0;
+    let mut start = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if format == TableFormat::Csv && field.trim().is_empty() && !quoted => {
+                field.clear();
+                in_quotes = true;
+                quoted = true;
+            }
+            '\n' if in_quotes => {
+                line += 1;
+                field.push('\n');
+            }
+            ',' if format == TableFormat::Csv && !in_quotes => {
+                fields.push(end_field(&mut field, &mut quoted));
+            }
+            '\t' if format == TableFormat::Tsv => fields.push(end_field(&mut field, &mut quoted)),
+            '\r' if !in_quotes => {}
+            '\n' => {
+                fields.push(end_field(&mut field, &mut quoted));
+                push_record(&mut records, std::mem::take(&mut fields), start);
+                line += 1;
+                start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !fields.is_empty() || quoted {
+        fields.push(end_field(&mut field, &mut quoted));
+        push_record(&mut records, fields, start);
+    }
+    records
+}
+
+fn end_field(field: &mut String, quoted: &mut bool) -> String {
+    let text = std::mem::take(field);
+    if std::mem::take(quoted) {
+        text
+    } else {
+        text.trim().to_string()
+    }
+}
+
+fn push_record(records: &mut Vec<Record>, fields: Vec<String>, line: usize) {
+    let blank = fields.len() == 1 && fields[0].is_empty();
+    if !blank {
+        records.push(Record { line, fields });
+    }
+}
+
+/// Convert delimited text to a Markdown table. Rows are padded to the
+/// widest record, since a GFM table drops cells its header has no column
+/// for.
+pub fn to_markdown(text: &str, format: TableFormat) -> String {
+    let records = records(text, format);
+    let columns = records
+        .iter()
+        .map(|record| record.fields.len())
+        .max()
+        .unwrap_or(0);
+    let row = |fields: &[String]| {
+        let cells: Vec<String> = (0..columns)
+            .map(|col| fields.get(col).map_or(String::new(), |field| cell(field)))
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut out = String::new();
+    for (idx, record) in records.iter().enumerate() {
+        out.push_str(&row(&record.fields));
+        if idx == 0 {
+            out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+        }
+    }
+    out
+}
+
+/// A field as table cell text: on one line, with its pipes escaped
+pub fn cell(field: &str) -> String {
+    field
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_to_markdown() {
+        let csv = "name, note\r\n\
+                   Ada,\"says \"\"hi\"\", twice\"\r\n\
+                   \n\
+                   \"two\nlines\",a|b,extra\n";
+        assert_eq!(
+            to_markdown(csv, TableFormat::Csv),
+            "| name | note |  |\n| --- | --- | --- |\n\
+             | Ada | says \"hi\", twice |  |\n\
+             | two lines | a\\|b | extra |\n"
+        );
+        let lines: Vec<usize> = records(csv, TableFormat::Csv)
+            .iter()
+            .map(|record| record.line)
+            .collect();
+        assert_eq!(lines, [0, 1, 3]);
+    }
+
+    #[test]
+    fn test_tsv_records() {
+        let tsv = "a\t\"b\"\n1\t2";
+        assert_eq!(
+            records(tsv, TableFormat::Tsv),
+            vec![
+                Record {
+                    line: 0,
+                    fields: vec!["a".into(), "\"b\"".into()]
+                },
+                Record {
+                    line: 1,
+                    fields: vec!["1".into(), "2".into()]
+                },
+            ]
+        );
+        assert!(is_csv_path(Path::new("data/Sales.TSV")));
+        assert!(!is_csv_path(Path::new("notes.md")));
+        assert_eq!(fence_format("csv title=x"), Some(TableFormat::Csv));
+        assert_eq!(fence_format("rust"), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Jupyter notebook (`.ipynb`) input adapter
+//!
+//! A notebook is shown as the Markdown document it reads as: markdown
+//! cells verbatim, code cells as fenced blocks in the kernel's language,
+//! and text outputs as `output` blocks under their cell. Cells are
+//! separated by a thematic break, so each one is also a slide.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Whether a path names a Jupyter notebook, going by its extension
+pub fn is_notebook_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Convert notebook JSON (nbformat 4) to Markdown
+pub fn to_markdown(json: &str) -> Result<String> {
+    let notebook: Value = serde_json::from_str(json).context("Not a valid notebook")?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .context("Notebook has no cells (nbformat 4 is required)")?;
+    let language = notebook_language(&notebook);
+
+    let mut blocks = Vec::new();
+    for cell in cells {
+        let source = multiline_text(cell.get("source"));
+        let block = match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") => source.trim_end().to_string(),
+            Some("code") => {
+                let count = cell
+                    .get("execution_count")
+                    .and_then(Value::as_u64)
+                    .map_or(String::new(), |n| format!(" [{}]", n));
+                let mut block = fenced(&format!("{}{}", language, count), &source);
+                let outputs = cell.get("outputs").and_then(Value::as_array);
+                for output in outputs.into_iter().flatten().filter_map(output_text) {
+                    block.push_str("\n\n");
+                    block.push_str(&fenced("output", &output));
+                }
+                block
+            }
+            // Raw cells and anything newer are shown as they are
+            _ => fenced("", &source),
+        };
+        blocks.push(block);
+    }
+    Ok(blocks.join("\n\n---\n\n") + "\n")
+}
+
+/// Kernel language from the notebook metadata, `python` if it has none
+fn notebook_language(notebook: &Value) -> String {
+    let metadata = notebook.get("metadata");
+    metadata
+        .and_then(|m| m.pointer("/language_info/name"))
+        .or_else(|| metadata.and_then(|m| m.pointer("/kernelspec/language")))
+        .and_then(Value::as_str)
+        .unwrap_or("python")
+        .to_string()
+}
+
+/// Notebook text fields are a string or a list of lines
+fn multiline_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Text of a stream, result, or error output; `None` for rich output
+/// (images, HTML) without a plain-text form
+fn output_text(output: &Value) -> Option<String> {
+    let text = match output.get("output_type").and_then(Value::as_str)? {
+        "stream" => multiline_text(output.get("text")),
+        "execute_result" | "display_data" => {
+            multiline_text(Some(output.get("data")?.get("text/plain")?))
+        }
+        "error" => {
+            let traceback = output.get("traceback").and_then(Value::as_array);
+            let lines: Vec<&str> = traceback
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            strip_ansi(&lines.join("\n"))
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Drop the ANSI color sequences Jupyter puts in tracebacks
+fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI: ESC [ parameters final-byte
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// A fenced code block
+fn fenced(info: &str, body: &str) -> String {
+    let fence = super::code_fence(body);
+    let body = body.trim_end_matches('\n');
+    if body.is_empty() {
+        format!("{}{}\n{}", fence, info, fence)
+    } else {
+        format!("{}{}\n{}\n{}", fence, info, body, fence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+      "cells": [
+        {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "\n", "Load the data."]},
+        {"cell_type": "code", "execution_count": 3, "metadata": {},
+         "source": "print(\"hi\")\n1 + 1",
+         "outputs": [
+           {"output_type": "stream", "name": "stdout", "text": ["hi\n"]},
+           {"output_type": "execute_result", "execution_count": 3,
+            "data": {"text/plain": ["2"]}, "metadata": {}},
+           {"output_type": "display_data", "data": {"image/png": "iVBOR"}, "metadata": {}}
+         ]},
+        {"cell_type": "code", "execution_count": null, "metadata": {}, "source": [], "outputs": [
+           {"output_type": "error", "ename": "ValueError", "evalue": "bad",
+            "traceback": ["\u001b[0;31mValueError\u001b[0m: bad"]}
+        ]}
+      ],
+      "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+      "nbformat": 4, "nbformat_minor": 5
+    }"##;
+
+    #[test]
+    fn test_notebook_to_markdown() {
+        let markdown = to_markdown(NOTEBOOK).unwrap();
+        assert_eq!(
+            markdown,
+            "# Analysis\n\nLoad the data.\n\n---\n\n\
+             ```python [3]\nprint(\"hi\")\n1 + 1\n```\n\n\
+             ```output\nhi\n```\n\n\
+             ```output\n2\n```\n\n---\n\n\
+             ```python\n```\n\n\
+             ```output\nValueError: bad\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_fence_outlasts_backticks_in_source() {
+        assert_eq!(fenced("", "s = '```'"), "````\ns = '```'\n````");
+    }
+
+    #[test]
+    fn test_invalid_notebook() {
+        assert!(to_markdown("not json").is_err());
+        assert!(to_markdown("{\"nbformat\": 3, \"worksheets\": []}").is_err());
+        assert!(is_notebook_path(Path::new("analysis.IPYNB")));
+        assert!(!is_notebook_path(Path::new("notes.md")));
+    }
+}
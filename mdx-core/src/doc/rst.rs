@@ -0,0 +1,348 @@
+//! reStructuredText (`.rst`) input adapter
+//!
+//! Converts the common subset of reST to Markdown: underlined (and
+//! overlined) section titles, bullet and enumerated lists, `code-block`
+//! directives and `::` literal blocks, admonitions, and hyperlinks
+//! (embedded, named targets, `:ref:`/`:doc:`). Other directives and
+//! comments are dropped; anything else is passed through as text.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Whether a path names a reStructuredText file, going by its extension
+pub fn is_rst_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rst"))
+}
+
+/// Convert reStructuredText to Markdown
+pub fn to_markdown(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let targets = link_targets(&lines);
+    // Title adornments (character, overlined) in the order they appear;
+    // the position is the section level
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut out: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some((style, title, used)) = section_title(&lines[i..]) {
+            let level = match styles.iter().position(|s| *s == style) {
+                Some(index) => index + 1,
+                None => {
+                    styles.push(style);
+                    styles.len()
+                }
+            };
+            out.push(format!(
+                "{} {}",
+                "#".repeat(level.min(6)),
+                inline(title, &targets)
+            ));
+            i += used;
+            continue;
+        }
+
+        if let Some(directive) = line.trim_start().strip_prefix(".. ") {
+            let indent = indent_of(line);
+            let (body, used) = indented_block(&lines[i + 1..], indent);
+            i += 1 + used;
+            if let Some(lang) = code_directive(directive) {
+                // Options (`:linenos:`) come before the code
+                let code: Vec<&str> = body
+                    .iter()
+                    .copied()
+                    .skip_while(|l| l.is_empty() || l.trim_start().starts_with(':'))
+                    .collect();
+                push_code_block(&mut out, lang, &code, indent);
+            } else if let Some((label, rest)) = admonition(directive) {
+                let first = inline(rest.trim(), &targets);
+                out.push(
+                    format!("{}> **{}:** {}", " ".repeat(indent), label, first)
+                        .trim_end()
+                        .to_string(),
+                );
+                for body_line in body.iter().skip_while(|l| l.is_empty()) {
+                    out.push(
+                        format!("{}> {}", " ".repeat(indent), inline(body_line, &targets))
+                            .trim_end()
+                            .to_string(),
+                    );
+                }
+            }
+            // Comments, link targets, and other directives are dropped,
+            // along with one of the blank lines around them
+            if out.last().is_some_and(String::is_empty)
+                && lines.get(i).is_some_and(|l| l.is_empty())
+            {
+                i += 1;
+            }
+            continue;
+        }
+
+        // A paragraph ending in `::` introduces an indented literal block
+        if let Some(paragraph) = line.strip_suffix("::") {
+            let paragraph = match paragraph.trim_end() {
+                "" => None,
+                text if text.len() < paragraph.len() => Some(text.to_string()),
+                text => Some(format!("{}:", text)),
+            };
+            if let Some(paragraph) = paragraph {
+                out.push(inline(&paragraph, &targets));
+            }
+            let indent = indent_of(line);
+            let (body, used) = indented_block(&lines[i + 1..], indent);
+            i += 1 + used;
+            if !body.is_empty() {
+                out.push(String::new());
+                push_code_block(&mut out, "", &body, indent);
+            }
+            continue;
+        }
+
+        out.push(list_item(line, &targets).unwrap_or_else(|| inline(line, &targets)));
+        i += 1;
+    }
+
+    let mut markdown = out.join("\n");
+    markdown.push('\n');
+    markdown
+}
+
+/// Named hyperlink targets (`.. _name: url`), keyed by lowercased name
+fn link_targets(lines: &[&str]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let target = line.trim_start().strip_prefix(".. _")?;
+            let (name, url) = target.split_once(": ")?;
+            let name = name.trim_matches('`');
+            Some((name.to_lowercase(), url.trim().to_string()))
+        })
+        .collect()
+}
+
+/// A title underlined (and maybe overlined) with punctuation: its style,
+/// text, and the number of lines it takes
+fn section_title<'a>(lines: &[&'a str]) -> Option<((char, bool), &'a str, usize)> {
+    if let [over, title, under, ..] = lines {
+        if let (Some(c), Some(d)) = (adornment(over), adornment(under)) {
+            if c == d && !title.trim().is_empty() {
+                return Some(((c, true), title.trim(), 3));
+            }
+        }
+    }
+    let [title, under, ..] = lines else {
+        return None;
+    };
+    let c = adornment(under)?;
+    let fits = under.chars().count() >= title.chars().count();
+    (fits && !title.is_empty() && !title.starts_with(' ') && adornment(title).is_none())
+        .then_some(((c, false), title.trim(), 2))
+}
+
+/// The character of a line made of one repeated punctuation character
+fn adornment(line: &str) -> Option<char> {
+    let c = line.chars().next().filter(|c| c.is_ascii_punctuation())?;
+    (line.chars().count() >= 3 && line.chars().all(|d| d == c)).then_some(c)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Lines indented past `indent` (and blank lines between them) below a
+/// directive or `::`, dedented, with the number of lines taken
+fn indented_block<'a>(lines: &[&'a str], indent: usize) -> (Vec<&'a str>, usize) {
+    let mut used = lines
+        .iter()
+        .take_while(|l| l.is_empty() || indent_of(l) > indent)
+        .count();
+    while used > 0 && lines[used - 1].is_empty() {
+        used -= 1;
+    }
+    let block = &lines[..used];
+    let dedent = block
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| indent_of(l))
+        .min()
+        .unwrap_or(0);
+    let body = block
+        .iter()
+        .map(|l| l.get(dedent..).unwrap_or(""))
+        .skip_while(|l| l.is_empty())
+        .collect();
+    (body, used)
+}
+
+fn push_code_block(out: &mut Vec<String>, lang: &str, code: &[&str], indent: usize) {
+    let body = code.join("\n");
+    let fence = format!("{}{}", " ".repeat(indent), super::code_fence(&body));
+    out.push(format!("{}{}", fence, lang));
+    out.extend(code.iter().map(|l| {
+        format!("{}{}", " ".repeat(indent), l)
+            .trim_end()
+            .to_string()
+    }));
+    out.push(fence);
+}
+
+/// Language of a `code-block::` (or `code::`, `sourcecode::`) directive
+fn code_directive(directive: &str) -> Option<&str> {
+    ["code-block::", "code::", "sourcecode::"]
+        .into_iter()
+        .find_map(|name| directive.strip_prefix(name))
+        .map(str::trim)
+}
+
+/// `.. note:: text` and the other admonitions
+fn admonition(directive: &str) -> Option<(String, &str)> {
+    let (name, rest) = directive.split_once("::")?;
+    let known = [
+        "attention",
+        "caution",
+        "danger",
+        "error",
+        "hint",
+        "important",
+        "note",
+        "tip",
+        "warning",
+    ];
+    known.contains(&name).then(|| {
+        let mut label = name.to_string();
+        label[..1].make_ascii_uppercase();
+        (label, rest)
+    })
+}
+
+/// `- item`, `* item`, `+ item`, `#. item`; numbered items stay as they are
+fn list_item(line: &str, targets: &HashMap<String, String>) -> Option<String> {
+    let indent = indent_of(line);
+    let trimmed = &line[indent..];
+    let text = ["- ", "* ", "+ "]
+        .into_iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+        .map(|text| ("- ", text))
+        .or_else(|| trimmed.strip_prefix("#. ").map(|text| ("1. ", text)));
+    let (marker, text) = text?;
+    Some(format!(
+        "{}{}{}",
+        " ".repeat(indent),
+        marker,
+        inline(text, targets)
+    ))
+}
+
+/// Convert inline markup: literals, hyperlinks, and roles
+fn inline(text: &str, targets: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(code) = rest.strip_prefix("``") {
+            // ``literal`` becomes a Markdown code span
+            let end = code.find("``").unwrap_or(code.len());
+            out.push('`');
+            out.push_str(&code[..end]);
+            out.push('`');
+            rest = code.get(end + 2..).unwrap_or("");
+        } else if let Some((converted, used)) = role(rest).or_else(|| interpreted(rest, targets)) {
+            out.push_str(&converted);
+            rest = &rest[used..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// `:role:`text``, with `:ref:` and `:doc:` as links and others as code
+fn role(text: &str) -> Option<(String, usize)> {
+    let name_end = text.strip_prefix(':')?.find(":`")? + 1;
+    let name = &text[1..name_end];
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    let body_start = name_end + 2;
+    let body_end = text[body_start..].find('`')? + body_start;
+    let body = &text[body_start..body_end];
+    let (label, target) = split_embedded(body).unwrap_or((body, body));
+    let converted = match name {
+        "ref" => format!("[{}](#{})", label, target),
+        "doc" => format!("[{}]({}.rst)", label, target),
+        _ => format!("`{}`", label),
+    };
+    Some((converted, body_end + 1))
+}
+
+/// `` `text <url>`_ ``, `` `name`_ ``, or plain `` `interpreted` `` text
+fn interpreted(text: &str, targets: &HashMap<String, String>) -> Option<(String, usize)> {
+    let inner = text.strip_prefix('`')?;
+    let close = inner.find('`')?;
+    let body = &inner[..close];
+    let after = &inner[close + 1..];
+    let underscores = after.chars().take_while(|c| *c == '_').count().min(2);
+    let used = close + 2 + underscores;
+    if underscores == 0 {
+        return Some((format!("*{}*", body), used));
+    }
+    let converted = match split_embedded(body) {
+        Some(("", url)) => format!("<{}>", url),
+        Some((label, url)) => format!("[{}]({})", label, url),
+        None => match targets.get(&body.to_lowercase()) {
+            Some(url) => format!("[{}]({})", body, url),
+            None => body.to_string(),
+        },
+    };
+    Some((converted, used))
+}
+
+/// `label <target>`
+fn split_embedded(body: &str) -> Option<(&str, &str)> {
+    let body = body.strip_suffix('>')?;
+    let open = body.rfind('<')?;
+    Some((body[..open].trim(), &body[open + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rst_to_markdown() {
+        let rst =
+            "=====\nGuide\n=====\n\nIntro with ``code`` and `Docs <https://example.com>`_.\n\n\
+                   Install\n-------\n\n* First\n\n  - Nested\n\n#. Step\n\n\
+                   .. code-block:: rust\n   :linenos:\n\n   fn main() {}\n\n\
+                   Usage\n-----\n\nRun it::\n\n   mdx README.md\n\nSee `the site`_.\n\n\
+                   .. _the site: https://example.org\n\n\
+                   .. note:: Read :ref:`install <install>`.\n";
+        assert_eq!(
+            to_markdown(rst),
+            "# Guide\n\nIntro with `code` and [Docs](https://example.com).\n\n\
+             ## Install\n\n- First\n\n  - Nested\n\n1. Step\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             ## Usage\n\nRun it:\n\n```\nmdx README.md\n```\n\nSee [the site](https://example.org).\n\n\
+             > **Note:** Read [install](#install).\n"
+        );
+    }
+
+    #[test]
+    fn test_rst_inline_roles() {
+        let targets = HashMap::new();
+        assert_eq!(inline(":doc:`setup`", &targets), "[setup](setup.rst)");
+        assert_eq!(inline(":py:func:`main`", &targets), "`main`");
+        assert_eq!(inline("a `title` b", &targets), "a *title* b");
+        assert_eq!(inline("`unknown`_ ref", &targets), "unknown ref");
+    }
+
+    #[test]
+    fn test_short_underline_is_not_a_title() {
+        assert_eq!(to_markdown("Long title\n---\n"), "Long title\n---\n");
+    }
+}
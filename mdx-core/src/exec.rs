@@ -0,0 +1,327 @@
+//! Running fenced code blocks
+//!
+//! Only shell and Python fences can be run. Callers are responsible for
+//! checking that execution is allowed before calling [`run_block`].
+
+use crate::fence::{FenceLine, FenceTracker};
+use anyhow::{Context, Result};
+use ropey::Rope;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the output pipes once a block has exited. A
+/// background process the block started can keep them open.
+const PIPE_GRACE: Duration = Duration::from_millis(200);
+
+/// A fenced code block found in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// Line of the opening fence
+    pub start_line: usize,
+    /// Line of the closing fence, or the last line if the block is unclosed
+    pub end_line: usize,
+    /// First word of the info string, lowercased
+    pub lang: String,
+    /// Lines between the fences
+    pub code: String,
+}
+
+/// Program that runs a block, reading the code from stdin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpreter {
+    Shell,
+    Python,
+}
+
+impl Interpreter {
+    /// Interpreter for a fence language, if it can be run
+    pub fn for_lang(lang: &str) -> Option<Self> {
+        match lang {
+            "sh" | "shell" | "bash" | "zsh" | "console" => Some(Self::Shell),
+            "python" | "python3" | "py" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn command(self) -> Command {
+        match self {
+            Self::Shell => {
+                let mut cmd = Command::new("sh");
+                cmd.arg("-s");
+                cmd
+            }
+            Self::Python => {
+                let mut cmd = Command::new("python3");
+                cmd.arg("-");
+                cmd
+            }
+        }
+    }
+}
+
+/// Captured result of running a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code, or `None` if the process was killed or stopped by a signal
+    pub code: Option<i32>,
+    pub timed_out: bool,
+}
+
+/// Find the fenced code block that contains a line (fences included).
+/// Fences are matched by [`FenceTracker`], as in the renderer.
+pub fn fenced_block_at(rope: &Rope, line: usize) -> Option<CodeBlock> {
+    let mut fences = FenceTracker::new();
+    let mut open: Option<(usize, String, usize)> = None;
+    let mut code = String::new();
+
+    for (idx, text) in rope.lines().enumerate() {
+        let text = text.to_string();
+        if text.is_empty() {
+            // Ropey yields an empty last line after a trailing newline
+            continue;
+        }
+        let text = text.trim_end_matches(['\n', '\r']);
+        match fences.next_line(text) {
+            FenceLine::Open => {
+                let fence = fences.open_fence()?;
+                let lang = fence
+                    .info
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("")
+                    .trim_start_matches('{')
+                    .trim_end_matches('}')
+                    .to_lowercase();
+                open = Some((idx, lang, fence.indent));
+                code.clear();
+            }
+            FenceLine::Close => {
+                let (start, lang, _) = open.take()?;
+                if (start..=idx).contains(&line) {
+                    return Some(CodeBlock {
+                        start_line: start,
+                        end_line: idx,
+                        lang,
+                        code,
+                    });
+                }
+            }
+            FenceLine::Code => {
+                // Code in an indented fence loses up to that much indent
+                let indent = open.as_ref().map_or(0, |(_, _, indent)| *indent);
+                let strip = text.len() - text.trim_start_matches(' ').len();
+                code.push_str(&text[strip.min(indent)..]);
+                code.push('\n');
+            }
+            FenceLine::Text => {
+                if idx > line {
+                    return None;
+                }
+            }
+        }
+    }
+
+    // An unclosed fence runs to the end of the document
+    let (start, lang, _) = open?;
+    (line >= start).then(|| CodeBlock {
+        start_line: start,
+        end_line: rope.len_lines().saturating_sub(1),
+        lang,
+        code,
+    })
+}
+
+/// Strip `$ ` prompts from `console` blocks, dropping the output lines
+/// that follow them. Blocks without prompts are returned unchanged.
+fn console_commands(code: &str) -> String {
+    if !code.lines().any(|l| l.trim_start().starts_with("$ ")) {
+        return code.to_string();
+    }
+    code.lines()
+        .filter_map(|l| l.trim_start().strip_prefix("$ "))
+        .map(|l| format!("{}\n", l))
+        .collect()
+}
+
+/// Run a block with its interpreter in `dir`, killing it after `timeout`
+pub fn run_block(
+    block: &CodeBlock,
+    dir: &std::path::Path,
+    timeout: Duration,
+) -> Result<ExecOutput> {
+    let interpreter = Interpreter::for_lang(&block.lang)
+        .with_context(|| format!("Cannot run '{}' blocks", block.lang))?;
+    let code = if block.lang == "console" {
+        console_commands(&block.code)
+    } else {
+        block.code.clone()
+    };
+
+    let mut child = interpreter
+        .command()
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start interpreter")?;
+
+    // Read both pipes on their own threads so a chatty block can't fill
+    // one and deadlock while we wait
+    let stdout = read_pipe(child.stdout.take().context("Missing stdout pipe")?);
+    let stderr = read_pipe(child.stderr.take().context("Missing stderr pipe")?);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(code.as_bytes());
+    }
+
+    let started = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // Don't wait for the pipes to close: a process left running by the
+    // block may hold them open long after it has exited
+    let deadline = Instant::now() + PIPE_GRACE;
+    let stdout = collect_pipe(&stdout, deadline);
+    let stderr = collect_pipe(&stderr, deadline);
+    Ok(ExecOutput {
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        code: status.code(),
+        timed_out,
+    })
+}
+
+/// Read a pipe on a new thread, sending what it reads in chunks until it
+/// closes
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0; 8192];
+        while let Ok(n @ 1..) = pipe.read(&mut buf) {
+            if tx.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Chunks read from a pipe until it closes or `deadline` passes
+fn collect_pipe(chunks: &mpsc::Receiver<Vec<u8>>, deadline: Instant) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Ok(chunk) = chunks.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        out.extend(chunk);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str =
+        "# Run\n\n```bash\necho one\necho two\n```\n\ntext\n\n~~~python {.run}\nprint(1)\n~~~\n";
+
+    #[test]
+    fn test_fenced_block_at() {
+        let rope = Rope::from(DOC);
+        let block = fenced_block_at(&rope, 3).unwrap();
+        assert_eq!((block.start_line, block.end_line), (2, 5));
+        assert_eq!(block.lang, "bash");
+        assert_eq!(block.code, "echo one\necho two\n");
+        // Fence lines belong to the block
+        assert_eq!(fenced_block_at(&rope, 5).unwrap().start_line, 2);
+
+        let block = fenced_block_at(&rope, 9).unwrap();
+        assert_eq!(block.lang, "python");
+        assert_eq!(block.code, "print(1)\n");
+
+        assert!(fenced_block_at(&rope, 0).is_none());
+        assert!(fenced_block_at(&rope, 7).is_none());
+    }
+
+    #[test]
+    fn test_fences_match_the_renderer() {
+        // A longer fence isn't closed by a shorter run, and an indented
+        // fence strips its indent from the code
+        let rope = Rope::from("  ````sh\n  ```\n  echo hi\n  ````\n");
+        let block = fenced_block_at(&rope, 2).unwrap();
+        assert_eq!((block.start_line, block.end_line), (0, 3));
+        assert_eq!(block.code, "```\necho hi\n");
+
+        // Backticks in the info string make it inline code, not a fence
+        assert!(fenced_block_at(&Rope::from("```sh `x`\nls\n```\n"), 1).is_none());
+    }
+
+    #[test]
+    fn test_unclosed_fence_runs_to_end() {
+        let rope = Rope::from("```sh\nls\n");
+        let block = fenced_block_at(&rope, 1).unwrap();
+        assert_eq!(block.code, "ls\n");
+    }
+
+    #[test]
+    fn test_console_prompts() {
+        assert_eq!(console_commands("$ ls\nfile\n$ pwd\n"), "ls\npwd\n");
+        assert_eq!(console_commands("ls\n"), "ls\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_block() {
+        let block = fenced_block_at(
+            &Rope::from("```sh\necho out\necho err >&2\nexit 3\n```\n"),
+            1,
+        )
+        .unwrap();
+        let output = run_block(&block, std::path::Path::new("."), Duration::from_secs(5)).unwrap();
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert_eq!(output.code, Some(3));
+        assert!(!output.timed_out);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_block_timeout() {
+        let block = fenced_block_at(&Rope::from("```sh\nsleep 1\n```\n"), 1).unwrap();
+        let output = run_block(
+            &block,
+            std::path::Path::new("."),
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        assert!(output.timed_out);
+
+        // A background process holding the pipes doesn't keep us waiting
+        let block =
+            fenced_block_at(&Rope::from("```sh\nsleep 5 &\necho started\n```\n"), 1).unwrap();
+        let started = Instant::now();
+        let output = run_block(&block, std::path::Path::new("."), Duration::from_secs(5)).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(output.stdout, "started\n");
+        assert_eq!(output.code, Some(0));
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        let block = fenced_block_at(&Rope::from("```rust\nfn main() {}\n```\n"), 1).unwrap();
+        assert!(run_block(&block, std::path::Path::new("."), Duration::from_secs(1)).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Tables exported as CSV or TSV, for spreadsheets
+//!
+//! Cells are split the way `mdx fmt` splits them and exported as the text
+//! the viewer shows: `**bold**` becomes `bold`, `\|` becomes `|`, and a
+//! link becomes its text.
+
+use crate::config::RenderConfig;
+use crate::links::LinkDefinitions;
+use crate::render::{inline, Role};
+use std::path::Path;
+
+/// Delimited text format of an exported table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Comma-separated, with fields quoted as RFC 4180 describes
+    Csv,
+    /// Tab-separated, which spreadsheets take from the clipboard as cells
+    Tsv,
+}
+
+impl TableFormat {
+    /// `csv` or `tsv`, in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+
+    /// The format a file name asks for: TSV for `.tsv` and `.tab`, else CSV
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("tsv") || ext.eq_ignore_ascii_case("tab") => {
+                Self::Tsv
+            }
+            _ => Self::Csv,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Tsv => "TSV",
+        }
+    }
+
+    fn field(self, text: &str) -> String {
+        match self {
+            Self::Csv if text.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            }
+            Self::Csv => text.to_string(),
+            // TSV has no quoting, so tabs and line breaks become spaces
+            Self::Tsv => text.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+}
+
+/// The table `rows` (header, delimiter row, then the body, as source
+/// lines) as delimited text with one record per line. Records have as
+/// many fields as the header has cells.
+pub fn export_table(
+    rows: &[&str],
+    format: TableFormat,
+    config: &RenderConfig,
+    links: &LinkDefinitions,
+) -> String {
+    let separator = match format {
+        TableFormat::Csv => ",",
+        TableFormat::Tsv => "\t",
+    };
+    let records: Vec<Vec<String>> = rows
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != 1)
+        .map(|(_, row)| {
+            crate::format::split_cells(strip_quote_markers(row))
+                .iter()
+                .map(|cell| {
+                    inline(cell, Role::Text, config, links)
+                        .iter()
+                        .map(|span| span.text.as_str())
+                        .collect::<String>()
+                })
+                .collect()
+        })
+        .collect();
+    let columns = records.first().map_or(0, Vec::len);
+
+    let mut out = String::new();
+    for record in &records {
+        let fields: Vec<String> = (0..columns)
+            .map(|col| format.field(record.get(col).map_or("", String::as_str)))
+            .collect();
+        out.push_str(&fields.join(separator));
+        out.push('\n');
+    }
+    out
+}
+
+/// A table row without the `>` markers of the block quotes around it
+fn strip_quote_markers(row: &str) -> &str {
+    let mut row = row.trim_start();
+    while let Some(rest) = row.strip_prefix('>') {
+        row = rest.trim_start();
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(text: &str, format: TableFormat) -> String {
+        let rows: Vec<&str> = text.lines().collect();
+        export_table(
+            &rows,
+            format,
+            &RenderConfig::default(),
+            &LinkDefinitions::new(),
+        )
+    }
+
+    #[test]
+    fn test_export_csv_quotes_fields() {
+        let table = "| Name | Note |\n\
+                     |------|:----:|\n\
+                     | **Ada** | says \"hi\", twice |\n\
+                     | a \\| b | [docs](https://example.com) |\n\
+                     | short |\n";
+        assert_eq!(
+            export(table, TableFormat::Csv),
+            "Name,Note\nAda,\"says \"\"hi\"\", twice\"\na | b,docs\nshort,\n"
+        );
+    }
+
+    #[test]
+    fn test_export_tsv_from_quoted_table() {
+        let table = "> a | b\n> --|--\n> `1` | 2\n";
+        assert_eq!(export(table, TableFormat::Tsv), "a\tb\n1\t2\n");
+        assert_eq!(
+            TableFormat::for_path(Path::new("out.TSV")),
+            TableFormat::Tsv
+        );
+        assert_eq!(
+            TableFormat::for_path(Path::new("out.txt")),
+            TableFormat::Csv
+        );
+        assert_eq!(TableFormat::from_name("Csv"), Some(TableFormat::Csv));
+    }
+}
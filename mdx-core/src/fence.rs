@@ -0,0 +1,147 @@
+//! Fenced code block tracking
+//!
+//! Heading extraction and the renderer both walk a document line by line
+//! and need to know which lines sit inside a fenced code block, so a
+//! `# comment` in a shell snippet isn't taken for a heading. A fence
+//! closes only on a run of the same character at least as long as the
+//! opening one, so a ```` ```` ```` block can show ```` ``` ```` lines and a
+//! `~~~` block can contain backtick fences.
+
+/// An opening fence line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fence {
+    /// '`' or '~'
+    pub ch: char,
+    /// Length of the marker run (3 or more)
+    pub len: usize,
+    /// Leading spaces before the marker
+    pub indent: usize,
+    /// Text after the marker, trimmed ("rust", "python title=x")
+    pub info: String,
+}
+
+impl Fence {
+    /// Parse a line that opens a fence
+    pub fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let ch = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+        let len = trimmed.chars().take_while(|&c| c == ch).count();
+        if len < 3 {
+            return None;
+        }
+        let info = trimmed[len..].trim();
+        // A backtick in the info string makes it inline code, not a fence
+        if ch == '`' && info.contains('`') {
+            return None;
+        }
+        Some(Self {
+            ch,
+            len,
+            indent: line.len() - trimmed.len(),
+            info: info.to_string(),
+        })
+    }
+
+    /// Whether `line` closes this fence
+    pub fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        let run = trimmed.chars().take_while(|&c| c == self.ch).count();
+        run >= self.len && run == trimmed.len()
+    }
+}
+
+/// What a line is, given the fences before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenceLine {
+    /// Opens a fenced code block
+    Open,
+    /// Closes the open block
+    Close,
+    /// Inside a fenced code block
+    Code,
+    /// Outside any fenced code block
+    Text,
+}
+
+/// Tracks fenced code blocks across successive lines
+#[derive(Debug, Clone, Default)]
+pub struct FenceTracker {
+    open: Option<Fence>,
+}
+
+impl FenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify the next line and update the open block
+    pub fn next_line(&mut self, line: &str) -> FenceLine {
+        match &self.open {
+            Some(fence) if fence.is_closed_by(line) => {
+                self.open = None;
+                FenceLine::Close
+            }
+            Some(_) => FenceLine::Code,
+            None => match Fence::parse(line) {
+                Some(fence) => {
+                    self.open = Some(fence);
+                    FenceLine::Open
+                }
+                None => FenceLine::Text,
+            },
+        }
+    }
+
+    /// Whether the lines fed so far left a block open
+    pub fn in_code_block(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// The fence of the open block, if any
+    pub fn open_fence(&self) -> Option<&Fence> {
+        self.open.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(text: &str) -> Vec<FenceLine> {
+        let mut tracker = FenceTracker::new();
+        text.lines().map(|line| tracker.next_line(line)).collect()
+    }
+
+    #[test]
+    fn test_parse_fence() {
+        let fence = Fence::parse("  ```rust ignore").unwrap();
+        assert_eq!(fence.ch, '`');
+        assert_eq!(fence.len, 3);
+        assert_eq!(fence.indent, 2);
+        assert_eq!(fence.info, "rust ignore");
+        assert_eq!(Fence::parse("~~~~").unwrap().len, 4);
+        assert!(Fence::parse("``not a fence").is_none());
+        assert!(Fence::parse("```inline``` code").is_none());
+    }
+
+    #[test]
+    fn test_close_needs_same_char_and_length() {
+        use FenceLine::*;
+        assert_eq!(
+            classify("````md\n```\n# Code\n```\n````\n# Text"),
+            [Open, Code, Code, Code, Close, Text]
+        );
+        assert_eq!(classify("~~~\n```\n~~~\nText"), [Open, Code, Close, Text]);
+        // A closing fence can't carry an info string
+        assert_eq!(classify("```\n```sh\n```"), [Open, Code, Close]);
+    }
+
+    #[test]
+    fn test_unclosed_block_stays_open() {
+        let mut tracker = FenceTracker::new();
+        tracker.next_line("```python");
+        tracker.next_line("# comment");
+        assert!(tracker.in_code_block());
+        assert_eq!(tracker.open_fence().unwrap().info, "python");
+    }
+}
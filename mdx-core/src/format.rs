@@ -0,0 +1,524 @@
+//! Markdown normalization (`mdx fmt`)
+//!
+//! Re-emits a document with the block structure [`crate::blocks::classify`]
+//! finds in it, spelled consistently: ATX headings with a blank line on
+//! either side, `-` bullets and `1.` item numbers, table columns padded to
+//! line up, single blank lines between blocks, and no trailing whitespace.
+//! With a width set, paragraphs are rewrapped to fit. Code, HTML, and front
+//! matter are copied as they are.
+
+use crate::blocks::{classify, quote_prefix_len, LeafKind, LineBlock};
+use crate::config::MarkdownFlavor;
+use unicode_width::UnicodeWidthStr;
+
+/// How to normalize a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Column to wrap paragraphs at; 0 keeps their line breaks
+    pub width: usize,
+    pub flavor: MarkdownFlavor,
+}
+
+/// Normalized form of a Markdown document, ending in a single newline
+pub fn format_markdown(text: &str, options: &FormatOptions) -> String {
+    let rope = ropey::Rope::from_str(text);
+    let lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+    let body_start =
+        crate::front_matter::detect_front_matter(&rope).map_or(0, |fm| fm.end_line + 1);
+
+    let mut out = Output::default();
+    for line in &lines[..body_start.min(lines.len())] {
+        out.lines.push(line.to_string());
+    }
+
+    let body = &lines[body_start.min(lines.len())..];
+    let mut blocks = classify(&body.join("\n"), options.flavor);
+    // Tight list items hold their text without a paragraph around it
+    for (block, line) in blocks.iter_mut().zip(body) {
+        if block.leaf == LeafKind::Blank
+            && !line[quote_prefix_len(line, block.quote_depth)..]
+                .trim()
+                .is_empty()
+        {
+            block.leaf = LeafKind::Paragraph;
+        }
+    }
+    let mut idx = 0;
+    while idx < body.len() {
+        let block = blocks[idx];
+        let line = if block.item_start {
+            normalize_marker(body[idx], block.quote_depth)
+        } else {
+            body[idx].to_string()
+        };
+        let top_level = block.quote_depth == 0 && block.list_depth == 0;
+
+        match block.leaf {
+            LeafKind::Fence | LeafKind::Code | LeafKind::IndentedCode | LeafKind::Html => {
+                out.push(line);
+                idx += 1;
+            }
+            LeafKind::Blank => {
+                out.blank(line.trim_end());
+                idx += 1;
+            }
+            LeafKind::Heading(level) if top_level => {
+                // A setext heading's text can run over several lines
+                let end = if line.trim_start().starts_with('#') {
+                    idx + 1
+                } else {
+                    (idx..body.len())
+                        .find(|&i| blocks[i].leaf != LeafKind::Heading(level))
+                        .unwrap_or(body.len())
+                };
+                let text = body[idx..end]
+                    .iter()
+                    .map(|line| heading_text(line))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                out.heading(format!("{} {}", "#".repeat(level as usize), text).trim_end());
+                idx = end;
+                if blocks
+                    .get(idx)
+                    .is_some_and(|b| b.leaf == LeafKind::HeadingUnderline(level))
+                {
+                    idx += 1;
+                }
+            }
+            LeafKind::Table if block.quote_depth == 0 => {
+                let end = (idx..body.len())
+                    .find(|&i| blocks[i].leaf != LeafKind::Table)
+                    .unwrap_or(body.len());
+                for row in format_table(&body[idx..end]) {
+                    out.push(row);
+                }
+                idx = end;
+            }
+            LeafKind::Paragraph if options.width > 0 && block.quote_depth == 0 => {
+                let end = (idx + 1..body.len())
+                    .find(|&i| {
+                        let next = blocks[i];
+                        next.leaf != LeafKind::Paragraph
+                            || next.item_start
+                            || next.list_depth != block.list_depth
+                    })
+                    .unwrap_or(body.len());
+                let mut paragraph: Vec<String> = vec![line];
+                paragraph.extend(body[idx + 1..end].iter().map(|line| line.to_string()));
+                for row in wrap_paragraph(&paragraph, &block, options.width) {
+                    out.push(row);
+                }
+                idx = end;
+            }
+            _ => {
+                let continues = blocks
+                    .get(idx + 1)
+                    .is_some_and(|next| next.leaf == LeafKind::Paragraph && !next.item_start);
+                let hard_break =
+                    block.leaf == LeafKind::Paragraph && continues && line.ends_with("  ");
+                let trimmed = line.trim_end();
+                out.push(if hard_break {
+                    format!("{}  ", trimmed)
+                } else {
+                    trimmed.to_string()
+                });
+                idx += 1;
+            }
+        }
+    }
+
+    while out.lines.last().is_some_and(|line| line.is_empty()) {
+        out.lines.pop();
+    }
+    if out.lines.is_empty() {
+        return String::new();
+    }
+    out.lines.join("\n") + "\n"
+}
+
+/// Lines written so far, and whether the next block needs a blank line
+/// before it (after a heading)
+#[derive(Default)]
+struct Output {
+    lines: Vec<String>,
+    blank_before_next: bool,
+}
+
+impl Output {
+    fn push(&mut self, line: String) {
+        if std::mem::take(&mut self.blank_before_next) {
+            self.blank("");
+        }
+        self.lines.push(line);
+    }
+
+    /// A blank line (or one of bare `>` markers), unless the last one is
+    /// the same
+    fn blank(&mut self, line: &str) {
+        self.blank_before_next = false;
+        if self
+            .lines
+            .last()
+            .is_some_and(|last| last.trim_end() != line)
+        {
+            self.lines.push(line.to_string());
+        }
+    }
+
+    fn heading(&mut self, line: &str) {
+        if !self.lines.is_empty() {
+            self.blank("");
+        }
+        self.lines.push(line.to_string());
+        self.blank_before_next = true;
+    }
+}
+
+/// Text of an ATX heading line without its `#` markers, or a line of a
+/// setext heading trimmed
+fn heading_text(line: &str) -> &str {
+    let trimmed = line.trim();
+    let Some(rest) = trimmed.strip_prefix('#') else {
+        return trimmed;
+    };
+    let text = rest.trim_start_matches('#').trim();
+    // An optional closing sequence of `#`s, after a space
+    let without_closing = text.trim_end_matches('#');
+    if without_closing.is_empty() {
+        ""
+    } else if without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        text
+    }
+}
+
+/// `*` and `+` bullets become `-`, and `1)` becomes `1.`. Both keep the
+/// marker's width, so the item's content stays in its column.
+fn normalize_marker(line: &str, quote_depth: usize) -> String {
+    let prefix = quote_prefix_len(line, quote_depth);
+    let rest = &line[prefix..];
+    let marker = prefix + rest.len() - rest.trim_start().len();
+    let after = &line[marker..];
+    let digits = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    let mut normalized = line.to_string();
+    match after[digits..].chars().next() {
+        Some('*' | '+') if digits == 0 => {
+            normalized.replace_range(marker..marker + 1, "-");
+        }
+        Some(')') if digits > 0 => {
+            normalized.replace_range(marker + digits..marker + digits + 1, ".");
+        }
+        _ => {}
+    }
+    normalized
+}
+
+/// Column alignment given by a table's delimiter row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Table rows with their cells padded so the pipes line up
+fn format_table(rows: &[&str]) -> Vec<String> {
+    let indent_len = rows[0].len() - rows[0].trim_start().len();
+    let indent = &rows[0][..indent_len];
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| split_cells(row)).collect();
+    let aligns: Vec<Align> = cells.get(1).map_or(Vec::new(), |delimiter| {
+        delimiter
+            .iter()
+            .map(|cell| match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Align::Center,
+                (true, false) => Align::Left,
+                (false, true) => Align::Right,
+                (false, false) => Align::None,
+            })
+            .collect()
+    });
+    let columns = aligns.len();
+
+    let mut widths = vec![3; columns];
+    for row in cells
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 1)
+        .map(|(_, row)| row)
+    {
+        for (col, cell) in row.iter().take(columns).enumerate() {
+            widths[col] = widths[col].max(cell.width());
+        }
+    }
+
+    cells
+        .iter()
+        .enumerate()
+        .map(|(row_idx, row)| {
+            let mut out = String::from(indent);
+            out.push('|');
+            for col in 0..columns.max(row.len()) {
+                let cell = row.get(col).map_or("", String::as_str);
+                let (Some(&width), Some(&align)) = (widths.get(col), aligns.get(col)) else {
+                    // Cells past the header's columns aren't shown; keep them as they are
+                    out.push_str(&format!(" {} |", cell));
+                    continue;
+                };
+                let text = if row_idx == 1 {
+                    delimiter(width, align)
+                } else {
+                    pad(cell, width, align)
+                };
+                out.push_str(&format!(" {} |", text));
+            }
+            out
+        })
+        .collect()
+}
+
+/// Trimmed cells of a table row, split at pipes that aren't escaped
+pub fn split_cells(row: &str) -> Vec<String> {
+    let trimmed = row.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = match inner.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => inner,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in inner.chars() {
+        match c {
+            '|' if !escaped => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            '\\' => {
+                escaped = !escaped;
+                cell.push(c);
+                continue;
+            }
+            _ => cell.push(c),
+        }
+        escaped = false;
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn delimiter(width: usize, align: Align) -> String {
+    match align {
+        Align::None => "-".repeat(width),
+        Align::Left => format!(":{}", "-".repeat(width - 1)),
+        Align::Right => format!("{}:", "-".repeat(width - 1)),
+        Align::Center => format!(":{}:", "-".repeat(width - 2)),
+    }
+}
+
+fn pad(cell: &str, width: usize, align: Align) -> String {
+    let space = width.saturating_sub(cell.width());
+    match align {
+        Align::Right => format!("{}{}", " ".repeat(space), cell),
+        Align::Center => format!(
+            "{}{}{}",
+            " ".repeat(space / 2),
+            cell,
+            " ".repeat(space - space / 2)
+        ),
+        Align::None | Align::Left => format!("{}{}", cell, " ".repeat(space)),
+    }
+}
+
+/// Rewrap the lines of a paragraph to `width` columns, keeping hard line
+/// breaks. A list item's paragraph keeps its marker and continues under
+/// the item's content.
+fn wrap_paragraph(lines: &[String], block: &LineBlock, width: usize) -> Vec<String> {
+    let indent = if block.list_depth > 0 {
+        " ".repeat(block.list_indent)
+    } else {
+        String::new()
+    };
+    // An item's first row starts with its marker
+    let first = &lines[0];
+    let (first_prefix, first_content) = match first.get(..block.list_indent) {
+        Some(marker) if block.item_start => (marker.to_string(), &first[block.list_indent..]),
+        _ => (indent.clone(), first.as_str()),
+    };
+
+    let mut out = Vec::new();
+    let mut prefix = first_prefix;
+    let mut words = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        let content = if idx == 0 {
+            first_content
+        } else {
+            line.as_str()
+        };
+        let hard_break = idx + 1 < lines.len() && (line.ends_with("  ") || line.ends_with('\\'));
+        words.extend(split_words(content.trim()));
+        if hard_break {
+            let ending = if line.ends_with('\\') { "" } else { "  " };
+            let mut rows = fill(&words, &prefix, &indent, width);
+            if let Some(last) = rows.last_mut() {
+                last.push_str(ending);
+            }
+            out.extend(rows);
+            words.clear();
+            prefix = indent.clone();
+        }
+    }
+    if !words.is_empty() {
+        out.extend(fill(&words, &prefix, &indent, width));
+    }
+    out
+}
+
+/// Words of a line, keeping a code span or a `[[wiki link]]` with spaces
+/// in it together
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            let end = code_span_len(rest);
+            word.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some(end) = rest.starts_with("[[").then(|| rest.find("]]")).flatten() {
+            word.push_str(&rest[..end + 2]);
+            rest = &rest[end + 2..];
+        } else if c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            rest = &rest[c.len_utf8()..];
+        } else {
+            word.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Length of the code span `text` starts with: up to the next run of as
+/// many backticks, or just the opening run if there is none
+fn code_span_len(text: &str) -> usize {
+    let run_len = |s: &str| s.len() - s.trim_start_matches('`').len();
+    let open = run_len(text);
+    let mut pos = open;
+    while let Some(offset) = text[pos..].find('`') {
+        let start = pos + offset;
+        let len = run_len(&text[start..]);
+        if len == open {
+            return start + len;
+        }
+        pos = start + len;
+    }
+    open
+}
+
+/// Greedily fill rows of at most `width` columns. A word that would start
+/// a new block at the start of a row (`#`, `-`, `1.`, ...) stays on the
+/// row before instead.
+fn fill(words: &[String], first_prefix: &str, indent: &str, width: usize) -> Vec<String> {
+    let mut rows = vec![first_prefix.to_string()];
+    let mut empty = true;
+    for word in words {
+        let row = rows.last_mut().expect("rows start with one");
+        if empty {
+            row.push_str(word);
+            empty = false;
+        } else if row.width() + 1 + word.width() <= width || starts_block(word) {
+            row.push(' ');
+            row.push_str(word);
+        } else {
+            rows.push(format!("{}{}", indent, word));
+        }
+    }
+    rows
+}
+
+/// Whether a line starting with `word` would be read as something other
+/// than paragraph text
+fn starts_block(word: &str) -> bool {
+    let digits = word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 {
+        return matches!(&word[digits..], "." | ")");
+    }
+    word.starts_with(['#', '>', '-', '+', '*', '=', '|', '<'])
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(text: &str, width: usize) -> String {
+        format_markdown(
+            text,
+            &FormatOptions {
+                width,
+                flavor: MarkdownFlavor::Gfm,
+            },
+        )
+    }
+
+    #[test]
+    fn test_headings_get_blank_lines_and_atx_markers() {
+        let text = "Title\n=====\nIntro\n##   Setup ##\n\n\n\nText  \n";
+        assert_eq!(format(text, 0), "# Title\n\nIntro\n\n## Setup\n\nText\n");
+    }
+
+    #[test]
+    fn test_list_markers_are_normalized() {
+        let text = "* one\n  + nested\n\n1) first\n2) second\n";
+        assert_eq!(
+            format(text, 0),
+            "- one\n  - nested\n\n1. first\n2. second\n"
+        );
+    }
+
+    #[test]
+    fn test_tables_are_padded_and_aligned() {
+        let text = "|Name|Qty|Note|\n|:-|--:|:-:|\n|apple|10|ok|\n|kiwi|2|\n";
+        assert_eq!(
+            format(text, 0),
+            "| Name  | Qty | Note |\n\
+             | :---- | --: | :--: |\n\
+             | apple |  10 |  ok  |\n\
+             | kiwi  |   2 |      |\n"
+        );
+    }
+
+    #[test]
+    fn test_code_and_front_matter_are_kept() {
+        let text = "---\ntitle:  x  \n---\n```\n*  keep   \n\n\n```\n";
+        assert_eq!(format(text, 0), text);
+    }
+
+    #[test]
+    fn test_paragraphs_wrap_to_width() {
+        let text = "- one two three four five six\n\nalpha `a  b` gamma\\\ndelta - epsilon\n";
+        assert_eq!(
+            format(text, 12),
+            "- one two\n  three four\n  five six\n\nalpha `a  b`\ngamma\\\ndelta -\nepsilon\n"
+        );
+        assert_eq!(
+            format("see [[Page Name]] now\n", 8),
+            "see\n[[Page Name]]\nnow\n"
+        );
+    }
+
+    #[test]
+    fn test_hard_breaks_are_kept_unwrapped() {
+        assert_eq!(format("a   \nb\n", 0), "a  \nb\n");
+    }
+}
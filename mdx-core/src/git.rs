@@ -14,43 +14,54 @@ pub struct RepoContext {
 /// Open repository containing the given path
 #[cfg(feature = "git")]
 pub fn open_repo_for_path(path: &Path) -> Result<Option<RepoContext>> {
-    use gix::discover;
-
-    // Get the absolute path of the file
-    let abs_path = match path.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return Ok(None),
+    let Some(ctx) = discover_repo(path) else {
+        return Ok(None);
     };
 
-    // Try to discover and open repository (use parent directory for discovery)
-    let discover_path = abs_path.parent().unwrap_or(&abs_path);
-    let repo = match discover(discover_path) {
-        Ok(r) => r,
-        Err(_) => return Ok(None),
-    };
+    // Check if file is gitignored
+    if is_path_ignored(&ctx.repo, &ctx.rel_path) {
+        return Ok(None);
+    }
 
-    // Get working directory
-    let workdir = match repo.workdir() {
-        Some(wd) => wd.to_path_buf(),
-        None => return Ok(None), // Bare repo
-    };
+    Ok(Some(ctx))
+}
 
-    // Compute relative path from workdir to file
-    let rel_path = match abs_path.strip_prefix(&workdir) {
-        Ok(p) => p.to_path_buf(),
-        Err(_) => return Ok(None),
-    };
+/// Find the repository a path belongs to, whether or not the file exists.
+/// The repository is discovered from the nearest directory above the path
+/// that exists, and the path made relative to its working directory
+/// without touching the file itself.
+#[cfg(feature = "git")]
+fn discover_repo(path: &Path) -> Option<RepoContext> {
+    use std::path::Component;
 
-    // Check if file is gitignored
-    if is_path_ignored(&repo, &rel_path) {
-        return Ok(None);
+    // Resolve `.` and `..` lexically; the path may not exist any more
+    let abs_path = std::path::absolute(path).ok()?;
+    let mut lexical = PathBuf::new();
+    for component in abs_path.components() {
+        match component {
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            Component::CurDir => {}
+            other => lexical.push(other),
+        }
     }
 
-    Ok(Some(RepoContext {
+    // Symlinks are only resolved in the part that exists
+    let existing = lexical.ancestors().skip(1).find(|dir| dir.is_dir())?;
+    let rest = lexical.strip_prefix(existing).ok()?;
+    let abs_path = existing.canonicalize().ok()?.join(rest);
+
+    let repo = gix::discover(existing).ok()?;
+    // Bare repositories have no working tree to map the path into
+    let workdir = repo.workdir()?.canonicalize().ok()?;
+    let rel_path = abs_path.strip_prefix(&workdir).ok()?.to_path_buf();
+
+    Some(RepoContext {
         repo,
         workdir,
         rel_path,
-    }))
+    })
 }
 
 /// Check if a path should be excluded from git diff display
@@ -146,3 +157,177 @@ pub fn get_base_text_gix(file_path: &Path) -> Result<Option<String>> {
     // Read file from HEAD
     read_head_file_text(&repo_ctx.repo, &repo_ctx.rel_path)
 }
+
+/// Commits looked at when listing a file's history before giving up
+const MAX_HISTORY_WALK: usize = 20_000;
+
+/// A commit that changed a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCommit {
+    /// Full hex id
+    pub id: String,
+    pub author: String,
+    /// Author date as `YYYY-MM-DD`
+    pub date: String,
+    /// First line of the message
+    pub summary: String,
+}
+
+impl FileCommit {
+    /// The abbreviated id `git log --oneline` shows
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+}
+
+/// Commits reachable from HEAD that changed the file at `path`, newest
+/// first and at most `limit` of them. A commit counts when the file's
+/// content differs from its first parent's.
+#[cfg(feature = "git")]
+pub fn file_history(path: &Path, limit: usize) -> Result<Vec<FileCommit>> {
+    use anyhow::Context;
+    use bstr::ByteSlice;
+
+    let Some(ctx) = open_repo_for_path(path)? else {
+        anyhow::bail!("{} is not tracked by git", path.display());
+    };
+    let blob_at = |commit: &gix::Commit<'_>| {
+        commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.lookup_entry_by_path(&ctx.rel_path).ok().flatten())
+            .map(|entry| entry.object_id())
+    };
+
+    let head = ctx.repo.head_commit().context("No commits yet")?;
+    let mut commits = Vec::new();
+    for info in head.ancestors().all()?.take(MAX_HISTORY_WALK) {
+        let info = info?;
+        let commit = info.object()?;
+        let Some(blob) = blob_at(&commit) else {
+            continue;
+        };
+        let parent_blob = info
+            .parent_ids()
+            .next()
+            .and_then(|id| id.object().ok())
+            .and_then(|object| object.try_into_commit().ok())
+            .and_then(|parent| blob_at(&parent));
+        if parent_blob == Some(blob) {
+            continue;
+        }
+        let author = commit.author()?;
+        let date = author
+            .time()
+            .map(|time| time.format_or_unix(gix::date::time::format::SHORT))
+            .unwrap_or_default();
+        commits.push(FileCommit {
+            id: info.id.to_string(),
+            author: author.name.to_str_lossy().trim().to_string(),
+            date,
+            summary: commit.message()?.summary().to_str_lossy().into_owned(),
+        });
+        if commits.len() >= limit {
+            break;
+        }
+    }
+    Ok(commits)
+}
+
+/// Text of the file at `path` as of `rev` (a commit id, `HEAD~3`, a
+/// branch or tag name, ...), or `None` when the file didn't exist there.
+/// The file is looked up in the revision's tree, so it needn't exist or be
+/// tracked in the working tree.
+#[cfg(feature = "git")]
+pub fn read_file_at_revision(path: &Path, rev: &str) -> Result<Option<String>> {
+    use anyhow::Context;
+    use bstr::ByteSlice;
+
+    let Some(ctx) = discover_repo(path) else {
+        anyhow::bail!("{} is not in a git repository", path.display());
+    };
+    let commit = ctx
+        .repo
+        .rev_parse_single(rev)
+        .with_context(|| format!("Unknown revision: {}", rev))?
+        .object()?
+        .peel_to_commit()?;
+    let Some(entry) = commit.tree()?.lookup_entry_by_path(&ctx.rel_path)? else {
+        return Ok(None);
+    };
+    let object = entry.object()?;
+    Ok(Some(object.data.to_str_lossy().into_owned()))
+}
+
+#[cfg(all(test, feature = "git"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("run git")
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    #[test]
+    fn test_file_history_and_old_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let doc = dir.join("doc.md");
+        git(dir, &["init", "-q"]);
+        std::fs::write(&doc, "# One\n").unwrap();
+        git(dir, &["add", "doc.md"]);
+        git(dir, &["commit", "-q", "-m", "Start the doc"]);
+        std::fs::write(dir.join("other.md"), "x\n").unwrap();
+        git(dir, &["add", "other.md"]);
+        git(dir, &["commit", "-q", "-m", "Unrelated"]);
+        std::fs::write(&doc, "# Two\n").unwrap();
+        git(
+            dir,
+            &["commit", "-q", "-am", "Rename the heading\n\nDetails"],
+        );
+
+        let history = file_history(&doc, 10).unwrap();
+        let summaries: Vec<&str> = history.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Rename the heading", "Start the doc"]);
+        assert_eq!(history[0].author, "Ada");
+        assert_eq!(history[0].date.len(), 10);
+        assert_eq!(history[0].short_id().len(), 7);
+        assert_eq!(file_history(&doc, 1).unwrap().len(), 1);
+
+        let old = read_file_at_revision(&doc, &history[1].id).unwrap();
+        assert_eq!(old.as_deref(), Some("# One\n"));
+        let old = read_file_at_revision(&doc, "HEAD~2").unwrap();
+        assert_eq!(old.as_deref(), Some("# One\n"));
+        assert!(read_file_at_revision(&doc, "no-such-branch").is_err());
+    }
+
+    #[test]
+    fn test_read_deleted_file_at_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        std::fs::create_dir(dir.join("docs")).unwrap();
+        let doc = dir.join("docs/doc.md");
+        git(dir, &["init", "-q"]);
+        std::fs::write(&doc, "# Kept in git\n").unwrap();
+        git(dir, &["add", "docs/doc.md"]);
+        git(dir, &["commit", "-q", "-m", "Add the doc"]);
+
+        // Gone from the working tree and the index, but not from HEAD
+        git(dir, &["rm", "-q", "-r", "docs"]);
+        assert!(!dir.join("docs").exists());
+        let text = read_file_at_revision(&doc, "HEAD").unwrap();
+        assert_eq!(text.as_deref(), Some("# Kept in git\n"));
+        let dotted = dir.join("other/../docs/./doc.md");
+        let text = read_file_at_revision(&dotted, "HEAD").unwrap();
+        assert_eq!(text.as_deref(), Some("# Kept in git\n"));
+        let missing = read_file_at_revision(&dir.join("new.md"), "HEAD").unwrap();
+        assert!(missing.is_none());
+    }
+}
@@ -0,0 +1,409 @@
+//! Minimal raw HTML handling
+//!
+//! mdx is not a browser: raw HTML is reduced to its text content, with two
+//! exceptions that carry real structure in READMEs — `<details>` blocks
+//! (rendered as collapsible sections) and `<img>` tags (fed into the image
+//! pipeline). Comments (`<!-- ... -->`) are found separately so the viewer
+//! can hide them or show them dimmed.
+
+use crate::fence::{FenceLine, FenceTracker};
+use ropey::Rope;
+use std::ops::Range;
+
+/// A `<details>` block spanning one or more source lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailsBlock {
+    /// Line containing the opening `<details>` tag
+    pub start: usize,
+    /// Line containing the closing `</details>` tag
+    pub end: usize,
+    /// Line containing `<summary>`, if any
+    pub summary_line: Option<usize>,
+    /// Summary text with tags stripped ("Details" when absent)
+    pub summary: String,
+    /// Whether the block has the `open` attribute (expanded by default)
+    pub open: bool,
+}
+
+/// An `<img>` tag found in raw HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlImage {
+    /// Byte offset of the tag within the scanned text
+    pub offset: usize,
+    /// Byte length of the tag
+    pub len: usize,
+    pub src: String,
+    pub alt: String,
+    pub title: Option<String>,
+}
+
+/// Whether a line starts with an HTML tag, comment, or closing tag.
+/// Autolinks such as `<https://example.com>` are not HTML.
+pub fn is_html_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if !trimmed.contains('>') {
+        return false;
+    }
+    let Some(rest) = trimmed.strip_prefix('<') else {
+        return false;
+    };
+    if rest.starts_with('!') {
+        return true;
+    }
+    let name = rest.strip_prefix('/').unwrap_or(rest);
+    let name_len = name
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(name.len());
+    name_len > 0
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name[name_len..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/')
+}
+
+/// Reduce HTML to its text content.
+///
+/// Tags are dropped, `<img>` becomes its alt text in brackets, and
+/// `<br>` becomes a space. Entities are left for the Markdown parser.
+pub fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find('<') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open..];
+        match after.find('>') {
+            Some(close) => {
+                let tag = &after[..=close];
+                let name = tag_name(tag);
+                if name == "img" {
+                    let alt = attribute(tag, "alt").unwrap_or_default();
+                    if !alt.is_empty() {
+                        out.push_str(&format!("[{}]", alt));
+                    }
+                } else if name == "br" {
+                    out.push(' ');
+                }
+                rest = &after[close + 1..];
+            }
+            None => {
+                // Unterminated tag: keep the rest verbatim
+                out.push_str(after);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Lower-cased tag name of a tag such as `<img src="x">` or `</details>`
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Value of attribute `name` in a single tag, quoted or bare
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+
+    while let Some(pos) = lower[search_from..].find(name) {
+        let start = search_from + pos;
+        search_from = start + name.len();
+
+        // Must be a whole attribute name preceded by whitespace
+        let preceded_by_space = lower[..start]
+            .chars()
+            .last()
+            .is_some_and(char::is_whitespace);
+        if !preceded_by_space {
+            continue;
+        }
+
+        let after = tag[start + name.len()..].trim_start();
+        let Some(value) = after.strip_prefix('=') else {
+            if after.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+                // Boolean attribute such as `open`
+                return Some(String::new());
+            }
+            continue;
+        };
+        let value = value.trim_start();
+
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..]
+                .split(quote)
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches('/')
+                .to_string(),
+        });
+    }
+
+    None
+}
+
+/// Find every `<img>` tag in a chunk of HTML
+pub fn parse_img_tags(text: &str) -> Vec<HtmlImage> {
+    let lower = text.to_ascii_lowercase();
+    let mut images = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(pos) = lower[search_from..].find("<img") {
+        let offset = search_from + pos;
+        let Some(close) = text[offset..].find('>') else {
+            break;
+        };
+        let tag = &text[offset..=offset + close];
+        search_from = offset + close + 1;
+
+        if tag_name(tag) != "img" {
+            continue;
+        }
+        if let Some(src) = attribute(tag, "src") {
+            images.push(HtmlImage {
+                offset,
+                len: tag.len(),
+                src,
+                alt: attribute(tag, "alt").unwrap_or_default(),
+                title: attribute(tag, "title").filter(|t| !t.is_empty()),
+            });
+        }
+    }
+
+    images
+}
+
+/// Source lines of each HTML comment standing on lines of its own, from
+/// `<!--` to `-->`, outside fenced code blocks. Include directives
+/// (`<!-- include: file.md -->`) are not comments here since they show
+/// the included text, and unterminated comments are dropped.
+pub fn extract_comments(rope: &Rope) -> Vec<Range<usize>> {
+    let mut comments = Vec::new();
+    let mut fences = FenceTracker::new();
+    let mut open = None;
+
+    for line_idx in 0..rope.len_lines() {
+        let line: String = rope.line(line_idx).chunks().collect();
+        let trimmed = line.trim();
+        if let Some(start) = open {
+            if trimmed.contains("-->") {
+                comments.push(start..line_idx + 1);
+                open = None;
+            }
+            continue;
+        }
+        if fences.next_line(line.trim_end()) != FenceLine::Text
+            || fences.in_code_block()
+            || crate::transclude::parse_include(trimmed).is_some()
+        {
+            continue;
+        }
+        let Some(body) = trimmed.strip_prefix("<!--") else {
+            continue;
+        };
+        match body.find("-->") {
+            // Text after the comment makes it an inline one
+            Some(end) if body[end + 3..].trim().is_empty() => {
+                comments.push(line_idx..line_idx + 1);
+            }
+            Some(_) => {}
+            None => open = Some(line_idx),
+        }
+    }
+
+    comments
+}
+
+/// `line` without its inline `<!-- ... -->` comments. Comments inside a
+/// code span and unclosed ones are kept.
+pub fn strip_comments(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    let mut search_from = 0;
+
+    while let Some(pos) = rest[search_from..].find("<!--") {
+        let open = search_from + pos;
+        let Some(len) = rest[open..].find("-->").map(|close| close + 3) else {
+            break;
+        };
+        // An odd number of backticks before it puts it in a code span
+        let ticks = out.matches('`').count() + rest[..open].matches('`').count();
+        if ticks % 2 == 1 {
+            search_from = open + len;
+            continue;
+        }
+        out.push_str(&rest[..open]);
+        rest = &rest[open + len..];
+        search_from = 0;
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Find `<details>` blocks, ignoring anything inside fenced code blocks.
+/// Nested blocks are supported; unterminated blocks are dropped.
+pub fn extract_details(rope: &Rope) -> Vec<DetailsBlock> {
+    let mut blocks = Vec::new();
+    let mut stack: Vec<DetailsBlock> = Vec::new();
+    let mut fences = FenceTracker::new();
+
+    for line_idx in 0..rope.len_lines() {
+        let line: String = rope.line(line_idx).chunks().collect();
+        let trimmed = line.trim();
+
+        if fences.next_line(line.trim_end()) != FenceLine::Text {
+            continue;
+        }
+
+        let lower = trimmed.to_ascii_lowercase();
+        if let Some(pos) = lower.find("<details") {
+            let tag_end = trimmed[pos..]
+                .find('>')
+                .map_or(trimmed.len(), |i| pos + i + 1);
+            stack.push(DetailsBlock {
+                start: line_idx,
+                end: line_idx,
+                summary_line: None,
+                summary: "Details".to_string(),
+                open: attribute(&trimmed[pos..tag_end], "open").is_some(),
+            });
+        }
+
+        if lower.contains("<summary") {
+            if let Some(block) = stack.last_mut() {
+                if block.summary_line.is_none() {
+                    block.summary_line = Some(line_idx);
+                    let text = strip_tags(trimmed);
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        block.summary = text.to_string();
+                    }
+                }
+            }
+        }
+
+        if lower.contains("</details>") {
+            if let Some(mut block) = stack.pop() {
+                block.end = line_idx;
+                blocks.push(block);
+            }
+        }
+    }
+
+    blocks.sort_by_key(|b| b.start);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_html_line() {
+        assert!(is_html_line("<details>"));
+        assert!(is_html_line("  </div>"));
+        assert!(is_html_line("<!-- comment -->"));
+        assert!(!is_html_line("a < b > c"));
+        assert!(!is_html_line("< not a tag >"));
+        assert!(!is_html_line("plain text"));
+        assert!(!is_html_line("<https://example.com>"));
+        assert!(!is_html_line("<user@example.com>"));
+    }
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(strip_tags("<p>Hello <b>world</b></p>"), "Hello world");
+        assert_eq!(strip_tags("<img src=\"a.png\" alt=\"Logo\">"), "[Logo]");
+        assert_eq!(strip_tags("line<br/>break"), "line break");
+        assert_eq!(strip_tags("a < b"), "a < b");
+    }
+
+    #[test]
+    fn test_attribute() {
+        let tag = "<img src=\"a.png\" alt='Alt text' width=40>";
+        assert_eq!(attribute(tag, "src"), Some("a.png".to_string()));
+        assert_eq!(attribute(tag, "alt"), Some("Alt text".to_string()));
+        assert_eq!(attribute(tag, "width"), Some("40".to_string()));
+        assert_eq!(attribute(tag, "title"), None);
+        assert_eq!(attribute("<details open>", "open"), Some(String::new()));
+        // "src" inside another attribute name must not match
+        assert_eq!(attribute("<img data-src=\"x\">", "src"), None);
+    }
+
+    #[test]
+    fn test_parse_img_tags() {
+        let html = "<p><img src=\"a.png\" alt=\"A\"> and <IMG SRC=b.png title=\"B\"/></p>";
+        let images = parse_img_tags(html);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].src, "a.png");
+        assert_eq!(images[0].alt, "A");
+        assert_eq!(images[0].offset, 3);
+        assert_eq!(images[1].src, "b.png");
+        assert_eq!(images[1].title, Some("B".to_string()));
+    }
+
+    #[test]
+    fn test_extract_details() {
+        let rope = Rope::from(
+            "# Title\n<details>\n<summary>More <b>info</b></summary>\n\nBody\n</details>\n\n<details open><summary>Open</summary>\nx\n</details>\n",
+        );
+        let blocks = extract_details(&rope);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].start, 1);
+        assert_eq!(blocks[0].end, 5);
+        assert_eq!(blocks[0].summary_line, Some(2));
+        assert_eq!(blocks[0].summary, "More info");
+        assert!(!blocks[0].open);
+        assert_eq!(blocks[1].start, 7);
+        assert_eq!(blocks[1].summary_line, Some(7));
+        assert!(blocks[1].open);
+    }
+
+    #[test]
+    fn test_extract_details_ignores_code_blocks() {
+        let rope = Rope::from("```html\n<details>\n</details>\n```\n");
+        assert!(extract_details(&rope).is_empty());
+
+        // A shorter fence inside a longer one doesn't close it
+        let rope = Rope::from("````md\n```\n<details>\n</details>\n```\n````\n");
+        assert!(extract_details(&rope).is_empty());
+    }
+
+    #[test]
+    fn test_extract_comments() {
+        let rope = Rope::from(
+            "# Title\n<!-- note -->\ntext <!-- inline -->\n<!--\nreview:\n  fix this\n-->\n\
+             ```html\n<!-- code -->\n```\n<!-- include: part.md -->\n<!-- open\n",
+        );
+        assert_eq!(extract_comments(&rope), vec![1..2, 3..7]);
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        assert_eq!(strip_comments("a <!-- b --> c"), "a  c");
+        assert_eq!(strip_comments("<!--x--><!--y-->z"), "z");
+        assert_eq!(strip_comments("use `<!-- -->` here"), "use `<!-- -->` here");
+        assert_eq!(strip_comments("a <!-- open"), "a <!-- open");
+    }
+
+    #[test]
+    fn test_extract_details_nested() {
+        let rope = Rope::from("<details>\n<details>\n</details>\n</details>\n");
+        let blocks = extract_details(&rope);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].start, blocks[0].end), (0, 3));
+        assert_eq!((blocks[1].start, blocks[1].end), (1, 2));
+        assert_eq!(blocks[0].summary, "Details");
+    }
+}
@@ -13,6 +13,25 @@ pub struct ImageNode {
     pub title: Option<String>,
     /// Source line number in document
     pub source_line: usize,
+    /// Byte column where the image markup starts in its source line
+    pub column: usize,
+    /// Block the image sits in
+    pub context: ImageContext,
+    /// Whether the image is the only content of its line, apart from
+    /// indentation and list or block quote markers
+    pub standalone: bool,
+}
+
+/// Where an image sits in the document structure
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageContext {
+    /// A paragraph or raw HTML block
+    #[default]
+    Paragraph,
+    /// A list item
+    ListItem,
+    /// A table cell
+    TableCell,
 }
 
 /// Image resolution result
@@ -32,6 +51,9 @@ impl ImageNode {
             alt,
             title: None,
             source_line,
+            column: 0,
+            context: ImageContext::Paragraph,
+            standalone: true,
         }
     }
 
@@ -42,9 +64,20 @@ impl ImageNode {
             alt,
             title: Some(title),
             source_line,
+            column: 0,
+            context: ImageContext::Paragraph,
+            standalone: true,
         }
     }
 
+    /// Text to show beneath the image: its title, or else its alt text
+    pub fn caption(&self) -> Option<&str> {
+        self.title
+            .as_deref()
+            .filter(|title| !title.trim().is_empty())
+            .or(Some(self.alt.as_str()).filter(|alt| !alt.trim().is_empty()))
+    }
+
     /// Resolve image source relative to document path
     pub fn resolve(&self, doc_path: &Path) -> Option<ImageSource> {
         self.resolve_with_policy(doc_path, true, true)
@@ -125,6 +158,12 @@ mod tests {
             10,
         );
         assert_eq!(img.title, Some("Title".to_string()));
+        assert_eq!(img.caption(), Some("Title"));
+
+        let untitled = ImageNode::new("test.png".to_string(), "Test image".to_string(), 10);
+        assert_eq!(untitled.caption(), Some("Test image"));
+        let bare = ImageNode::new("test.png".to_string(), " ".to_string(), 10);
+        assert_eq!(bare.caption(), None);
     }
 
     #[test]
@@ -1,18 +1,39 @@
 //! MDX Core - Document model, parsing, and configuration
 //!
 //! This crate contains the core logic for mdx, independent of terminal UI concerns:
-//! - Document model with Rope-based text storage
-//! - Markdown parsing and TOC extraction
+//! - Document model with Rope-based text storage (Markdown, or Jupyter
+//!   notebooks and CSV/TSV tables converted to Markdown)
+//! - Markdown parsing and TOC extraction, and CommonMark block structure
+//!   mapped to source lines
+//! - Terminal-agnostic rendering to semantically styled spans
 //! - Selection model
 //! - Configuration management
 //! - Git diff computation (optional feature)
 
+pub mod blocks;
 pub mod config;
+pub mod conflict;
+pub mod decode;
 pub mod doc;
+pub mod exec;
+pub mod export;
+pub mod fence;
+pub mod format;
 pub mod front_matter;
+pub mod html;
+pub mod links;
+pub mod list;
+pub mod lint;
+pub mod outline;
+pub mod render;
 pub mod security;
+pub mod session;
 pub mod selection;
+pub mod slides;
+pub mod spell;
+pub mod stats;
 pub mod toc;
+pub mod transclude;
 
 #[cfg(feature = "git")]
 pub mod diff;
@@ -0,0 +1,869 @@
+//! Link and image reference extraction and local validation
+
+use crate::config::MarkdownFlavor;
+use crate::doc::{Document, Heading};
+use crate::html;
+use ropey::Rope;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directories searched below a vault root for a `[[Page]]` by file name
+const MAX_VAULT_DEPTH: usize = 8;
+
+/// Directory entries looked at in that search before giving up
+const MAX_VAULT_ENTRIES: usize = 20_000;
+
+/// A link or image destination found in the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRef {
+    /// Line containing the start of the link
+    pub line: usize,
+    /// Byte range of the link syntax within the document
+    pub range: std::ops::Range<usize>,
+    /// Destination as written
+    pub dest: String,
+    /// Link text, or alt text for images
+    pub text: String,
+    /// Whether this is an image rather than a link
+    pub is_image: bool,
+    /// Whether this is an Obsidian-style `[[page#heading|alias]]` link,
+    /// whose destination is a page name; see [`resolve_wiki_link`]
+    pub wiki: bool,
+    /// Label of a reference-style link (`[text][label]`, `[label]`), whose
+    /// destination comes from a definition
+    pub reference: Option<String>,
+}
+
+/// Destination and title of a reference definition (`[label]: dest "title"`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkDefinition {
+    pub dest: String,
+    pub title: String,
+}
+
+/// Reference definitions of a document, keyed by [`normalize_label`]
+pub type LinkDefinitions = HashMap<String, LinkDefinition>;
+
+/// A footnote reference (`[^label]`) found in the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FootnoteRef {
+    pub line: usize,
+    pub label: String,
+}
+
+/// Footnote references of a document, and the source lines of each
+/// definition keyed by [`normalize_label`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footnotes {
+    pub references: Vec<FootnoteRef>,
+    pub definitions: HashMap<String, std::ops::Range<usize>>,
+}
+
+/// A link whose target could not be found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub link: LinkRef,
+    /// Why the target is considered broken
+    pub problem: String,
+}
+
+impl LinkRef {
+    /// Whether the destination points off this machine (http, mailto, ...)
+    pub fn is_remote(&self) -> bool {
+        is_remote(&self.dest)
+    }
+
+    /// Whether this is a `[[wiki link]]` to another page, which may take a
+    /// search of the whole vault to find
+    pub fn is_wiki_page(&self) -> bool {
+        self.wiki && !self.dest.starts_with('#')
+    }
+}
+
+/// Whether a destination has a URL scheme or is protocol-relative
+pub fn is_remote(dest: &str) -> bool {
+    if dest.starts_with("//") {
+        return true;
+    }
+    // A scheme is letters/digits/+.- followed by ':'; a single letter is a
+    // Windows drive, not a scheme
+    match dest.find(':') {
+        Some(pos) if pos > 1 => dest[..pos]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')),
+        _ => false,
+    }
+}
+
+/// Find every link and image in the document, including raw `<img>` tags
+/// and, in GitHub Flavored Markdown, bare URLs
+pub fn extract_links(rope: &Rope, flavor: MarkdownFlavor) -> Vec<LinkRef> {
+    use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd, TextMergeWithOffset};
+
+    let text: String = rope.chunks().collect();
+    let options = crate::blocks::parser_options(flavor);
+    let last_byte = rope.len_bytes().saturating_sub(1);
+
+    let mut links: Vec<LinkRef> = Vec::new();
+    // Index into `links` of the link whose text is being collected
+    let mut open_link: Option<usize> = None;
+
+    let events = TextMergeWithOffset::new(Parser::new_ext(&text, options).into_offset_iter());
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                ref dest_url,
+                ref id,
+                ..
+            })
+            | Event::Start(Tag::Image {
+                link_type,
+                ref dest_url,
+                ref id,
+                ..
+            }) => {
+                let is_image = matches!(event, Event::Start(Tag::Image { .. }));
+                open_link = Some(links.len());
+                links.push(LinkRef {
+                    line: rope.byte_to_line(range.start.min(last_byte)),
+                    range,
+                    dest: dest_url.to_string(),
+                    text: String::new(),
+                    is_image,
+                    wiki: matches!(link_type, LinkType::WikiLink { .. }),
+                    reference: matches!(
+                        link_type,
+                        LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut
+                    )
+                    .then(|| id.to_string()),
+                });
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => open_link = None,
+            Event::Text(ref content) | Event::Code(ref content) => {
+                if let Some(idx) = open_link {
+                    links[idx].text.push_str(content);
+                } else if flavor == MarkdownFlavor::Gfm && matches!(event, Event::Text(_)) {
+                    for (url, dest) in find_bare_urls(content) {
+                        let start = range.start + url.start;
+                        links.push(LinkRef {
+                            line: rope.byte_to_line(start.min(last_byte)),
+                            range: start..range.start + url.end,
+                            dest,
+                            text: content[url].to_string(),
+                            is_image: false,
+                            wiki: false,
+                            reference: None,
+                        });
+                    }
+                }
+            }
+            Event::Html(ref raw) | Event::InlineHtml(ref raw) => {
+                for tag in html::parse_img_tags(raw) {
+                    let start = range.start + tag.offset;
+                    links.push(LinkRef {
+                        line: rope.byte_to_line(start.min(last_byte)),
+                        range: start..start + 4,
+                        dest: tag.src,
+                        text: tag.alt,
+                        is_image: true,
+                        wiki: false,
+                        reference: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    links
+}
+
+/// Every footnote reference in the document, and where each footnote is
+/// defined
+pub fn extract_footnotes(rope: &Rope, flavor: MarkdownFlavor) -> Footnotes {
+    use pulldown_cmark::{Event, Parser, Tag};
+
+    let text: String = rope.chunks().collect();
+    let last_byte = rope.len_bytes().saturating_sub(1);
+    let line_of = |byte: usize| rope.byte_to_line(byte.min(last_byte));
+
+    let mut footnotes = Footnotes::default();
+    let parser = Parser::new_ext(&text, crate::blocks::parser_options(flavor));
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::FootnoteReference(label) => footnotes.references.push(FootnoteRef {
+                line: line_of(range.start),
+                label: label.to_string(),
+            }),
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                // The definition's range takes in the blank lines after it
+                let start = line_of(range.start);
+                let mut end = line_of(range.end.saturating_sub(1).max(range.start)) + 1;
+                while end > start + 1 && rope.line(end - 1).chars().all(char::is_whitespace) {
+                    end -= 1;
+                }
+                footnotes
+                    .definitions
+                    .entry(normalize_label(&label))
+                    .or_insert(start..end);
+            }
+            _ => {}
+        }
+    }
+    footnotes
+}
+
+/// Bare URLs that GitHub Flavored Markdown makes links of
+/// (`https://example.com`, `www.example.com`): their byte range in `text`
+/// and the destination. Trailing punctuation and an unbalanced closing
+/// parenthesis are left out, as GitHub does.
+pub fn find_bare_urls(text: &str) -> Vec<(std::ops::Range<usize>, String)> {
+    let mut urls = Vec::new();
+    let mut search = 0;
+    while let Some(found) = ["https://", "http://", "www."]
+        .iter()
+        .filter_map(|prefix| Some((text[search..].find(prefix)? + search, prefix.len())))
+        .min()
+    {
+        let (start, prefix_len) = found;
+        // Only at the start of a word, so `xhttps://` stays text
+        let starts_word = text[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '*' | '_' | '~' | '('));
+        let mut end = text[start..]
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .map_or(text.len(), |i| start + i);
+        loop {
+            let url = &text[start..end];
+            let unbalanced = url.matches(')').count() > url.matches('(').count();
+            if url.ends_with(['?', '!', '.', ',', ':', '*', '_', '~', '\'', '"'])
+                || url.ends_with(')') && unbalanced
+            {
+                end -= 1;
+            } else {
+                break;
+            }
+        }
+        // A URL needs something after the scheme, with a dot in the domain
+        let host = text[start + prefix_len..end]
+            .split('/')
+            .next()
+            .unwrap_or("");
+        if starts_word && host.contains('.') && !host.starts_with('.') {
+            let url = &text[start..end];
+            let dest = if url.starts_with("www.") {
+                format!("http://{}", url)
+            } else {
+                url.to_string()
+            };
+            urls.push((start..end, dest));
+            search = end;
+        } else {
+            search = start + prefix_len;
+        }
+    }
+    urls
+}
+
+/// File paths written as plain text (`./notes/todo.md`, `../README.md`,
+/// `~/notes/inbox.md`, `/etc/hosts`, `docs/guide.md`, `notes.md`), with
+/// an optional `:line` suffix: their byte range in `text` and the path as
+/// written. Paths not starting with `.`, `~` or `/` need a file extension,
+/// and a bare file name a Markdown one, so `and/or` and `e.g.` stay text.
+pub fn find_bare_paths(text: &str) -> Vec<(std::ops::Range<usize>, String)> {
+    let mut paths = Vec::new();
+    let mut offset = 0;
+    for word in text.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += word.len();
+        // Brackets, quotes, and sentence punctuation around the path
+        let word = word.trim_end();
+        let trimmed = word.trim_start_matches(['(', '[', '"', '\'']);
+        let lead = word.len() - trimmed.len();
+        let path = trimmed.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '"', '\'']);
+        if is_bare_path(path) {
+            paths.push((start + lead..start + lead + path.len(), path.to_string()));
+        }
+    }
+    paths
+}
+
+/// Whether a word reads as a file path; see [`find_bare_paths`]
+fn is_bare_path(word: &str) -> bool {
+    let (path, _) = split_line_suffix(word);
+    let allowed = |c: char| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '~' | '+');
+    if path.is_empty()
+        || path.starts_with("//")
+        || path.starts_with("www.")
+        || !path.chars().all(allowed)
+    {
+        return false;
+    }
+    if ["./", "../", "~/"]
+        .iter()
+        .any(|prefix| path.len() > prefix.len() && path.starts_with(prefix))
+    {
+        return true;
+    }
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let extension = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !stem.ends_with('.') => ext,
+        _ => "",
+    };
+    let has_extension = (1..=10).contains(&extension.len())
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        && extension.chars().any(|c| c.is_ascii_alphabetic());
+    if let Some(rest) = path.strip_prefix('/') {
+        // `/etc/hosts`, but not a lone `/usr` or `/s`
+        return rest.contains('/') && !rest.starts_with('/') || has_extension;
+    }
+    has_extension
+        && (path.contains('/')
+            || ["md", "markdown", "mdx"]
+                .iter()
+                .any(|md| extension.eq_ignore_ascii_case(md)))
+}
+
+/// Split a `:line` (or `:line:column`) suffix off a path written as
+/// plain text, as compilers and grep print them
+pub fn split_line_suffix(path: &str) -> (&str, Option<usize>) {
+    let mut rest = path;
+    let mut line = None;
+    for _ in 0..2 {
+        match rest.rsplit_once(':') {
+            Some((head, number))
+                if !head.is_empty()
+                    && !number.is_empty()
+                    && number.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                line = number.parse().ok();
+                rest = head;
+            }
+            _ => break,
+        }
+    }
+    (rest, line)
+}
+
+/// A local file a link or plain-text path points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalTarget {
+    pub path: PathBuf,
+    /// `#fragment` of a Markdown link
+    pub fragment: Option<String>,
+    /// 1-based line of a `path:line` reference
+    pub line: Option<usize>,
+}
+
+/// The file `dest` points at from a document in `base_dir`, with `%XX`
+/// escapes decoded and `~/` taken from `$HOME`. `None` for remote and
+/// same-document destinations.
+pub fn resolve_local_target(dest: &str, base_dir: &Path) -> Option<LocalTarget> {
+    if dest.is_empty() || is_remote(dest) {
+        return None;
+    }
+    let (path, fragment) = match dest.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment.to_string())),
+        None => (dest, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    let (path, line) = split_line_suffix(path);
+    let decoded = percent_decode(path);
+    let path = match decoded.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => base_dir.join(&decoded),
+    };
+    Some(LocalTarget {
+        path,
+        fragment,
+        line,
+    })
+}
+
+/// Every reference definition in the document, so that `[text][label]`
+/// can be resolved when styling a single line
+pub fn extract_definitions(rope: &Rope) -> LinkDefinitions {
+    use pulldown_cmark::{Options, Parser};
+
+    let text: String = rope.chunks().collect();
+    let mut parser = Parser::new_ext(&text, Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES);
+    // Definitions are collected while parsing
+    parser.by_ref().for_each(drop);
+    parser
+        .reference_definitions()
+        .iter()
+        .map(|(label, def)| {
+            (
+                normalize_label(label),
+                LinkDefinition {
+                    dest: def.dest.to_string(),
+                    title: def.title.as_deref().unwrap_or_default().to_string(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Reference labels match case-insensitively with runs of whitespace
+/// collapsed
+pub fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Links and images in a document whose local targets are missing, with
+/// `[[wiki links]]` also looked up under `vault_root`. Remote URLs are
+/// skipped; see `check_remote_link`.
+pub fn find_broken_links(doc: &Document, vault_root: Option<&Path>) -> Vec<BrokenLink> {
+    let base_dir = doc.path.parent().unwrap_or(Path::new("."));
+    // Bare URLs are always remote and skipped; the GFM flavor is for wiki links
+    let links = extract_links(&doc.rope, MarkdownFlavor::Gfm);
+    let mut broken = find_broken_local_links(doc, &links);
+    broken.extend(find_missing_wiki_pages(&links, base_dir, vault_root));
+    broken.sort_by_key(|b| b.link.range.start);
+    broken
+}
+
+/// Broken links among `links` of a document, other than `[[wiki links]]`
+/// to other pages: those are left to [`find_missing_wiki_pages`]
+pub fn find_broken_local_links(doc: &Document, links: &[LinkRef]) -> Vec<BrokenLink> {
+    let base_dir = doc.path.parent().unwrap_or(Path::new("."));
+    links
+        .iter()
+        .filter(|link| !link.is_wiki_page())
+        .filter_map(|link| {
+            let problem = if link.wiki {
+                check_wiki_link(&link.dest, base_dir, None, &doc.headings)
+            } else {
+                check_local_link(&link.dest, base_dir, &doc.headings)
+            }?;
+            let link = link.clone();
+            Some(BrokenLink { link, problem })
+        })
+        .collect()
+}
+
+/// `[[wiki links]]` among `links` to pages that can't be found from
+/// `base_dir`; see [`resolve_wiki_link`]
+pub fn find_missing_wiki_pages(
+    links: &[LinkRef],
+    base_dir: &Path,
+    vault_root: Option<&Path>,
+) -> Vec<BrokenLink> {
+    links
+        .iter()
+        .filter(|link| link.is_wiki_page())
+        .filter_map(|link| {
+            let problem = check_wiki_link(&link.dest, base_dir, vault_root, &[])?;
+            let link = link.clone();
+            Some(BrokenLink { link, problem })
+        })
+        .collect()
+}
+
+/// Check a `[[page#heading]]` destination; like [`check_local_link`],
+/// headings are only checked within the document itself
+fn check_wiki_link(
+    dest: &str,
+    base_dir: &Path,
+    vault_root: Option<&Path>,
+    headings: &[Heading],
+) -> Option<String> {
+    let (page, heading) = dest.split_once('#').unwrap_or((dest, ""));
+    if page.is_empty() {
+        return (!heading.is_empty() && find_wiki_heading(headings, heading).is_none())
+            .then(|| format!("no heading '{}'", heading));
+    }
+    match resolve_wiki_link(page, base_dir, vault_root) {
+        Some(_) => None,
+        None => Some(format!("no page named '{}'", page)),
+    }
+}
+
+/// The file a `[[page]]` link names: `page` (with `.md` added when it has
+/// no extension) next to the linking file, then at the same path under
+/// the vault root, then anywhere below the vault root by file name, as
+/// Obsidian resolves links
+pub fn resolve_wiki_link(
+    page: &str,
+    base_dir: &Path,
+    vault_root: Option<&Path>,
+) -> Option<PathBuf> {
+    let page = page.trim();
+    if page.is_empty() || Path::new(page).is_absolute() {
+        return None;
+    }
+    let file = if Path::new(page).extension().is_some() {
+        PathBuf::from(page)
+    } else {
+        PathBuf::from(format!("{}.md", page))
+    };
+    let candidate = base_dir.join(&file);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    let vault_root = vault_root?;
+    let candidate = vault_root.join(&file);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    let name = file.file_name()?;
+    find_file_named(vault_root, name)
+}
+
+/// Breadth-first search below `root` for a file called `name`, skipping
+/// hidden directories (`.obsidian`, `.git`)
+fn find_file_named(root: &Path, name: &std::ffi::OsStr) -> Option<PathBuf> {
+    let mut dirs = std::collections::VecDeque::from([(root.to_path_buf(), 0)]);
+    let mut seen = 0;
+    while let Some((dir, depth)) = dirs.pop_front() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = read_dir.flatten().collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            seen += 1;
+            if seen > MAX_VAULT_ENTRIES {
+                return None;
+            }
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if path.is_dir() && !hidden && depth < MAX_VAULT_DEPTH {
+                dirs.push_back((path, depth + 1));
+            } else if entry.file_name().eq_ignore_ascii_case(name) && path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+/// The heading a `[[page#Heading]]` names. Obsidian links by heading
+/// text, ignoring case; `#anchor` slugs are accepted as well.
+pub fn find_wiki_heading<'a>(headings: &'a [Heading], heading: &str) -> Option<&'a Heading> {
+    let heading = heading.trim();
+    headings
+        .iter()
+        .find(|h| h.text.trim().to_lowercase() == heading.to_lowercase())
+        .or_else(|| crate::toc::find_anchor(headings, heading))
+}
+
+/// Check a remote URL with an HTTP HEAD request, falling back to GET for
+/// servers that reject HEAD. Returns a description of the problem, or
+/// `None` when the server answers with a success or redirect status.
+#[cfg(feature = "remote-links")]
+pub fn check_remote_link(url: &str, timeout: std::time::Duration) -> Option<String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return None;
+    }
+
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let response = match agent.head(url).call() {
+        Err(ureq::Error::Status(405, _)) | Err(ureq::Error::Status(501, _)) => {
+            agent.get(url).call()
+        }
+        other => other,
+    };
+
+    match response {
+        Ok(_) => None,
+        Err(ureq::Error::Status(code, _)) => Some(format!("server returned HTTP {}", code)),
+        Err(ureq::Error::Transport(err)) => Some(format!("request failed: {}", err)),
+    }
+}
+
+/// Check a local link against the filesystem and the document's headings.
+///
+/// Returns a description of the problem, or `None` when the target exists.
+/// Remote URLs and site-absolute paths (`/docs/x.md`) are not checked.
+pub fn check_local_link(dest: &str, base_dir: &Path, headings: &[Heading]) -> Option<String> {
+    if dest.is_empty() || is_remote(dest) || dest.starts_with('/') {
+        return None;
+    }
+
+    let (path, fragment) = match dest.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (dest, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+
+    if path.is_empty() {
+        // Same-document anchor
+        let fragment = fragment.unwrap_or_default();
+        if !fragment.is_empty() && crate::toc::find_anchor(headings, fragment).is_none() {
+            return Some(format!("no heading with anchor '#{}'", fragment));
+        }
+        return None;
+    }
+
+    let decoded = percent_decode(path);
+    if !base_dir.join(&decoded).exists() {
+        return Some(format!("'{}' does not exist", decoded));
+    }
+
+    None
+}
+
+/// Decode `%XX` escapes, leaving malformed sequences as written
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hi = (bytes[i + 1] as char).to_digit(16);
+            let lo = (bytes[i + 2] as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hi, lo) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toc::extract_headings;
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("https://example.com"));
+        assert!(is_remote("mailto:someone@example.com"));
+        assert!(is_remote("//cdn.example.com/x.png"));
+        assert!(!is_remote("docs/guide.md"));
+        assert!(!is_remote("C:/docs/guide.md"));
+        assert!(!is_remote("#section"));
+    }
+
+    #[test]
+    fn test_extract_links() {
+        let rope = Rope::from(
+            "# Title\n\nSee [guide](guide.md) and ![logo](img/logo.png).\n\n<img src=\"a.png\">\n",
+        );
+        let links = extract_links(&rope, MarkdownFlavor::Gfm);
+        let dests: Vec<_> = links
+            .iter()
+            .map(|l| (l.dest.as_str(), l.text.as_str(), l.is_image, l.line))
+            .collect();
+        assert_eq!(
+            dests,
+            vec![
+                ("guide.md", "guide", false, 2),
+                ("img/logo.png", "logo", true, 2),
+                ("a.png", "", true, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_paths() {
+        let text = "Edit ./notes/todo.md, (~/inbox.md) or src/main.rs:42. \
+                    See README.md and /etc/hosts; not and/or, e.g. or Node.js";
+        let found = find_bare_paths(text);
+        assert!(found
+            .iter()
+            .all(|(range, path)| text[range.clone()] == *path));
+        let paths: Vec<&str> = found.iter().map(|(_, path)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "./notes/todo.md",
+                "~/inbox.md",
+                "src/main.rs:42",
+                "README.md",
+                "/etc/hosts"
+            ]
+        );
+        assert!(find_bare_paths("https://x.org/a.md www.x.org/a.md /usr 1/2.5").is_empty());
+
+        assert_eq!(
+            split_line_suffix("src/main.rs:42:7"),
+            ("src/main.rs", Some(42))
+        );
+        assert_eq!(split_line_suffix("notes.md"), ("notes.md", None));
+
+        let base = Path::new("/docs");
+        let target = resolve_local_target("guide%20one.md#setup", base).unwrap();
+        assert_eq!(target.path, PathBuf::from("/docs/guide one.md"));
+        assert_eq!(target.fragment.as_deref(), Some("setup"));
+        let target = resolve_local_target("../src/lib.rs:10", base).unwrap();
+        assert_eq!(target.path, PathBuf::from("/docs/../src/lib.rs"));
+        assert_eq!(target.line, Some(10));
+        assert!(resolve_local_target("https://x.org/a.md", base).is_none());
+        assert!(resolve_local_target("#intro", base).is_none());
+    }
+
+    #[test]
+    fn test_bare_urls() {
+        let text =
+            "See https://example.com/a_(b). and (www.rust-lang.org), not xhttp://a.b or https://x";
+        let urls: Vec<_> = find_bare_urls(text)
+            .into_iter()
+            .map(|(range, dest)| (&text[range], dest))
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                (
+                    "https://example.com/a_(b)",
+                    "https://example.com/a_(b)".to_string()
+                ),
+                ("www.rust-lang.org", "http://www.rust-lang.org".to_string()),
+            ]
+        );
+
+        let rope = Rope::from("Docs at https://docs.rs and `https://code.example`\n");
+        let dests = |flavor| -> Vec<String> {
+            extract_links(&rope, flavor)
+                .into_iter()
+                .map(|l| l.dest)
+                .collect()
+        };
+        assert_eq!(dests(MarkdownFlavor::Gfm), ["https://docs.rs"]);
+        assert!(dests(MarkdownFlavor::CommonMark).is_empty());
+    }
+
+    #[test]
+    fn test_reference_links_and_definitions() {
+        let rope = Rope::from(
+            "[![Build][badge]][ci] see [Docs]\n\n[ci]: https://ci.example.com\n\
+             [badge]: img/badge.svg \"Build status\"\n[docs]: docs/index.md\n",
+        );
+        let dests: Vec<_> = extract_links(&rope, MarkdownFlavor::Gfm)
+            .into_iter()
+            .map(|l| (l.dest, l.is_image))
+            .collect();
+        assert_eq!(
+            dests,
+            vec![
+                ("https://ci.example.com".to_string(), false),
+                ("img/badge.svg".to_string(), true),
+                ("docs/index.md".to_string(), false),
+            ]
+        );
+
+        let defs = extract_definitions(&rope);
+        assert_eq!(defs.len(), 3);
+        assert_eq!(defs["badge"].title, "Build status");
+        assert_eq!(defs[&normalize_label("  DOCS ")].dest, "docs/index.md");
+    }
+
+    #[test]
+    fn test_check_local_link() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("exists.md"), "x").unwrap();
+        std::fs::write(dir.path().join("with space.md"), "x").unwrap();
+        let headings = extract_headings(&Rope::from("# Getting Started\n"));
+
+        assert_eq!(check_local_link("exists.md", dir.path(), &headings), None);
+        assert_eq!(
+            check_local_link("exists.md#anything", dir.path(), &headings),
+            None
+        );
+        assert_eq!(
+            check_local_link("with%20space.md", dir.path(), &headings),
+            None
+        );
+        assert_eq!(
+            check_local_link("#getting-started", dir.path(), &headings),
+            None
+        );
+        assert_eq!(
+            check_local_link("https://x.invalid", dir.path(), &headings),
+            None
+        );
+        assert!(check_local_link("missing.md", dir.path(), &headings).is_some());
+        assert!(check_local_link("#nowhere", dir.path(), &headings).is_some());
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let rope = Rope::from("See [[Page Name]] and [[notes/b#Setup Steps|setup]].\n");
+        let links: Vec<_> = extract_links(&rope, MarkdownFlavor::Gfm)
+            .into_iter()
+            .map(|l| (l.dest, l.text, l.wiki))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("Page Name".to_string(), "Page Name".to_string(), true),
+                ("notes/b#Setup Steps".to_string(), "setup".to_string(), true),
+            ]
+        );
+        assert!(extract_links(&rope, MarkdownFlavor::CommonMark).is_empty());
+
+        let vault = tempfile::tempdir().unwrap();
+        let here = vault.path().join("daily");
+        std::fs::create_dir_all(here.join("deep")).unwrap();
+        std::fs::create_dir_all(vault.path().join(".obsidian")).unwrap();
+        std::fs::write(here.join("Page Name.md"), "x").unwrap();
+        std::fs::write(vault.path().join("top.md"), "x").unwrap();
+        std::fs::write(here.join("deep/Buried.md"), "x").unwrap();
+        std::fs::write(vault.path().join(".obsidian/hidden.md"), "x").unwrap();
+
+        let resolve = |page| resolve_wiki_link(page, &here, Some(vault.path()));
+        assert_eq!(resolve("Page Name"), Some(here.join("Page Name.md")));
+        assert_eq!(resolve("top"), Some(vault.path().join("top.md")));
+        assert_eq!(resolve("buried"), Some(here.join("deep/Buried.md")));
+        assert_eq!(resolve("hidden"), None);
+        assert_eq!(resolve_wiki_link("top", &here, None), None);
+
+        let headings = extract_headings(&Rope::from("# Setup Steps\n"));
+        assert!(find_wiki_heading(&headings, "setup steps").is_some());
+        assert!(find_wiki_heading(&headings, "setup-steps").is_some());
+    }
+
+    #[test]
+    fn test_find_broken_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(
+            &path,
+            "# T\n\n[ok](doc.md) and [`gone`](gone.md)\n\n[[doc]] [[#T]] [[Nowhere]]\n",
+        )
+        .unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+
+        let broken = find_broken_links(&doc, None);
+        assert_eq!(broken.len(), 2);
+        assert_eq!(broken[1].problem, "no page named 'Nowhere'");
+        assert_eq!(broken[0].link.dest, "gone.md");
+        assert_eq!(broken[0].link.text, "gone");
+        assert_eq!(broken[0].link.line, 2);
+    }
+
+    #[test]
+    fn test_extract_footnotes_and_reference_labels() {
+        let rope = Rope::from(
+            "Claim[^1] and [docs][Ref].\n\n[^1]: First line\n    and more.\n\n\
+             [ref]: https://example.com\n",
+        );
+        let footnotes = extract_footnotes(&rope, MarkdownFlavor::Gfm);
+        assert_eq!(
+            footnotes.references,
+            vec![FootnoteRef {
+                line: 0,
+                label: "1".to_string()
+            }]
+        );
+        assert_eq!(footnotes.definitions.get("1"), Some(&(2..4)));
+
+        let links = extract_links(&rope, MarkdownFlavor::Gfm);
+        assert_eq!(links[0].reference.as_deref(), Some("Ref"));
+        assert_eq!(links[0].dest, "https://example.com");
+    }
+}
@@ -0,0 +1,337 @@
+//! Structural checks for Markdown documents (`mdx lint`)
+
+use crate::doc::Document;
+use crate::fence::{FenceLine, FenceTracker};
+use crate::links;
+use ropey::Rope;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// How serious a lint finding is. Errors fail `mdx lint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// 0-based line the issue was found on
+    pub line: usize,
+    pub severity: Severity,
+    /// Short rule identifier, e.g. `broken-link`
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}]: {}",
+            self.line + 1,
+            self.severity,
+            self.rule,
+            self.message
+        )
+    }
+}
+
+/// Run every check on a document, sorted by line. `[[wiki links]]` are
+/// also looked up under `vault_root`.
+pub fn lint(doc: &Document, vault_root: Option<&Path>) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    issues.extend(check_heading_levels(doc));
+    issues.extend(check_duplicate_anchors(doc));
+    issues.extend(check_links(doc, vault_root));
+    issues.extend(check_tables(&doc.rope));
+    issues.extend(check_fences(&doc.rope));
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// Headings that skip a level, e.g. `#` followed by `###`
+fn check_heading_levels(doc: &Document) -> Vec<LintIssue> {
+    doc.headings
+        .windows(2)
+        .filter(|pair| pair[1].level > pair[0].level + 1)
+        .map(|pair| LintIssue {
+            line: pair[1].line,
+            severity: Severity::Warning,
+            rule: "heading-increment",
+            message: format!(
+                "heading level jumps from h{} to h{}",
+                pair[0].level, pair[1].level
+            ),
+        })
+        .collect()
+}
+
+/// Headings whose anchors collide. Later ones get a numbered anchor
+/// (`#usage-1`), which changes whenever the headings are reordered.
+fn check_duplicate_anchors(doc: &Document) -> Vec<LintIssue> {
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    let mut issues = Vec::new();
+
+    for heading in &doc.headings {
+        let slug = crate::toc::slugify(&heading.text);
+        if slug.is_empty() {
+            continue;
+        }
+        match first_seen.get(&slug) {
+            Some(&first_line) => issues.push(LintIssue {
+                line: heading.line,
+                severity: Severity::Warning,
+                rule: "duplicate-anchor",
+                message: format!(
+                    "anchor '#{}' already used by the heading on line {}; this one is '#{}'",
+                    slug,
+                    first_line + 1,
+                    heading.anchor
+                ),
+            }),
+            None => {
+                first_seen.insert(slug, heading.line);
+            }
+        }
+    }
+
+    issues
+}
+
+/// Relative links and images whose targets are missing
+fn check_links(doc: &Document, vault_root: Option<&Path>) -> Vec<LintIssue> {
+    links::find_broken_links(doc, vault_root)
+        .into_iter()
+        .map(|broken| {
+            let (rule, kind) = if broken.link.is_image {
+                ("broken-image", "image")
+            } else {
+                ("broken-link", "link")
+            };
+            LintIssue {
+                line: broken.link.line,
+                severity: Severity::Error,
+                rule,
+                message: format!("{} target {}", kind, broken.problem),
+            }
+        })
+        .collect()
+}
+
+/// Whether a line is a GFM table delimiter row such as `|---|:--:|`
+pub(crate) fn is_table_separator(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('-')
+        && (trimmed.contains('|') || trimmed.starts_with(':'))
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '-' | ':' | '|' | ' ' | '\t'))
+}
+
+/// Number of cells in a table row, ignoring outer pipes and escaped `\|`
+fn count_table_cells(line: &str) -> usize {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = if inner.ends_with('|') && !inner.ends_with("\\|") {
+        &inner[..inner.len() - 1]
+    } else {
+        inner
+    };
+
+    let mut cells = 1;
+    let mut escaped = false;
+    for c in inner.chars() {
+        match c {
+            '\\' => escaped = !escaped,
+            '|' if !escaped => cells += 1,
+            _ => escaped = false,
+        }
+    }
+    cells
+}
+
+/// Tables whose rows do not all have the header's column count
+fn check_tables(rope: &Rope) -> Vec<LintIssue> {
+    let lines: Vec<String> = rope.lines().map(|l| l.to_string()).collect();
+    let mut issues = Vec::new();
+    let mut fences = FenceTracker::new();
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let line = &lines[idx];
+        if fences.next_line(line.trim_end()) != FenceLine::Text {
+            idx += 1;
+            continue;
+        }
+
+        let starts_table = line.contains('|')
+            && lines
+                .get(idx + 1)
+                .is_some_and(|next| is_table_separator(next));
+        if !starts_table {
+            idx += 1;
+            continue;
+        }
+
+        let columns = count_table_cells(line);
+        let separator_columns = count_table_cells(&lines[idx + 1]);
+        if separator_columns != columns {
+            issues.push(LintIssue {
+                line: idx + 1,
+                severity: Severity::Error,
+                rule: "table-columns",
+                message: format!(
+                    "delimiter row has {} columns but the header has {}",
+                    separator_columns, columns
+                ),
+            });
+        }
+
+        fences.next_line(lines[idx + 1].trim_end());
+        idx += 2;
+        while idx < lines.len() && lines[idx].contains('|') && !lines[idx].trim().is_empty() {
+            fences.next_line(lines[idx].trim_end());
+            let cells = count_table_cells(&lines[idx]);
+            if cells != columns {
+                issues.push(LintIssue {
+                    line: idx,
+                    severity: Severity::Error,
+                    rule: "table-columns",
+                    message: format!("row has {} cells but the header has {}", cells, columns),
+                });
+            }
+            idx += 1;
+        }
+    }
+
+    issues
+}
+
+/// Code fences that are never closed and swallow the rest of the document
+fn check_fences(rope: &Rope) -> Vec<LintIssue> {
+    let mut fences = FenceTracker::new();
+    // Line of the open fence
+    let mut open_line = 0;
+
+    for (idx, line) in rope.lines().enumerate() {
+        let line = line.to_string();
+        if fences.next_line(line.trim_end()) == FenceLine::Open {
+            open_line = idx;
+        }
+    }
+
+    fences
+        .open_fence()
+        .map(|fence| LintIssue {
+            line: open_line,
+            severity: Severity::Error,
+            rule: "unclosed-fence",
+            message: format!(
+                "code fence '{}' is never closed",
+                fence.ch.to_string().repeat(fence.len)
+            ),
+        })
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn lint_text(content: &str) -> (Vec<LintIssue>, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        (lint(&doc, None), dir)
+    }
+
+    fn rules(issues: &[LintIssue]) -> Vec<(usize, &'static str)> {
+        issues.iter().map(|i| (i.line, i.rule)).collect()
+    }
+
+    #[test]
+    fn test_clean_document() {
+        let (issues, _dir) = lint_text(
+            "# Title\n\n## Section\n\n[Top](#title)\n\n| a | b |\n|---|---|\n| 1 | 2 |\n",
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_heading_increment() {
+        let (issues, _dir) = lint_text("# Title\n\n### Too deep\n\n## Fine\n");
+        assert_eq!(rules(&issues), vec![(2, "heading-increment")]);
+        assert_eq!(issues[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_duplicate_anchor() {
+        let (issues, _dir) = lint_text("# Usage\n\n## Usage\n");
+        assert_eq!(rules(&issues), vec![(2, "duplicate-anchor")]);
+    }
+
+    #[test]
+    fn test_broken_links_and_images() {
+        let (issues, dir) = lint_text("# T\n\n[ok](doc.md) [bad](missing.md)\n\n![img](nope.png)\n\n[web](https://example.com)\n");
+        assert_eq!(
+            rules(&issues),
+            vec![(2, "broken-link"), (4, "broken-image")]
+        );
+        assert!(issues.iter().all(|i| i.severity == Severity::Error));
+        drop(dir);
+    }
+
+    #[test]
+    fn test_table_columns() {
+        let (issues, _dir) = lint_text("| a | b |\n|---|---|\n| 1 | 2 | 3 |\n| 4 \\| 5 | 6 |\n");
+        assert_eq!(rules(&issues), vec![(2, "table-columns")]);
+    }
+
+    #[test]
+    fn test_table_in_longer_fence_is_code() {
+        let (issues, _dir) =
+            lint_text("# T\n\n````md\n```\n| a | b |\n|---|---|\n| 1 |\n```\n````\n");
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_unclosed_fence() {
+        let (issues, _dir) = lint_text("# T\n\n````rust\n```\nstill code\n");
+        assert_eq!(rules(&issues), vec![(2, "unclosed-fence")]);
+
+        let (issues, _dir) = lint_text("```\ncode\n```\n");
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_issue_display() {
+        let issue = LintIssue {
+            line: 4,
+            severity: Severity::Error,
+            rule: "broken-link",
+            message: "link target 'x.md' does not exist".to_string(),
+        };
+        assert_eq!(
+            issue.to_string(),
+            "5: error[broken-link]: link target 'x.md' does not exist"
+        );
+    }
+}
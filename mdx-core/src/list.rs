@@ -0,0 +1,310 @@
+//! List item structure
+//!
+//! The renderer draws nested list items with a bullet per level and lines up
+//! wrapped and continued text under the item's content. Both need to know
+//! how deeply a line is nested, which depends on the items above it, so
+//! [`ListTracker`] follows the open items line by line the way
+//! [`crate::fence::FenceTracker`] follows code fences.
+
+/// A list item marker at the start of a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListMarker {
+    /// Leading spaces before the marker
+    pub indent: usize,
+    /// The marker as written: "-", "*", "+", "1." or "1)"
+    pub marker: String,
+    /// The item number of an ordered marker
+    pub number: Option<u64>,
+    /// Column where the item's content starts
+    pub content_indent: usize,
+}
+
+impl ListMarker {
+    /// Parse a line that starts a list item
+    pub fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        let indent = line.len() - trimmed.len();
+
+        let (marker, number) = if trimmed.starts_with(['-', '*', '+']) {
+            (&trimmed[..1], None)
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            // CommonMark caps ordered markers at nine digits
+            if digits == 0 || digits > 9 || !trimmed[digits..].starts_with(['.', ')']) {
+                return None;
+            }
+            (&trimmed[..digits + 1], trimmed[..digits].parse().ok())
+        };
+
+        let rest = &trimmed[marker.len()..];
+        let rest = rest.trim_end_matches(['\n', '\r']);
+        if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+            return None;
+        }
+        Some(Self {
+            indent,
+            marker: marker.to_string(),
+            number,
+            content_indent: indent + marker.len() + 1,
+        })
+    }
+
+    /// Whether this is a numbered item
+    pub fn is_ordered(&self) -> bool {
+        self.number.is_some()
+    }
+}
+
+/// What a line is, given the list items before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListLine {
+    /// Starts an item `depth` levels deep (0 for a top-level item).
+    /// `number` is the item's place in an ordered list: the list's start
+    /// number plus the items before it, whatever the source says.
+    Item {
+        depth: usize,
+        content_indent: usize,
+        number: Option<u64>,
+    },
+    /// Continues the text of the innermost open item
+    Continuation { content_indent: usize },
+    /// An empty line; open items stay open
+    Blank,
+    /// Outside any list
+    Text,
+}
+
+/// An item whose content later lines can continue
+#[derive(Debug, Clone)]
+struct OpenItem {
+    content_indent: usize,
+    /// Last character of the marker; a different one starts a new list
+    delimiter: char,
+    number: Option<u64>,
+}
+
+/// Tracks open list items across successive lines. Feed it prose lines
+/// only; lines of a fenced code block don't open or close items.
+#[derive(Debug, Clone, Default)]
+pub struct ListTracker {
+    /// Open items, outermost first
+    open: Vec<OpenItem>,
+    prev_blank: bool,
+}
+
+impl ListTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify the next line and update the open items
+    pub fn next_line(&mut self, line: &str) -> ListLine {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            self.prev_blank = true;
+            return ListLine::Blank;
+        }
+        let prev_blank = std::mem::replace(&mut self.prev_blank, false);
+        let indent = line.len() - line.trim_start_matches(' ').len();
+
+        if let Some(marker) = ListMarker::parse(line) {
+            // A marker left of an item's content closes that item; the
+            // last one closed at this depth is the new item's sibling
+            let depth = self
+                .open
+                .iter()
+                .take_while(|item| item.content_indent <= indent)
+                .count();
+            let sibling = self.open.drain(depth..).next();
+            let delimiter = marker.marker.chars().last().unwrap_or('-');
+            let number = match (sibling, marker.number) {
+                (Some(prev), Some(_)) if prev.delimiter == delimiter => prev.number.map(|n| n + 1),
+                (_, number) => number,
+            };
+            self.open.push(OpenItem {
+                content_indent: marker.content_indent,
+                delimiter,
+                number,
+            });
+            return ListLine::Item {
+                depth,
+                content_indent: marker.content_indent,
+                number,
+            };
+        }
+
+        if line.trim_start().starts_with('#') {
+            // A heading always ends the list
+            self.open.clear();
+        } else if prev_blank {
+            // After a blank line, text belongs to the items it's indented under
+            self.close_beyond(indent);
+        }
+        // Otherwise it's a lazy continuation of the item's paragraph
+        match self.open.last() {
+            Some(item) => ListLine::Continuation {
+                content_indent: item.content_indent,
+            },
+            None => ListLine::Text,
+        }
+    }
+
+    fn close_beyond(&mut self, indent: usize) {
+        while self
+            .open
+            .last()
+            .is_some_and(|item| item.content_indent > indent)
+        {
+            self.open.pop();
+        }
+    }
+}
+
+/// The marker GitHub shows for item `number` of an ordered list nested
+/// `depth` levels deep: 1. 2. 3. at the top, i. ii. iii. one level down,
+/// and a. b. c. below that
+pub fn ordered_marker(number: u64, depth: usize) -> String {
+    match depth {
+        0 => format!("{}.", number),
+        1 if number > 0 => format!("{}.", lower_roman(number)),
+        _ if number > 0 => format!("{}.", lower_alpha(number)),
+        _ => format!("{}.", number),
+    }
+}
+
+fn lower_roman(mut n: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// a..z, then aa, ab, like a spreadsheet column
+fn lower_alpha(mut n: u64) -> String {
+    let mut out = Vec::new();
+    while n > 0 {
+        n -= 1;
+        out.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(text: &str) -> Vec<ListLine> {
+        let mut tracker = ListTracker::new();
+        text.lines().map(|line| tracker.next_line(line)).collect()
+    }
+
+    fn item(depth: usize, content_indent: usize) -> ListLine {
+        ListLine::Item {
+            depth,
+            content_indent,
+            number: None,
+        }
+    }
+
+    fn numbers(text: &str) -> Vec<Option<u64>> {
+        classify(text)
+            .into_iter()
+            .filter_map(|line| match line {
+                ListLine::Item { number, .. } => Some(number),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_marker() {
+        let marker = ListMarker::parse("  - item").unwrap();
+        assert_eq!(marker.indent, 2);
+        assert_eq!(marker.marker, "-");
+        assert_eq!(marker.content_indent, 4);
+        assert!(!marker.is_ordered());
+
+        let marker = ListMarker::parse("10) item").unwrap();
+        assert_eq!(marker.marker, "10)");
+        assert_eq!(marker.number, Some(10));
+        assert_eq!(marker.content_indent, 4);
+
+        assert!(ListMarker::parse("-").is_some());
+        assert!(ListMarker::parse("**bold**").is_none());
+        assert!(ListMarker::parse("3.14 is pi").is_none());
+        assert!(ListMarker::parse(". not a list").is_none());
+        assert!(ListMarker::parse("1234567890. too long").is_none());
+    }
+
+    #[test]
+    fn test_nested_items_get_depths() {
+        assert_eq!(
+            classify("- a\n  - b\n    * c\n  - d\n- e"),
+            [item(0, 2), item(1, 4), item(2, 6), item(1, 4), item(0, 2)]
+        );
+        // Four-space indents nest one level at a time too
+        assert_eq!(classify("1. a\n    - b")[1], item(1, 6));
+    }
+
+    #[test]
+    fn test_continuation_lines() {
+        use ListLine::*;
+        assert_eq!(
+            classify("- a\n  - b\nlazy\n\n  more of a\n\nafter"),
+            [
+                item(0, 2),
+                item(1, 4),
+                Continuation { content_indent: 4 },
+                Blank,
+                Continuation { content_indent: 2 },
+                Blank,
+                Text,
+            ]
+        );
+        assert_eq!(classify("- a\n# Heading")[1], Text);
+    }
+
+    #[test]
+    fn test_ordered_items_number_sequentially() {
+        assert_eq!(
+            numbers("1. a\n1. b\n   1. c\n   1. d\n\n1. e"),
+            [Some(1), Some(2), Some(1), Some(2), Some(3)]
+        );
+        // The first item sets the start; bullets aren't numbered
+        assert_eq!(numbers("3. a\n3. b\n- c"), [Some(3), Some(4), None]);
+        // A new delimiter or a paragraph in between starts a new list
+        assert_eq!(numbers("1. a\n1) b"), [Some(1), Some(1)]);
+        assert_eq!(numbers("1. a\n\ntext\n\n1. b"), [Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn test_ordered_marker_matches_github() {
+        assert_eq!(ordered_marker(12, 0), "12.");
+        assert_eq!(ordered_marker(4, 1), "iv.");
+        assert_eq!(ordered_marker(1994, 1), "mcmxciv.");
+        assert_eq!(ordered_marker(2, 2), "b.");
+        assert_eq!(ordered_marker(28, 3), "ab.");
+        assert_eq!(ordered_marker(0, 1), "0.");
+    }
+}
@@ -0,0 +1,187 @@
+//! Restructuring a document by its headings
+//!
+//! A section here is a heading with everything under it, subsections
+//! included: it runs to the next heading at the same or a higher level.
+//! The functions return the rewritten text and leave writing it to the
+//! caller.
+
+use crate::doc::Heading;
+use ropey::Rope;
+use std::ops::Range;
+
+/// Index one past the last heading inside the section of `headings[idx]`
+fn section_end(headings: &[Heading], idx: usize) -> usize {
+    let level = headings[idx].level;
+    headings[idx + 1..]
+        .iter()
+        .position(|h| h.level <= level)
+        .map_or(headings.len(), |offset| idx + 1 + offset)
+}
+
+/// Source lines of the section of `headings[idx]`
+pub fn section_lines(rope: &Rope, headings: &[Heading], idx: usize) -> Range<usize> {
+    let end = headings
+        .get(section_end(headings, idx))
+        .map_or(rope.len_lines(), |h| h.line);
+    headings[idx].line..end
+}
+
+/// The section before or after `headings[idx]` at the same level and
+/// under the same parent, if there is one
+pub fn sibling(headings: &[Heading], idx: usize, up: bool) -> Option<usize> {
+    let level = headings.get(idx)?.level;
+    if up {
+        let prev = headings[..idx].iter().rposition(|h| h.level <= level)?;
+        (headings[prev].level == level).then_some(prev)
+    } else {
+        let next = section_end(headings, idx);
+        headings
+            .get(next)
+            .filter(|h| h.level == level)
+            .map(|_| next)
+    }
+}
+
+/// Text with the section of `headings[idx]` swapped with its sibling
+/// above or below, and the index its heading ends up at. `None` when
+/// there is no sibling that way.
+pub fn move_section(
+    rope: &Rope,
+    headings: &[Heading],
+    idx: usize,
+    up: bool,
+) -> Option<(String, usize)> {
+    let other = sibling(headings, idx, up)?;
+    let (first, second) = if up { (other, idx) } else { (idx, other) };
+    let first_lines = section_lines(rope, headings, first);
+    let second_lines = section_lines(rope, headings, second);
+
+    let lines =
+        |range: Range<usize>| -> String { range.map(|line| rope.line(line).to_string()).collect() };
+    let before = lines(0..first_lines.start);
+    let after = lines(second_lines.end..rope.len_lines());
+    let mut upper = lines(second_lines);
+    let mut lower = lines(first_lines);
+
+    // The section that was last may not end in a newline or a blank line
+    // to keep it apart from the one now after it
+    if after.is_empty() {
+        let ending = upper.len() - upper.trim_end().len();
+        upper.truncate(upper.trim_end().len());
+        upper.push_str("\n\n");
+        lower.truncate(lower.trim_end().len());
+        lower.push_str(if ending > 0 { "\n" } else { "" });
+    }
+
+    let moved = if up {
+        other
+    } else {
+        idx + section_end(headings, other) - other
+    };
+    Some((before + &upper + &lower + &after, moved))
+}
+
+/// Text with the heading `headings[idx]` and every heading in its section
+/// moved `delta` levels deeper (negative promotes). Setext headings are
+/// rewritten as ATX ones. `None` if a level would leave 1 to 6.
+pub fn shift_levels(rope: &Rope, headings: &[Heading], idx: usize, delta: i8) -> Option<String> {
+    let section = &headings[idx..section_end(headings, idx)];
+    let new_level = |h: &Heading| {
+        let level = h.level as i8 + delta;
+        (1..=6).contains(&level).then_some(level as usize)
+    };
+    if section.iter().any(|h| new_level(h).is_none()) {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut line = 0;
+    for heading in section {
+        out.extend((line..heading.line).map(|l| rope.line(l).to_string()));
+        let text = rope.line(heading.line).to_string();
+        let ending = &text[text.trim_end_matches(['\n', '\r']).len()..];
+        let trimmed = text.trim_start();
+        let atx = trimmed.len() - trimmed.trim_start_matches('#').len();
+        let hashes = "#".repeat(new_level(heading).unwrap_or(1));
+        if atx > 0 {
+            let indent = &text[..text.len() - trimmed.len()];
+            out.push_str(&format!("{}{}{}", indent, hashes, &trimmed[atx..]));
+            line = heading.line + 1;
+        } else {
+            // Setext: the text line, then its underline, which is dropped
+            out.push_str(&format!("{} {}{}", hashes, heading.text, ending));
+            line = heading.line + 2;
+        }
+    }
+    out.extend((line..rope.len_lines()).map(|l| rope.line(l).to_string()));
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toc::extract_headings;
+
+    fn apply(text: &str, f: impl Fn(&Rope, &[Heading]) -> Option<String>) -> Option<String> {
+        let rope = Rope::from_str(text);
+        f(&rope, &extract_headings(&rope))
+    }
+
+    const DOC: &str = "Intro\n\n# A\n\na\n\n## A1\n\n# B\n\nb\n\n# C\n\nc\n";
+
+    #[test]
+    fn test_move_section_swaps_siblings_with_subsections() {
+        let moved = apply(DOC, |rope, headings| {
+            move_section(rope, headings, 2, true).map(|(text, idx)| {
+                assert_eq!(idx, 0);
+                text
+            })
+        });
+        assert_eq!(
+            moved.unwrap(),
+            "Intro\n\n# B\n\nb\n\n# A\n\na\n\n## A1\n\n# C\n\nc\n"
+        );
+
+        // A subsection has no sibling outside its parent
+        assert!(
+            apply(DOC, |rope, headings| move_section(rope, headings, 1, true)
+                .map(|m| m.0))
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_move_last_section_keeps_blank_line_and_ending() {
+        let text = "# A\n\na\n\n# B\n\nb";
+        let moved = apply(text, |rope, headings| {
+            move_section(rope, headings, 1, true).map(|(text, idx)| {
+                assert_eq!(idx, 0);
+                text
+            })
+        });
+        assert_eq!(moved.unwrap(), "# B\n\nb\n\n# A\n\na");
+
+        let moved = apply(DOC, |rope, headings| {
+            move_section(rope, headings, 2, false).map(|m| m.0)
+        });
+        assert_eq!(
+            moved.unwrap(),
+            "Intro\n\n# A\n\na\n\n## A1\n\n# C\n\nc\n\n# B\n\nb\n"
+        );
+    }
+
+    #[test]
+    fn test_shift_levels_moves_subsections_too() {
+        let demoted = apply(DOC, |rope, headings| shift_levels(rope, headings, 0, 1));
+        assert_eq!(
+            demoted.unwrap(),
+            "Intro\n\n## A\n\na\n\n### A1\n\n# B\n\nb\n\n# C\n\nc\n"
+        );
+        assert!(apply(DOC, |rope, headings| shift_levels(rope, headings, 0, -1)).is_none());
+
+        let setext = apply("Title\n=====\n\ntext\n", |rope, headings| {
+            shift_levels(rope, headings, 0, 1)
+        });
+        assert_eq!(setext.unwrap(), "## Title\n\ntext\n");
+    }
+}
@@ -0,0 +1,964 @@
+//! Terminal-agnostic rendering
+//!
+//! Turns Markdown into lines of text spans tagged with what they are
+//! (heading, inline code, list marker, table border) rather than how they
+//! look. mdx-tui maps these styles onto its theme and ratatui types; other
+//! tools can embed mdx's rendering the same way without pulling in a
+//! terminal library.
+//!
+//! Lines are not wrapped: each [`RenderedLine`] is one source line, and
+//! [`RenderedLine::indent`] says where wrapped rows of it should start.
+
+use crate::blocks::LeafKind;
+use crate::config::{MarkdownFlavor, RenderConfig};
+use crate::doc::Document;
+use crate::fence::{FenceLine, FenceTracker};
+use crate::links::{self, LinkDefinitions};
+use crate::list::{ListLine, ListMarker, ListTracker};
+use ropey::Rope;
+
+/// Bullets for unordered items, cycling with the nesting depth
+pub const LIST_BULLETS: [char; 3] = ['•', '◦', '▪'];
+
+/// What a run of text is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Role {
+    /// Ordinary prose
+    #[default]
+    Text,
+    /// Heading text and its `#` marker, with the level (1-6)
+    Heading(u8),
+    /// `inline code`
+    InlineCode,
+    /// A line inside a fenced code block
+    CodeBlock,
+    /// The opening or closing line of a fenced code block
+    Fence,
+    /// A list bullet or number
+    ListMarker,
+    /// A horizontal rule
+    Rule,
+    /// Table cell borders and the header separator row
+    TableBorder,
+    /// The bar in front of a block quote
+    QuoteMarker,
+    /// The marker in front of a definition list description
+    DefinitionMarker,
+    /// A definition list term
+    DefinitionTerm,
+    /// An HTML comment, with `render.show_comments`
+    Comment,
+}
+
+/// The semantic style of a span: its role plus inline emphasis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SpanStyle {
+    pub role: Role,
+    /// Inside `**strong**`
+    pub strong: bool,
+    /// Inside `*emphasis*`
+    pub emphasis: bool,
+    /// Inside `~~strikethrough~~` (GitHub Flavored Markdown)
+    pub strikethrough: bool,
+    /// A URL or file path written as plain text rather than as a link
+    pub autolink: bool,
+}
+
+impl SpanStyle {
+    pub fn new(role: Role) -> Self {
+        Self {
+            role,
+            ..Self::default()
+        }
+    }
+}
+
+/// A run of text with one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+    /// Destination of the link the text belongs to
+    pub link: Option<String>,
+}
+
+impl Span {
+    fn new(text: impl Into<String>, role: Role) -> Self {
+        Self {
+            text: text.into(),
+            style: SpanStyle::new(role),
+            link: None,
+        }
+    }
+}
+
+/// The kind of block a line belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Blank,
+    Paragraph,
+    Heading(u8),
+    /// Starts a list item nested `depth` levels deep
+    ListItem {
+        depth: usize,
+    },
+    /// Opens or closes a fenced code block; `info` is the opening fence's
+    /// info string ("rust", "python title=x")
+    Fence {
+        info: String,
+    },
+    /// Inside a fenced code block
+    Code {
+        info: String,
+    },
+    Rule,
+    Table,
+    Quote {
+        depth: usize,
+    },
+    /// Raw HTML, reduced to its text content
+    Html,
+    DefinitionTerm,
+    Definition,
+    /// YAML or TOML front matter, shown when `skip_front_matter` is off
+    FrontMatter,
+}
+
+/// One rendered source line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedLine {
+    /// 0-based line in the source
+    pub source_line: usize,
+    pub block: Block,
+    /// Column where wrapped rows of this line continue, so list items and
+    /// their continuation lines hang under the item's text
+    pub indent: usize,
+    pub spans: Vec<Span>,
+}
+
+impl RenderedLine {
+    /// The line's text without styles
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+/// Render a loaded document
+pub fn render_document(doc: &Document, config: &RenderConfig) -> Vec<RenderedLine> {
+    render_rope(&doc.rope, config, &doc.link_definitions)
+}
+
+/// Render Markdown text that isn't backed by a [`Document`]
+pub fn render_text(text: &str, config: &RenderConfig) -> Vec<RenderedLine> {
+    let rope = Rope::from_str(text);
+    let definitions = links::extract_definitions(&rope);
+    render_rope(&rope, config, &definitions)
+}
+
+fn render_rope(rope: &Rope, config: &RenderConfig, links: &LinkDefinitions) -> Vec<RenderedLine> {
+    if config.commonmark {
+        return render_commonmark(rope, config, links);
+    }
+    let front_matter = crate::front_matter::detect_front_matter(rope);
+    let mut fences = FenceTracker::new();
+    let mut lists = ListTracker::new();
+    let mut out: Vec<RenderedLine> = Vec::new();
+    // Table rows as the parser finds them; a `|` alone doesn't make one
+    let tables = match config.markdown.flavor {
+        MarkdownFlavor::Gfm => crate::blocks::extract_tables(rope),
+        MarkdownFlavor::CommonMark => Vec::new(),
+    };
+
+    for (idx, line) in rope.lines().enumerate() {
+        let line = line.to_string();
+        let line = line.trim_end_matches(['\n', '\r']);
+        // The empty "line" after a trailing newline isn't shown
+        if idx > 0 && idx + 1 == rope.len_lines() && line.is_empty() {
+            break;
+        }
+        let mut push = |block, indent, spans| {
+            out.push(RenderedLine {
+                source_line: idx,
+                block,
+                indent,
+                spans,
+            })
+        };
+
+        if front_matter.as_ref().is_some_and(|fm| idx <= fm.end_line) {
+            if !config.skip_front_matter {
+                push(
+                    Block::FrontMatter,
+                    0,
+                    vec![Span::new(line, Role::CodeBlock)],
+                );
+            }
+            continue;
+        }
+
+        // A closing fence belongs to the block it closes
+        let open_info = fences.open_fence().map(|fence| fence.info.clone());
+        match fences.next_line(line) {
+            FenceLine::Open => {
+                let info = fences.open_fence().map(|fence| fence.info.clone());
+                let block = Block::Fence {
+                    info: info.unwrap_or_default(),
+                };
+                push(block, 0, vec![Span::new(line, Role::Fence)]);
+                continue;
+            }
+            FenceLine::Close => {
+                let block = Block::Fence {
+                    info: open_info.unwrap_or_default(),
+                };
+                push(block, 0, vec![Span::new(line, Role::Fence)]);
+                continue;
+            }
+            FenceLine::Code => {
+                let block = Block::Code {
+                    info: open_info.unwrap_or_default(),
+                };
+                push(block, 0, vec![Span::new(line, Role::CodeBlock)]);
+                continue;
+            }
+            FenceLine::Text => {}
+        }
+
+        let is_table = tables.iter().any(|table| table.contains(&idx));
+        let (block, indent, spans) = match lists.next_line(line) {
+            ListLine::Blank => (Block::Blank, 0, Vec::new()),
+            ListLine::Item { depth, number, .. } => {
+                let marker = ListMarker::parse(line).expect("list item has a marker");
+                let mut spans = Vec::new();
+                if marker.indent > 0 {
+                    spans.push(Span::new(" ".repeat(marker.indent), Role::Text));
+                }
+                // A marker wider than the source one ("viii." for "1.")
+                // still gets a space before the content
+                let display = list_marker(&marker, depth, number, config);
+                let width =
+                    (marker.content_indent - marker.indent).max(display.chars().count() + 1);
+                spans.push(Span::new(
+                    format!("{:<width$}", display, width = width),
+                    Role::ListMarker,
+                ));
+                let content = line[marker.content_indent.min(line.len())..].trim_start();
+                spans.extend(inline(content, Role::Text, config, links));
+                (Block::ListItem { depth }, marker.indent + width, spans)
+            }
+            ListLine::Continuation { content_indent } => {
+                let (block, mut spans) = render_line(line.trim_start(), is_table, config, links);
+                spans.insert(0, Span::new(" ".repeat(content_indent), Role::Text));
+                (block, content_indent, spans)
+            }
+            ListLine::Text => {
+                let (block, spans) = render_line(line, is_table, config, links);
+                (block, 0, spans)
+            }
+        };
+
+        // A description makes the paragraph line right above it a term
+        if block == Block::Definition {
+            if let Some(term) = out
+                .last_mut()
+                .filter(|prev| prev.source_line + 1 == idx && prev.block == Block::Paragraph)
+            {
+                term.block = Block::DefinitionTerm;
+                for span in term.spans.iter_mut().filter(|s| s.style.role == Role::Text) {
+                    span.style.role = Role::DefinitionTerm;
+                }
+            }
+        }
+        out.push(RenderedLine {
+            source_line: idx,
+            block,
+            indent,
+            spans,
+        });
+    }
+    out
+}
+
+/// Render from the block structure of a full CommonMark parse
+/// ([`crate::blocks::classify`]) rather than from each line's own syntax
+fn render_commonmark(
+    rope: &Rope,
+    config: &RenderConfig,
+    links: &LinkDefinitions,
+) -> Vec<RenderedLine> {
+    let front_matter = crate::front_matter::detect_front_matter(rope);
+    let in_front_matter = |idx: usize| front_matter.as_ref().is_some_and(|fm| idx <= fm.end_line);
+    let lines: Vec<String> = rope
+        .lines()
+        .map(|line| line.to_string().trim_end_matches(['\n', '\r']).to_string())
+        .collect();
+    // Front matter isn't Markdown; parse the rest as if it were blank
+    let text = lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| if in_front_matter(idx) { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let blocks = crate::blocks::classify(&text, config.markdown.flavor);
+    let bar = if config.use_utf8_graphics {
+        "│ "
+    } else {
+        "> "
+    };
+    let mut fence_info = String::new();
+    let mut in_fence = false;
+    let mut out = Vec::new();
+
+    for (idx, (line, line_block)) in lines.iter().zip(&blocks).enumerate() {
+        if idx > 0 && idx + 1 == lines.len() && line.is_empty() {
+            break;
+        }
+        if in_front_matter(idx) {
+            if !config.skip_front_matter {
+                out.push(RenderedLine {
+                    source_line: idx,
+                    block: Block::FrontMatter,
+                    indent: 0,
+                    spans: vec![Span::new(line, Role::CodeBlock)],
+                });
+            }
+            continue;
+        }
+
+        // Container prefixes: the quote bars and the list item's marker (or
+        // the indent its continuation lines hang at), in the order they nest
+        let mut spans = Vec::new();
+        let quote_depth = line_block.quote_depth;
+        let list_depth = line_block.list_depth.saturating_sub(1);
+        let leading = line.len() - line.trim_start_matches(' ').len();
+        let quote_outside = line_block.list_depth == 0
+            || line[leading..].starts_with('>')
+                && (line_block.item_start || leading < line_block.list_indent);
+        let bars = Span::new(bar.repeat(quote_depth), Role::QuoteMarker);
+        let mut content = line.as_str();
+        let mut indent = 0;
+        if quote_depth > 0 && quote_outside {
+            content = &content[crate::blocks::quote_prefix_len(content, quote_depth)..];
+            spans.push(bars.clone());
+            indent += bar.chars().count() * quote_depth;
+        }
+        let marker = if line_block.item_start {
+            ListMarker::parse(content)
+        } else {
+            None
+        };
+        if let Some(marker) = &marker {
+            if marker.indent > 0 {
+                spans.push(Span::new(" ".repeat(marker.indent), Role::Text));
+            }
+            let display = list_marker(marker, list_depth, line_block.item_number, config);
+            let width = (marker.content_indent - marker.indent).max(display.chars().count() + 1);
+            spans.push(Span::new(
+                format!("{:<width$}", display, width = width),
+                Role::ListMarker,
+            ));
+            content = content[marker.content_indent.min(content.len())..].trim_start();
+            indent += marker.indent + width;
+        } else if line_block.list_depth > 0 {
+            let hang = line_block.list_indent;
+            let spaces = content.len() - content.trim_start_matches(' ').len();
+            content = &content[spaces.min(hang)..];
+            if hang > 0 {
+                spans.push(Span::new(" ".repeat(hang), Role::Text));
+            }
+            indent += hang;
+        }
+        if quote_depth > 0 && !quote_outside {
+            content = &content[crate::blocks::quote_prefix_len(content, quote_depth)..];
+            spans.push(bars);
+            indent += bar.chars().count() * quote_depth;
+        }
+
+        if !matches!(line_block.leaf, LeafKind::Fence | LeafKind::Code) {
+            in_fence = false;
+        }
+        let block = match line_block.leaf {
+            LeafKind::Blank => {
+                spans.retain(|span| span.style.role == Role::QuoteMarker);
+                if quote_depth > 0 {
+                    Block::Quote { depth: quote_depth }
+                } else {
+                    Block::Blank
+                }
+            }
+            LeafKind::Fence => {
+                // A closing fence keeps the info of the fence it closes
+                if !in_fence {
+                    fence_info = content
+                        .trim()
+                        .trim_start_matches(['`', '~'])
+                        .trim()
+                        .to_string();
+                }
+                in_fence = !in_fence;
+                spans.push(Span::new(content, Role::Fence));
+                Block::Fence {
+                    info: fence_info.clone(),
+                }
+            }
+            LeafKind::Code => {
+                spans.push(Span::new(content, Role::CodeBlock));
+                Block::Code {
+                    info: fence_info.clone(),
+                }
+            }
+            LeafKind::IndentedCode => {
+                let spaces = content.len() - content.trim_start_matches(' ').len();
+                spans.push(Span::new(&content[spaces.min(4)..], Role::CodeBlock));
+                Block::Code {
+                    info: String::new(),
+                }
+            }
+            LeafKind::Heading(level) => {
+                let trimmed = content.trim();
+                match heading_level(trimmed) {
+                    Some(atx) => {
+                        let marker_len = atx as usize + 1;
+                        let title = trimmed[marker_len..].trim_end_matches('#').trim_end();
+                        let marker = heading_marker(&trimmed[..marker_len], level, config);
+                        spans.push(Span::new(marker, Role::Heading(level)));
+                        spans.extend(inline(title, Role::Heading(level), config, links));
+                    }
+                    None => {
+                        if config.a11y {
+                            spans.push(Span::new(heading_label(level), Role::Heading(level)));
+                        }
+                        spans.extend(inline(trimmed, Role::Heading(level), config, links))
+                    }
+                }
+                Block::Heading(level)
+            }
+            LeafKind::HeadingUnderline(level) => {
+                // The heading line above already said what this is
+                let underline = if config.a11y { "" } else { content.trim() };
+                spans.push(Span::new(underline, Role::Heading(level)));
+                Block::Heading(level)
+            }
+            LeafKind::Rule => {
+                let trimmed = content.trim();
+                let rule = if config.use_utf8_graphics {
+                    "─".repeat(trimmed.chars().filter(|c| !c.is_whitespace()).count())
+                } else {
+                    trimmed.to_string()
+                };
+                spans.push(Span::new(rule, Role::Rule));
+                Block::Rule
+            }
+            LeafKind::Table => {
+                spans.extend(table_row(content, config, links));
+                Block::Table
+            }
+            LeafKind::Html if config.strip_html => {
+                let text = crate::html::strip_tags(content);
+                spans.extend(inline(text.trim(), Role::Text, config, links));
+                Block::Html
+            }
+            LeafKind::Html | LeafKind::Paragraph => {
+                spans.extend(inline(content.trim_start(), Role::Text, config, links));
+                if quote_depth > 0 {
+                    Block::Quote { depth: quote_depth }
+                } else {
+                    Block::Paragraph
+                }
+            }
+        };
+        let block = match block {
+            Block::Blank | Block::Paragraph | Block::Quote { .. } if marker.is_some() => {
+                Block::ListItem { depth: list_depth }
+            }
+            block => block,
+        };
+        out.push(RenderedLine {
+            source_line: idx,
+            block,
+            indent,
+            spans,
+        });
+    }
+    out
+}
+
+/// Render a line outside a code block that doesn't start a list item;
+/// `table` says whether the parser found it to be a table row
+fn render_line(
+    line: &str,
+    table: bool,
+    config: &RenderConfig,
+    links: &LinkDefinitions,
+) -> (Block, Vec<Span>) {
+    let trimmed = line.trim();
+
+    if config.strip_html && crate::html::is_html_line(line) {
+        let text = crate::html::strip_tags(line);
+        return (Block::Html, inline(text.trim(), Role::Text, config, links));
+    }
+
+    if let Some(level) = heading_level(line) {
+        let marker_len = level as usize + 1;
+        let marker = heading_marker(&line[..marker_len], level, config);
+        let mut spans = vec![Span::new(marker, Role::Heading(level))];
+        spans.extend(inline(
+            &line[marker_len..],
+            Role::Heading(level),
+            config,
+            links,
+        ));
+        return (Block::Heading(level), spans);
+    }
+
+    if trimmed.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| trimmed.chars().all(|ch| ch == c))
+    {
+        let rule = if config.use_utf8_graphics {
+            "─".repeat(trimmed.len())
+        } else {
+            line.to_string()
+        };
+        return (Block::Rule, vec![Span::new(rule, Role::Rule)]);
+    }
+
+    if table {
+        return (Block::Table, table_row(line, config, links));
+    }
+
+    if let Some(rest) = trimmed.strip_prefix('>') {
+        let mut depth = 1;
+        let mut rest = rest.trim_start();
+        while let Some(inner) = rest.strip_prefix('>') {
+            depth += 1;
+            rest = inner.trim_start();
+        }
+        let bar = if config.use_utf8_graphics {
+            "│ "
+        } else {
+            "> "
+        };
+        let mut spans = vec![Span::new(bar.repeat(depth), Role::QuoteMarker)];
+        spans.extend(inline(rest, Role::Text, config, links));
+        return (Block::Quote { depth }, spans);
+    }
+
+    if let Some(content) = definition_content(line) {
+        let marker = if config.use_utf8_graphics {
+            "  ▸ "
+        } else {
+            "  : "
+        };
+        let mut spans = vec![Span::new(marker, Role::DefinitionMarker)];
+        spans.extend(inline(content, Role::Text, config, links));
+        return (Block::Definition, spans);
+    }
+
+    (Block::Paragraph, inline(line, Role::Text, config, links))
+}
+
+/// How screen readers hear a heading's level in `render.a11y` mode
+pub fn heading_label(level: u8) -> String {
+    format!("Heading level {}: ", level)
+}
+
+/// The `## ` marker of a heading, or its spoken label in `render.a11y` mode
+fn heading_marker(marker: &str, level: u8, config: &RenderConfig) -> String {
+    if config.a11y {
+        heading_label(level)
+    } else {
+        marker.to_string()
+    }
+}
+
+/// Level of an ATX heading line (`## Title`)
+fn heading_level(line: &str) -> Option<u8> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&hashes) && line[hashes..].starts_with(' ')).then_some(hashes as u8)
+}
+
+/// Content of a definition list description line (`: definition`),
+/// without its line ending
+pub fn definition_content(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    trimmed
+        .strip_prefix(": ")
+        .or_else(|| trimmed.strip_prefix(":\t"))
+        .map(|rest| rest.trim_end_matches(['\n', '\r']))
+}
+
+/// A table row: cells rendered inline between border spans. The header
+/// separator row becomes a single border span.
+fn table_row(line: &str, config: &RenderConfig, links: &LinkDefinitions) -> Vec<Span> {
+    let is_separator = line
+        .chars()
+        .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+        && line.contains('-');
+    if is_separator {
+        let row = if config.use_utf8_graphics {
+            line.replace('-', "─").replace('|', "│")
+        } else {
+            line.to_string()
+        };
+        return vec![Span::new(row, Role::TableBorder)];
+    }
+
+    let border = if config.use_utf8_graphics { "│" } else { "|" };
+    let mut spans = Vec::new();
+    for (i, cell) in line.split('|').enumerate() {
+        if i > 0 {
+            spans.push(Span::new(border, Role::TableBorder));
+        }
+        // The parser drops the padding around the cell text; keep it
+        let text = cell.trim();
+        let lead = cell.len() - cell.trim_start().len();
+        if !text.is_empty() && lead > 0 {
+            spans.push(Span::new(&cell[..lead], Role::Text));
+        }
+        spans.extend(inline(text, Role::Text, config, links));
+        let trail = cell.len() - cell.trim_end().len();
+        if !text.is_empty() && trail > 0 {
+            spans.push(Span::new(&cell[cell.len() - trail..], Role::Text));
+        }
+    }
+    spans
+}
+
+/// The marker shown for a list item `depth` levels deep: a bullet for its
+/// level (with UTF-8 graphics), or its number. `sequence` is the item's
+/// number counted from the start of its list, shown instead of the source
+/// number when `renumber_lists` is on.
+pub fn list_marker(
+    marker: &ListMarker,
+    depth: usize,
+    sequence: Option<u64>,
+    config: &RenderConfig,
+) -> String {
+    match (marker.number, sequence) {
+        (Some(_), Some(number)) if config.renumber_lists => {
+            crate::list::ordered_marker(number, depth)
+        }
+        // "3)" reads the same as "3." once rendered
+        (Some(number), _) => format!("{}.", number),
+        (None, _) if config.use_utf8_graphics => {
+            LIST_BULLETS[depth % LIST_BULLETS.len()].to_string()
+        }
+        (None, _) => marker.marker.clone(),
+    }
+}
+
+/// Render inline Markdown (strong, emphasis, code, links) in `text`.
+/// Text outside inline code gets `role`. Reference links are resolved
+/// against `links`, since a line is parsed on its own.
+pub fn inline(text: &str, role: Role, config: &RenderConfig, links: &LinkDefinitions) -> Vec<Span> {
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    // HTML entities (`&amp;`, `&nbsp;`) are always decoded; smart
+    // punctuation is opt-in
+    let gfm = config.markdown.flavor == MarkdownFlavor::Gfm;
+    let mut options = Options::empty();
+    if config.smart_punctuation {
+        options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    }
+    if gfm {
+        options.insert(Options::ENABLE_STRIKETHROUGH | Options::ENABLE_WIKILINKS);
+    }
+    let parser = Parser::new_with_broken_link_callback(
+        text,
+        options,
+        Some(|link: pulldown_cmark::BrokenLink| {
+            links
+                .get(&links::normalize_label(&link.reference))
+                .map(|def| (def.dest.clone().into(), def.title.clone().into()))
+        }),
+    );
+
+    let mut spans = Vec::new();
+    let mut style = SpanStyle::new(role);
+    let mut link: Option<String> = None;
+    // Text arrives in pieces split at would-be delimiters; whole runs keep
+    // URLs together
+    for event in pulldown_cmark::TextMergeStream::new(parser) {
+        match event {
+            Event::Start(Tag::Strong) => style.strong = true,
+            Event::End(TagEnd::Strong) => style.strong = false,
+            Event::Start(Tag::Emphasis) => style.emphasis = true,
+            Event::End(TagEnd::Emphasis) => style.emphasis = false,
+            Event::Start(Tag::Strikethrough) => style.strikethrough = true,
+            Event::End(TagEnd::Strikethrough) => style.strikethrough = false,
+            Event::Start(Tag::Link { dest_url, .. }) => link = Some(dest_url.to_string()),
+            Event::End(TagEnd::Link) => link = None,
+            // Bare URLs (in GFM) and file paths outside links become links
+            // of their own
+            Event::Text(content) if link.is_none() => {
+                let mut found = if gfm {
+                    links::find_bare_urls(&content)
+                } else {
+                    Vec::new()
+                };
+                found.extend(links::find_bare_paths(&content));
+                found.sort_by_key(|(range, _)| range.start);
+                let mut last = 0;
+                for (url, dest) in found {
+                    if url.start < last {
+                        continue;
+                    }
+                    if url.start > last {
+                        spans.push(Span {
+                            text: content[last..url.start].to_string(),
+                            style,
+                            link: None,
+                        });
+                    }
+                    spans.push(Span {
+                        text: content[url.clone()].to_string(),
+                        style: SpanStyle {
+                            autolink: true,
+                            ..style
+                        },
+                        link: Some(dest),
+                    });
+                    last = url.end;
+                }
+                if last < content.len() || content.is_empty() {
+                    spans.push(Span {
+                        text: content[last..].to_string(),
+                        style,
+                        link: None,
+                    });
+                }
+            }
+            Event::Text(content) => spans.push(Span {
+                text: content.to_string(),
+                style,
+                link: link.clone(),
+            }),
+            Event::Code(code) => spans.push(Span {
+                text: code.to_string(),
+                style: SpanStyle {
+                    role: Role::InlineCode,
+                    ..style
+                },
+                link: link.clone(),
+            }),
+            // Other inline HTML is dropped, and comments are unless shown
+            Event::InlineHtml(html) if config.show_comments && html.starts_with("<!--") => spans
+                .push(Span {
+                    text: html.to_string(),
+                    style: SpanStyle {
+                        role: Role::Comment,
+                        ..style
+                    },
+                    link: None,
+                }),
+            _ => {}
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(spans: &[Span]) -> Vec<(&str, Role, bool, bool)> {
+        spans
+            .iter()
+            .map(|s| {
+                (
+                    s.text.as_str(),
+                    s.style.role,
+                    s.style.strong,
+                    s.style.emphasis,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inline_styles_and_links() {
+        let spans = inline(
+            "**bold** *it* `code` [site](https://x.org)",
+            Role::Text,
+            &RenderConfig::default(),
+            &LinkDefinitions::new(),
+        );
+        assert_eq!(
+            styled(&spans),
+            [
+                ("bold", Role::Text, true, false),
+                (" ", Role::Text, false, false),
+                ("it", Role::Text, false, true),
+                (" ", Role::Text, false, false),
+                ("code", Role::InlineCode, false, false),
+                (" ", Role::Text, false, false),
+                ("site", Role::Text, false, false),
+            ]
+        );
+        assert_eq!(spans[6].link.as_deref(), Some("https://x.org"));
+    }
+
+    #[test]
+    fn test_gfm_extensions_follow_flavor() {
+        let mut config = RenderConfig::default();
+        let text = "~~old~~ see https://example.com.\n\nshell a | b pipes\n";
+        let lines = render_text(text, &config);
+        let spans = &lines[0].spans;
+        assert!(spans[0].style.strikethrough);
+        assert_eq!(spans[2].text, "https://example.com");
+        assert_eq!(spans[2].link.as_deref(), Some("https://example.com"));
+        assert_eq!(spans[3].text, ".");
+        assert_eq!(lines[2].block, Block::Paragraph);
+
+        config.markdown.flavor = MarkdownFlavor::CommonMark;
+        let lines = render_text(text, &config);
+        assert_eq!(lines[0].text(), "~~old~~ see https://example.com.");
+        assert!(lines[0].spans.iter().all(|span| span.link.is_none()));
+        let lines = render_text("| A | B |\n|---|---|\n", &config);
+        assert_eq!(lines[0].block, Block::Paragraph);
+    }
+
+    #[test]
+    fn test_plain_text_paths_are_autolinks() {
+        let spans = inline(
+            "open ./notes.md, `./code.md` or [a](b.md)",
+            Role::Text,
+            &RenderConfig::default(),
+            &LinkDefinitions::new(),
+        );
+        let autolinks: Vec<(&str, Option<&str>)> = spans
+            .iter()
+            .filter(|span| span.style.autolink)
+            .map(|span| (span.text.as_str(), span.link.as_deref()))
+            .collect();
+        assert_eq!(autolinks, [("./notes.md", Some("./notes.md"))]);
+        assert_eq!(spans.last().unwrap().link.as_deref(), Some("b.md"));
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let text = "---\ntitle: x\n---\n# Title\n\n- a\n  - b\n\n```rust\nfn main() {}\n```\n\n| A | B |\n|---|---|\n\n> quote\n\nTerm\n: meaning\n";
+        let lines = render_text(text, &RenderConfig::default());
+        let blocks: Vec<(usize, &Block)> =
+            lines.iter().map(|l| (l.source_line, &l.block)).collect();
+        let rust = || "rust".to_string();
+        assert_eq!(
+            blocks,
+            [
+                (3, &Block::Heading(1)),
+                (4, &Block::Blank),
+                (5, &Block::ListItem { depth: 0 }),
+                (6, &Block::ListItem { depth: 1 }),
+                (7, &Block::Blank),
+                (8, &Block::Fence { info: rust() }),
+                (9, &Block::Code { info: rust() }),
+                (10, &Block::Fence { info: rust() }),
+                (11, &Block::Blank),
+                (12, &Block::Table),
+                (13, &Block::Table),
+                (14, &Block::Blank),
+                (15, &Block::Quote { depth: 1 }),
+                (16, &Block::Blank),
+                (17, &Block::DefinitionTerm),
+                (18, &Block::Definition),
+            ]
+        );
+
+        assert_eq!(lines[0].spans[0].style.role, Role::Heading(1));
+        assert_eq!(lines[2].text(), "• a");
+        assert_eq!(lines[3].text(), "  ◦ b");
+        assert_eq!(lines[3].indent, 4);
+        assert_eq!(lines[9].text(), "│ A │ B │");
+        assert_eq!(lines[10].text(), "│───│───│");
+        assert_eq!(lines[12].text(), "│ quote");
+        assert_eq!(lines[14].spans[0].style.role, Role::DefinitionTerm);
+    }
+
+    #[test]
+    fn test_a11y_spells_out_heading_levels() {
+        let config = RenderConfig {
+            a11y: true,
+            ..RenderConfig::default()
+        };
+        let text_of = |line: &RenderedLine| -> String {
+            line.spans.iter().map(|span| span.text.as_str()).collect()
+        };
+        let lines = render_text("## Setup\n\nText\n", &config);
+        assert_eq!(text_of(&lines[0]), "Heading level 2: Setup");
+
+        // The CommonMark parse drops a setext underline instead of drawing it
+        let config = RenderConfig {
+            commonmark: true,
+            ..config
+        };
+        let lines = render_text("# One\n\nTwo\n---\n", &config);
+        assert_eq!(text_of(&lines[0]), "Heading level 1: One");
+        assert_eq!(text_of(&lines[2]), "Heading level 2: Two");
+        assert_eq!(text_of(&lines[3]), "");
+    }
+
+    #[test]
+    fn test_continuation_lines_hang_under_the_item() {
+        let config = RenderConfig {
+            renumber_lists: true,
+            use_utf8_graphics: false,
+            ..RenderConfig::default()
+        };
+        let lines = render_text("1. one\n   more\n1. two\n", &config);
+        assert_eq!(lines[0].text(), "1. one");
+        assert_eq!(lines[1].text(), "   more");
+        assert_eq!(lines[1].indent, 3);
+        assert_eq!(lines[2].text(), "2. two");
+    }
+
+    #[test]
+    fn test_commonmark_mode_uses_document_structure() {
+        let config = RenderConfig {
+            commonmark: true,
+            ..RenderConfig::default()
+        };
+        let text = "Title\n=====\n\n    let x = 1;\n\n> quoted\nlazy\n\n- item\n  > > deep\n\n```sh\nls\n```\n";
+        let lines = render_text(text, &config);
+        let blocks: Vec<&Block> = lines.iter().map(|l| &l.block).collect();
+        let code = |info: &str| Block::Code {
+            info: info.to_string(),
+        };
+        let fence = || Block::Fence {
+            info: "sh".to_string(),
+        };
+        assert_eq!(
+            blocks,
+            [
+                &Block::Heading(1),
+                &Block::Heading(1),
+                &Block::Blank,
+                &code(""),
+                &Block::Blank,
+                &Block::Quote { depth: 1 },
+                &Block::Quote { depth: 1 },
+                &Block::Blank,
+                &Block::ListItem { depth: 0 },
+                &Block::Quote { depth: 2 },
+                &Block::Blank,
+                &fence(),
+                &code("sh"),
+                &fence(),
+            ]
+        );
+        assert_eq!(lines[3].text(), "let x = 1;");
+        assert_eq!(lines[6].text(), "│ lazy");
+        assert_eq!(lines[9].text(), "  │ │ deep");
+        assert_eq!(lines[9].indent, 6);
+
+        // Line by line, the indented code is a paragraph and the lazy line
+        // leaves the quote
+        let lines = render_text(text, &RenderConfig::default());
+        assert_eq!(lines[3].block, Block::Paragraph);
+        assert_eq!(lines[6].block, Block::Paragraph);
+    }
+}
@@ -1,5 +1,8 @@
 //! Linewise selection model for Visual Line mode
 
+use crate::fence::{Fence, FenceLine, FenceTracker};
+use crate::Document;
+
 /// Represents a linewise selection in the document
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct LineSelection {
@@ -24,6 +27,243 @@ impl LineSelection {
     }
 }
 
+/// Block that a visual-mode text object (`as`, `ic`, ...) selects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextObject {
+    /// Heading and its body, subsections included
+    Section,
+    /// Fenced code block
+    CodeBlock,
+    /// List item, or the whole list it belongs to
+    ListItem,
+    /// Table
+    Table,
+}
+
+impl TextObject {
+    /// Object for the key typed after `a` or `i`
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            's' => Some(Self::Section),
+            'c' => Some(Self::CodeBlock),
+            'l' => Some(Self::ListItem),
+            't' => Some(Self::Table),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name for messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Section => "section",
+            Self::CodeBlock => "code block",
+            Self::ListItem => "list item",
+            Self::Table => "table",
+        }
+    }
+}
+
+/// Lines (inclusive) of the `object` around `line`, or `None` if the line
+/// is not inside one.
+///
+/// The "around" form (`inner == false`) takes the whole block: a section
+/// with its heading, a code block with its fences, the whole list, or the
+/// whole table. The inner form leaves out the heading, fences, or table
+/// header, and for lists selects just the item under the cursor.
+pub fn text_object(
+    doc: &Document,
+    line: usize,
+    object: TextObject,
+    inner: bool,
+) -> Option<(usize, usize)> {
+    let lines: Vec<String> = doc
+        .rope
+        .lines()
+        .map(|l| l.to_string().trim_end_matches(['\n', '\r']).to_string())
+        .collect();
+    // Ropey yields an empty last line after a trailing newline
+    let last = match lines.last() {
+        Some(l) if l.is_empty() && lines.len() > 1 => lines.len() - 2,
+        _ => lines.len().saturating_sub(1),
+    };
+    if line > last {
+        return None;
+    }
+
+    match object {
+        TextObject::Section => {
+            let heading = doc.headings.iter().rev().find(|h| h.line <= line)?;
+            let end = doc
+                .headings
+                .iter()
+                .find(|h| h.line > heading.line && h.level <= heading.level)
+                .map_or(last, |next| next.line - 1);
+            if inner {
+                trim_blank(&lines, heading.line + 1, end)
+            } else {
+                Some((heading.line, end))
+            }
+        }
+        TextObject::CodeBlock => {
+            let block = crate::exec::fenced_block_at(&doc.rope, line)?;
+            let end = block.end_line.min(last);
+            if !inner {
+                return Some((block.start_line, end));
+            }
+            // Replay the block's fences, as a ```` ``` ```` line in a longer
+            // fence is code rather than a close
+            let mut fences = FenceTracker::new();
+            let closed = lines[block.start_line..=end]
+                .iter()
+                .map(|l| fences.next_line(l))
+                .last()
+                == Some(FenceLine::Close);
+            let inner_end = if closed { end.checked_sub(1)? } else { end };
+            (block.start_line < inner_end).then_some((block.start_line + 1, inner_end))
+        }
+        TextObject::ListItem => {
+            let (start, indent) = list_item_start(&lines, line)?;
+            if inner {
+                return Some((start, list_item_end(&lines, start, indent, last)));
+            }
+            // Climb to the top-level item, then take its earlier siblings
+            let (mut start, mut indent) = (start, indent);
+            while let Some((above, above_indent)) = marker_above(&lines, start, indent) {
+                let sibling = above_indent == indent
+                    && (list_item_end(&lines, above, indent, last) + 1..start)
+                        .all(|l| lines[l].trim().is_empty());
+                if above_indent < indent || sibling {
+                    (start, indent) = (above, above_indent);
+                } else {
+                    break;
+                }
+            }
+            let mut end = list_item_end(&lines, start, indent, last);
+            loop {
+                let next = (end + 1..=last).find(|&l| !lines[l].trim().is_empty());
+                match next.and_then(|l| list_marker_indent(&lines[l]).map(|i| (l, i))) {
+                    Some((next, next_indent)) if next_indent == indent => {
+                        end = list_item_end(&lines, next, indent, last);
+                    }
+                    _ => break,
+                }
+            }
+            Some((start, end))
+        }
+        TextObject::Table => {
+            let is_row = |l: usize| lines[l].contains('|') && !lines[l].trim().is_empty();
+            if !is_row(line) {
+                return None;
+            }
+            let start = (0..line)
+                .rev()
+                .take_while(|&l| is_row(l))
+                .last()
+                .unwrap_or(line);
+            let end = (line + 1..=last)
+                .take_while(|&l| is_row(l))
+                .last()
+                .unwrap_or(line);
+            let separator = (start..=end).find(|&l| crate::lint::is_table_separator(&lines[l]))?;
+            if inner {
+                (separator < end).then_some((separator + 1, end))
+            } else {
+                Some((start, end))
+            }
+        }
+    }
+}
+
+/// `start..=end` without leading and trailing blank lines
+fn trim_blank(lines: &[String], start: usize, end: usize) -> Option<(usize, usize)> {
+    let first = (start..=end).find(|&l| !lines[l].trim().is_empty())?;
+    let last = (first..=end).rev().find(|&l| !lines[l].trim().is_empty())?;
+    Some((first, last))
+}
+
+/// Indent of a list item's marker (`-`, `*`, `+`, `1.`, `1)`), if the line
+/// starts a list item
+fn list_marker_indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = if digits > 0 {
+        trimmed[digits..].strip_prefix(['.', ')'])?
+    } else {
+        trimmed.strip_prefix(['-', '*', '+'])?
+    };
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then_some(indent)
+}
+
+/// Start line and marker indent of the list item containing `line`
+fn list_item_start(lines: &[String], line: usize) -> Option<(usize, usize)> {
+    let text = &lines[line];
+    if let Some(indent) = list_marker_indent(text) {
+        return Some((line, indent));
+    }
+    let blank = text.trim().is_empty();
+    let own_indent = text.len() - text.trim_start().len();
+    let mut after_blank = blank;
+    for l in (0..line).rev() {
+        let text = &lines[l];
+        if text.trim().is_empty() {
+            after_blank = true;
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        match list_marker_indent(text) {
+            // Content must be indented past the marker once a blank line
+            // separates it from the item
+            Some(marker) if blank || !after_blank || own_indent > marker => {
+                return Some((l, marker));
+            }
+            Some(_) => return None,
+            None if text.trim_start().starts_with('#') => return None,
+            None if Fence::parse(text).is_some() => return None,
+            None if after_blank && indent == 0 => return None,
+            None => {}
+        }
+    }
+    None
+}
+
+/// Nearest list marker above `line` indented no deeper than `indent`,
+/// skipping deeper (nested) content. `None` at any other line that is not
+/// indented past `indent`.
+fn marker_above(lines: &[String], line: usize, indent: usize) -> Option<(usize, usize)> {
+    let l = (0..line).rev().find(|&l| {
+        let text = &lines[l];
+        !text.trim().is_empty() && text.len() - text.trim_start().len() <= indent
+    })?;
+    list_marker_indent(&lines[l]).map(|marker| (l, marker))
+}
+
+/// Last line of the list item starting at `start`, nested items included
+fn list_item_end(lines: &[String], start: usize, indent: usize, last: usize) -> usize {
+    let mut end = start;
+    let mut after_blank = false;
+    for (l, text) in lines.iter().enumerate().take(last + 1).skip(start + 1) {
+        if text.trim().is_empty() {
+            after_blank = true;
+            continue;
+        }
+        let line_indent = text.len() - text.trim_start().len();
+        let nested = line_indent > indent;
+        let marker = list_marker_indent(text);
+        // Lazy continuation: unindented paragraph text right below the item
+        let lazy = !after_blank
+            && marker.is_none()
+            && !text.trim_start().starts_with('#')
+            && Fence::parse(text).is_none();
+        if !(nested || lazy) {
+            break;
+        }
+        end = l;
+        after_blank = false;
+    }
+    end
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +291,94 @@ mod tests {
         let sel = LineSelection::new(7);
         assert_eq!(sel.range(), (7, 7));
     }
+
+    fn doc(text: &str) -> (Document, tempfile::NamedTempFile) {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        let (doc, _warnings) = Document::load(file.path()).unwrap();
+        (doc, file)
+    }
+
+    #[test]
+    fn test_section_text_objects() {
+        let (doc, _file) = doc("# A\n\nintro\n\n## B\n\nbody\n\n### C\n\ndeep\n\n## D\n\nend\n");
+        assert_eq!(
+            text_object(&doc, 6, TextObject::Section, false),
+            Some((4, 11))
+        );
+        assert_eq!(
+            text_object(&doc, 6, TextObject::Section, true),
+            Some((6, 10))
+        );
+        assert_eq!(
+            text_object(&doc, 14, TextObject::Section, false),
+            Some((12, 14))
+        );
+        assert_eq!(
+            text_object(&doc, 0, TextObject::Section, false),
+            Some((0, 14))
+        );
+        let (doc, _file) = self::doc("no heading\n");
+        assert_eq!(text_object(&doc, 0, TextObject::Section, false), None);
+    }
+
+    #[test]
+    fn test_code_block_text_objects() {
+        let (doc, _file) = doc("text\n\n```rust\nfn a() {}\nfn b() {}\n```\n");
+        assert_eq!(
+            text_object(&doc, 3, TextObject::CodeBlock, false),
+            Some((2, 5))
+        );
+        assert_eq!(
+            text_object(&doc, 2, TextObject::CodeBlock, true),
+            Some((3, 4))
+        );
+        assert_eq!(text_object(&doc, 0, TextObject::CodeBlock, false), None);
+    }
+
+    #[test]
+    fn test_code_block_shorter_fence_is_code() {
+        // Unclosed, so the last line is code rather than a closing fence
+        let (doc, _file) = doc("````md\ncode\n```");
+        assert_eq!(
+            text_object(&doc, 1, TextObject::CodeBlock, true),
+            Some((1, 2))
+        );
+    }
+
+    #[test]
+    fn test_list_text_objects() {
+        let text =
+            "Intro\n\n- one\n  more one\n- two\n  - nested\n  - nested two\n- three\n\nAfter\n";
+        let (doc, _file) = doc(text);
+        // Inner: the item under the cursor, nested items included
+        assert_eq!(
+            text_object(&doc, 3, TextObject::ListItem, true),
+            Some((2, 3))
+        );
+        assert_eq!(
+            text_object(&doc, 4, TextObject::ListItem, true),
+            Some((4, 6))
+        );
+        assert_eq!(
+            text_object(&doc, 6, TextObject::ListItem, true),
+            Some((6, 6))
+        );
+        // Around: the whole list, even from a nested item
+        assert_eq!(
+            text_object(&doc, 6, TextObject::ListItem, false),
+            Some((2, 7))
+        );
+        assert_eq!(text_object(&doc, 9, TextObject::ListItem, false), None);
+        assert_eq!(text_object(&doc, 0, TextObject::ListItem, true), None);
+    }
+
+    #[test]
+    fn test_table_text_objects() {
+        let (doc, _file) = doc("Text\n\n| a | b |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |\n\nAfter\n");
+        assert_eq!(text_object(&doc, 4, TextObject::Table, false), Some((2, 5)));
+        assert_eq!(text_object(&doc, 2, TextObject::Table, true), Some((4, 5)));
+        assert_eq!(text_object(&doc, 7, TextObject::Table, false), None);
+    }
 }
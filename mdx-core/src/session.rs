@@ -0,0 +1,125 @@
+//! Reading positions kept between runs
+//!
+//! The viewer writes the files it has open, with the cursor, scroll
+//! position, and jump list of each, to a small JSON file in the state
+//! directory: every `session.autosave_secs` while something changed, and
+//! on exit. The file is replaced atomically, so a crash mid-write leaves
+//! the previous state; a file that still fails to parse is moved aside
+//! rather than stopping mdx from starting.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Files whose positions are remembered, most recently saved first
+pub const MAX_FILES: usize = 200;
+
+/// Jump list entries remembered per file
+pub const MAX_JUMPS: usize = 20;
+
+/// Where a file was left
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileState {
+    pub path: PathBuf,
+    pub cursor_line: usize,
+    /// Source line at the top of the view
+    pub scroll_line: usize,
+    /// Cursor lines of the jump list, oldest first
+    pub jumps: Vec<usize>,
+}
+
+/// Everything the session file holds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// Files open when the state was last saved, in buffer order
+    pub open: Vec<PathBuf>,
+    /// Positions of open and earlier files, most recent first
+    pub files: Vec<FileState>,
+}
+
+impl SessionState {
+    /// Read the session file. A missing file is an empty session; one that
+    /// can't be parsed is renamed to `<name>.bad`, so the next save starts
+    /// over, and reported as an error.
+    pub fn load(path: &Path) -> Result<Self> {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Ok(Self::default());
+        };
+        serde_json::from_str(&text).or_else(|e| {
+            let bad = path.with_extension("json.bad");
+            let _ = std::fs::rename(path, &bad);
+            anyhow::bail!(
+                "Corrupt session file {} ({}), moved to {}",
+                path.display(),
+                e,
+                bad.display()
+            )
+        })
+    }
+
+    /// Write the session file through a temporary file renamed over it
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize session")?;
+        std::fs::write(&tmp, json).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    /// Where `path` was left, if it is remembered
+    pub fn file(&self, path: &Path) -> Option<&FileState> {
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    /// Remember a file's position, moving it to the front
+    pub fn remember(&mut self, mut file: FileState) {
+        self.files.retain(|known| known.path != file.path);
+        let skip = file.jumps.len().saturating_sub(MAX_JUMPS);
+        file.jumps.drain(..skip);
+        self.files.insert(0, file);
+        self.files.truncate(MAX_FILES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip_and_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("session.json");
+        assert_eq!(SessionState::load(&path).unwrap(), SessionState::default());
+
+        let mut state = SessionState::default();
+        for (name, line) in [("a.md", 3), ("b.md", 7), ("a.md", 9)] {
+            state.remember(FileState {
+                path: PathBuf::from(name),
+                cursor_line: line,
+                scroll_line: 0,
+                jumps: (0..30).collect(),
+            });
+        }
+        state.open = vec![PathBuf::from("b.md")];
+        state.save(&path).unwrap();
+
+        let loaded = SessionState::load(&path).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.files.len(), 2);
+        let a = loaded.file(Path::new("a.md")).unwrap();
+        assert_eq!(a.cursor_line, 9);
+        assert_eq!(a.jumps.len(), MAX_JUMPS);
+        assert_eq!(a.jumps[0], 10);
+
+        // A torn write is set aside instead of failing the load
+        std::fs::write(&path, "{\"open\": [\"b.m").unwrap();
+        assert!(SessionState::load(&path).is_err());
+        assert_eq!(SessionState::load(&path).unwrap(), SessionState::default());
+        assert!(path.with_extension("json.bad").exists());
+    }
+}
@@ -0,0 +1,133 @@
+//! Splitting a document into presentation slides
+
+use crate::doc::Heading;
+use ropey::Rope;
+use std::ops::Range;
+
+/// Whether a line is a thematic break (`---`, `***`, or `___`)
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&marker| compact.chars().all(|c| c == marker))
+}
+
+/// Split a document into slides, returned as line ranges.
+///
+/// A new slide starts at every top-level heading (the shallowest level used
+/// in the document) and after every thematic break. A `---` directly under
+/// text is a setext underline, not a break, so breaks must follow a blank
+/// line. Break lines, front matter, and slides with nothing but blank lines
+/// are left out.
+pub fn split_slides(rope: &Rope, headings: &[Heading]) -> Vec<Range<usize>> {
+    let line_count = rope.len_lines();
+    let body_start = crate::detect_front_matter(rope).map_or(0, |fm| fm.end_line + 1);
+    let top_level = headings
+        .iter()
+        .filter(|h| h.line >= body_start)
+        .map(|h| h.level)
+        .min();
+    let is_slide_heading = |idx: usize| {
+        headings
+            .iter()
+            .any(|h| h.line == idx && Some(h.level) == top_level)
+    };
+
+    let mut slides = Vec::new();
+    let mut start = body_start;
+    let mut fence: Option<(char, usize)> = None;
+    let mut prev_blank = true;
+
+    for idx in body_start..line_count {
+        let line = rope.line(idx).to_string();
+        let trimmed = line.trim();
+
+        let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        if let Some(fc) = fence_char {
+            let run = trimmed.chars().take_while(|&c| c == fc).count();
+            if run >= 3 {
+                fence = match fence {
+                    None => Some((fc, run)),
+                    Some((open, len)) if open == fc && run >= len => None,
+                    other => other,
+                };
+            }
+        }
+
+        if fence.is_none() && prev_blank && is_thematic_break(trimmed) {
+            slides.push(start..idx);
+            start = idx + 1;
+        } else if fence.is_none() && idx > start && is_slide_heading(idx) {
+            slides.push(start..idx);
+            start = idx;
+        }
+        prev_blank = trimmed.is_empty();
+    }
+    slides.push(start..line_count);
+
+    slides.retain(|slide| {
+        slide
+            .clone()
+            .any(|idx| !rope.line(idx).to_string().trim().is_empty())
+    });
+    slides
+}
+
+/// Index of the slide containing a line, or the nearest slide before it
+pub fn slide_at_line(slides: &[Range<usize>], line: usize) -> usize {
+    slides
+        .iter()
+        .rposition(|slide| slide.start <= line)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toc::extract_headings;
+
+    fn slides_for(text: &str) -> Vec<Range<usize>> {
+        let rope = Rope::from(text);
+        split_slides(&rope, &extract_headings(&rope))
+    }
+
+    #[test]
+    fn test_split_on_top_level_headings() {
+        let slides = slides_for("# One\n\ntext\n\n## Sub\n\n# Two\n\nmore\n");
+        assert_eq!(slides, vec![0..6, 6..10]);
+    }
+
+    #[test]
+    fn test_split_on_thematic_breaks() {
+        let slides = slides_for("Title slide\n\n---\n\nSecond\n\n* * *\n\nThird\n");
+        assert_eq!(slides, vec![0..2, 3..6, 7..10]);
+    }
+
+    #[test]
+    fn test_break_before_heading_makes_no_empty_slide() {
+        let slides = slides_for("# One\n\n---\n\n# Two\n");
+        assert_eq!(slides, vec![0..2, 4..6]);
+    }
+
+    #[test]
+    fn test_setext_underline_and_code_are_not_breaks() {
+        let slides = slides_for("Heading\n---\n\ntext\n\n```\n---\n# not a heading\n```\n");
+        assert_eq!(slides, vec![0..10]);
+    }
+
+    #[test]
+    fn test_front_matter_is_skipped() {
+        let slides = slides_for("+++\ntitle = \"x\"\n+++\n# Only\n");
+        assert_eq!(slides, vec![3..5]);
+    }
+
+    #[test]
+    fn test_slide_at_line() {
+        let slides = vec![0..2, 3..6, 7..10];
+        assert_eq!(slide_at_line(&slides, 0), 0);
+        assert_eq!(slide_at_line(&slides, 2), 0);
+        assert_eq!(slide_at_line(&slides, 5), 1);
+        assert_eq!(slide_at_line(&slides, 99), 2);
+    }
+}
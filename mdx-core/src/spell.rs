@@ -0,0 +1,678 @@
+//! Spell checking for prose
+//!
+//! Reads hunspell `.dic`/`.aff` dictionaries (the format shipped by most
+//! systems and editors). Prefix and suffix rules are expanded up front into
+//! a word set; compounding and morphology are not supported.
+
+use anyhow::{Context, Result};
+use ropey::Rope;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A word in the document, located by line and byte offsets within it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordRef {
+    pub line: usize,
+    /// Byte offset of the first character within the line
+    pub start: usize,
+    /// Byte offset one past the last character within the line
+    pub end: usize,
+    pub word: String,
+}
+
+/// A set of known words
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// Characters tried when generating suggestions (hunspell `TRY`)
+    try_chars: Vec<char>,
+}
+
+/// One character position of an affix condition
+#[derive(Debug, Clone)]
+enum CondChar {
+    Any,
+    Literal(char),
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CondChar {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CondChar::Any => true,
+            CondChar::Literal(l) => *l == c,
+            CondChar::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CondChar>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    is_prefix: bool,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// How flags are encoded in `.dic` entries (hunspell `FLAG`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagMode {
+    Char,
+    Long,
+    Num,
+}
+
+fn parse_flags(flags: &str, mode: FlagMode) -> Vec<String> {
+    match mode {
+        FlagMode::Char => flags.chars().map(String::from).collect(),
+        FlagMode::Long => flags
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| pair.iter().collect())
+            .collect(),
+        FlagMode::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+    }
+}
+
+/// Parse an affix condition such as `[^aeiou]y` or `.`
+fn parse_condition(condition: &str) -> Vec<CondChar> {
+    let mut out = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => out.push(CondChar::Any),
+            '[' => {
+                let mut set = Vec::new();
+                let mut negated = false;
+                for (i, c) in chars.by_ref().enumerate() {
+                    match c {
+                        '^' if i == 0 => negated = true,
+                        ']' => break,
+                        c => set.push(c),
+                    }
+                }
+                out.push(CondChar::Set {
+                    chars: set,
+                    negated,
+                });
+            }
+            c => out.push(CondChar::Literal(c)),
+        }
+    }
+    out
+}
+
+impl AffixRule {
+    /// Apply the rule to `word`, returning the derived form when the
+    /// condition holds
+    fn apply(&self, word: &str, is_prefix: bool) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if self.condition.len() > chars.len() {
+            return None;
+        }
+        let window = if is_prefix {
+            &chars[..self.condition.len()]
+        } else {
+            &chars[chars.len() - self.condition.len()..]
+        };
+        if !self
+            .condition
+            .iter()
+            .zip(window)
+            .all(|(cond, &c)| cond.matches(c))
+        {
+            return None;
+        }
+
+        if is_prefix {
+            let rest = word.strip_prefix(self.strip.as_str())?;
+            Some(format!("{}{}", self.add, rest))
+        } else {
+            let rest = word.strip_suffix(self.strip.as_str())?;
+            Some(format!("{}{}", rest, self.add))
+        }
+    }
+}
+
+impl Dictionary {
+    /// Build a dictionary from the contents of a hunspell `.dic` file and
+    /// its optional `.aff` file
+    pub fn from_hunspell(dic: &str, aff: Option<&str>) -> Self {
+        let mut flag_mode = FlagMode::Char;
+        let mut try_chars = Vec::new();
+        let mut classes: HashMap<String, AffixClass> = HashMap::new();
+
+        for line in aff.unwrap_or_default().lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => flag_mode = FlagMode::Long,
+                ["FLAG", "num", ..] => flag_mode = FlagMode::Num,
+                ["TRY", chars, ..] => try_chars = chars.chars().collect(),
+                [kind @ ("PFX" | "SFX"), flag, cross, count]
+                    if count.parse::<usize>().is_ok() && matches!(*cross, "Y" | "N") =>
+                {
+                    classes.insert(
+                        flag.to_string(),
+                        AffixClass {
+                            is_prefix: *kind == "PFX",
+                            cross_product: *cross == "Y",
+                            rules: Vec::new(),
+                        },
+                    );
+                }
+                ["PFX" | "SFX", flag, strip, add, rest @ ..] => {
+                    if let Some(class) = classes.get_mut(*flag) {
+                        let zero_to_empty = |s: &str| {
+                            if s == "0" {
+                                String::new()
+                            } else {
+                                s.to_string()
+                            }
+                        };
+                        // Continuation flags after '/' are not supported
+                        let add = add.split('/').next().unwrap_or_default();
+                        class.rules.push(AffixRule {
+                            strip: zero_to_empty(strip),
+                            add: zero_to_empty(add),
+                            condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut words = HashSet::new();
+        for (idx, line) in dic.lines().enumerate() {
+            let entry = line.split_whitespace().next().unwrap_or_default();
+            // The first line holds the approximate word count
+            if entry.is_empty() || (idx == 0 && entry.parse::<usize>().is_ok()) {
+                continue;
+            }
+            let (stem, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            words.insert(stem.to_string());
+
+            let entry_classes: Vec<&AffixClass> = parse_flags(flags, flag_mode)
+                .iter()
+                .filter_map(|flag| classes.get(flag))
+                .collect();
+
+            let mut suffixed = Vec::new();
+            for class in entry_classes.iter().filter(|c| !c.is_prefix) {
+                for rule in &class.rules {
+                    if let Some(form) = rule.apply(stem, false) {
+                        if class.cross_product {
+                            suffixed.push(form.clone());
+                        }
+                        words.insert(form);
+                    }
+                }
+            }
+            for class in entry_classes.iter().filter(|c| c.is_prefix) {
+                for rule in &class.rules {
+                    if let Some(form) = rule.apply(stem, true) {
+                        words.insert(form);
+                    }
+                    if class.cross_product {
+                        for form in suffixed.iter().filter_map(|s| rule.apply(s, true)) {
+                            words.insert(form);
+                        }
+                    }
+                }
+            }
+        }
+
+        Self { words, try_chars }
+    }
+
+    /// Load `path` (a `.dic` file) and the `.aff` file beside it, if any
+    pub fn load(path: &Path) -> Result<Self> {
+        let dic = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read dictionary: {}", path.display()))?;
+        let aff = std::fs::read_to_string(path.with_extension("aff")).ok();
+        Ok(Self::from_hunspell(&dic, aff.as_deref()))
+    }
+
+    /// Number of known word forms
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Add a word, e.g. from the user's personal word list
+    pub fn add_word(&mut self, word: &str) {
+        self.words.insert(normalize_apostrophes(word));
+    }
+
+    /// Whether a word is spelled correctly. Capitalized and all-caps
+    /// forms of known lowercase words are accepted, as are possessives.
+    pub fn check(&self, word: &str) -> bool {
+        let word = normalize_apostrophes(word);
+        let word = word.trim_matches('\'');
+        if word.is_empty() || self.words.contains(word) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        if lower != word && self.words.contains(&lower) {
+            return true;
+        }
+        // Title case of a known proper noun spelled in all caps
+        if word.chars().all(|c| !c.is_lowercase()) && self.words.contains(&title_case(&lower)) {
+            return true;
+        }
+        match word.strip_suffix("'s").or_else(|| word.strip_suffix("'S")) {
+            Some(base) if !base.is_empty() => self.check(base),
+            _ => false,
+        }
+    }
+
+    /// Known words one edit away from `word` (insert, delete, replace,
+    /// swap), plus two-word splits, in a stable order
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let word = normalize_apostrophes(word);
+        let lower = word.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        let alphabet: Vec<char> = if self.try_chars.is_empty() {
+            ('a'..='z').collect()
+        } else {
+            self.try_chars
+                .iter()
+                .copied()
+                .filter(|c| !c.is_uppercase())
+                .collect()
+        };
+
+        let mut candidates: Vec<String> = Vec::new();
+        // Swaps first: the most common typing error
+        for i in 0..chars.len().saturating_sub(1) {
+            let mut c = chars.clone();
+            c.swap(i, i + 1);
+            candidates.push(c.into_iter().collect());
+        }
+        for i in 0..chars.len() {
+            for &a in &alphabet {
+                if a != chars[i] {
+                    let mut c = chars.clone();
+                    c[i] = a;
+                    candidates.push(c.into_iter().collect());
+                }
+            }
+        }
+        for i in 0..chars.len() {
+            let mut c = chars.clone();
+            c.remove(i);
+            candidates.push(c.into_iter().collect());
+        }
+        for i in 0..=chars.len() {
+            for &a in &alphabet {
+                let mut c = chars.clone();
+                c.insert(i, a);
+                candidates.push(c.into_iter().collect());
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut suggestions: Vec<String> = candidates
+            .into_iter()
+            .filter(|c| !c.is_empty() && self.words.contains(c))
+            .filter(|c| seen.insert(c.clone()))
+            .collect();
+
+        for split in 1..chars.len() {
+            let (left, right): (String, String) = (
+                chars[..split].iter().collect(),
+                chars[split..].iter().collect(),
+            );
+            if self.words.contains(&left) && self.words.contains(&right) {
+                let joined = format!("{} {}", left, right);
+                if seen.insert(joined.clone()) {
+                    suggestions.push(joined);
+                }
+            }
+        }
+
+        // Keep the original capitalization
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        suggestions
+            .into_iter()
+            .take(limit)
+            .map(|s| if capitalized { title_case(&s) } else { s })
+            .collect()
+    }
+}
+
+fn normalize_apostrophes(word: &str) -> String {
+    word.replace('\u{2019}', "'")
+}
+
+fn title_case(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Common install locations of hunspell dictionaries for `lang`
+/// (e.g. `en_US`)
+pub fn default_dictionary_paths(lang: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = vec![
+        PathBuf::from("/usr/share/hunspell"),
+        PathBuf::from("/usr/share/myspell"),
+        PathBuf::from("/usr/share/myspell/dicts"),
+        PathBuf::from("/usr/local/share/hunspell"),
+        PathBuf::from("/opt/homebrew/share/hunspell"),
+        PathBuf::from("/Library/Spelling"),
+    ];
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(&home).join("Library/Spelling"));
+        dirs.push(PathBuf::from(&home).join(".local/share/hunspell"));
+    }
+    dirs.into_iter()
+        .map(|dir| dir.join(format!("{}.dic", lang)))
+        .collect()
+}
+
+/// Resolve the dictionary to load: `configured` when set, otherwise the
+/// first existing system dictionary for `lang`
+pub fn find_dictionary(configured: &str, lang: &str) -> Option<PathBuf> {
+    if !configured.is_empty() {
+        return Some(PathBuf::from(configured));
+    }
+    default_dictionary_paths(lang)
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+/// Byte mask of a line: `true` for bytes that are not prose (inline code,
+/// link destinations, HTML tags, URLs, emails, and file paths)
+fn non_prose_mask(line: &str) -> Vec<bool> {
+    let bytes = line.as_bytes();
+    let mut mask = vec![false; bytes.len()];
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                // Inline code: a run of backticks closed by a run of the same length
+                let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[i..i + run];
+                let close = line[i + run..]
+                    .find(fence)
+                    .map_or(bytes.len(), |p| i + run + p + run);
+                mask[i..close].fill(true);
+                i = close;
+            }
+            b']' if bytes.get(i + 1) == Some(&b'(') => {
+                let close = line[i..].find(')').map_or(bytes.len(), |p| i + p + 1);
+                mask[i..close].fill(true);
+                i = close;
+            }
+            b']' if bytes.get(i + 1) == Some(&b':') => {
+                // Reference definition: everything after `]:` is a URL/title
+                mask[i..].fill(true);
+                i = bytes.len();
+            }
+            b'<' if bytes
+                .get(i + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || matches!(b, b'/' | b'!')) =>
+            {
+                let close = line[i..].find('>').map_or(bytes.len(), |p| i + p + 1);
+                mask[i..close].fill(true);
+                i = close;
+            }
+            _ => i += 1,
+        }
+    }
+
+    // Whitespace-separated tokens that look like URLs, emails, or paths.
+    // Already-masked spans also end a token, so `[text](url)` keeps `text`.
+    let structural = mask.clone();
+    let mut token_start = None;
+    for (idx, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        if c.is_whitespace() || structural.get(idx).copied().unwrap_or(false) {
+            if let Some(start) = token_start.take() {
+                let token = &line[start..idx];
+                let looks_technical = token.contains("://")
+                    || token.starts_with("www.")
+                    || token.contains('@')
+                    || token.contains('/')
+                    || token.contains('\\')
+                    || token
+                        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+                        .split('.')
+                        .filter(|part| !part.is_empty())
+                        .count()
+                        > 1;
+                if looks_technical {
+                    mask[start..idx].fill(true);
+                }
+            }
+        } else if token_start.is_none() {
+            token_start = Some(idx);
+        }
+    }
+
+    mask
+}
+
+/// Words of a single line of prose
+fn line_words(line: &str, line_idx: usize) -> Vec<WordRef> {
+    let mask = non_prose_mask(line);
+    let mut words = Vec::new();
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+
+    let is_word_char = |c: char| c.is_alphabetic();
+    let is_apostrophe = |c: char| c == '\'' || c == '\u{2019}';
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        if !is_word_char(c) || mask[start] {
+            i += 1;
+            continue;
+        }
+        let mut j = i;
+        while j < chars.len() {
+            let (_, c) = chars[j];
+            let continues_with_apostrophe = is_apostrophe(c)
+                && chars
+                    .get(j + 1)
+                    .is_some_and(|&(_, next)| is_word_char(next));
+            if is_word_char(c) || continues_with_apostrophe {
+                j += 1;
+            } else {
+                break;
+            }
+        }
+        let end = chars.get(j).map_or(line.len(), |&(b, _)| b);
+        let word = &line[start..end];
+
+        // Skip identifiers and numbers glued to letters (3rd, v2, foo_bar)
+        let touches_code =
+            |pos: Option<&(usize, char)>| pos.is_some_and(|&(_, c)| c.is_ascii_digit() || c == '_');
+        let glued =
+            touches_code(i.checked_sub(1).and_then(|k| chars.get(k))) || touches_code(chars.get(j));
+        // Acronyms and camelCase are not prose
+        let inner_upper = word.chars().skip(1).any(char::is_uppercase);
+        let masked = mask[start..end].iter().any(|&m| m);
+
+        if !glued && !inner_upper && !masked && word.chars().count() > 1 {
+            words.push(WordRef {
+                line: line_idx,
+                start,
+                end,
+                word: word.to_string(),
+            });
+        }
+        i = j.max(i + 1);
+    }
+
+    words
+}
+
+/// Extract prose words, skipping front matter, fenced code, and HTML
+/// comments
+pub fn prose_words(rope: &Rope) -> Vec<WordRef> {
+    let front_matter_end = crate::detect_front_matter(rope).map(|fm| fm.end_line);
+    let mut words = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut in_comment = false;
+
+    for (idx, line) in rope.lines().enumerate() {
+        if front_matter_end.is_some_and(|end| idx <= end) {
+            continue;
+        }
+        let line = line.to_string();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+
+        // Fenced code blocks
+        let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        if let Some(fc) = fence_char {
+            let run = trimmed.chars().take_while(|&c| c == fc).count();
+            if run >= 3 {
+                fence = match fence {
+                    None => Some((fc, run)),
+                    Some((open, len)) if open == fc && run >= len => None,
+                    other => other,
+                };
+                continue;
+            }
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        // Multi-line HTML comments
+        if in_comment {
+            if line.contains("-->") {
+                in_comment = false;
+            }
+            continue;
+        }
+        if trimmed.starts_with("<!--") && !trimmed.contains("-->") {
+            in_comment = true;
+            continue;
+        }
+
+        words.extend(line_words(line, idx));
+    }
+
+    words
+}
+
+/// Words in the document that the dictionary does not know
+pub fn find_misspellings(rope: &Rope, dict: &Dictionary) -> Vec<WordRef> {
+    prose_words(rope)
+        .into_iter()
+        .filter(|w| !dict.check(&w.word))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\nTRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'\n\nPFX A Y 1\nPFX A   0     re         .\n\nSFX D Y 4\nSFX D   0     d          e\nSFX D   y     ied        [^aeiou]y\nSFX D   0     ed         [^ey]\nSFX D   0     ed         [aeiou]y\n\nSFX S Y 2\nSFX S   y     ies        [^aeiou]y\nSFX S   0     s          [^sxzhy]\n";
+    const DIC: &str = "6\nwalk/DS\ncarry/DS\ncreate/AD\nthe\nhello\nParis\n";
+
+    fn dict() -> Dictionary {
+        Dictionary::from_hunspell(DIC, Some(AFF))
+    }
+
+    #[test]
+    fn test_affix_expansion() {
+        let d = dict();
+        for word in [
+            "walk",
+            "walked",
+            "walks",
+            "carried",
+            "carries",
+            "created",
+            "recreate",
+            "recreated",
+        ] {
+            assert!(d.check(word), "{} should be known", word);
+        }
+        assert!(!d.check("walkied"));
+        assert!(!d.check("rewalk"));
+    }
+
+    #[test]
+    fn test_check_case_and_possessive() {
+        let d = dict();
+        assert!(d.check("Hello"));
+        assert!(d.check("HELLO"));
+        assert!(d.check("PARIS"));
+        assert!(!d.check("paris"));
+        assert!(d.check("Paris's"));
+        assert!(d.check("Paris\u{2019}s"));
+        assert!(!d.check("helo"));
+    }
+
+    #[test]
+    fn test_suggest() {
+        let d = dict();
+        assert_eq!(d.suggest("hte", 3), vec!["the"]);
+        assert_eq!(d.suggest("Helo", 3), vec!["Hello"]);
+        assert!(d.suggest("walkd", 5).contains(&"walked".to_string()));
+        assert!(d.suggest("thewalk", 5).contains(&"the walk".to_string()));
+    }
+
+    #[test]
+    fn test_long_flags() {
+        let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+        let d = Dictionary::from_hunspell("1\ncat/Aa\n", Some(aff));
+        assert!(d.check("cats"));
+    }
+
+    #[test]
+    fn test_prose_words_skip_code_and_urls() {
+        let rope = Rope::from(
+            "---\ntitle: Ignored\n---\n# Heading wrd\n\nSee `inline codde` and [link txt](http://exmple.com).\nVisit https://exmple.org or mail a@b.com, config.toml, 3rd, snake_case, HTTP, camelCase.\n\n```\nfenced cdoe\n```\n<!--\nhidden cmment\n-->\nDon't stop\n",
+        );
+        let words: Vec<String> = prose_words(&rope).into_iter().map(|w| w.word).collect();
+        assert_eq!(
+            words,
+            vec![
+                "Heading", "wrd", "See", "and", "link", "txt", "Visit", "or", "mail", "Don't",
+                "stop"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_word_offsets() {
+        let rope = Rope::from("héllo wörld\n");
+        let words = prose_words(&rope);
+        assert_eq!(words[1].word, "wörld");
+        assert_eq!(&"héllo wörld"[words[1].start..words[1].end], "wörld");
+    }
+
+    #[test]
+    fn test_find_misspellings() {
+        let mut d = dict();
+        d.add_word("mdx");
+        let rope = Rope::from("Hello the wrld of mdx\n");
+        let found: Vec<String> = find_misspellings(&rope, &d)
+            .into_iter()
+            .map(|w| w.word)
+            .collect();
+        assert_eq!(found, vec!["wrld", "of"]);
+    }
+}
@@ -0,0 +1,217 @@
+//! Word counts and reading time
+
+use crate::doc::Heading;
+use ropey::Rope;
+
+/// Average adult silent reading speed used for reading time estimates
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Size of one heading's section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionStats {
+    /// Index into the document's headings
+    pub heading: usize,
+    /// Words from the heading line up to the next heading of any level
+    pub words: usize,
+    /// Words including all subsections
+    pub total_words: usize,
+    /// Source lines from the heading line up to the next heading
+    pub lines: usize,
+    /// Source lines including all subsections
+    pub total_lines: usize,
+    /// Fenced code blocks opened before the next heading
+    pub code_blocks: usize,
+    /// Code blocks including all subsections
+    pub total_code_blocks: usize,
+}
+
+/// Statistics over the prose of a document. Fenced code and front matter
+/// are not counted as words or characters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DocumentStats {
+    pub words: usize,
+    /// Characters of prose lines, excluding line breaks
+    pub characters: usize,
+    pub code_blocks: usize,
+    /// Words before the first heading
+    pub preamble_words: usize,
+    pub sections: Vec<SectionStats>,
+}
+
+impl DocumentStats {
+    /// Estimated reading time in whole minutes, rounded up
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+
+    /// Stats of the section owned by a heading index
+    pub fn section(&self, heading: usize) -> Option<&SectionStats> {
+        self.sections.iter().find(|s| s.heading == heading)
+    }
+}
+
+/// Count words: whitespace-separated tokens with at least one letter or
+/// digit, ignoring Markdown markers such as `#`, `>`, `-`, and `|`
+fn count_words(line: &str) -> usize {
+    line.split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Compute statistics for a document
+pub fn compute(rope: &Rope, headings: &[Heading]) -> DocumentStats {
+    let front_matter_end = crate::detect_front_matter(rope).map(|fm| fm.end_line);
+    let mut stats = DocumentStats {
+        sections: headings
+            .iter()
+            .enumerate()
+            .map(|(heading, _)| SectionStats {
+                heading,
+                words: 0,
+                total_words: 0,
+                lines: 0,
+                total_lines: 0,
+                code_blocks: 0,
+                total_code_blocks: 0,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut fence: Option<(char, usize)> = None;
+    let mut next_heading = 0;
+    let mut current_section: Option<usize> = None;
+
+    for (idx, line) in rope.lines().enumerate() {
+        while headings.get(next_heading).is_some_and(|h| h.line <= idx) {
+            current_section = Some(next_heading);
+            next_heading += 1;
+        }
+        if front_matter_end.is_some_and(|end| idx <= end) {
+            // A `---` closing line can look like a setext underline
+            current_section = None;
+            continue;
+        }
+
+        let line = line.to_string();
+        let line = line.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+
+        let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        if let Some(fc) = fence_char {
+            let run = trimmed.chars().take_while(|&c| c == fc).count();
+            if run >= 3 {
+                fence = match fence {
+                    None => {
+                        stats.code_blocks += 1;
+                        if let Some(section) = current_section {
+                            stats.sections[section].code_blocks += 1;
+                        }
+                        Some((fc, run))
+                    }
+                    Some((open, len)) if open == fc && run >= len => None,
+                    other => other,
+                };
+                continue;
+            }
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        let words = count_words(line);
+        stats.words += words;
+        stats.characters += line.chars().count();
+        match current_section {
+            Some(section) => stats.sections[section].words += words,
+            None => stats.preamble_words += words,
+        }
+    }
+
+    // The empty line after a final newline isn't one of the document's
+    let line_count = match rope.len_chars() {
+        0 => 0,
+        len if rope.char(len - 1) == '\n' => rope.len_lines() - 1,
+        _ => rope.len_lines(),
+    };
+    let line_of = |i: usize| headings.get(i).map_or(line_count, |h| h.line);
+    for i in 0..headings.len() {
+        stats.sections[i].lines = line_of(i + 1).saturating_sub(line_of(i));
+    }
+
+    // A section's total runs until the next heading at the same or a
+    // higher level
+    for i in 0..headings.len() {
+        let level = headings[i].level;
+        let end = headings[i + 1..]
+            .iter()
+            .position(|h| h.level <= level)
+            .map_or(headings.len(), |p| i + 1 + p);
+        let subsections = &stats.sections[i..end];
+        let total_words = subsections.iter().map(|s| s.words).sum();
+        let total_code_blocks = subsections.iter().map(|s| s.code_blocks).sum();
+        let section = &mut stats.sections[i];
+        section.total_words = total_words;
+        section.total_code_blocks = total_code_blocks;
+        section.total_lines = line_of(end).saturating_sub(line_of(i));
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toc::extract_headings;
+
+    fn stats_for(text: &str) -> DocumentStats {
+        let rope = Rope::from(text);
+        let headings = extract_headings(&rope);
+        compute(&rope, &headings)
+    }
+
+    #[test]
+    fn test_counts_prose_only() {
+        let stats = stats_for(
+            "+++\ntitle = \"Not counted\"\n+++\nIntro words here.\n\n# One\n\nA - b\n\n```rust\nlet x = 1;\n```\n\n## Two\n\n- three four five\n\n# Six\n\nseven\n",
+        );
+        assert_eq!(stats.preamble_words, 3);
+        // Heading text counts toward its own section
+        let words: Vec<(usize, usize)> = stats
+            .sections
+            .iter()
+            .map(|s| (s.words, s.total_words))
+            .collect();
+        assert_eq!(words, vec![(3, 7), (4, 4), (2, 2)]);
+        assert_eq!(stats.words, 3 + 3 + 4 + 2);
+        assert_eq!(stats.code_blocks, 1);
+    }
+
+    #[test]
+    fn test_section_lines_and_code_blocks() {
+        let stats = stats_for("# One\n\n```\na\n```\n\n## Two\n\n~~~\nb\n~~~\n\n# Three\nlast\n");
+        let sizes: Vec<(usize, usize, usize, usize)> = stats
+            .sections
+            .iter()
+            .map(|s| (s.lines, s.total_lines, s.code_blocks, s.total_code_blocks))
+            .collect();
+        assert_eq!(sizes, vec![(6, 12, 1, 2), (6, 6, 1, 1), (2, 2, 0, 0)]);
+    }
+
+    #[test]
+    fn test_characters_exclude_line_breaks() {
+        let stats = stats_for("héllo\r\nworld\n");
+        assert_eq!(stats.characters, 10);
+        assert_eq!(stats.words, 2);
+    }
+
+    #[test]
+    fn test_reading_minutes() {
+        let mut stats = DocumentStats::default();
+        assert_eq!(stats.reading_minutes(), 0);
+        stats.words = 1;
+        assert_eq!(stats.reading_minutes(), 1);
+        stats.words = WORDS_PER_MINUTE * 3 + 1;
+        assert_eq!(stats.reading_minutes(), 4);
+    }
+}
@@ -1,29 +1,25 @@
 //! Table of Contents extraction from Markdown
 
 use crate::doc::Heading;
+use crate::fence::{FenceLine, FenceTracker};
 use ropey::Rope;
+use std::collections::HashMap;
 
 /// Extract headings from markdown text using regex scanning
 pub fn extract_headings(rope: &Rope) -> Vec<Heading> {
     let mut headings = Vec::new();
     let line_count = rope.len_lines();
-    let mut in_code_block = false;
+    let mut fences = FenceTracker::new();
+    let mut slugger = Slugger::new();
 
     let mut line_idx = 0;
     while line_idx < line_count {
         let line = rope.line(line_idx);
         let line_str: String = line.chunks().collect();
         let trimmed = line_str.trim_end();
-        let trimmed_start = trimmed.trim_start();
 
-        // Track fenced code blocks (``` or ~~~), ignoring headings inside.
-        if trimmed_start.starts_with("```") || trimmed_start.starts_with("~~~") {
-            in_code_block = !in_code_block;
-            line_idx += 1;
-            continue;
-        }
-
-        if in_code_block {
+        // Headings inside fenced code blocks are just code
+        if fences.next_line(trimmed) != FenceLine::Text {
             line_idx += 1;
             continue;
         }
@@ -31,7 +27,7 @@ pub fn extract_headings(rope: &Rope) -> Vec<Heading> {
         // Check for ATX headings: ^#{1,6}\s+
         if let Some(level) = parse_atx_heading(trimmed) {
             let text = trimmed[level..].trim().to_string();
-            let anchor = make_anchor(&text);
+            let anchor = slugger.slug(&text);
 
             headings.push(Heading {
                 level: level as u8,
@@ -48,7 +44,7 @@ pub fn extract_headings(rope: &Rope) -> Vec<Heading> {
 
             if let Some(level) = parse_setext_underline(next_trimmed) {
                 let text = trimmed.to_string();
-                let anchor = make_anchor(&text);
+                let anchor = slugger.slug(&text);
 
                 headings.push(Heading {
                     level,
@@ -107,28 +103,121 @@ fn parse_setext_underline(line: &str) -> Option<u8> {
     }
 }
 
-/// Create an anchor from heading text (simplified version)
-fn make_anchor(text: &str) -> String {
-    text.to_lowercase()
+/// Section numbers for headings ("1", "1.2", "2.3.4"), one per heading.
+/// The shallowest level in the document is the top level; a skipped
+/// level shows as 0 ("1.0.1").
+pub fn section_numbers(headings: &[Heading]) -> Vec<String> {
+    let top = headings.iter().map(|h| h.level).min().unwrap_or(1);
+    let mut counters = [0usize; 6];
+    headings
+        .iter()
+        .map(|heading| {
+            let depth = usize::from(heading.level - top);
+            counters[depth] += 1;
+            counters[depth + 1..].fill(0);
+            counters[..=depth]
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect()
+}
+
+/// GitHub's anchor for heading text, before duplicate suffixes: the
+/// rendered text (link text, code span contents, no emphasis markers)
+/// lowercased, with punctuation and symbols dropped and each space turned
+/// into `-`. Letters and digits in any script are kept.
+pub fn slugify(text: &str) -> String {
+    rendered_text(text)
+        .to_lowercase()
         .chars()
-        .map(|c| {
-            if c.is_alphanumeric() {
-                c
-            } else if c.is_whitespace() {
-                '-'
-            } else {
-                '_'
-            }
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() || is_combining_mark(c) => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Text of a heading as rendered, with inline Markdown resolved
+fn rendered_text(text: &str) -> String {
+    use pulldown_cmark::{Event, Options, Parser};
+
+    // Parse as a heading so leading `1.` or `-` aren't taken for a list
+    // and a closing `##` sequence is dropped, as on GitHub
+    let source = format!("# {}", text);
+    Parser::new_ext(&source, Options::ENABLE_STRIKETHROUGH)
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.into_string()),
+            _ => None,
         })
-        .collect::<String>()
-        .trim_matches('-')
-        .to_string()
+        .collect()
+}
+
+/// Combining marks, which GitHub keeps with the letters they modify
+fn is_combining_mark(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+/// Assigns unique anchors the way GitHub does: the first heading with a
+/// slug gets it as is, later ones get `-1`, `-2`, ... appended
+#[derive(Debug, Default)]
+pub struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Anchor for the next heading with this text
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify(text);
+        let mut slug = base.clone();
+        while self.seen.contains_key(&slug) {
+            let count = self.seen.entry(base.clone()).or_default();
+            *count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+/// The heading a `#fragment` points at. Fragments may be percent-encoded
+/// (`#caf%C3%A9`); a fragment that doesn't match exactly is tried in
+/// lowercase, since hand-written links often keep the heading's case.
+pub fn find_anchor<'a>(headings: &'a [Heading], fragment: &str) -> Option<&'a Heading> {
+    let fragment = crate::links::percent_decode(fragment);
+    headings.iter().find(|h| h.anchor == fragment).or_else(|| {
+        let lower = fragment.to_lowercase();
+        headings.iter().find(|h| h.anchor == lower)
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_section_numbers() {
+        let rope = Rope::from("## Intro\n### Scope\n### Terms\n## Design\n#### Detail\n## End\n");
+        let headings = extract_headings(&rope);
+        assert_eq!(
+            section_numbers(&headings),
+            vec!["1", "1.1", "1.2", "2", "2.0.1", "3"]
+        );
+        assert!(section_numbers(&[]).is_empty());
+    }
+
     #[test]
     fn test_extract_headings_empty() {
         let rope = Rope::from("");
@@ -200,9 +289,65 @@ mod tests {
 
     #[test]
     fn test_anchor_generation() {
-        assert_eq!(make_anchor("Hello World"), "hello-world");
-        assert_eq!(make_anchor("Test & Demo"), "test-_-demo");
-        assert_eq!(make_anchor("Multiple   Spaces"), "multiple---spaces");
+        assert_eq!(slugify("Hello World"), "hello-world");
+        assert_eq!(slugify("Test & Demo"), "test--demo");
+        assert_eq!(slugify("Multiple   Spaces"), "multiple---spaces");
+        assert_eq!(slugify("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(
+            slugify("snake_case and kebab-case"),
+            "snake_case-and-kebab-case"
+        );
+        assert_eq!(slugify("1. Introduction"), "1-introduction");
+        assert_eq!(slugify("Heading ##"), "heading");
+    }
+
+    #[test]
+    fn test_anchor_uses_rendered_text() {
+        assert_eq!(
+            slugify("Using `__init__` in **Python**"),
+            "using-__init__-in-python"
+        );
+        assert_eq!(
+            slugify("See [the docs](https://example.com/x)"),
+            "see-the-docs"
+        );
+        assert_eq!(slugify("_Emphasis_ and ~~gone~~"), "emphasis-and-gone");
+    }
+
+    #[test]
+    fn test_anchor_unicode() {
+        assert_eq!(slugify("Café Übersicht"), "café-übersicht");
+        assert_eq!(slugify("日本語の見出し"), "日本語の見出し");
+        assert_eq!(slugify("Rocket 🚀 launch"), "rocket--launch");
+        assert_eq!(slugify("Cafe\u{301}"), "cafe\u{301}");
+    }
+
+    #[test]
+    fn test_duplicate_anchors_get_suffixes() {
+        let mut slugger = Slugger::new();
+        assert_eq!(slugger.slug("Usage"), "usage");
+        assert_eq!(slugger.slug("Usage"), "usage-1");
+        assert_eq!(slugger.slug("Usage 1"), "usage-1-1");
+        assert_eq!(slugger.slug("usage"), "usage-2");
+
+        let rope = Rope::from("# Notes\n## Notes\n## Notes\n");
+        let anchors: Vec<_> = extract_headings(&rope)
+            .into_iter()
+            .map(|h| h.anchor)
+            .collect();
+        assert_eq!(anchors, ["notes", "notes-1", "notes-2"]);
+    }
+
+    #[test]
+    fn test_find_anchor() {
+        let rope = Rope::from("# Café\n## Getting Started\n");
+        let headings = extract_headings(&rope);
+        assert_eq!(find_anchor(&headings, "caf%C3%A9").map(|h| h.line), Some(0));
+        assert_eq!(
+            find_anchor(&headings, "Getting-Started").map(|h| h.line),
+            Some(1)
+        );
+        assert!(find_anchor(&headings, "missing").is_none());
     }
 
     #[test]
@@ -243,4 +388,28 @@ mod tests {
         assert_eq!(headings.len(), 1);
         assert_eq!(headings[0].text, "Heading");
     }
+
+    #[test]
+    fn test_headings_ignored_in_nested_and_tilde_fences() {
+        let text = "\
+````markdown
+```sh
+# shell comment
+```
+# Still inside the outer fence
+````
+# Real
+~~~
+```
+# tilde block
+~~~
+## After
+";
+        let rope = Rope::from(text);
+        let headings = extract_headings(&rope);
+
+        let texts: Vec<_> = headings.iter().map(|h| h.text.as_str()).collect();
+        assert_eq!(texts, ["Real", "After"]);
+        assert_eq!(headings[0].line, 6);
+    }
 }
@@ -0,0 +1,299 @@
+//! Transclusion: showing part of another file in place
+//!
+//! A line holding only `![[other.md#section]]` (Obsidian's embed) or
+//! `<!-- include: other.md#section -->` (as mkdocs include plugins write
+//! it) stands for the named section of the other file, or all of it
+//! without a `#section`. Included files can include others, up to
+//! [`MAX_DEPTH`] deep; a file including itself, directly or not, is
+//! reported rather than followed. As with images, only files inside the
+//! including document's directory are read.
+
+use crate::fence::{FenceLine, FenceTracker};
+use ropey::Rope;
+use std::path::{Path, PathBuf};
+
+/// How many includes deep nested includes are followed
+pub const MAX_DEPTH: usize = 4;
+
+/// Lines shown for one include before the rest is cut off
+pub const MAX_LINES: usize = 200;
+
+/// An include directive: a path relative to the including file and an
+/// optional heading anchor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    pub path: String,
+    pub section: Option<String>,
+}
+
+/// A line shown for an include: text from the included file, or why some
+/// of it couldn't be shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncludedLine {
+    Text(String),
+    Error(String),
+}
+
+/// The include directive a line consists of, if it is one
+pub fn parse_include(line: &str) -> Option<Include> {
+    let line = line.trim();
+    let target = line
+        .strip_prefix("![[")
+        .and_then(|rest| rest.strip_suffix("]]"))
+        // `![[note|alias]]` shows the alias as a title; only the target matters
+        .map(|inner| inner.split('|').next().unwrap_or(inner))
+        .or_else(|| {
+            line.strip_prefix("<!--")?
+                .strip_suffix("-->")?
+                .trim()
+                .strip_prefix("include:")
+        })?
+        .trim();
+    if target.is_empty() {
+        return None;
+    }
+
+    let (path, section) = match target.split_once('#') {
+        Some((path, section)) => (path, Some(section.trim().to_string())),
+        None => (target, None),
+    };
+    // Obsidian leaves off the extension of notes
+    let path = if Path::new(path).extension().is_none() && !path.is_empty() {
+        format!("{}.md", path.trim())
+    } else {
+        path.trim().to_string()
+    };
+    Some(Include { path, section })
+}
+
+/// The lines `include` shows from a file including it from `base_dir`,
+/// with nested includes expanded. Files over `max_bytes` (0: no limit)
+/// and files outside `base_dir` aren't read.
+pub fn expand(include: &Include, base_dir: &Path, max_bytes: u64) -> Vec<IncludedLine> {
+    let mut lines = Vec::new();
+    let Ok(root) = base_dir.canonicalize() else {
+        lines.push(IncludedLine::Error(format!(
+            "{}: document directory not found",
+            include.path
+        )));
+        return lines;
+    };
+    let context = Context {
+        root: &root,
+        max_bytes,
+    };
+    expand_into(include, base_dir, &context, &mut Vec::new(), &mut lines);
+    if lines.len() > MAX_LINES {
+        let more = lines.len() - MAX_LINES;
+        lines.truncate(MAX_LINES);
+        lines.push(IncludedLine::Error(format!(
+            "{} more lines not shown",
+            more
+        )));
+    }
+    lines
+}
+
+/// What every include of one expansion is checked against
+struct Context<'a> {
+    /// Canonical directory of the document the expansion started in
+    root: &'a Path,
+    max_bytes: u64,
+}
+
+fn expand_into(
+    include: &Include,
+    base_dir: &Path,
+    context: &Context,
+    stack: &mut Vec<(PathBuf, Option<String>)>,
+    out: &mut Vec<IncludedLine>,
+) {
+    match read_section(include, base_dir, context, stack) {
+        Ok((path, text)) => {
+            let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            stack.push((path, include.section.clone()));
+            let mut fences = FenceTracker::new();
+            for line in text {
+                let nested = (fences.next_line(&line) == FenceLine::Text)
+                    .then(|| parse_include(&line))
+                    .flatten();
+                match nested {
+                    Some(nested) => expand_into(&nested, &dir, context, stack, out),
+                    None => out.push(IncludedLine::Text(line)),
+                }
+            }
+            stack.pop();
+        }
+        Err(problem) => out.push(IncludedLine::Error(format!(
+            "{}: {}",
+            include.path, problem
+        ))),
+    }
+}
+
+/// The file's canonical path and the lines of the included section
+fn read_section(
+    include: &Include,
+    base_dir: &Path,
+    context: &Context,
+    stack: &[(PathBuf, Option<String>)],
+) -> Result<(PathBuf, Vec<String>), String> {
+    let relative = Path::new(&include.path);
+    if relative.is_absolute() {
+        return Err("only relative paths are included".to_string());
+    }
+    // Reject path traversal before canonicalization, as for images
+    if relative
+        .components()
+        .any(|c| c == std::path::Component::ParentDir)
+    {
+        return Err("paths with '..' aren't included".to_string());
+    }
+    if stack.len() >= MAX_DEPTH {
+        return Err(format!("includes nest more than {} deep", MAX_DEPTH));
+    }
+    let path = base_dir
+        .join(&include.path)
+        .canonicalize()
+        .map_err(|_| "file not found".to_string())?;
+    // A symlink can still point outside
+    if !path.starts_with(context.root) {
+        return Err("only files in the document's directory are included".to_string());
+    }
+    if stack
+        .iter()
+        .any(|(p, s)| *p == path && *s == include.section)
+    {
+        return Err("includes itself".to_string());
+    }
+    let size = std::fs::metadata(&path).map_err(|e| e.to_string())?.len();
+    if context.max_bytes > 0 && size > context.max_bytes {
+        return Err(format!("file is larger than {} bytes", context.max_bytes));
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let rope = Rope::from_str(&text);
+
+    let lines = match &include.section {
+        Some(section) => {
+            let headings = crate::toc::extract_headings(&rope);
+            let heading = crate::toc::find_anchor(&headings, section)
+                .ok_or_else(|| format!("no section '#{}'", section))?;
+            let idx = headings
+                .iter()
+                .position(|h| h.line == heading.line)
+                .unwrap_or(0);
+            crate::outline::section_lines(&rope, &headings, idx)
+        }
+        None => {
+            let start =
+                crate::front_matter::detect_front_matter(&rope).map_or(0, |fm| fm.end_line + 1);
+            start..rope.len_lines()
+        }
+    };
+    let mut lines: Vec<String> = lines
+        .map(|line| {
+            rope.line(line)
+                .to_string()
+                .trim_end_matches(['\n', '\r'])
+                .to_string()
+        })
+        .collect();
+    // The blank lines that end a section only separate it from the next
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    Ok((path, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn include(path: &str, section: Option<&str>) -> Include {
+        Include {
+            path: path.to_string(),
+            section: section.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_include() {
+        assert_eq!(
+            parse_include("![[notes#Setup]]"),
+            Some(include("notes.md", Some("Setup")))
+        );
+        assert_eq!(
+            parse_include("  ![[a.md|Alias]] "),
+            Some(include("a.md", None))
+        );
+        assert_eq!(
+            parse_include("<!-- include: docs/part.md#usage -->"),
+            Some(include("docs/part.md", Some("usage")))
+        );
+        assert_eq!(parse_include("see ![[a.md]]"), None);
+        assert_eq!(parse_include("<!-- a comment -->"), None);
+        assert_eq!(parse_include("![[]]"), None);
+    }
+
+    #[test]
+    fn test_expand_section_nested_and_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.md"),
+            "# A\n\nintro\n\n## Part\n\npart text\n![[b#Inner]]\n\n## Next\n\nnext\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.md"),
+            "# Inner\n\ninner\n<!-- include: a.md#part -->\n",
+        )
+        .unwrap();
+
+        let lines = expand(&include("a.md", Some("part")), dir.path(), 0);
+        assert_eq!(lines[0], IncludedLine::Text("## Part".to_string()));
+        assert!(lines.contains(&IncludedLine::Text("inner".to_string())));
+        assert!(!lines.contains(&IncludedLine::Text("next".to_string())));
+
+        // b.md includes the section of a.md that included it
+        let error = lines.iter().find_map(|line| match line {
+            IncludedLine::Error(e) => Some(e.as_str()),
+            _ => None,
+        });
+        assert_eq!(error, Some("a.md: includes itself"));
+
+        let missing = expand(&include("a.md", Some("nope")), dir.path(), 0);
+        assert_eq!(
+            missing,
+            vec![IncludedLine::Error("a.md: no section '#nope'".to_string())]
+        );
+        let absolute = expand(&include("/etc/hosts", None), dir.path(), 0);
+        assert!(matches!(&absolute[0], IncludedLine::Error(e) if e.contains("relative")));
+    }
+
+    #[test]
+    fn test_expand_stays_in_document_directory() {
+        let outer = tempfile::tempdir().unwrap();
+        std::fs::write(outer.path().join("secret.md"), "secret\n").unwrap();
+        let docs = outer.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(docs.join("nested.md"), "![[../secret.md]]\n").unwrap();
+
+        let is_error = |lines: &[IncludedLine]| {
+            lines.len() == 1 && matches!(&lines[0], IncludedLine::Error(_))
+        };
+        assert!(is_error(&expand(&include("../secret.md", None), &docs, 0)));
+        let dotted = include("sub/../../secret.md", None);
+        assert!(is_error(&expand(&dotted, &docs, 0)));
+        // Nested includes are checked too
+        let nested = expand(&include("nested.md", None), &docs, 0);
+        assert!(is_error(&nested), "{:?}", nested);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outer.path().join("secret.md"), docs.join("link.md"))
+                .unwrap();
+            let linked = expand(&include("link.md", None), &docs, 0);
+            assert!(matches!(&linked[0], IncludedLine::Error(e) if e.contains("directory")));
+        }
+    }
+}
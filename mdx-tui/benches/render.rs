@@ -0,0 +1,141 @@
+//! Render path benchmarks: line styling, tables, wrapping, and whole frames
+//!
+//! Run with `cargo bench -p mdx-tui`; criterion compares each run against
+//! the previous one, so a renderer change that costs time shows up here.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mdx_core::{Config, Document};
+use mdx_tui::line_layout::LineLayoutCache;
+use mdx_tui::ui::bench;
+use mdx_tui::App;
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use std::io::Write as _;
+use tempfile::NamedTempFile;
+
+/// Lines covering the main branches of the line styler
+const SAMPLE_LINES: &[(&str, &str)] = &[
+    ("heading", "## Installation and *first* steps"),
+    (
+        "inline",
+        "Some **bold**, _italic_, `code`, ~~struck~~ and a [link](https://example.com) in one line.",
+    ),
+    ("list", "  - [x] A task item with `inline code` and **emphasis**"),
+    ("quote", "> A quoted line with a [reference link][docs] inside"),
+    ("rule", "---"),
+    ("html", "<details><summary>More</summary> hidden text</details>"),
+];
+
+/// A mixed document: headings, prose, lists, code, quotes, and tables
+fn mixed_document(sections: usize) -> String {
+    let mut doc = String::from("# Benchmark Document\n\n");
+    for i in 0..sections {
+        doc.push_str(&format!("## Section {}\n\n", i));
+        doc.push_str(
+            "Paragraph text with **bold**, _italic_, `code` and a [link](https://example.com). \
+             It runs long enough to wrap at common terminal widths, which exercises the layout \
+             code as well as the styler.\n\n",
+        );
+        doc.push_str("- First item\n- Second item with `code`\n  - Nested item\n\n");
+        doc.push_str("```rust\nfn main() {\n    println!(\"hello\");\n}\n```\n\n");
+        doc.push_str("> A blockquote line\n\n");
+        doc.push_str(&table(4, 3));
+        doc.push('\n');
+    }
+    doc.push_str("[docs]: https://example.com/docs\n");
+    doc
+}
+
+/// A pipe table with `rows` body rows and `columns` columns
+fn table(rows: usize, columns: usize) -> String {
+    let header: Vec<String> = (0..columns).map(|c| format!("Column {}", c)).collect();
+    let mut out = format!("| {} |\n", header.join(" | "));
+    out.push_str(&format!("|{}\n", "---|".repeat(columns)));
+    for r in 0..rows {
+        let cells: Vec<String> = (0..columns)
+            .map(|c| format!("cell {} {} with some **longer** text", r, c))
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// Load `content` into an app; keep the file alive while the app is used
+fn app_for(content: &str) -> (App, NamedTempFile) {
+    let mut file = NamedTempFile::new().expect("Failed to create temp file");
+    file.write_all(content.as_bytes())
+        .expect("Failed to write document");
+    file.flush().expect("Failed to flush");
+    let (doc, _warnings) = Document::load(file.path()).expect("Failed to load document");
+    (App::new(Config::default(), doc, vec![]), file)
+}
+
+fn bench_style_line(c: &mut Criterion) {
+    let (app, _file) = app_for(&mixed_document(1));
+    let mut group = c.benchmark_group("style_markdown_line");
+    for (name, line) in SAMPLE_LINES {
+        group.bench_with_input(BenchmarkId::from_parameter(name), line, |b, line| {
+            b.iter(|| bench::style_line(&app, black_box(line)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_table(c: &mut Criterion) {
+    let mut group = c.benchmark_group("table");
+    for rows in [10, 100] {
+        let (app, _file) = app_for(&table(rows, 5));
+        group.bench_with_input(BenchmarkId::new("rows", rows), &app, |b, app| {
+            b.iter(|| bench::table_lines(app, 0, black_box(100)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_wrapping(c: &mut Criterion) {
+    let (app, _file) = app_for(&mixed_document(40));
+    let mut group = c.benchmark_group("wrap_layout");
+    for width in [40usize, 80, 160] {
+        group.bench_with_input(BenchmarkId::new("width", width), &width, |b, &width| {
+            b.iter(|| {
+                let mut cache = LineLayoutCache::new();
+                cache.ensure_for(black_box(width), 0, 0, &app.doc.rope);
+                cache
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_full_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_frame");
+    for (width, height) in [(80u16, 24u16), (200, 60)] {
+        // Styled lines are cached between frames; a resize drops the cache
+        for cold in [false, true] {
+            let (mut app, _file) = app_for(&mixed_document(40));
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+            let kind = if cold { "cold" } else { "warm" };
+            let id = BenchmarkId::new(kind, format!("{}x{}", width, height));
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    if cold {
+                        app.on_resize(width, height);
+                    }
+                    terminal
+                        .draw(|frame| mdx_tui::ui::draw(frame, &mut app))
+                        .expect("draw");
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_style_line,
+    bench_table,
+    bench_wrapping,
+    bench_full_frame
+);
+criterion_main!(benches);
@@ -2,7 +2,7 @@
 
 use crate::panes::{PaneId, PaneManager};
 use crate::theme::Theme;
-use log::{info, trace};
+use tracing::{info, trace};
 use mdx_core::{
     config::ThemeVariant, detect_front_matter, Config, Document, FrontMatter, LineSelection,
 };
@@ -18,6 +18,10 @@ pub enum Mode {
     VisualLine,
     Search,
     VisualCommand,
+    /// Editing the source text of the cursor line
+    Insert,
+    /// Typing a `:` command
+    Command,
 }
 
 /// Mouse interaction state
@@ -45,7 +49,31 @@ pub enum KeyPrefix {
     None,
     CtrlW,
     Z, // For fold commands (za, zo, zc, zM, zR)
-    G, // For gg (jump to top). Reserved for future g-prefixed commands.
+    G, // For gg (jump to top), gb (breadcrumb popup), and gt/gT/gq (buffers)
+    LeftBracket,  // For [h (parent heading), [[ (previous heading), and [s (previous misspelling)
+    RightBracket, // For ]] (next heading) and ]s (next misspelling)
+    Around,       // For text objects in visual mode (as, ac, al, at)
+    Inner,        // For inner text objects in visual mode (is, ic, il, it)
+    Y,            // For yc (copy code block) outside visual mode
+}
+
+impl KeyPrefix {
+    /// The key that set the prefix, as the first key of a
+    /// [`KEYMAP`](crate::commands::KEYMAP) sequence
+    pub fn key(self) -> Option<crate::commands::KeyPress> {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let key = match self {
+            KeyPrefix::CtrlW => return Some((KeyCode::Char('w'), KeyModifiers::CONTROL)),
+            KeyPrefix::Z => 'z',
+            KeyPrefix::G => 'g',
+            KeyPrefix::LeftBracket => '[',
+            KeyPrefix::RightBracket => ']',
+            KeyPrefix::Y => 'y',
+            KeyPrefix::None | KeyPrefix::Around | KeyPrefix::Inner => return None,
+        };
+        Some((KeyCode::Char(key), KeyModifiers::NONE))
+    }
 }
 
 /// View state for a document viewport
@@ -59,6 +87,16 @@ pub struct ViewState {
     pub selection: Option<LineSelection>,
     pub show_raw: bool, // Toggle between rendered markdown and raw text
     pub collapsed_headings: std::collections::BTreeSet<usize>, // Line numbers of collapsed headings
+    /// Start lines of `<details>` blocks flipped from their HTML default
+    pub toggled_details: std::collections::BTreeSet<usize>,
+    /// Character column of the insert cursor on `cursor_line`
+    pub edit_col: usize,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ViewState {
@@ -71,6 +109,8 @@ impl ViewState {
             selection: None,
             show_raw: false,
             collapsed_headings: std::collections::BTreeSet::new(),
+            toggled_details: std::collections::BTreeSet::new(),
+            edit_col: 0,
         }
     }
 
@@ -85,6 +125,25 @@ impl ViewState {
     pub fn set_scroll_line(&mut self, source_line: usize) {
         self.scroll_pos = crate::scroll_math::VisualPos::at(source_line);
     }
+
+    /// All collapsed ranges (headings and `<details>` blocks) for this view
+    pub fn collapsed_ranges(&self, doc: &Document) -> Vec<crate::collapse::CollapseRange> {
+        crate::collapse::compute_all_collapsed_ranges(
+            &self.collapsed_headings,
+            &self.toggled_details,
+            doc,
+        )
+    }
+
+    /// Expand a collapsed range returned by `collapsed_ranges`
+    pub fn expand_range(&mut self, range: &crate::collapse::CollapseRange) {
+        if range.level.is_some() {
+            self.collapsed_headings.remove(&range.start);
+        } else if !self.toggled_details.remove(&range.start) {
+            // Collapsed details block that is open by default
+            self.toggled_details.insert(range.start);
+        }
+    }
 }
 
 /// Named layout constants used throughout the TUI. Kept in one place so
@@ -135,6 +194,12 @@ pub struct LayoutContext {
     last_signature: Option<u64>,
 }
 
+impl Default for LayoutContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LayoutContext {
     pub fn new() -> Self {
         Self {
@@ -149,6 +214,7 @@ impl LayoutContext {
         layout: &HashMap<PaneId, Rect>,
         doc_line_count: usize,
         show_scrollbar_flag: bool,
+        max_width: usize,
     ) {
         // Build a stable signature from inputs. Sort by pane id so
         // HashMap iteration order does not cause spurious bumps.
@@ -164,13 +230,15 @@ impl LayoutContext {
         }
         sig = sig.wrapping_mul(1315423911).wrapping_add(doc_line_count as u64);
         sig = sig.wrapping_mul(1315423911).wrapping_add(show_scrollbar_flag as u64);
+        sig = sig.wrapping_mul(1315423911).wrapping_add(max_width as u64);
 
         let changed = self.last_signature != Some(sig);
         self.last_signature = Some(sig);
 
         self.viewports.clear();
         for (pane_id, rect) in layout {
-            let viewport = PaneViewport::from_rect(*rect, doc_line_count, show_scrollbar_flag);
+            let viewport =
+                PaneViewport::from_rect(*rect, doc_line_count, show_scrollbar_flag, max_width);
             self.viewports.insert(*pane_id, viewport);
         }
 
@@ -192,7 +260,12 @@ impl LayoutContext {
 }
 
 impl PaneViewport {
-    fn from_rect(rect: Rect, doc_line_count: usize, show_scrollbar_flag: bool) -> Self {
+    fn from_rect(
+        rect: Rect,
+        doc_line_count: usize,
+        show_scrollbar_flag: bool,
+        max_width: usize,
+    ) -> Self {
         let content_area_height = rect.height.saturating_sub(layout_const::BREADCRUMB_ROWS);
         let visible_height =
             content_area_height.saturating_sub(layout_const::PANE_BORDER_ROWS) as usize;
@@ -201,6 +274,9 @@ impl PaneViewport {
         if has_scrollbar {
             content_width = content_width.saturating_sub(layout_const::SCROLLBAR_COLS);
         }
+        if max_width > 0 {
+            content_width = content_width.min(max_width.min(u16::MAX as usize) as u16);
+        }
 
         Self {
             visible_height,
@@ -262,6 +338,22 @@ impl ScrollContext {
     }
 }
 
+/// Lines a search is limited to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchScope {
+    pub lines: std::ops::Range<usize>,
+    pub kind: SearchScopeKind,
+}
+
+/// What a scoped search was limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScopeKind {
+    /// The cursor's section with its subsections (`g/`)
+    Section,
+    /// The visual selection (`/` in visual line mode)
+    Selection,
+}
+
 /// One entry on the jump-back stack (Ctrl-O / Ctrl-I).
 #[derive(Debug, Clone, Copy)]
 pub struct JumpEntry {
@@ -273,12 +365,159 @@ pub struct JumpEntry {
 /// Maximum number of entries kept on the jump stack.
 pub const JUMP_STACK_CAP: usize = 100;
 
+/// How long an animated page jump takes (`render.smooth_scroll`)
+pub const SMOOTH_SCROLL_DURATION: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// A page jump being animated: the pane's scroll moves from line `from`
+/// to `to` over [`SMOOTH_SCROLL_DURATION`]
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollAnimation {
+    pub pane: PaneId,
+    pub from: usize,
+    pub to: crate::scroll_math::VisualPos,
+    pub started: std::time::Instant,
+}
+
+/// Section moves and level changes kept for undo
+pub const OUTLINE_UNDO_LIMIT: usize = 50;
+
+/// A restructuring of the document from the TOC: its text before, and
+/// after so an undo can tell whether the file changed since
+#[derive(Debug, Clone)]
+pub struct OutlineEdit {
+    pub doc_id: usize,
+    pub before: ropey::Rope,
+    pub after: ropey::Rope,
+}
+
+/// Split typed search text into the query and a line offset, as vim
+/// reads `/query/+2` or `?query?-1`. A bare `+`/`-` is one line and a bare
+/// number counts down. `\/` (`\?`) puts the delimiter in the query, and
+/// text after the delimiter that isn't an offset stays part of the query.
+fn parse_search_input(input: &str, delimiter: char) -> (String, isize) {
+    let unescape = |text: &str| text.replace(&format!("\\{}", delimiter), &delimiter.to_string());
+    let mut escaped = false;
+    let end = input.char_indices().find(|&(_, c)| {
+        let end = c == delimiter && !escaped;
+        escaped = c == '\\' && !escaped;
+        end
+    });
+    let Some((at, _)) = end else {
+        return (unescape(input), 0);
+    };
+    let offset = match &input[at + 1..] {
+        "" => Some(0),
+        "+" => Some(1),
+        "-" => Some(-1),
+        text => text.strip_prefix('+').unwrap_or(text).parse().ok(),
+    };
+    match offset {
+        Some(offset) => (unescape(&input[..at]), offset),
+        None => (unescape(input), 0),
+    }
+}
+
+/// Lines kept above the target when opening at a location
+pub const OPEN_AT_CONTEXT_LINES: usize = 3;
+
+/// Characters in a line, excluding its line break
+fn line_len_chars(rope: &ropey::Rope, line: usize) -> usize {
+    if line >= rope.len_lines() {
+        return 0;
+    }
+    let text = rope.line(line);
+    let mut len = text.len_chars();
+    for ending in ['\n', '\r'] {
+        if len > 0 && text.char(len - 1) == ending {
+            len -= 1;
+        }
+    }
+    len
+}
+
 #[derive(Debug, Clone)]
 pub struct CommandOutput {
     pub command: String,
     pub output: String,
 }
 
+/// First and last line (inclusive) that a `:` range command applies to
+pub type LineRange = (usize, usize);
+
+/// How long a code block may run before it is killed
+pub const RUN_BLOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Output of a fenced code block run with `X`, shown in a split below the
+/// panes
+#[derive(Debug, Clone)]
+pub struct BlockOutput {
+    pub lang: String,
+    /// Line of the block's opening fence
+    pub start_line: usize,
+    /// Which run this is, to match it with the worker's result
+    pub run: u64,
+    /// Captured output, or why the block could not be started; `None`
+    /// while the block is still running
+    pub result: Option<Result<mdx_core::exec::ExecOutput, String>>,
+}
+
+/// What a confirmation prompt does once answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAction {
+    /// Quit over unsaved edits: yes saves them first, no discards them
+    Quit,
+    /// Reload the document from disk, discarding unsaved edits
+    Reload,
+    /// Run the fenced code block whose opening fence is on this line
+    RunBlock(usize),
+}
+
+impl ConfirmAction {
+    /// The answers offered, as shown after the question. Only quitting has
+    /// a "no" that differs from cancelling.
+    pub fn choices(self) -> &'static str {
+        match self {
+            ConfirmAction::Quit => "[y]es [n]o [c]ancel",
+            ConfirmAction::Reload | ConfirmAction::RunBlock(_) => "[y]es [n]o",
+        }
+    }
+}
+
+/// Answer to a confirmation prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAnswer {
+    Yes,
+    No,
+    Cancel,
+}
+
+/// A yes/no question in the status bar; it takes every key until answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmPrompt {
+    pub question: String,
+    pub action: ConfirmAction,
+}
+
+/// A document that couldn't be read, shown in place of its panes
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    pub path: std::path::PathBuf,
+    /// The error and its causes, outermost first
+    pub chain: Vec<String>,
+    /// Path being typed after `o`, to open another file instead
+    pub prompt: Option<String>,
+}
+
+impl LoadError {
+    pub fn new(path: &std::path::Path, error: &anyhow::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            chain: error.chain().map(|cause| cause.to_string()).collect(),
+            prompt: None,
+        }
+    }
+}
+
 /// Type of status message
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StatusMessageKind {
@@ -291,6 +530,24 @@ pub enum StatusMessageKind {
 pub struct App {
     pub config: Config,
     pub doc: Document,
+    /// Id of the shown document, used by its panes and diff requests
+    pub doc_id: usize,
+    /// Every open document in order. The shown document's slot is `None`
+    /// because its state lives in the fields of `App` itself.
+    pub buffers: Vec<Option<crate::buffers::Buffer>>,
+    /// Index of the shown document in `buffers`
+    pub active_buffer: usize,
+    next_doc_id: usize,
+    /// Directory given on the command line; its Markdown files are open
+    /// as buffers
+    pub directory: Option<std::path::PathBuf>,
+    pub show_buffer_picker: bool,
+    pub buffer_picker_selected: usize,
+    /// Fuzzy finder overlay (`Ctrl+P`), `None` when closed
+    pub finder: Option<crate::finder::Finder>,
+    /// Set when the shown document failed to load or reload; the error
+    /// screen replaces the panes until it is retried or dismissed
+    pub load_error: Option<LoadError>,
     pub front_matter: Option<FrontMatter>,
     pub panes: PaneManager,
     pub theme: Theme,
@@ -304,6 +561,9 @@ pub struct App {
     /// scroll changed *because* the TOC moved, so re-selecting from the
     /// new scroll would just echo back.
     pub toc_tracking_suppress_once: bool,
+    /// Section moves and level changes made from the TOC, newest last,
+    /// for `u` to undo
+    pub outline_undo: Vec<OutlineEdit>,
     /// Vim-style jump history for Ctrl-O / Ctrl-I. Each entry captures the
     /// focused pane and its scroll/cursor position at the moment of a
     /// *jump* (TOC click, search result, `G`/`gg`, goto-line). Ordinary
@@ -316,12 +576,80 @@ pub struct App {
     pub show_toc_dialog: bool,
     pub toc_dialog_selected: usize,
     pub toc_dialog_scroll: usize,
+    /// Filter for the TOC dialog's headings
+    pub toc_dialog_query: String,
+    /// Whether keys go to `toc_dialog_query` rather than moving
+    pub toc_dialog_filtering: bool,
+    pub show_breadcrumb_popup: bool,
+    /// Index into `breadcrumb_heading_indices` of the focused pane
+    pub breadcrumb_popup_selected: usize,
+    /// Links and images whose targets are missing, sorted by line
+    pub broken_links: Vec<mdx_core::links::BrokenLink>,
+    pub show_link_diagnostics: bool,
+    pub link_diagnostics_selected: usize,
+    /// What each include line (`![[file#section]]`) shows below itself,
+    /// by source line
+    pub transclusions: HashMap<usize, Vec<mdx_core::transclude::IncludedLine>>,
+    /// Misspelled prose words, in document order
+    pub misspellings: Vec<mdx_core::spell::WordRef>,
+    /// Dictionary the misspellings were found with, used for suggestions
+    pub spell_dictionary: Option<std::sync::Arc<mdx_core::spell::Dictionary>>,
+    /// Threads the background workers share
+    pub workers: crate::worker_pool::WorkerPool,
+    /// Made the first time spell checking is enabled
+    pub spell_worker: Option<crate::spell_worker::SpellWorker>,
+    pub show_spell_popup: bool,
+    /// Target of the reference on the cursor line, shown by `K`
+    pub preview: Option<crate::preview::Preview>,
+    /// Document statistics, computed when the stats popup opens
+    pub stats_popup: Option<mdx_core::stats::DocumentStats>,
+    /// Index into the stats popup's section list
+    pub stats_popup_selected: usize,
+    /// Slides as line ranges while presenting, `None` otherwise
+    pub presentation: Option<Vec<std::ops::Range<usize>>>,
+    pub presentation_slide: usize,
+    /// Quit instead of returning to the document when the presentation
+    /// ends (`mdx present`)
+    pub quit_after_presentation: bool,
+    /// Two-file comparison shown instead of the document (`mdx --diff`)
+    #[cfg(feature = "git")]
+    pub diff_view: Option<crate::diff_view::DiffView>,
+    /// Commits that touched the document (`gl`, `:GitLog`)
+    #[cfg(feature = "git")]
+    pub git_log: Option<crate::git_log::GitLog>,
+    /// Dim other sections and hide line numbers, gutters, and the status bar
+    pub focus_mode: bool,
     pub key_prefix: KeyPrefix,
+    /// Count typed before a command (the `5` of `5j`), shown in the
+    /// status bar until the command consumes it
+    pub pending_count: Option<usize>,
     pub should_quit: bool,
     pub search_query: String,
     pub search_matches: Vec<usize>,
     pub search_current_match: Option<usize>,
+    /// `search_matches` stopped at `search.max_matches`; more matches lie
+    /// past its last entry
+    pub search_matches_capped: bool,
+    /// Matches are highlighted; `:noh` hides them until the next search
+    pub search_highlight: bool,
+    /// Text typed after `/` or `?`: the query, then optionally the
+    /// delimiter and a line offset (`/todo/+2`)
+    pub search_input: String,
+    /// The search runs upwards (`?`), so `n` goes up and `N` down
+    pub search_backward: bool,
+    /// Lines from a match to where the cursor lands
+    pub search_offset: isize,
+    /// Position the search being typed started from, restored by Esc
+    pub search_origin: Option<JumpEntry>,
+    /// Lines the search is limited to, if it is
+    pub search_scope: Option<SearchScope>,
     pub show_help: bool,
+    /// First help line shown in the help popup
+    pub help_scroll: usize,
+    /// Filter for the help popup's entries
+    pub help_query: String,
+    /// Whether keys go to `help_query` rather than scrolling
+    pub help_searching: bool,
     pub options_dialog: Option<crate::options_dialog::OptionsDialog>,
     pub security_warnings: Vec<mdx_core::SecurityEvent>,
     pub show_security_warnings: bool,
@@ -331,12 +659,110 @@ pub struct App {
     /// Wrapped-line height cache. Kept on App so multiple scroll/render
     /// paths can share the same O(lines)-rebuild amortized work.
     pub line_layout_cache: crate::line_layout::LineLayoutCache,
+    /// Styled spans per source line, reused across frames while the
+    /// document, theme, search query, and width stay the same.
+    pub styled_line_cache: crate::render::StyledLineCache,
+    /// Whole-document rendering for `render.commonmark`
+    pub commonmark_lines: crate::render::CommonMarkLines,
+    /// Section sizes for `toc.stats`
+    pub toc_stats: crate::render::SectionStatsCache,
     pub visual_command_buffer: String,
+    /// Text typed after `:`
+    pub command_line: String,
+    /// Lines of the visual selection `:` was typed in, for `:'<,'>` ranges
+    pub visual_marks: Option<LineRange>,
     pub command_output: Option<CommandOutput>,
+    /// Last code block run, shown until closed with Esc
+    pub block_output: Option<BlockOutput>,
+    /// Made the first time a code block runs
+    pub exec_worker: Option<crate::exec_worker::ExecWorker>,
+    /// Number of code blocks run, to tell runs apart
+    block_runs: u64,
+    /// Question waiting for an answer before a destructive action
+    pub confirm: Option<ConfirmPrompt>,
+    /// Set by `--insecure`. Running code blocks needs this as well as
+    /// `security.no_exec = false`.
+    pub insecure: bool,
+    /// Startup timings, present only when `--profile-startup` is set
+    pub startup_profile: Option<crate::profile::StartupProfile>,
     #[cfg(feature = "watch")]
     pub watcher: Option<crate::watcher::FileWatcher>,
+    /// Watches the directory tree in directory mode
+    #[cfg(feature = "watch")]
+    pub dir_watcher: Option<crate::watcher::DirWatcher>,
+    /// Watches the config file so edits apply without a restart
+    #[cfg(feature = "watch")]
+    pub config_watcher: Option<crate::watcher::FileWatcher>,
+    /// Modification time left by our own options save, so the config
+    /// watcher doesn't reload it straight back
+    #[cfg(feature = "watch")]
+    pub config_saved_mtime: Option<std::time::SystemTime>,
     #[cfg(feature = "git")]
     pub diff_worker: crate::diff_worker::DiffWorker,
+    /// Show removed base lines under their diff mark (`git.show_deleted`)
+    #[cfg(feature = "git")]
+    pub show_deleted_lines: bool,
+    /// Image metadata kept between frames, within `images.cache_mb`
+    #[cfg(feature = "images")]
+    pub image_cache: crate::image_cache::ImageCache,
+    /// Show images as their alt text only (`images.alt_text`, toggled with `I`)
+    #[cfg(feature = "images")]
+    pub show_image_alt_text: bool,
+    /// Show image cache counters (`:imagecache`)
+    pub show_image_cache_stats: bool,
+    /// Recent log events, recorded when started with `--verbose`
+    pub log_buffer: Option<crate::logging::LogBuffer>,
+    /// Log viewer (`:log`) scroll, in events above the newest; None when closed
+    pub log_viewer: Option<usize>,
+    /// Keys arrive through the kitty keyboard protocol, so Tab and Ctrl+I
+    /// are different keys
+    pub enhanced_keys: bool,
+    /// Whether the terminal shows 24-bit color (`COLORTERM`)
+    pub truecolor: bool,
+    /// Socket an editor sends requests to (`--listen`)
+    pub listener: Option<crate::listen::Listener>,
+    /// Page jump being animated (`render.smooth_scroll`)
+    pub scroll_animation: Option<ScrollAnimation>,
+    /// Session file reading positions are saved to; None (in tests, or
+    /// comparing files with `--diff`) keeps none
+    pub session_path: Option<std::path::PathBuf>,
+    /// Input arrived since the session was last saved
+    pub session_dirty: bool,
+    /// When the session was last saved, for `session.autosave_secs`
+    pub session_saved: std::time::Instant,
+    /// Opening fence line of the code block last copied with `yc`, and
+    /// when, for the "copied!" flash on its label
+    pub copied_code_block: Option<(usize, std::time::Instant)>,
+    /// Made the first time a document has wiki page or remote links to check
+    pub link_worker: Option<crate::link_worker::LinkWorker>,
+}
+
+/// Apply a remembered position to a document's view and jump list,
+/// clamped to the document as it is now
+fn restore_file(
+    file: &mdx_core::session::FileState,
+    doc: &Document,
+    panes: &mut PaneManager,
+    jump_stack: &mut std::collections::VecDeque<JumpEntry>,
+    jump_cursor: &mut usize,
+) {
+    let last = doc.line_count().saturating_sub(1);
+    let pane = panes.focused;
+    let Some(view) = panes.focused_pane_mut().map(|pane| &mut pane.view) else {
+        return;
+    };
+    view.cursor_line = file.cursor_line.min(last);
+    view.set_scroll_line(file.scroll_line.min(view.cursor_line));
+    *jump_stack = file
+        .jumps
+        .iter()
+        .map(|&line| JumpEntry {
+            pane,
+            scroll_pos: crate::scroll_math::VisualPos::at(line.min(last)),
+            cursor_line: line.min(last),
+        })
+        .collect();
+    *jump_cursor = jump_stack.len();
 }
 
 impl App {
@@ -352,23 +778,42 @@ impl App {
         };
         #[cfg(not(feature = "images"))]
         let config = config;
+        let mut config = config;
+        config.apply_a11y();
 
         let show_toc = config.toc.enabled;
+        let focus_mode = config.render.focus_mode;
+        #[cfg(feature = "git")]
+        let show_deleted_lines = config.git.show_deleted;
         let theme_variant = config.theme;
-        let theme = Theme::for_variant(theme_variant);
+        let theme = Theme::for_config(theme_variant, &config.render, true);
         let panes = PaneManager::new(0); // Single pane for single document
         let show_security_warnings = !warnings.is_empty();
 
         #[cfg(feature = "watch")]
-        let watcher = if config.watch.enabled {
+        let watcher = if config.watch.enabled && doc.revision.is_none() {
             crate::watcher::FileWatcher::new(&doc.path).ok()
         } else {
             None
         };
+        #[cfg(feature = "watch")]
+        let config_watcher = if config.watch.enabled {
+            Config::config_path()
+                .filter(|path| path.exists())
+                .and_then(|path| crate::watcher::FileWatcher::new(&path).ok())
+        } else {
+            None
+        };
+
+        let workers = crate::worker_pool::WorkerPool::spawn();
+        #[cfg(feature = "images")]
+        let image_cache = crate::image_cache::ImageCache::new(config.images.cache_mb, &workers);
+        #[cfg(feature = "images")]
+        let show_image_alt_text = config.images.alt_text;
 
         #[cfg(feature = "git")]
         let diff_worker = {
-            let worker = crate::diff_worker::DiffWorker::spawn();
+            let worker = crate::diff_worker::DiffWorker::new(&workers);
             // Send initial diff request
             if config.git.diff {
                 let current_text: String = doc.rope.chunks().collect();
@@ -377,6 +822,8 @@ impl App {
                     path: doc.path.clone(),
                     rev: doc.rev,
                     current_text,
+                    git: true,
+                    saved_text: None,
                 });
             }
             worker
@@ -385,6 +832,15 @@ impl App {
         let mut app = Self {
             config,
             doc,
+            doc_id: 0,
+            buffers: vec![None],
+            active_buffer: 0,
+            next_doc_id: 1,
+            directory: None,
+            show_buffer_picker: false,
+            buffer_picker_selected: 0,
+            finder: None,
+            load_error: None,
             front_matter: None,
             panes,
             theme,
@@ -394,17 +850,53 @@ impl App {
             toc_selected: 0,
             toc_scroll: 0,
             toc_tracking_suppress_once: false,
+            outline_undo: Vec::new(),
             jump_stack: std::collections::VecDeque::new(),
             jump_cursor: 0,
             show_toc_dialog: false,
+            show_breadcrumb_popup: false,
+            breadcrumb_popup_selected: 0,
+            broken_links: Vec::new(),
+            show_link_diagnostics: false,
+            link_diagnostics_selected: 0,
+            transclusions: HashMap::new(),
+            misspellings: Vec::new(),
+            spell_dictionary: None,
+            workers,
+            spell_worker: None,
+            show_spell_popup: false,
+            preview: None,
+            stats_popup: None,
+            stats_popup_selected: 0,
+            presentation: None,
+            presentation_slide: 0,
+            quit_after_presentation: false,
+            #[cfg(feature = "git")]
+            diff_view: None,
+            #[cfg(feature = "git")]
+            git_log: None,
+            focus_mode,
             toc_dialog_selected: 0,
             toc_dialog_scroll: 0,
+            toc_dialog_query: String::new(),
+            toc_dialog_filtering: false,
             key_prefix: KeyPrefix::None,
+            pending_count: None,
             should_quit: false,
             search_query: String::new(),
             search_matches: Vec::new(),
             search_current_match: None,
+            search_matches_capped: false,
+            search_highlight: true,
+            search_input: String::new(),
+            search_backward: false,
+            search_offset: 0,
+            search_origin: None,
+            search_scope: None,
             show_help: false,
+            help_scroll: 0,
+            help_query: String::new(),
+            help_searching: false,
             options_dialog: None,
             security_warnings: warnings,
             show_security_warnings,
@@ -412,15 +904,53 @@ impl App {
             mouse_state: MouseState::Idle,
             layout_context: LayoutContext::new(),
             line_layout_cache: crate::line_layout::LineLayoutCache::new(),
+            styled_line_cache: crate::render::StyledLineCache::new(),
+            commonmark_lines: crate::render::CommonMarkLines::default(),
+            toc_stats: crate::render::SectionStatsCache::default(),
             visual_command_buffer: String::new(),
+            command_line: String::new(),
+            visual_marks: None,
             command_output: None,
+            block_output: None,
+            exec_worker: None,
+            block_runs: 0,
+            confirm: None,
+            insecure: false,
+            startup_profile: None,
             #[cfg(feature = "watch")]
             watcher,
+            #[cfg(feature = "watch")]
+            dir_watcher: None,
+            #[cfg(feature = "watch")]
+            config_watcher,
+            #[cfg(feature = "watch")]
+            config_saved_mtime: None,
             #[cfg(feature = "git")]
             diff_worker,
+            #[cfg(feature = "git")]
+            show_deleted_lines,
+            #[cfg(feature = "images")]
+            image_cache,
+            #[cfg(feature = "images")]
+            show_image_alt_text,
+            show_image_cache_stats: false,
+            log_buffer: crate::logging::buffer(),
+            log_viewer: None,
+            enhanced_keys: false,
+            truecolor: true,
+            listener: None,
+            scroll_animation: None,
+            session_path: None,
+            session_dirty: false,
+            session_saved: std::time::Instant::now(),
+            copied_code_block: None,
+            link_worker: None,
         };
 
         app.refresh_front_matter_info();
+        app.refresh_link_diagnostics();
+        app.refresh_transclusions();
+        app.refresh_spell_check();
 
         app
     }
@@ -501,6 +1031,7 @@ impl App {
             layout,
             self.doc.line_count(),
             self.config.render.show_scrollbar,
+            self.config.render.max_width,
         );
     }
 
@@ -508,6 +1039,54 @@ impl App {
         self.layout_context.focused_viewport(self.panes.focused)
     }
 
+    /// Vim-style position of the focused pane: `All` when the document fits,
+    /// `Top` or `Bot` at either end, otherwise the share of wrapped rows
+    /// above the viewport as `NN%`. Folded sections don't count.
+    pub fn scroll_position(&mut self) -> String {
+        let (Some(viewport), Some(pane)) = (self.focused_viewport(), self.panes.focused_pane())
+        else {
+            return "All".to_string();
+        };
+        let top = pane.view.scroll_line();
+        let wrap_row = pane.view.scroll_pos.wrap_row as usize;
+        let mut folded: Vec<LineRange> = pane
+            .view
+            .collapsed_ranges(&self.doc)
+            .iter()
+            .map(|r| (r.start + 1, r.end + 1))
+            .collect();
+        folded.sort_unstable();
+
+        let gen = self.layout_context.generation();
+        self.line_layout_cache
+            .ensure_for(viewport.content_width, self.doc.rev, gen, &self.doc.rope);
+        let cache = &self.line_layout_cache;
+        // Visual rows in [start, end), less the hidden part of any fold
+        let rows = |start: usize, end: usize| {
+            let mut hidden_to = start;
+            let mut count = cache.visual_rows_in_range(start, end);
+            for &(from, to) in &folded {
+                let (from, to) = (from.max(hidden_to), to.min(end));
+                if from < to {
+                    count -= cache.visual_rows_in_range(from, to);
+                    hidden_to = to;
+                }
+            }
+            count
+        };
+
+        let (lo, hi) = self.rendered_content_bounds();
+        let top = top.max(lo);
+        let above = rows(lo, top) + wrap_row;
+        let below = rows(top, hi + 1).saturating_sub(wrap_row + viewport.visible_height);
+        match (above, below) {
+            (0, 0) => "All".to_string(),
+            (0, _) => "Top".to_string(),
+            (_, 0) => "Bot".to_string(),
+            _ => format!("{}%", above * 100 / (above + below)),
+        }
+    }
+
     /// Set an error message to display in the status bar
     pub fn set_error_message(&mut self, message: impl Into<String>) {
         self.status_message = Some((message.into(), StatusMessageKind::Error));
@@ -531,6 +1110,9 @@ impl App {
     /// Toggle help dialog
     pub fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
+        self.help_scroll = 0;
+        self.help_query.clear();
+        self.help_searching = false;
     }
 
     /// Open options dialog
@@ -546,43 +1128,88 @@ impl App {
     /// Apply options from dialog (Ok button)
     pub fn apply_options(&mut self) {
         if let Some(dialog) = &self.options_dialog {
-            self.config = dialog.get_config();
-            self.refresh_front_matter_info();
-            // Update theme if it changed
-            if self.config.theme != self.theme_variant {
-                self.theme_variant = self.config.theme;
-                self.theme = crate::theme::Theme::for_variant(self.theme_variant);
+            self.set_config(dialog.get_config());
+        }
+        self.options_dialog = None;
+    }
+
+    /// Save options to config file (Save button), returning the path
+    /// written
+    pub fn save_options(&mut self) -> anyhow::Result<std::path::PathBuf> {
+        let Some(dialog) = &self.options_dialog else {
+            anyhow::bail!("Options dialog is not open");
+        };
+        let new_config = dialog.get_config();
+
+        // Only the settings changed in the dialog are written, so neither
+        // `--insecure` nor a project `.mdx.toml` leaks into the user's file
+        let (user_config, _warnings) = Config::load()?;
+        let saved = user_config.with_changes(&dialog.original_config, &new_config)?;
+        let path = mdx_core::Config::save_to_file(&saved)?;
+
+        #[cfg(feature = "watch")]
+        {
+            self.config_saved_mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if self.config_watcher.is_none() && new_config.watch.enabled {
+                self.config_watcher = crate::watcher::FileWatcher::new(&path).ok();
             }
-            // Update TOC visibility
-            self.show_toc = self.config.toc.enabled;
         }
+
+        // Apply changes
+        self.set_config(new_config);
         self.options_dialog = None;
+        Ok(path)
+    }
+
+    /// Replace the configuration and bring the theme, TOC, and checks in
+    /// line with it
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.config.apply_a11y();
+        self.refresh_front_matter_info();
+        // Update theme if it changed
+        self.theme_variant = self.config.theme;
+        self.refresh_theme();
+        // Update TOC visibility
+        self.show_toc = self.config.toc.enabled;
+        #[cfg(feature = "git")]
+        {
+            self.show_deleted_lines = self.config.git.show_deleted;
+        }
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        // Render settings are not part of the styled line cache key
+        self.styled_line_cache.clear();
+        self.commonmark_lines.clear();
+        #[cfg(feature = "images")]
+        {
+            self.image_cache.set_budget_mb(self.config.images.cache_mb);
+            self.show_image_alt_text = self.config.images.alt_text;
+        }
         // Toggling the scrollbar or TOC changes per-pane content_width, which
         // changes wrapping. Re-clamp so nothing is scrolled past the new end.
         // The layout_context will be refreshed at the next draw.
         self.enforce_rendered_bounds();
     }
 
-    /// Save options to config file (Save button)
-    pub fn save_options(&mut self) -> anyhow::Result<()> {
-        if let Some(dialog) = &self.options_dialog {
-            let new_config = dialog.get_config();
-            // Save to file
-            mdx_core::Config::save_to_file(&new_config)?;
-            // Apply changes
-            self.config = new_config;
-            self.refresh_front_matter_info();
-            self.enforce_rendered_bounds();
-            // Update theme if it changed
-            if self.config.theme != self.theme_variant {
-                self.theme_variant = self.config.theme;
-                self.theme = crate::theme::Theme::for_variant(self.theme_variant);
-            }
-            // Update TOC visibility
-            self.show_toc = self.config.toc.enabled;
+    /// Re-read the config file, and any project `.mdx.toml`, and apply it
+    /// (`:config reload`, or a change seen by the config watcher).
+    /// `--insecure` stays in effect.
+    pub fn reload_config(&mut self) {
+        match Config::load_for(self.doc.file_path()) {
+            Ok((mut config, _warnings)) => {
+                if self.insecure {
+                    config.security.safe_mode = false;
+                }
+                self.set_config(config);
+                let source = Config::config_path()
+                    .filter(|path| path.exists())
+                    .map_or("defaults".to_string(), |path| path.display().to_string());
+                self.set_info_message(format!("Reloaded config from {}", source));
+            }
+            Err(e) => self.set_error_message(format!("Config reload failed: {:#}", e)),
         }
-        self.options_dialog = None;
-        Ok(())
     }
 
     /// Toggle security warnings pane
@@ -596,795 +1223,2565 @@ impl App {
         self.show_security_warnings = true;
     }
 
-    /// Handle quit request
-    pub fn quit(&mut self) {
-        self.should_quit = true;
+    /// Re-check links and images against the filesystem, and queue remote
+    /// checks when enabled
+    pub fn refresh_link_diagnostics(&mut self) {
+        self.broken_links.clear();
+        self.link_diagnostics_selected = 0;
+        if !self.config.links.check {
+            return;
+        }
+        // Bare URLs are always remote and skipped; the GFM flavor is for wiki links
+        let mut links =
+            mdx_core::links::extract_links(&self.doc.rope, mdx_core::config::MarkdownFlavor::Gfm);
+        self.broken_links = mdx_core::links::find_broken_local_links(&self.doc, &links);
+
+        // Finding a wiki page may take a search of the whole vault, and a
+        // remote link a request, so those are checked on the link worker
+        let check_remote = cfg!(feature = "remote-links")
+            && self.config.links.check_remote
+            && !self.config.security.safe_mode;
+        links.retain(|link| link.is_wiki_page());
+        if check_remote {
+            let flavor = self.config.render.markdown.flavor;
+            let remote = mdx_core::links::extract_links(&self.doc.rope, flavor);
+            links.extend(remote.into_iter().filter(|link| link.is_remote()));
+        }
+        // An empty check still overtakes one in flight
+        if links.is_empty() && self.link_worker.is_none() {
+            return;
+        }
+        let base_dir = self.doc.path.parent().unwrap_or(std::path::Path::new("."));
+        let request = crate::link_worker::LinkCheckRequest {
+            rev: self.doc.rev,
+            links,
+            base_dir: base_dir.to_path_buf(),
+            vault_root: self.config.links.vault_root.clone(),
+        };
+        self.link_worker
+            .get_or_insert_with(|| crate::link_worker::LinkWorker::new(&self.workers))
+            .request_check(request);
     }
 
-    /// Reload document from disk
-    pub fn reload_document(&mut self) -> anyhow::Result<()> {
-        self.doc.reload()?;
-        self.refresh_front_matter_info();
-
-        // Re-validate viewport positions after reload
-        // Use a reasonable default height; actual height from layout context
-        // will be used inside auto_scroll if available
-        let default_height = 20;
-        self.auto_scroll(default_height);
+    /// Re-read the files and sections include lines show. Fenced code is
+    /// left alone, as are documents read from standard input.
+    pub fn refresh_transclusions(&mut self) {
+        self.transclusions.clear();
+        // Safe mode reads no files besides the document
+        if !self.config.render.transclude || self.config.security.safe_mode {
+            return;
+        }
+        let Some(base_dir) = self.doc.file_path().and_then(|path| path.parent()) else {
+            return;
+        };
+        let max_bytes = self.config.document.max_file_bytes;
+        let mut fences = mdx_core::fence::FenceTracker::new();
+        for (idx, line) in self.doc.rope.lines().enumerate() {
+            let line = line.to_string();
+            if fences.next_line(&line) != mdx_core::fence::FenceLine::Text {
+                continue;
+            }
+            if let Some(include) = mdx_core::transclude::parse_include(&line) {
+                let lines = mdx_core::transclude::expand(&include, base_dir, max_bytes);
+                self.transclusions.insert(idx, lines);
+            }
+        }
+    }
 
-        // Request diff computation in background
-        #[cfg(feature = "git")]
-        if self.config.git.diff {
-            let current_text: String = self.doc.rope.chunks().collect();
-            self.diff_worker
-                .request_diff(crate::diff_worker::DiffRequest {
-                    doc_id: 0,
-                    path: self.doc.path.clone(),
-                    rev: self.doc.rev,
-                    current_text,
-                });
+    /// Merge broken wiki page and remote links reported by the link worker
+    pub fn apply_link_results(&mut self, result: crate::link_worker::LinkCheckResult) {
+        if result.rev != self.doc.rev || !self.config.links.check {
+            return;
         }
+        self.broken_links
+            .retain(|b| !b.link.is_remote() && !b.link.is_wiki_page());
+        self.broken_links.extend(result.broken);
+        self.broken_links.sort_by_key(|b| b.link.range.start);
+    }
 
-        Ok(())
+    /// Broken links starting on a source line
+    pub fn broken_links_on_line(
+        &self,
+        line: usize,
+    ) -> impl Iterator<Item = &mdx_core::links::BrokenLink> {
+        self.broken_links.iter().filter(move |b| b.link.line == line)
     }
 
-    /// Move cursor down by n lines, skipping collapsed blocks
-    pub fn move_cursor_down(&mut self, n: usize) {
-        let bounds = self.rendered_content_bounds();
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            let next_line = pane.view.cursor_line.saturating_add(n);
-            pane.view.cursor_line = next_line.clamp(bounds.0, bounds.1);
+    /// Toggle the broken link diagnostics panel
+    pub fn toggle_link_diagnostics(&mut self) {
+        if !self.show_link_diagnostics && self.broken_links.is_empty() {
+            self.set_success_message("No broken links");
+            return;
+        }
+        self.show_link_diagnostics = !self.show_link_diagnostics;
+        self.link_diagnostics_selected = self
+            .link_diagnostics_selected
+            .min(self.broken_links.len().saturating_sub(1));
+    }
 
-            // Debug assertion: cursor should always be within bounds
-            debug_assert!(
-                pane.view.cursor_line >= bounds.0 && pane.view.cursor_line <= bounds.1,
-                "Cursor line {} out of bounds [{}, {}]",
-                pane.view.cursor_line,
-                bounds.0,
-                bounds.1
-            );
+    /// Move diagnostics selection down
+    pub fn link_diagnostics_move_down(&mut self) {
+        if !self.broken_links.is_empty() {
+            self.link_diagnostics_selected =
+                (self.link_diagnostics_selected + 1).min(self.broken_links.len() - 1);
         }
-        self.adjust_cursor_for_collapsed_blocks(true);
-        self.update_selection();
     }
 
-    /// Move cursor up by n lines, skipping collapsed blocks
-    pub fn move_cursor_up(&mut self, n: usize) {
-        let bounds = self.rendered_content_bounds();
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            let prev_line = pane.view.cursor_line.saturating_sub(n);
-            pane.view.cursor_line = prev_line.clamp(bounds.0, bounds.1);
+    /// Move diagnostics selection up
+    pub fn link_diagnostics_move_up(&mut self) {
+        self.link_diagnostics_selected = self.link_diagnostics_selected.saturating_sub(1);
+    }
 
-            // Debug assertion: cursor should always be within bounds
-            debug_assert!(
-                pane.view.cursor_line >= bounds.0 && pane.view.cursor_line <= bounds.1,
-                "Cursor line {} out of bounds [{}, {}]",
-                pane.view.cursor_line,
-                bounds.0,
-                bounds.1
-            );
+    /// Jump to the selected broken link and close the panel
+    pub fn link_diagnostics_jump_to_selected(&mut self) {
+        self.show_link_diagnostics = false;
+        if let Some(line) = self
+            .broken_links
+            .get(self.link_diagnostics_selected)
+            .map(|b| b.link.line)
+        {
+            self.push_jump();
+            let pane_id = self.panes.focused;
+            self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::Center);
         }
-        self.adjust_cursor_for_collapsed_blocks(false);
-        self.update_selection();
     }
 
-    /// Adjust cursor position if it lands inside a collapsed block
-    /// moving_down: if true, cursor lands on the line after the collapsed block; if false, on the heading
-    fn adjust_cursor_for_collapsed_blocks(&mut self, moving_down: bool) {
-        let bounds = self.rendered_content_bounds();
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            let cursor = pane.view.cursor_line;
+    /// Queue a spell check of the current revision, or clear the results
+    /// when spell checking is off
+    pub fn refresh_spell_check(&mut self) {
+        if !self.config.spell.enabled {
+            self.misspellings.clear();
+            self.spell_dictionary = None;
+            self.show_spell_popup = false;
+            return;
+        }
 
-            // Compute collapsed ranges
-            let collapsed_ranges = crate::collapse::compute_all_collapsed_ranges(
-                &pane.view.collapsed_headings,
-                &self.doc,
+        let spell = &self.config.spell;
+        let Some(dictionary) = mdx_core::spell::find_dictionary(&spell.dictionary, &spell.language)
+        else {
+            let msg = format!(
+                "No {} dictionary found; set spell.dictionary to a hunspell .dic file",
+                spell.language
             );
+            self.set_error_message(msg);
+            return;
+        };
 
-            // Check if cursor is inside a collapsed range (but not at the start)
-            if let Some(range) =
-                crate::collapse::find_range_containing_line(&collapsed_ranges, cursor)
-            {
-                if moving_down {
-                    // When moving down, jump to the line after the collapsed block
-                    let target_line = range.end.saturating_add(1);
-                    pane.view.cursor_line = target_line.clamp(bounds.0, bounds.1);
-                } else {
-                    // When moving up, jump to the heading line
-                    pane.view.cursor_line = range.start.clamp(bounds.0, bounds.1);
-                }
+        let request = crate::spell_worker::SpellCheckRequest {
+            rev: self.doc.rev,
+            rope: self.doc.rope.clone(),
+            dictionary,
+            words: spell.words.clone(),
+        };
+        self.spell_worker
+            .get_or_insert_with(|| crate::spell_worker::SpellWorker::new(&self.workers))
+            .request_check(request);
+    }
+
+    /// Store misspellings reported by the spell worker
+    pub fn apply_spell_results(&mut self, result: crate::spell_worker::SpellCheckResult) {
+        if result.rev != self.doc.rev || !self.config.spell.enabled {
+            return;
+        }
+        match result.outcome {
+            Ok((dictionary, misspellings)) => {
+                self.spell_dictionary = Some(dictionary);
+                self.misspellings = misspellings;
             }
+            Err(e) => self.set_error_message(format!("Spell check failed: {}", e)),
         }
     }
 
-    /// Jump to specific line, expanding collapsed blocks if necessary
-    pub fn jump_to_line(&mut self, line: usize) {
-        let bounds = self.rendered_content_bounds();
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            let target_line = line.clamp(bounds.0, bounds.1);
-
-            // Expand ALL collapsed blocks that contain the target line
-            // This handles nested collapsed headings (e.g., "## Parent" contains "### Child")
-            // We need multiple passes because removing one heading may reveal others
-            loop {
-                let collapsed_ranges = crate::collapse::compute_all_collapsed_ranges(
-                    &pane.view.collapsed_headings,
-                    &self.doc,
-                );
+    /// Misspelled words on a source line
+    pub fn misspellings_on_line(
+        &self,
+        line: usize,
+    ) -> impl Iterator<Item = &mdx_core::spell::WordRef> {
+        self.misspellings.iter().filter(move |w| w.line == line)
+    }
 
-                // Find any collapsed range containing the target
-                let containing_range = collapsed_ranges
-                    .iter()
-                    .find(|r| r.contains_line(target_line) || r.start == target_line);
+    /// Move the cursor to the next (or previous) line with a misspelling,
+    /// wrapping around the document
+    pub fn jump_to_misspelling(&mut self, forward: bool) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let mut lines: Vec<usize> = self.misspellings.iter().map(|w| w.line).collect();
+        lines.dedup();
 
-                if let Some(range) = containing_range {
-                    // Expand this collapsed heading
-                    pane.view.collapsed_headings.remove(&range.start);
-                } else {
-                    // No more collapsed ranges containing target
-                    break;
-                }
+        let target = if forward {
+            lines.iter().find(|&&l| l > cursor).or(lines.first())
+        } else {
+            lines.iter().rev().find(|&&l| l < cursor).or(lines.last())
+        };
+        match target.copied() {
+            Some(line) => {
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::NearestEdge);
             }
-
-            pane.view.cursor_line = target_line;
+            None => self.set_success_message("No misspellings"),
         }
-        self.update_selection();
     }
 
-    /// Walk the rope from `start_line` by approximately `visual_lines`
-    /// rendered rows and return the number of source lines spanned.
-    ///
-    /// Shared wrapping heuristic used by both keyboard half-page scroll
-    /// (relative to cursor) and mouse-wheel scroll (relative to viewport top),
-    /// so the two input paths cover comparable visual distances on wrapped
-    /// content.
-    /// Single jump primitive used by all "move cursor + adjust scroll"
-    /// call sites (G/gg, TOC jump, search next/prev, jump-to-line).
-    ///
-    /// * `pane` — which pane to move. Must exist; no-op otherwise.
-    /// * `target` — target source line. Clamped to the rendered range.
-    /// * `policy` — how the viewport should be positioned around the
-    ///   new cursor. See `scroll_math::ScrollPolicy`.
-    ///
-    /// Also refreshes the TOC auto-selection (via `sync_toc_to_scroll`).
-    pub fn goto(
-        &mut self,
-        pane: crate::panes::PaneId,
-        target: usize,
-        policy: crate::scroll_math::ScrollPolicy,
-    ) {
-        let (bounds_lo, bounds_hi) = self.rendered_content_bounds();
-        let line_count = self.doc.line_count();
-        let visible_height = self
-            .layout_context
-            .focused_viewport(pane)
-            .map(|v| v.visible_height)
-            .unwrap_or(layout_const::DEFAULT_FALLBACK_HEIGHT)
-            .max(1);
+    /// Move the cursor to the start of the next (or previous) diff hunk,
+    /// wrapping around the document
+    #[cfg(feature = "git")]
+    pub fn jump_to_hunk(&mut self, forward: bool) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let starts: Vec<usize> = self.doc.diff_gutter.hunks().iter().map(|h| h.start).collect();
 
-        let clamped_target = crate::scroll_math::clamp_cursor(target, bounds_lo, bounds_hi);
+        let target = if forward {
+            starts.iter().find(|&&l| l > cursor).or(starts.first())
+        } else {
+            starts.iter().rev().find(|&&l| l < cursor).or(starts.last())
+        };
+        match target.copied() {
+            Some(line) => {
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::NearestEdge);
+            }
+            None => self.set_success_message("No changes"),
+        }
+    }
 
-        // Expand any collapsed blocks containing the target so the cursor
-        // lands on a visible line. Mirrors jump_to_line's expansion pass.
-        if let Some(p) = self.panes.panes.get_mut(&pane) {
-            loop {
-                let collapsed_ranges = crate::collapse::compute_all_collapsed_ranges(
-                    &p.view.collapsed_headings,
-                    &self.doc,
-                );
-                let containing = collapsed_ranges
-                    .iter()
-                    .find(|r| r.contains_line(clamped_target) || r.start == clamped_target);
-                if let Some(range) = containing {
-                    p.view.collapsed_headings.remove(&range.start);
-                } else {
-                    break;
-                }
+    /// Move the cursor to the next (or previous) merge conflict's
+    /// `<<<<<<<` line, wrapping around the document
+    pub fn jump_to_conflict(&mut self, forward: bool) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let starts: Vec<usize> = self.doc.conflicts.iter().map(|c| c.start).collect();
+
+        let target = if forward {
+            starts.iter().position(|&l| l > cursor).or((!starts.is_empty()).then_some(0))
+        } else {
+            starts.iter().rposition(|&l| l < cursor).or(starts.len().checked_sub(1))
+        };
+        match target {
+            Some(index) => {
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, starts[index], crate::scroll_math::ScrollPolicy::NearestEdge);
+                self.set_info_message(format!("Conflict {}/{}", index + 1, starts.len()));
             }
+            None => self.set_success_message("No conflicts"),
         }
+    }
 
-        let Some(p) = self.panes.panes.get_mut(&pane) else { return };
-        let prev_cursor = p.view.cursor_line;
-        let prev_scroll = p.view.scroll_line();
-        p.view.cursor_line = clamped_target;
-        let new_scroll = crate::scroll_math::scroll_for_policy(
-            clamped_target,
-            prev_cursor,
-            prev_scroll,
-            visible_height,
-            bounds_lo,
-            bounds_hi,
-            line_count,
-            policy,
-        );
-        p.view.set_scroll_line(new_scroll);
+    /// The hunk under the cursor as (1-based index, hunk count), or
+    /// `(0, count)` when the cursor is outside every hunk
+    #[cfg(feature = "git")]
+    pub fn hunk_position(&self) -> Option<(usize, usize)> {
+        if !self.config.git.diff {
+            return None;
+        }
+        let cursor = self.panes.focused_pane()?.view.cursor_line;
+        let hunks = self.doc.diff_gutter.hunks();
+        if hunks.is_empty() {
+            return None;
+        }
+        let current = hunks.iter().position(|h| h.contains(&cursor)).map_or(0, |i| i + 1);
+        Some((current, hunks.len()))
+    }
 
-        self.enforce_rendered_bounds();
-        self.update_selection();
-        self.sync_toc_to_scroll();
+    /// Misspelled words on the cursor line with up to five suggestions each
+    pub fn spell_suggestions_at_cursor(&self) -> Vec<(String, Vec<String>)> {
+        let (Some(pane), Some(dict)) = (self.panes.focused_pane(), &self.spell_dictionary) else {
+            return Vec::new();
+        };
+        self.misspellings_on_line(pane.view.cursor_line)
+            .map(|w| (w.word.clone(), dict.suggest(&w.word, 5)))
+            .collect()
+    }
 
-        trace!(
-            target: "mdx::scroll",
-            "goto: pane={:?} policy={:?} prev_cursor={} prev_scroll={} -> cursor={} scroll={}",
-            pane, policy, prev_cursor, prev_scroll, clamped_target, new_scroll
-        );
+    /// Toggle the spelling suggestions popup for the cursor line
+    pub fn toggle_spell_popup(&mut self) {
+        if self.show_spell_popup {
+            self.show_spell_popup = false;
+        } else if !self.config.spell.enabled {
+            self.set_error_message("Spell check is off; enable it in options (O)");
+        } else if self.spell_suggestions_at_cursor().is_empty() {
+            self.set_success_message("No misspellings on this line");
+        } else {
+            self.show_spell_popup = true;
+        }
     }
 
-    pub fn visual_delta_to_source_lines(
-        &mut self,
-        start_line: usize,
-        visual_lines: usize,
-        content_width: usize,
-        forward: bool,
-    ) -> usize {
-        if visual_lines == 0 {
-            return 0;
+    /// Show what the footnote reference or link on the cursor line points
+    /// at (`K`) without jumping there
+    pub fn preview_at_cursor(&mut self) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let flavor = self.config.render.markdown.flavor;
+        match crate::preview::Preview::at_line(&self.doc, cursor, flavor) {
+            Ok(Some(preview)) => self.preview = Some(preview),
+            Ok(None) => self.set_info_message("No link or footnote on this line"),
+            Err(e) => self.set_error_message(format!("{:#}", e)),
         }
-        let line_count = self.doc.line_count();
-        if line_count == 0 {
-            return 0;
+    }
+
+    /// Toggle focus mode
+    pub fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+    }
+
+    /// Toggle between hiding HTML comments and showing them dimmed
+    /// (`render.show_comments`)
+    pub fn toggle_comments(&mut self) {
+        self.config.render.show_comments = !self.config.render.show_comments;
+        // Render settings are not part of the styled line cache key
+        self.styled_line_cache.clear();
+        if self.config.render.show_comments {
+            self.set_info_message("Showing HTML comments");
+        } else {
+            self.set_info_message("Hiding HTML comments");
         }
-        if content_width == 0 {
-            return visual_lines;
+        self.adjust_cursor_for_collapsed_blocks(true);
+    }
+
+    /// Toggle showing deleted base lines as virtual lines
+    #[cfg(feature = "git")]
+    pub fn toggle_deleted_lines(&mut self) {
+        self.show_deleted_lines = !self.show_deleted_lines;
+        if self.show_deleted_lines {
+            self.set_info_message("Showing deleted lines");
+        } else {
+            self.set_info_message("Hiding deleted lines");
         }
-        let gen = self.layout_context.generation();
-        self.line_layout_cache
-            .ensure_for(content_width, self.doc.rev, gen, &self.doc.rope);
-        self.line_layout_cache
-            .advance_visual(start_line, visual_lines, forward)
     }
 
-    /// Calculate how many source lines to move for a given visual line count
-    /// This accounts for line wrapping by estimating wrapped lines
-    fn calculate_source_lines_for_visual_lines(
-        &self,
-        visual_lines: usize,
-        viewport_width: usize,
-        forward: bool,
-    ) -> usize {
-        if let Some(pane) = self.panes.focused_pane() {
-            let start_line = if forward {
-                pane.view.cursor_line
-            } else {
-                pane.view.cursor_line.saturating_sub(visual_lines)
-            };
+    /// Toggle between image placeholders and alt text only
+    #[cfg(feature = "images")]
+    pub fn toggle_image_alt_text(&mut self) {
+        self.show_image_alt_text = !self.show_image_alt_text;
+        if self.show_image_alt_text {
+            self.set_info_message("Showing image alt text only");
+        } else {
+            self.set_info_message("Showing images");
+        }
+    }
 
-            // Use actual content width from layout context if available
-            // This accounts for split panes having smaller widths
-            let content_width = self
-                .focused_viewport()
-                .map(|v| v.content_width)
-                .filter(|&w| w > 0)
-                .unwrap_or_else(|| viewport_width.saturating_sub(10)); // Fallback estimate
+    /// Source lines of the section containing `line`: from the nearest
+    /// heading at or above it up to the next heading of any level
+    pub fn section_range_at(&self, line: usize) -> std::ops::Range<usize> {
+        let headings = &self.doc.headings;
+        let next = headings.partition_point(|h| h.line <= line);
+        let start = next.checked_sub(1).map_or(0, |idx| headings[idx].line);
+        let end = headings.get(next).map_or(self.doc.line_count(), |h| h.line);
+        start..end
+    }
 
-            if content_width == 0 {
-                // Degenerate viewport — avoid div-by-zero below
-                return visual_lines.max(1);
+    /// Whether the status bar has transient content (a message, a pending
+    /// key prefix, or search input) that focus mode should still show
+    pub fn status_bar_needed(&self) -> bool {
+        self.status_message.is_some()
+            || self.key_prefix != KeyPrefix::None
+            || self
+                .panes
+                .focused_pane()
+                .is_some_and(|p| p.view.mode != Mode::Normal)
+    }
+
+    /// Present the document as slides, starting at the cursor's slide
+    pub fn start_presentation(&mut self) {
+        let slides = mdx_core::slides::split_slides(&self.doc.rope, &self.doc.headings);
+        let cursor = self
+            .panes
+            .focused_pane()
+            .map_or(0, |p| p.view.cursor_line);
+        self.presentation_slide = mdx_core::slides::slide_at_line(&slides, cursor);
+        self.presentation = Some(slides);
+    }
+
+    /// Leave presentation mode with the cursor on the current slide, or
+    /// quit when started with `mdx present`
+    pub fn stop_presentation(&mut self) {
+        let Some(slides) = self.presentation.take() else {
+            return;
+        };
+        if self.quit_after_presentation {
+            self.quit();
+            return;
+        }
+        if let Some(slide) = slides.get(self.presentation_slide) {
+            let pane_id = self.panes.focused;
+            self.goto(pane_id, slide.start, crate::scroll_math::ScrollPolicy::TopQuarter);
+        }
+    }
+
+    /// Line range of the slide being shown
+    pub fn current_slide(&self) -> Option<std::ops::Range<usize>> {
+        self.presentation
+            .as_ref()
+            .and_then(|slides| slides.get(self.presentation_slide))
+            .cloned()
+    }
+
+    /// Show a slide by index, clamped to the last slide
+    pub fn goto_slide(&mut self, index: usize) {
+        if let Some(slides) = &self.presentation {
+            self.presentation_slide = index.min(slides.len().saturating_sub(1));
+        }
+    }
+
+    pub fn next_slide(&mut self) {
+        self.goto_slide(self.presentation_slide.saturating_add(1));
+    }
+
+    pub fn prev_slide(&mut self) {
+        self.goto_slide(self.presentation_slide.saturating_sub(1));
+    }
+
+    /// Handle quit request
+    pub fn quit(&mut self) {
+        self.should_quit = true;
+    }
+
+    /// Quit, first asking whether to save if any open document has
+    /// unsaved edits
+    pub fn confirm_quit(&mut self) {
+        let names: Vec<String> = self
+            .buffer_docs()
+            .filter(|doc| doc.modified)
+            .map(|doc| self.buffer_name(doc))
+            .collect();
+        let question = match names.as_slice() {
+            [] => return self.quit(),
+            [name] => format!("Save changes to {} before quitting?", name),
+            names => format!("Save changes to {} files before quitting?", names.len()),
+        };
+        self.ask(question, ConfirmAction::Quit);
+    }
+
+    /// Reload the document from disk, first asking if that would throw
+    /// away unsaved edits
+    pub fn confirm_reload(&mut self) {
+        if self.doc.modified {
+            let question = format!(
+                "Discard unsaved edits to {} and reload?",
+                self.buffer_name(&self.doc)
+            );
+            self.ask(question, ConfirmAction::Reload);
+        } else if let Err(e) = self.reload_document() {
+            self.set_error_message(format!("Failed to reload document: {}", e));
+        }
+    }
+
+    /// Put a question in the status bar; [`App::answer_confirm`] acts on it
+    pub fn ask(&mut self, question: impl Into<String>, action: ConfirmAction) {
+        self.confirm = Some(ConfirmPrompt {
+            question: question.into(),
+            action,
+        });
+    }
+
+    /// Act on the answer to the pending question, if there is one
+    pub fn answer_confirm(&mut self, answer: ConfirmAnswer) {
+        let Some(prompt) = self.confirm.take() else {
+            return;
+        };
+        match (prompt.action, answer) {
+            (_, ConfirmAnswer::Cancel) => {}
+            (ConfirmAction::Quit, ConfirmAnswer::Yes) => {
+                if self.save_all_documents() {
+                    self.quit();
+                }
+            }
+            (ConfirmAction::Quit, ConfirmAnswer::No) => self.quit(),
+            (ConfirmAction::Reload, ConfirmAnswer::Yes) => {
+                if let Err(e) = self.reload_document() {
+                    self.set_error_message(format!("Failed to reload document: {}", e));
+                }
             }
+            (ConfirmAction::RunBlock(line), ConfirmAnswer::Yes) => self.run_block(line),
+            (_, ConfirmAnswer::No) => {}
+        }
+    }
 
-            if content_width < layout_const::MIN_WRAP_AWARE_WIDTH {
-                // Very narrow viewport, fallback to 1:1 mapping
-                return visual_lines;
+    /// Save every open document with unsaved edits; false if one failed
+    fn save_all_documents(&mut self) -> bool {
+        if self.doc.modified && !self.save_document() {
+            return false;
+        }
+        for buffer in self.buffers.iter_mut().flatten() {
+            if buffer.doc.modified {
+                if let Err(e) = buffer.doc.save() {
+                    self.status_message =
+                        Some((format!("Save failed: {:#}", e), StatusMessageKind::Error));
+                    return false;
+                }
             }
+        }
+        true
+    }
 
-            let mut visual_count = 0;
-            let mut source_count = 0;
-            let line_count = self.doc.line_count();
+    /// Reload the shown document from disk. Panes other than the focused
+    /// one stay on the text they showed. A failure brings up the error screen.
+    pub fn reload_document(&mut self) -> anyhow::Result<()> {
+        let before = self.doc.rope.clone();
+        if let Err(e) = self.doc.reload() {
+            self.load_error = Some(LoadError::new(&self.doc.path, &e));
+            return Err(e);
+        }
+        self.load_error = None;
 
-            while visual_count < visual_lines && start_line + source_count < line_count {
-                let line_idx = if forward {
-                    start_line + source_count
-                } else {
-                    start_line.saturating_sub(source_count).min(start_line)
-                };
+        // Other panes keep showing the same text, wherever it moved to
+        let focused = self.panes.focused;
+        for (&id, pane) in self.panes.panes.iter_mut() {
+            if id == focused {
+                continue;
+            }
+            let follow = |line| crate::editor::follow_line(&before, &self.doc.rope, line);
+            pane.view.set_scroll_line(follow(pane.view.scroll_line()));
+            pane.view.cursor_line = follow(pane.view.cursor_line);
+        }
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        if self.presentation.is_some() {
+            self.presentation = Some(mdx_core::slides::split_slides(
+                &self.doc.rope,
+                &self.doc.headings,
+            ));
+            self.goto_slide(self.presentation_slide);
+        }
+
+        // Re-validate viewport positions after reload
+        // Use a reasonable default height; actual height from layout context
+        // will be used inside auto_scroll if available
+        let default_height = 20;
+        self.auto_scroll(default_height);
+
+        self.request_diff();
+
+        Ok(())
+    }
+
+    /// Try the document on the error screen again (`r`). A stand-in for a
+    /// file that never loaded is opened afresh; otherwise it is reloaded
+    /// in place, keeping the view.
+    pub fn retry_load(&mut self) {
+        let result = if self.doc.loaded_mtime.is_some() {
+            self.reload_document()
+        } else {
+            let path = self.doc.path.clone();
+            self.open_document(&path)
+        };
+        match result {
+            Ok(()) => self.set_success_message(format!("Loaded {}", self.doc.path.display())),
+            Err(e) => {
+                self.load_error = Some(LoadError::new(&self.doc.path, &e));
+                self.set_error_message("Still failing");
+            }
+        }
+    }
+
+    /// Replace the shown document with the file at `path`, in a fresh view.
+    /// On failure nothing changes.
+    pub fn open_document(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let max_bytes = self.config.document.max_file_bytes;
+        let (doc, _warnings) = Document::load_with_limit(path, max_bytes)
+            .with_context(|| format!("Failed to load document: {}", path.display()))?;
+
+        self.doc = doc;
+        self.doc_id = self.next_doc_id;
+        self.next_doc_id += 1;
+        self.panes = crate::panes::PaneManager::new(self.doc_id);
+        self.toc_selected = 0;
+        self.toc_scroll = 0;
+        self.jump_stack.clear();
+        self.jump_cursor = 0;
+        self.load_error = None;
+        #[cfg(feature = "watch")]
+        {
+            self.watcher = if self.config.watch.enabled {
+                crate::watcher::FileWatcher::new(&self.doc.path).ok()
+            } else {
+                None
+            };
+        }
+
+        self.styled_line_cache.clear();
+        self.commonmark_lines.clear();
+        self.line_layout_cache = crate::line_layout::LineLayoutCache::new();
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        self.collect_search_matches();
+        self.request_diff();
+        Ok(())
+    }
+
+    /// Request diff computation in background: against git with
+    /// `git.diff`, and against the saved text while there are in-app edits
+    fn request_diff(&self) {
+        #[cfg(feature = "git")]
+        let git = self.config.git.diff && self.doc.revision.is_none();
+        if git || self.doc.modified {
+            let current_text: String = self.doc.rope.chunks().collect();
+            let saved_text = self.doc.modified.then(|| self.doc.saved_rope.to_string());
+            self.diff_worker
+                .request_diff(crate::diff_worker::DiffRequest {
+                    doc_id: self.doc_id,
+                    path: self.doc.path.clone(),
+                    rev: self.doc.rev,
+                    current_text,
+                    git,
+                    saved_text,
+                });
+        }
+    }
+
+    /// Reload after the external editor exits, if the file changed, and put
+    /// the cursor on the first edited line. The scroll position is kept
+    /// unless that line is off screen.
+    pub fn reload_after_editor(&mut self, before: &ropey::Rope) {
+        if self.doc.modified {
+            // The editor was never launched over unsaved in-app edits
+            return;
+        }
+        let modified = std::fs::metadata(&self.doc.path)
+            .and_then(|m| m.modified())
+            .ok();
+        if modified.is_some() && modified == self.doc.loaded_mtime {
+            self.set_info_message("No changes");
+            return;
+        }
+        if let Err(e) = self.reload_document() {
+            self.set_error_message(format!("Failed to reload document: {}", e));
+            return;
+        }
+        match crate::editor::first_changed_line(before, &self.doc.rope) {
+            Some(line) => {
+                self.jump_to_line(line);
+                self.auto_scroll(20);
+                self.set_info_message(format!("Reloaded, changed from line {}", line + 1));
+            }
+            None => self.set_info_message("No changes"),
+        }
+    }
+
+    /// Follow a link on the cursor line to its heading (Enter / `gf`),
+    /// recording a jump so Ctrl+O comes back. `#anchor` links are
+    /// preferred when the line has several links; `[[wiki links]]` to
+    /// another page open it.
+    pub fn follow_link_at_cursor(&mut self) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let flavor = self.config.render.markdown.flavor;
+        let links: Vec<_> = mdx_core::links::extract_links(&self.doc.rope, flavor)
+            .into_iter()
+            .filter(|link| link.line == cursor && !link.is_image)
+            .collect();
+        let link = links
+            .iter()
+            .find(|link| link.dest.starts_with('#'))
+            .or(links.first());
+        // URLs and paths written as plain text, as underlined on screen
+        let Some((dest, wiki)) = link
+            .map(|link| (link.dest.clone(), link.wiki))
+            .or_else(|| self.autolink_on_line(cursor).map(|dest| (dest, false)))
+        else {
+            self.set_info_message("No link on this line");
+            return;
+        };
+        if wiki {
+            self.follow_wiki_link(&dest);
+            return;
+        }
+        if mdx_core::links::is_remote(&dest) {
+            self.open_url(&dest);
+            return;
+        }
+        let Some(fragment) = dest.strip_prefix('#') else {
+            self.follow_file_link(&dest);
+            return;
+        };
+        match mdx_core::toc::find_anchor(&self.doc.headings, fragment) {
+            Some(heading) => {
+                let line = heading.line;
+                self.push_jump();
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::TopQuarter);
+            }
+            None => self.set_error_message(format!("No heading with anchor '#{}'", fragment)),
+        }
+    }
+
+    /// The first URL or file path written as plain text on a line
+    fn autolink_on_line(&self, line: usize) -> Option<String> {
+        let text = self.doc.rope.get_line(line)?.to_string();
+        mdx_core::render::inline(
+            text.trim_end_matches(['\n', '\r']),
+            mdx_core::render::Role::Text,
+            &self.config.render,
+            &self.doc.link_definitions,
+        )
+        .into_iter()
+        .find(|span| span.style.autolink)
+        .and_then(|span| span.link)
+    }
+
+    /// Open a web link in the browser, which runs an external command
+    fn open_url(&mut self, url: &str) {
+        if self.config.security.no_exec || self.config.security.safe_mode {
+            let setting = if self.config.security.safe_mode {
+                "security.safe_mode"
+            } else {
+                "security.no_exec"
+            };
+            self.set_error_message(format!("Not opening {} ({} = true)", url, setting));
+            return;
+        }
+        match crate::editor::open_url(url) {
+            Ok(()) => self.set_info_message(format!("Opened {}", url)),
+            Err(e) => self.set_error_message(format!("{:#}", e)),
+        }
+    }
+
+    /// Open the file a relative link or plain-text path points at as a
+    /// buffer, at its `#heading` or `:line` if it has one
+    fn follow_file_link(&mut self, dest: &str) {
+        let base_dir = self.doc.path.parent().unwrap_or(std::path::Path::new("."));
+        let Some(target) = mdx_core::links::resolve_local_target(dest, base_dir) else {
+            self.set_info_message(format!("Not a link to a file: {}", dest));
+            return;
+        };
+        if !target.path.is_file() {
+            self.set_error_message(format!("No file at {}", target.path.display()));
+            return;
+        }
+        if let Err(e) = self.open_or_switch(&target.path) {
+            self.set_error_message(format!("{:#}", e));
+            return;
+        }
+        let line = match (&target.fragment, target.line) {
+            (_, Some(line)) => Some(line.saturating_sub(1)),
+            (Some(fragment), None) => {
+                mdx_core::toc::find_anchor(&self.doc.headings, fragment).map(|h| h.line)
+            }
+            (None, None) => None,
+        };
+        if let Some(line) = line {
+            let pane_id = self.panes.focused;
+            self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::TopQuarter);
+        }
+    }
+
+    /// Show the page and heading of a `[[page#heading]]` link, opening the
+    /// page as a buffer
+    fn follow_wiki_link(&mut self, dest: &str) {
+        let (page, heading) = dest.split_once('#').unwrap_or((dest, ""));
+        if !page.is_empty() {
+            let base_dir = self.doc.path.parent().unwrap_or(std::path::Path::new("."));
+            let vault_root = self.config.links.vault_root.as_deref();
+            let Some(path) = mdx_core::links::resolve_wiki_link(page, base_dir, vault_root) else {
+                self.set_error_message(format!("No page named '{}'", page));
+                return;
+            };
+            if let Err(e) = self.open_or_switch(&path) {
+                self.set_error_message(format!("{:#}", e));
+                return;
+            }
+        }
+        if heading.is_empty() {
+            return;
+        }
+        match mdx_core::links::find_wiki_heading(&self.doc.headings, heading) {
+            Some(heading) => {
+                let line = heading.line;
+                if page.is_empty() {
+                    self.push_jump();
+                }
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::TopQuarter);
+            }
+            None => self.set_error_message(format!("No heading '{}'", heading)),
+        }
+    }
+
+    /// Open at a line or heading from the command line, with the target
+    /// a few lines below the top of the pane
+    pub fn open_at(&mut self, location: &crate::location::Location) {
+        use crate::location::Location;
+
+        let line = match location {
+            Location::Line(line) => line.saturating_sub(1),
+            Location::Anchor(anchor) => {
+                match mdx_core::toc::find_anchor(&self.doc.headings, anchor) {
+                    Some(heading) => heading.line,
+                    None => {
+                        self.set_error_message(format!("No heading with anchor '#{}'", anchor));
+                        return;
+                    }
+                }
+            }
+        };
+        self.jump_to_line(line);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let cursor = pane.view.cursor_line;
+            pane.view.set_scroll_line(cursor.saturating_sub(OPEN_AT_CONTEXT_LINES));
+        }
+        self.enforce_rendered_bounds();
+    }
+
+    /// Move cursor down by n lines, skipping collapsed blocks
+    pub fn move_cursor_down(&mut self, n: usize) {
+        let bounds = self.rendered_content_bounds();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let next_line = pane.view.cursor_line.saturating_add(n);
+            pane.view.cursor_line = next_line.clamp(bounds.0, bounds.1);
+
+            // Debug assertion: cursor should always be within bounds
+            debug_assert!(
+                pane.view.cursor_line >= bounds.0 && pane.view.cursor_line <= bounds.1,
+                "Cursor line {} out of bounds [{}, {}]",
+                pane.view.cursor_line,
+                bounds.0,
+                bounds.1
+            );
+        }
+        self.adjust_cursor_for_collapsed_blocks(true);
+        self.update_selection();
+    }
+
+    /// Move cursor up by n lines, skipping collapsed blocks
+    pub fn move_cursor_up(&mut self, n: usize) {
+        let bounds = self.rendered_content_bounds();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let prev_line = pane.view.cursor_line.saturating_sub(n);
+            pane.view.cursor_line = prev_line.clamp(bounds.0, bounds.1);
+
+            // Debug assertion: cursor should always be within bounds
+            debug_assert!(
+                pane.view.cursor_line >= bounds.0 && pane.view.cursor_line <= bounds.1,
+                "Cursor line {} out of bounds [{}, {}]",
+                pane.view.cursor_line,
+                bounds.0,
+                bounds.1
+            );
+        }
+        self.adjust_cursor_for_collapsed_blocks(false);
+        self.update_selection();
+    }
+
+    /// Adjust cursor position if it lands inside a collapsed block or a
+    /// hidden comment
+    /// moving_down: if true, cursor lands on the line after the collapsed block; if false, on the heading
+    fn adjust_cursor_for_collapsed_blocks(&mut self, moving_down: bool) {
+        let bounds = self.rendered_content_bounds();
+        self.skip_hidden_comments(moving_down, bounds);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let cursor = pane.view.cursor_line;
+
+            // Compute collapsed ranges
+            let collapsed_ranges = pane.view.collapsed_ranges(&self.doc);
+
+            // Check if cursor is inside a collapsed range (but not at the start)
+            if let Some(range) =
+                crate::collapse::find_range_containing_line(&collapsed_ranges, cursor)
+            {
+                if moving_down {
+                    // When moving down, jump to the line after the collapsed block
+                    let target_line = range.end.saturating_add(1);
+                    pane.view.cursor_line = target_line.clamp(bounds.0, bounds.1);
+                } else {
+                    // When moving up, jump to the heading line
+                    pane.view.cursor_line = range.start.clamp(bounds.0, bounds.1);
+                }
+            }
+        }
+    }
+
+    /// Move the cursor off hidden comment lines, past them in the
+    /// direction of travel, or back the other way at the document's edge
+    fn skip_hidden_comments(&mut self, moving_down: bool, bounds: (usize, usize)) {
+        if self.config.render.show_comments {
+            return;
+        }
+        let comments = &self.doc.comments;
+        let Some(pane) = self.panes.focused_pane_mut() else { return };
+        // Raw and insert views show every line
+        if pane.view.show_raw || pane.view.mode == Mode::Insert {
+            return;
+        }
+        let hidden = |line: usize| comments.iter().find(|range| range.contains(&line));
+        let mut down = moving_down;
+        for _ in 0..=comments.len() * 2 {
+            let Some(range) = hidden(pane.view.cursor_line) else { return };
+            if down && range.end > bounds.1 {
+                down = false;
+            }
+            if !down && range.start <= bounds.0 {
+                if range.end > bounds.1 {
+                    // Nothing but comments to show
+                    return;
+                }
+                down = true;
+            }
+            pane.view.cursor_line = if down { range.end } else { range.start - 1 };
+        }
+    }
+
+    /// Jump to specific line, expanding collapsed blocks if necessary
+    pub fn jump_to_line(&mut self, line: usize) {
+        let bounds = self.rendered_content_bounds();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let target_line = line.clamp(bounds.0, bounds.1);
+
+            // Expand ALL collapsed blocks that contain the target line
+            // This handles nested collapsed headings (e.g., "## Parent" contains "### Child")
+            // We need multiple passes because removing one heading may reveal others
+            loop {
+                let collapsed_ranges = pane.view.collapsed_ranges(&self.doc);
+
+                // Find any collapsed range containing the target
+                let containing_range = collapsed_ranges
+                    .iter()
+                    .find(|r| r.contains_line(target_line) || r.start == target_line);
+
+                if let Some(range) = containing_range {
+                    // Expand this collapsed heading or details block
+                    pane.view.expand_range(range);
+                } else {
+                    // No more collapsed ranges containing target
+                    break;
+                }
+            }
+
+            pane.view.cursor_line = target_line;
+        }
+        self.update_selection();
+    }
+
+    /// Walk the rope from `start_line` by approximately `visual_lines`
+    /// rendered rows and return the number of source lines spanned.
+    ///
+    /// Shared wrapping heuristic used by both keyboard half-page scroll
+    /// (relative to cursor) and mouse-wheel scroll (relative to viewport top),
+    /// so the two input paths cover comparable visual distances on wrapped
+    /// content.
+    /// Single jump primitive used by all "move cursor + adjust scroll"
+    /// call sites (G/gg, TOC jump, search next/prev, jump-to-line).
+    ///
+    /// * `pane` — which pane to move. Must exist; no-op otherwise.
+    /// * `target` — target source line. Clamped to the rendered range.
+    /// * `policy` — how the viewport should be positioned around the
+    ///   new cursor. See `scroll_math::ScrollPolicy`.
+    ///
+    /// Also refreshes the TOC auto-selection (via `sync_toc_to_scroll`).
+    pub fn goto(
+        &mut self,
+        pane: crate::panes::PaneId,
+        target: usize,
+        policy: crate::scroll_math::ScrollPolicy,
+    ) {
+        let (bounds_lo, bounds_hi) = self.rendered_content_bounds();
+        let line_count = self.doc.line_count();
+        let visible_height = self
+            .layout_context
+            .focused_viewport(pane)
+            .map(|v| v.visible_height)
+            .unwrap_or(layout_const::DEFAULT_FALLBACK_HEIGHT)
+            .max(1);
+
+        let clamped_target = crate::scroll_math::clamp_cursor(target, bounds_lo, bounds_hi);
+
+        // Expand any collapsed blocks containing the target so the cursor
+        // lands on a visible line. Mirrors jump_to_line's expansion pass.
+        if let Some(p) = self.panes.panes.get_mut(&pane) {
+            loop {
+                let collapsed_ranges = p.view.collapsed_ranges(&self.doc);
+                let containing = collapsed_ranges
+                    .iter()
+                    .find(|r| r.contains_line(clamped_target) || r.start == clamped_target);
+                if let Some(range) = containing {
+                    p.view.expand_range(range);
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let Some(p) = self.panes.panes.get_mut(&pane) else { return };
+        let prev_cursor = p.view.cursor_line;
+        let prev_scroll = p.view.scroll_line();
+        p.view.cursor_line = clamped_target;
+        let new_scroll = crate::scroll_math::scroll_for_policy(
+            clamped_target,
+            prev_cursor,
+            prev_scroll,
+            visible_height,
+            bounds_lo,
+            bounds_hi,
+            line_count,
+            policy,
+        );
+        p.view.set_scroll_line(new_scroll);
+
+        self.enforce_rendered_bounds();
+        self.update_selection();
+        self.sync_toc_to_scroll();
+
+        trace!(
+            target: "mdx::scroll",
+            "goto: pane={:?} policy={:?} prev_cursor={} prev_scroll={} -> cursor={} scroll={}",
+            pane, policy, prev_cursor, prev_scroll, clamped_target, new_scroll
+        );
+    }
+
+    /// Move the cursor to `target` and center it by rendered rows rather
+    /// than source lines, so a line below long wrapped paragraphs still
+    /// lands mid-screen. Used when an editor drives the scroll position.
+    pub fn goto_centered(&mut self, pane: crate::panes::PaneId, target: usize) {
+        let Some(viewport) = self.layout_context.focused_viewport(pane) else {
+            self.goto(pane, target, crate::scroll_math::ScrollPolicy::Center);
+            return;
+        };
+        self.goto(pane, target, crate::scroll_math::ScrollPolicy::NearestEdge);
+
+        let (bounds_lo, _) = self.rendered_content_bounds();
+        let gen = self.layout_context.generation();
+        self.line_layout_cache
+            .ensure_for(viewport.content_width, self.doc.rev, gen, &self.doc.rope);
+        let cache = &self.line_layout_cache;
+        let Some(p) = self.panes.panes.get_mut(&pane) else { return };
+        let folded = p.view.collapsed_ranges(&self.doc);
+        let height_of = |line: usize| {
+            if folded.iter().any(|r| r.contains_line(line)) {
+                0
+            } else {
+                cache.visual_height_of_line(line) as usize
+            }
+        };
+
+        // Walk up from the cursor until half the viewport is filled; the
+        // line that overflows it is shown from a wrapped row partway in
+        let half = viewport.visible_height / 2;
+        let mut top = crate::scroll_math::VisualPos::at(p.view.cursor_line);
+        let mut rows = 0;
+        while rows < half && top.source_line > bounds_lo {
+            let line = top.source_line - 1;
+            let height = height_of(line);
+            top = crate::scroll_math::VisualPos::at(line);
+            if rows + height > half {
+                top.wrap_row = (rows + height - half) as u16;
+                break;
+            }
+            rows += height;
+        }
+        p.view.scroll_pos = top;
+        self.sync_toc_to_scroll();
+    }
+
+    pub fn visual_delta_to_source_lines(
+        &mut self,
+        start_line: usize,
+        visual_lines: usize,
+        content_width: usize,
+        forward: bool,
+    ) -> usize {
+        if visual_lines == 0 {
+            return 0;
+        }
+        let line_count = self.doc.line_count();
+        if line_count == 0 {
+            return 0;
+        }
+        if content_width == 0 {
+            return visual_lines;
+        }
+        let gen = self.layout_context.generation();
+        self.line_layout_cache
+            .ensure_for(content_width, self.doc.rev, gen, &self.doc.rope);
+        self.line_layout_cache
+            .advance_visual(start_line, visual_lines, forward)
+    }
+
+    /// Calculate how many source lines to move for a given visual line count
+    /// This accounts for line wrapping by estimating wrapped lines
+    fn calculate_source_lines_for_visual_lines(
+        &self,
+        visual_lines: usize,
+        viewport_width: usize,
+        forward: bool,
+    ) -> usize {
+        if let Some(pane) = self.panes.focused_pane() {
+            let start_line = if forward {
+                pane.view.cursor_line
+            } else {
+                pane.view.cursor_line.saturating_sub(visual_lines)
+            };
+
+            // Use actual content width from layout context if available
+            // This accounts for split panes having smaller widths
+            let content_width = self
+                .focused_viewport()
+                .map(|v| v.content_width)
+                .filter(|&w| w > 0)
+                .unwrap_or_else(|| viewport_width.saturating_sub(10)); // Fallback estimate
+
+            if content_width == 0 {
+                // Degenerate viewport — avoid div-by-zero below
+                return visual_lines.max(1);
+            }
+
+            if content_width < layout_const::MIN_WRAP_AWARE_WIDTH {
+                // Very narrow viewport, fallback to 1:1 mapping
+                return visual_lines;
+            }
+
+            let mut visual_count = 0;
+            let mut source_count = 0;
+            let line_count = self.doc.line_count();
+
+            while visual_count < visual_lines && start_line + source_count < line_count {
+                let line_idx = if forward {
+                    start_line + source_count
+                } else {
+                    start_line.saturating_sub(source_count).min(start_line)
+                };
+
+                if line_idx >= line_count {
+                    break;
+                }
+
+                // Get line length
+                let line_text: String = self.doc.rope.line(line_idx).chars().collect();
+                let line_len =
+                    unicode_width::UnicodeWidthStr::width(line_text.trim_end_matches('\n'));
+
+                // Estimate wrapped lines (simple heuristic)
+                let wrapped_lines = if line_len == 0 {
+                    1
+                } else {
+                    line_len.div_ceil(content_width).max(1)
+                };
+
+                visual_count += wrapped_lines;
+                source_count += 1;
+
+                if visual_count >= visual_lines {
+                    break;
+                }
+            }
+
+            source_count.max(1)
+        } else {
+            visual_lines
+        }
+    }
+
+    /// Scroll down by half viewport height (accounting for wrapping). The
+    /// view and the cursor move together, like vim's `Ctrl-d`, so the
+    /// cursor keeps its place on screen; at the end the cursor goes on alone.
+    pub fn scroll_half_page_down(&mut self, viewport_height: usize, viewport_width: usize) {
+        let half_page = viewport_height / 2;
+        let source_lines =
+            self.calculate_source_lines_for_visual_lines(half_page, viewport_width, true);
+        let max_scroll = self.doc.line_count().saturating_sub(viewport_height);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let scroll = pane.view.scroll_line();
+            pane.view.set_scroll_line((scroll + source_lines).min(max_scroll.max(scroll)));
+        }
+        self.move_cursor_down(source_lines);
+    }
+
+    /// Scroll up by half viewport height (accounting for wrapping), moving
+    /// the view and the cursor together like vim's `Ctrl-u`
+    pub fn scroll_half_page_up(&mut self, viewport_height: usize, viewport_width: usize) {
+        let half_page = viewport_height / 2;
+        let source_lines =
+            self.calculate_source_lines_for_visual_lines(half_page, viewport_width, false);
+        let (bounds_lo, _) = self.rendered_content_bounds();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let scroll = pane.view.scroll_line();
+            let target = scroll.saturating_sub(source_lines).max(bounds_lo);
+            pane.view.set_scroll_line(target.min(scroll));
+        }
+        self.move_cursor_up(source_lines);
+    }
+
+    /// Scroll the view `lines` source lines (`Ctrl-e`/`Ctrl-y`) without
+    /// moving the cursor, unless that would take it off screen or into
+    /// the `render.scrolloff` margin; then it moves just enough to stay.
+    pub fn scroll_view(&mut self, lines: usize, down: bool, viewport_height: usize) {
+        let height = self
+            .focused_viewport()
+            .map(|v| v.visible_height)
+            .filter(|&h| h > 0)
+            .unwrap_or(viewport_height);
+        let (bounds_lo, bounds_hi) = self.rendered_content_bounds();
+        let line_count = self.doc.line_count();
+        let margin = self.config.render.scrolloff.min(height.saturating_sub(1) / 2);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let scroll = crate::scroll_math::advance_scroll(
+                pane.view.scroll_line(),
+                lines,
+                down,
+                bounds_lo,
+                bounds_hi,
+                line_count,
+                height,
+            );
+            pane.view.set_scroll_line(scroll);
+            // No margin is needed where there is nothing beyond to show
+            let top = if scroll > bounds_lo { scroll + margin } else { scroll };
+            let bottom = scroll + height.saturating_sub(1);
+            let bottom = if bottom < bounds_hi { bottom.saturating_sub(margin) } else { bottom };
+            pane.view.cursor_line = pane
+                .view
+                .cursor_line
+                .clamp(top, bottom.max(top))
+                .clamp(bounds_lo, bounds_hi);
+        }
+        self.update_selection();
+        self.sync_toc_to_scroll();
+    }
+
+    /// Update `toc_selected` and `toc_scroll` to track the most recent
+    /// heading at or above the focused pane's scroll line.
+    ///
+    /// Skips the update once if `toc_tracking_suppress_once` is set — used
+    /// to prevent a feedback loop after the TOC itself moved the scroll.
+    pub fn sync_toc_to_scroll(&mut self) {
+        if self.toc_tracking_suppress_once {
+            self.toc_tracking_suppress_once = false;
+            return;
+        }
+        if self.doc.headings.is_empty() {
+            return;
+        }
+        let scroll_line = match self.panes.focused_pane() {
+            Some(p) => p.view.scroll_line(),
+            None => return,
+        };
+        // Last heading with line <= scroll_line; if none, use first.
+        let idx = self
+            .doc
+            .headings
+            .iter()
+            .rposition(|h| h.line <= scroll_line)
+            .unwrap_or(0);
+        if idx != self.toc_selected {
+            self.toc_selected = idx;
+            // Best-effort scroll; if TOC is not open or viewport unknown,
+            // fall back to a reasonable window height.
+            let toc_height = self
+                .focused_viewport()
+                .map(|v| v.visible_height)
+                .filter(|&h| h > 0)
+                .unwrap_or(layout_const::DEFAULT_FALLBACK_HEIGHT);
+            self.toc_auto_scroll(toc_height);
+        }
+    }
+
+    /// Capture the current focused pane's scroll/cursor as a jump-stack
+    /// entry. Called *before* a jump (TOC click, search, G, gg, goto).
+    /// Truncates any forward history at the current cursor.
+    pub fn push_jump(&mut self) {
+        if let Some(entry) = self.current_jump_entry() {
+            self.push_jump_entry(entry);
+        }
+    }
+
+    /// The focused pane's position, as a jump list entry
+    fn current_jump_entry(&self) -> Option<JumpEntry> {
+        let pane = self.panes.focused_pane()?;
+        Some(JumpEntry {
+            pane: self.panes.focused,
+            scroll_pos: pane.view.scroll_pos,
+            cursor_line: pane.view.cursor_line,
+        })
+    }
+
+    fn push_jump_entry(&mut self, entry: JumpEntry) {
+        // If we're not at the tip, drop everything past the cursor — new
+        // jumps always extend from the current position.
+        while self.jump_stack.len() > self.jump_cursor {
+            self.jump_stack.pop_back();
+        }
+        // Jumping from the same line again replaces the entry rather than
+        // stacking copies Ctrl-O would step through one by one.
+        if self
+            .jump_stack
+            .back()
+            .is_some_and(|last| last.pane == entry.pane && last.cursor_line == entry.cursor_line)
+        {
+            self.jump_stack.pop_back();
+        }
+        // Bound the stack; drop the oldest entry once at cap.
+        if self.jump_stack.len() >= JUMP_STACK_CAP {
+            self.jump_stack.pop_front();
+            self.jump_cursor = self.jump_cursor.saturating_sub(1);
+        }
+        self.jump_stack.push_back(entry);
+        self.jump_cursor = self.jump_stack.len();
+    }
+
+    /// Pop back to the previous jump position (Ctrl-O). Returns true if a
+    /// jump was applied.
+    pub fn jump_back(&mut self) -> bool {
+        if self.jump_cursor == 0 {
+            return false;
+        }
+        // If we are at the tip (jump_cursor == len()), record the current
+        // position first so Ctrl-I can return to it.
+        if self.jump_cursor == self.jump_stack.len() {
+            if let Some(pane) = self.panes.focused_pane() {
+                let entry = JumpEntry {
+                    pane: self.panes.focused,
+                    scroll_pos: pane.view.scroll_pos,
+                    cursor_line: pane.view.cursor_line,
+                };
+                if self.jump_stack.len() >= JUMP_STACK_CAP {
+                    self.jump_stack.pop_front();
+                    self.jump_cursor = self.jump_cursor.saturating_sub(1);
+                }
+                self.jump_stack.push_back(entry);
+                // jump_cursor stays where it is — we're adding the "tip"
+                // marker past the most recent real jump.
+            }
+        }
+        self.jump_cursor -= 1;
+        self.apply_jump_at_cursor();
+        true
+    }
+
+    /// Move forward in the jump stack (Ctrl-I). Returns true if a jump
+    /// was applied.
+    pub fn jump_forward(&mut self) -> bool {
+        if self.jump_cursor + 1 >= self.jump_stack.len() {
+            return false;
+        }
+        self.jump_cursor += 1;
+        self.apply_jump_at_cursor();
+        true
+    }
+
+    fn apply_jump_at_cursor(&mut self) {
+        let Some(entry) = self.jump_stack.get(self.jump_cursor).copied() else { return };
+        // Restore focus and position. If the pane was removed (after a
+        // split close) fall back to the currently focused pane.
+        if self.panes.panes.contains_key(&entry.pane) {
+            self.panes.focused = entry.pane;
+        }
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.scroll_pos = entry.scroll_pos;
+            pane.view.cursor_line = entry.cursor_line;
+        }
+        // Re-clamp in case the doc length or layout changed since the
+        // entry was recorded.
+        self.enforce_rendered_bounds();
+        // Don't let TOC tracking re-echo from the restored scroll.
+        self.toc_tracking_suppress_once = true;
+    }
+
+    /// Auto-scroll viewport to keep cursor visible
+    ///
+    /// Uses the actual pane height from layout context when available,
+    /// falling back to the provided viewport_height parameter.
+    pub fn auto_scroll(&mut self, viewport_height: usize) {
+        // Get actual pane height from layout context if available
+        let actual_height = self
+            .focused_viewport()
+            .map(|v| v.visible_height)
+            .filter(|&h| h > 0)
+            .unwrap_or(viewport_height);
+
+        // Debug assertion: height should be reasonable
+        debug_assert!(
+            actual_height > 0 && actual_height < 10000,
+            "auto_scroll: viewport height {} seems unreasonable",
+            actual_height
+        );
+
+        let margin = self.config.render.scrolloff;
+        let line_count = self.doc.line_count();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let cursor = pane.view.cursor_line;
+            let scroll = pane.view.scroll_line();
+
+            // Scroll when the cursor leaves the viewport, or comes within
+            // `render.scrolloff` lines of its edge
+            let new_scroll = crate::scroll_math::scroll_with_margin(
+                cursor,
+                scroll,
+                actual_height,
+                margin,
+                line_count,
+            );
+            if new_scroll != scroll {
+                pane.view.set_scroll_line(new_scroll);
+            }
+
+            if pane.view.scroll_line() != scroll {
+                trace!(
+                    target: "mdx::scroll",
+                    "auto_scroll: cursor={} height={} scroll {} -> {}",
+                    cursor, actual_height, scroll, pane.view.scroll_line()
+                );
+            }
+
+            // Debug assertion: after auto_scroll, cursor should be visible
+            debug_assert!(
+                pane.view.cursor_line >= pane.view.scroll_line()
+                    && pane.view.cursor_line < pane.view.scroll_line() + actual_height,
+                "auto_scroll: cursor {} not visible in viewport [{}, {})",
+                pane.view.cursor_line,
+                pane.view.scroll_line(),
+                pane.view.scroll_line() + actual_height
+            );
+        }
+        self.sync_toc_to_scroll();
+    }
+
+    /// Run a page jump, then animate the focused pane's scroll from where
+    /// it was to where the jump left it (`render.smooth_scroll`)
+    pub fn smooth_scroll(&mut self, jump: impl FnOnce(&mut Self)) {
+        self.finish_scroll_animation();
+        let pane = self.panes.focused;
+        let from = self.panes.focused_pane().map(|p| p.view.scroll_line());
+        jump(self);
+        if !self.config.render.smooth_scroll {
+            return;
+        }
+        let view = self.panes.focused_pane_mut().map(|p| &mut p.view);
+        let (Some(from), Some(view)) = (from, view) else {
+            return;
+        };
+        let to = view.scroll_pos;
+        if from.abs_diff(to.source_line) > 1 {
+            view.set_scroll_line(from);
+            self.scroll_animation = Some(ScrollAnimation {
+                pane,
+                from,
+                to,
+                started: std::time::Instant::now(),
+            });
+        }
+    }
+
+    /// Move an animated scroll on to where it should be by now
+    pub fn step_scroll_animation(&mut self) {
+        let Some(animation) = self.scroll_animation else {
+            return;
+        };
+        let t = animation.started.elapsed().as_secs_f64() / SMOOTH_SCROLL_DURATION.as_secs_f64();
+        let Some(pane) = self.panes.panes.get_mut(&animation.pane) else {
+            self.scroll_animation = None;
+            return;
+        };
+        if t >= 1.0 {
+            pane.view.scroll_pos = animation.to;
+            self.scroll_animation = None;
+        } else {
+            // Ease out: fast at first, settling onto the target
+            let eased = 1.0 - (1.0 - t) * (1.0 - t);
+            let distance = animation.to.source_line as f64 - animation.from as f64;
+            let line = animation.from as f64 + distance * eased;
+            pane.view.set_scroll_line(line.round() as usize);
+        }
+    }
+
+    /// End an animated scroll at its target, before input that expects the
+    /// view where the jump put it
+    pub fn finish_scroll_animation(&mut self) {
+        if let Some(animation) = self.scroll_animation.take() {
+            if let Some(pane) = self.panes.panes.get_mut(&animation.pane) {
+                pane.view.scroll_pos = animation.to;
+            }
+        }
+    }
+
+    /// Switch to the next built-in theme
+    pub fn toggle_theme(&mut self) {
+        self.theme_variant = self.theme_variant.next();
+        self.refresh_theme();
+    }
+
+    /// Note whether the terminal shows 24-bit color, which picks the code
+    /// block background
+    pub fn set_truecolor(&mut self, truecolor: bool) {
+        self.truecolor = truecolor;
+        self.refresh_theme();
+    }
+
+    /// Rebuild the theme from the variant, config, and color support
+    fn refresh_theme(&mut self) {
+        self.theme = Theme::for_config(self.theme_variant, &self.config.render, self.truecolor);
+        self.styled_line_cache.clear();
+    }
+
+    /// Toggle TOC visibility and focus
+    pub fn toggle_toc(&mut self) {
+        if self.show_toc {
+            // If already shown, hide it
+            self.show_toc = false;
+            self.toc_focus = false;
+        } else {
+            // Show and focus TOC
+            self.show_toc = true;
+            self.toc_focus = true;
+        }
+    }
+
+    /// Move TOC selection down
+    pub fn toc_move_down(&mut self, toc_height: usize) {
+        if !self.doc.headings.is_empty() {
+            self.toc_selected = (self.toc_selected + 1).min(self.doc.headings.len() - 1);
+            self.toc_auto_scroll(toc_height);
+        }
+    }
+
+    /// Move TOC selection up
+    pub fn toc_move_up(&mut self, toc_height: usize) {
+        self.toc_selected = self.toc_selected.saturating_sub(1);
+        self.toc_auto_scroll(toc_height);
+    }
+
+    /// Move TOC selection down by half page
+    pub fn toc_scroll_half_page_down(&mut self, toc_height: usize) {
+        if !self.doc.headings.is_empty() {
+            let jump = (toc_height / 2).max(1);
+            self.toc_selected = (self.toc_selected + jump).min(self.doc.headings.len() - 1);
+            self.toc_auto_scroll(toc_height);
+        }
+    }
+
+    /// Move TOC selection up by half page
+    pub fn toc_scroll_half_page_up(&mut self, toc_height: usize) {
+        let jump = (toc_height / 2).max(1);
+        self.toc_selected = self.toc_selected.saturating_sub(jump);
+        self.toc_auto_scroll(toc_height);
+    }
+
+    /// Move TOC selection down by full page
+    pub fn toc_scroll_full_page_down(&mut self, toc_height: usize) {
+        if !self.doc.headings.is_empty() {
+            let jump = toc_height.max(1);
+            self.toc_selected = (self.toc_selected + jump).min(self.doc.headings.len() - 1);
+            self.toc_auto_scroll(toc_height);
+        }
+    }
+
+    /// Move TOC selection up by full page
+    pub fn toc_scroll_full_page_up(&mut self, toc_height: usize) {
+        let jump = toc_height.max(1);
+        self.toc_selected = self.toc_selected.saturating_sub(jump);
+        self.toc_auto_scroll(toc_height);
+    }
+
+    /// Jump to top of TOC
+    pub fn toc_jump_to_top(&mut self, toc_height: usize) {
+        self.toc_selected = 0;
+        self.toc_auto_scroll(toc_height);
+    }
+
+    /// Jump to bottom of TOC
+    pub fn toc_jump_to_bottom(&mut self, toc_height: usize) {
+        if !self.doc.headings.is_empty() {
+            self.toc_selected = self.doc.headings.len() - 1;
+            self.toc_auto_scroll(toc_height);
+        }
+    }
+
+    /// Auto-scroll TOC to keep selection visible
+    pub fn toc_auto_scroll(&mut self, toc_height: usize) {
+        let selected = self.toc_selected;
+        let scroll = self.toc_scroll;
+
+        // Selection above viewport - scroll up
+        if selected < scroll {
+            self.toc_scroll = selected;
+        }
+        // Selection below viewport - scroll down
+        else if selected >= scroll + toc_height {
+            self.toc_scroll = selected.saturating_sub(toc_height - 1);
+        }
+    }
+
+    /// Jump to the selected heading in TOC, making it the top line
+    pub fn toc_jump_to_selected(&mut self) {
+        if let Some(heading) = self.doc.headings.get(self.toc_selected) {
+            let target_line = heading.line;
+            // Use jump_to_line to handle collapsed section expansion
+            self.jump_to_line(target_line);
+            // Set scroll to make heading the top line
+            if let Some(pane) = self.panes.focused_pane_mut() {
+                pane.view.set_scroll_line(target_line);
+            }
+            // Suppress the next TOC-tracking update; the scroll moved
+            // *because* of this TOC click, so re-selecting would echo.
+            self.toc_tracking_suppress_once = true;
+        }
+    }
+
+    /// Swap the section selected in the TOC with its sibling above or below
+    pub fn outline_move_section(&mut self, up: bool) {
+        let idx = self.toc_selected;
+        match mdx_core::outline::move_section(&self.doc.rope, &self.doc.headings, idx, up) {
+            Some((text, moved)) => {
+                let message = if up { "Moved section up" } else { "Moved section down" };
+                self.restructure(text, moved, message);
+            }
+            None => self.set_info_message(if up {
+                "No section above at this level"
+            } else {
+                "No section below at this level"
+            }),
+        }
+    }
+
+    /// Promote (`delta` -1) or demote (+1) the heading selected in the TOC
+    /// and the headings under it
+    pub fn outline_shift_level(&mut self, delta: i8) {
+        let idx = self.toc_selected;
+        if idx >= self.doc.headings.len() {
+            return;
+        }
+        match mdx_core::outline::shift_levels(&self.doc.rope, &self.doc.headings, idx, delta) {
+            Some(text) => {
+                let message = if delta < 0 { "Promoted section" } else { "Demoted section" };
+                self.restructure(text, idx, message);
+            }
+            None => self.set_info_message("Heading levels go from 1 to 6"),
+        }
+    }
+
+    /// Undo the last section move or level change, writing the file back
+    pub fn outline_undo(&mut self) {
+        let Some(edit) = self.outline_undo.pop() else {
+            self.set_info_message("Nothing to undo");
+            return;
+        };
+        // Anything changed since would be lost
+        if edit.doc_id != self.doc_id || edit.after != self.doc.rope {
+            self.outline_undo.clear();
+            self.set_error_message("The document changed since; nothing to undo");
+            return;
+        }
+        let select = self.toc_selected;
+        self.restructure(edit.before.to_string(), select, "Undid outline change");
+        // Undoing isn't itself undoable
+        self.outline_undo.pop();
+    }
+
+    /// Replace the document's text with a restructured version and write
+    /// it to disk, selecting heading `select` in the TOC. Refused over
+    /// unsaved edits, which the write would include, and over changes on
+    /// disk, which it would throw away.
+    fn restructure(&mut self, text: String, select: usize, message: &str) {
+        if self.doc.modified {
+            self.set_error_message("Unsaved changes (use :w to save them first)");
+            return;
+        }
+        if self.doc.changed_on_disk() {
+            self.set_error_message(format!(
+                "{} changed on disk (reload it first)",
+                self.buffer_name(&self.doc)
+            ));
+            return;
+        }
+        let before = self.doc.rope.clone();
+        self.doc.edit(0..before.len_chars(), &text);
+        if !self.save_document() {
+            // The save leaves the file as it was; put its text back
+            self.doc.edit(0..self.doc.rope.len_chars(), &before.to_string());
+            self.doc.modified = false;
+            return;
+        }
+        self.outline_undo.push(OutlineEdit {
+            doc_id: self.doc_id,
+            before,
+            after: self.doc.rope.clone(),
+        });
+        if self.outline_undo.len() > OUTLINE_UNDO_LIMIT {
+            self.outline_undo.remove(0);
+        }
+
+        // Folds are kept by line, which no longer match their headings
+        for pane in self.panes.panes.values_mut() {
+            pane.view.collapsed_headings.clear();
+            pane.view.selection = None;
+        }
+        self.styled_line_cache.clear();
+        self.toc_selected = select.min(self.doc.headings.len().saturating_sub(1));
+        self.toc_jump_to_selected();
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        self.collect_search_matches();
+        self.request_diff();
+        self.set_success_message(message);
+    }
+
+    /// Toggle TOC dialog
+    pub fn toggle_toc_dialog(&mut self) {
+        self.show_toc_dialog = !self.show_toc_dialog;
+        if self.show_toc_dialog {
+            // Reset selection when opening
+            self.toc_dialog_selected = 0;
+            self.toc_dialog_scroll = 0;
+            self.toc_dialog_query.clear();
+            self.toc_dialog_filtering = false;
+        }
+    }
+
+    /// Indices of the headings the TOC dialog lists: those whose text
+    /// contains the filter, ignoring case
+    pub fn toc_dialog_headings(&self) -> Vec<usize> {
+        let query = self.toc_dialog_query.to_lowercase();
+        self.doc
+            .headings
+            .iter()
+            .enumerate()
+            .filter(|(_, heading)| heading.text.to_lowercase().contains(&query))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Set the TOC dialog's filter, selecting its first heading
+    pub fn set_toc_dialog_query(&mut self, query: String) {
+        self.toc_dialog_query = query;
+        self.toc_dialog_selected = 0;
+        self.toc_dialog_scroll = 0;
+    }
+
+    /// Move TOC dialog selection down
+    pub fn toc_dialog_move_down(&mut self, dialog_height: usize) {
+        let count = self.toc_dialog_headings().len();
+        if count > 0 {
+            self.toc_dialog_selected = (self.toc_dialog_selected + 1).min(count - 1);
+            self.toc_dialog_auto_scroll(dialog_height);
+        }
+    }
+
+    /// Move TOC dialog selection up
+    pub fn toc_dialog_move_up(&mut self, dialog_height: usize) {
+        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(1);
+        self.toc_dialog_auto_scroll(dialog_height);
+    }
+
+    /// Move TOC dialog selection down by half page
+    pub fn toc_dialog_scroll_half_page_down(&mut self, dialog_height: usize) {
+        let count = self.toc_dialog_headings().len();
+        if count > 0 {
+            let jump = (dialog_height / 2).max(1);
+            self.toc_dialog_selected = (self.toc_dialog_selected + jump).min(count - 1);
+            self.toc_dialog_auto_scroll(dialog_height);
+        }
+    }
+
+    /// Move TOC dialog selection up by half page
+    pub fn toc_dialog_scroll_half_page_up(&mut self, dialog_height: usize) {
+        let jump = (dialog_height / 2).max(1);
+        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(jump);
+        self.toc_dialog_auto_scroll(dialog_height);
+    }
+
+    /// Move TOC dialog selection down by full page
+    pub fn toc_dialog_scroll_full_page_down(&mut self, dialog_height: usize) {
+        let count = self.toc_dialog_headings().len();
+        if count > 0 {
+            let jump = dialog_height.max(1);
+            self.toc_dialog_selected = (self.toc_dialog_selected + jump).min(count - 1);
+            self.toc_dialog_auto_scroll(dialog_height);
+        }
+    }
+
+    /// Move TOC dialog selection up by full page
+    pub fn toc_dialog_scroll_full_page_up(&mut self, dialog_height: usize) {
+        let jump = dialog_height.max(1);
+        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(jump);
+        self.toc_dialog_auto_scroll(dialog_height);
+    }
+
+    /// Jump to top of TOC dialog
+    pub fn toc_dialog_jump_to_top(&mut self, dialog_height: usize) {
+        self.toc_dialog_selected = 0;
+        self.toc_dialog_auto_scroll(dialog_height);
+    }
+
+    /// Jump to bottom of TOC dialog
+    pub fn toc_dialog_jump_to_bottom(&mut self, dialog_height: usize) {
+        let count = self.toc_dialog_headings().len();
+        if count > 0 {
+            self.toc_dialog_selected = count - 1;
+            self.toc_dialog_auto_scroll(dialog_height);
+        }
+    }
+
+    /// Auto-scroll TOC dialog to keep selection visible
+    pub fn toc_dialog_auto_scroll(&mut self, dialog_height: usize) {
+        let selected = self.toc_dialog_selected;
+        let scroll = self.toc_dialog_scroll;
+
+        // Selection above viewport - scroll up
+        if selected < scroll {
+            self.toc_dialog_scroll = selected;
+        }
+        // Selection below viewport - scroll down
+        else if selected >= scroll + dialog_height {
+            self.toc_dialog_scroll = selected.saturating_sub(dialog_height - 1);
+        }
+    }
+
+    /// Jump to the selected heading in TOC dialog and close dialog
+    pub fn toc_dialog_jump_to_selected(&mut self) {
+        let selected = self.toc_dialog_headings().get(self.toc_dialog_selected).copied();
+        if let Some(heading) = selected.and_then(|idx| self.doc.headings.get(idx)) {
+            let target_line = heading.line;
+            // Use jump_to_line to handle collapsed section expansion
+            self.jump_to_line(target_line);
+            // Set scroll to make heading the top line
+            if let Some(pane) = self.panes.focused_pane_mut() {
+                pane.view.set_scroll_line(target_line);
+            }
+        }
+        // Close the dialog
+        self.show_toc_dialog = false;
+    }
+
+    /// Get the index of the current heading based on cursor position
+    pub fn current_heading_index(&self) -> Option<usize> {
+        if self.doc.headings.is_empty() {
+            return None;
+        }
+
+        let cursor_line = self.panes.focused_pane()?.view.cursor_line;
+
+        // Find the last heading that's at or before the cursor
+        for (i, heading) in self.doc.headings.iter().enumerate().rev() {
+            if heading.line <= cursor_line {
+                return Some(i);
+            }
+        }
 
-                if line_idx >= line_count {
-                    break;
-                }
+        None
+    }
 
-                // Get line length
-                let line_text: String = self.doc.rope.line(line_idx).chars().collect();
-                let line_len = line_text.chars().count();
+    /// Heading indices from the top-level ancestor down to the heading
+    /// containing the pane's cursor
+    pub fn breadcrumb_heading_indices(&self, pane_id: usize) -> Vec<usize> {
+        let Some(pane) = self.panes.panes.get(&pane_id) else {
+            return Vec::new();
+        };
 
-                // Estimate wrapped lines (simple heuristic)
-                let wrapped_lines = if line_len == 0 {
-                    1
-                } else {
-                    ((line_len + content_width - 1) / content_width).max(1)
-                };
+        let cursor_line = pane.view.cursor_line;
 
-                visual_count += wrapped_lines;
-                source_count += 1;
+        // Find the current heading
+        let Some(current_idx) = self
+            .doc
+            .headings
+            .iter()
+            .rposition(|h| h.line <= cursor_line)
+        else {
+            return Vec::new();
+        };
 
-                if visual_count >= visual_lines {
-                    break;
+        // Build the path by walking back through headings
+        let mut path = vec![current_idx];
+        let mut current_level = self.doc.headings[current_idx].level;
+        for (idx, heading) in self.doc.headings[..current_idx].iter().enumerate().rev() {
+            if heading.level < current_level {
+                path.push(idx);
+                current_level = heading.level;
+                if current_level == 1 {
+                    break; // Stop at top-level heading
                 }
             }
-
-            source_count.max(1)
-        } else {
-            visual_lines
         }
-    }
 
-    /// Scroll down by half viewport height (accounting for wrapping)
-    pub fn scroll_half_page_down(&mut self, viewport_height: usize, viewport_width: usize) {
-        let half_page = viewport_height / 2;
-        let source_lines =
-            self.calculate_source_lines_for_visual_lines(half_page, viewport_width, true);
-        self.move_cursor_down(source_lines);
+        // Reverse to get top-down order
+        path.reverse();
+        path
     }
 
-    /// Scroll up by half viewport height (accounting for wrapping)
-    pub fn scroll_half_page_up(&mut self, viewport_height: usize, viewport_width: usize) {
-        let half_page = viewport_height / 2;
-        let source_lines =
-            self.calculate_source_lines_for_visual_lines(half_page, viewport_width, false);
-        self.move_cursor_up(source_lines);
+    /// Get breadcrumb path for a specific pane based on its cursor position
+    pub fn get_breadcrumb_path(&self, pane_id: usize) -> Vec<String> {
+        self.breadcrumb_heading_indices(pane_id)
+            .into_iter()
+            .map(|idx| self.doc.headings[idx].text.clone())
+            .collect()
     }
 
-    /// Update `toc_selected` and `toc_scroll` to track the most recent
-    /// heading at or above the focused pane's scroll line.
-    ///
-    /// Skips the update once if `toc_tracking_suppress_once` is set — used
-    /// to prevent a feedback loop after the TOC itself moved the scroll.
-    pub fn sync_toc_to_scroll(&mut self) {
-        if self.toc_tracking_suppress_once {
-            self.toc_tracking_suppress_once = false;
-            return;
-        }
-        if self.doc.headings.is_empty() {
+    /// Open the breadcrumb popup listing the ancestors of the current section
+    pub fn open_breadcrumb_popup(&mut self) {
+        let path = self.breadcrumb_heading_indices(self.panes.focused);
+        if path.is_empty() {
+            self.set_error_message("No heading above cursor");
             return;
         }
-        let scroll_line = match self.panes.focused_pane() {
-            Some(p) => p.view.scroll_line(),
-            None => return,
-        };
-        // Last heading with line <= scroll_line; if none, use first.
-        let idx = self
-            .doc
-            .headings
-            .iter()
-            .rposition(|h| h.line <= scroll_line)
-            .unwrap_or(0);
-        if idx != self.toc_selected {
-            self.toc_selected = idx;
-            // Best-effort scroll; if TOC is not open or viewport unknown,
-            // fall back to a reasonable window height.
-            let toc_height = self
-                .focused_viewport()
-                .map(|v| v.visible_height)
-                .filter(|&h| h > 0)
-                .unwrap_or(layout_const::DEFAULT_FALLBACK_HEIGHT);
-            self.toc_auto_scroll(toc_height);
-        }
+        // Preselect the direct parent, or the current heading at the top level
+        self.breadcrumb_popup_selected = path.len().saturating_sub(2);
+        self.show_breadcrumb_popup = true;
     }
 
-    /// Capture the current focused pane's scroll/cursor as a jump-stack
-    /// entry. Called *before* a jump (TOC click, search, G, gg, goto).
-    /// Truncates any forward history at the current cursor.
-    pub fn push_jump(&mut self) {
-        let Some(pane_id) = Some(self.panes.focused) else { return };
-        let Some(pane) = self.panes.focused_pane() else { return };
-        let entry = JumpEntry {
-            pane: pane_id,
-            scroll_pos: pane.view.scroll_pos,
-            cursor_line: pane.view.cursor_line,
-        };
-        // If we're not at the tip, drop everything past the cursor — new
-        // jumps always extend from the current position.
-        while self.jump_stack.len() > self.jump_cursor {
-            self.jump_stack.pop_back();
-        }
-        // Bound the stack; drop the oldest entry once at cap.
-        if self.jump_stack.len() >= JUMP_STACK_CAP {
-            self.jump_stack.pop_front();
-            self.jump_cursor = self.jump_cursor.saturating_sub(1);
-        }
-        self.jump_stack.push_back(entry);
-        self.jump_cursor = self.jump_stack.len();
+    /// Close the breadcrumb popup without jumping
+    pub fn close_breadcrumb_popup(&mut self) {
+        self.show_breadcrumb_popup = false;
     }
 
-    /// Pop back to the previous jump position (Ctrl-O). Returns true if a
-    /// jump was applied.
-    pub fn jump_back(&mut self) -> bool {
-        if self.jump_cursor == 0 {
-            return false;
-        }
-        // If we are at the tip (jump_cursor == len()), record the current
-        // position first so Ctrl-I can return to it.
-        if self.jump_cursor == self.jump_stack.len() {
-            if let Some(pane) = self.panes.focused_pane() {
-                let entry = JumpEntry {
-                    pane: self.panes.focused,
-                    scroll_pos: pane.view.scroll_pos,
-                    cursor_line: pane.view.cursor_line,
-                };
-                if self.jump_stack.len() >= JUMP_STACK_CAP {
-                    self.jump_stack.pop_front();
-                    self.jump_cursor = self.jump_cursor.saturating_sub(1);
-                }
-                self.jump_stack.push_back(entry);
-                // jump_cursor stays where it is — we're adding the "tip"
-                // marker past the most recent real jump.
-            }
+    /// Move breadcrumb popup selection down
+    pub fn breadcrumb_popup_move_down(&mut self) {
+        let len = self.breadcrumb_heading_indices(self.panes.focused).len();
+        if len > 0 {
+            self.breadcrumb_popup_selected = (self.breadcrumb_popup_selected + 1).min(len - 1);
         }
-        self.jump_cursor -= 1;
-        self.apply_jump_at_cursor();
-        true
     }
 
-    /// Move forward in the jump stack (Ctrl-I). Returns true if a jump
-    /// was applied.
-    pub fn jump_forward(&mut self) -> bool {
-        if self.jump_cursor + 1 >= self.jump_stack.len() {
-            return false;
-        }
-        self.jump_cursor += 1;
-        self.apply_jump_at_cursor();
-        true
+    /// Move breadcrumb popup selection up
+    pub fn breadcrumb_popup_move_up(&mut self) {
+        self.breadcrumb_popup_selected = self.breadcrumb_popup_selected.saturating_sub(1);
     }
 
-    fn apply_jump_at_cursor(&mut self) {
-        let Some(entry) = self.jump_stack.get(self.jump_cursor).copied() else { return };
-        // Restore focus and position. If the pane was removed (after a
-        // split close) fall back to the currently focused pane.
-        if self.panes.panes.contains_key(&entry.pane) {
-            self.panes.focused = entry.pane;
-        }
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            pane.view.scroll_pos = entry.scroll_pos;
-            pane.view.cursor_line = entry.cursor_line;
+    /// Jump to the selected ancestor heading and close the popup
+    pub fn breadcrumb_popup_jump_to_selected(&mut self) {
+        let path = self.breadcrumb_heading_indices(self.panes.focused);
+        self.show_breadcrumb_popup = false;
+        if let Some(&heading_idx) = path.get(self.breadcrumb_popup_selected) {
+            self.jump_to_heading_index(heading_idx);
         }
-        // Re-clamp in case the doc length or layout changed since the
-        // entry was recorded.
-        self.enforce_rendered_bounds();
-        // Don't let TOC tracking re-echo from the restored scroll.
-        self.toc_tracking_suppress_once = true;
     }
 
-    /// Auto-scroll viewport to keep cursor visible
-    ///
-    /// Uses the actual pane height from layout context when available,
-    /// falling back to the provided viewport_height parameter.
-    pub fn auto_scroll(&mut self, viewport_height: usize) {
-        // Get actual pane height from layout context if available
-        let actual_height = self
-            .focused_viewport()
-            .map(|v| v.visible_height)
-            .filter(|&h| h > 0)
-            .unwrap_or(viewport_height);
-
-        // Debug assertion: height should be reasonable
-        debug_assert!(
-            actual_height > 0 && actual_height < 10000,
-            "auto_scroll: viewport height {} seems unreasonable",
-            actual_height
-        );
-
-        if let Some(pane) = self.panes.focused_pane_mut() {
-            let cursor = pane.view.cursor_line;
-            let scroll = pane.view.scroll_line();
-
-            // Cursor above viewport - scroll up
-            if cursor < scroll {
-                pane.view.set_scroll_line(cursor);
-            }
-            // Cursor below viewport - scroll down
-            else if cursor >= scroll + actual_height {
-                pane.view.set_scroll_line(cursor.saturating_sub(actual_height.saturating_sub(1)));
+    /// Move to the `count`th heading after (or before) the cursor line
+    /// (`]]`/`[[`), stopping at the last one there is
+    pub fn jump_to_next_heading(&mut self, forward: bool, count: usize) {
+        let Some(cursor) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let lines = self.doc.headings.iter().map(|h| h.line);
+        let target = if forward {
+            lines.filter(|&l| l > cursor).take(count).last()
+        } else {
+            lines.rev().filter(|&l| l < cursor).take(count).last()
+        };
+        match target {
+            Some(line) => {
+                self.push_jump();
+                let pane_id = self.panes.focused;
+                self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::NearestEdge);
             }
+            None if forward => self.set_error_message("No heading below cursor"),
+            None => self.set_error_message("No heading above cursor"),
+        }
+    }
 
-            if pane.view.scroll_line() != scroll {
-                trace!(
-                    target: "mdx::scroll",
-                    "auto_scroll: cursor={} height={} scroll {} -> {}",
-                    cursor, actual_height, scroll, pane.view.scroll_line()
-                );
-            }
+    /// The pending count for the command being run (1 without one),
+    /// clearing it
+    pub fn take_count(&mut self) -> usize {
+        self.pending_count.take().unwrap_or(1)
+    }
 
-            // Debug assertion: after auto_scroll, cursor should be visible
-            debug_assert!(
-                pane.view.cursor_line >= pane.view.scroll_line()
-                    && pane.view.cursor_line < pane.view.scroll_line() + actual_height,
-                "auto_scroll: cursor {} not visible in viewport [{}, {})",
-                pane.view.cursor_line,
-                pane.view.scroll_line(),
-                pane.view.scroll_line() + actual_height
-            );
+    /// Jump to the parent heading of the current section (`[h`)
+    pub fn jump_to_parent_heading(&mut self) {
+        let path = self.breadcrumb_heading_indices(self.panes.focused);
+        match path.len() {
+            0 => self.set_error_message("No heading above cursor"),
+            1 => self.set_error_message("Already at a top-level section"),
+            len => self.jump_to_heading_index(path[len - 2]),
         }
-        self.sync_toc_to_scroll();
     }
 
-    /// Toggle between dark and light themes
-    pub fn toggle_theme(&mut self) {
-        self.theme_variant = match self.theme_variant {
-            ThemeVariant::Dark => ThemeVariant::Light,
-            ThemeVariant::Light => ThemeVariant::Dark,
+    /// Open another document as a buffer after the existing ones
+    pub fn add_buffer(&mut self, doc: Document) {
+        let doc_id = self.next_doc_id;
+        self.next_doc_id += 1;
+        self.buffers
+            .push(Some(crate::buffers::Buffer::new(doc_id, doc, &self.config)));
+    }
+
+    /// Show the file at `path`: switch to its buffer if it's open, else
+    /// load it as a new buffer. On failure nothing changes.
+    pub fn open_or_switch(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let open = self.buffer_docs().position(|doc| {
+            doc.path == path || doc.path.canonicalize().is_ok_and(|p| p == canonical)
+        });
+        let index = match open {
+            Some(index) => index,
+            None => {
+                let max_bytes = self.config.document.max_file_bytes;
+                let (doc, _warnings) = Document::load_with_limit(path, max_bytes)
+                    .with_context(|| format!("Failed to load document: {}", path.display()))?;
+                self.add_buffer(doc);
+                self.buffer_count() - 1
+            }
         };
-        self.theme = Theme::for_variant(self.theme_variant);
+        self.switch_buffer(index);
+        Ok(())
     }
 
-    /// Toggle TOC visibility and focus
-    pub fn toggle_toc(&mut self) {
-        if self.show_toc {
-            // If already shown, hide it
-            self.show_toc = false;
-            self.toc_focus = false;
-        } else {
-            // Show and focus TOC
-            self.show_toc = true;
-            self.toc_focus = true;
-        }
+    /// Number of open documents
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
     }
 
-    /// Move TOC selection down
-    pub fn toc_move_down(&mut self, toc_height: usize) {
-        if !self.doc.headings.is_empty() {
-            self.toc_selected = (self.toc_selected + 1).min(self.doc.headings.len() - 1);
-            self.toc_auto_scroll(toc_height);
-        }
+    /// Every open document, in buffer order
+    pub fn buffer_docs(&self) -> impl Iterator<Item = &Document> {
+        self.buffers.iter().map(|slot| match slot {
+            Some(buffer) => &buffer.doc,
+            None => &self.doc,
+        })
     }
 
-    /// Move TOC selection up
-    pub fn toc_move_up(&mut self, toc_height: usize) {
-        self.toc_selected = self.toc_selected.saturating_sub(1);
-        self.toc_auto_scroll(toc_height);
+    /// Name of each open document, in buffer order
+    pub fn buffer_names(&self) -> Vec<String> {
+        self.buffer_docs().map(|doc| self.buffer_name(doc)).collect()
     }
 
-    /// Move TOC selection down by half page
-    pub fn toc_scroll_half_page_down(&mut self, toc_height: usize) {
-        if !self.doc.headings.is_empty() {
-            let jump = (toc_height / 2).max(1);
-            self.toc_selected = (self.toc_selected + jump).min(self.doc.headings.len() - 1);
-            self.toc_auto_scroll(toc_height);
-        }
+    /// Name shown for an open document: its path below the directory in
+    /// directory mode, where file names alone can repeat, else the file name
+    pub fn buffer_name(&self, doc: &Document) -> String {
+        self.directory
+            .as_deref()
+            .and_then(|dir| doc.path.strip_prefix(dir).ok())
+            .map(|rel| rel.display().to_string())
+            .unwrap_or_else(|| crate::buffers::display_name(doc))
     }
 
-    /// Move TOC selection up by half page
-    pub fn toc_scroll_half_page_up(&mut self, toc_height: usize) {
-        let jump = (toc_height / 2).max(1);
-        self.toc_selected = self.toc_selected.saturating_sub(jump);
-        self.toc_auto_scroll(toc_height);
+    /// Whether the hidden buffer at `index` changed on disk since it was
+    /// last shown
+    pub fn buffer_changed(&self, index: usize) -> bool {
+        self.buffers
+            .get(index)
+            .and_then(Option::as_ref)
+            .is_some_and(|buffer| buffer.changed)
     }
 
-    /// Move TOC selection down by full page
-    pub fn toc_scroll_full_page_down(&mut self, toc_height: usize) {
-        if !self.doc.headings.is_empty() {
-            let jump = toc_height.max(1);
-            self.toc_selected = (self.toc_selected + jump).min(self.doc.headings.len() - 1);
-            self.toc_auto_scroll(toc_height);
+    /// Enter directory mode for `dir`, whose files were opened as buffers,
+    /// and start watching the tree when watching is enabled
+    pub fn set_directory(&mut self, dir: std::path::PathBuf) {
+        #[cfg(feature = "watch")]
+        {
+            self.dir_watcher = if self.config.watch.enabled {
+                crate::watcher::DirWatcher::new(&dir).ok()
+            } else {
+                None
+            };
         }
+        self.directory = Some(dir);
     }
 
-    /// Move TOC selection up by full page
-    pub fn toc_scroll_full_page_up(&mut self, toc_height: usize) {
-        let jump = toc_height.max(1);
-        self.toc_selected = self.toc_selected.saturating_sub(jump);
-        self.toc_auto_scroll(toc_height);
+    /// Apply changes reported by the directory watcher: new Markdown files
+    /// open as buffers, and changed hidden buffers are reloaded (or marked
+    /// dirty if they have edits) and flagged in the tab bar and picker. The
+    /// shown document is left to its own watcher.
+    pub fn apply_directory_changes(&mut self, paths: Vec<std::path::PathBuf>) {
+        let mut added = Vec::new();
+        for path in paths {
+            let index = self.buffer_docs().position(|doc| doc.path == path);
+            match index {
+                Some(index) if index == self.active_buffer => {}
+                Some(index) => {
+                    let auto_reload = self.config.watch.auto_reload;
+                    let Some(buffer) = self.buffers[index].as_mut() else {
+                        continue;
+                    };
+                    let disk_mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                    if disk_mtime.is_none() || disk_mtime == buffer.doc.loaded_mtime {
+                        continue;
+                    }
+                    if !(auto_reload && !buffer.doc.modified && buffer.doc.reload().is_ok()) {
+                        buffer.doc.dirty_on_disk = true;
+                    }
+                    buffer.changed = true;
+                }
+                None if path.is_file() => {
+                    let max_bytes = self.config.document.max_file_bytes;
+                    if let Ok((doc, _warnings)) = Document::load_with_limit(&path, max_bytes) {
+                        added.push(self.buffer_name(&doc));
+                        self.add_buffer(doc);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        match added.as_slice() {
+            [] => {}
+            [name] => self.set_info_message(format!("New file: {}", name)),
+            names => self.set_info_message(format!("{} new files", names.len())),
+        }
     }
 
-    /// Jump to top of TOC
-    pub fn toc_jump_to_top(&mut self, toc_height: usize) {
-        self.toc_selected = 0;
-        self.toc_auto_scroll(toc_height);
+    /// Where each open document read from a file was left, in buffer order
+    fn session_files(&self) -> Vec<mdx_core::session::FileState> {
+        use std::collections::VecDeque;
+
+        let file_state = |doc: &Document, panes: &PaneManager, jumps: &VecDeque<JumpEntry>| {
+            doc.loaded_mtime?;
+            let view = &panes.focused_pane()?.view;
+            Some(mdx_core::session::FileState {
+                path: doc.path.clone(),
+                cursor_line: view.cursor_line,
+                scroll_line: view.scroll_line(),
+                jumps: jumps.iter().map(|jump| jump.cursor_line).collect(),
+            })
+        };
+        self.buffers
+            .iter()
+            .filter_map(|slot| match slot {
+                Some(buffer) => file_state(&buffer.doc, &buffer.panes, &buffer.jump_stack),
+                None => file_state(&self.doc, &self.panes, &self.jump_stack),
+            })
+            .collect()
     }
 
-    /// Jump to bottom of TOC
-    pub fn toc_jump_to_bottom(&mut self, toc_height: usize) {
-        if !self.doc.headings.is_empty() {
-            self.toc_selected = self.doc.headings.len() - 1;
-            self.toc_auto_scroll(toc_height);
+    /// Write the open documents and their positions to the session file,
+    /// keeping what it remembers of other files
+    pub fn save_session(&mut self) {
+        let Some(path) = self.session_path.clone() else {
+            return;
+        };
+        let mut state = mdx_core::session::SessionState::load(&path).unwrap_or_else(|e| {
+            tracing::warn!("{:#}", e);
+            Default::default()
+        });
+        let files = self.session_files();
+        state.open = files.iter().map(|file| file.path.clone()).collect();
+        // The first buffer ends up the most recent
+        for file in files.into_iter().rev() {
+            state.remember(file);
+        }
+        if let Err(e) = state.save(&path) {
+            tracing::warn!("Failed to save session: {:#}", e);
         }
+        self.session_dirty = false;
+        self.session_saved = std::time::Instant::now();
     }
 
-    /// Auto-scroll TOC to keep selection visible
-    pub fn toc_auto_scroll(&mut self, toc_height: usize) {
-        let selected = self.toc_selected;
-        let scroll = self.toc_scroll;
-
-        // Selection above viewport - scroll up
-        if selected < scroll {
-            self.toc_scroll = selected;
-        }
-        // Selection below viewport - scroll down
-        else if selected >= scroll + toc_height {
-            self.toc_scroll = selected.saturating_sub(toc_height - 1);
+    /// Save the session if input arrived since the last save and
+    /// `session.autosave_secs` have passed
+    pub fn autosave_session(&mut self) {
+        let interval = self.config.session.autosave_secs;
+        if self.session_dirty
+            && interval > 0
+            && self.session_saved.elapsed() >= std::time::Duration::from_secs(interval)
+        {
+            self.save_session();
         }
     }
 
-    /// Jump to the selected heading in TOC, making it the top line
-    pub fn toc_jump_to_selected(&mut self) {
-        if let Some(heading) = self.doc.headings.get(self.toc_selected) {
-            let target_line = heading.line;
-            // Use jump_to_line to handle collapsed section expansion
-            self.jump_to_line(target_line);
-            // Set scroll to make heading the top line
-            if let Some(pane) = self.panes.focused_pane_mut() {
-                pane.view.set_scroll_line(target_line);
+    /// Put the cursor of each open document back where the session file
+    /// says it was left (`session.restore`)
+    pub fn restore_session(&mut self) {
+        let Some(path) = self.session_path.as_deref().filter(|_| self.config.session.restore) else {
+            return;
+        };
+        let state = match mdx_core::session::SessionState::load(path) {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                self.set_error_message("Session file was corrupt; reading positions start over");
+                return;
+            }
+        };
+        if let Some(file) = state.file(&self.doc.path) {
+            let (panes, jumps) = (&mut self.panes, &mut self.jump_stack);
+            restore_file(file, &self.doc, panes, jumps, &mut self.jump_cursor);
+        }
+        for buffer in self.buffers.iter_mut().flatten() {
+            if let Some(file) = state.file(&buffer.doc.path) {
+                let (panes, jumps) = (&mut buffer.panes, &mut buffer.jump_stack);
+                restore_file(file, &buffer.doc, panes, jumps, &mut buffer.jump_cursor);
             }
-            // Suppress the next TOC-tracking update; the scroll moved
-            // *because* of this TOC click, so re-selecting would echo.
-            self.toc_tracking_suppress_once = true;
         }
     }
 
-    /// Toggle TOC dialog
-    pub fn toggle_toc_dialog(&mut self) {
-        self.show_toc_dialog = !self.show_toc_dialog;
-        if self.show_toc_dialog {
-            // Reset selection when opening
-            self.toc_dialog_selected = 0;
-            self.toc_dialog_scroll = 0;
-        }
+    /// Whether the tab bar is drawn above the panes
+    pub fn tab_bar_visible(&self) -> bool {
+        self.config.render.tab_bar && self.buffers.len() > 1 && self.presentation.is_none()
     }
 
-    /// Move TOC dialog selection down
-    pub fn toc_dialog_move_down(&mut self, dialog_height: usize) {
-        if !self.doc.headings.is_empty() {
-            self.toc_dialog_selected =
-                (self.toc_dialog_selected + 1).min(self.doc.headings.len() - 1);
-            self.toc_dialog_auto_scroll(dialog_height);
+    /// Show the document at `index` in the buffer list
+    pub fn switch_buffer(&mut self, index: usize) {
+        if index == self.active_buffer {
+            return;
+        }
+        let Some(mut buffer) = self.buffers.get_mut(index).and_then(Option::take) else {
+            return;
+        };
+        buffer.changed = false;
+
+        std::mem::swap(&mut self.doc_id, &mut buffer.doc_id);
+        std::mem::swap(&mut self.doc, &mut buffer.doc);
+        std::mem::swap(&mut self.panes, &mut buffer.panes);
+        std::mem::swap(&mut self.toc_selected, &mut buffer.toc_selected);
+        std::mem::swap(&mut self.toc_scroll, &mut buffer.toc_scroll);
+        std::mem::swap(&mut self.jump_stack, &mut buffer.jump_stack);
+        std::mem::swap(&mut self.jump_cursor, &mut buffer.jump_cursor);
+        std::mem::swap(&mut self.load_error, &mut buffer.load_error);
+        #[cfg(feature = "watch")]
+        std::mem::swap(&mut self.watcher, &mut buffer.watcher);
+        self.buffers[self.active_buffer] = Some(buffer);
+        self.active_buffer = index;
+
+        // Popups and modes belong to the document that was shown
+        self.toc_focus = false;
+        self.key_prefix = KeyPrefix::None;
+        self.show_breadcrumb_popup = false;
+        self.show_link_diagnostics = false;
+        self.show_spell_popup = false;
+        self.preview = None;
+        self.stats_popup = None;
+        self.block_output = None;
+
+        // Both caches are keyed by document revision, which isn't unique
+        // across documents
+        self.styled_line_cache.clear();
+        self.commonmark_lines.clear();
+        self.line_layout_cache = crate::line_layout::LineLayoutCache::new();
+
+        // Changes on disk while hidden weren't seen by the watcher loop
+        let disk_mtime = std::fs::metadata(&self.doc.path)
+            .and_then(|m| m.modified())
+            .ok();
+        if disk_mtime.is_some() && disk_mtime != self.doc.loaded_mtime {
+            self.doc.dirty_on_disk = true;
         }
+
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        self.collect_search_matches();
+        self.request_diff();
     }
 
-    /// Move TOC dialog selection up
-    pub fn toc_dialog_move_up(&mut self, dialog_height: usize) {
-        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(1);
-        self.toc_dialog_auto_scroll(dialog_height);
+    /// Switch buffers and name the new one in the status bar
+    pub fn show_buffer(&mut self, index: usize) {
+        if self.buffers.len() < 2 {
+            self.set_info_message("Only one buffer open");
+            return;
+        }
+        self.switch_buffer(index);
+        self.set_info_message(format!(
+            "[{}/{}] {}",
+            self.active_buffer + 1,
+            self.buffers.len(),
+            self.doc.path.display()
+        ));
     }
 
-    /// Move TOC dialog selection down by half page
-    pub fn toc_dialog_scroll_half_page_down(&mut self, dialog_height: usize) {
-        if !self.doc.headings.is_empty() {
-            let jump = (dialog_height / 2).max(1);
-            self.toc_dialog_selected =
-                (self.toc_dialog_selected + jump).min(self.doc.headings.len() - 1);
-            self.toc_dialog_auto_scroll(dialog_height);
+    /// Open the fuzzy finder over the headings of every open file, the
+    /// links of this one, and (with several files open) the file names
+    pub fn open_finder(&mut self) {
+        use crate::finder::{FinderItem, FinderKind};
+
+        let several = self.buffers.len() > 1;
+        let mut items = Vec::new();
+        for (buffer, doc) in self.buffer_docs().enumerate() {
+            let name = self.buffer_name(doc);
+            items.extend(doc.headings.iter().map(|heading| FinderItem {
+                kind: FinderKind::Heading,
+                label: heading.text.clone(),
+                detail: if several {
+                    format!("{} {}", "#".repeat(heading.level as usize), name)
+                } else {
+                    "#".repeat(heading.level as usize)
+                },
+                buffer,
+                line: Some(heading.line),
+            }));
         }
+        let flavor = self.config.render.markdown.flavor;
+        for link in mdx_core::links::extract_links(&self.doc.rope, flavor) {
+            if link.is_image {
+                continue;
+            }
+            // Links to a heading in this file go to the heading
+            let line = link
+                .dest
+                .strip_prefix('#')
+                .and_then(|anchor| mdx_core::toc::find_anchor(&self.doc.headings, anchor))
+                .map_or(link.line, |heading| heading.line);
+            let label = if link.text.trim().is_empty() {
+                link.dest.clone()
+            } else {
+                link.text.clone()
+            };
+            items.push(FinderItem {
+                kind: FinderKind::Link,
+                label,
+                detail: link.dest,
+                buffer: self.active_buffer,
+                line: Some(line),
+            });
+        }
+        if several {
+            items.extend(self.buffer_names().into_iter().enumerate().map(|(buffer, name)| {
+                FinderItem {
+                    kind: FinderKind::File,
+                    label: name,
+                    detail: String::new(),
+                    buffer,
+                    line: None,
+                }
+            }));
+        }
+        self.finder = Some(crate::finder::Finder::new(items));
     }
 
-    /// Move TOC dialog selection up by half page
-    pub fn toc_dialog_scroll_half_page_up(&mut self, dialog_height: usize) {
-        let jump = (dialog_height / 2).max(1);
-        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(jump);
-        self.toc_dialog_auto_scroll(dialog_height);
+    /// Open the command palette over every command, with its key
+    pub fn open_command_palette(&mut self) {
+        use crate::finder::{FinderItem, FinderKind};
+
+        let items = crate::commands::Command::ALL
+            .iter()
+            .map(|&command| FinderItem {
+                kind: FinderKind::Command(command),
+                label: command.title().to_string(),
+                detail: command.keys(),
+                buffer: self.active_buffer,
+                line: None,
+            })
+            .collect();
+        self.finder = Some(crate::finder::Finder::new(items));
     }
 
-    /// Move TOC dialog selection down by full page
-    pub fn toc_dialog_scroll_full_page_down(&mut self, dialog_height: usize) {
-        if !self.doc.headings.is_empty() {
-            let jump = dialog_height.max(1);
-            self.toc_dialog_selected =
-                (self.toc_dialog_selected + jump).min(self.doc.headings.len() - 1);
-            self.toc_dialog_auto_scroll(dialog_height);
+    /// Close the finder and go to the selected entry
+    pub fn finder_open_selected(&mut self) {
+        let Some(item) = self.finder.take().and_then(|f| f.selected_item().cloned()) else {
+            return;
+        };
+        if item.buffer != self.active_buffer {
+            self.switch_buffer(item.buffer);
+        }
+        if let Some(line) = item.line {
+            self.push_jump();
+            let pane_id = self.panes.focused;
+            self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::Center);
         }
     }
 
-    /// Move TOC dialog selection up by full page
-    pub fn toc_dialog_scroll_full_page_up(&mut self, dialog_height: usize) {
-        let jump = dialog_height.max(1);
-        self.toc_dialog_selected = self.toc_dialog_selected.saturating_sub(jump);
-        self.toc_dialog_auto_scroll(dialog_height);
+    /// Show the next buffer, wrapping around (`gt`)
+    pub fn next_buffer(&mut self) {
+        self.show_buffer((self.active_buffer + 1) % self.buffers.len());
     }
 
-    /// Jump to top of TOC dialog
-    pub fn toc_dialog_jump_to_top(&mut self, dialog_height: usize) {
-        self.toc_dialog_selected = 0;
-        self.toc_dialog_auto_scroll(dialog_height);
+    /// Show the previous buffer, wrapping around (`gT`)
+    pub fn prev_buffer(&mut self) {
+        let len = self.buffers.len();
+        self.show_buffer((self.active_buffer + len - 1) % len);
     }
 
-    /// Jump to bottom of TOC dialog
-    pub fn toc_dialog_jump_to_bottom(&mut self, dialog_height: usize) {
-        if !self.doc.headings.is_empty() {
-            self.toc_dialog_selected = self.doc.headings.len() - 1;
-            self.toc_dialog_auto_scroll(dialog_height);
+    /// Close the document at `index` in the buffer list. Unsaved edits
+    /// are only thrown away with `force`; the last open document stays.
+    pub fn close_buffer(&mut self, index: usize, force: bool) {
+        let len = self.buffers.len();
+        if index >= len {
+            self.set_error_message(format!("No buffer {}", index + 1));
+            return;
+        }
+        if len == 1 {
+            self.set_error_message("Last open file (use :q to quit)");
+            return;
+        }
+        let name = self.buffer_names().swap_remove(index);
+        let modified = self.buffer_docs().nth(index).is_some_and(|doc| doc.modified);
+        if modified && !force {
+            self.set_error_message(format!(
+                "Unsaved changes in {} (use :w to save or :bd! to discard)",
+                name
+            ));
+            return;
         }
-    }
-
-    /// Auto-scroll TOC dialog to keep selection visible
-    pub fn toc_dialog_auto_scroll(&mut self, dialog_height: usize) {
-        let selected = self.toc_dialog_selected;
-        let scroll = self.toc_dialog_scroll;
 
-        // Selection above viewport - scroll up
-        if selected < scroll {
-            self.toc_dialog_scroll = selected;
+        if index == self.active_buffer {
+            self.switch_buffer(if index + 1 < len { index + 1 } else { index - 1 });
         }
-        // Selection below viewport - scroll down
-        else if selected >= scroll + dialog_height {
-            self.toc_dialog_scroll = selected.saturating_sub(dialog_height - 1);
+        self.buffers.remove(index);
+        if index < self.active_buffer {
+            self.active_buffer -= 1;
         }
+        self.buffer_picker_selected = self.buffer_picker_selected.min(len - 2);
+        self.set_info_message(format!("Closed {}", name));
     }
 
-    /// Jump to the selected heading in TOC dialog and close dialog
-    pub fn toc_dialog_jump_to_selected(&mut self) {
-        if let Some(heading) = self.doc.headings.get(self.toc_dialog_selected) {
-            let target_line = heading.line;
-            // Use jump_to_line to handle collapsed section expansion
-            self.jump_to_line(target_line);
-            // Set scroll to make heading the top line
-            if let Some(pane) = self.panes.focused_pane_mut() {
-                pane.view.set_scroll_line(target_line);
+    /// Run `:b N`, `:bd`, or `:bd!`. Returns false for other commands.
+    fn run_buffer_command(&mut self, command: &str) -> bool {
+        let (name, arg) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, arg)| (name, arg.trim()));
+        let index = match arg {
+            "" => None,
+            arg => match arg.parse::<usize>() {
+                Ok(number) if number >= 1 => Some(number - 1),
+                _ => {
+                    self.set_error_message(format!("Not a buffer number: {}", arg));
+                    return true;
+                }
+            },
+        };
+        match name {
+            "b" | "buffer" => match index {
+                Some(index) if index < self.buffers.len() => {
+                    if index != self.active_buffer {
+                        self.show_buffer(index);
+                    }
+                }
+                Some(index) => self.set_error_message(format!("No buffer {}", index + 1)),
+                None => self.open_buffer_picker(),
+            },
+            "bd" | "bdelete" | "bd!" | "bdelete!" => {
+                let force = name.ends_with('!');
+                self.close_buffer(index.unwrap_or(self.active_buffer), force);
             }
+            _ => return false,
         }
-        // Close the dialog
-        self.show_toc_dialog = false;
+        true
     }
 
-    /// Get the index of the current heading based on cursor position
-    pub fn current_heading_index(&self) -> Option<usize> {
-        if self.doc.headings.is_empty() {
-            return None;
-        }
+    /// Open the buffer picker with the shown document selected
+    pub fn open_buffer_picker(&mut self) {
+        self.buffer_picker_selected = self.active_buffer;
+        self.show_buffer_picker = true;
+    }
 
-        let cursor_line = self.panes.focused_pane()?.view.cursor_line;
+    /// Close the buffer picker without switching
+    pub fn close_buffer_picker(&mut self) {
+        self.show_buffer_picker = false;
+    }
 
-        // Find the last heading that's at or before the cursor
-        for (i, heading) in self.doc.headings.iter().enumerate().rev() {
-            if heading.line <= cursor_line {
-                return Some(i);
-            }
-        }
+    /// Move buffer picker selection down
+    pub fn buffer_picker_move_down(&mut self) {
+        self.buffer_picker_selected =
+            (self.buffer_picker_selected + 1).min(self.buffers.len().saturating_sub(1));
+    }
 
-        None
+    /// Move buffer picker selection up
+    pub fn buffer_picker_move_up(&mut self) {
+        self.buffer_picker_selected = self.buffer_picker_selected.saturating_sub(1);
     }
 
-    /// Get breadcrumb path for a specific pane based on its cursor position
-    pub fn get_breadcrumb_path(&self, pane_id: usize) -> Vec<String> {
-        let mut breadcrumbs = Vec::new();
+    /// Switch to the selected buffer and close the picker
+    pub fn buffer_picker_open_selected(&mut self) {
+        self.show_buffer_picker = false;
+        if self.buffer_picker_selected != self.active_buffer {
+            self.show_buffer(self.buffer_picker_selected);
+        }
+    }
 
-        if self.doc.headings.is_empty() {
-            return breadcrumbs;
+    /// List the commits that touched the document
+    #[cfg(feature = "git")]
+    pub fn open_git_log(&mut self) {
+        match crate::git_log::GitLog::load(&self.doc.path) {
+            Ok(log) if log.commits.is_empty() => {
+                self.set_info_message("No commits touch this file")
+            }
+            Ok(log) => self.git_log = Some(log),
+            Err(e) => self.set_error_message(format!("Git log failed: {}", e)),
         }
+    }
 
-        let pane = match self.panes.panes.get(&pane_id) {
-            Some(p) => p,
-            None => return breadcrumbs,
+    /// Compare the selected revision with the document, read-only
+    #[cfg(feature = "git")]
+    pub fn git_log_open_selected(&mut self) {
+        let Some(commit) = self.git_log.take().and_then(|log| log.selected_commit().cloned())
+        else {
+            return;
         };
+        let text = match mdx_core::git::read_file_at_revision(&self.doc.path, &commit.id) {
+            Ok(Some(text)) => text,
+            Ok(None) => {
+                self.set_info_message(format!("File not present in {}", commit.short_id()));
+                return;
+            }
+            Err(e) => {
+                self.set_error_message(format!("Reading {} failed: {}", commit.short_id(), e));
+                return;
+            }
+        };
+        let name = self
+            .doc
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let label = std::path::PathBuf::from(format!("{}@{}", name, commit.short_id()));
+        self.diff_view = Some(crate::diff_view::DiffView::with_revision(
+            label, &text, &self.doc,
+        ));
+    }
 
-        let cursor_line = pane.view.cursor_line;
+    /// Open the statistics popup with the current section preselected
+    pub fn open_stats_popup(&mut self) {
+        let stats = self.doc.stats();
+        self.stats_popup_selected = self
+            .breadcrumb_heading_indices(self.panes.focused)
+            .last()
+            .copied()
+            .unwrap_or(0)
+            .min(stats.sections.len().saturating_sub(1));
+        self.stats_popup = Some(stats);
+    }
 
-        // Find the current heading
-        let current_idx = self
-            .doc
-            .headings
-            .iter()
-            .enumerate()
-            .rev()
-            .find(|(_, h)| h.line <= cursor_line)
-            .map(|(i, _)| i);
+    /// Open the log viewer (`:log`) at the newest event
+    pub fn open_log_viewer(&mut self) {
+        if self.log_buffer.is_some() {
+            self.log_viewer = Some(0);
+        } else {
+            self.set_info_message("Logging is off; start mdx with --verbose");
+        }
+    }
 
-        let current_idx = match current_idx {
-            Some(idx) => idx,
-            None => return breadcrumbs,
-        };
+    /// Scroll the log viewer towards older (positive) or newer events
+    pub fn scroll_log_viewer(&mut self, delta: isize) {
+        let total = self.log_buffer.as_ref().map_or(0, |buffer| buffer.len());
+        if let Some(scroll) = self.log_viewer.as_mut() {
+            *scroll = scroll
+                .saturating_add_signed(delta)
+                .min(total.saturating_sub(1));
+        }
+    }
 
-        // Build breadcrumb path by walking back through headings
-        let current_heading = &self.doc.headings[current_idx];
-        let mut path_headings = vec![current_heading];
+    /// Close the statistics popup
+    pub fn close_stats_popup(&mut self) {
+        self.stats_popup = None;
+    }
 
-        // Walk backwards to find parent headings
-        let mut current_level = current_heading.level;
-        for heading in self.doc.headings[..current_idx].iter().rev() {
-            if heading.level < current_level {
-                path_headings.push(heading);
-                current_level = heading.level;
-                if current_level == 1 {
-                    break; // Stop at top-level heading
-                }
-            }
+    /// Move stats popup selection down
+    pub fn stats_popup_move_down(&mut self) {
+        let len = self.stats_popup.as_ref().map_or(0, |s| s.sections.len());
+        if len > 0 {
+            self.stats_popup_selected = (self.stats_popup_selected + 1).min(len - 1);
         }
+    }
 
-        // Reverse to get top-down order
-        path_headings.reverse();
+    /// Move stats popup selection up
+    pub fn stats_popup_move_up(&mut self) {
+        self.stats_popup_selected = self.stats_popup_selected.saturating_sub(1);
+    }
 
-        // Extract text
-        for heading in path_headings {
-            breadcrumbs.push(heading.text.clone());
+    /// Jump to the selected section and close the popup
+    pub fn stats_popup_jump_to_selected(&mut self) {
+        let Some(stats) = self.stats_popup.take() else {
+            return;
+        };
+        if let Some(section) = stats.sections.get(self.stats_popup_selected) {
+            self.jump_to_heading_index(section.heading);
         }
+    }
 
-        breadcrumbs
+    fn jump_to_heading_index(&mut self, heading_idx: usize) {
+        let Some(line) = self.doc.headings.get(heading_idx).map(|h| h.line) else {
+            return;
+        };
+        self.push_jump();
+        let pane_id = self.panes.focused;
+        self.goto(pane_id, line, crate::scroll_math::ScrollPolicy::NearestEdge);
     }
 
     /// Get git status for the document (overall file status)
@@ -1428,7 +3825,7 @@ impl App {
 
     /// Split the focused pane
     pub fn split_focused(&mut self, dir: crate::panes::SplitDir) {
-        self.panes.split_focused(dir, 0); // doc_id is 0 for single document
+        self.panes.split_focused(dir, self.doc_id);
         // Note: layout_context will be stale after this.
         // It will be refreshed on next draw() or via refresh_layout_context_with_area()
     }
@@ -1443,7 +3840,7 @@ impl App {
         // Estimate the pane area (accounting for status bar and TOC)
         let status_bar_height = 1;
         let toc_width = if self.show_toc {
-            self.config.toc.width as u16
+            self.config.toc.width
         } else {
             0
         };
@@ -1476,6 +3873,9 @@ impl App {
     /// main loop on `Event::Resize`.
     pub fn on_resize(&mut self, width: u16, height: u16) {
         self.refresh_layout_context_with_area(width, height);
+        // Lines styled for the old width won't be asked for again
+        self.styled_line_cache.clear();
+        self.anchor_panes_to_cursor();
         self.enforce_rendered_bounds();
 
         // Clamp TOC scroll offsets to the new heading count / window.
@@ -1490,6 +3890,62 @@ impl App {
         }
     }
 
+    /// After the wrap width changed, scroll each pane whose cursor line no
+    /// longer fits so it sits on the bottom row (or the top, if it is now
+    /// above the viewport). Folded lines are counted as shown, which only
+    /// ever errs towards leaving the cursor higher on screen.
+    fn anchor_panes_to_cursor(&mut self) {
+        let (bounds_lo, _) = self.rendered_content_bounds();
+        let gen = self.layout_context.generation();
+        let mut pane_ids: Vec<PaneId> = self.panes.panes.keys().copied().collect();
+        // The focused pane goes last so the cache is left at its width
+        pane_ids.sort_by_key(|&id| id == self.panes.focused);
+
+        for pane_id in pane_ids {
+            let Some(viewport) = self.layout_context.focused_viewport(pane_id) else {
+                continue;
+            };
+            let height = viewport.visible_height;
+            self.line_layout_cache
+                .ensure_for(viewport.content_width, self.doc.rev, gen, &self.doc.rope);
+            let cache = &self.line_layout_cache;
+            let Some(pane) = self.panes.panes.get_mut(&pane_id) else {
+                continue;
+            };
+            let cursor = pane.view.cursor_line;
+            let scroll = pane.view.scroll_line();
+            let cursor_rows = cache.visual_height_of_line(cursor) as usize;
+            let rows_above = cache
+                .visual_rows_in_range(scroll, cursor)
+                .saturating_sub(pane.view.scroll_pos.wrap_row as usize);
+
+            if height == 0 || (cursor >= scroll && rows_above + cursor_rows <= height) {
+                continue;
+            }
+            let top = if cursor < scroll || cursor_rows >= height {
+                cursor
+            } else {
+                let mut top = cursor;
+                let mut rows = cursor_rows;
+                while top > bounds_lo {
+                    let above = cache.visual_height_of_line(top - 1) as usize;
+                    if rows + above > height {
+                        break;
+                    }
+                    rows += above;
+                    top -= 1;
+                }
+                top
+            };
+            pane.view.scroll_pos = crate::scroll_math::VisualPos::at(top);
+            trace!(
+                target: "mdx::scroll",
+                "resize anchor: pane={:?} cursor={} scroll {}->{}",
+                pane_id, cursor, scroll, top
+            );
+        }
+    }
+
     /// Enter visual line mode
     pub fn enter_visual_line_mode(&mut self) {
         if let Some(pane) = self.panes.focused_pane_mut() {
@@ -1576,11 +4032,7 @@ impl App {
         if start > end {
             return None;
         }
-        let mut text = self.doc.get_lines(start, end);
-        if !text.ends_with('\n') {
-            text.push('\n');
-        }
-        Some(text)
+        Some(self.range_text((start, end)))
     }
 
     fn execute_shell_command(&self, command: &str, input: &str) -> String {
@@ -1629,6 +4081,110 @@ impl App {
         }
     }
 
+    /// Whether fenced code blocks may be run
+    pub fn block_exec_allowed(&self) -> bool {
+        self.insecure && !self.config.security.no_exec
+    }
+
+    /// Ask to run the shell or Python block under the cursor; once
+    /// confirmed, its output is shown below the panes
+    pub fn run_block_at_cursor(&mut self) {
+        if !self.block_exec_allowed() {
+            self.set_error_message(
+                "Running code blocks requires --insecure and security.no_exec = false",
+            );
+            return;
+        }
+        let Some(line) = self.panes.focused_pane().map(|p| p.view.cursor_line) else {
+            return;
+        };
+        let Some(block) = mdx_core::exec::fenced_block_at(&self.doc.rope, line) else {
+            self.set_error_message("Cursor is not in a fenced code block");
+            return;
+        };
+        if mdx_core::exec::Interpreter::for_lang(&block.lang).is_none() {
+            let lang = if block.lang.is_empty() { "untagged" } else { &block.lang };
+            self.set_error_message(format!(
+                "Cannot run {} blocks (shell and Python only)",
+                lang
+            ));
+            return;
+        }
+        let question = format!(
+            "Run {} block at line {}?",
+            block.lang,
+            block.start_line + 1
+        );
+        self.ask(question, ConfirmAction::RunBlock(block.start_line));
+    }
+
+    /// Start the fenced code block containing `line` on the exec worker;
+    /// its output is shown once it finishes
+    fn run_block(&mut self, line: usize) {
+        let Some(block) = mdx_core::exec::fenced_block_at(&self.doc.rope, line) else {
+            self.set_error_message("The code block is gone");
+            return;
+        };
+
+        // Relative paths in runbooks are relative to the document
+        let dir = self
+            .doc
+            .path
+            .parent()
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        self.block_runs += 1;
+        self.block_output = Some(BlockOutput {
+            lang: block.lang.clone(),
+            start_line: block.start_line,
+            run: self.block_runs,
+            result: None,
+        });
+        self.exec_worker
+            .get_or_insert_with(|| crate::exec_worker::ExecWorker::new(&self.workers))
+            .request_run(crate::exec_worker::RunBlockRequest {
+                run: self.block_runs,
+                block,
+                dir,
+            });
+    }
+
+    /// Show the output of a finished code block, unless its split was
+    /// closed or another block has been started since
+    pub fn apply_block_result(&mut self, result: crate::exec_worker::RunBlockResult) {
+        if let Some(output) = self.block_output.as_mut().filter(|o| o.run == result.run) {
+            output.result = Some(result.result);
+        }
+    }
+
+    /// Close the code block output split
+    pub fn close_block_output(&mut self) {
+        self.block_output = None;
+    }
+
+    /// Select a section, code block, list, or table around the cursor in
+    /// visual line mode (`as`, `ic`, ...)
+    pub fn select_text_object(&mut self, object: mdx_core::selection::TextObject, inner: bool) {
+        let Some(pane) = self.panes.focused_pane() else {
+            return;
+        };
+        if pane.view.mode != Mode::VisualLine {
+            return;
+        }
+        let pane_id = pane.id;
+        let line = pane.view.cursor_line;
+        let Some((start, end)) = mdx_core::selection::text_object(&self.doc, line, object, inner)
+        else {
+            self.set_info_message(format!("Not in a {}", object.name()));
+            return;
+        };
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.selection = Some(LineSelection { anchor: start, cursor: end });
+        }
+        self.goto(pane_id, end, crate::scroll_math::ScrollPolicy::NearestEdge);
+    }
+
     /// Yank selected lines to clipboard
     #[cfg(feature = "clipboard")]
     pub fn yank_selection(&self) -> anyhow::Result<usize> {
@@ -1668,6 +4224,186 @@ impl App {
         Err(anyhow::anyhow!("Clipboard feature not enabled"))
     }
 
+    /// Copy a `#anchor` link to the heading above the cursor (`:anchor`),
+    /// using the same anchors GitHub generates
+    pub fn yank_heading_anchor(&mut self) {
+        let Some(index) = self.current_heading_index() else {
+            self.set_error_message("No heading above the cursor");
+            return;
+        };
+        let link = format!("#{}", self.doc.headings[index].anchor);
+
+        #[cfg(feature = "clipboard")]
+        let result = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(link.clone()))
+            .map_err(|e| format!("Failed to copy anchor: {}", e));
+        #[cfg(not(feature = "clipboard"))]
+        let result: Result<(), String> = Err("Clipboard feature not enabled".to_string());
+
+        match result {
+            Ok(()) => self.set_success_message(format!("Copied {}", link)),
+            Err(message) => self.set_error_message(message),
+        }
+    }
+
+    /// Copy the contents of the code block under the cursor, without its
+    /// fences or the indentation of a fence inside a list item (`yc`)
+    pub fn copy_code_block(&mut self) {
+        use mdx_core::selection::TextObject;
+
+        let Some(line) = self.panes.focused_pane().map(|pane| pane.view.cursor_line) else {
+            return;
+        };
+        let Some((start, _)) =
+            mdx_core::selection::text_object(&self.doc, line, TextObject::CodeBlock, false)
+        else {
+            self.set_info_message("Not in a code block");
+            return;
+        };
+        // An empty block has no inner lines
+        let (first, last) =
+            mdx_core::selection::text_object(&self.doc, line, TextObject::CodeBlock, true)
+                .unwrap_or((start + 1, start));
+        let fence: String = self.doc.rope.line(start).chunks().collect();
+        let indent = fence.len() - fence.trim_start().len();
+        let code: Vec<String> = (first..=last)
+            .map(|idx| {
+                let text: String = self.doc.rope.line(idx).chunks().collect();
+                let text = text.trim_end_matches(['\n', '\r']);
+                let strip = text
+                    .char_indices()
+                    .take_while(|(i, c)| *i < indent && *c == ' ')
+                    .count();
+                text[strip..].to_string()
+            })
+            .collect();
+        let text = code.join("\n");
+
+        #[cfg(feature = "clipboard")]
+        let result = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| format!("Failed to copy code block: {}", e));
+        #[cfg(not(feature = "clipboard"))]
+        let result: Result<(), String> = {
+            let _ = text;
+            Err("Clipboard feature not enabled".to_string())
+        };
+
+        match result {
+            Ok(()) => {
+                self.copied_code_block = Some((start, std::time::Instant::now()));
+                self.set_success_message(format!(
+                    "Copied {} line{} of code",
+                    code.len(),
+                    if code.len() == 1 { "" } else { "s" }
+                ));
+            }
+            Err(message) => self.set_error_message(message),
+        }
+    }
+
+    /// The table under the cursor as delimited text, with its number of
+    /// data rows
+    fn table_at_cursor(&self, format: mdx_core::export::TableFormat) -> Option<(String, usize)> {
+        let line = self.panes.focused_pane()?.view.cursor_line;
+        let table = self.doc.tables.iter().find(|table| table.contains(&line))?;
+        let rows: Vec<String> = table
+            .clone()
+            .map(|idx| self.doc.rope.line(idx).chunks().collect())
+            .collect();
+        let rows: Vec<&str> = rows.iter().map(|row| row.trim_end_matches(['\n', '\r'])).collect();
+        let text = mdx_core::export::export_table(
+            &rows,
+            format,
+            &self.config.render,
+            &self.doc.link_definitions,
+        );
+        Some((text, rows.len().saturating_sub(2)))
+    }
+
+    /// Copy the table under the cursor to the clipboard (`yt` for TSV,
+    /// which spreadsheets paste as cells)
+    pub fn copy_table(&mut self, format: mdx_core::export::TableFormat) {
+        let Some((text, rows)) = self.table_at_cursor(format) else {
+            self.set_info_message("Not in a table");
+            return;
+        };
+
+        #[cfg(feature = "clipboard")]
+        let result = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| format!("Failed to copy table: {}", e));
+        #[cfg(not(feature = "clipboard"))]
+        let result: Result<(), String> = {
+            let _ = text;
+            Err("Clipboard feature not enabled".to_string())
+        };
+
+        match result {
+            Ok(()) => self.set_success_message(format!(
+                "Copied {} row{} as {}",
+                rows,
+                if rows == 1 { "" } else { "s" },
+                format.name()
+            )),
+            Err(message) => self.set_error_message(message),
+        }
+    }
+
+    /// `:export[!] [csv|tsv] [PATH]` - write the table under the cursor to
+    /// PATH, in the named format or else the one its extension asks for.
+    /// Without a path the table goes to the clipboard, as TSV by default.
+    pub fn export_table(&mut self, args: &str, force: bool) {
+        use mdx_core::export::TableFormat;
+
+        let (format, path) = match args.split_once(char::is_whitespace) {
+            Some((name, path)) if TableFormat::from_name(name).is_some() => {
+                (TableFormat::from_name(name), path.trim())
+            }
+            None if TableFormat::from_name(args).is_some() => (TableFormat::from_name(args), ""),
+            _ => (None, args),
+        };
+        if path.is_empty() {
+            self.copy_table(format.unwrap_or(TableFormat::Tsv));
+            return;
+        }
+
+        let path = std::path::Path::new(path);
+        let format = format.unwrap_or_else(|| TableFormat::for_path(path));
+        let Some((text, rows)) = self.table_at_cursor(format) else {
+            self.set_info_message("Not in a table");
+            return;
+        };
+        if path.exists() && !force {
+            self.set_error_message(format!("{} exists (add ! to overwrite)", path.display()));
+            return;
+        }
+        match std::fs::write(path, text) {
+            Ok(()) => self.set_info_message(format!(
+                "Wrote {} row{} as {} to {}",
+                rows,
+                if rows == 1 { "" } else { "s" },
+                format.name(),
+                path.display()
+            )),
+            Err(e) => self.set_error_message(format!("Write failed: {}", e)),
+        }
+    }
+
+    /// Open the command line ready for `:export csv PATH`
+    pub fn prompt_table_export(&mut self) {
+        self.enter_command_mode();
+        self.command_line.push_str("export csv ");
+    }
+
+    /// Whether the code block opened at `fence_line` was copied recently
+    /// enough to still show "copied!" in place of its label
+    pub fn code_block_copied(&self, fence_line: usize) -> bool {
+        const FLASH: std::time::Duration = std::time::Duration::from_millis(1500);
+        self.copied_code_block
+            .is_some_and(|(line, at)| line == fence_line && at.elapsed() < FLASH)
+    }
+
     /// Open the current file in an external editor
     pub fn open_in_editor(&self) -> anyhow::Result<()> {
         use crate::editor;
@@ -1680,6 +4416,14 @@ impl App {
             anyhow::bail!("External commands are disabled (security.safe_mode = true)");
         }
 
+        if self.doc.modified {
+            anyhow::bail!("Save in-app edits with :w before opening an external editor");
+        }
+
+        if let Some(rev) = &self.doc.revision {
+            anyhow::bail!("Not editing the working tree file while showing it as of {}", rev);
+        }
+
         let pane = self
             .panes
             .focused_pane()
@@ -1700,82 +4444,681 @@ impl App {
     /// Search for text in the document
     pub fn search(&mut self, query: &str) {
         if query.is_empty() {
-            self.search_query.clear();
-            self.search_matches.clear();
-            self.search_current_match = None;
+            self.clear_search();
+            self.restore_search_origin();
             return;
         }
 
         self.search_query = query.to_lowercase();
+        self.collect_search_matches();
+        self.goto_first_search_match();
+    }
+
+    /// Select the first match at or after (searching up: at or before)
+    /// the line the search started from, wrapping around, and center it.
+    /// Without a match the view goes back to where the search started.
+    fn goto_first_search_match(&mut self) {
+        let Some(from) = self
+            .search_origin
+            .or_else(|| self.current_jump_entry())
+            .map(|origin| origin.cursor_line)
+        else {
+            return;
+        };
+        let step = self.search_match_step();
+        let first = if self.search_backward {
+            while self.search_matches_capped
+                && self.search_matches.last().is_some_and(|&line| line < from)
+            {
+                self.extend_search_matches(step);
+            }
+            let at_or_before = self.search_matches.iter().rposition(|&line| line <= from);
+            if at_or_before.is_none() {
+                // Wrapping around to the last match in the document
+                self.extend_search_matches(usize::MAX);
+            }
+            at_or_before.or(self.search_matches.len().checked_sub(1))
+        } else {
+            let mut at_or_after = self.search_matches.iter().position(|&line| line >= from);
+            while at_or_after.is_none() && self.search_matches_capped {
+                let searched = self.search_matches.len();
+                self.extend_search_matches(step);
+                at_or_after = self.search_matches[searched..]
+                    .iter()
+                    .position(|&line| line >= from)
+                    .map(|idx| idx + searched);
+            }
+            at_or_after.or((!self.search_matches.is_empty()).then_some(0))
+        };
+        match first {
+            Some(idx) => self.goto_search_match(idx),
+            None => self.restore_search_origin(),
+        }
+    }
+
+    /// Make match `idx` the current one and center the line the search
+    /// offset leads to from it
+    fn goto_search_match(&mut self, idx: usize) {
+        let Some(&line) = self.search_matches.get(idx) else {
+            return;
+        };
+        self.search_current_match = Some(idx);
+        let last_line = self.doc.line_count().saturating_sub(1);
+        let target = line.saturating_add_signed(self.search_offset).min(last_line);
+        let pane_id = self.panes.focused;
+        self.goto(pane_id, target, crate::scroll_math::ScrollPolicy::Center);
+    }
+
+    /// Put the focused pane back where the search being typed started
+    fn restore_search_origin(&mut self) {
+        let Some(origin) = self.search_origin else {
+            return;
+        };
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.scroll_pos = origin.scroll_pos;
+            pane.view.cursor_line = origin.cursor_line;
+        }
+    }
+
+    /// Find the lines matching the current search query, without moving
+    /// the cursor. The scan stops after `search.max_matches`, leaving the
+    /// rest for navigation to find.
+    fn collect_search_matches(&mut self) {
+        self.search_matches.clear();
+        self.search_current_match = None;
+        self.search_matches_capped = false;
+        if self.search_query.is_empty() {
+            return;
+        }
+        self.search_highlight = true;
+        self.extend_search_matches(self.search_match_step());
+    }
+
+    /// Matches found per scan, from `search.max_matches`
+    fn search_match_step(&self) -> usize {
+        match self.config.search.max_matches {
+            0 => usize::MAX,
+            max => max,
+        }
+    }
+
+    /// Continue the match scan after the last match found, stopping once
+    /// `limit` more have turned up
+    fn extend_search_matches(&mut self, limit: usize) {
+        let (scope_start, scope_end) = self
+            .search_scope
+            .as_ref()
+            .map_or((0, usize::MAX), |scope| (scope.lines.start, scope.lines.end));
+        let start = self
+            .search_matches
+            .last()
+            .map_or(0, |&line| line + 1)
+            .max(scope_start);
+        let mut found = 0;
+        self.search_matches_capped = false;
+        for line_idx in start..self.doc.line_count().min(scope_end) {
+            if found == limit {
+                self.search_matches_capped = true;
+                break;
+            }
+            let mut line_text: String = self.doc.rope.line(line_idx).chunks().collect();
+            if !self.config.search.include_comments {
+                if self.doc.comments.iter().any(|range| range.contains(&line_idx)) {
+                    continue;
+                }
+                if line_text.contains("<!--") {
+                    line_text = mdx_core::html::strip_comments(&line_text);
+                }
+            }
+            if line_text.to_lowercase().contains(&self.search_query) {
+                self.search_matches.push(line_idx);
+                found += 1;
+            }
+        }
+    }
+
+    /// Whether a line is inside the search's scope (every line is when
+    /// the search isn't scoped)
+    pub fn search_in_scope(&self, line: usize) -> bool {
+        self.search_scope
+            .as_ref()
+            .is_none_or(|scope| scope.lines.contains(&line))
+    }
+
+    /// Status bar mark for a scoped search
+    pub fn search_scope_badge(&self) -> &'static str {
+        match self.search_scope.as_ref().map(|scope| scope.kind) {
+            Some(SearchScopeKind::Section) => " [SECTION]",
+            Some(SearchScopeKind::Selection) => " [SELECTION]",
+            None => "",
+        }
+    }
+
+    /// Number of matches for the status bar: `N+` while more lie past
+    /// the ones found so far
+    pub fn search_match_count(&self) -> String {
+        let more = if self.search_matches_capped { "+" } else { "" };
+        format!("{}{}", self.search_matches.len(), more)
+    }
+
+    /// Jump to next search match
+    pub fn next_search_match(&mut self, _viewport_height: usize) {
+        if self.search_matches.is_empty() {
+            return;
+        }
+
+        if let Some(current_idx) = self.search_current_match {
+            if current_idx + 1 == self.search_matches.len() && self.search_matches_capped {
+                self.extend_search_matches(self.search_match_step());
+            }
+            let next_idx = (current_idx + 1) % self.search_matches.len();
+            self.goto_search_match(next_idx);
+        }
+    }
+
+    /// Jump to previous search match
+    pub fn prev_search_match(&mut self, _viewport_height: usize) {
+        if self.search_matches.is_empty() {
+            return;
+        }
+
+        if let Some(current_idx) = self.search_current_match {
+            if current_idx == 0 && self.search_matches_capped {
+                // Wrapping around to the last match in the document
+                self.extend_search_matches(usize::MAX);
+            }
+            let prev_idx = if current_idx == 0 {
+                self.search_matches.len() - 1
+            } else {
+                current_idx - 1
+            };
+            self.goto_search_match(prev_idx);
+        }
+    }
+
+    /// Jump to the next match in the search's direction (`n`), or against
+    /// it (`N`)
+    pub fn repeat_search(&mut self, reverse: bool, viewport_height: usize) {
+        self.search_highlight = true;
+        if self.search_backward != reverse {
+            self.prev_search_match(viewport_height);
+        } else {
+            self.next_search_match(viewport_height);
+        }
+    }
+
+    /// Clear search
+    pub fn clear_search(&mut self) {
+        self.search_matches_capped = false;
+        self.search_input.clear();
+        self.search_offset = 0;
+        self.search_query.clear();
         self.search_matches.clear();
         self.search_current_match = None;
+    }
+
+    /// Say so and return true when the document is an old git revision
+    /// (`mdx --rev`), which can't be edited
+    fn refuse_read_only(&mut self) -> bool {
+        if self.doc.revision.is_none() {
+            return false;
+        }
+        let message = format!("{} is a read-only git revision", self.buffer_name(&self.doc));
+        self.set_error_message(message);
+        true
+    }
+
+    /// Enter insert mode with the edit cursor at the end of the cursor line
+    pub fn enter_insert_mode(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let Some(pane) = self.panes.focused_pane_mut() else {
+            return;
+        };
+        let line = pane.view.cursor_line.min(self.doc.line_count().saturating_sub(1));
+        pane.view.cursor_line = line;
+        pane.view.edit_col = line_len_chars(&self.doc.rope, line);
+        pane.view.selection = None;
+        pane.view.mode = Mode::Insert;
+    }
+
+    /// Leave insert mode and re-run the checks skipped while typing
+    pub fn exit_insert_mode(&mut self) {
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.mode = Mode::Normal;
+        }
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+    }
+
+    /// Rope character index of the edit cursor
+    fn edit_char_idx(&self) -> Option<usize> {
+        let view = &self.panes.focused_pane()?.view;
+        let line_start = self.doc.rope.line_to_char(view.cursor_line);
+        Some(line_start + view.edit_col.min(line_len_chars(&self.doc.rope, view.cursor_line)))
+    }
+
+    /// Replace a character range and move the edit cursor to `cursor`
+    fn apply_edit(&mut self, chars: std::ops::Range<usize>, text: &str, cursor: usize) {
+        self.doc.edit(chars, text);
+        let line = self.doc.rope.char_to_line(cursor);
+        let col = cursor - self.doc.rope.line_to_char(line);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.cursor_line = line;
+            pane.view.edit_col = col;
+        }
+        self.auto_scroll(20);
+        self.request_diff();
+    }
+
+    /// Insert text at the edit cursor
+    pub fn insert_text(&mut self, text: &str) {
+        if let Some(idx) = self.edit_char_idx() {
+            self.apply_edit(idx..idx, text, idx + text.chars().count());
+        }
+    }
+
+    /// Delete the character before the edit cursor, joining lines at the
+    /// start of a line
+    pub fn delete_char_before(&mut self) {
+        if let Some(idx) = self.edit_char_idx().filter(|&idx| idx > 0) {
+            // Treat CRLF as one character
+            let start = if self.doc.rope.char(idx - 1) == '\n'
+                && idx >= 2
+                && self.doc.rope.char(idx - 2) == '\r'
+            {
+                idx - 2
+            } else {
+                idx - 1
+            };
+            self.apply_edit(start..idx, "", start);
+        }
+    }
+
+    /// Delete the character under the edit cursor, joining the next line at
+    /// the end of a line
+    pub fn delete_char_at(&mut self) {
+        if let Some(idx) = self.edit_char_idx().filter(|&idx| idx < self.doc.rope.len_chars()) {
+            let end = if self.doc.rope.char(idx) == '\r'
+                && self.doc.rope.get_char(idx + 1) == Some('\n')
+            {
+                idx + 2
+            } else {
+                idx + 1
+            };
+            self.apply_edit(idx..end, "", idx);
+        }
+    }
+
+    /// Move the edit cursor by `delta` characters, wrapping across lines
+    pub fn move_edit_cursor(&mut self, delta: isize) {
+        let Some(idx) = self.edit_char_idx() else {
+            return;
+        };
+        let target = idx.saturating_add_signed(delta).min(self.doc.rope.len_chars());
+        let line = self.doc.rope.char_to_line(target);
+        let col = (target - self.doc.rope.line_to_char(line)).min(line_len_chars(&self.doc.rope, line));
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.cursor_line = line;
+            pane.view.edit_col = col;
+        }
+        self.auto_scroll(20);
+    }
+
+    /// Move the edit cursor to the start or end of its line
+    pub fn edit_cursor_to_line_edge(&mut self, end: bool) {
+        let rope = &self.doc.rope;
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.edit_col = if end {
+                line_len_chars(rope, pane.view.cursor_line)
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Move the edit cursor up or down a line, keeping its column if possible
+    pub fn move_edit_line(&mut self, down: bool) {
+        let last = self.doc.line_count().saturating_sub(1);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.cursor_line = if down {
+                (pane.view.cursor_line + 1).min(last)
+            } else {
+                pane.view.cursor_line.saturating_sub(1)
+            };
+        }
+        self.auto_scroll(20);
+    }
+
+    /// Start typing a `:` command. From visual line mode the command line
+    /// starts with `'<,'>` so the command applies to the selection.
+    pub fn enter_command_mode(&mut self) {
+        self.command_line.clear();
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            if pane.view.mode == Mode::VisualLine {
+                if let Some(selection) = pane.view.selection.take() {
+                    self.visual_marks = Some(selection.range());
+                    self.command_line.push_str("'<,'>");
+                }
+            }
+            pane.view.mode = Mode::Command;
+        }
+    }
+
+    /// Abandon the `:` command
+    pub fn cancel_command_mode(&mut self) {
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.mode = Mode::Normal;
+        }
+        self.command_line.clear();
+    }
+
+    /// Run the typed `:` command: `w`, `q`, `q!`, `wq`, `x`, `config
+    /// reload`, a buffer command (`ls`, `bn`, `bp`, `b N`, `bd`), or a
+    /// range command (`w FILE`, `!CMD`)
+    pub fn run_command_line(&mut self) {
+        let command = std::mem::take(&mut self.command_line);
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.mode = Mode::Normal;
+        }
+        let (range, command) = match self.parse_command_range(command.trim()) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                self.set_error_message(message);
+                return;
+            }
+        };
+        if let Some(shell_command) = command.strip_prefix('!') {
+            self.pipe_range(range, shell_command.trim());
+            return;
+        }
+        if let Some((force, path)) = command
+            .strip_prefix("w!")
+            .map(|rest| (true, rest))
+            .or_else(|| command.strip_prefix('w').map(|rest| (false, rest)))
+            .filter(|(_, rest)| rest.starts_with(char::is_whitespace))
+        {
+            self.write_range(range, path.trim(), force);
+            return;
+        }
+        if range.is_some() {
+            self.set_error_message(format!("Not a range command: {}", command));
+            return;
+        }
+        if let Some((force, args)) = command
+            .strip_prefix("export!")
+            .map(|rest| (true, rest))
+            .or_else(|| command.strip_prefix("export").map(|rest| (false, rest)))
+            .filter(|(_, rest)| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            self.export_table(args.trim(), force);
+            return;
+        }
+        match command {
+            "" => {}
+            "w" => {
+                self.save_document();
+            }
+            "w!" => {
+                self.write_document(true);
+            }
+            "wq" | "x" => {
+                if self.save_document() {
+                    self.quit();
+                }
+            }
+            "q" => self.confirm_quit(),
+            "q!" => self.quit(),
+            "bn" | "bnext" => self.next_buffer(),
+            "bp" | "bprevious" => self.prev_buffer(),
+            "ls" | "buffers" => self.open_buffer_picker(),
+            "config reload" => self.reload_config(),
+            "imagecache" => self.show_image_cache_stats = true,
+            "log" => self.open_log_viewer(),
+            "anchor" => self.yank_heading_anchor(),
+            #[cfg(feature = "git")]
+            "GitLog" | "gitlog" => self.open_git_log(),
+            "noh" | "nohlsearch" => self.search_highlight = false,
+            "fmt" => self.format_document(),
+            other => {
+                if !self.run_buffer_command(other) {
+                    self.set_error_message(format!("Not a command: {}", other));
+                }
+            }
+        }
+    }
+
+    /// Split a leading line range off a `:` command: `'<,'>` (the last
+    /// visual selection), `%` (the whole document), or `N,M` / `N`
+    /// (1-based). Returns the 0-based inclusive range and the rest.
+    fn parse_command_range<'a>(
+        &self,
+        command: &'a str,
+    ) -> Result<(Option<LineRange>, &'a str), String> {
+        if let Some(rest) = command.strip_prefix("'<,'>") {
+            let marks = self.visual_marks.ok_or("No visual selection")?;
+            return Ok((Some(marks), rest.trim_start()));
+        }
+        let last = self.last_content_line();
+        if let Some(rest) = command.strip_prefix('%') {
+            return Ok((Some((0, last)), rest.trim_start()));
+        }
+
+        let digits = |text: &'a str| {
+            let len = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+            (text[..len].parse::<usize>().ok(), &text[len..])
+        };
+        let (Some(first), rest) = digits(command) else {
+            return Ok((None, command));
+        };
+        let (second, rest) = match rest.strip_prefix(',') {
+            Some(rest) => match digits(rest) {
+                (Some(second), rest) => (second, rest),
+                (None, _) => return Err(format!("Invalid range: {}", command)),
+            },
+            None => (first, rest),
+        };
+        if first == 0 || second < first || second > last + 1 {
+            return Err(format!("Invalid range: {},{}", first, second));
+        }
+        Ok((Some((first - 1, second - 1)), rest.trim_start()))
+    }
 
-        // Find all matching lines
-        let line_count = self.doc.line_count();
-        for line_idx in 0..line_count {
-            let line_text: String = self.doc.rope.line(line_idx).chunks().collect();
-            if line_text.to_lowercase().contains(&self.search_query) {
-                self.search_matches.push(line_idx);
-            }
+    /// Index of the last line with content, not counting the empty line
+    /// after a trailing newline
+    fn last_content_line(&self) -> usize {
+        let count = self.doc.line_count();
+        if count > 1 && self.doc.rope.line(count - 1).len_chars() == 0 {
+            count - 2
+        } else {
+            count.saturating_sub(1)
         }
+    }
 
-        // Jump to first match if any
-        if !self.search_matches.is_empty() {
-            self.search_current_match = Some(0);
-            let first_match = self.search_matches[0];
-            let bounds = self.rendered_content_bounds();
-            if let Some(pane) = self.panes.focused_pane_mut() {
-                pane.view.cursor_line = first_match.clamp(bounds.0, bounds.1);
-            }
+    /// The given range, else the section around the cursor, else the whole
+    /// document
+    fn range_or_section(&self, range: Option<LineRange>) -> LineRange {
+        range
+            .or_else(|| {
+                let line = self.panes.focused_pane()?.view.cursor_line;
+                mdx_core::selection::text_object(
+                    &self.doc,
+                    line,
+                    mdx_core::selection::TextObject::Section,
+                    false,
+                )
+            })
+            .unwrap_or((0, self.last_content_line()))
+    }
+
+    /// Text of lines `start..=end`, ending in a newline
+    fn range_text(&self, (start, end): LineRange) -> String {
+        let mut text = self.doc.get_lines(start, end);
+        if !text.ends_with('\n') {
+            text.push('\n');
         }
+        text
     }
 
-    /// Jump to next search match
-    pub fn next_search_match(&mut self, _viewport_height: usize) {
-        if self.search_matches.is_empty() {
+    /// `:[range]w FILE` - write lines to a file (`w!` overwrites)
+    pub fn write_range(&mut self, range: Option<LineRange>, path: &str, force: bool) {
+        let path = std::path::Path::new(path);
+        if path.exists() && !force {
+            self.set_error_message(format!("{} exists (add ! to overwrite)", path.display()));
             return;
         }
+        let range = self.range_or_section(range);
+        match std::fs::write(path, self.range_text(range)) {
+            Ok(()) => self.set_info_message(format!(
+                "Wrote {} lines to {}",
+                range.1 - range.0 + 1,
+                path.display()
+            )),
+            Err(e) => self.set_error_message(format!("Write failed: {}", e)),
+        }
+    }
 
-        if let Some(current_idx) = self.search_current_match {
-            let next_idx = (current_idx + 1) % self.search_matches.len();
-            self.search_current_match = Some(next_idx);
-            let match_line = self.search_matches[next_idx];
-            let pane_id = self.panes.focused;
-            self.goto(pane_id, match_line, crate::scroll_math::ScrollPolicy::Center);
+    /// `:[range]!CMD` - pipe lines through a shell command and show its
+    /// output. Refused while `security.no_exec` is set.
+    pub fn pipe_range(&mut self, range: Option<LineRange>, command: &str) {
+        if self.config.security.no_exec {
+            self.set_error_message("Piping to commands is disabled (security.no_exec = true)");
+            return;
+        }
+        if command.is_empty() {
+            self.set_error_message("Command cannot be empty");
+            return;
         }
+        let input = self.range_text(self.range_or_section(range));
+        self.command_output = Some(CommandOutput {
+            command: command.to_string(),
+            output: self.execute_shell_command(command, &input),
+        });
     }
 
-    /// Jump to previous search match
-    pub fn prev_search_match(&mut self, _viewport_height: usize) {
-        if self.search_matches.is_empty() {
+    /// Normalize the document's Markdown the way `mdx fmt` does (`:fmt`),
+    /// as an unsaved edit. Panes stay on the text they showed.
+    pub fn format_document(&mut self) {
+        if self.refuse_read_only() {
+            return;
+        }
+        let before = self.doc.rope.clone();
+        let text = before.to_string();
+        let options = mdx_core::format::FormatOptions {
+            width: self.config.format.width,
+            flavor: self.config.render.markdown.flavor,
+        };
+        let formatted = mdx_core::format::format_markdown(&text, &options);
+        if formatted == text {
+            self.set_info_message("Already formatted");
             return;
         }
 
-        if let Some(current_idx) = self.search_current_match {
-            let prev_idx = if current_idx == 0 {
-                self.search_matches.len() - 1
-            } else {
-                current_idx - 1
-            };
-            self.search_current_match = Some(prev_idx);
-            let match_line = self.search_matches[prev_idx];
-            let pane_id = self.panes.focused;
-            self.goto(pane_id, match_line, crate::scroll_math::ScrollPolicy::Center);
+        self.doc.edit(0..before.len_chars(), &formatted);
+        for pane in self.panes.panes.values_mut() {
+            let follow = |line| crate::editor::follow_line(&before, &self.doc.rope, line);
+            pane.view.set_scroll_line(follow(pane.view.scroll_line()));
+            pane.view.cursor_line = follow(pane.view.cursor_line);
+            pane.view.selection = None;
         }
+        self.auto_scroll(20);
+        self.refresh_front_matter_info();
+        self.refresh_link_diagnostics();
+        self.refresh_transclusions();
+        self.refresh_spell_check();
+        self.collect_search_matches();
+        self.request_diff();
+        self.set_info_message("Formatted (:w to save)");
     }
 
-    /// Clear search
-    pub fn clear_search(&mut self) {
-        self.search_query.clear();
-        self.search_matches.clear();
-        self.search_current_match = None;
+    /// Write in-app edits to disk, reporting the outcome in the status bar
+    pub fn save_document(&mut self) -> bool {
+        self.write_document(false)
+    }
+
+    /// Write the document to disk; `force` (`:w!`) writes over changes
+    /// made on disk since it was loaded
+    fn write_document(&mut self, force: bool) -> bool {
+        let saved = if force {
+            self.doc.overwrite()
+        } else {
+            self.doc.save()
+        };
+        match saved {
+            Ok(()) => {
+                self.set_info_message(format!(
+                    "Wrote {} lines to {}",
+                    self.doc.line_count(),
+                    self.doc.path.display()
+                ));
+                true
+            }
+            Err(e) => {
+                self.set_error_message(format!("Save failed: {:#}", e));
+                false
+            }
+        }
     }
 
-    /// Enter search mode
+    /// Enter search mode (`/`), remembering where the search starts
     pub fn enter_search_mode(&mut self) {
+        self.start_search(false);
+    }
+
+    /// Enter search mode searching upwards (`?`)
+    pub fn enter_backward_search_mode(&mut self) {
+        self.start_search(true);
+    }
+
+    /// Enter search mode searching only the visual selection or, outside
+    /// visual mode, the cursor's section and its subsections (`g/`)
+    pub fn enter_scoped_search_mode(&mut self) {
+        let Some(pane) = self.panes.focused_pane() else {
+            return;
+        };
+        let selection = pane
+            .view
+            .selection
+            .as_ref()
+            .filter(|_| pane.view.mode == Mode::VisualLine)
+            .map(|selection| selection.range());
+        let scope = match selection {
+            Some((start, end)) => SearchScope {
+                lines: start..end + 1,
+                kind: SearchScopeKind::Selection,
+            },
+            None => {
+                let cursor = pane.view.cursor_line;
+                let headings = &self.doc.headings;
+                let lines = match headings.iter().rposition(|h| h.line <= cursor) {
+                    Some(idx) => mdx_core::outline::section_lines(&self.doc.rope, headings, idx),
+                    // Before the first heading: the preamble
+                    None => 0..headings.first().map_or(self.doc.line_count(), |h| h.line),
+                };
+                SearchScope {
+                    lines,
+                    kind: SearchScopeKind::Section,
+                }
+            }
+        };
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            pane.view.selection = None;
+        }
+        self.start_search(false);
+        self.search_scope = Some(scope);
+    }
+
+    fn start_search(&mut self, backward: bool) {
+        self.search_origin = self.current_jump_entry();
+        self.search_backward = backward;
+        self.search_scope = None;
         if let Some(pane) = self.panes.focused_pane_mut() {
             pane.view.mode = Mode::Search;
         }
+        self.search_input.clear();
         self.search_query.clear();
     }
 
@@ -1784,18 +5127,58 @@ impl App {
         if let Some(pane) = self.panes.focused_pane_mut() {
             pane.view.mode = Mode::Normal;
         }
+        self.search_origin = None;
+    }
+
+    /// Run the typed search (Enter). The position it started from goes on
+    /// the jump list if the cursor moved.
+    pub fn confirm_search(&mut self) {
+        if !self.config.search.incremental && !self.search_query.is_empty() {
+            self.goto_first_search_match();
+        }
+        if let Some(origin) = self.search_origin {
+            let moved = self
+                .current_jump_entry()
+                .is_some_and(|now| now.cursor_line != origin.cursor_line);
+            if moved {
+                self.push_jump_entry(origin);
+            }
+        }
+        self.exit_search_mode();
+    }
+
+    /// Abandon the typed search (Esc) and go back to where it started
+    pub fn cancel_search(&mut self) {
+        self.clear_search();
+        self.search_scope = None;
+        self.restore_search_origin();
+        self.exit_search_mode();
     }
 
     /// Add character to search query
     pub fn search_add_char(&mut self, c: char) {
-        self.search_query.push(c);
-        self.search(&self.search_query.clone());
+        self.search_input.push(c);
+        self.update_search();
     }
 
     /// Remove last character from search query
     pub fn search_backspace(&mut self) {
-        self.search_query.pop();
-        self.search(&self.search_query.clone());
+        self.search_input.pop();
+        self.update_search();
+    }
+
+    /// Follow an edit of the typed search: highlight its matches and,
+    /// with `search.incremental`, preview the first one
+    fn update_search(&mut self) {
+        let delimiter = if self.search_backward { '?' } else { '/' };
+        let (query, offset) = parse_search_input(&self.search_input, delimiter);
+        self.search_offset = offset;
+        if self.config.search.incremental || query.is_empty() {
+            self.search(&query);
+        } else {
+            self.search_query = query.to_lowercase();
+            self.collect_search_matches();
+        }
     }
 
     // ===== Collapse/Fold Operations =====
@@ -1845,9 +5228,44 @@ impl App {
         }
     }
 
+    /// Start line of the `<details>` block whose header the cursor is on
+    fn details_block_at_cursor(&self) -> Option<usize> {
+        let pane = self.panes.focused_pane()?;
+        crate::collapse::details_block_at(pane.view.cursor_line, &self.doc).map(|d| d.start)
+    }
+
+    /// Collapse or expand the `<details>` block under the cursor. Returns
+    /// false when the cursor is not on a details header.
+    fn set_details_at_cursor(&mut self, collapsed: Option<bool>) -> bool {
+        let Some(start) = self.details_block_at_cursor() else {
+            return false;
+        };
+        let Some(block) = self.doc.details.iter().find(|d| d.start == start) else {
+            return false;
+        };
+        let default_collapsed = !block.open;
+        if let Some(pane) = self.panes.focused_pane_mut() {
+            let currently_collapsed =
+                default_collapsed != pane.view.toggled_details.contains(&start);
+            let want_collapsed = collapsed.unwrap_or(!currently_collapsed);
+            if want_collapsed == default_collapsed {
+                pane.view.toggled_details.remove(&start);
+            } else {
+                pane.view.toggled_details.insert(start);
+            }
+            // Keep the cursor on the visible header line
+            pane.view.cursor_line = start;
+        }
+        true
+    }
+
     /// Toggle collapse at cursor (collapse if expanded, expand if collapsed)
-    /// Works on the heading at cursor, or the nearest heading above
+    /// Works on the `<details>` block or heading at cursor, or the nearest
+    /// heading above
     pub fn toggle_collapse_at_cursor(&mut self) {
+        if self.set_details_at_cursor(None) {
+            return;
+        }
         // Get cursor line and find target heading first
         let target_heading = if let Some(pane) = self.panes.focused_pane() {
             let cursor_line = pane.view.cursor_line;
@@ -1877,6 +5295,9 @@ impl App {
 
     /// Expand (open) fold at cursor or nearest heading above
     pub fn expand_at_cursor(&mut self) {
+        if self.set_details_at_cursor(Some(false)) {
+            return;
+        }
         // Get cursor line and find target heading first
         let target_heading = if let Some(pane) = self.panes.focused_pane() {
             let cursor_line = pane.view.cursor_line;
@@ -1901,6 +5322,9 @@ impl App {
 
     /// Collapse (close) fold at cursor or nearest heading above
     pub fn collapse_at_cursor(&mut self) {
+        if self.set_details_at_cursor(Some(true)) {
+            return;
+        }
         // Get cursor line and find target heading first
         let target_heading = if let Some(pane) = self.panes.focused_pane() {
             let cursor_line = pane.view.cursor_line;
@@ -1937,6 +5361,16 @@ impl App {
                     pane.view.collapsed_headings.insert(heading.line);
                 }
             }
+            if max_level.is_none() {
+                // Close every `<details>` block that is open by default
+                pane.view.toggled_details = self
+                    .doc
+                    .details
+                    .iter()
+                    .filter(|d| d.open)
+                    .map(|d| d.start)
+                    .collect();
+            }
         }
     }
 
@@ -1944,6 +5378,14 @@ impl App {
     pub fn expand_all_headings(&mut self) {
         if let Some(pane) = self.panes.focused_pane_mut() {
             pane.view.collapsed_headings.clear();
+            // Open every `<details>` block that is closed by default
+            pane.view.toggled_details = self
+                .doc
+                .details
+                .iter()
+                .filter(|d| !d.open)
+                .map(|d| d.start)
+                .collect();
         }
     }
 }
@@ -1968,6 +5410,799 @@ mod tests {
         doc
     }
 
+    #[test]
+    fn test_broken_link_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Doc\n\n[fine](doc.md)\n\n[gone](gone.md)\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+
+        assert_eq!(app.broken_links.len(), 1);
+        assert_eq!(app.broken_links_on_line(4).count(), 1);
+        assert_eq!(app.broken_links_on_line(2).count(), 0);
+
+        app.toggle_link_diagnostics();
+        assert!(app.show_link_diagnostics);
+        app.link_diagnostics_jump_to_selected();
+        assert!(!app.show_link_diagnostics);
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 4);
+
+        // Fixing the target clears the diagnostic on reload
+        std::fs::write(dir.path().join("gone.md"), "x").unwrap();
+        app.reload_document().unwrap();
+        assert!(app.broken_links.is_empty());
+
+        let mut config = Config::default();
+        config.links.check = false;
+        std::fs::remove_file(dir.path().join("gone.md")).unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let app = App::new(config, doc, vec![]);
+        assert!(app.broken_links.is_empty());
+    }
+
+    #[test]
+    fn test_wiki_page_links_checked_on_worker() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Doc\n\n[[doc]] [[#Doc]]\n\n[[Nowhere]]\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        assert!(app.broken_links.is_empty(), "pages are looked for off the UI thread");
+
+        let result = app
+            .link_worker
+            .as_ref()
+            .and_then(|w| {
+                (0..500).find_map(|_| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    w.try_recv_result()
+                })
+            })
+            .expect("link worker result");
+        app.apply_link_results(result);
+        assert_eq!(app.broken_links.len(), 1);
+        assert_eq!(app.broken_links_on_line(4).count(), 1);
+    }
+
+    #[test]
+    fn test_set_config_applies_live() {
+        let mut app = App::new(Config::default(), create_test_doc(10), vec![]);
+        let mut config = Config::default();
+        config.theme = mdx_core::config::ThemeVariant::Light;
+        config.toc.enabled = !app.show_toc;
+        app.set_config(config);
+        assert_eq!(app.theme_variant, mdx_core::config::ThemeVariant::Light);
+        assert_eq!(app.show_toc, app.config.toc.enabled);
+
+        // --insecure survives a reload from disk
+        app.insecure = true;
+        app.command_line = "config reload".to_string();
+        app.run_command_line();
+        if matches!(app.status_message, Some((_, StatusMessageKind::Info))) {
+            assert!(!app.config.security.safe_mode);
+        }
+    }
+
+    #[test]
+    fn test_scrolloff_and_smooth_page_jumps() {
+        let mut config = Config::default();
+        config.render.scrolloff = 3;
+        config.render.smooth_scroll = true;
+        let mut app = App::new(config, create_test_doc(100), vec![]);
+        let view = |app: &App| app.panes.focused_pane().unwrap().view.clone();
+
+        // Three lines stay visible below the cursor
+        app.move_cursor_down(17);
+        app.auto_scroll(20);
+        assert_eq!(view(&app).scroll_line(), 1);
+
+        // A page jump leaves the view where it was until the animation runs
+        app.smooth_scroll(|app| {
+            app.move_cursor_down(40);
+            app.auto_scroll(20);
+        });
+        assert_eq!(view(&app).cursor_line, 57);
+        assert_eq!(view(&app).scroll_line(), 1);
+        let animation = app.scroll_animation.expect("animating");
+        assert_eq!(animation.to.source_line, 41);
+
+        app.scroll_animation.as_mut().unwrap().started -= SMOOTH_SCROLL_DURATION / 2;
+        app.step_scroll_animation();
+        let halfway = view(&app).scroll_line();
+        assert!(halfway > 1 && halfway < 41, "{}", halfway);
+
+        app.finish_scroll_animation();
+        assert_eq!(view(&app).scroll_line(), 41);
+        assert!(app.scroll_animation.is_none());
+
+        // Without smooth_scroll the jump is immediate
+        app.config.render.smooth_scroll = false;
+        app.smooth_scroll(|app| {
+            app.move_cursor_up(40);
+            app.auto_scroll(20);
+        });
+        assert_eq!(view(&app).scroll_line(), 14);
+        assert!(app.scroll_animation.is_none());
+    }
+
+    #[test]
+    fn test_view_scrolling_moves_view_with_or_without_cursor() {
+        let mut app = App::new(Config::default(), create_test_doc(100), vec![]);
+        let view = |app: &App| app.panes.focused_pane().unwrap().view.clone();
+
+        // Ctrl-d moves the view along with the cursor
+        app.move_cursor_down(4);
+        app.scroll_half_page_down(20, 80);
+        assert_eq!(view(&app).scroll_line(), 10);
+        assert_eq!(view(&app).cursor_line, 14);
+        app.scroll_half_page_up(20, 80);
+        assert_eq!(view(&app).scroll_line(), 0);
+        assert_eq!(view(&app).cursor_line, 4);
+
+        // Ctrl-e leaves the cursor until it would scroll off the top
+        app.scroll_view(3, true, 20);
+        assert_eq!(view(&app).scroll_line(), 3);
+        assert_eq!(view(&app).cursor_line, 4);
+        app.scroll_view(5, true, 20);
+        assert_eq!(view(&app).scroll_line(), 8);
+        assert_eq!(view(&app).cursor_line, 8);
+
+        // Ctrl-y pulls it up from the bottom, keeping the scrolloff margin
+        app.config.render.scrolloff = 2;
+        app.move_cursor_down(19);
+        app.scroll_view(4, false, 20);
+        assert_eq!(view(&app).scroll_line(), 4);
+        assert_eq!(view(&app).cursor_line, 21);
+    }
+
+    #[test]
+    fn test_session_saves_and_restores_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        let text: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &text).unwrap();
+        let session = dir.path().join("state").join("session.json");
+        let open = || {
+            let (doc, _warnings) = Document::load(&path).unwrap();
+            let mut app = App::new(Config::default(), doc, vec![]);
+            app.session_path = Some(session.clone());
+            app
+        };
+
+        let mut app = open();
+        app.restore_session();
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+        app.jump_to_line(12);
+        app.push_jump();
+        app.jump_to_line(30);
+
+        // Nothing is written until the interval has passed
+        app.session_dirty = true;
+        app.autosave_session();
+        assert!(!session.exists());
+        app.session_saved -= std::time::Duration::from_secs(app.config.session.autosave_secs);
+        app.autosave_session();
+        assert!(session.exists());
+        assert!(!app.session_dirty);
+
+        let mut app = open();
+        app.restore_session();
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 30);
+        assert!(app.jump_back());
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 12);
+
+        // A corrupt file is set aside and reported
+        std::fs::write(&session, "{").unwrap();
+        let mut app = open();
+        app.restore_session();
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+        assert!(app.status_message.is_some());
+        app.save_session();
+        assert!(mdx_core::session::SessionState::load(&session).is_ok());
+    }
+
+    #[test]
+    fn test_insert_mode_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Titel\r\nsecond\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+
+        app.enter_insert_mode();
+        let view = &app.panes.focused_pane().unwrap().view;
+        assert_eq!((view.mode, view.edit_col), (Mode::Insert, 7));
+
+        app.move_edit_cursor(-1);
+        app.delete_char_before();
+        app.move_edit_cursor(1);
+        app.insert_text("e");
+        assert_eq!(app.doc.rope.line(0).to_string(), "# Title\r\n");
+        assert_eq!(app.doc.headings[0].text, "Title");
+
+        // Joining lines removes the whole CRLF
+        app.move_edit_line(true);
+        app.edit_cursor_to_line_edge(false);
+        app.delete_char_before();
+        assert_eq!(app.doc.rope.to_string(), "# Titlesecond\n");
+        assert_eq!(app.panes.focused_pane().unwrap().view.edit_col, 7);
+
+        app.insert_text("\n");
+        app.delete_char_at();
+        assert_eq!(app.doc.rope.to_string(), "# Title\necond\n");
+        assert!(app.doc.modified);
+
+        app.exit_insert_mode();
+        app.command_line = "q".to_string();
+        app.run_command_line();
+        assert!(!app.should_quit, "unsaved edits block :q");
+        app.answer_confirm(ConfirmAnswer::Cancel);
+        assert!(!app.should_quit);
+
+        app.command_line = "wq".to_string();
+        app.run_command_line();
+        assert!(app.should_quit);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Title\necond\n");
+    }
+
+    #[test]
+    fn test_write_over_changes_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Title\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        app.doc.edit(0..0, "mine\n");
+
+        // The watcher saw another program write the file
+        std::fs::write(&path, "theirs\n").unwrap();
+        app.doc.dirty_on_disk = true;
+        app.command_line = "w".to_string();
+        app.run_command_line();
+        assert!(matches!(app.status_message, Some((_, StatusMessageKind::Error))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "theirs\n");
+
+        app.command_line = "w!".to_string();
+        app.run_command_line();
+        assert!(!app.doc.modified);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "mine\n# Title\n");
+    }
+
+    #[test]
+    fn test_open_at_location() {
+        use crate::location::Location;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        let mut text: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+        text.push_str("## Getting Started\n\nmore\n");
+        std::fs::write(&path, &text).unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+
+        app.open_at(&Location::Line(21));
+        let view = &app.panes.focused_pane().unwrap().view;
+        assert_eq!(view.cursor_line, 20);
+        assert_eq!(view.scroll_line(), 20 - OPEN_AT_CONTEXT_LINES);
+
+        app.open_at(&Location::Anchor("getting-started".to_string()));
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 40);
+
+        app.open_at(&Location::Anchor("missing".to_string()));
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 40);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_buffers_keep_their_own_view() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.md");
+        let second = dir.path().join("second.md");
+        std::fs::write(&first, "# First\n\none\ntwo\nthree\n").unwrap();
+        std::fs::write(&second, "# Second\n\nalpha\n").unwrap();
+        let (doc, _warnings) = Document::load(&first).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        app.add_buffer(Document::load(&second).unwrap().0);
+        assert_eq!(app.buffer_names(), vec!["first.md", "second.md"]);
+
+        app.jump_to_line(3);
+        app.next_buffer();
+        assert_eq!(app.active_buffer, 1);
+        assert_eq!(app.doc.headings[0].text, "Second");
+        assert_eq!(app.doc_id, 1);
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+
+        // Wraps around, and the first document's cursor was kept
+        app.next_buffer();
+        assert_eq!(app.active_buffer, 0);
+        assert_eq!(app.doc.headings[0].text, "First");
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 3);
+
+        app.prev_buffer();
+        assert_eq!(app.active_buffer, 1);
+        app.open_buffer_picker();
+        app.buffer_picker_move_up();
+        app.buffer_picker_open_selected();
+        assert_eq!(app.active_buffer, 0);
+        assert!(!app.show_buffer_picker);
+    }
+
+    #[test]
+    fn test_quit_blocked_by_edits_in_other_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.md");
+        let second = dir.path().join("second.md");
+        std::fs::write(&first, "first\n").unwrap();
+        std::fs::write(&second, "second\n").unwrap();
+        let mut app = App::new(Config::default(), Document::load(&first).unwrap().0, vec![]);
+        app.add_buffer(Document::load(&second).unwrap().0);
+
+        app.enter_insert_mode();
+        app.insert_text("x");
+        app.exit_insert_mode();
+        app.next_buffer();
+
+        app.command_line = "q".to_string();
+        app.run_command_line();
+        assert!(!app.should_quit);
+        let prompt = app.confirm.clone().unwrap();
+        assert_eq!(prompt.action, ConfirmAction::Quit);
+        assert!(prompt.question.contains("first.md"), "{}", prompt.question);
+
+        // Yes saves the other buffer before quitting
+        app.answer_confirm(ConfirmAnswer::Yes);
+        assert!(app.should_quit);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "firstx\n");
+    }
+
+    #[test]
+    fn test_confirm_reload_over_unsaved_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "one\n").unwrap();
+        let mut app = App::new(Config::default(), Document::load(&path).unwrap().0, vec![]);
+        app.enter_insert_mode();
+        app.insert_text("x");
+        app.exit_insert_mode();
+
+        app.confirm_reload();
+        assert_eq!(app.confirm.as_ref().unwrap().action, ConfirmAction::Reload);
+        app.answer_confirm(ConfirmAnswer::No);
+        assert!(app.confirm.is_none());
+        assert!(app.doc.modified, "no keeps the edits");
+
+        app.confirm_reload();
+        app.answer_confirm(ConfirmAnswer::Yes);
+        assert!(!app.doc.modified);
+        assert_eq!(app.doc.rope.to_string(), "one\n");
+
+        // With nothing to lose, there is nothing to ask
+        app.confirm_reload();
+        assert!(app.confirm.is_none());
+        app.confirm_quit();
+        assert!(app.confirm.is_none());
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_close_buffer_and_switch_by_number() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = ["one.md", "two.md", "three.md"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                std::fs::write(&path, format!("# {}\n", name)).unwrap();
+                path
+            })
+            .collect();
+        let mut app = App::new(Config::default(), Document::load(&paths[0]).unwrap().0, vec![]);
+        app.add_buffer(Document::load(&paths[1]).unwrap().0);
+        app.add_buffer(Document::load(&paths[2]).unwrap().0);
+
+        app.command_line = "b 3".to_string();
+        app.run_command_line();
+        assert_eq!(app.active_buffer, 2);
+
+        // Edits block closing until forced
+        app.enter_insert_mode();
+        app.insert_text("x");
+        app.exit_insert_mode();
+        app.command_line = "bd".to_string();
+        app.run_command_line();
+        assert_eq!(app.buffer_count(), 3);
+        app.command_line = "bd!".to_string();
+        app.run_command_line();
+        assert_eq!(app.buffer_names(), vec!["one.md", "two.md"]);
+        assert_eq!(app.active_buffer, 1);
+        assert_eq!(app.doc.headings[0].text, "two.md");
+
+        // Closing a buffer before the shown one keeps showing the same file
+        app.close_buffer(0, false);
+        assert_eq!(app.buffer_names(), vec!["two.md"]);
+        assert_eq!(app.active_buffer, 0);
+        assert_eq!(app.doc.headings[0].text, "two.md");
+
+        app.close_buffer(0, true);
+        assert_eq!(app.buffer_count(), 1);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_directory_changes_add_and_flag_buffers() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::create_dir(root.join("guide")).unwrap();
+        std::fs::write(root.join("a.md"), "# A\n").unwrap();
+        std::fs::write(root.join("guide/b.md"), "# B\n").unwrap();
+        let mut config = Config::default();
+        config.watch.enabled = false;
+        config.watch.auto_reload = true;
+        let mut app = App::new(config, Document::load(&root.join("a.md")).unwrap().0, vec![]);
+        app.add_buffer(Document::load(&root.join("guide/b.md")).unwrap().0);
+        app.set_directory(root.clone());
+        assert_eq!(app.buffer_names(), vec!["a.md", "guide/b.md"]);
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(root.join("guide/b.md"), "# B2\n").unwrap();
+        std::fs::write(root.join("c.md"), "# C\n").unwrap();
+        app.apply_directory_changes(vec![root.join("guide/b.md"), root.join("c.md")]);
+
+        assert_eq!(app.buffer_names(), vec!["a.md", "guide/b.md", "c.md"]);
+        assert!(app.buffer_changed(1));
+        assert!(!app.buffer_changed(2));
+        assert_eq!(
+            app.status_message.as_ref().map(|(text, _)| text.as_str()),
+            Some("New file: c.md")
+        );
+
+        // The hidden buffer was reloaded, and showing it clears the flag
+        app.switch_buffer(1);
+        assert_eq!(app.doc.headings[0].text, "B2");
+        assert!(!app.doc.dirty_on_disk);
+        assert!(!app.buffer_changed(1));
+    }
+
+    #[test]
+    fn test_load_error_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# One\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+
+        // A failed reload keeps the old text behind the error screen
+        std::fs::remove_file(&path).unwrap();
+        assert!(app.reload_document().is_err());
+        let error = app.load_error.as_ref().unwrap();
+        assert!(error.chain[0].contains("Failed to reload file"));
+        assert_eq!(app.doc.headings.len(), 1);
+
+        app.retry_load();
+        assert!(app.load_error.is_some());
+        std::fs::write(&path, "# One\n# Two\n").unwrap();
+        app.retry_load();
+        assert!(app.load_error.is_none());
+        assert_eq!(app.doc.headings.len(), 2);
+
+        // A file that never loaded is opened afresh on retry
+        let missing = dir.path().join("later.md");
+        let mut app = App::new(Config::default(), Document::unloaded(&missing, 0), vec![]);
+        app.load_error = Some(LoadError::new(&missing, &anyhow::anyhow!("missing")));
+        std::fs::write(&missing, "# Later\n").unwrap();
+        app.retry_load();
+        assert!(app.load_error.is_none());
+        assert_eq!(app.doc.headings[0].text, "Later");
+    }
+
+    #[test]
+    fn test_reload_after_editor_moves_to_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        let text: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &text).unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        app.jump_to_line(5);
+        let before = app.doc.rope.clone();
+
+        // Untouched file: nothing to do
+        app.reload_after_editor(&before);
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 5);
+        let rev = app.doc.rev;
+
+        std::fs::write(&path, text.replace("line 30\n", "edited\n")).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        app.reload_after_editor(&before);
+        assert!(app.doc.rev > rev);
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 30);
+        assert_eq!(app.doc.rope.line(30).to_string(), "edited\n");
+    }
+
+    #[test]
+    fn test_write_range_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# One\n\nfirst\n\n# Two\n\nsecond\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        let out = dir.path().join("out.md");
+        let run = |app: &mut App, command: String| {
+            app.command_line = command;
+            app.run_command_line();
+        };
+
+        // No range: the section under the cursor
+        app.panes.focused_pane_mut().unwrap().view.cursor_line = 6;
+        run(&mut app, format!("w {}", out.display()));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "# Two\n\nsecond\n");
+
+        // Existing files need w!
+        run(&mut app, format!("1,3w {}", out.display()));
+        assert!(matches!(app.status_message, Some((_, StatusMessageKind::Error))));
+        run(&mut app, format!("1,3w! {}", out.display()));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "# One\n\nfirst\n");
+        run(&mut app, format!("%w! {}", out.display()));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), app.doc.rope.to_string());
+
+        // `:` from visual mode targets the selection
+        app.panes.focused_pane_mut().unwrap().view.cursor_line = 2;
+        app.enter_visual_line_mode();
+        app.move_cursor_down(2);
+        app.enter_command_mode();
+        assert_eq!(app.command_line, "'<,'>");
+        app.command_line.push_str(&format!("w! {}", out.display()));
+        app.run_command_line();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "first\n\n# Two\n");
+        assert_eq!(app.panes.focused_pane().unwrap().view.mode, Mode::Normal);
+
+        run(&mut app, "0,2w x.md".to_string());
+        assert!(matches!(app.status_message, Some((_, StatusMessageKind::Error))));
+    }
+
+    #[test]
+    fn test_export_table_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        let text = "# Data\n\n| City | Note |\n|---|---|\n| Oslo | cold, dark |\n";
+        std::fs::write(&path, text).unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        let run = |app: &mut App, command: String| {
+            app.command_line = command;
+            app.run_command_line();
+        };
+        let csv = dir.path().join("out.csv");
+        let tsv = dir.path().join("out.tsv");
+
+        run(&mut app, format!("export {}", csv.display()));
+        assert!(matches!(app.status_message, Some((ref m, _)) if m == "Not in a table"));
+        assert!(!csv.exists());
+
+        // The format follows the extension unless one is named
+        app.panes.focused_pane_mut().unwrap().view.cursor_line = 4;
+        run(&mut app, format!("export {}", csv.display()));
+        assert_eq!(std::fs::read_to_string(&csv).unwrap(), "City,Note\nOslo,\"cold, dark\"\n");
+        run(&mut app, format!("export {}", tsv.display()));
+        assert_eq!(std::fs::read_to_string(&tsv).unwrap(), "City\tNote\nOslo\tcold, dark\n");
+
+        // Existing files need export!
+        run(&mut app, format!("export tsv {}", csv.display()));
+        assert!(matches!(app.status_message, Some((_, StatusMessageKind::Error))));
+        run(&mut app, format!("export! tsv {}", csv.display()));
+        assert_eq!(std::fs::read_to_string(&csv).unwrap(), "City\tNote\nOslo\tcold, dark\n");
+
+        app.prompt_table_export();
+        assert_eq!(app.command_line, "export csv ");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe_range_requires_exec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Notes\n\none two\nthree\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+
+        // The default config keeps no_exec on
+        let mut app = App::new(Config::default(), doc.clone(), vec![]);
+        app.command_line = "3,4!wc -w".to_string();
+        app.run_command_line();
+        assert!(app.command_output.is_none());
+
+        let mut config = Config::default();
+        config.security.no_exec = false;
+        let mut app = App::new(config, doc, vec![]);
+        app.command_line = "3,4!wc -w".to_string();
+        app.run_command_line();
+        let output = app.command_output.as_ref().unwrap();
+        assert_eq!(output.command, "wc -w");
+        assert!(output.output.trim_start().starts_with('3'));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_block_requires_insecure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "# Runbook\n\n```sh\npwd\n```\n").unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+
+        // The default config keeps no_exec on
+        let mut app = App::new(Config::default(), doc.clone(), vec![]);
+        app.insecure = true;
+        app.panes.focused_pane_mut().unwrap().view.cursor_line = 3;
+        app.run_block_at_cursor();
+        assert!(app.block_output.is_none());
+
+        // no_exec off is not enough without --insecure
+        let mut config = Config::default();
+        config.security.no_exec = false;
+        let mut app = App::new(config, doc, vec![]);
+        app.panes.focused_pane_mut().unwrap().view.cursor_line = 3;
+        app.run_block_at_cursor();
+        assert!(app.block_output.is_none());
+
+        app.insecure = true;
+        app.run_block_at_cursor();
+        assert!(app.block_output.is_none(), "running waits for confirmation");
+        assert_eq!(app.confirm.as_ref().unwrap().action, ConfirmAction::RunBlock(2));
+        app.answer_confirm(ConfirmAnswer::Yes);
+        assert!(app.block_output.as_ref().unwrap().result.is_none());
+        let result = app
+            .exec_worker
+            .as_ref()
+            .and_then(|w| {
+                (0..500).find_map(|_| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    w.try_recv_result()
+                })
+            })
+            .expect("exec worker result");
+        app.apply_block_result(result);
+        let output = app.block_output.as_ref().unwrap();
+        assert_eq!(output.start_line, 2);
+        let result = output.result.as_ref().unwrap().as_ref().unwrap();
+        // Blocks run in the document's directory
+        assert_eq!(
+            std::path::Path::new(result.stdout.trim()).canonicalize().unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
+
+        app.close_block_output();
+        assert!(app.block_output.is_none());
+    }
+
+    #[test]
+    fn test_spell_check_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let dic = dir.path().join("en_TEST.dic");
+        std::fs::write(&dic, "4\nthe\nquick\nfox\nbrown\n").unwrap();
+        let path = dir.path().join("doc.md");
+        std::fs::write(&path, "The quick fox\n\nThe qiuck brwn fox\n\n```\nnot chekced\n```\n")
+            .unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+
+        let mut config = Config::default();
+        config.spell.enabled = true;
+        config.spell.dictionary = dic.to_string_lossy().into_owned();
+        let mut app = App::new(config, doc, vec![]);
+
+        let result = app
+            .spell_worker
+            .as_ref()
+            .and_then(|w| {
+                (0..200).find_map(|_| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    w.try_recv_result()
+                })
+            })
+            .expect("spell worker result");
+        app.apply_spell_results(result);
+
+        let words: Vec<&str> = app.misspellings.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(words, vec!["qiuck", "brwn"]);
+
+        app.jump_to_misspelling(true);
+        assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 2);
+        assert_eq!(
+            app.spell_suggestions_at_cursor(),
+            vec![
+                ("qiuck".to_string(), vec!["quick".to_string()]),
+                ("brwn".to_string(), vec!["brown".to_string()])
+            ]
+        );
+        app.toggle_spell_popup();
+        assert!(app.show_spell_popup);
+
+        // Disabling clears everything
+        app.config.spell.enabled = false;
+        app.refresh_spell_check();
+        assert!(app.misspellings.is_empty());
+        assert!(!app.show_spell_popup);
+    }
+
+    #[test]
+    fn test_goto_centered_counts_wrapped_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+        let paragraph = "word ".repeat(30);
+        let content: String = (0..40).map(|_| format!("{}\n", paragraph)).collect();
+        std::fs::write(&path, content).unwrap();
+        let (doc, _warnings) = Document::load(&path).unwrap();
+        let mut app = App::new(Config::default(), doc, vec![]);
+        app.refresh_layout_context_with_area(60, 30);
+
+        let pane = app.panes.focused;
+        app.goto_centered(pane, 30);
+        let half = app.focused_viewport().unwrap().visible_height / 2;
+        let view = &app.panes.focused_pane().unwrap().view;
+        assert_eq!(view.cursor_line, 30);
+        // Every line wraps, so far fewer than `half` source lines sit above
+        assert!(30 - view.scroll_line() < half);
+        let rows_above = app.line_layout_cache.visual_rows_in_range(view.scroll_line(), 30)
+            - view.scroll_pos.wrap_row as usize;
+        assert_eq!(rows_above, half);
+    }
+
+    #[test]
+    fn test_theme_follows_color_support_and_padding() {
+        let mut config = Config::default();
+        config.render.code_padding = 3;
+        let mut app = App::new(config, create_test_doc(3), vec![]);
+        assert_eq!(app.theme.code_padding, 3);
+        assert!(matches!(
+            app.theme.code_block_bg,
+            ratatui::style::Color::Rgb(..)
+        ));
+
+        // Without 24-bit color the code background is a 256-color index,
+        // for the light theme too
+        app.set_truecolor(false);
+        assert_eq!(app.theme.code_block_bg, ratatui::style::Color::Indexed(233));
+        app.toggle_theme();
+        assert_eq!(app.theme.code_block_bg, ratatui::style::Color::Indexed(255));
+        assert_eq!(app.theme.code_padding, 3);
+    }
+
+    #[test]
+    fn test_max_width_caps_content_width() {
+        let mut config = Config::default();
+        config.render.max_width = 60;
+        let doc = create_test_doc(10);
+        let mut app = App::new(config, doc, vec![]);
+
+        app.refresh_layout_context_with_area(200, 40);
+        assert_eq!(app.focused_viewport().unwrap().content_width, 60);
+
+        app.config.render.max_width = 0;
+        app.refresh_layout_context_with_area(200, 40);
+        assert!(app.focused_viewport().unwrap().content_width > 60);
+    }
+
+    #[test]
+    fn test_scroll_position() {
+        let mut app = App::new(Config::default(), create_test_doc(5), vec![]);
+        app.refresh_layout_context_with_area(100, 40);
+        assert_eq!(app.scroll_position(), "All");
+
+        let mut app = App::new(Config::default(), create_test_doc(200), vec![]);
+        app.refresh_layout_context_with_area(100, 40);
+        let height = app.focused_viewport().unwrap().visible_height;
+        assert_eq!(app.scroll_position(), "Top");
+
+        let pane = app.panes.focused;
+        app.panes.panes.get_mut(&pane).unwrap().view.set_scroll_line(50);
+        let expected = 50 * 100 / (200 - height);
+        assert_eq!(app.scroll_position(), format!("{}%", expected));
+
+        // The last screenful reads as the bottom
+        app.panes.panes.get_mut(&pane).unwrap().view.set_scroll_line(200 - height);
+        assert_eq!(app.scroll_position(), "Bot");
+    }
+
     #[test]
     fn test_move_cursor_down() {
         let config = Config::default();
@@ -2037,6 +6272,20 @@ mod tests {
         assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 9);
     }
 
+    #[test]
+    fn test_parse_search_input() {
+        let parse = |input| parse_search_input(input, '/');
+        assert_eq!(parse("todo"), ("todo".to_string(), 0));
+        assert_eq!(parse("todo/"), ("todo".to_string(), 0));
+        assert_eq!(parse("todo/+2"), ("todo".to_string(), 2));
+        assert_eq!(parse("todo/3"), ("todo".to_string(), 3));
+        assert_eq!(parse("todo/-"), ("todo".to_string(), -1));
+        assert_eq!(parse(r"a\/b/+"), ("a/b".to_string(), 1));
+        // Not an offset: the delimiter is part of the query
+        assert_eq!(parse("and/or"), ("and/or".to_string(), 0));
+        assert_eq!(parse_search_input("why?-1", '?'), ("why".to_string(), -1));
+    }
+
     #[test]
     fn test_scroll_half_page() {
         let config = Config::default();
@@ -2360,4 +6609,26 @@ mod tests {
         let result = app.open_in_editor();
         assert!(result.is_err());
     }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_jump_to_hunk_wraps_and_counts() {
+        use mdx_core::diff::DiffMark;
+
+        let mut app = App::new(Config::default(), create_test_doc(20), vec![]);
+        app.doc.diff_gutter.marks[3] = DiffMark::Added;
+        app.doc.diff_gutter.marks[4] = DiffMark::Modified;
+        app.doc.diff_gutter.marks[12] = DiffMark::DeletedAfter(1);
+        let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+        assert_eq!(app.hunk_position(), Some((0, 2)));
+        app.jump_to_hunk(true);
+        assert_eq!((cursor(&app), app.hunk_position()), (3, Some((1, 2))));
+        app.jump_to_hunk(true);
+        assert_eq!((cursor(&app), app.hunk_position()), (12, Some((2, 2))));
+        app.jump_to_hunk(true);
+        assert_eq!(cursor(&app), 3);
+        app.jump_to_hunk(false);
+        assert_eq!(cursor(&app), 12);
+    }
 }
@@ -0,0 +1,175 @@
+//! Bidirectional text support for RTL paragraphs
+//!
+//! Terminals draw cells strictly left to right, so Arabic and Hebrew text
+//! has to be put into visual order before it reaches ratatui. Each
+//! rendered row is resolved on its own with the Unicode Bidirectional
+//! Algorithm (via `wezterm-bidi`), and rows whose base direction is RTL
+//! are right-aligned within the pane.
+//!
+//! Rows without strong RTL characters are returned untouched, so LTR
+//! documents only pay for a character scan.
+
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
+use wezterm_bidi::{
+    bidi_class_for_char, BidiClass, BidiContext, Direction, ParagraphDirectionHint,
+};
+
+/// Whether `text` contains any strong right-to-left character
+pub fn contains_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            bidi_class_for_char(c),
+            BidiClass::RightToLeft | BidiClass::ArabicLetter
+        )
+    })
+}
+
+/// Reorder one rendered row into visual order.
+///
+/// The first `prefix_width` columns (line number and gutter) are left in
+/// place. The remainder is resolved as a paragraph with an auto-detected
+/// base direction; when that direction is RTL the text is right-aligned
+/// so it ends at `total_width`.
+pub fn reorder_line(line: Line<'static>, prefix_width: usize, total_width: usize) -> Line<'static> {
+    if !line.spans.iter().any(|span| contains_rtl(&span.content)) {
+        return line;
+    }
+
+    let line_style = line.style;
+    let alignment = line.alignment;
+
+    // Flatten to styled characters and split off the gutter prefix.
+    let mut prefix: Vec<(char, Style)> = Vec::new();
+    let mut body: Vec<(char, Style)> = Vec::new();
+    let mut width = 0;
+    for span in &line.spans {
+        for ch in span.content.chars() {
+            if width < prefix_width {
+                width += ch.width().unwrap_or(0);
+                prefix.push((ch, span.style));
+            } else {
+                body.push((ch, span.style));
+            }
+        }
+    }
+
+    let chars: Vec<char> = body.iter().map(|(ch, _)| *ch).collect();
+    let mut context = BidiContext::new();
+    context.set_reorder_non_spacing_marks(true);
+    context.resolve_paragraph(&chars, ParagraphDirectionHint::AutoLeftToRight);
+    let is_rtl = context.base_level().direction() == Direction::RightToLeft;
+
+    // Trailing whitespace would land on the visual left of an RTL row and
+    // defeat the right alignment, so drop it before reordering.
+    let mut logical_end = chars.len();
+    if is_rtl {
+        while logical_end > 0 && chars[logical_end - 1].is_whitespace() {
+            logical_end -= 1;
+        }
+    }
+    let (_, order) = context.reorder_line(0..logical_end);
+    let visual: Vec<(char, Style)> = order.iter().map(|&idx| body[idx]).collect();
+
+    let mut cells = prefix;
+    if is_rtl {
+        let used: usize = width
+            + visual
+                .iter()
+                .map(|(ch, _)| ch.width().unwrap_or(0))
+                .sum::<usize>();
+        let padding = total_width.saturating_sub(used);
+        let pad_style = body
+            .first()
+            .and_then(|(_, style)| style.bg)
+            .map(|bg| Style::default().bg(bg))
+            .unwrap_or_default();
+        cells.extend(std::iter::repeat_n((' ', pad_style), padding));
+    }
+    cells.extend(visual);
+
+    let mut reordered = Line::from(group_spans(cells)).style(line_style);
+    reordered.alignment = alignment;
+    reordered
+}
+
+/// Merge consecutive characters that share a style back into spans
+fn group_spans(cells: Vec<(char, Style)>) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut current = String::new();
+    let mut current_style: Option<Style> = None;
+
+    for (ch, style) in cells {
+        if current_style != Some(style) {
+            if let Some(prev_style) = current_style {
+                spans.push(Span::styled(std::mem::take(&mut current), prev_style));
+            }
+            current_style = Some(style);
+        }
+        current.push(ch);
+    }
+    if let Some(style) = current_style {
+        spans.push(Span::styled(current, style));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn line_text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_contains_rtl() {
+        assert!(contains_rtl("שלום"));
+        assert!(contains_rtl("hello مرحبا"));
+        assert!(!contains_rtl("hello world"));
+        assert!(!contains_rtl("日本語"));
+    }
+
+    #[test]
+    fn test_ltr_line_unchanged() {
+        let line = Line::from(vec![Span::raw(" 1 "), Span::raw("hello")]);
+        let result = reorder_line(line.clone(), 3, 20);
+        assert_eq!(result, line);
+    }
+
+    #[test]
+    fn test_rtl_line_reversed_and_right_aligned() {
+        let line = Line::from(vec![Span::raw(" 1 "), Span::raw("אבג")]);
+        let result = reorder_line(line, 3, 10);
+        assert_eq!(line_text(&result), " 1     גבא");
+    }
+
+    #[test]
+    fn test_ltr_paragraph_with_rtl_run() {
+        let line = Line::from(vec![Span::raw("> "), Span::raw("say אבג now")]);
+        let result = reorder_line(line, 2, 40);
+        assert_eq!(line_text(&result), "> say גבא now");
+    }
+
+    #[test]
+    fn test_styles_follow_characters() {
+        let red = Style::default().fg(Color::Red);
+        let line = Line::from(vec![
+            Span::raw(" "),
+            Span::styled("אב", red),
+            Span::raw("ג"),
+        ]);
+        let result = reorder_line(line, 1, 4);
+        assert_eq!(line_text(&result), " גבא");
+        // The unstyled gutter and "ג" merge; the red run stays intact.
+        assert_eq!(result.spans.len(), 2);
+        assert_eq!(result.spans[1].content, "בא");
+        assert_eq!(result.spans[1].style, red);
+    }
+}
@@ -0,0 +1,160 @@
+//! Open documents that are not on screen
+//!
+//! `App` keeps the shown document in its own fields (`doc`, `panes`, the
+//! jump list, ...). Switching buffers swaps that state with a stored
+//! [`Buffer`], so everything else only ever deals with one document.
+
+use crate::app::JumpEntry;
+use crate::panes::PaneManager;
+use mdx_core::{Config, Document};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// File extensions opened in directory mode, besides the formats shown
+/// converted to Markdown
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
+
+/// Per-document state kept while another document is shown
+pub struct Buffer {
+    /// Stable id used by panes and diff requests
+    pub doc_id: usize,
+    pub doc: Document,
+    pub panes: PaneManager,
+    pub toc_selected: usize,
+    pub toc_scroll: usize,
+    pub jump_stack: VecDeque<JumpEntry>,
+    pub jump_cursor: usize,
+    /// Changed on disk since it was last shown
+    pub changed: bool,
+    /// Why the document couldn't be loaded, if it couldn't
+    pub load_error: Option<crate::app::LoadError>,
+    #[cfg(feature = "watch")]
+    pub watcher: Option<crate::watcher::FileWatcher>,
+}
+
+impl Buffer {
+    /// A buffer for a newly opened document, with a single pane at the top
+    pub fn new(doc_id: usize, doc: Document, config: &Config) -> Self {
+        #[cfg(feature = "watch")]
+        let watcher = if config.watch.enabled && doc.revision.is_none() {
+            crate::watcher::FileWatcher::new(&doc.path).ok()
+        } else {
+            None
+        };
+        #[cfg(not(feature = "watch"))]
+        let _ = config;
+
+        Self {
+            doc_id,
+            doc,
+            panes: PaneManager::new(doc_id),
+            toc_selected: 0,
+            toc_scroll: 0,
+            jump_stack: VecDeque::new(),
+            jump_cursor: 0,
+            changed: false,
+            load_error: None,
+            #[cfg(feature = "watch")]
+            watcher,
+        }
+    }
+}
+
+/// Short name for a buffer list: the file name, or the path if it has
+/// none, followed by `@REV` for a git revision
+pub fn display_name(doc: &Document) -> String {
+    let name = doc
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| doc.path.display().to_string());
+    match &doc.revision {
+        Some(rev) => format!("{}@{}", name, rev),
+        None => name,
+    }
+}
+
+/// Whether a path names a Markdown file, or one shown converted to
+/// Markdown (a notebook, ...), going by its extension
+pub fn is_markdown_path(path: &Path) -> bool {
+    let markdown = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            MARKDOWN_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        });
+    markdown || mdx_core::doc::is_converted_path(path)
+}
+
+/// Whether a path is inside a hidden file or directory below `root`
+pub fn is_hidden_below(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root).is_ok_and(|rel| {
+        rel.components()
+            .any(|part| part.as_os_str().to_string_lossy().starts_with('.'))
+    })
+}
+
+/// Markdown files under `dir`, sorted by path. Hidden files and
+/// directories (`.git`, ...) are skipped.
+pub fn markdown_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push(path),
+                Ok(_) if is_markdown_path(&path) && path.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_files_skips_hidden_and_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("guide/deep")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        for name in [
+            "README.md",
+            "guide/intro.markdown",
+            "guide/deep/notes.MD",
+            "guide/image.png",
+            ".git/HEAD.md",
+            ".draft.md",
+        ] {
+            std::fs::write(root.join(name), "# x\n").unwrap();
+        }
+
+        let found: Vec<_> = markdown_files(root)
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("README.md"),
+                PathBuf::from("guide/deep/notes.MD"),
+                PathBuf::from("guide/intro.markdown"),
+            ]
+        );
+        assert!(is_hidden_below(root, &root.join(".git/HEAD.md")));
+        assert!(!is_hidden_below(root, &root.join("guide/intro.markdown")));
+    }
+}
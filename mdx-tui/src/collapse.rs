@@ -1,8 +1,10 @@
 //! Collapsible block support for markdown documents
 //!
 //! This module provides utilities for computing and managing collapsed regions
-//! of markdown content, particularly headings and code blocks.
+//! of markdown content, particularly headings, code blocks, and raw HTML
+//! `<details>` blocks.
 
+use mdx_core::html::DetailsBlock;
 use mdx_core::Document;
 use std::collections::BTreeSet;
 
@@ -79,11 +81,49 @@ pub fn compute_heading_range(heading_line: usize, doc: &Document) -> Option<Coll
     })
 }
 
-/// Compute all collapsed ranges from a set of collapsed heading lines
+/// Whether a `<details>` block is currently collapsed.
 ///
-/// Returns a sorted vector of non-overlapping collapsed ranges
+/// Blocks follow their HTML default (closed unless `open` is set);
+/// `toggled_details` holds the start lines the user has flipped.
+pub fn is_details_collapsed(block: &DetailsBlock, toggled_details: &BTreeSet<usize>) -> bool {
+    block.open == toggled_details.contains(&block.start)
+}
+
+/// Find the `<details>` block whose opening or `<summary>` line is `line`
+pub fn details_block_at(line: usize, doc: &Document) -> Option<&DetailsBlock> {
+    doc.details
+        .iter()
+        .find(|d| d.start == line || d.summary_line == Some(line))
+}
+
+/// Compute the collapsed range for a `<details>` block
+fn details_range(block: &DetailsBlock) -> Option<CollapseRange> {
+    if block.end <= block.start {
+        return None;
+    }
+
+    let display_text = if block.summary.chars().count() > 32 {
+        format!("{}...", block.summary.chars().take(29).collect::<String>())
+    } else {
+        block.summary.clone()
+    };
+
+    Some(CollapseRange {
+        start: block.start,
+        end: block.end,
+        level: None,
+        text: display_text,
+        line_count: block.end - block.start,
+    })
+}
+
+/// Compute all collapsed ranges from a set of collapsed heading lines and
+/// the user's `<details>` toggles
+///
+/// Returns a vector of collapsed ranges sorted by start line
 pub fn compute_all_collapsed_ranges(
     collapsed_headings: &BTreeSet<usize>,
+    toggled_details: &BTreeSet<usize>,
     doc: &Document,
 ) -> Vec<CollapseRange> {
     let mut ranges = Vec::new();
@@ -94,6 +134,12 @@ pub fn compute_all_collapsed_ranges(
         }
     }
 
+    for block in &doc.details {
+        if is_details_collapsed(block, toggled_details) {
+            ranges.extend(details_range(block));
+        }
+    }
+
     // Sort by start line (BTreeSet iteration should already be sorted, but be explicit)
     ranges.sort_by_key(|r| r.start);
 
@@ -201,7 +247,7 @@ mod tests {
         collapsed.insert(0); // Collapse first H1
         collapsed.insert(2); // Collapse H2
 
-        let ranges = compute_all_collapsed_ranges(&collapsed, &doc);
+        let ranges = compute_all_collapsed_ranges(&collapsed, &BTreeSet::new(), &doc);
 
         assert_eq!(ranges.len(), 2);
         assert_eq!(ranges[0].start, 0);
@@ -214,7 +260,7 @@ mod tests {
         let mut collapsed = BTreeSet::new();
         collapsed.insert(0);
 
-        let ranges = compute_all_collapsed_ranges(&collapsed, &doc);
+        let ranges = compute_all_collapsed_ranges(&collapsed, &BTreeSet::new(), &doc);
 
         assert!(find_range_at_line(&ranges, 0).is_some());
         assert!(find_range_at_line(&ranges, 1).is_none());
@@ -227,7 +273,7 @@ mod tests {
         let mut collapsed = BTreeSet::new();
         collapsed.insert(0); // Collapse H1
 
-        let ranges = compute_all_collapsed_ranges(&collapsed, &doc);
+        let ranges = compute_all_collapsed_ranges(&collapsed, &BTreeSet::new(), &doc);
 
         assert!(find_range_containing_line(&ranges, 0).is_none()); // Start line doesn't count
         assert!(find_range_containing_line(&ranges, 1).is_some()); // Content line
@@ -296,4 +342,42 @@ mod tests {
         assert_eq!(range.start, 3);
         assert_eq!(range.end, 4); // Stops before ## H2b
     }
+
+    #[test]
+    fn test_details_collapsed_by_default() {
+        let doc =
+            create_test_doc("<details>\n<summary>More</summary>\n\nHidden\n</details>\nAfter\n");
+        let mut toggled = BTreeSet::new();
+
+        let ranges = compute_all_collapsed_ranges(&BTreeSet::new(), &toggled, &doc);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges[0].end, 4);
+        assert_eq!(ranges[0].level, None);
+        assert_eq!(ranges[0].text, "More");
+
+        // Toggling the block expands it
+        toggled.insert(0);
+        let ranges = compute_all_collapsed_ranges(&BTreeSet::new(), &toggled, &doc);
+        assert!(ranges.is_empty());
+    }
+
+    #[test]
+    fn test_details_open_attribute_starts_expanded() {
+        let doc = create_test_doc("<details open>\n<summary>More</summary>\nBody\n</details>\n");
+        let mut toggled = BTreeSet::new();
+        assert!(compute_all_collapsed_ranges(&BTreeSet::new(), &toggled, &doc).is_empty());
+
+        toggled.insert(0);
+        let ranges = compute_all_collapsed_ranges(&BTreeSet::new(), &toggled, &doc);
+        assert_eq!(ranges.len(), 1);
+    }
+
+    #[test]
+    fn test_details_block_at_summary_line() {
+        let doc = create_test_doc("Intro\n<details>\n<summary>More</summary>\nBody\n</details>\n");
+        assert_eq!(details_block_at(1, &doc).map(|d| d.start), Some(1));
+        assert_eq!(details_block_at(2, &doc).map(|d| d.start), Some(1));
+        assert!(details_block_at(3, &doc).is_none());
+    }
 }
@@ -0,0 +1,644 @@
+//! Named commands behind the normal-mode keys and the command palette
+//!
+//! [`KEYMAP`] binds keys and two-key sequences (`gg`, `]]`, `za`,
+//! `Ctrl+W v`, ...) to commands. [`crate::input`] looks keys up in it and
+//! runs the commands through [`run`], and the palette (`Ctrl+Shift+P`) and
+//! the help list every command with the keys the map gives it.
+
+use crate::app::{App, ScrollContext};
+use crate::input::Action;
+use crate::panes::SplitDir;
+use crate::scroll_math::ScrollPolicy;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something the user can do from normal mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    Quit,
+    ForceQuit,
+    Redraw,
+    CommandPalette,
+    FuzzyFinder,
+    CommandLine,
+    InsertMode,
+    OpenEditor,
+    MoveDown,
+    MoveUp,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    ScrollDown,
+    ScrollUp,
+    Top,
+    Bottom,
+    NextHeading,
+    PrevHeading,
+    ParentHeading,
+    JumpBack,
+    JumpForward,
+    Search,
+    SearchBackward,
+    NextMatch,
+    PrevMatch,
+    ToggleFold,
+    OpenFold,
+    CloseFold,
+    CloseAllFolds,
+    OpenAllFolds,
+    SplitH,
+    SplitV,
+    NextBuffer,
+    PrevBuffer,
+    BufferPicker,
+    ToggleToc,
+    TocDialog,
+    Breadcrumbs,
+    Stats,
+    CopyTable,
+    ExportTable,
+    LinkDiagnostics,
+    SecurityWarnings,
+    SpellSuggestions,
+    Preview,
+    ToggleRaw,
+    ToggleTheme,
+    FocusMode,
+    ToggleComments,
+    #[cfg(feature = "images")]
+    ImageAltText,
+    #[cfg(feature = "git")]
+    DeletedLines,
+    #[cfg(feature = "git")]
+    GitLog,
+    RunBlock,
+    Present,
+    Options,
+    Reload,
+    Help,
+}
+
+impl Command {
+    /// Every command the palette offers
+    pub const ALL: &'static [Command] = &[
+        Command::Quit,
+        Command::ForceQuit,
+        Command::Redraw,
+        Command::CommandPalette,
+        Command::FuzzyFinder,
+        Command::CommandLine,
+        Command::InsertMode,
+        Command::OpenEditor,
+        Command::MoveDown,
+        Command::MoveUp,
+        Command::HalfPageDown,
+        Command::HalfPageUp,
+        Command::PageDown,
+        Command::PageUp,
+        Command::ScrollDown,
+        Command::ScrollUp,
+        Command::Top,
+        Command::Bottom,
+        Command::NextHeading,
+        Command::PrevHeading,
+        Command::ParentHeading,
+        Command::JumpBack,
+        Command::JumpForward,
+        Command::Search,
+        Command::SearchBackward,
+        Command::NextMatch,
+        Command::PrevMatch,
+        Command::ToggleFold,
+        Command::OpenFold,
+        Command::CloseFold,
+        Command::CloseAllFolds,
+        Command::OpenAllFolds,
+        Command::SplitH,
+        Command::SplitV,
+        Command::NextBuffer,
+        Command::PrevBuffer,
+        Command::BufferPicker,
+        Command::ToggleToc,
+        Command::TocDialog,
+        Command::Breadcrumbs,
+        Command::Stats,
+        Command::CopyTable,
+        Command::ExportTable,
+        Command::LinkDiagnostics,
+        Command::SecurityWarnings,
+        Command::SpellSuggestions,
+        Command::Preview,
+        Command::ToggleRaw,
+        Command::ToggleTheme,
+        Command::FocusMode,
+        Command::ToggleComments,
+        #[cfg(feature = "images")]
+        Command::ImageAltText,
+        #[cfg(feature = "git")]
+        Command::DeletedLines,
+        #[cfg(feature = "git")]
+        Command::GitLog,
+        Command::RunBlock,
+        Command::Present,
+        Command::Options,
+        Command::Reload,
+        Command::Help,
+    ];
+
+    /// What the command does, as listed in the palette
+    pub fn title(self) -> &'static str {
+        match self {
+            Command::Quit => "Close pane, or quit with the last one",
+            Command::ForceQuit => "Quit immediately",
+            Command::Redraw => "Redraw the screen",
+            Command::CommandPalette => "Command palette",
+            Command::FuzzyFinder => "Find heading, link or file",
+            Command::CommandLine => "Command line (:w, :q, ...)",
+            Command::InsertMode => "Edit the cursor line",
+            Command::OpenEditor => "Open in external editor",
+            Command::MoveDown => "Move down",
+            Command::MoveUp => "Move up",
+            Command::HalfPageDown => "Half page down",
+            Command::HalfPageUp => "Half page up",
+            Command::PageDown => "Page down",
+            Command::PageUp => "Page up",
+            Command::ScrollDown => "Scroll view down a line",
+            Command::ScrollUp => "Scroll view up a line",
+            Command::Top => "Go to top",
+            Command::Bottom => "Go to bottom",
+            Command::NextHeading => "Next heading",
+            Command::PrevHeading => "Previous heading",
+            Command::ParentHeading => "Parent heading",
+            Command::JumpBack => "Jump back",
+            Command::JumpForward => "Jump forward",
+            Command::Search => "Search",
+            Command::SearchBackward => "Search backwards",
+            Command::NextMatch => "Next search match",
+            Command::PrevMatch => "Previous search match",
+            Command::ToggleFold => "Toggle fold at cursor",
+            Command::OpenFold => "Open fold at cursor",
+            Command::CloseFold => "Close fold at cursor",
+            Command::CloseAllFolds => "Close all folds",
+            Command::OpenAllFolds => "Open all folds",
+            Command::SplitH => "Split horizontally",
+            Command::SplitV => "Split vertically",
+            Command::NextBuffer => "Next file",
+            Command::PrevBuffer => "Previous file",
+            Command::BufferPicker => "List open files",
+            Command::ToggleToc => "Toggle TOC sidebar",
+            Command::TocDialog => "TOC dialog",
+            Command::Breadcrumbs => "Heading breadcrumbs",
+            Command::Stats => "Word count and reading time",
+            Command::CopyTable => "Copy table as TSV",
+            Command::ExportTable => "Export table to a CSV/TSV file",
+            Command::LinkDiagnostics => "List broken links",
+            Command::SecurityWarnings => "Toggle security warnings",
+            Command::SpellSuggestions => "Spelling suggestions",
+            Command::Preview => "Preview link or footnote target",
+            Command::ToggleRaw => "Toggle raw markdown",
+            Command::ToggleTheme => "Next theme",
+            Command::FocusMode => "Toggle focus mode",
+            Command::ToggleComments => "Show or hide HTML comments",
+            #[cfg(feature = "images")]
+            Command::ImageAltText => "Toggle images and alt text",
+            #[cfg(feature = "git")]
+            Command::DeletedLines => "Toggle deleted lines under diff marks",
+            #[cfg(feature = "git")]
+            Command::GitLog => "Git log of this file",
+            Command::RunBlock => "Run the code block at cursor",
+            Command::Present => "Present as slides",
+            Command::Options => "Options",
+            Command::Reload => "Reload from disk",
+            Command::Help => "Help",
+        }
+    }
+
+    /// The keys the command is bound to in [`KEYMAP`], as shown to the user
+    pub fn keys(self) -> String {
+        KEYMAP
+            .iter()
+            .filter(|(_, command)| *command == self)
+            .map(|(keys, _)| keys_name(keys))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A key and the modifiers held with it
+pub type KeyPress = (KeyCode, KeyModifiers);
+
+const fn key(c: char) -> KeyPress {
+    (KeyCode::Char(c), KeyModifiers::NONE)
+}
+
+const fn ctrl(c: char) -> KeyPress {
+    (KeyCode::Char(c), KeyModifiers::CONTROL)
+}
+
+const fn plain(code: KeyCode) -> KeyPress {
+    (code, KeyModifiers::NONE)
+}
+
+/// Normal-mode keys and two-key sequences, in the order the palette and
+/// help show them. Printable characters are listed without Shift, since
+/// the character itself says whether it was shifted. A few keys (`q`,
+/// `Ctrl+C`, `/`, `n`, ...) must win over modes and prefixes, so
+/// [`crate::input`] checks for their commands before anything else.
+pub const KEYMAP: &[(&[KeyPress], Command)] = &[
+    (&[key('q')], Command::Quit),
+    (&[ctrl('c')], Command::ForceQuit),
+    (&[ctrl('l')], Command::Redraw),
+    (
+        &[(
+            KeyCode::Char('P'),
+            KeyModifiers::CONTROL.union(KeyModifiers::SHIFT),
+        )],
+        Command::CommandPalette,
+    ),
+    (&[ctrl('p')], Command::FuzzyFinder),
+    (&[key(':')], Command::CommandLine),
+    (&[key('i')], Command::InsertMode),
+    (&[key('e')], Command::OpenEditor),
+    (&[key('j')], Command::MoveDown),
+    (&[plain(KeyCode::Down)], Command::MoveDown),
+    (&[key('k')], Command::MoveUp),
+    (&[plain(KeyCode::Up)], Command::MoveUp),
+    (&[ctrl('d')], Command::HalfPageDown),
+    (&[ctrl('u')], Command::HalfPageUp),
+    (&[key(' ')], Command::PageDown),
+    (&[plain(KeyCode::PageDown)], Command::PageDown),
+    (&[plain(KeyCode::PageUp)], Command::PageUp),
+    (&[ctrl('e')], Command::ScrollDown),
+    (&[ctrl('y')], Command::ScrollUp),
+    (&[key('g'), key('g')], Command::Top),
+    (&[plain(KeyCode::Home)], Command::Top),
+    (&[key('G')], Command::Bottom),
+    (&[plain(KeyCode::End)], Command::Bottom),
+    (&[key(']'), key(']')], Command::NextHeading),
+    (&[key('['), key('[')], Command::PrevHeading),
+    (&[key('['), key('h')], Command::ParentHeading),
+    (&[ctrl('o')], Command::JumpBack),
+    (&[ctrl('i')], Command::JumpForward),
+    (&[key('/')], Command::Search),
+    (&[key('?')], Command::SearchBackward),
+    (&[key('n')], Command::NextMatch),
+    (&[key('N')], Command::PrevMatch),
+    (&[key('z'), key('a')], Command::ToggleFold),
+    (&[key('z'), key('o')], Command::OpenFold),
+    (&[key('z'), key('c')], Command::CloseFold),
+    (&[key('z'), key('M')], Command::CloseAllFolds),
+    (&[key('z'), key('R')], Command::OpenAllFolds),
+    (&[ctrl('w'), key('s')], Command::SplitH),
+    (&[ctrl('w'), key('v')], Command::SplitV),
+    (&[key('g'), key('t')], Command::NextBuffer),
+    (&[key('g'), key('T')], Command::PrevBuffer),
+    (&[key('B')], Command::BufferPicker),
+    (&[key('t')], Command::ToggleToc),
+    (&[key('T')], Command::TocDialog),
+    (&[key('g'), key('b')], Command::Breadcrumbs),
+    (&[key('g'), ctrl('g')], Command::Stats),
+    (&[key('y'), key('t')], Command::CopyTable),
+    (&[key('y'), key('T')], Command::ExportTable),
+    (&[key('L')], Command::LinkDiagnostics),
+    (&[key('W')], Command::SecurityWarnings),
+    (&[key('z'), key('=')], Command::SpellSuggestions),
+    (&[key('K')], Command::Preview),
+    (&[key('r')], Command::ToggleRaw),
+    (&[key('m')], Command::ToggleTheme),
+    (&[key('F')], Command::FocusMode),
+    (&[key('C')], Command::ToggleComments),
+    #[cfg(feature = "images")]
+    (&[key('I')], Command::ImageAltText),
+    #[cfg(feature = "git")]
+    (&[key('D')], Command::DeletedLines),
+    #[cfg(feature = "git")]
+    (&[key('g'), key('l')], Command::GitLog),
+    (&[key('X')], Command::RunBlock),
+    (&[key('P')], Command::Present),
+    (&[key('O')], Command::Options),
+    (&[key('R')], Command::Reload),
+    (&[plain(KeyCode::F(1))], Command::Help),
+];
+
+/// A key event as [`KEYMAP`] lists it: Shift is dropped from characters
+fn key_press(key: KeyEvent) -> KeyPress {
+    let mut modifiers = key.modifiers;
+    if matches!(key.code, KeyCode::Char(_)) && modifiers == KeyModifiers::SHIFT {
+        modifiers = KeyModifiers::NONE;
+    }
+    (key.code, modifiers)
+}
+
+fn command_for_keys(keys: &[KeyPress]) -> Option<Command> {
+    KEYMAP
+        .iter()
+        .find(|(bound, _)| *bound == keys)
+        .map(|(_, command)| *command)
+}
+
+/// The [`KEYMAP`] command bound to `key` on its own
+pub fn command_for_key(key: KeyEvent) -> Option<Command> {
+    command_for_keys(&[key_press(key)])
+}
+
+/// The [`KEYMAP`] command bound to `prefix` followed by `key`
+pub fn command_after(prefix: KeyPress, key: KeyEvent) -> Option<Command> {
+    command_for_keys(&[prefix, key_press(key)])
+}
+
+/// A key as the help writes it: `j`, `Space`, `Ctrl+D`, `↓`
+pub fn key_name((code, modifiers): KeyPress) -> String {
+    let name = match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            c.to_ascii_uppercase().to_string()
+        }
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    };
+    let mut out = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        out.push_str("Ctrl+");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        out.push_str("Alt+");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        out.push_str("Shift+");
+    }
+    out.push_str(&name);
+    out
+}
+
+/// A key sequence as the help writes it: `gg` and `z=` run together,
+/// `g Ctrl+G` and `Ctrl+W s` don't
+pub fn keys_name(keys: &[KeyPress]) -> String {
+    let run_together = keys.iter().all(|&(code, modifiers)| {
+        matches!(code, KeyCode::Char(c) if c != ' ') && modifiers.is_empty()
+    });
+    let names: Vec<String> = keys.iter().map(|&key| key_name(key)).collect();
+    names.join(if run_together { "" } else { " " })
+}
+
+/// Whether `key` opens the command palette. The kitty keyboard protocol
+/// reports Ctrl+Shift+P with the unshifted 'p'.
+pub fn is_palette_key(key: KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('P' | 'p'))
+        && key.modifiers.contains(KeyModifiers::CONTROL)
+        && key.modifiers.contains(KeyModifiers::SHIFT)
+}
+
+/// Visible height and content width of the focused pane. Before the first
+/// draw the layout is worked out from the raw terminal size.
+pub fn pane_size(app: &mut App, ctx: &ScrollContext) -> (usize, usize) {
+    if let Some(vp) = ctx.viewport.filter(|v| v.visible_height > 0) {
+        return (vp.visible_height, vp.content_width);
+    }
+    app.refresh_layout_context_with_area(ctx.term_width, ctx.term_height);
+    app.focused_viewport()
+        .filter(|v| v.visible_height > 0)
+        .map(|v| (v.visible_height, v.content_width))
+        .unwrap_or((ctx.visible_height(), ctx.content_width()))
+}
+
+/// Run `command` on the focused pane, using up any count typed before it
+pub fn run(app: &mut App, command: Command, ctx: &ScrollContext) -> Action {
+    let (pane_height, pane_width) = pane_size(app, ctx);
+    let page_step = crate::scroll_math::page_step(pane_height, app.config.render.page_overlap_rows);
+    match command {
+        Command::Quit => {
+            if app.panes.panes.len() == 1 {
+                app.confirm_quit();
+                return if app.should_quit {
+                    Action::Quit
+                } else {
+                    Action::Continue
+                };
+            }
+            if !app.panes.close_focused() {
+                app.quit();
+                return Action::Quit;
+            }
+        }
+        Command::ForceQuit => {
+            app.quit();
+            return Action::Quit;
+        }
+        Command::Redraw => {
+            app.clear_status_message();
+            return Action::Redraw;
+        }
+        Command::CommandPalette => app.open_command_palette(),
+        Command::FuzzyFinder => app.open_finder(),
+        Command::CommandLine => app.enter_command_mode(),
+        Command::InsertMode => app.enter_insert_mode(),
+        Command::OpenEditor => return Action::OpenEditor,
+        Command::MoveDown => {
+            let count = app.take_count();
+            app.move_cursor_down(count);
+            app.auto_scroll(pane_height);
+        }
+        Command::MoveUp => {
+            let count = app.take_count();
+            app.move_cursor_up(count);
+            app.auto_scroll(pane_height);
+        }
+        Command::HalfPageDown => app.smooth_scroll(|app| {
+            for _ in 0..app.take_count() {
+                app.scroll_half_page_down(pane_height, pane_width);
+            }
+            app.auto_scroll(pane_height);
+        }),
+        Command::HalfPageUp => app.smooth_scroll(|app| {
+            for _ in 0..app.take_count() {
+                app.scroll_half_page_up(pane_height, pane_width);
+            }
+            app.auto_scroll(pane_height);
+        }),
+        Command::PageDown => {
+            let step = page_step * app.take_count();
+            app.smooth_scroll(|app| {
+                app.move_cursor_down(step);
+                app.auto_scroll(pane_height);
+            });
+        }
+        Command::PageUp => {
+            let step = page_step * app.take_count();
+            app.smooth_scroll(|app| {
+                app.move_cursor_up(step);
+                app.auto_scroll(pane_height);
+            });
+        }
+        Command::ScrollDown => {
+            let count = app.take_count();
+            app.scroll_view(count, true, pane_height);
+        }
+        Command::ScrollUp => {
+            let count = app.take_count();
+            app.scroll_view(count, false, pane_height);
+        }
+        // Both go to line N after a count
+        Command::Top | Command::Bottom => {
+            let last_line = app.doc.line_count().saturating_sub(1);
+            let default = if command == Command::Top {
+                0
+            } else {
+                last_line
+            };
+            let line = app
+                .pending_count
+                .take()
+                .map_or(default, |n| (n - 1).min(last_line));
+            app.push_jump();
+            let pane_id = app.panes.focused;
+            app.goto(pane_id, line, ScrollPolicy::NearestEdge);
+        }
+        Command::NextHeading => {
+            let count = app.take_count();
+            app.jump_to_next_heading(true, count);
+        }
+        Command::PrevHeading => {
+            let count = app.take_count();
+            app.jump_to_next_heading(false, count);
+        }
+        Command::ParentHeading => app.jump_to_parent_heading(),
+        Command::JumpBack => {
+            app.jump_back();
+        }
+        Command::JumpForward => {
+            app.jump_forward();
+        }
+        Command::Search => app.enter_search_mode(),
+        Command::SearchBackward => app.enter_backward_search_mode(),
+        Command::NextMatch | Command::PrevMatch => {
+            app.push_jump();
+            app.repeat_search(command == Command::PrevMatch, pane_height);
+        }
+        Command::ToggleFold => app.toggle_collapse_at_cursor(),
+        Command::OpenFold => app.expand_at_cursor(),
+        Command::CloseFold => app.collapse_at_cursor(),
+        Command::CloseAllFolds => app.collapse_all_headings(None),
+        Command::OpenAllFolds => app.expand_all_headings(),
+        Command::SplitH | Command::SplitV => {
+            let dir = if command == Command::SplitH {
+                SplitDir::Horizontal
+            } else {
+                SplitDir::Vertical
+            };
+            app.split_focused(dir);
+            // Refresh layout context immediately so subsequent commands use correct pane sizes
+            app.refresh_layout_context_with_area(ctx.term_width, ctx.term_height);
+        }
+        Command::NextBuffer => app.next_buffer(),
+        Command::PrevBuffer => app.prev_buffer(),
+        Command::BufferPicker => app.open_buffer_picker(),
+        Command::ToggleToc => app.toggle_toc(),
+        Command::TocDialog => app.toggle_toc_dialog(),
+        Command::Breadcrumbs => app.open_breadcrumb_popup(),
+        Command::Stats => app.open_stats_popup(),
+        Command::CopyTable => app.copy_table(mdx_core::export::TableFormat::Tsv),
+        Command::ExportTable => app.prompt_table_export(),
+        Command::LinkDiagnostics => app.toggle_link_diagnostics(),
+        Command::SecurityWarnings => app.toggle_security_warnings(),
+        Command::SpellSuggestions => app.toggle_spell_popup(),
+        Command::Preview => app.preview_at_cursor(),
+        Command::ToggleRaw => {
+            if let Some(pane) = app.panes.focused_pane_mut() {
+                pane.view.show_raw = !pane.view.show_raw;
+            }
+        }
+        Command::ToggleTheme => app.toggle_theme(),
+        Command::FocusMode => app.toggle_focus_mode(),
+        Command::ToggleComments => app.toggle_comments(),
+        #[cfg(feature = "images")]
+        Command::ImageAltText => app.toggle_image_alt_text(),
+        #[cfg(feature = "git")]
+        Command::DeletedLines => app.toggle_deleted_lines(),
+        #[cfg(feature = "git")]
+        Command::GitLog => app.open_git_log(),
+        Command::RunBlock => app.run_block_at_cursor(),
+        Command::Present => app.start_presentation(),
+        Command::Options => app.open_options(),
+        Command::Reload => app.confirm_reload(),
+        Command::Help => app.toggle_help(),
+    }
+    Action::Continue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_keymap_lookup() {
+        let shifted = key(KeyCode::Char('T'), KeyModifiers::SHIFT);
+        assert_eq!(command_for_key(shifted), Some(Command::TocDialog));
+        let plain = key(KeyCode::Char('T'), KeyModifiers::NONE);
+        assert_eq!(command_for_key(plain), Some(Command::TocDialog));
+        let ctrl_d = key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(command_for_key(ctrl_d), Some(Command::HalfPageDown));
+        let alt_j = key(KeyCode::Char('j'), KeyModifiers::ALT);
+        assert_eq!(command_for_key(alt_j), None);
+        let shift_m = key(KeyCode::Char('M'), KeyModifiers::SHIFT);
+        let z = (KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(command_after(z, shift_m), Some(Command::CloseAllFolds));
+        assert_eq!(command_for_key(shift_m), None);
+
+        assert!(is_palette_key(key(
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        assert!(!is_palette_key(key(
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL
+        )));
+    }
+
+    #[test]
+    fn test_every_bound_command_is_listed() {
+        for (_, command) in KEYMAP {
+            assert!(Command::ALL.contains(command), "{command:?}");
+        }
+        let mut seen = std::collections::HashSet::new();
+        for command in Command::ALL {
+            assert!(seen.insert(command), "{command:?} listed twice");
+            assert!(!command.title().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_every_command_has_a_key() {
+        for command in Command::ALL {
+            assert!(
+                KEYMAP.iter().any(|(_, bound)| bound == command),
+                "{command:?} has no key"
+            );
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (keys, _) in KEYMAP {
+            assert!(seen.insert(keys), "{} bound twice", keys_name(keys));
+        }
+    }
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!(Command::MoveDown.keys(), "j, ↓");
+        assert_eq!(Command::Top.keys(), "gg, Home");
+        assert_eq!(Command::Stats.keys(), "g Ctrl+G");
+        assert_eq!(Command::SplitV.keys(), "Ctrl+W v");
+        assert_eq!(Command::CommandPalette.keys(), "Ctrl+Shift+P");
+        assert_eq!(Command::PageDown.keys(), "Space, PgDn");
+        assert_eq!(Command::Help.keys(), "F1");
+    }
+}
@@ -0,0 +1,211 @@
+//! Side-by-side comparison of two files (`mdx --diff OLD NEW`)
+//!
+//! Both files are shown as source, one per column. Rows come from
+//! [`mdx_core::diff::side_by_side`], so the columns scroll together and
+//! lines missing on one side leave a gap on that side.
+
+use mdx_core::diff::{RowKind, SideBySideRow};
+use mdx_core::Document;
+use std::path::PathBuf;
+
+/// State of the two-file diff view
+pub struct DiffView {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub rows: Vec<SideBySideRow>,
+    /// First row shown
+    pub scroll: usize,
+    /// Rows that fit on screen, updated on every draw
+    pub page_height: usize,
+    /// Quit mdx on `q` instead of returning to the document
+    pub quit_on_close: bool,
+}
+
+impl DiffView {
+    /// Compare `old` with `new`
+    pub fn new(old: &Document, new: &Document) -> Self {
+        let old_text: String = old.rope.chunks().collect();
+        let new_text: String = new.rope.chunks().collect();
+        Self {
+            old_path: old.path.clone(),
+            new_path: new.path.clone(),
+            rows: mdx_core::diff::side_by_side(&old_text, &new_text),
+            scroll: 0,
+            page_height: 20,
+            quit_on_close: true,
+        }
+    }
+
+    /// Compare an older revision's `old_text`, titled `label`, with the
+    /// open document. Closing returns to the document.
+    pub fn with_revision(label: PathBuf, old_text: &str, new: &Document) -> Self {
+        let new_text: String = new.rope.chunks().collect();
+        Self {
+            old_path: label,
+            new_path: new.path.clone(),
+            rows: mdx_core::diff::side_by_side(old_text, &new_text),
+            scroll: 0,
+            page_height: 20,
+            quit_on_close: false,
+        }
+    }
+
+    /// Last useful scroll position: the final row at the bottom
+    fn max_scroll(&self) -> usize {
+        self.rows.len().saturating_sub(self.page_height.max(1))
+    }
+
+    /// Scroll by `delta` rows, staying within the document
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.max_scroll());
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = self.max_scroll();
+    }
+
+    /// First row of each run of differing rows
+    pub fn change_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::new();
+        let mut in_change = false;
+        for (idx, row) in self.rows.iter().enumerate() {
+            let changed = row.kind != RowKind::Equal;
+            if changed && !in_change {
+                starts.push(idx);
+            }
+            in_change = changed;
+        }
+        starts
+    }
+
+    /// Scroll the next change below the top row into view. Returns false
+    /// when there is none.
+    pub fn next_change(&mut self) -> bool {
+        let target = self
+            .change_starts()
+            .into_iter()
+            .find(|&start| start > self.scroll);
+        self.scroll_to_change(target)
+    }
+
+    /// Scroll the previous change above the top row into view. Returns
+    /// false when there is none.
+    pub fn prev_change(&mut self) -> bool {
+        let target = self
+            .change_starts()
+            .into_iter()
+            .rev()
+            .find(|&start| start < self.scroll);
+        self.scroll_to_change(target)
+    }
+
+    fn scroll_to_change(&mut self, target: Option<usize>) -> bool {
+        match target {
+            Some(start) => {
+                self.scroll = start;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Index (1-based) of the change at the top of the view, if any
+    pub fn current_change(&self) -> Option<usize> {
+        let starts = self.change_starts();
+        let passed = starts.iter().filter(|&&start| start <= self.scroll).count();
+        (passed > 0).then_some(passed)
+    }
+
+    /// Number of (added, removed, changed) rows
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.rows
+            .iter()
+            .fold((0, 0, 0), |(added, removed, changed), row| match row.kind {
+                RowKind::Added => (added + 1, removed, changed),
+                RowKind::Removed => (added, removed + 1, changed),
+                RowKind::Changed => (added, removed, changed + 1),
+                RowKind::Equal => (added, removed, changed),
+            })
+    }
+
+    /// Plain unified listing (`-`, `+`, or ` ` before each line), used when
+    /// stdout is not a terminal
+    pub fn plain_text(&self) -> String {
+        let mut out = String::new();
+        let mut added = Vec::new();
+        for row in &self.rows {
+            match (row.kind, &row.left, &row.right) {
+                (RowKind::Equal, Some(left), _) => {
+                    out.extend(added.drain(..));
+                    out.push_str(&format!(" {}\n", left.text));
+                }
+                (_, left, right) => {
+                    if let Some(left) = left {
+                        out.push_str(&format!("-{}\n", left.text));
+                    }
+                    if let Some(right) = right {
+                        added.push(format!("+{}\n", right.text));
+                    }
+                }
+            }
+        }
+        out.extend(added);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn doc(text: &str) -> (Document, tempfile::NamedTempFile) {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        let (doc, _warnings) = Document::load(file.path()).unwrap();
+        (doc, file)
+    }
+
+    #[test]
+    fn test_change_navigation() {
+        let old: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+        let new = old
+            .replace("line 5\n", "line five\n")
+            .replace("line 20\n", "");
+        let (old, _old_file) = doc(&old);
+        let (new, _new_file) = doc(&new);
+        let mut view = DiffView::new(&old, &new);
+        view.page_height = 10;
+
+        assert_eq!(view.change_starts(), vec![5, 20]);
+        assert_eq!(view.counts(), (0, 1, 1));
+        assert_eq!(view.current_change(), None);
+        assert!(view.next_change());
+        assert_eq!((view.scroll, view.current_change()), (5, Some(1)));
+        assert!(view.next_change());
+        assert_eq!(view.scroll, 20);
+        assert!(!view.next_change());
+        assert!(view.prev_change());
+        assert_eq!(view.scroll, 5);
+
+        view.scroll_to_bottom();
+        assert_eq!(view.scroll, 20);
+        view.scroll_by(-100);
+        assert_eq!(view.scroll, 0);
+    }
+
+    #[test]
+    fn test_plain_text_groups_removed_before_added() {
+        let (old, _old_file) = doc("a\nb\nc\n");
+        let (new, _new_file) = doc("a\nB\nc\nd\n");
+        let view = DiffView::new(&old, &new);
+        assert_eq!(view.plain_text(), " a\n-b\n+B\n c\n+d\n");
+    }
+}
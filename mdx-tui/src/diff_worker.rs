@@ -1,11 +1,11 @@
 //! Background diff computation worker thread
 
-use crossbeam_channel::{Receiver, Sender};
+use crate::worker_pool::{Queue, QueueOptions, Task, WorkerPool};
 use mdx_core::diff::DiffGutter;
-use std::collections::HashMap;
 use std::path::PathBuf;
-use std::thread;
 use std::time::{Duration, Instant};
+#[cfg(feature = "git")]
+use tracing::debug;
 
 /// Request to compute diff for a document
 #[derive(Debug, Clone)]
@@ -14,6 +14,10 @@ pub struct DiffRequest {
     pub path: PathBuf,
     pub rev: u64,
     pub current_text: String,
+    /// Compare with the git base (`git.diff`)
+    pub git: bool,
+    /// The text as last saved, when there are in-app edits to mark
+    pub saved_text: Option<String>,
 }
 
 /// Result of diff computation
@@ -24,77 +28,42 @@ pub struct DiffResult {
     pub gutter: DiffGutter,
 }
 
+impl Task for DiffRequest {
+    fn key(&self) -> usize {
+        self.doc_id
+    }
+
+    fn rev(&self) -> u64 {
+        self.rev
+    }
+}
+
 /// Diff worker handle
 pub struct DiffWorker {
-    request_tx: Sender<DiffRequest>,
-    result_rx: Receiver<DiffResult>,
-    _worker_thread: thread::JoinHandle<()>,
+    queue: Queue<DiffRequest, DiffResult>,
 }
 
 impl DiffWorker {
-    /// Spawn a new diff worker thread
-    pub fn spawn() -> Self {
-        let (request_tx, request_rx) = crossbeam_channel::unbounded();
-        let (result_tx, result_rx) = crossbeam_channel::unbounded();
-
-        let worker_thread = thread::spawn(move || {
-            worker_loop(request_rx, result_tx);
-        });
-
+    /// Diff worker computing diffs on `pool`
+    pub fn new(pool: &WorkerPool) -> Self {
+        let options = QueueOptions {
+            limit: 2,
+            // Coalesce bursts of edits and reloads
+            debounce: Duration::from_millis(75),
+        };
         Self {
-            request_tx,
-            result_rx,
-            _worker_thread: worker_thread,
+            queue: Queue::new(pool, options, || compute_diff),
         }
     }
 
     /// Send a diff request
     pub fn request_diff(&self, req: DiffRequest) {
-        let _ = self.request_tx.send(req);
+        self.queue.submit(req);
     }
 
     /// Try to receive a diff result (non-blocking)
     pub fn try_recv_result(&self) -> Option<DiffResult> {
-        self.result_rx.try_recv().ok()
-    }
-}
-
-/// Worker thread main loop
-fn worker_loop(request_rx: Receiver<DiffRequest>, result_tx: Sender<DiffResult>) {
-    let mut pending: HashMap<usize, DiffRequest> = HashMap::new();
-    let mut last_process = Instant::now();
-    let coalesce_window = Duration::from_millis(75);
-
-    loop {
-        // Try to receive requests with timeout
-        match request_rx.recv_timeout(Duration::from_millis(50)) {
-            Ok(req) => {
-                // Coalesce: keep only the latest request per doc_id
-                pending.insert(req.doc_id, req);
-                last_process = Instant::now();
-            }
-            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
-                // Timeout - check if we should process pending requests
-                if !pending.is_empty() && last_process.elapsed() >= coalesce_window {
-                    // Process all pending requests
-                    for (_doc_id, req) in pending.drain() {
-                        if let Some(result) = compute_diff(req) {
-                            let _ = result_tx.send(result);
-                        }
-                    }
-                }
-            }
-            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                // Channel closed, process any pending requests before exiting
-                for (_doc_id, req) in pending.drain() {
-                    if let Some(result) = compute_diff(req) {
-                        // Try to send result (may fail if receiver is already dropped)
-                        let _ = result_tx.send(result);
-                    }
-                }
-                break;
-            }
-        }
+        self.queue.try_recv()
     }
 }
 
@@ -105,22 +74,35 @@ fn compute_diff(req: DiffRequest) -> Option<DiffResult> {
         use mdx_core::diff::{diff_gutter_from_text, DiffGutter};
         use mdx_core::git::get_base_text_gix;
 
+        let start = Instant::now();
+
         // Get base text from git
-        let base_text = match get_base_text_gix(&req.path) {
-            Ok(Some(text)) => text,
-            Ok(None) | Err(_) => {
-                // Not in git or error - return empty gutter
-                let line_count = req.current_text.lines().count().max(1);
-                return Some(DiffResult {
-                    doc_id: req.doc_id,
-                    rev: req.rev,
-                    gutter: DiffGutter::empty(line_count),
-                });
+        let base_text = if req.git {
+            match get_base_text_gix(&req.path) {
+                Ok(Some(text)) => Some(text),
+                Ok(None) | Err(_) => {
+                    debug!(doc_id = req.doc_id, "No git base for {}", req.path.display());
+                    None
+                }
             }
+        } else {
+            None
         };
 
-        // Compute diff
-        let gutter = diff_gutter_from_text(&base_text, &req.current_text);
+        // Compute diff; not in git (or an error) leaves the git layer empty
+        let mut gutter = match base_text {
+            Some(base_text) => diff_gutter_from_text(&base_text, &req.current_text),
+            None => DiffGutter::empty(req.current_text.lines().count().max(1)),
+        };
+        if let Some(saved_text) = &req.saved_text {
+            gutter.set_saved_layer(saved_text, &req.current_text);
+        }
+        debug!(
+            doc_id = req.doc_id,
+            rev = req.rev,
+            "Diff computed in {:.1} ms",
+            start.elapsed().as_secs_f64() * 1000.0
+        );
 
         Some(DiffResult {
             doc_id: req.doc_id,
@@ -145,23 +127,24 @@ fn compute_diff(req: DiffRequest) -> Option<DiffResult> {
 mod tests {
     use super::*;
     use std::thread;
-    use std::time::Duration;
 
     #[test]
     fn test_worker_spawns() {
-        let _worker = DiffWorker::spawn();
+        let _worker = DiffWorker::new(&WorkerPool::spawn());
         // Worker should spawn without crashing
     }
 
     #[test]
     fn test_worker_processes_request() {
-        let worker = DiffWorker::spawn();
+        let worker = DiffWorker::new(&WorkerPool::spawn());
 
         let req = DiffRequest {
             doc_id: 0,
             path: PathBuf::from("/tmp/test.md"),
             rev: 1,
             current_text: "line 1\nline 2\n".to_string(),
+            git: true,
+            saved_text: None,
         };
 
         worker.request_diff(req);
@@ -180,7 +163,7 @@ mod tests {
 
     #[test]
     fn test_worker_coalesces_requests() {
-        let worker = DiffWorker::spawn();
+        let worker = DiffWorker::new(&WorkerPool::spawn());
 
         // Send multiple requests for the same doc
         for i in 1..=5 {
@@ -189,6 +172,8 @@ mod tests {
                 path: PathBuf::from("/tmp/test.md"),
                 rev: i,
                 current_text: format!("revision {}\n", i),
+                git: true,
+                saved_text: None,
             };
             worker.request_diff(req);
         }
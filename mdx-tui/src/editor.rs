@@ -1,6 +1,7 @@
 //! External editor integration
 
 use anyhow::{Context, Result};
+use ropey::Rope;
 use std::path::Path;
 use std::process::Command;
 
@@ -64,11 +65,84 @@ pub fn launch_editor(command: &str, args: &[String], file_path: &Path, line: usi
     Ok(())
 }
 
+/// Open a URL with the desktop's default handler, without waiting for it
+pub fn open_url(url: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = Command::new("xdg-open");
+
+    command
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to open {}", url))?;
+    Ok(())
+}
+
+/// First line that differs between two versions of a document, or `None`
+/// if they are identical
+pub fn first_changed_line(before: &Rope, after: &Rope) -> Option<usize> {
+    if before == after {
+        return None;
+    }
+    let changed = before
+        .lines()
+        .zip(after.lines())
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| before.len_lines().min(after.len_lines()));
+    // Appending to the last line shows up past the end of the shorter text
+    Some(changed.min(after.len_lines().saturating_sub(1)))
+}
+
+/// Where `line` of `before` ends up in `after`: lines above the first
+/// change stay put, lines below the last one shift by the change in length,
+/// and lines inside the changed stretch keep their number, within bounds
+pub fn follow_line(before: &Rope, after: &Rope, line: usize) -> usize {
+    let (old_len, new_len) = (before.len_lines(), after.len_lines());
+    let prefix = first_changed_line(before, after).unwrap_or(old_len);
+    let mut suffix = 0;
+    while suffix < old_len.min(new_len) - prefix
+        && before.line(old_len - 1 - suffix) == after.line(new_len - 1 - suffix)
+    {
+        suffix += 1;
+    }
+    if line >= old_len - suffix {
+        line + new_len - old_len
+    } else {
+        line.min(new_len.saturating_sub(1))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn test_follow_line() {
+        let before = Rope::from_str("a\nb\nc\nd\n");
+        let after = Rope::from_str("a\nnew\nnew\nb\nc\nd\n");
+        assert_eq!(follow_line(&before, &after, 0), 0);
+        assert_eq!(follow_line(&before, &after, 2), 4);
+        assert_eq!(follow_line(&before, &after, 3), 5);
+
+        // Removing lines pulls the ones below up
+        assert_eq!(follow_line(&after, &before, 5), 3);
+        assert_eq!(follow_line(&after, &before, 2), 2);
+
+        let same = Rope::from_str("a\nb\n");
+        assert_eq!(follow_line(&same, &same, 1), 1);
+    }
+
     #[test]
     fn test_expand_template() {
         let path = PathBuf::from("/tmp/test.md");
@@ -83,6 +157,16 @@ mod tests {
         assert_eq!(result, "--goto /home/user/doc.md:10:0");
     }
 
+    #[test]
+    fn test_first_changed_line() {
+        let before = Rope::from("a\nb\nc\n");
+        assert_eq!(first_changed_line(&before, &before.clone()), None);
+        assert_eq!(first_changed_line(&before, &Rope::from("a\nB\nc\n")), Some(1));
+        // Appended and removed lines
+        assert_eq!(first_changed_line(&before, &Rope::from("a\nb\nc\nd\n")), Some(3));
+        assert_eq!(first_changed_line(&before, &Rope::from("a\n")), Some(1));
+    }
+
     #[test]
     fn test_resolve_editor_command_literal() {
         let result = resolve_editor_command("nvim");
@@ -0,0 +1,75 @@
+//! Background code block runs
+//!
+//! A block run with `X` may take up to [`RUN_BLOCK_TIMEOUT`] before it is
+//! killed, so it runs on a worker thread while the UI keeps drawing.
+//!
+//! [`RUN_BLOCK_TIMEOUT`]: crate::app::RUN_BLOCK_TIMEOUT
+
+use crate::worker_pool::{Queue, QueueOptions, Task, WorkerPool};
+use mdx_core::exec::{CodeBlock, ExecOutput};
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::info;
+
+/// Request to run a code block
+#[derive(Debug, Clone)]
+pub struct RunBlockRequest {
+    /// Counts runs, so only the latest one's result is shown
+    pub run: u64,
+    pub block: CodeBlock,
+    /// Directory the block runs in
+    pub dir: PathBuf,
+}
+
+/// Output of a finished run
+#[derive(Debug, Clone)]
+pub struct RunBlockResult {
+    pub run: u64,
+    /// Captured output, or why the block could not be started
+    pub result: Result<ExecOutput, String>,
+}
+
+impl Task for RunBlockRequest {
+    fn rev(&self) -> u64 {
+        self.run
+    }
+}
+
+/// Code block runner handle
+pub struct ExecWorker {
+    queue: Queue<RunBlockRequest, RunBlockResult>,
+}
+
+impl ExecWorker {
+    /// Block runner running one block at a time on `pool`
+    pub fn new(pool: &WorkerPool) -> Self {
+        let options = QueueOptions {
+            limit: 1,
+            debounce: Duration::ZERO,
+        };
+        Self {
+            queue: Queue::new(pool, options, || run_block),
+        }
+    }
+
+    /// Queue a block to run
+    pub fn request_run(&self, req: RunBlockRequest) {
+        self.queue.submit(req);
+    }
+
+    /// Try to receive a run's output (non-blocking)
+    pub fn try_recv_result(&self) -> Option<RunBlockResult> {
+        self.queue.try_recv()
+    }
+}
+
+fn run_block(req: RunBlockRequest) -> Option<RunBlockResult> {
+    let block = &req.block;
+    info!("Running {} block at line {}", block.lang, block.start_line + 1);
+    let result = mdx_core::exec::run_block(block, &req.dir, crate::app::RUN_BLOCK_TIMEOUT)
+        .map_err(|e| format!("{:#}", e));
+    Some(RunBlockResult {
+        run: req.run,
+        result,
+    })
+}
@@ -0,0 +1,255 @@
+//! Fuzzy finder overlay (`Ctrl+P`)
+//!
+//! One list of headings, links, and open files, narrowed as you type and
+//! ranked by how well each entry matches the query. The command palette
+//! (`Ctrl+Shift+P`) is the same overlay over the list of commands.
+
+/// What a finder entry points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinderKind {
+    Heading,
+    Link,
+    File,
+    /// A command of the palette
+    Command(crate::commands::Command),
+}
+
+impl FinderKind {
+    /// Short tag shown in front of each entry
+    pub fn tag(self) -> &'static str {
+        match self {
+            FinderKind::Heading => "head",
+            FinderKind::Link => "link",
+            FinderKind::File => "file",
+            FinderKind::Command(_) => "cmd",
+        }
+    }
+}
+
+/// An entry of the finder list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinderItem {
+    pub kind: FinderKind,
+    /// Text matched against the query
+    pub label: String,
+    /// Extra context shown dimmed after the label
+    pub detail: String,
+    /// Buffer the destination is in
+    pub buffer: usize,
+    /// Destination line, or `None` to keep the buffer's own position
+    pub line: Option<usize>,
+}
+
+/// An entry that matches the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinderMatch {
+    /// Index into [`Finder::items`]
+    pub item: usize,
+    pub score: i64,
+    /// Char indices of the label that matched, for highlighting
+    pub positions: Vec<usize>,
+}
+
+/// State of the finder overlay
+pub struct Finder {
+    pub query: String,
+    pub items: Vec<FinderItem>,
+    /// Matching entries, best first
+    pub matches: Vec<FinderMatch>,
+    /// Index into `matches`
+    pub selected: usize,
+}
+
+impl Finder {
+    pub fn new(items: Vec<FinderItem>) -> Self {
+        let mut finder = Self {
+            query: String::new(),
+            items,
+            matches: Vec::new(),
+            selected: 0,
+        };
+        finder.refresh();
+        finder
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refresh();
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.refresh();
+    }
+
+    pub fn move_down(&mut self) {
+        self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Whether this is the command palette rather than the finder
+    pub fn is_palette(&self) -> bool {
+        self.items
+            .first()
+            .is_some_and(|item| matches!(item.kind, FinderKind::Command(_)))
+    }
+
+    /// The highlighted entry, if anything matches
+    pub fn selected_item(&self) -> Option<&FinderItem> {
+        self.matches
+            .get(self.selected)
+            .and_then(|m| self.items.get(m.item))
+    }
+
+    /// Re-rank the entries for the current query. Equal scores favour the
+    /// shorter label, then the list order (headings, links, files).
+    fn refresh(&mut self) {
+        self.matches = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(item, entry)| {
+                fuzzy_match(&self.query, &entry.label).map(|(score, positions)| FinderMatch {
+                    item,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        let items = &self.items;
+        self.matches
+            .sort_by_key(|m| (std::cmp::Reverse(m.score), items[m.item].label.len()));
+        self.selected = 0;
+    }
+}
+
+/// Match `query` as a subsequence of `text`, returning a score (higher is
+/// better) and the matched char indices of `text`.
+///
+/// Matching ignores case unless the query has an uppercase letter.
+/// Consecutive matches and matches at word starts score higher; skipped
+/// characters cost a little. Every start position of the first query
+/// character is tried and the best result kept.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    let text: Vec<char> = text.chars().collect();
+    let Some(&first) = query.first() else {
+        return Some((0, Vec::new()));
+    };
+
+    let word_start = |idx: usize| {
+        idx == 0 || {
+            let (prev, cur) = (text[idx - 1], text[idx]);
+            !prev.is_alphanumeric() || (prev.is_lowercase() && cur.is_uppercase())
+        }
+    };
+
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..text.len()).filter(|&idx| fold(text[idx]) == first) {
+        let mut positions = vec![start];
+        let mut idx = start + 1;
+        for &wanted in &query[1..] {
+            while idx < text.len() && fold(text[idx]) != wanted {
+                idx += 1;
+            }
+            if idx == text.len() {
+                break;
+            }
+            positions.push(idx);
+            idx += 1;
+        }
+        if positions.len() < query.len() {
+            // Later starts have even less text left to match
+            break;
+        }
+
+        let mut score = -(start.min(5) as i64);
+        for (n, &pos) in positions.iter().enumerate() {
+            score += 1;
+            if word_start(pos) {
+                score += 8;
+            }
+            if n > 0 {
+                let gap = pos - positions[n - 1] - 1;
+                if gap == 0 {
+                    score += 5;
+                } else {
+                    score -= gap.min(3) as i64;
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|(b, _)| score > *b) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: FinderKind, label: &str) -> FinderItem {
+        FinderItem {
+            kind,
+            label: label.to_string(),
+            detail: String::new(),
+            buffer: 0,
+            line: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match_prefers_word_starts_and_runs() {
+        let (_, positions) = fuzzy_match("inst", "Installation").unwrap();
+        assert_eq!(positions, vec![0, 1, 2, 3]);
+        assert!(fuzzy_match("xyz", "Installation").is_none());
+        // Case is ignored unless the query has capitals
+        assert!(fuzzy_match("INST", "installation").is_none());
+
+        let word = fuzzy_match("cg", "Config guide").unwrap().0;
+        let middle = fuzzy_match("cg", "Packaging").unwrap().0;
+        assert!(word > middle);
+    }
+
+    #[test]
+    fn test_finder_ranks_and_selects() {
+        let mut finder = Finder::new(vec![
+            item(FinderKind::Heading, "Usage"),
+            item(FinderKind::Heading, "Install on Linux"),
+            item(FinderKind::File, "install.md"),
+        ]);
+        assert_eq!(finder.matches.len(), 3);
+
+        for c in "instl".chars() {
+            finder.push_char(c);
+        }
+        let labels: Vec<&str> = finder
+            .matches
+            .iter()
+            .map(|m| finder.items[m.item].label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["install.md", "Install on Linux"]);
+
+        finder.move_down();
+        finder.move_down();
+        assert_eq!(finder.selected_item().unwrap().label, "Install on Linux");
+        finder.pop_char();
+        assert_eq!(finder.selected, 0);
+    }
+}
@@ -0,0 +1,37 @@
+//! Commits that touched the current file (`gl`, `:GitLog`)
+//!
+//! Selecting a commit compares that revision with the document in a
+//! [`DiffView`](crate::diff_view::DiffView).
+
+use mdx_core::git::FileCommit;
+
+/// Commits listed in the git log panel
+const MAX_COMMITS: usize = 500;
+
+/// State of the git log panel
+pub struct GitLog {
+    pub commits: Vec<FileCommit>,
+    pub selected: usize,
+}
+
+impl GitLog {
+    /// History of the file at `path`, newest first
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            commits: mdx_core::git::file_history(path, MAX_COMMITS)?,
+            selected: 0,
+        })
+    }
+
+    pub fn move_down(&mut self) {
+        self.selected = (self.selected + 1).min(self.commits.len().saturating_sub(1));
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_commit(&self) -> Option<&FileCommit> {
+        self.commits.get(self.selected)
+    }
+}
@@ -0,0 +1,354 @@
+//! Keybinding reference
+//!
+//! One table drives the `F1` help popup, `mdx help`, and the KEYS section of
+//! the man page, so they can't drift apart. Entries for commands take their
+//! keys from [`KEYMAP`](crate::commands::KEYMAP).
+
+use crate::commands::Command;
+use std::borrow::Cow;
+
+/// Keys of a help entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpKeys {
+    /// Written out, for keys that aren't a command of their own
+    Text(&'static str),
+    /// Whatever the command is bound to
+    Command(Command),
+}
+
+impl HelpKeys {
+    /// The keys as shown in the help
+    pub fn text(self) -> Cow<'static, str> {
+        match self {
+            HelpKeys::Text(text) => Cow::Borrowed(text),
+            HelpKeys::Command(command) => Cow::Owned(command.keys()),
+        }
+    }
+}
+
+/// A key (or key sequence) and what it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelpEntry {
+    pub keys: HelpKeys,
+    pub description: &'static str,
+}
+
+/// A titled group of keybindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelpSection {
+    pub title: &'static str,
+    pub entries: &'static [HelpEntry],
+    /// Extra remark shown after the entries
+    pub note: Option<&'static str>,
+}
+
+const fn entry(keys: &'static str, description: &'static str) -> HelpEntry {
+    HelpEntry {
+        keys: HelpKeys::Text(keys),
+        description,
+    }
+}
+
+const fn command(command: Command, description: &'static str) -> HelpEntry {
+    HelpEntry {
+        keys: HelpKeys::Command(command),
+        description,
+    }
+}
+
+/// Every keybinding, grouped as in the help popup
+pub const SECTIONS: &[HelpSection] = &[
+    HelpSection {
+        title: "Navigation",
+        entries: &[
+            command(Command::MoveDown, "Move cursor down"),
+            command(Command::MoveUp, "Move cursor up"),
+            command(Command::HalfPageDown, "Scroll view and cursor half a page down"),
+            command(Command::HalfPageUp, "Scroll view and cursor half a page up"),
+            command(Command::ScrollDown, "Scroll view a line down, keeping the cursor"),
+            command(Command::ScrollUp, "Scroll view a line up, keeping the cursor"),
+            command(Command::PageDown, "Scroll full page down"),
+            command(Command::PageUp, "Scroll full page up"),
+            command(Command::Top, "Go to top"),
+            command(Command::Bottom, "Go to bottom"),
+            entry("5G, 5gg", "Go to line 5"),
+            command(Command::Breadcrumbs, "Jump to an ancestor heading"),
+            command(Command::FuzzyFinder, "Fuzzy find headings, links, files"),
+            command(Command::CommandPalette, "Command palette (or > in Ctrl+P)"),
+            command(Command::ParentHeading, "Jump to parent heading"),
+            command(Command::NextHeading, "Next heading"),
+            command(Command::PrevHeading, "Previous heading"),
+            entry("Enter, gf", "Follow the link, URL, or file path on the line"),
+            command(Command::Preview, "Preview the footnote or link target on the line"),
+            command(Command::JumpBack, "Jump back"),
+            command(Command::JumpForward, "Jump forward"),
+            command(Command::Stats, "Word count and reading time"),
+        ],
+        note: Some("A count repeats a motion: 5j, 3]], 2 Ctrl+d"),
+    },
+    HelpSection {
+        title: "Search",
+        entries: &[
+            command(Command::Search, "Start search"),
+            command(Command::SearchBackward, "Start search upwards"),
+            entry("/text/+2", "Land 2 lines below the match (?text?-1 above)"),
+            command(Command::NextMatch, "Next match in the search's direction"),
+            command(Command::PrevMatch, "Next match the other way"),
+            entry("g/", "Search only the current section"),
+            entry("Esc", "Cancel search and go back"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Visual Mode",
+        entries: &[
+            entry("V", "Enter visual line mode"),
+            entry("as / is", "Select section (is: without heading)"),
+            entry("ac / ic", "Select code block (ic: without fences)"),
+            entry("al / il", "Select whole list / current item"),
+            entry("at / it", "Select table (it: body rows only)"),
+            entry("Y", "Yank (copy) selected lines"),
+            entry("/", "Search only the selected lines"),
+            entry("|", "Pipe selected lines to a shell command"),
+            entry(":'<,'>w FILE", "Write selection (or section) to a file"),
+            entry(":'<,'>!CMD", "Pipe selection to CMD (needs no_exec off)"),
+            entry("Esc", "Exit visual mode"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Folding",
+        entries: &[
+            entry("←", "Collapse current section"),
+            entry("→", "Expand current section"),
+            command(Command::ToggleFold, "Toggle fold of current section"),
+            command(Command::OpenFold, "Open fold of current section"),
+            command(Command::CloseFold, "Close fold of current section"),
+            command(Command::CloseAllFolds, "Close all folds"),
+            command(Command::OpenAllFolds, "Open all folds"),
+        ],
+        note: Some("Works on heading or anywhere in section"),
+    },
+    HelpSection {
+        title: "Panes",
+        entries: &[
+            command(Command::SplitH, "Split horizontally"),
+            command(Command::SplitV, "Split vertically"),
+            entry("Ctrl+W hjkl/↑↓←→", "Move focus between panes"),
+            entry("Ctrl+↑↓←→", "Move focus between panes"),
+            command(Command::Quit, "Close pane (quit if last, asking to save edits)"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Buffers",
+        entries: &[
+            command(Command::NextBuffer, "Next open file"),
+            command(Command::PrevBuffer, "Previous open file"),
+            command(Command::BufferPicker, "Pick an open file (d closes it; also :ls)"),
+            entry(":bn / :bp / :b N", "Next/previous/Nth open file"),
+            entry("gq, :bd", "Close the shown file (:bd! discards edits)"),
+            entry("Click tab", "Show that file"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Editing",
+        entries: &[
+            command(Command::InsertMode, "Edit the line in place (Esc to stop)"),
+            entry(":w / :q / :wq", "Save / quit / save and quit"),
+            entry(":w!", "Save over changes made on disk"),
+            entry(":q!", "Quit discarding edits"),
+            command(Command::OpenEditor, "Open in $EDITOR"),
+            command(Command::RunBlock, "Run shell/Python block (--insecure, no_exec off)"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Outline",
+        entries: &[
+            entry("K / J", "Move the section up/down past its sibling"),
+            entry("< / >", "Promote/demote the section's headings"),
+            entry("u", "Undo the last outline change"),
+        ],
+        note: Some("In the focused TOC sidebar; changes are saved to the file"),
+    },
+    HelpSection {
+        title: "Mouse",
+        entries: &[
+            entry("Click pane", "Focus pane and move cursor"),
+            entry("Click+drag", "Select text (line-based)"),
+            entry("Ctrl+Shift+C", "Copy selection to clipboard"),
+            entry("Click TOC", "Jump to heading"),
+            entry("Scroll wheel", "Scroll pane or TOC"),
+            entry("Drag border", "Resize split panes"),
+        ],
+        note: None,
+    },
+    HelpSection {
+        title: "Other",
+        entries: &[
+            command(Command::ToggleToc, "Toggle TOC sidebar"),
+            command(Command::TocDialog, "Open TOC picker (/ filters, section preview)"),
+            command(Command::ToggleTheme, "Cycle through the built-in themes"),
+            command(Command::Options, "Open options dialog"),
+            command(Command::SecurityWarnings, "Toggle security warnings pane"),
+            command(Command::LinkDiagnostics, "List broken links"),
+            command(Command::Present, "Present as slides (←/→, q to exit)"),
+            command(Command::FocusMode, "Toggle focus mode"),
+            #[cfg(feature = "git")]
+            command(Command::DeletedLines, "Show/hide deleted lines (git diff)"),
+            #[cfg(feature = "git")]
+            command(
+                Command::GitLog,
+                "Commits touching this file (Enter to compare; also :GitLog)",
+            ),
+            #[cfg(feature = "images")]
+            command(Command::ImageAltText, "Toggle images/alt text only"),
+            command(Command::ToggleComments, "Show/hide HTML comments"),
+            entry("]s / [s", "Next/previous misspelling"),
+            entry("]c / [c", "Next/previous git change (hunk)"),
+            entry("]x / [x", "Next/previous merge conflict"),
+            command(Command::SpellSuggestions, "Spelling suggestions for line"),
+            command(Command::ToggleRaw, "Toggle raw/rendered mode"),
+            command(Command::Reload, "Reload document"),
+            entry(":config reload", "Re-read the config file"),
+            entry(":imagecache", "Image cache hits, misses, and size"),
+            entry(":log", "Recent log events (with --verbose)"),
+            entry(":anchor", "Copy the #anchor of the current heading"),
+            entry(":noh", "Hide search highlights (n/N shows them again)"),
+            entry(":fmt", "Normalize the Markdown, as mdx fmt does"),
+            entry("yc", "Copy the code block under the cursor"),
+            command(Command::CopyTable, "Copy the table under the cursor as TSV"),
+            command(Command::ExportTable, "Write the table under the cursor to a file"),
+            entry(":export [csv|tsv] FILE", "Write the table under the cursor to FILE"),
+            entry("gs", "Show the cursor line in the editor (--listen)"),
+            command(Command::Redraw, "Redraw/refresh screen"),
+            command(Command::Help, "Toggle this help (/ inside it searches)"),
+            entry("Ctrl+Z", "Suspend to the shell (fg resumes)"),
+            command(Command::ForceQuit, "Force quit"),
+        ],
+        note: None,
+    },
+];
+
+/// Width of the key column in plain-text listings
+pub const KEY_COLUMN_WIDTH: usize = 18;
+
+/// Sections with the entries that match `query`, ignoring case. A query
+/// matching a section title keeps the whole section; empty sections are
+/// left out, and an empty query keeps everything.
+pub fn matching_sections(query: &str) -> Vec<(&'static HelpSection, Vec<&'static HelpEntry>)> {
+    let query = query.to_lowercase();
+    SECTIONS
+        .iter()
+        .filter_map(|section| {
+            let whole = section.title.to_lowercase().contains(&query);
+            let entries: Vec<&HelpEntry> = section
+                .entries
+                .iter()
+                .filter(|entry| {
+                    whole
+                        || entry.keys.text().to_lowercase().contains(&query)
+                        || entry.description.to_lowercase().contains(&query)
+                })
+                .collect();
+            (!entries.is_empty()).then_some((section, entries))
+        })
+        .collect()
+}
+
+/// Plain-text reference, as printed by `mdx help`
+pub fn plain_text() -> String {
+    use unicode_width::UnicodeWidthStr;
+
+    let mut out = String::new();
+    for (idx, section) in SECTIONS.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        out.push_str(section.title);
+        out.push('\n');
+        for entry in section.entries {
+            let keys = entry.keys.text();
+            let pad = KEY_COLUMN_WIDTH.saturating_sub(keys.width()).max(1);
+            out.push_str(&format!("  {}{}{}\n", keys, " ".repeat(pad), entry.description));
+        }
+        if let Some(note) = section.note {
+            out.push_str(&format!("  Note: {}\n", note));
+        }
+    }
+    out
+}
+
+/// Escape text for roff: backslashes, and dots or quotes that would start
+/// a request at the beginning of a line
+pub fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// The KEYS section of the man page, in roff
+pub fn man_keys_section() -> String {
+    let mut out = String::from(".SH KEYS\n");
+    for section in SECTIONS {
+        out.push_str(&format!(".SS {}\n", roff_escape(section.title)));
+        for entry in section.entries {
+            out.push_str(&format!(
+                ".TP\n.B {}\n{}\n",
+                roff_escape(&entry.keys.text()),
+                roff_escape(entry.description)
+            ));
+        }
+        if let Some(note) = section.note {
+            out.push_str(&format!(".PP\n{}\n", roff_escape(note)));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_aligns_descriptions() {
+        let text = plain_text();
+        assert!(text.starts_with("Navigation\n"));
+        assert!(text.contains(&format!("\n  j, ↓{}Move cursor down\n", " ".repeat(14))));
+        assert!(text.contains("  Note: Works on heading or anywhere in section\n"));
+    }
+
+    #[test]
+    fn test_matching_sections() {
+        assert_eq!(matching_sections("").len(), SECTIONS.len());
+
+        let matches = matching_sections("FOLD");
+        let folding = matches.iter().find(|(s, _)| s.title == "Folding").unwrap();
+        assert_eq!(folding.1.len(), folding.0.entries.len());
+        assert!(matches.iter().all(|(_, entries)| !entries.is_empty()));
+
+        let matches = matching_sections("yank");
+        assert!(matches.iter().any(|(_, e)| e.iter().any(|e| e.keys.text() == "Y")));
+        assert!(matching_sections("no such binding").is_empty());
+    }
+
+    #[test]
+    fn test_man_keys_section() {
+        let roff = man_keys_section();
+        assert!(roff.starts_with(".SH KEYS\n.SS Navigation\n"));
+        assert!(roff.contains(".TP\n.B :w / :q / :wq\nSave / quit / save and quit\n"));
+        assert!(roff.contains(".B Ctrl+W s\n"));
+    }
+
+    #[test]
+    fn test_roff_escape() {
+        assert_eq!(roff_escape(".hidden"), "\\&.hidden");
+        assert_eq!(roff_escape("a\\b"), "a\\eb");
+        assert_eq!(roff_escape("--insecure"), "\\-\\-insecure");
+    }
+}
@@ -1,7 +1,18 @@
-//! Image metadata reading for placeholders
+//! Image metadata reading for placeholders, and the cache that keeps it
+//! between frames
+//!
+//! Images are read on the worker pool, so a large or slow file doesn't
+//! stall a frame; until its metadata arrives an image shows the plain
+//! placeholder.
 
 #[cfg(feature = "images")]
-use std::path::Path;
+use crate::worker_pool::{Queue, QueueOptions, Task, WorkerPool};
+#[cfg(feature = "images")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "images")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "images")]
+use std::time::SystemTime;
 
 #[cfg(feature = "images")]
 /// Image metadata (just dimensions)
@@ -24,6 +35,200 @@ impl ImageMetadata {
             height: size.height,
         })
     }
+
+    /// Memory the image takes once decoded to RGBA
+    pub fn decoded_bytes(&self) -> u64 {
+        self.width as u64 * self.height as u64 * 4
+    }
+}
+
+/// An image file as of a modification time
+#[cfg(feature = "images")]
+type ImageKey = (PathBuf, Option<SystemTime>);
+
+/// Request to read the metadata of an image
+#[cfg(feature = "images")]
+struct ImageRequest {
+    /// Tells requests apart, as every image is read on its own
+    id: usize,
+    image: ImageKey,
+}
+
+#[cfg(feature = "images")]
+impl Task for ImageRequest {
+    fn key(&self) -> usize {
+        self.id
+    }
+
+    fn rev(&self) -> u64 {
+        0
+    }
+}
+
+/// Metadata read for an image, or why it couldn't be
+#[cfg(feature = "images")]
+type ImageLoaded = (ImageKey, Result<ImageMetadata, String>);
+
+/// Hit and miss counts, shown by `:imagecache`
+#[cfg(feature = "images")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to stay within the budget
+    pub evictions: u64,
+}
+
+/// Images keyed by path and modification time. The cache is bounded by
+/// the decoded RGBA size of its images rather than by entry count, so a
+/// few huge images can't pin hundreds of megabytes; least recently used
+/// images go first.
+#[cfg(feature = "images")]
+pub struct ImageCache {
+    entries: lru::LruCache<ImageKey, ImageMetadata>,
+    used_bytes: u64,
+    budget_bytes: u64,
+    stats: CacheStats,
+    /// Images being read on the pool
+    loading: HashSet<ImageKey>,
+    /// Images too large for the budget, which only their metadata is
+    /// kept for, so they aren't read every frame
+    oversized: HashMap<ImageKey, ImageMetadata>,
+    /// Images that couldn't be read
+    failed: HashSet<ImageKey>,
+    requests: usize,
+    queue: Queue<ImageRequest, ImageLoaded>,
+}
+
+#[cfg(feature = "images")]
+impl ImageCache {
+    /// Create an empty cache holding up to `budget_mb` MiB, reading
+    /// images on `pool`
+    pub fn new(budget_mb: u64, pool: &WorkerPool) -> Self {
+        let options = QueueOptions {
+            limit: 2,
+            debounce: std::time::Duration::ZERO,
+        };
+        Self {
+            entries: lru::LruCache::unbounded(),
+            used_bytes: 0,
+            budget_bytes: budget_mb.saturating_mul(1024 * 1024),
+            stats: CacheStats::default(),
+            loading: HashSet::new(),
+            oversized: HashMap::new(),
+            failed: HashSet::new(),
+            requests: 0,
+            queue: Queue::new(pool, options, || read_metadata),
+        }
+    }
+
+    /// Change the budget, evicting images until the cache fits
+    pub fn set_budget_mb(&mut self, budget_mb: u64) {
+        self.budget_bytes = budget_mb.saturating_mul(1024 * 1024);
+        self.evict_to(self.budget_bytes);
+    }
+
+    /// Metadata for the image at `path`, if it has been read. A miss, or
+    /// a file changed since it was read, queues a read on the pool and
+    /// gives `None` until [`ImageCache::poll`] takes in the result.
+    pub fn get(&mut self, path: &Path) -> Option<ImageMetadata> {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let key = (path.to_path_buf(), mtime);
+        if let Some(metadata) = self.entries.get(&key).or(self.oversized.get(&key)) {
+            self.stats.hits += 1;
+            return Some(metadata.clone());
+        }
+        if self.loading.contains(&key) || self.failed.contains(&key) {
+            return None;
+        }
+        self.stats.misses += 1;
+
+        self.requests += 1;
+        self.loading.insert(key.clone());
+        self.queue.submit(ImageRequest {
+            id: self.requests,
+            image: key,
+        });
+        None
+    }
+
+    /// Take in images read since the last call; true if any were. Images
+    /// larger than the whole budget aren't cached.
+    pub fn poll(&mut self) -> bool {
+        let mut any = false;
+        while let Some((key, result)) = self.queue.try_recv() {
+            any = true;
+            self.loading.remove(&key);
+            match result {
+                Ok(metadata) => self.insert(key, metadata),
+                Err(e) => {
+                    tracing::debug!("Could not read image {}: {}", key.0.display(), e);
+                    self.failed.insert(key);
+                }
+            }
+        }
+        any
+    }
+
+    fn insert(&mut self, key: ImageKey, metadata: ImageMetadata) {
+        tracing::debug!(
+            "Image cache miss for {} ({}x{})",
+            key.0.display(),
+            metadata.width,
+            metadata.height
+        );
+        let size = metadata.decoded_bytes();
+        if size > self.budget_bytes {
+            self.oversized.insert(key, metadata);
+            return;
+        }
+        self.evict_to(self.budget_bytes - size);
+        self.used_bytes += size;
+        if let Some((_, old)) = self.entries.push(key, metadata) {
+            self.used_bytes -= old.decoded_bytes();
+        }
+    }
+
+    /// Drop least recently used images until at most `limit` bytes are used
+    fn evict_to(&mut self, limit: u64) {
+        while self.used_bytes > limit {
+            let Some((_, old)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.used_bytes -= old.decoded_bytes();
+            self.stats.evictions += 1;
+            tracing::debug!(used_bytes = self.used_bytes, "Evicted image from cache");
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Number of cached images
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Decoded size of the cached images
+    pub fn used_bytes(&self) -> u64 {
+        self.used_bytes
+    }
+
+    pub fn budget_bytes(&self) -> u64 {
+        self.budget_bytes
+    }
+}
+
+/// Pool handler reading the metadata of a requested image
+#[cfg(feature = "images")]
+fn read_metadata(req: ImageRequest) -> Option<ImageLoaded> {
+    let metadata = ImageMetadata::from_path(&req.image.0).map_err(|e| e.to_string());
+    Some((req.image, metadata))
 }
 
 #[cfg(all(test, feature = "images"))]
@@ -51,6 +256,84 @@ mod tests {
         file
     }
 
+    /// PNG header claiming `width` x `height`; enough for the size reader
+    fn create_png_header(width: u32, height: u32) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        let mut data = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52]);
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[0x08, 0x06, 0x00, 0x00, 0x00]);
+        file.write_all(&data).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    /// Wait for the pool to read the image at `path`
+    fn load(cache: &mut ImageCache, path: &Path) -> Option<ImageMetadata> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            if let Some(metadata) = cache.get(path) {
+                return Some(metadata);
+            }
+            if std::time::Instant::now() > deadline {
+                return None;
+            }
+            if !cache.poll() {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cache_counts_hits_and_misses() {
+        let file = create_test_png();
+        let mut cache = ImageCache::new(1, &WorkerPool::spawn());
+        assert!(cache.get(file.path()).is_none(), "read on the pool");
+        assert_eq!(load(&mut cache, file.path()).unwrap().width, 1);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 0
+            }
+        );
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.used_bytes(), 4);
+    }
+
+    #[test]
+    fn test_cache_evicts_to_budget() {
+        // 512x512 RGBA is exactly 1 MiB
+        let first = create_png_header(512, 512);
+        let second = create_png_header(256, 256);
+        let huge = create_png_header(1024, 1024);
+        let mut cache = ImageCache::new(1, &WorkerPool::spawn());
+
+        load(&mut cache, first.path()).unwrap();
+        load(&mut cache, second.path()).unwrap();
+        assert_eq!(cache.len(), 1, "the first image was evicted");
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.used_bytes(), 256 * 256 * 4);
+
+        // Too big for the whole budget: read, but not cached
+        assert_eq!(load(&mut cache, huge.path()).unwrap().width, 1024);
+        assert_eq!(cache.len(), 1);
+
+        let text = tempfile::Builder::new().suffix(".png").tempfile().unwrap();
+        assert!(cache.get(text.path()).is_none());
+        while !cache.poll() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(cache.get(text.path()).is_none(), "not an image");
+        assert_eq!(cache.stats().misses, 4, "and not read again");
+
+        cache.set_budget_mb(0);
+        assert!(cache.is_empty());
+        assert_eq!(cache.used_bytes(), 0);
+    }
+
     #[test]
     fn test_read_metadata() {
         let file = create_test_png();
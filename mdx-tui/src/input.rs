@@ -1,11 +1,15 @@
 //! Input handling and keybindings
 
 use crate::app::{App, KeyPrefix, MouseState};
-use crate::panes::{Direction, PaneId, SplitDir};
+use crate::commands::{self, Command};
+use crate::panes::{Direction, PaneId};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::layout::Rect;
 
+/// Largest count prefix; more digits leave it there
+const MAX_COUNT: usize = 99_999;
+
 /// Result of handling input
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
@@ -13,6 +17,199 @@ pub enum Action {
     Quit,
     OpenEditor,
     Redraw,
+    /// Stop the process like a shell job (Ctrl+Z)
+    Suspend,
+}
+
+/// Keys while presenting slides: next/previous, first/last, and exit
+fn handle_presentation_key(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+            return Action::Quit;
+        }
+        KeyCode::Right
+        | KeyCode::Down
+        | KeyCode::PageDown
+        | KeyCode::Enter
+        | KeyCode::Char(' ' | 'l' | 'j' | 'n') => app.next_slide(),
+        KeyCode::Left
+        | KeyCode::Up
+        | KeyCode::PageUp
+        | KeyCode::Backspace
+        | KeyCode::Char('h' | 'k' | 'p' | 'N') => app.prev_slide(),
+        KeyCode::Home | KeyCode::Char('g') => app.goto_slide(0),
+        KeyCode::End | KeyCode::Char('G') => app.goto_slide(usize::MAX),
+        KeyCode::Esc | KeyCode::Char('q' | 'P') => {
+            app.stop_presentation();
+            if app.should_quit {
+                return Action::Quit;
+            }
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Keys on the error screen for a document that couldn't be loaded:
+/// retry, open another file, go back to the last loaded copy, or quit
+fn handle_load_error_key(app: &mut App, key: KeyEvent) -> Action {
+    let Some(error) = app.load_error.as_mut() else {
+        return Action::Continue;
+    };
+    if let Some(prompt) = error.prompt.as_mut() {
+        match key.code {
+            KeyCode::Esc => error.prompt = None,
+            KeyCode::Backspace => {
+                prompt.pop();
+            }
+            KeyCode::Char(c) => prompt.push(c),
+            KeyCode::Enter => {
+                let path = std::path::PathBuf::from(prompt.trim());
+                error.prompt = None;
+                if let Err(e) = app.open_document(&path) {
+                    app.set_error_message(format!("{:#}", e));
+                }
+            }
+            _ => {}
+        }
+        return Action::Continue;
+    }
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+            return Action::Quit;
+        }
+        KeyCode::Char('r') => app.retry_load(),
+        KeyCode::Char('o') => error.prompt = Some(error.path.display().to_string()),
+        // A failed reload can go back to the copy loaded before it
+        KeyCode::Esc if app.doc.loaded_mtime.is_some() => app.load_error = None,
+        KeyCode::Char('q') => {
+            app.confirm_quit();
+            if app.should_quit {
+                return Action::Quit;
+            }
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Keys while a confirmation prompt is up: only its answers (and Ctrl+C)
+/// do anything
+fn handle_confirm_key(app: &mut App, key: KeyEvent) -> Action {
+    let answer = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+            return Action::Quit;
+        }
+        KeyCode::Char('y' | 'Y') => crate::app::ConfirmAnswer::Yes,
+        KeyCode::Char('n' | 'N') => crate::app::ConfirmAnswer::No,
+        KeyCode::Char('c' | 'C') | KeyCode::Esc => crate::app::ConfirmAnswer::Cancel,
+        _ => return Action::Continue,
+    };
+    app.answer_confirm(answer);
+    if app.should_quit {
+        Action::Quit
+    } else {
+        Action::Continue
+    }
+}
+
+/// Keys in the two-file diff view: scrolling, jumping between changes,
+/// and quitting (or closing, for a revision opened from the git log)
+#[cfg(feature = "git")]
+fn handle_diff_view_key(app: &mut App, key: KeyEvent) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let Some(view) = app.diff_view.as_mut() else {
+        return Action::Continue;
+    };
+    let half_page = (view.page_height / 2).max(1) as isize;
+    let page = view.page_height.max(1) as isize;
+    match key.code {
+        KeyCode::Char('c') if ctrl => {
+            app.quit();
+            return Action::Quit;
+        }
+        KeyCode::Char('d') if ctrl => view.scroll_by(half_page),
+        KeyCode::Char('u') if ctrl => view.scroll_by(-half_page),
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => view.scroll_by(1),
+        KeyCode::Char('k') | KeyCode::Up => view.scroll_by(-1),
+        KeyCode::Char(' ') | KeyCode::PageDown => view.scroll_by(page),
+        KeyCode::PageUp => view.scroll_by(-page),
+        KeyCode::Char('g') | KeyCode::Home => view.scroll_to_top(),
+        KeyCode::Char('G') | KeyCode::End => view.scroll_to_bottom(),
+        KeyCode::Char('n') if !view.next_change() => app.set_info_message("No more changes"),
+        KeyCode::Char('N') if !view.prev_change() => {
+            app.set_info_message("No earlier changes")
+        }
+        KeyCode::Char('q') | KeyCode::Esc if !view.quit_on_close => app.diff_view = None,
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.quit();
+            return Action::Quit;
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Keys while editing the cursor line in insert mode
+fn handle_insert_key(app: &mut App, key: KeyEvent) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('c') if ctrl => {
+            app.quit();
+            return Action::Quit;
+        }
+        KeyCode::Char('s') if ctrl => {
+            app.save_document();
+        }
+        KeyCode::Esc => app.exit_insert_mode(),
+        KeyCode::Char(c) if !ctrl => app.insert_text(c.encode_utf8(&mut [0; 4])),
+        KeyCode::Tab => app.insert_text("\t"),
+        KeyCode::Enter => app.insert_text("\n"),
+        KeyCode::Backspace => app.delete_char_before(),
+        KeyCode::Delete => app.delete_char_at(),
+        KeyCode::Left => app.move_edit_cursor(-1),
+        KeyCode::Right => app.move_edit_cursor(1),
+        KeyCode::Up => app.move_edit_line(false),
+        KeyCode::Down => app.move_edit_line(true),
+        KeyCode::Home => app.edit_cursor_to_line_edge(false),
+        KeyCode::End => app.edit_cursor_to_line_edge(true),
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Keys while typing a `:` command
+fn handle_command_line_key(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => {
+            app.run_command_line();
+            if app.should_quit {
+                return Action::Quit;
+            }
+        }
+        KeyCode::Esc => app.cancel_command_mode(),
+        // Backspace on an empty line leaves command mode
+        KeyCode::Backspace if app.command_line.is_empty() => app.cancel_command_mode(),
+        KeyCode::Backspace => {
+            app.command_line.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.command_line.push(c);
+        }
+        _ => {}
+    }
+    Action::Continue
+}
+
+/// Save the options dialog to the config file and report where it went
+fn save_options(app: &mut App) {
+    match app.save_options() {
+        Ok(path) => app.set_success_message(format!("Saved options to {}", path.display())),
+        Err(e) => app.set_error_message(format!("Failed to save options: {}", e)),
+    }
 }
 
 /// Handle a key event. Viewport dimensions come from `ctx`, which is
@@ -22,6 +219,17 @@ pub fn handle_input(
     key: KeyEvent,
     ctx: &crate::app::ScrollContext,
 ) -> Result<Action> {
+    let count = app.pending_count;
+    let action = handle_key(app, key, ctx);
+    // A count belongs to the next command only; a prefix key (`g`, `]`)
+    // keeps it for the key that completes the command
+    if app.pending_count == count && app.key_prefix == KeyPrefix::None {
+        app.pending_count = None;
+    }
+    action
+}
+
+fn handle_key(app: &mut App, key: KeyEvent, ctx: &crate::app::ScrollContext) -> Result<Action> {
     // Clear status message on any keystroke (except pure modifiers)
     // This ensures messages don't persist indefinitely
     if !matches!(key.code, KeyCode::Modifier(_)) {
@@ -33,19 +241,48 @@ pub fn handle_input(
         return Ok(Action::Continue);
     }
 
-    // Resolve pane dimensions from the pre-computed context.
-    // If the layout context was not yet populated (first tick), do a
-    // one-shot refresh with the raw terminal size from ctx.
-    let (pane_height, pane_width) =
-        if let Some(vp) = ctx.viewport.filter(|v| v.visible_height > 0) {
-            (vp.visible_height, vp.content_width)
-        } else {
-            app.refresh_layout_context_with_area(ctx.term_width, ctx.term_height);
-            app.focused_viewport()
-                .filter(|v| v.visible_height > 0)
-                .map(|v| (v.visible_height, v.content_width))
-                .unwrap_or((ctx.visible_height(), ctx.content_width()))
-        };
+    if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Ok(Action::Suspend);
+    }
+
+    if app.confirm.is_some() {
+        return Ok(handle_confirm_key(app, key));
+    }
+
+    if app.load_error.is_some() {
+        return Ok(handle_load_error_key(app, key));
+    }
+
+    #[cfg(feature = "git")]
+    if app.diff_view.is_some() {
+        return Ok(handle_diff_view_key(app, key));
+    }
+
+    if app.presentation.is_some() {
+        return Ok(handle_presentation_key(app, key));
+    }
+
+    // Log viewer: scroll through recent events
+    if app.log_viewer.is_some() {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.scroll_log_viewer(-1),
+            KeyCode::Char('k') | KeyCode::Up => app.scroll_log_viewer(1),
+            KeyCode::PageDown => app.scroll_log_viewer(-10),
+            KeyCode::PageUp => app.scroll_log_viewer(10),
+            KeyCode::Char('g') => app.scroll_log_viewer(isize::MAX),
+            KeyCode::Char('G') => app.log_viewer = Some(0),
+            KeyCode::Char('q') | KeyCode::Esc => app.log_viewer = None,
+            // Ignore all other keys while the viewer is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    match app.panes.focused_pane().map(|p| p.view.mode) {
+        Some(crate::app::Mode::Insert) => return Ok(handle_insert_key(app, key)),
+        Some(crate::app::Mode::Command) => return Ok(handle_command_line_key(app, key)),
+        _ => {}
+    }
 
     if let Some(pane) = app.panes.focused_pane() {
         if pane.view.mode == crate::app::Mode::VisualLine
@@ -71,25 +308,27 @@ pub fn handle_input(
             ..
         }
     ) {
-        // Don't quit if we're in search mode (q is part of search input)
-        // or visual line mode (should use Esc to exit first)
-        let in_special_mode = app
-            .panes
-            .focused_pane()
-            .map(|p| {
-                p.view.mode == crate::app::Mode::Search
-                    || p.view.mode == crate::app::Mode::VisualLine
-            })
-            .unwrap_or(false);
+        // Don't quit if we're in search mode (q is part of search input),
+        // visual line mode (should use Esc to exit first), or after g (gq)
+        let in_special_mode = app.key_prefix == KeyPrefix::G
+            || app
+                .panes
+                .focused_pane()
+                .map(|p| {
+                    p.view.mode == crate::app::Mode::Search
+                        || p.view.mode == crate::app::Mode::VisualLine
+                })
+                .unwrap_or(false);
 
         if !in_special_mode {
-            // Try to close the focused pane
-            let has_remaining_panes = app.panes.close_focused();
-            if !has_remaining_panes {
-                // Was the last pane, quit the app
-                app.quit();
-                return Ok(Action::Quit);
-            }
+            return Ok(commands::run(app, Command::Quit, ctx));
+        }
+        // Nor does q in visual line mode reach the keymap
+        let visual = app
+            .panes
+            .focused_pane()
+            .is_some_and(|p| p.view.mode == crate::app::Mode::VisualLine);
+        if visual && app.key_prefix != KeyPrefix::G {
             return Ok(Action::Continue);
         }
     }
@@ -103,8 +342,7 @@ pub fn handle_input(
             ..
         }
     ) {
-        app.quit();
-        return Ok(Action::Quit);
+        return Ok(commands::run(app, Command::ForceQuit, ctx));
     }
 
     // Handle Ctrl+L - force redraw/refresh
@@ -116,8 +354,7 @@ pub fn handle_input(
             ..
         }
     ) {
-        app.clear_status_message();
-        return Ok(Action::Redraw);
+        return Ok(commands::run(app, Command::Redraw, ctx));
     }
 
     // Ctrl+O - jump back in jump stack
@@ -129,11 +366,11 @@ pub fn handle_input(
             ..
         }
     ) {
-        app.jump_back();
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::JumpBack, ctx));
     }
 
-    // Ctrl+I - jump forward in jump stack (Tab shares this code in terminals)
+    // Ctrl+I - jump forward in jump stack (legacy terminals send Tab instead;
+    // see the Tab binding in normal mode)
     if matches!(
         key,
         KeyEvent {
@@ -142,12 +379,12 @@ pub fn handle_input(
             ..
         }
     ) {
-        app.jump_forward();
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::JumpForward, ctx));
     }
 
     // Handle Ctrl+Shift+C - copy selection to clipboard
-    if key.code == KeyCode::Char('C')
+    // (the kitty keyboard protocol reports the unshifted 'c')
+    if matches!(key.code, KeyCode::Char('C' | 'c'))
         && key.modifiers.contains(KeyModifiers::CONTROL)
         && key.modifiers.contains(KeyModifiers::SHIFT)
     {
@@ -162,24 +399,55 @@ pub fn handle_input(
         return Ok(Action::Continue);
     }
 
-    // Handle help dialog - close with Esc or ?
+    // Handle help dialog - close with Esc or ?, search with /
     if app.show_help {
-        if matches!(
-            key,
-            KeyEvent {
-                code: KeyCode::Esc,
-                ..
-            }
-        ) || matches!(
-            key,
-            KeyEvent {
-                code: KeyCode::Char('?'),
-                ..
+        if app.help_searching {
+            match key.code {
+                KeyCode::Esc => {
+                    app.help_query.clear();
+                    app.help_searching = false;
+                }
+                KeyCode::Enter => app.help_searching = false,
+                KeyCode::Backspace => {
+                    app.help_query.pop();
+                }
+                KeyCode::Char(c) => app.help_query.push(c),
+                _ => {}
             }
-        ) {
-            app.toggle_help();
+            app.help_scroll = 0;
             return Ok(Action::Continue);
         }
+        match key.code {
+            // Esc drops a search before it closes the popup
+            KeyCode::Esc if !app.help_query.is_empty() => {
+                app.help_query.clear();
+                app.help_scroll = 0;
+                return Ok(Action::Continue);
+            }
+            KeyCode::Esc | KeyCode::F(1) => {
+                app.toggle_help();
+                return Ok(Action::Continue);
+            }
+            KeyCode::Char('/') => {
+                app.help_query.clear();
+                app.help_searching = true;
+                app.help_scroll = 0;
+                return Ok(Action::Continue);
+            }
+            _ => {}
+        }
+        // Drawing clamps the scroll to the last page
+        let page = ctx.visible_height().max(1);
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.help_scroll += 1,
+            KeyCode::Char('k') | KeyCode::Up => {
+                app.help_scroll = app.help_scroll.saturating_sub(1)
+            }
+            KeyCode::PageDown | KeyCode::Char(' ') => app.help_scroll += page,
+            KeyCode::PageUp => app.help_scroll = app.help_scroll.saturating_sub(page),
+            KeyCode::Char('g') | KeyCode::Home => app.help_scroll = 0,
+            _ => {}
+        }
         // Ignore all other keys when help is shown
         return Ok(Action::Continue);
     }
@@ -280,11 +548,7 @@ pub fn handle_input(
                         crate::options_dialog::DialogButton::Ok => {
                             app.apply_options();
                         }
-                        crate::options_dialog::DialogButton::Save => {
-                            if let Err(e) = app.save_options() {
-                                eprintln!("Failed to save options: {}", e);
-                            }
-                        }
+                        crate::options_dialog::DialogButton::Save => save_options(app),
                     }
                 }
                 return Ok(Action::Continue);
@@ -339,14 +603,7 @@ pub fn handle_input(
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                match app.save_options() {
-                    Ok(()) => {
-                        app.set_success_message("Configuration saved successfully");
-                    }
-                    Err(e) => {
-                        app.set_error_message(format!("Failed to save options: {}", e));
-                    }
-                }
+                save_options(app);
                 return Ok(Action::Continue);
             }
 
@@ -357,11 +614,247 @@ pub fn handle_input(
         }
     }
 
+    // Handle broken link diagnostics
+    if app.show_link_diagnostics {
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('j'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.link_diagnostics_move_down(),
+
+            KeyEvent {
+                code: KeyCode::Char('k'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.link_diagnostics_move_up(),
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => app.link_diagnostics_jump_to_selected(),
+
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('L'),
+                ..
+            } => app.toggle_link_diagnostics(),
+
+            // Ignore all other keys while the panel is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    // Any key closes the spelling suggestions popup
+    if app.show_spell_popup {
+        app.show_spell_popup = false;
+        return Ok(Action::Continue);
+    }
+
+    // Any key closes the link preview
+    if app.preview.is_some() {
+        app.preview = None;
+        return Ok(Action::Continue);
+    }
+
+    // Handle statistics popup
+    if app.stats_popup.is_some() {
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('j'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.stats_popup_move_down(),
+
+            KeyEvent {
+                code: KeyCode::Char('k'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.stats_popup_move_up(),
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => app.stats_popup_jump_to_selected(),
+
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('q'),
+                ..
+            } => app.close_stats_popup(),
+
+            // Ignore all other keys while the popup is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    // Image cache counters: any key closes
+    if app.show_image_cache_stats {
+        app.show_image_cache_stats = false;
+        return Ok(Action::Continue);
+    }
+
+    // Handle fuzzy finder and command palette
+    if let Some(finder) = app.finder.as_mut() {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => app.finder = None,
+            KeyCode::Enter => {
+                if let Some(crate::finder::FinderKind::Command(command)) =
+                    finder.selected_item().map(|item| item.kind)
+                {
+                    app.finder = None;
+                    return Ok(commands::run(app, command, ctx));
+                }
+                app.finder_open_selected();
+            }
+            KeyCode::Down => finder.move_down(),
+            KeyCode::Up => finder.move_up(),
+            KeyCode::Char('n') if ctrl => finder.move_down(),
+            KeyCode::Char('p') if ctrl => finder.move_up(),
+            KeyCode::Backspace => finder.pop_char(),
+            // A leading '>' switches to the commands, as in other editors
+            KeyCode::Char('>') if finder.query.is_empty() && !finder.is_palette() => {
+                app.open_command_palette()
+            }
+            KeyCode::Char(c) if !ctrl => finder.push_char(c),
+            // Ignore all other keys while the finder is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    // Handle git log panel
+    #[cfg(feature = "git")]
+    if let Some(log) = app.git_log.as_mut() {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => log.move_down(),
+            KeyCode::Char('k') | KeyCode::Up => log.move_up(),
+            KeyCode::Enter => app.git_log_open_selected(),
+            KeyCode::Esc | KeyCode::Char('q') => app.git_log = None,
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    // Handle buffer picker
+    if app.show_buffer_picker {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.buffer_picker_move_down(),
+            KeyCode::Char('k') | KeyCode::Up => app.buffer_picker_move_up(),
+            KeyCode::Enter => app.buffer_picker_open_selected(),
+            KeyCode::Char('d') => app.close_buffer(app.buffer_picker_selected, false),
+            KeyCode::Esc | KeyCode::Char('q' | 'B') => app.close_buffer_picker(),
+            // Ignore all other keys while the picker is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
+    // Handle breadcrumb popup
+    if app.show_breadcrumb_popup {
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('j'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Down,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.breadcrumb_popup_move_down(),
+
+            KeyEvent {
+                code: KeyCode::Char('k'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Up,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.breadcrumb_popup_move_up(),
+
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => app.breadcrumb_popup_jump_to_selected(),
+
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('q'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => app.close_breadcrumb_popup(),
+
+            // Ignore all other keys while the popup is open
+            _ => {}
+        }
+        return Ok(Action::Continue);
+    }
+
     // Handle TOC dialog
     if app.show_toc_dialog {
         let dialog_height = ctx.visible_height();
 
+        if app.toc_dialog_filtering {
+            let mut query = app.toc_dialog_query.clone();
+            match key.code {
+                KeyCode::Esc => {
+                    query.clear();
+                    app.toc_dialog_filtering = false;
+                }
+                KeyCode::Enter => app.toc_dialog_filtering = false,
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Char(c) => query.push(c),
+                _ => return Ok(Action::Continue),
+            }
+            app.set_toc_dialog_query(query);
+            return Ok(Action::Continue);
+        }
+
         match key {
+            // / - filter headings
+            KeyEvent {
+                code: KeyCode::Char('/'),
+                ..
+            } => {
+                app.set_toc_dialog_query(String::new());
+                app.toc_dialog_filtering = true;
+                return Ok(Action::Continue);
+            }
+
             // j or Down - move down in TOC dialog
             KeyEvent {
                 code: KeyCode::Char('j'),
@@ -487,6 +980,14 @@ pub fn handle_input(
                 return Ok(Action::Continue);
             }
 
+            // Esc drops a filter before it closes the dialog
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } if !app.toc_dialog_query.is_empty() => {
+                app.set_toc_dialog_query(String::new());
+                return Ok(Action::Continue);
+            }
+
             // Esc or T - close TOC dialog
             KeyEvent {
                 code: KeyCode::Esc, ..
@@ -504,6 +1005,16 @@ pub fn handle_input(
         }
     }
 
+    // Second key of a KEYMAP sequence (gg, ]], za, Ctrl+W s, ...)
+    let sequence = app
+        .key_prefix
+        .key()
+        .and_then(|prefix| commands::command_after(prefix, key));
+    if let Some(command) = sequence {
+        app.key_prefix = KeyPrefix::None;
+        return Ok(commands::run(app, command, ctx));
+    }
+
     // Handle key prefix sequences
     if app.key_prefix == KeyPrefix::CtrlW {
         // Compute pane layouts for focus movement
@@ -515,32 +1026,6 @@ pub fn handle_input(
         });
 
         match key {
-            // ^w s - horizontal split
-            KeyEvent {
-                code: KeyCode::Char('s'),
-                modifiers: KeyModifiers::NONE,
-                ..
-            } => {
-                app.split_focused(SplitDir::Horizontal);
-                // Refresh layout context immediately so subsequent commands use correct pane sizes
-                app.refresh_layout_context_with_area(ctx.term_width, ctx.term_height);
-                app.key_prefix = KeyPrefix::None;
-                return Ok(Action::Continue);
-            }
-
-            // ^w v - vertical split
-            KeyEvent {
-                code: KeyCode::Char('v'),
-                modifiers: KeyModifiers::NONE,
-                ..
-            } => {
-                app.split_focused(SplitDir::Vertical);
-                // Refresh layout context immediately so subsequent commands use correct pane sizes
-                app.refresh_layout_context_with_area(ctx.term_width, ctx.term_height);
-                app.key_prefix = KeyPrefix::None;
-                return Ok(Action::Continue);
-            }
-
             // ^w ↑ - move focus up
             KeyEvent {
                 code: KeyCode::Up,
@@ -712,6 +1197,23 @@ pub fn handle_input(
         }
     }
 
+    // Ctrl+Shift+P - command palette
+    if commands::is_palette_key(key) {
+        return Ok(commands::run(app, Command::CommandPalette, ctx));
+    }
+
+    // ^p - fuzzy finder
+    if matches!(
+        key,
+        KeyEvent {
+            code: KeyCode::Char('p'),
+            modifiers: KeyModifiers::CONTROL,
+            ..
+        }
+    ) {
+        return Ok(commands::run(app, Command::FuzzyFinder, ctx));
+    }
+
     // ^w - enter prefix mode
     if matches!(
         key,
@@ -823,16 +1325,15 @@ pub fn handle_input(
                     code: KeyCode::Enter,
                     ..
                 } => {
-                    app.exit_search_mode();
+                    app.confirm_search();
                     return Ok(Action::Continue);
                 }
 
-                // Esc - cancel search and exit search mode
+                // Esc - cancel search, going back to where it started
                 KeyEvent {
                     code: KeyCode::Esc, ..
                 } => {
-                    app.clear_search();
-                    app.exit_search_mode();
+                    app.cancel_search();
                     return Ok(Action::Continue);
                 }
 
@@ -957,6 +1458,37 @@ pub fn handle_input(
                 return Ok(Action::Continue);
             }
 
+            // K/J - move the selected section up/down among its siblings
+            KeyEvent {
+                code: KeyCode::Char(c @ ('K' | 'J')),
+                ..
+            } => {
+                app.outline_move_section(c == 'K');
+                app.toc_auto_scroll(toc_height);
+                return Ok(Action::Continue);
+            }
+
+            // < / > - promote/demote the selected section
+            KeyEvent {
+                code: KeyCode::Char(c @ ('<' | '>')),
+                ..
+            } => {
+                app.outline_shift_level(if c == '<' { -1 } else { 1 });
+                app.toc_auto_scroll(toc_height);
+                return Ok(Action::Continue);
+            }
+
+            // u - undo the last section move or level change
+            KeyEvent {
+                code: KeyCode::Char('u'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                app.outline_undo();
+                app.toc_auto_scroll(toc_height);
+                return Ok(Action::Continue);
+            }
+
             // Enter or l - jump to selected heading
             KeyEvent {
                 code: KeyCode::Enter,
@@ -1054,6 +1586,22 @@ pub fn handle_input(
         }
     }
 
+    // Count prefix - digits before a motion repeat it (5j, 3]], 2 Ctrl+d).
+    // A leading 0 isn't a count.
+    if let KeyEvent {
+        code: KeyCode::Char(digit @ '0'..='9'),
+        modifiers: KeyModifiers::NONE,
+        ..
+    } = key
+    {
+        if app.key_prefix == KeyPrefix::None && (digit != '0' || app.pending_count.is_some()) {
+            let digit = digit as usize - '0' as usize;
+            let count = app.pending_count.unwrap_or(0);
+            app.pending_count = Some(count.saturating_mul(10).saturating_add(digit).min(MAX_COUNT));
+            return Ok(Action::Continue);
+        }
+    }
+
     // Shift+V - enter visual line mode
     if matches!(
         key,
@@ -1062,20 +1610,119 @@ pub fn handle_input(
             modifiers: KeyModifiers::SHIFT,
             ..
         }
-    ) {
-        app.enter_visual_line_mode();
-        return Ok(Action::Continue);
+    ) {
+        app.enter_visual_line_mode();
+        return Ok(Action::Continue);
+    }
+
+    // Esc - close the code block output split outside visual mode
+    if app.block_output.is_some()
+        && key.code == KeyCode::Esc
+        && app
+            .panes
+            .focused_pane()
+            .is_some_and(|p| p.view.mode == crate::app::Mode::Normal)
+    {
+        app.close_block_output();
+        return Ok(Action::Continue);
+    }
+
+    // Esc - exit visual line mode
+    if matches!(
+        key,
+        KeyEvent {
+            code: KeyCode::Esc,
+            ..
+        }
+    ) {
+        app.exit_visual_line_mode();
+        return Ok(Action::Continue);
+    }
+
+    // a/i prefix - text objects in visual line mode (as, ic, al, it, ...)
+    if matches!(app.key_prefix, KeyPrefix::Around | KeyPrefix::Inner) {
+        let inner = app.key_prefix == KeyPrefix::Inner;
+        app.key_prefix = KeyPrefix::None;
+        if let KeyEvent {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+            ..
+        } = key
+        {
+            if let Some(object) = mdx_core::selection::TextObject::from_key(c) {
+                app.select_text_object(object, inner);
+                return Ok(Action::Continue);
+            }
+        }
+        // Fall through so the user's second key is processed normally.
+    }
+    if app
+        .panes
+        .focused_pane()
+        .is_some_and(|p| p.view.mode == crate::app::Mode::VisualLine)
+    {
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('a'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                app.key_prefix = KeyPrefix::Around;
+                return Ok(Action::Continue);
+            }
+            KeyEvent {
+                code: KeyCode::Char('i'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                app.key_prefix = KeyPrefix::Inner;
+                return Ok(Action::Continue);
+            }
+            // / - search within the selection
+            KeyEvent {
+                code: KeyCode::Char('/'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                app.enter_scoped_search_mode();
+                return Ok(Action::Continue);
+            }
+            _ => {}
+        }
+    }
+
+    // y prefix - yc copies the code block under the cursor (yt and yT are
+    // in the keymap)
+    if app.key_prefix == KeyPrefix::Y {
+        app.key_prefix = KeyPrefix::None;
+        if matches!(
+            key,
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+        ) {
+            app.copy_code_block();
+            return Ok(Action::Continue);
+        }
+        // Fall through so the user's second key is processed normally.
     }
 
-    // Esc - exit visual line mode
+    // y - prefix for yc and yt outside visual line mode
     if matches!(
         key,
         KeyEvent {
-            code: KeyCode::Esc,
+            code: KeyCode::Char('y'),
+            modifiers: KeyModifiers::NONE,
             ..
         }
-    ) {
-        app.exit_visual_line_mode();
+    ) && app
+        .panes
+        .focused_pane()
+        .is_some_and(|p| p.view.mode == crate::app::Mode::Normal)
+    {
+        app.key_prefix = KeyPrefix::Y;
         return Ok(Action::Continue);
     }
 
@@ -1109,7 +1756,7 @@ pub fn handle_input(
         return Ok(Action::Continue);
     }
 
-    // / - enter search mode
+    // / - enter search mode; g/ searches only the current section
     if matches!(
         key,
         KeyEvent {
@@ -1118,215 +1765,209 @@ pub fn handle_input(
             ..
         }
     ) {
-        app.enter_search_mode();
-        return Ok(Action::Continue);
-    }
-
-    // n - next search match
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('n'),
-            modifiers: KeyModifiers::NONE,
-            ..
+        if app.key_prefix == KeyPrefix::G {
+            app.key_prefix = KeyPrefix::None;
+            app.enter_scoped_search_mode();
+            return Ok(Action::Continue);
         }
-    ) {
-        app.push_jump();
-        app.next_search_match(pane_height);
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::Search, ctx));
     }
 
-    // N - previous search match
+    // ? - enter search mode, searching upwards
     if matches!(
         key,
         KeyEvent {
-            code: KeyCode::Char('N'),
-            modifiers: KeyModifiers::SHIFT,
+            code: KeyCode::Char('?'),
             ..
         }
     ) {
-        app.push_jump();
-        app.prev_search_match(pane_height);
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::SearchBackward, ctx));
     }
 
-    // t - toggle TOC sidebar
+    // n - next search match in the search's direction
     if matches!(
         key,
         KeyEvent {
-            code: KeyCode::Char('t'),
+            code: KeyCode::Char('n'),
             modifiers: KeyModifiers::NONE,
             ..
         }
     ) {
-        app.toggle_toc();
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::NextMatch, ctx));
     }
 
-    // T - open TOC dialog
+    // N - next search match against the search's direction
     if matches!(
         key,
         KeyEvent {
-            code: KeyCode::Char('T'),
+            code: KeyCode::Char('N'),
             modifiers: KeyModifiers::SHIFT,
             ..
         }
     ) {
-        app.toggle_toc_dialog();
-        return Ok(Action::Continue);
+        return Ok(commands::run(app, Command::PrevMatch, ctx));
     }
 
-    // W - toggle security warnings pane
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('W'),
-            modifiers: KeyModifiers::SHIFT,
-            ..
+    // g prefix — the keymap has gg, gt, gb and the like; these are the
+    // sequences that aren't commands. Any other key cancels the prefix and
+    // falls through to normal handling.
+    if app.key_prefix == KeyPrefix::G {
+        // gq - close the shown file
+        if matches!(
+            key,
+            KeyEvent {
+                code: KeyCode::Char('q'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+        ) {
+            app.key_prefix = KeyPrefix::None;
+            app.close_buffer(app.active_buffer, false);
+            return Ok(Action::Continue);
         }
-    ) {
-        app.toggle_security_warnings();
-        return Ok(Action::Continue);
-    }
-
-    // ? - toggle help dialog
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('?'),
-            ..
+        // gs - send the cursor line to the connected editors
+        if matches!(
+            key,
+            KeyEvent {
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+        ) {
+            app.key_prefix = KeyPrefix::None;
+            crate::listen::reveal_cursor(app);
+            return Ok(Action::Continue);
         }
-    ) {
-        app.toggle_help();
-        return Ok(Action::Continue);
-    }
-
-    // O - open options dialog
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('O'),
-            modifiers: KeyModifiers::SHIFT,
-            ..
+        // gf - follow the #heading link on the cursor line
+        if matches!(
+            key,
+            KeyEvent {
+                code: KeyCode::Char('f'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            }
+        ) {
+            app.key_prefix = KeyPrefix::None;
+            app.follow_link_at_cursor();
+            return Ok(Action::Continue);
         }
-    ) {
-        app.open_options();
-        return Ok(Action::Continue);
+        app.key_prefix = KeyPrefix::None;
+        // Fall through so the user's second key is processed normally.
     }
 
-    // e - open in editor
+    // Enter - follow the #heading link on the cursor line
     if matches!(
         key,
         KeyEvent {
-            code: KeyCode::Char('e'),
+            code: KeyCode::Enter,
             modifiers: KeyModifiers::NONE,
             ..
         }
-    ) {
-        return Ok(Action::OpenEditor);
+    ) && app.panes.focused_pane().map(|p| p.view.mode) == Some(crate::app::Mode::Normal)
+    {
+        app.follow_link_at_cursor();
+        return Ok(Action::Continue);
     }
 
-    // r - toggle raw/rendered mode in active pane
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('r'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        }
-    ) {
-        if let Some(pane) = app.panes.focused_pane_mut() {
-            pane.view.show_raw = !pane.view.show_raw;
-        }
+    // Tab - what legacy terminals send for Ctrl+I
+    if key.code == KeyCode::Tab
+        && key.modifiers.is_empty()
+        && !app.enhanced_keys
+        && app.panes.focused_pane().map(|p| p.view.mode) == Some(crate::app::Mode::Normal)
+    {
+        app.jump_forward();
         return Ok(Action::Continue);
     }
 
-    // Handle 'z' prefix for fold commands
-    // g prefix — second 'g' completes gg and jumps to top. Any other
-    // key cancels the prefix and falls through to normal handling.
-    if app.key_prefix == KeyPrefix::G {
-        if matches!(
-            key,
-            KeyEvent {
-                code: KeyCode::Char('g'),
-                modifiers: KeyModifiers::NONE,
-                ..
-            }
-        ) {
-            app.key_prefix = KeyPrefix::None;
-            app.push_jump();
-            let pane_id = app.panes.focused;
-            app.goto(pane_id, 0, crate::scroll_math::ScrollPolicy::NearestEdge);
-            return Ok(Action::Continue);
-        }
+    // [ prefix — [h and [[ are in the keymap; [s jumps to the previous
+    // misspelling, [c to the previous diff hunk, [x to the previous merge
+    // conflict
+    if app.key_prefix == KeyPrefix::LeftBracket {
         app.key_prefix = KeyPrefix::None;
-        // Fall through so the user's second key is processed normally.
-    }
-
-    if app.key_prefix == KeyPrefix::Z {
         match key {
-            // za - toggle fold at cursor
             KeyEvent {
-                code: KeyCode::Char('a'),
+                code: KeyCode::Char('s'),
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                app.toggle_collapse_at_cursor();
-                app.key_prefix = KeyPrefix::None;
+                for _ in 0..app.take_count() {
+                    app.jump_to_misspelling(false);
+                }
                 return Ok(Action::Continue);
             }
-
-            // zo - open fold at cursor
             KeyEvent {
-                code: KeyCode::Char('o'),
+                code: KeyCode::Char('x'),
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                app.expand_at_cursor();
-                app.key_prefix = KeyPrefix::None;
+                for _ in 0..app.take_count() {
+                    app.jump_to_conflict(false);
+                }
                 return Ok(Action::Continue);
             }
-
-            // zc - close fold at cursor
+            #[cfg(feature = "git")]
             KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                app.collapse_at_cursor();
-                app.key_prefix = KeyPrefix::None;
+                for _ in 0..app.take_count() {
+                    app.jump_to_hunk(false);
+                }
                 return Ok(Action::Continue);
             }
+            // Fall through so the user's second key is processed normally.
+            _ => {}
+        }
+    }
 
-            // zM - close all folds
+    // ] prefix — ]] is in the keymap; ]s jumps to the next misspelling, ]c
+    // to the next diff hunk, ]x to the next merge conflict
+    if app.key_prefix == KeyPrefix::RightBracket {
+        app.key_prefix = KeyPrefix::None;
+        match key {
             KeyEvent {
-                code: KeyCode::Char('M'),
-                modifiers: KeyModifiers::SHIFT,
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                app.collapse_all_headings(None);
-                app.key_prefix = KeyPrefix::None;
+                for _ in 0..app.take_count() {
+                    app.jump_to_misspelling(true);
+                }
                 return Ok(Action::Continue);
             }
-
-            // zR - open all folds
             KeyEvent {
-                code: KeyCode::Char('R'),
-                modifiers: KeyModifiers::SHIFT,
+                code: KeyCode::Char('x'),
+                modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                app.expand_all_headings();
-                app.key_prefix = KeyPrefix::None;
+                for _ in 0..app.take_count() {
+                    app.jump_to_conflict(true);
+                }
                 return Ok(Action::Continue);
             }
-
-            // Any other key cancels the prefix
-            _ => {
-                app.key_prefix = KeyPrefix::None;
+            #[cfg(feature = "git")]
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                for _ in 0..app.take_count() {
+                    app.jump_to_hunk(true);
+                }
+                return Ok(Action::Continue);
             }
+            // Fall through so the user's second key is processed normally.
+            _ => {}
         }
     }
 
+    // z prefix — the fold and spelling commands are in the keymap; any
+    // other key cancels the prefix
+    if app.key_prefix == KeyPrefix::Z {
+        app.key_prefix = KeyPrefix::None;
+    }
+
     // z - enter fold prefix mode
     if matches!(
         key,
@@ -1340,77 +1981,13 @@ pub fn handle_input(
         return Ok(Action::Continue);
     }
 
-    // m - toggle theme (placed after z prefix handling to avoid conflict with zM)
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('m'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        }
-    ) {
-        app.toggle_theme();
-        return Ok(Action::Continue);
-    }
-
-    // R - reload document from disk (placed after z prefix handling to avoid conflict with zR)
-    if matches!(
-        key,
-        KeyEvent {
-            code: KeyCode::Char('R'),
-            modifiers: KeyModifiers::SHIFT,
-            ..
-        }
-    ) {
-        if let Err(e) = app.reload_document() {
-            // Silently fail - would need message system for full implementation
-            eprintln!("Failed to reload document: {}", e);
-        }
-        return Ok(Action::Continue);
+    // Single keys of the keymap
+    if let Some(command) = commands::command_for_key(key) {
+        return Ok(commands::run(app, command, ctx));
     }
 
-    // Navigation commands (when not in TOC)
+    // Prefixes and fold arrows
     match key {
-        // j - move down
-        KeyEvent {
-            code: KeyCode::Char('j'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => {
-            app.move_cursor_down(1);
-            app.auto_scroll(pane_height);
-        }
-
-        // k - move up
-        KeyEvent {
-            code: KeyCode::Char('k'),
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => {
-            app.move_cursor_up(1);
-            app.auto_scroll(pane_height);
-        }
-
-        // Ctrl+d - half page down
-        KeyEvent {
-            code: KeyCode::Char('d'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        } => {
-            app.scroll_half_page_down(pane_height, pane_width);
-            app.auto_scroll(pane_height);
-        }
-
-        // Ctrl+u - half page up
-        KeyEvent {
-            code: KeyCode::Char('u'),
-            modifiers: KeyModifiers::CONTROL,
-            ..
-        } => {
-            app.scroll_half_page_up(pane_height, pane_width);
-            app.auto_scroll(pane_height);
-        }
-
         // g - prefix for gg (go to top). The second press is handled
         // earlier via KeyPrefix::G.
         KeyEvent {
@@ -1421,38 +1998,27 @@ pub fn handle_input(
             app.key_prefix = KeyPrefix::G;
         }
 
-        // G - go to bottom
-        KeyEvent {
-            code: KeyCode::Char('G'),
-            modifiers: KeyModifiers::SHIFT,
-            ..
-        } => {
-            let last_line = app.doc.line_count().saturating_sub(1);
-            app.push_jump();
-            let pane_id = app.panes.focused;
-            app.goto(pane_id, last_line, crate::scroll_math::ScrollPolicy::NearestEdge);
-        }
-
-        // Arrow keys - same as j/k for up/down, collapse/expand for left/right
+        // [ - prefix for [h (parent heading), [[ (previous heading), and [s
+        // (previous misspelling)
         KeyEvent {
-            code: KeyCode::Down,
+            code: KeyCode::Char('['),
             modifiers: KeyModifiers::NONE,
             ..
         } => {
-            app.move_cursor_down(1);
-            app.auto_scroll(pane_height);
+            app.key_prefix = KeyPrefix::LeftBracket;
         }
 
+        // ] - prefix for ]] (next heading) and ]s (next misspelling)
         KeyEvent {
-            code: KeyCode::Up,
+            code: KeyCode::Char(']'),
             modifiers: KeyModifiers::NONE,
             ..
         } => {
-            app.move_cursor_up(1);
-            app.auto_scroll(pane_height);
+            app.key_prefix = KeyPrefix::RightBracket;
         }
 
         // Left arrow - collapse the section at or above cursor
+        #[allow(clippy::collapsible_match)]
         KeyEvent {
             code: KeyCode::Left,
             modifiers: KeyModifiers::NONE,
@@ -1465,6 +2031,7 @@ pub fn handle_input(
         }
 
         // Right arrow - expand the collapsed section at or above cursor
+        #[allow(clippy::collapsible_match)]
         KeyEvent {
             code: KeyCode::Right,
             modifiers: KeyModifiers::NONE,
@@ -1475,55 +2042,6 @@ pub fn handle_input(
             }
         }
 
-        // PageDown/PageUp - scroll by full page (minus overlap)
-        KeyEvent {
-            code: KeyCode::PageDown,
-            ..
-        } => {
-            let step = page_step(app, pane_height);
-            app.move_cursor_down(step);
-            app.auto_scroll(pane_height);
-        }
-
-        KeyEvent {
-            code: KeyCode::PageUp,
-            ..
-        } => {
-            let step = page_step(app, pane_height);
-            app.move_cursor_up(step);
-            app.auto_scroll(pane_height);
-        }
-
-        // Space - same as PageDown
-        KeyEvent {
-            code: KeyCode::Char(' '),
-            modifiers: KeyModifiers::NONE,
-            ..
-        } => {
-            let step = page_step(app, pane_height);
-            app.move_cursor_down(step);
-            app.auto_scroll(pane_height);
-        }
-
-        // Home/End - same as g/G
-        KeyEvent {
-            code: KeyCode::Home,
-            ..
-        } => {
-            app.push_jump();
-            let pane_id = app.panes.focused;
-            app.goto(pane_id, 0, crate::scroll_math::ScrollPolicy::NearestEdge);
-        }
-
-        KeyEvent {
-            code: KeyCode::End, ..
-        } => {
-            let last_line = app.doc.line_count().saturating_sub(1);
-            app.push_jump();
-            let pane_id = app.panes.focused;
-            app.goto(pane_id, last_line, crate::scroll_math::ScrollPolicy::NearestEdge);
-        }
-
         _ => {}
     }
 
@@ -1533,6 +2051,8 @@ pub fn handle_input(
 /// Hit test result - what was clicked
 #[derive(Debug, Clone, PartialEq)]
 enum HitTarget {
+    /// Tab for the buffer at this index
+    Tab(usize),
     Pane(PaneId, Rect),
     Toc(Rect),
     SplitBorder { path: Vec<usize>, is_vertical: bool },
@@ -1581,9 +2101,14 @@ pub fn handle_mouse(
     Ok(())
 }
 
+/// A visible tab's buffer index and the columns it covers
+type TabColumns = (usize, std::ops::Range<u16>);
+
 /// Layout information for hit testing
 #[derive(Debug)]
 struct LayoutInfo {
+    /// Tab bar row, with each visible tab's buffer index and columns
+    tabs: Option<(Rect, Vec<TabColumns>)>,
     toc_rect: Option<Rect>,
     pane_rects: std::collections::HashMap<PaneId, Rect>,
     split_boundaries: Vec<crate::panes::SplitBoundary>,
@@ -1616,19 +2141,35 @@ fn compute_layout_info(app: &App, term_width: u16, term_height: u16) -> LayoutIn
         [chunks[0], chunks[1]]
     };
 
+    // Tab bar row above the TOC and panes
+    let (tabs, content_area) = if app.tab_bar_visible() {
+        let chunks = Layout::default()
+            .direction(LayoutDir::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(base_chunks[0]);
+        let columns = crate::ui::tab_layout(app, chunks[0].width)
+            .into_iter()
+            .map(|(idx, columns, _)| (idx, columns))
+            .collect();
+        (Some((chunks[0], columns)), chunks[1])
+    } else {
+        (None, base_chunks[0])
+    };
+
     // Split TOC and panes area
     let (toc_rect, panes_area) = if app.show_toc {
+        #[allow(clippy::unnecessary_cast)]
         let toc_width = app.config.toc.width as u16;
         let chunks = if app.config.toc.side == mdx_core::config::TocSide::Left {
             Layout::default()
                 .direction(LayoutDir::Horizontal)
                 .constraints([Constraint::Length(toc_width), Constraint::Min(1)])
-                .split(base_chunks[0])
+                .split(content_area)
         } else {
             Layout::default()
                 .direction(LayoutDir::Horizontal)
                 .constraints([Constraint::Min(1), Constraint::Length(toc_width)])
-                .split(base_chunks[0])
+                .split(content_area)
         };
 
         if app.config.toc.side == mdx_core::config::TocSide::Left {
@@ -1637,7 +2178,7 @@ fn compute_layout_info(app: &App, term_width: u16, term_height: u16) -> LayoutIn
             (Some(chunks[1]), chunks[0])
         }
     } else {
-        (None, base_chunks[0])
+        (None, content_area)
     };
 
     // Compute pane layout and split boundaries
@@ -1645,6 +2186,7 @@ fn compute_layout_info(app: &App, term_width: u16, term_height: u16) -> LayoutIn
     let split_boundaries = app.panes.compute_split_boundaries(panes_area);
 
     LayoutInfo {
+        tabs,
         toc_rect,
         pane_rects,
         split_boundaries,
@@ -1653,6 +2195,15 @@ fn compute_layout_info(app: &App, term_width: u16, term_height: u16) -> LayoutIn
 
 /// Perform hit testing to determine what was clicked
 fn hit_test(x: u16, y: u16, layout: &LayoutInfo) -> HitTarget {
+    if let Some((row, tabs)) = &layout.tabs {
+        if y == row.y {
+            return tabs
+                .iter()
+                .find(|(_, columns)| columns.contains(&x.saturating_sub(row.x)))
+                .map_or(HitTarget::None, |(idx, _)| HitTarget::Tab(*idx));
+        }
+    }
+
     // Check TOC first
     if let Some(toc_rect) = layout.toc_rect {
         if x >= toc_rect.x
@@ -1669,6 +2220,7 @@ fn hit_test(x: u16, y: u16, layout: &LayoutInfo) -> HitTarget {
         let is_near = match boundary.dir {
             crate::panes::SplitDir::Vertical => {
                 // Vertical split: check if x is within 1 cell of split_x
+                #[allow(clippy::manual_abs_diff)]
                 let distance = if x > boundary.position {
                     x - boundary.position
                 } else {
@@ -1678,6 +2230,7 @@ fn hit_test(x: u16, y: u16, layout: &LayoutInfo) -> HitTarget {
             }
             crate::panes::SplitDir::Horizontal => {
                 // Horizontal split: check if y is within 1 cell of split_y
+                #[allow(clippy::manual_abs_diff)]
                 let distance = if y > boundary.position {
                     y - boundary.position
                 } else {
@@ -1793,6 +2346,12 @@ fn handle_mouse_down(
                 app.mouse_state = MouseState::Idle;
             }
         }
+        HitTarget::Tab(index) => {
+            app.mouse_state = MouseState::Idle;
+            if index != app.active_buffer {
+                app.show_buffer(index);
+            }
+        }
         HitTarget::None => {
             app.mouse_state = MouseState::Idle;
         }
@@ -1945,14 +2504,6 @@ fn handle_mouse_up(app: &mut App) -> Result<()> {
     Ok(())
 }
 
-/// How many source lines a PgDn/PgUp should move: the focused pane's
-/// visible height minus the configured overlap, clamped so that pages
-/// always advance by at least one line. Delegates to `scroll_math` so the
-/// rule stays testable.
-fn page_step(app: &App, pane_height: usize) -> usize {
-    crate::scroll_math::page_step(pane_height, app.config.render.page_overlap_rows)
-}
-
 /// Handle scroll wheel event
 fn handle_scroll(
     app: &mut App,
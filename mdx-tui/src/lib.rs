@@ -8,25 +8,46 @@
 //! - Theme system
 
 pub mod app;
+pub mod bidi;
+pub mod buffers;
 pub mod collapse;
+pub mod commands;
 pub mod editor;
 pub mod event;
+pub mod exec_worker;
+pub mod finder;
+pub mod help;
 pub mod input;
 pub mod line_layout;
+pub mod listen;
+pub mod location;
+pub mod logging;
 pub mod options_dialog;
 pub mod panes;
+pub mod preview;
+pub mod print;
+pub mod profile;
 pub mod render;
 pub mod scroll_math;
+pub mod spell_worker;
+pub mod statusbar;
 pub mod terminal;
 pub mod theme;
+pub mod typeset;
 pub mod ui;
+pub mod worker_pool;
 
 // These will be added in later stages
 // pub mod toc;
 #[cfg(feature = "git")]
+pub mod diff_view;
+#[cfg(feature = "git")]
 pub mod diff_worker;
+#[cfg(feature = "git")]
+pub mod git_log;
 #[cfg(feature = "images")]
 pub mod image_cache;
+pub mod link_worker;
 #[cfg(feature = "watch")]
 pub mod watcher;
 
@@ -40,28 +61,50 @@ pub use event::AppEvent;
 
 /// Run the TUI application
 pub fn run(mut app: App) -> Result<()> {
+    terminal::request_enhanced_keys(app.config.input.enhanced_keys);
     let mut terminal = terminal::init().context("Failed to initialize terminal")?;
+    app.enhanced_keys = terminal::enhanced_keys();
+    app.set_truecolor(terminal::supports_truecolor());
 
     // Main event loop
     let result = run_loop(&mut terminal, &mut app);
 
     // Always restore terminal, even if run_loop fails
     terminal::restore().context("Failed to restore terminal")?;
+    app.save_session();
+
+    // Print the startup breakdown once the terminal is back to normal
+    if let Some(profile) = &app.startup_profile {
+        eprint!("{}", profile.report());
+    }
 
     result
 }
 
 fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
+    #[cfg(unix)]
+    let suspend_signal = terminal::suspend_signal()?;
+
     loop {
+        #[cfg(unix)]
+        if suspend_signal.swap(false, std::sync::atomic::Ordering::Relaxed) {
+            terminal::suspend(terminal)?;
+        }
+
         let term_size = terminal.size()?;
+        app.step_scroll_animation();
 
         // Draw UI (this populates app.layout_context for the current frame).
+        let draw_start = std::time::Instant::now();
         terminal
             .draw(|frame| ui::draw(frame, app))
             .context("Failed to draw frame")?;
+        if let Some(profile) = app.startup_profile.as_mut() {
+            profile.mark_first_frame(draw_start.elapsed());
+        }
 
         // Build the scroll context after the draw so layout_context is fresh.
-        let ctx = app::ScrollContext::from_app(app, term_size.width, term_size.height);
+        let mut ctx = app::ScrollContext::from_app(app, term_size.width, term_size.height);
 
         // Check if we should quit
         if app.should_quit {
@@ -74,15 +117,20 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
         // keep the UI responsive if something goes pathological.
         const MAX_EVENTS_PER_TICK: usize = 32;
         let mut drained = 0usize;
-        let mut had_event =
-            crossterm::event::poll(Duration::from_millis(100)).context("Failed to poll events")?;
+        // Poll at frame rate while a page jump is animating
+        let timeout = if app.scroll_animation.is_some() { 16 } else { 100 };
+        let mut had_event = crossterm::event::poll(Duration::from_millis(timeout))
+            .context("Failed to poll events")?;
         while had_event && drained < MAX_EVENTS_PER_TICK {
             let event = crossterm::event::read().context("Failed to read event")?;
             drained += 1;
+            app.finish_scroll_animation();
             match event {
+                #[allow(clippy::collapsible_match)]
                 Event::Key(key) => {
-                    // Only handle key press events, ignore release
-                    if key.kind == KeyEventKind::Press {
+                    // Handle presses and auto-repeats; Windows terminals also
+                    // report releases, which would run every binding twice
+                    if key.kind != KeyEventKind::Release {
                         let action =
                             input::handle_input(app, key, &ctx)?;
 
@@ -94,6 +142,7 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
                                     .context("Failed to restore terminal for editor")?;
 
                                 // Launch editor
+                                let before = app.doc.rope.clone();
                                 let editor_result = app.open_in_editor();
 
                                 // Restore terminal
@@ -101,8 +150,13 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
                                     .context("Failed to reinitialize terminal after editor")?;
 
                                 // Handle editor errors (after terminal is restored)
-                                if let Err(e) = editor_result {
-                                    app.set_error_message(format!("Editor error: {}", e));
+                                match editor_result {
+                                    Ok(()) => app.reload_after_editor(&before),
+                                    Err(e) => {
+                                        // A failing editor may still have saved
+                                        app.reload_after_editor(&before);
+                                        app.set_error_message(format!("Editor error: {}", e));
+                                    }
                                 }
                             }
                             input::Action::Quit => {
@@ -111,6 +165,12 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
                             input::Action::Redraw => {
                                 terminal.clear().context("Failed to clear terminal")?;
                             }
+                            #[cfg(unix)]
+                            input::Action::Suspend => terminal::suspend(terminal)?,
+                            #[cfg(not(unix))]
+                            input::Action::Suspend => {
+                                app.set_info_message("Suspending isn't supported on this platform");
+                            }
                             input::Action::Continue => {
                                 // Nothing to do
                             }
@@ -122,6 +182,8 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
                 }
                 Event::Resize(width, height) => {
                     app.on_resize(width, height);
+                    // Keys queued behind the resize see the new geometry
+                    ctx = app::ScrollContext::from_app(app, width, height);
                     // Force a clean redraw so any stale cells from the prior
                     // geometry are cleared.
                     terminal.clear().context("Failed to clear terminal on resize")?;
@@ -140,36 +202,106 @@ fn run_loop(terminal: &mut terminal::Tui, app: &mut App) -> Result<()> {
             }
         }
 
+        // Anything the user did may have moved a reading position
+        if drained > 0 {
+            app.session_dirty = true;
+        }
+        app.autosave_session();
+
+        // Requests from an editor driving mdx (`--listen`)
+        if let Some(listener) = app.listener.take() {
+            listener.poll(app);
+            app.listener = Some(listener);
+        }
+
         // Check for file changes (with debouncing)
         #[cfg(feature = "watch")]
         {
             if let Some(ref mut watcher) = app.watcher {
                 if watcher.check_changed(250) {
+                    tracing::debug!("{} changed on disk", app.doc.path.display());
+                    // Our own `:w` leaves the disk mtime equal to the loaded one
+                    let disk_mtime = std::fs::metadata(&app.doc.path)
+                        .and_then(|m| m.modified())
+                        .ok();
+                    let own_write = disk_mtime.is_some() && disk_mtime == app.doc.loaded_mtime;
+
                     // File changed on disk after debounce period
-                    if app.config.watch.auto_reload {
+                    if own_write {
+                        // Nothing new to load
+                    } else if app.config.watch.auto_reload && !app.doc.modified {
                         // Auto reload
                         if let Err(e) = app.reload_document() {
-                            eprintln!("Failed to reload document: {}", e);
+                            app.set_error_message(format!("Failed to reload document: {}", e));
                         }
                     } else {
-                        // Just mark as dirty
+                        // Just mark as dirty; unsaved edits are never
+                        // replaced by an automatic reload
                         app.doc.dirty_on_disk = true;
                     }
                 }
             }
         }
 
+        // New and changed files in directory mode
+        #[cfg(feature = "watch")]
+        if let Some(watcher) = app.dir_watcher.as_mut() {
+            let changed = watcher.changed_paths(250);
+            if !changed.is_empty() {
+                tracing::debug!("{} file(s) changed in the directory", changed.len());
+                app.apply_directory_changes(changed);
+            }
+        }
+
+        // Apply config file edits
+        #[cfg(feature = "watch")]
+        if app
+            .config_watcher
+            .as_mut()
+            .is_some_and(|watcher| watcher.check_changed(250))
+        {
+            let disk_mtime = mdx_core::Config::config_path()
+                .and_then(|path| std::fs::metadata(path).ok())
+                .and_then(|m| m.modified().ok());
+            // Saving from the options dialog already applied the config
+            if disk_mtime.is_none() || disk_mtime != app.config_saved_mtime {
+                app.reload_config();
+            }
+        }
+
         // Check for diff results from worker
         #[cfg(feature = "git")]
         {
             if let Some(result) = app.diff_worker.try_recv_result() {
                 // Check if result matches current document revision
-                if result.doc_id == 0 && result.rev == app.doc.rev {
+                if result.doc_id == app.doc_id && result.rev == app.doc.rev {
                     // Apply the diff gutter
                     app.doc.diff_gutter = result.gutter;
                 }
             }
         }
+
+        // Merge wiki page and remote link check results
+        let result = app.link_worker.as_ref().and_then(|w| w.try_recv_result());
+        if let Some(result) = result {
+            app.apply_link_results(result);
+        }
+
+        // Merge spell check results
+        let result = app.spell_worker.as_ref().and_then(|w| w.try_recv_result());
+        if let Some(result) = result {
+            app.apply_spell_results(result);
+        }
+
+        // Take in image metadata read on the worker pool
+        #[cfg(feature = "images")]
+        app.image_cache.poll();
+
+        // Show the output of a finished code block
+        let result = app.exec_worker.as_ref().and_then(|w| w.try_recv_result());
+        if let Some(result) = result {
+            app.apply_block_result(result);
+        }
     }
 
     Ok(())
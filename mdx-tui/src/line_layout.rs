@@ -10,8 +10,8 @@
 //! held-j burst. This cache amortizes that to O(1) per query after a
 //! O(lines) rebuild whenever the width or document changes.
 //!
-//! Wrapping heuristic: `(line_len_chars + width - 1) / width`, floored
-//! at 1 — matches the heuristic that used to live on App and that
+//! Wrapping heuristic: `(line_display_width + width - 1) / width`,
+//! floored at 1 — matches the heuristic that used to live on App and that
 //! `scroll_math` implicitly assumed. It is NOT word-boundary aware, so
 //! it can disagree with the renderer's word-wrap by a row or two on
 //! very long paragraphs. Aligning the two is a follow-up.
@@ -23,6 +23,7 @@
 
 use crate::app::LayoutGeneration;
 use ropey::Rope;
+use unicode_width::UnicodeWidthChar;
 
 /// Minimum content width below which wrapping math falls back to a 1:1
 /// mapping. Mirrors `layout_const::MIN_WRAP_AWARE_WIDTH`.
@@ -86,17 +87,18 @@ impl LineLayoutCache {
                 // `Rope::line` includes the trailing newline; exclude it
                 // so the visual-row count doesn't tick over for every
                 // line whose content happens to exactly fill the width.
-                let mut len = rope.line(i).len_chars();
-                if len > 0 {
-                    let line = rope.line(i);
-                    if line.char(len - 1) == '\n' {
-                        len -= 1;
-                    }
-                }
+                // Measure display columns so wide CJK/emoji glyphs count
+                // as the two cells they occupy on screen.
+                let len: usize = rope
+                    .line(i)
+                    .chars()
+                    .filter(|&c| c != '\n')
+                    .map(|c| c.width().unwrap_or(0))
+                    .sum();
                 if len == 0 {
                     1
                 } else {
-                    let rows = (len + effective_width - 1) / effective_width;
+                    let rows = len.div_ceil(effective_width);
                     rows.min(u16::MAX as usize) as u16
                 }
             }
@@ -214,6 +216,15 @@ mod tests {
         assert_eq!(c.visual_height_of_line(1), 2);
     }
 
+    #[test]
+    fn wide_chars_count_display_columns() {
+        // 30 CJK ideographs are 60 columns wide -> 2 rows at width 50.
+        let r = rope_from(&[&"漢".repeat(30)]);
+        let mut c = LineLayoutCache::new();
+        c.ensure_for(50, 1, 1, &r);
+        assert_eq!(c.visual_height_of_line(0), 2);
+    }
+
     #[test]
     fn empty_line_still_takes_one_row() {
         let r = rope_from(&["", "x", ""]);
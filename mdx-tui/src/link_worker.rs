@@ -0,0 +1,144 @@
+//! Background link checks: remote links over HTTP, and `[[wiki links]]`
+//! to other pages, which may take a search of the whole vault
+
+use crate::worker_pool::{Queue, QueueOptions, Task, WorkerPool};
+use mdx_core::links::{BrokenLink, LinkRef};
+#[cfg(feature = "remote-links")]
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Per-request timeout for a single URL
+#[cfg(feature = "remote-links")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request to check the links of a document revision
+#[derive(Debug, Clone)]
+pub struct LinkCheckRequest {
+    pub rev: u64,
+    pub links: Vec<LinkRef>,
+    /// Directory of the document, where wiki pages are looked for first
+    pub base_dir: PathBuf,
+    /// Vault wiki pages are also looked for in (`links.vault_root`)
+    pub vault_root: Option<PathBuf>,
+}
+
+/// Broken links found for a document revision
+#[derive(Debug, Clone)]
+pub struct LinkCheckResult {
+    pub rev: u64,
+    pub broken: Vec<BrokenLink>,
+}
+
+impl Task for LinkCheckRequest {
+    fn rev(&self) -> u64 {
+        self.rev
+    }
+}
+
+/// Link checker handle
+pub struct LinkWorker {
+    queue: Queue<LinkCheckRequest, LinkCheckResult>,
+}
+
+impl LinkWorker {
+    /// Link checker running its checks on `pool`
+    pub fn new(pool: &WorkerPool) -> Self {
+        let options = QueueOptions {
+            limit: 1,
+            debounce: Duration::ZERO,
+        };
+        Self {
+            queue: Queue::new(pool, options, check_links),
+        }
+    }
+
+    /// Queue a check of the remote links and wiki page links in
+    /// `req.links`; other links are checked in place
+    pub fn request_check(&self, mut req: LinkCheckRequest) {
+        req.links
+            .retain(|link| link.is_remote() || link.is_wiki_page());
+        self.queue.submit(req);
+    }
+
+    /// Try to receive a check result (non-blocking)
+    pub fn try_recv_result(&self) -> Option<LinkCheckResult> {
+        self.queue.try_recv()
+    }
+}
+
+/// A handler checking the links of a request
+fn check_links() -> impl FnMut(LinkCheckRequest) -> Option<LinkCheckResult> {
+    // URL -> problem, so reloads don't re-request every link
+    #[cfg(feature = "remote-links")]
+    let mut cache: HashMap<String, Option<String>> = HashMap::new();
+
+    move |req| {
+        let mut broken = mdx_core::links::find_missing_wiki_pages(
+            &req.links,
+            &req.base_dir,
+            req.vault_root.as_deref(),
+        );
+        #[cfg(feature = "remote-links")]
+        broken.extend(
+            req.links
+                .into_iter()
+                .filter(LinkRef::is_remote)
+                .filter_map(|link| {
+                    let problem = cache
+                        .entry(link.dest.clone())
+                        .or_insert_with(|| {
+                            mdx_core::links::check_remote_link(&link.dest, REQUEST_TIMEOUT)
+                        })
+                        .clone()?;
+                    Some(BrokenLink { link, problem })
+                }),
+        );
+        broken.sort_by_key(|b| b.link.range.start);
+        Some(LinkCheckResult {
+            rev: req.rev,
+            broken,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(dest: &str, wiki: bool) -> LinkRef {
+        LinkRef {
+            line: 0,
+            range: 0..10,
+            dest: dest.to_string(),
+            text: dest.to_string(),
+            is_image: false,
+            wiki,
+            reference: None,
+        }
+    }
+
+    #[test]
+    fn test_worker_checks_wiki_pages_and_skips_local_links() {
+        let vault = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(vault.path().join("deep")).unwrap();
+        std::fs::write(vault.path().join("deep/Found.md"), "x").unwrap();
+
+        let worker = LinkWorker::new(&WorkerPool::spawn());
+        worker.request_check(LinkCheckRequest {
+            rev: 3,
+            links: vec![
+                link("missing.md", false),
+                link("Found", true),
+                link("Nowhere", true),
+            ],
+            base_dir: vault.path().join("notes"),
+            vault_root: Some(vault.path().to_path_buf()),
+        });
+
+        let result = worker.queue.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(result.rev, 3);
+        assert_eq!(result.broken.len(), 1);
+        assert_eq!(result.broken[0].problem, "no page named 'Nowhere'");
+    }
+}
@@ -0,0 +1,469 @@
+//! Editor integration: JSON-RPC 2.0 over a Unix socket (`mdx --listen PATH`)
+//!
+//! An editor connects to the socket and writes one request per line, e.g.
+//! `{"jsonrpc":"2.0","id":1,"method":"scrollTo","params":{"line":42}}`.
+//! Each request with an `id` gets one response line back; requests without
+//! one are notifications and get none. Methods:
+//!
+//! - `scrollTo {line}`: put the cursor on 1-based source `line`, centered
+//! - `reload`: read the shown document from disk again
+//! - `open {path, line?}`: show `path`, opening it as a new buffer if needed
+//! - `quit`: exit mdx, unless a document has unsaved edits
+//!
+//! Params can also be given by position (`"params": [42]`). Connections are
+//! served on background threads, and the requests run on the UI thread
+//! between frames through [`Listener::poll`].
+//!
+//! The other way round, `gs` in mdx sends every connected editor a
+//! `revealLine {path, line}` notification for the cursor's source line.
+
+use crate::app::App;
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The method ran and failed, e.g. a file didn't load
+const SERVER_ERROR: i64 = -32000;
+
+/// A request line from a client and where its response goes
+struct Incoming {
+    line: String,
+    reply: Sender<String>,
+}
+
+/// Where to send notifications for each connected client
+type Clients = Arc<Mutex<Vec<Sender<String>>>>;
+
+fn lock(clients: &Clients) -> std::sync::MutexGuard<'_, Vec<Sender<String>>> {
+    // A client thread that panicked leaves the list itself intact
+    clients.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A listening socket; the socket file is removed when this is dropped
+pub struct Listener {
+    path: PathBuf,
+    incoming: Receiver<Incoming>,
+    clients: Clients,
+}
+
+impl Listener {
+    /// Listen on a Unix socket at `path`. A socket file left behind by an
+    /// mdx that didn't exit cleanly is replaced; one that is still being
+    /// served, or any other kind of file, is an error.
+    #[cfg(unix)]
+    pub fn bind(path: &Path) -> Result<Self> {
+        use anyhow::Context;
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("{} exists and isn't a socket", path.display());
+            }
+            if UnixStream::connect(path).is_ok() {
+                anyhow::bail!("{} is already in use", path.display());
+            }
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket: {}", path.display()))?;
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+        let (incoming_tx, incoming_rx) = crossbeam_channel::unbounded();
+        let clients = Clients::default();
+        let accepted = Arc::clone(&clients);
+        std::thread::Builder::new()
+            .name("mdx-listen".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let incoming = incoming_tx.clone();
+                    let clients = Arc::clone(&accepted);
+                    std::thread::spawn(move || serve(stream, incoming, clients));
+                }
+            })
+            .context("Failed to start the listener thread")?;
+
+        tracing::info!("Listening for editor requests on {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            incoming: incoming_rx,
+            clients,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(path: &Path) -> Result<Self> {
+        let _ = path;
+        anyhow::bail!("--listen needs Unix domain sockets, which this platform doesn't have")
+    }
+
+    /// Path of the socket
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Run the requests that arrived since the last call
+    pub fn poll(&self, app: &mut App) {
+        while let Ok(incoming) = self.incoming.try_recv() {
+            if let Some(response) = handle_line(app, &incoming.line) {
+                let _ = incoming.reply.send(response);
+            }
+        }
+    }
+
+    /// Send a notification to every connected client; returns how many
+    /// there were
+    pub fn notify(&self, method: &str, params: Value) -> usize {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params }).to_string();
+        lock(&self.clients)
+            .iter()
+            .filter(|client| client.send(message.clone()).is_ok())
+            .count()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Read request lines from one client and write back the responses
+#[cfg(unix)]
+fn serve(stream: std::os::unix::net::UnixStream, incoming: Sender<Incoming>, clients: Clients) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let (reply_tx, reply_rx) = crossbeam_channel::unbounded::<String>();
+    // Responses come back in request order; the writer ends once the
+    // reader and every pending request have dropped their senders
+    std::thread::spawn(move || {
+        for response in reply_rx {
+            if writeln!(writer, "{}", response).is_err() {
+                break;
+            }
+        }
+    });
+    lock(&clients).push(reply_tx.clone());
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = Incoming {
+            line,
+            reply: reply_tx.clone(),
+        };
+        if incoming.send(request).is_err() {
+            break;
+        }
+    }
+    lock(&clients).retain(|client| !client.same_channel(&reply_tx));
+}
+
+/// Run one JSON-RPC request line against the app. Returns the response
+/// line, or `None` for a notification.
+pub fn handle_line(app: &mut App, line: &str) -> Option<String> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        let id = id.unwrap_or(Value::Null);
+        return Some(error_response(
+            id,
+            INVALID_REQUEST,
+            "Missing method".to_string(),
+        ));
+    };
+    let params = request.get("params").unwrap_or(&Value::Null);
+
+    tracing::debug!(method, "Editor request");
+    let result = dispatch(app, method, params);
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+    .to_string()
+}
+
+/// A param given by name or, failing that, by position
+fn param<'a>(params: &'a Value, name: &str, position: usize) -> Option<&'a Value> {
+    params.get(name).or_else(|| params.get(position))
+}
+
+/// A 1-based line param as a 0-based line
+fn line_param(params: &Value, position: usize) -> Result<Option<usize>, (i64, String)> {
+    match param(params, "line", position) {
+        None | Some(Value::Null) => Ok(None),
+        Some(line) => match line.as_u64() {
+            Some(line) if line > 0 => Ok(Some(line as usize - 1)),
+            _ => Err((INVALID_PARAMS, "line must be a number from 1".to_string())),
+        },
+    }
+}
+
+fn dispatch(app: &mut App, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "scrollTo" => {
+            let line = line_param(params, 0)?
+                .ok_or((INVALID_PARAMS, "scrollTo needs a line".to_string()))?;
+            Ok(json!({ "line": scroll_to(app, line) + 1 }))
+        }
+        "reload" => {
+            if app.doc.modified {
+                return Err((SERVER_ERROR, "The document has unsaved edits".to_string()));
+            }
+            app.reload_document()
+                .map_err(|e| (SERVER_ERROR, format!("{:#}", e)))?;
+            Ok(json!({ "lines": app.doc.line_count() }))
+        }
+        "open" => {
+            let path = param(params, "path", 0)
+                .and_then(Value::as_str)
+                .ok_or((INVALID_PARAMS, "open needs a path".to_string()))?;
+            let line = line_param(params, 1)?;
+            app.open_or_switch(Path::new(path))
+                .map_err(|e| (SERVER_ERROR, format!("{:#}", e)))?;
+            if let Some(line) = line {
+                scroll_to(app, line);
+            }
+            Ok(json!({ "path": app.doc.path.display().to_string() }))
+        }
+        "quit" => {
+            if app.buffer_docs().any(|doc| doc.modified) {
+                return Err((SERVER_ERROR, "A document has unsaved edits".to_string()));
+            }
+            app.quit();
+            Ok(Value::Null)
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    }
+}
+
+/// Center 0-based `line` in the focused pane; returns the line it landed on
+fn scroll_to(app: &mut App, line: usize) -> usize {
+    let line = line.min(app.doc.line_count().saturating_sub(1));
+    let pane = app.panes.focused;
+    app.goto_centered(pane, line);
+    line
+}
+
+/// Tell the connected editors to show the cursor's source line (`gs`)
+pub fn reveal_cursor(app: &mut App) {
+    let Some(line) = app.panes.focused_pane().map(|pane| pane.view.cursor_line) else {
+        return;
+    };
+    let Some(listener) = &app.listener else {
+        app.set_info_message("No editor connection (start mdx with --listen)");
+        return;
+    };
+    let path = app
+        .doc
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| app.doc.path.clone());
+    let params = json!({ "path": path.display().to_string(), "line": line + 1 });
+    match listener.notify("revealLine", params) {
+        0 => app.set_info_message("No editor is connected"),
+        _ => app.set_success_message(format!("Sent line {} to the editor", line + 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdx_core::{Config, Document};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn md_file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    fn app_with(content: &str) -> (App, NamedTempFile) {
+        let file = md_file(content);
+        let (doc, _warnings) = Document::load(file.path()).unwrap();
+        (App::new(Config::default(), doc, vec![]), file)
+    }
+
+    fn call(app: &mut App, request: &str) -> Value {
+        serde_json::from_str(&handle_line(app, request).unwrap()).unwrap()
+    }
+
+    fn cursor(app: &App) -> usize {
+        app.panes.focused_pane().unwrap().view.cursor_line
+    }
+
+    #[test]
+    fn test_scroll_to_moves_cursor() {
+        let content: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        let (mut app, _file) = app_with(&content);
+
+        let response = call(
+            &mut app,
+            r#"{"jsonrpc":"2.0","id":1,"method":"scrollTo","params":{"line":42}}"#,
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["line"], 42);
+        assert_eq!(cursor(&app), 41);
+
+        // Positional params, and a notification gets no response
+        let request = r#"{"jsonrpc":"2.0","method":"scrollTo","params":[7]}"#;
+        assert!(handle_line(&mut app, request).is_none());
+        assert_eq!(cursor(&app), 6);
+    }
+
+    #[test]
+    fn test_errors_follow_json_rpc() {
+        let (mut app, _file) = app_with("# Doc\n");
+        assert_eq!(call(&mut app, "not json")["error"]["code"], PARSE_ERROR);
+        let unknown = call(&mut app, r#"{"jsonrpc":"2.0","id":"a","method":"fly"}"#);
+        assert_eq!(unknown["id"], "a");
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        let bad_line = call(
+            &mut app,
+            r#"{"jsonrpc":"2.0","id":2,"method":"scrollTo","params":{"line":0}}"#,
+        );
+        assert_eq!(bad_line["error"]["code"], INVALID_PARAMS);
+        let missing = call(
+            &mut app,
+            r#"{"jsonrpc":"2.0","id":3,"method":"open","params":{"path":"/no/such.md"}}"#,
+        );
+        assert_eq!(missing["error"]["code"], SERVER_ERROR);
+        assert_eq!(app.buffer_count(), 1);
+    }
+
+    #[test]
+    fn test_open_and_reload() {
+        let (mut app, mut file) = app_with("# First\n");
+        let other = md_file("# Second\n\ntext\n");
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "open",
+            "params": { "path": other.path(), "line": 3 },
+        });
+        let response = call(&mut app, &request.to_string());
+        assert!(response["error"].is_null(), "{}", response);
+        assert_eq!(app.buffer_count(), 2);
+        assert_eq!(cursor(&app), 2);
+
+        // Opening it again switches instead of adding a buffer
+        let request =
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "open", "params": [file.path()] });
+        call(&mut app, &request.to_string());
+        assert_eq!(app.buffer_count(), 2);
+        assert_eq!(app.doc.path, file.path());
+
+        file.write_all(b"\nmore\n").unwrap();
+        file.flush().unwrap();
+        let response = call(&mut app, r#"{"jsonrpc":"2.0","id":3,"method":"reload"}"#);
+        assert_eq!(response["result"]["lines"], app.doc.line_count());
+        assert!(app.doc.rope.to_string().contains("more"));
+    }
+
+    #[test]
+    fn test_quit_refused_over_unsaved_edits() {
+        let (mut app, _file) = app_with("# Doc\n");
+        app.doc.modified = true;
+        let response = call(&mut app, r#"{"jsonrpc":"2.0","id":1,"method":"quit"}"#);
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert!(!app.should_quit);
+
+        app.doc.modified = false;
+        let response = call(&mut app, r#"{"jsonrpc":"2.0","id":2,"method":"quit"}"#);
+        assert!(response["error"].is_null(), "{}", response);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_reveal_cursor_without_listener() {
+        let (mut app, _file) = app_with("# Doc\n");
+        reveal_cursor(&mut app);
+        let message = app.status_message.as_ref().unwrap();
+        assert!(message.0.contains("--listen"), "{:?}", message);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_leaves_other_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keep.txt");
+        std::fs::write(&path, "notes").unwrap();
+        let err = Listener::bind(&path).err().unwrap();
+        assert!(err.to_string().contains("isn't a socket"), "{:#}", err);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_round_trip() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixStream;
+
+        let (mut app, _file) = app_with("a\nb\nc\n");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mdx.sock");
+        let listener = Listener::bind(&path).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        writeln!(
+            client,
+            r#"{{"jsonrpc":"2.0","id":9,"method":"scrollTo","params":[3]}}"#
+        )
+        .unwrap();
+        // The request reaches the UI thread on a later poll
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while cursor(&app) != 2 && std::time::Instant::now() < deadline {
+            listener.poll(&mut app);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let mut reader = BufReader::new(client);
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["result"]["line"], 3);
+
+        // gs sends the cursor line back to every connected editor
+        app.listener = Some(listener);
+        reveal_cursor(&mut app);
+        let mut notification = String::new();
+        reader.read_line(&mut notification).unwrap();
+        let notification: Value = serde_json::from_str(&notification).unwrap();
+        assert_eq!(notification["method"], "revealLine");
+        assert_eq!(notification["params"]["line"], 3);
+        assert!(notification.get("id").is_none());
+        let listener = app.listener.take().unwrap();
+
+        // A second mdx can't take over a live socket; dropping removes it
+        assert!(Listener::bind(&path).is_err());
+        drop(listener);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,109 @@
+//! Start locations given on the command line
+//!
+//! `mdx file.md:120`, `mdx file.md +120`, and `mdx file.md#anchor` open the
+//! file with the cursor on a line or heading.
+
+use std::path::{Path, PathBuf};
+
+/// Where to put the cursor when a document opens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// 1-based line number, as printed by compilers and `grep -n`
+    Line(usize),
+    /// Heading anchor, without the `#`
+    Anchor(String),
+}
+
+/// Split a `FILE:LINE`, `FILE:LINE:COL`, or `FILE#anchor` argument.
+///
+/// An argument naming an existing file is taken as is, so files with `:`
+/// or `#` in their names still open.
+pub fn split_file_arg(arg: &Path) -> (PathBuf, Option<Location>) {
+    let unchanged = (arg.to_path_buf(), None);
+    if arg.exists() {
+        return unchanged;
+    }
+    let Some(text) = arg.to_str() else {
+        return unchanged;
+    };
+
+    if let Some((file, anchor)) = text.rsplit_once('#') {
+        if !file.is_empty() && !anchor.is_empty() {
+            return (
+                PathBuf::from(file),
+                Some(Location::Anchor(anchor.to_string())),
+            );
+        }
+    }
+
+    // Drop a trailing column, then take the line
+    let mut parts = text.rsplitn(3, ':').collect::<Vec<_>>();
+    parts.reverse();
+    let numbers = parts
+        .iter()
+        .skip(1)
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    match parts.as_slice() {
+        [file, line, ..] if numbers && !file.is_empty() => match line.parse() {
+            Ok(line) => (PathBuf::from(file), Some(Location::Line(line))),
+            Err(_) => unchanged,
+        },
+        _ => unchanged,
+    }
+}
+
+/// Parse a `+LINE` argument
+pub fn parse_line_arg(arg: &str) -> Option<Location> {
+    arg.strip_prefix('+')?.parse().ok().map(Location::Line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(arg: &str) -> (PathBuf, Option<Location>) {
+        split_file_arg(Path::new(arg))
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(
+            split("notes.md:120"),
+            (PathBuf::from("notes.md"), Some(Location::Line(120)))
+        );
+        assert_eq!(
+            split("docs/notes.md:12:7"),
+            (PathBuf::from("docs/notes.md"), Some(Location::Line(12)))
+        );
+    }
+
+    #[test]
+    fn test_split_anchor() {
+        assert_eq!(
+            split("notes.md#getting-started"),
+            (
+                PathBuf::from("notes.md"),
+                Some(Location::Anchor("getting-started".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_plain_and_existing_paths_are_unchanged() {
+        assert_eq!(split("notes.md"), (PathBuf::from("notes.md"), None));
+        assert_eq!(split("notes.md:"), (PathBuf::from("notes.md:"), None));
+        assert_eq!(split("a:b.md"), (PathBuf::from("a:b.md"), None));
+
+        let dir = tempfile::tempdir().unwrap();
+        let odd = dir.path().join("odd:12");
+        std::fs::write(&odd, "# Odd\n").unwrap();
+        assert_eq!(split_file_arg(&odd), (odd, None));
+    }
+
+    #[test]
+    fn test_parse_line_arg() {
+        assert_eq!(parse_line_arg("+42"), Some(Location::Line(42)));
+        assert_eq!(parse_line_arg("42"), None);
+        assert_eq!(parse_line_arg("+x"), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Tracing setup: with `--verbose`, events go to a log file in the state
+//! directory and to a ring buffer that the in-app log viewer (`:log`) reads
+
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Events kept for the log viewer; older ones are dropped
+pub const LOG_CAPACITY: usize = 1000;
+
+/// Filter used by `--verbose` when `RUST_LOG` isn't set
+const VERBOSE_FILTER: &str = "mdx=debug,mdx_core=debug,mdx_tui=debug";
+
+static BUFFER: OnceLock<LogBuffer> = OnceLock::new();
+
+/// One recorded event
+#[derive(Clone, Debug)]
+pub struct LogEvent {
+    /// Time since logging started
+    pub elapsed: Duration,
+    pub level: Level,
+    pub target: String,
+    /// The message followed by any other fields as `name=value`
+    pub message: String,
+}
+
+/// Shared ring buffer of recent events
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    events: Arc<Mutex<VecDeque<LogEvent>>>,
+    start: Instant,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: Arc::new(Mutex::new(VecDeque::new())),
+            start: Instant::now(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an event, dropping the oldest one when full
+    pub fn push(&self, level: Level, target: &str, message: String) {
+        let event = LogEvent {
+            elapsed: self.start.elapsed(),
+            level,
+            target: target.to_string(),
+            message,
+        };
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Copy of the recorded events, oldest first
+    pub fn snapshot(&self) -> Vec<LogEvent> {
+        let events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Layer that copies every event into a [`LogBuffer`]
+pub struct BufferLayer {
+    buffer: LogBuffer,
+}
+
+impl BufferLayer {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.buffer
+            .push(*metadata.level(), metadata.target(), visitor.finish());
+    }
+}
+
+/// Collects the `message` field and appends the others as `name=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.message.is_empty() {
+            self.fields.trim_start().to_string()
+        } else {
+            self.message + &self.fields
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// The buffer installed by [`init`], if `--verbose` was given
+pub fn buffer() -> Option<LogBuffer> {
+    BUFFER.get().cloned()
+}
+
+/// Install the global subscriber. With `verbose`, debug events from mdx
+/// (or whatever `RUST_LOG` selects) are appended to the log file and kept
+/// for the log viewer. Without it, `RUST_LOG` still prints to stderr when
+/// set.
+pub fn init(verbose: bool) -> Result<()> {
+    if !verbose {
+        if std::env::var_os(EnvFilter::DEFAULT_ENV).is_some() {
+            let _ = tracing_subscriber::fmt()
+                .with_env_filter(EnvFilter::from_default_env())
+                .with_writer(std::io::stderr)
+                .try_init();
+        }
+        return Ok(());
+    }
+
+    let path = mdx_core::Config::log_path().context("No state directory for the log file")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create log directory: {}", parent.display()))?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+
+    let buffer = BUFFER.get_or_init(|| LogBuffer::new(LOG_CAPACITY)).clone();
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(VERBOSE_FILTER));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file)),
+        )
+        .with(BufferLayer::new(buffer))
+        .try_init()
+        .context("Failed to install the logger")?;
+
+    tracing::info!("mdx {} started", env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_layer_records_message_and_fields() {
+        let buffer = LogBuffer::new(10);
+        let subscriber = tracing_subscriber::registry().with(BufferLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(doc_id = 3, "Diff computed in {} ms", 12);
+            tracing::warn!(path = "a.md");
+        });
+
+        let events = buffer.snapshot();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, Level::DEBUG);
+        assert_eq!(events[0].message, "Diff computed in 12 ms doc_id=3");
+        assert_eq!(events[0].target, module_path!());
+        assert_eq!(events[1].message, "path=a.md");
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest_when_full() {
+        let buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(Level::INFO, "test", format!("event {}", i));
+        }
+        let messages: Vec<_> = buffer.snapshot().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["event 2", "event 3", "event 4"]);
+    }
+}
@@ -1,6 +1,6 @@
 //! Options dialog for configuration management
 
-use mdx_core::{config::ThemeVariant, Config};
+use mdx_core::Config;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DialogButton {
@@ -15,11 +15,19 @@ pub enum OptionField {
     TocEnabled,
     TocSide,
     TocWidth,
+    TocStats,
     SafeMode,
     NoExec,
     Utf8Graphics,
     ShowScrollbar,
     SkipFrontMatter,
+    NumberHeadings,
+    RenumberLists,
+    Hyphenate,
+    Justify,
+    CodeLabel,
+    LineNumbers,
+    SpellCheck,
     #[cfg(feature = "watch")]
     WatchEnabled,
     #[cfg(feature = "watch")]
@@ -37,11 +45,19 @@ impl OptionField {
             OptionField::TocEnabled,
             OptionField::TocSide,
             OptionField::TocWidth,
+            OptionField::TocStats,
             OptionField::SafeMode,
             OptionField::NoExec,
             OptionField::Utf8Graphics,
             OptionField::ShowScrollbar,
             OptionField::SkipFrontMatter,
+            OptionField::NumberHeadings,
+            OptionField::RenumberLists,
+            OptionField::Hyphenate,
+            OptionField::Justify,
+            OptionField::CodeLabel,
+            OptionField::LineNumbers,
+            OptionField::SpellCheck,
             #[cfg(feature = "watch")]
             OptionField::WatchEnabled,
             #[cfg(feature = "watch")]
@@ -59,11 +75,19 @@ impl OptionField {
             OptionField::TocEnabled => "Table of Contents",
             OptionField::TocSide => "TOC Side",
             OptionField::TocWidth => "TOC Width",
+            OptionField::TocStats => "TOC Section Sizes",
             OptionField::SafeMode => "Safe Mode",
             OptionField::NoExec => "No Exec",
             OptionField::Utf8Graphics => "UTF-8 Graphics",
             OptionField::ShowScrollbar => "Show Scrollbar",
             OptionField::SkipFrontMatter => "Skip Front Matter",
+            OptionField::NumberHeadings => "Number Headings",
+            OptionField::RenumberLists => "Renumber Lists",
+            OptionField::Hyphenate => "Hyphenate",
+            OptionField::Justify => "Justify Text",
+            OptionField::CodeLabel => "Code Label",
+            OptionField::LineNumbers => "Line Numbers",
+            OptionField::SpellCheck => "Spell Check",
             #[cfg(feature = "watch")]
             OptionField::WatchEnabled => "File Watching",
             #[cfg(feature = "watch")]
@@ -114,10 +138,7 @@ impl OptionsDialog {
 
         match self.fields[self.selected_index] {
             OptionField::Theme => {
-                self.editing_config.theme = match self.editing_config.theme {
-                    ThemeVariant::Dark => ThemeVariant::Light,
-                    ThemeVariant::Light => ThemeVariant::Dark,
-                };
+                self.editing_config.theme = self.editing_config.theme.next();
             }
             OptionField::TocEnabled => {
                 self.editing_config.toc.enabled = !self.editing_config.toc.enabled;
@@ -138,6 +159,9 @@ impl OptionsDialog {
                     _ => 25,
                 };
             }
+            OptionField::TocStats => {
+                self.editing_config.toc.stats = !self.editing_config.toc.stats;
+            }
             OptionField::SafeMode => {
                 self.editing_config.security.safe_mode = !self.editing_config.security.safe_mode;
             }
@@ -156,6 +180,42 @@ impl OptionsDialog {
                 self.editing_config.render.skip_front_matter =
                     !self.editing_config.render.skip_front_matter;
             }
+            OptionField::NumberHeadings => {
+                self.editing_config.render.number_headings =
+                    !self.editing_config.render.number_headings;
+            }
+            OptionField::RenumberLists => {
+                self.editing_config.render.renumber_lists =
+                    !self.editing_config.render.renumber_lists;
+            }
+            OptionField::Hyphenate => {
+                self.editing_config.render.hyphenate = !self.editing_config.render.hyphenate;
+            }
+            OptionField::Justify => {
+                self.editing_config.render.justify = !self.editing_config.render.justify;
+            }
+            OptionField::CodeLabel => {
+                use mdx_core::config::CodeLabel;
+                self.editing_config.render.code_label = match self.editing_config.render.code_label
+                {
+                    CodeLabel::Right => CodeLabel::Left,
+                    CodeLabel::Left => CodeLabel::Hidden,
+                    CodeLabel::Hidden => CodeLabel::Right,
+                };
+            }
+            OptionField::LineNumbers => {
+                use mdx_core::config::LineNumbers;
+                self.editing_config.render.line_numbers =
+                    match self.editing_config.render.line_numbers {
+                        LineNumbers::Absolute => LineNumbers::Relative,
+                        LineNumbers::Relative => LineNumbers::Hybrid,
+                        LineNumbers::Hybrid => LineNumbers::Off,
+                        LineNumbers::Off => LineNumbers::Absolute,
+                    };
+            }
+            OptionField::SpellCheck => {
+                self.editing_config.spell.enabled = !self.editing_config.spell.enabled;
+            }
             #[cfg(feature = "watch")]
             OptionField::WatchEnabled => {
                 self.editing_config.watch.enabled = !self.editing_config.watch.enabled;
@@ -181,6 +241,7 @@ impl OptionsDialog {
             OptionField::TocEnabled => format!("{}", self.editing_config.toc.enabled),
             OptionField::TocSide => format!("{:?}", self.editing_config.toc.side),
             OptionField::TocWidth => format!("{}", self.editing_config.toc.width),
+            OptionField::TocStats => format!("{}", self.editing_config.toc.stats),
             OptionField::SafeMode => format!("{}", self.editing_config.security.safe_mode),
             OptionField::NoExec => format!("{}", self.editing_config.security.no_exec),
             OptionField::Utf8Graphics => {
@@ -192,6 +253,17 @@ impl OptionsDialog {
             OptionField::SkipFrontMatter => {
                 format!("{}", self.editing_config.render.skip_front_matter)
             }
+            OptionField::NumberHeadings => {
+                format!("{}", self.editing_config.render.number_headings)
+            }
+            OptionField::RenumberLists => {
+                format!("{}", self.editing_config.render.renumber_lists)
+            }
+            OptionField::Hyphenate => format!("{}", self.editing_config.render.hyphenate),
+            OptionField::Justify => format!("{}", self.editing_config.render.justify),
+            OptionField::CodeLabel => format!("{:?}", self.editing_config.render.code_label),
+            OptionField::LineNumbers => format!("{:?}", self.editing_config.render.line_numbers),
+            OptionField::SpellCheck => format!("{}", self.editing_config.spell.enabled),
             #[cfg(feature = "watch")]
             OptionField::WatchEnabled => format!("{}", self.editing_config.watch.enabled),
             #[cfg(feature = "watch")]
@@ -0,0 +1,168 @@
+//! Preview of what a reference on the cursor line points at (`K`)
+//!
+//! A footnote reference shows its definition and an `#anchor` link the
+//! start of its section, so a reader can check a target without jumping
+//! there. Other links show their destination, and reference-style links
+//! the title of their definition.
+
+use mdx_core::config::MarkdownFlavor;
+use mdx_core::Document;
+
+/// Lines of a section or footnote shown before the preview is cut short
+const MAX_PREVIEW_LINES: usize = 12;
+
+/// Target of a reference, shown in a popup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preview {
+    /// What is previewed, for the popup title
+    pub title: String,
+    /// Markdown source lines of the target
+    pub lines: Vec<String>,
+}
+
+impl Preview {
+    /// Preview of a reference on `line`: a footnote first, then an
+    /// `#anchor` link, then any other link. `Ok(None)` if the line has
+    /// none; an error if the target is missing.
+    pub fn at_line(
+        doc: &Document,
+        line: usize,
+        flavor: MarkdownFlavor,
+    ) -> anyhow::Result<Option<Self>> {
+        let footnotes = mdx_core::links::extract_footnotes(&doc.rope, flavor);
+        if let Some(reference) = footnotes.references.iter().find(|r| r.line == line) {
+            let label = mdx_core::links::normalize_label(&reference.label);
+            let Some(lines) = footnotes.definitions.get(&label) else {
+                anyhow::bail!("No definition for footnote [^{}]", reference.label);
+            };
+            return Ok(Some(Self {
+                title: format!("Footnote [^{}]", reference.label),
+                lines: footnote_text(doc, lines.clone()),
+            }));
+        }
+
+        let links: Vec<_> = mdx_core::links::extract_links(&doc.rope, flavor)
+            .into_iter()
+            .filter(|link| link.line == line && !link.is_image && !link.wiki)
+            .collect();
+        let Some(link) = links
+            .iter()
+            .find(|link| link.dest.starts_with('#'))
+            .or(links.first())
+        else {
+            return Ok(None);
+        };
+
+        let title = match &link.reference {
+            Some(label) => format!("[{}]", label),
+            None => link.text.clone(),
+        };
+        if let Some(fragment) = link.dest.strip_prefix('#') {
+            let Some(heading) = mdx_core::toc::find_anchor(&doc.headings, fragment) else {
+                anyhow::bail!("No heading with anchor '#{}'", fragment);
+            };
+            let idx = doc
+                .headings
+                .iter()
+                .position(|h| h.line == heading.line)
+                .unwrap_or_default();
+            let section = mdx_core::outline::section_lines(&doc.rope, &doc.headings, idx);
+            return Ok(Some(Self {
+                title: format!("{} → {}", title, heading.text),
+                lines: section_text(doc, section),
+            }));
+        }
+
+        let mut lines = vec![link.dest.clone()];
+        let definition = link.reference.as_deref().and_then(|label| {
+            doc.link_definitions
+                .get(&mdx_core::links::normalize_label(label))
+        });
+        if let Some(definition) = definition.filter(|def| !def.title.is_empty()) {
+            lines.push(format!("\"{}\"", definition.title));
+        }
+        Ok(Some(Self { title, lines }))
+    }
+}
+
+/// Text of the footnote defined on `lines`, without its `[^label]:`
+/// marker or the indent of its continuation lines
+fn footnote_text(doc: &Document, lines: std::ops::Range<usize>) -> Vec<String> {
+    source_lines(doc, lines)
+        .enumerate()
+        .map(|(idx, text)| {
+            if idx == 0 {
+                let marker_end = text.find("]:").map_or(0, |pos| pos + 2);
+                text[marker_end..].trim_start().to_string()
+            } else {
+                let indent = text.len() - text.trim_start_matches(' ').len();
+                text[indent.min(4)..].to_string()
+            }
+        })
+        .take(MAX_PREVIEW_LINES)
+        .collect()
+}
+
+/// The start of the section on `lines`, under its heading
+fn section_text(doc: &Document, lines: std::ops::Range<usize>) -> Vec<String> {
+    let mut text: Vec<String> = source_lines(doc, lines)
+        .skip(1)
+        .skip_while(|line| line.trim().is_empty())
+        .take(MAX_PREVIEW_LINES + 1)
+        .collect();
+    if text.len() > MAX_PREVIEW_LINES {
+        text.truncate(MAX_PREVIEW_LINES);
+        text.push("…".to_string());
+    }
+    while text.last().is_some_and(|line| line.trim().is_empty()) {
+        text.pop();
+    }
+    text
+}
+
+fn source_lines(
+    doc: &Document,
+    lines: std::ops::Range<usize>,
+) -> impl Iterator<Item = String> + '_ {
+    lines
+        .filter_map(|idx| doc.rope.get_line(idx))
+        .map(|line| line.to_string().trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn preview(text: &str, line: usize) -> anyhow::Result<Option<Preview>> {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        let (doc, _warnings) = Document::load(file.path()).unwrap();
+        Preview::at_line(&doc, line, MarkdownFlavor::Gfm)
+    }
+
+    #[test]
+    fn test_preview_footnote_and_anchor() {
+        let text = "# Spec\n\nSee [setup](#setup) and the note[^n].\n\n\
+                    ## Setup\n\nInstall it.\n\n## Other\n\n[^n]: A note\n    over two lines.\n";
+        let note = preview(text, 2).unwrap().unwrap();
+        assert_eq!(note.title, "Footnote [^n]");
+        assert_eq!(note.lines, ["A note", "over two lines."]);
+
+        let text = text.replace("note[^n]", "note");
+        let section = preview(&text, 2).unwrap().unwrap();
+        assert_eq!(section.title, "setup → Setup");
+        assert_eq!(section.lines, ["Install it."]);
+
+        assert!(preview(&text, 0).unwrap().is_none());
+        assert!(preview("[gone](#nowhere)\n", 0).is_err());
+    }
+
+    #[test]
+    fn test_preview_reference_link() {
+        let text = "Read [the docs][docs].\n\n[docs]: https://example.com \"Manual\"\n";
+        let link = preview(text, 0).unwrap().unwrap();
+        assert_eq!(link.title, "[docs]");
+        assert_eq!(link.lines, ["https://example.com", "\"Manual\""]);
+    }
+}
@@ -0,0 +1,325 @@
+//! Printing the rendered document when stdout is not a terminal
+//!
+//! Lines are styled the same way as in the viewer, laid out into a ratatui
+//! buffer at a fixed width, and written out as ANSI escape sequences (or
+//! plain text) so the output can be piped to a pager or a file.
+
+use crate::app::App;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Paragraph, Widget, Wrap};
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// Width used when `$COLUMNS` is unset or invalid
+pub const DEFAULT_WIDTH: u16 = 80;
+
+/// Output width: `render.max_width` if set, else `$COLUMNS`, else 80
+pub fn output_width(max_width: usize) -> u16 {
+    if max_width > 0 {
+        return max_width.min(u16::MAX as usize) as u16;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|cols| cols.trim().parse::<u16>().ok())
+        .filter(|&cols| cols > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Render the document at `width` columns, with ANSI styling when `color`
+/// is set
+pub fn render_document(app: &mut App, width: u16, color: bool) -> String {
+    let mut out = String::new();
+    for (buffer, rows) in rendered_lines(app, width, color) {
+        for y in 0..rows {
+            write_row(&mut out, &buffer, y, color);
+        }
+    }
+    out
+}
+
+/// Render the document at `width` columns as plain text with a row of
+/// style letters under each text row, followed by a legend of the styles.
+/// Used for the golden-file rendering tests, where a diff of this shows
+/// exactly which cells changed text or style.
+pub fn render_style_dump(app: &mut App, width: u16) -> String {
+    let mut styles: Vec<Style> = Vec::new();
+    let mut out = String::new();
+    for (buffer, rows) in rendered_lines(app, width, true) {
+        for y in 0..rows {
+            let mut text = String::new();
+            write_row(&mut text, &buffer, y, false);
+            let end = (0..buffer.area.width)
+                .rposition(|x| !is_blank(&buffer[(x, y)], true))
+                .map_or(0, |last| last as u16 + 1);
+            let mut marks = String::new();
+            for x in 0..end {
+                let cell = &buffer[(x, y)];
+                if is_blank(cell, true) {
+                    marks.push(' ');
+                    continue;
+                }
+                let style = cell.style();
+                let index = styles.iter().position(|&s| s == style).unwrap_or_else(|| {
+                    styles.push(style);
+                    styles.len() - 1
+                });
+                marks.push(style_letter(index));
+            }
+            out.push_str(&text);
+            out.push_str(marks.trim_end());
+            out.push('\n');
+        }
+    }
+    out.push_str("--\n");
+    for (index, style) in styles.iter().enumerate() {
+        out.push_str(&format!("{}: {}\n", style_letter(index), describe_style(*style)));
+    }
+    out
+}
+
+/// Lay out each document line in a buffer of its own, with the number of
+/// rows it takes up
+fn rendered_lines(app: &mut App, width: u16, color: bool) -> Vec<(Buffer, u16)> {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for line in crate::ui::document_lines(app, width) {
+        // Wrapping never needs more rows than the line has columns
+        let height = (line.width() as u16).max(1);
+        let area = Rect::new(0, 0, width, height);
+        let mut buffer = Buffer::empty(area);
+        Paragraph::new(line)
+            .wrap(Wrap { trim: false })
+            .render(area, &mut buffer);
+        // The viewer paints its own background; piped output keeps the
+        // terminal's
+        if let Some(base_bg) = app.theme.base.bg {
+            for cell in buffer.content.iter_mut().filter(|c| c.bg == base_bg) {
+                cell.bg = Color::Reset;
+            }
+        }
+
+        let rows = (0..height)
+            .rposition(|y| (0..width).any(|x| !is_blank(&buffer[(x, y)], color)))
+            .map_or(1, |last| last as u16 + 1);
+        out.push((buffer, rows));
+    }
+    out
+}
+
+/// Letter naming the `index`th style of a style dump
+fn style_letter(index: usize) -> char {
+    const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    LETTERS.get(index).map_or('?', |&b| b as char)
+}
+
+/// A style as "fg=Cyan bg=Rgb(1, 2, 3) +BOLD | ITALIC"
+fn describe_style(style: Style) -> String {
+    let mut parts = Vec::new();
+    if let Some(fg) = style.fg.filter(|&c| c != Color::Reset) {
+        parts.push(format!("fg={:?}", fg));
+    }
+    if let Some(bg) = style.bg.filter(|&c| c != Color::Reset) {
+        parts.push(format!("bg={:?}", bg));
+    }
+    if !style.add_modifier.is_empty() {
+        parts.push(format!("+{:?}", style.add_modifier));
+    }
+    if parts.is_empty() {
+        "default".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Write the rendered document to stdout, ignoring a closed pipe (e.g. a
+/// pager quit early)
+pub fn print_document(app: &mut App, width: u16, color: bool) -> io::Result<()> {
+    let text = render_document(app, width, color);
+    let mut stdout = io::stdout().lock();
+    match stdout
+        .write_all(text.as_bytes())
+        .and_then(|()| stdout.flush())
+    {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether a cell prints as nothing; a colored background only counts
+/// when colors are written
+fn is_blank(cell: &ratatui::buffer::Cell, color: bool) -> bool {
+    cell.symbol() == " "
+        && (!color || (cell.bg == Color::Reset && !cell.modifier.intersects(visible_on_blank())))
+}
+
+/// Modifiers that show up even on a space
+fn visible_on_blank() -> Modifier {
+    Modifier::UNDERLINED | Modifier::REVERSED | Modifier::CROSSED_OUT
+}
+
+/// Append one buffer row, dropping trailing blank cells
+fn write_row(out: &mut String, buffer: &Buffer, y: u16, color: bool) {
+    let width = buffer.area.width;
+    let end = (0..width)
+        .rposition(|x| !is_blank(&buffer[(x, y)], color))
+        .map_or(0, |last| last as u16 + 1);
+
+    let mut current = Style::default();
+    let mut skip = 0;
+    for x in 0..end {
+        // Cells covered by the previous wide character
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        let cell = &buffer[(x, y)];
+        if color {
+            let style = cell.style();
+            if style != current {
+                out.push_str(&sgr(style));
+                current = style;
+            }
+        }
+        out.push_str(cell.symbol());
+        skip = cell.symbol().width().saturating_sub(1);
+    }
+    if color && current != Style::default() {
+        out.push_str("\x1b[0m");
+    }
+    out.push('\n');
+}
+
+/// Escape sequence that resets attributes and applies `style`
+fn sgr(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    for (modifier, code) in modifiers {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    if let Some(code) = style.fg.and_then(|c| color_code(c, true)) {
+        codes.push(code);
+    }
+    if let Some(code) = style.bg.and_then(|c| color_code(c, false)) {
+        codes.push(code);
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// SGR parameters for a foreground or background color
+fn color_code(color: Color, fg: bool) -> Option<String> {
+    let base = if fg { 30 } else { 40 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => base,
+        Color::Red => base + 1,
+        Color::Green => base + 2,
+        Color::Yellow => base + 3,
+        Color::Blue => base + 4,
+        Color::Magenta => base + 5,
+        Color::Cyan => base + 6,
+        Color::Gray => base + 7,
+        Color::DarkGray => base + 60,
+        Color::LightRed => base + 61,
+        Color::LightGreen => base + 62,
+        Color::LightYellow => base + 63,
+        Color::LightBlue => base + 64,
+        Color::LightMagenta => base + 65,
+        Color::LightCyan => base + 66,
+        Color::White => base + 67,
+        Color::Indexed(i) => return Some(format!("{};5;{}", base + 8, i)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+    };
+    Some(code.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mdx_core::{Config, Document};
+
+    fn app_for(content: &str) -> (App, tempfile::NamedTempFile) {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let (doc, _warnings) = Document::load(file.path()).unwrap();
+        (App::new(Config::default(), doc, vec![]), file)
+    }
+
+    #[test]
+    fn test_plain_output_has_no_escapes() {
+        let (mut app, _file) = app_for("+++\ntitle = \"x\"\n+++\n# Title\n\nSome **bold** text\n");
+        let text = render_document(&mut app, 40, false);
+        assert!(!text.contains('\x1b'));
+        assert!(
+            !text.contains("title = "),
+            "front matter is hidden: {text:?}"
+        );
+        assert!(text.contains("Some bold text"), "{text:?}");
+        assert!(text.lines().all(|l| l == l.trim_end()));
+    }
+
+    #[test]
+    fn test_color_output_styles_text() {
+        let (mut app, _file) = app_for("Some **bold** text\n");
+        let text = render_document(&mut app, 40, true);
+        assert!(text.contains("\x1b[0;1"), "{text:?}");
+        assert!(text.trim_end().ends_with("\x1b[0m"));
+    }
+
+    #[test]
+    fn test_style_dump_marks_each_cell() {
+        let (mut app, _file) = app_for("Some **bold** text\n");
+        let dump = render_style_dump(&mut app, 40);
+        let mut lines = dump.lines();
+        assert_eq!(lines.next(), Some("Some bold text"));
+        assert_eq!(lines.next(), Some("aaaa bbbb aaaa"));
+        assert_eq!(lines.next(), Some("--"));
+        assert_eq!(lines.nth(1), Some("b: fg=LightYellow +BOLD"));
+    }
+
+    #[test]
+    fn test_comments_and_conflicts_render_as_in_the_viewer() {
+        let (mut app, _file) = app_for(
+            "# T\n\n<!-- comment\nmulti -->\n\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> b\n",
+        );
+        let text = render_document(&mut app, 40, false);
+        assert!(!text.contains("comment"), "comments are hidden: {text:?}");
+        assert!(text.contains("<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> b\n"), "{text:?}");
+    }
+
+    #[test]
+    fn test_list_items_wrap_under_their_text() {
+        let (mut app, _file) = app_for(&format!("- {}\n", "word ".repeat(12)));
+        let text = render_document(&mut app, 20, false);
+        assert!(text.lines().skip(1).all(|l| l.starts_with("  ")), "{text:?}");
+    }
+
+    #[test]
+    fn test_long_lines_wrap_to_width() {
+        let (mut app, _file) = app_for(&"word ".repeat(30));
+        let text = render_document(&mut app, 20, false);
+        assert!(text.lines().count() > 1);
+        assert!(text.lines().all(|l| l.width() <= 20));
+    }
+
+    #[test]
+    fn test_color_codes() {
+        assert_eq!(color_code(Color::Red, true).as_deref(), Some("31"));
+        assert_eq!(color_code(Color::LightBlue, false).as_deref(), Some("104"));
+        assert_eq!(
+            color_code(Color::Rgb(1, 2, 3), true).as_deref(),
+            Some("38;2;1;2;3")
+        );
+        assert_eq!(color_code(Color::Reset, true), None);
+    }
+}
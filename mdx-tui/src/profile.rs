@@ -0,0 +1,205 @@
+//! Startup profiling for `--profile-startup`, frame timing for
+//! `--profile-frames`
+//!
+//! Records how long each startup phase takes (config load, document load,
+//! app init, first draw) so regressions on large files show up as numbers
+//! rather than a vague feeling of lag. The breakdown is printed to stderr
+//! after the terminal is restored.
+
+use crate::app::App;
+use std::time::{Duration, Instant};
+
+/// Timing breakdown of the startup phases
+#[derive(Debug, Clone)]
+pub struct StartupProfile {
+    started: Instant,
+    phases: Vec<(&'static str, Duration)>,
+    first_frame: Option<Duration>,
+}
+
+impl StartupProfile {
+    /// Start the clock. Call as early as possible in `main`.
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            phases: Vec::new(),
+            first_frame: None,
+        }
+    }
+
+    /// Run `f` and record its duration under `name`
+    pub fn measure<T>(&mut self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.phases.push((name, start.elapsed()));
+        result
+    }
+
+    /// Record the end of the first draw. Only the first call counts.
+    pub fn mark_first_frame(&mut self, draw_time: Duration) {
+        if self.first_frame.is_none() {
+            self.phases.push(("first draw", draw_time));
+            self.first_frame = Some(self.started.elapsed());
+        }
+    }
+
+    /// Whether the first frame has been recorded yet
+    pub fn has_first_frame(&self) -> bool {
+        self.first_frame.is_some()
+    }
+
+    /// Recorded phases in the order they ran
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    /// Human-readable breakdown, one phase per line
+    pub fn report(&self) -> String {
+        let mut out = String::from("Startup profile:\n");
+        for (name, duration) in &self.phases {
+            out.push_str(&format!("  {:<16} {:>10.3} ms\n", name, ms(*duration)));
+        }
+        match self.first_frame {
+            Some(total) => {
+                out.push_str(&format!("  {:<16} {:>10.3} ms\n", "time to frame", ms(total)))
+            }
+            None => out.push_str("  (no frame drawn)\n"),
+        }
+        out
+    }
+}
+
+/// Draw times of a run of frames
+#[derive(Debug, Clone, Default)]
+pub struct FrameProfile {
+    frames: Vec<Duration>,
+}
+
+impl FrameProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, draw_time: Duration) {
+        self.frames.push(draw_time);
+    }
+
+    pub fn frames(&self) -> &[Duration] {
+        &self.frames
+    }
+
+    /// Human-readable summary: count, then min/median/p95/max and mean
+    pub fn report(&self) -> String {
+        let mut sorted = self.frames.clone();
+        sorted.sort();
+        let Some((&min, &max)) = sorted.first().zip(sorted.last()) else {
+            return "Frame profile: no frames drawn\n".to_string();
+        };
+        let percentile = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+        let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+
+        let mut out = format!("Frame profile ({} frames):\n", sorted.len());
+        for (name, duration) in [
+            ("min", min),
+            ("median", percentile(50)),
+            ("p95", percentile(95)),
+            ("max", max),
+            ("mean", mean),
+        ] {
+            out.push_str(&format!("  {:<16} {:>10.3} ms\n", name, ms(duration)));
+        }
+        out
+    }
+}
+
+/// Size to profile frames at: the terminal's, or 80x24 without one
+pub fn frame_size() -> (u16, u16) {
+    crossterm::terminal::size().unwrap_or((80, 24))
+}
+
+/// Draw `frames` frames of `app` off screen at `width` x `height`, paging
+/// through the document half a screen per frame and starting over at the
+/// end, and time each draw
+pub fn profile_frames(
+    app: &mut App,
+    frames: usize,
+    width: u16,
+    height: u16,
+) -> anyhow::Result<FrameProfile> {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let mut profile = FrameProfile::new();
+    for _ in 0..frames {
+        let start = Instant::now();
+        terminal.draw(|frame| crate::ui::draw(frame, app))?;
+        profile.record(start.elapsed());
+
+        let Some(viewport) = app.focused_viewport() else {
+            continue;
+        };
+        let before = app.panes.focused_pane().map(|p| p.view.cursor_line);
+        app.scroll_half_page_down(viewport.visible_height, viewport.content_width);
+        if app.panes.focused_pane().map(|p| p.view.cursor_line) == before {
+            let pane = app.panes.focused;
+            app.goto(pane, 0, crate::scroll_math::ScrollPolicy::TopQuarter);
+        }
+    }
+    Ok(profile)
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_records_phase() {
+        let mut profile = StartupProfile::start();
+        let value = profile.measure("config load", || 42);
+        assert_eq!(value, 42);
+        assert_eq!(profile.phases().len(), 1);
+        assert_eq!(profile.phases()[0].0, "config load");
+    }
+
+    #[test]
+    fn test_first_frame_recorded_once() {
+        let mut profile = StartupProfile::start();
+        profile.mark_first_frame(Duration::from_millis(3));
+        profile.mark_first_frame(Duration::from_millis(5));
+        assert!(profile.has_first_frame());
+        assert_eq!(profile.phases().len(), 1);
+        assert_eq!(profile.phases()[0].1, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn test_frame_report_percentiles() {
+        let mut profile = FrameProfile::new();
+        assert!(profile.report().contains("no frames drawn"));
+        for millis in 1..=20 {
+            profile.record(Duration::from_millis(millis));
+        }
+        let report = profile.report();
+        assert!(report.contains("(20 frames)"));
+        assert!(report.contains("median") && report.contains("10.000 ms"));
+        assert!(report.contains("p95") && report.contains("19.000 ms"));
+        assert!(report.contains("20.000 ms"));
+    }
+
+    #[test]
+    fn test_report_lists_phases() {
+        let mut profile = StartupProfile::start();
+        profile.measure("document load", || ());
+        let report = profile.report();
+        assert!(report.contains("document load"));
+        assert!(report.contains("no frame drawn"));
+
+        profile.mark_first_frame(Duration::from_millis(1));
+        let report = profile.report();
+        assert!(report.contains("first draw"));
+        assert!(report.contains("time to frame"));
+    }
+}
@@ -1,7 +1,10 @@
 //! Rendering cache and composition
 
 use mdx_core::config::ThemeVariant;
-use ratatui::text::Line;
+use mdx_core::list::ListLine;
+use mdx_core::render::RenderedLine;
+use ratatui::text::{Line, Span};
+use std::rc::Rc;
 
 /// Key for render cache
 #[derive(Hash, PartialEq, Eq, Clone, Debug)]
@@ -46,3 +49,226 @@ impl Default for RendererCache {
         Self::new()
     }
 }
+
+/// Key for the per-line styled span cache.
+///
+/// Everything that can change the spans produced for a single source line
+/// is part of the key, so a stale entry can never be returned.
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub struct StyledLineKey {
+    pub line_idx: usize,
+    pub doc_rev: u64,
+    pub theme: ThemeVariant,
+    pub use_utf8_graphics: bool,
+    pub smart_punctuation: bool,
+    pub strip_html: bool,
+    pub search_query: Option<String>,
+    pub width: u16,
+    /// Indent of the enclosing code fence, or `None` for prose lines.
+    pub code_indent: Option<usize>,
+    /// Where the line sits in a list, which picks its bullet and indent.
+    pub list: ListLine,
+}
+
+/// Number of styled lines kept around. Large enough to hold several
+/// screens across a few split panes.
+const STYLED_LINE_CACHE_CAPACITY: usize = 4096;
+
+/// LRU cache of styled spans for individual source lines.
+///
+/// `render_markdown` runs every frame (~100ms). Styling a line runs
+/// pulldown-cmark over it, so re-styling an unchanged viewport dominates
+/// idle CPU. Entries are keyed by `StyledLineKey`; the whole cache is also
+/// dropped when the document revision changes, since every entry for the
+/// old revision is dead weight.
+pub struct StyledLineCache {
+    cache: lru::LruCache<StyledLineKey, Vec<Span<'static>>>,
+    doc_rev: u64,
+}
+
+impl StyledLineCache {
+    pub fn new() -> Self {
+        Self {
+            cache: lru::LruCache::new(
+                std::num::NonZeroUsize::new(STYLED_LINE_CACHE_CAPACITY).unwrap(),
+            ),
+            doc_rev: 0,
+        }
+    }
+
+    /// Return the cached spans for `key`, computing and storing them with
+    /// `style` on a miss.
+    pub fn get_or_insert_with<F>(&mut self, key: StyledLineKey, style: F) -> Vec<Span<'static>>
+    where
+        F: FnOnce() -> Vec<Span<'static>>,
+    {
+        if key.doc_rev != self.doc_rev {
+            self.cache.clear();
+            self.doc_rev = key.doc_rev;
+        }
+        if let Some(spans) = self.cache.get(&key) {
+            return spans.clone();
+        }
+        let spans = style();
+        self.cache.put(key, spans.clone());
+        spans
+    }
+
+    /// Drop every cached line.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+impl Default for StyledLineCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Core render output per source line for `render.commonmark`, where a
+/// line's styling depends on the whole document's block structure.
+/// Rebuilt when the document revision changes.
+#[derive(Default)]
+pub struct CommonMarkLines {
+    lines: Option<(u64, Rc<Vec<Option<RenderedLine>>>)>,
+}
+
+impl CommonMarkLines {
+    /// Rendered lines of `doc`, indexed by source line (`None` for hidden
+    /// front matter)
+    pub fn get(
+        &mut self,
+        doc: &mdx_core::Document,
+        config: &mdx_core::config::RenderConfig,
+    ) -> Rc<Vec<Option<RenderedLine>>> {
+        match &self.lines {
+            Some((rev, lines)) if *rev == doc.rev => lines.clone(),
+            _ => {
+                let mut by_source = vec![None; doc.line_count()];
+                for line in mdx_core::render::render_document(doc, config) {
+                    if let Some(slot) = by_source.get_mut(line.source_line) {
+                        *slot = Some(line);
+                    }
+                }
+                let lines = Rc::new(by_source);
+                self.lines = Some((doc.rev, lines.clone()));
+                lines
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines = None;
+    }
+}
+
+/// Document statistics for the TOC's `toc.stats` column, computed the
+/// first time they are shown and again once the document changes
+#[derive(Default)]
+pub struct SectionStatsCache {
+    stats: Option<(std::path::PathBuf, u64, Rc<mdx_core::stats::DocumentStats>)>,
+}
+
+impl SectionStatsCache {
+    pub fn get(&mut self, doc: &mdx_core::Document) -> Rc<mdx_core::stats::DocumentStats> {
+        match &self.stats {
+            Some((path, rev, stats)) if *path == doc.path && *rev == doc.rev => stats.clone(),
+            _ => {
+                let stats = Rc::new(doc.stats());
+                self.stats = Some((doc.path.clone(), doc.rev, stats.clone()));
+                stats
+            }
+        }
+    }
+}
+
+/// Map a line of `mdx_core::render` output onto the theme
+pub fn styled_line(line: &RenderedLine, theme: &crate::theme::Theme) -> Line<'static> {
+    line.spans
+        .iter()
+        .map(|span| Span::styled(span.text.clone(), theme.span_style(span.style)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(line_idx: usize, doc_rev: u64, query: Option<&str>) -> StyledLineKey {
+        StyledLineKey {
+            line_idx,
+            doc_rev,
+            theme: ThemeVariant::Dark,
+            use_utf8_graphics: true,
+            smart_punctuation: false,
+            strip_html: true,
+            search_query: query.map(str::to_string),
+            width: 80,
+            code_indent: None,
+            list: ListLine::Text,
+        }
+    }
+
+    #[test]
+    fn test_styled_line_maps_semantic_styles() {
+        use ratatui::style::Modifier;
+
+        let theme = crate::theme::Theme::dark();
+        let config = mdx_core::config::RenderConfig::default();
+        let lines = mdx_core::render::render_text("## Title\n\n- **a** `b`\n", &config);
+        let heading = styled_line(&lines[0], &theme);
+        assert_eq!(heading.spans[0].content, "## ");
+        assert_eq!(heading.spans[1].style, theme.heading[1]);
+
+        let item = styled_line(&lines[2], &theme);
+        let text: Vec<_> = item.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, ["• ", "a", " ", "b"]);
+        assert_eq!(item.spans[0].style, theme.list_marker);
+        assert!(item.spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(item.spans[3].style, theme.code);
+    }
+
+    #[test]
+    fn test_styled_line_cache_hit_skips_styling() {
+        let mut cache = StyledLineCache::new();
+        let mut calls = 0;
+        for _ in 0..3 {
+            let spans = cache.get_or_insert_with(key(0, 1, None), || {
+                calls += 1;
+                vec![Span::raw("hello")]
+            });
+            assert_eq!(spans[0].content, "hello");
+        }
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_styled_line_cache_keys_on_search_query() {
+        let mut cache = StyledLineCache::new();
+        cache.get_or_insert_with(key(0, 1, None), || vec![Span::raw("a")]);
+        let spans = cache.get_or_insert_with(key(0, 1, Some("a")), || vec![Span::raw("b")]);
+        assert_eq!(spans[0].content, "b");
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_styled_line_cache_clears_on_new_revision() {
+        let mut cache = StyledLineCache::new();
+        cache.get_or_insert_with(key(0, 1, None), || vec![Span::raw("old")]);
+        cache.get_or_insert_with(key(1, 1, None), || vec![Span::raw("old")]);
+        assert_eq!(cache.len(), 2);
+
+        let spans = cache.get_or_insert_with(key(0, 2, None), || vec![Span::raw("new")]);
+        assert_eq!(spans[0].content, "new");
+        assert_eq!(cache.len(), 1);
+    }
+}
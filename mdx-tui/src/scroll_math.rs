@@ -145,6 +145,7 @@ pub enum ScrollPolicy {
 
 /// Compute the new scroll position after moving the cursor to
 /// `new_cursor`, given the previous cursor/scroll and the policy.
+#[allow(clippy::too_many_arguments)]
 pub fn scroll_for_policy(
     new_cursor: usize,
     prev_cursor: usize,
@@ -225,6 +226,32 @@ pub fn auto_scroll_to_cursor(
     clamp_scroll(new_scroll, bounds_lo, bounds_hi, line_count, visible_height)
 }
 
+/// Like [`auto_scroll_to_cursor`], but keeping `margin` lines of context
+/// above and below the cursor (`render.scrolloff`). The margin shrinks to
+/// fit small viewports, and below the cursor stops at the last line so the
+/// view doesn't scroll past the end to make room for it.
+pub fn scroll_with_margin(
+    cursor_line: usize,
+    scroll_line: usize,
+    visible_height: usize,
+    margin: usize,
+    line_count: usize,
+) -> usize {
+    if visible_height == 0 {
+        return scroll_line;
+    }
+    let margin = margin.min(visible_height.saturating_sub(1) / 2);
+    if cursor_line < scroll_line + margin {
+        cursor_line.saturating_sub(margin)
+    } else if cursor_line + margin >= scroll_line + visible_height {
+        let last = line_count.saturating_sub(1).max(cursor_line);
+        let bottom = (cursor_line + margin).min(last);
+        (bottom + 1).saturating_sub(visible_height)
+    } else {
+        scroll_line
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -432,6 +459,34 @@ mod tests {
         assert_eq!(auto_scroll_to_cursor(99, 0, 20, 0, 99, 100), 80);
     }
 
+    // --- scroll_with_margin -------------------------------------------
+
+    #[test]
+    fn margin_zero_matches_nearest_edge() {
+        assert_eq!(scroll_with_margin(5, 30, 20, 0, 100), 5);
+        assert_eq!(scroll_with_margin(80, 30, 20, 0, 100), 61);
+        assert_eq!(scroll_with_margin(35, 30, 20, 0, 100), 30);
+    }
+
+    #[test]
+    fn margin_keeps_context_around_cursor() {
+        // Moving down to row 47 of [30, 49] leaves fewer than 3 lines below
+        assert_eq!(scroll_with_margin(47, 30, 20, 3, 100), 31);
+        assert_eq!(scroll_with_margin(32, 30, 20, 3, 100), 29);
+        assert_eq!(scroll_with_margin(40, 30, 20, 3, 100), 30);
+        // Near the top of the document there is nothing above to show
+        assert_eq!(scroll_with_margin(1, 0, 20, 3, 100), 0);
+    }
+
+    #[test]
+    fn margin_stops_at_document_end_and_shrinks() {
+        // The last line can sit at the bottom row
+        assert_eq!(scroll_with_margin(99, 70, 20, 3, 100), 80);
+        assert_eq!(scroll_with_margin(98, 80, 20, 3, 100), 80);
+        // A 5-row pane keeps at most 2 lines each side
+        assert_eq!(scroll_with_margin(20, 10, 5, 10, 100), 18);
+    }
+
     // --- scroll_for_policy -----------------------------------------------
 
     #[test]
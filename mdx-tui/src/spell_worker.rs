@@ -0,0 +1,140 @@
+//! Background spell checking
+//!
+//! Loading a hunspell dictionary and scanning a long document both take
+//! long enough to stall a frame, so they run on a worker thread.
+
+use crate::worker_pool::{Queue, QueueOptions, Task, WorkerPool};
+use mdx_core::spell::{Dictionary, WordRef};
+use ropey::Rope;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Request to spell check a document revision
+#[derive(Debug, Clone)]
+pub struct SpellCheckRequest {
+    pub rev: u64,
+    pub rope: Rope,
+    /// Dictionary `.dic` file to load
+    pub dictionary: PathBuf,
+    /// Extra words to accept
+    pub words: Vec<String>,
+}
+
+/// Misspellings found for a document revision
+#[derive(Debug, Clone)]
+pub struct SpellCheckResult {
+    pub rev: u64,
+    /// The loaded dictionary, for suggestions, and the misspelled words,
+    /// or a description of why the dictionary could not be loaded
+    pub outcome: Result<(Arc<Dictionary>, Vec<WordRef>), String>,
+}
+
+impl Task for SpellCheckRequest {
+    fn rev(&self) -> u64 {
+        self.rev
+    }
+}
+
+/// Spell checker handle
+pub struct SpellWorker {
+    queue: Queue<SpellCheckRequest, SpellCheckResult>,
+}
+
+impl SpellWorker {
+    /// Spell checker running its checks on `pool`
+    pub fn new(pool: &WorkerPool) -> Self {
+        let options = QueueOptions {
+            limit: 1,
+            debounce: Duration::ZERO,
+        };
+        Self {
+            queue: Queue::new(pool, options, check_spelling),
+        }
+    }
+
+    /// Queue a spell check
+    pub fn request_check(&self, req: SpellCheckRequest) {
+        self.queue.submit(req);
+    }
+
+    /// Try to receive a check result (non-blocking)
+    pub fn try_recv_result(&self) -> Option<SpellCheckResult> {
+        self.queue.try_recv()
+    }
+}
+
+/// A handler spell checking the document of a request
+fn check_spelling() -> impl FnMut(SpellCheckRequest) -> Option<SpellCheckResult> {
+    // The dictionary is reloaded only when its path or word list changes
+    let mut loaded: Option<(PathBuf, Vec<String>, Arc<Dictionary>)> = None;
+
+    move |req| {
+        let cached = loaded
+            .as_ref()
+            .filter(|(path, words, _)| *path == req.dictionary && *words == req.words)
+            .map(|(_, _, dict)| Arc::clone(dict));
+        let dict = match cached {
+            Some(dict) => Ok(dict),
+            None => Dictionary::load(&req.dictionary)
+                .map(|mut dict| {
+                    for word in &req.words {
+                        dict.add_word(word);
+                    }
+                    let dict = Arc::new(dict);
+                    loaded = Some((req.dictionary.clone(), req.words.clone(), Arc::clone(&dict)));
+                    dict
+                })
+                .map_err(|e| e.to_string()),
+        };
+
+        let outcome = dict.map(|dict| {
+            let misspellings = mdx_core::spell::find_misspellings(&req.rope, &dict);
+            (dict, misspellings)
+        });
+        Some(SpellCheckResult {
+            rev: req.rev,
+            outcome,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_reports_misspellings() {
+        let dir = tempfile::tempdir().unwrap();
+        let dic = dir.path().join("test.dic");
+        std::fs::write(&dic, "2\nhello\nworld\n").unwrap();
+
+        let worker = SpellWorker::new(&WorkerPool::spawn());
+        worker.request_check(SpellCheckRequest {
+            rev: 7,
+            rope: Rope::from("Hello wrld, mdx world\n"),
+            dictionary: dic,
+            words: vec!["mdx".to_string()],
+        });
+
+        let result = worker.queue.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(result.rev, 7);
+        let (_dict, misspellings) = result.outcome.unwrap();
+        let words: Vec<_> = misspellings.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(words, vec!["wrld"]);
+    }
+
+    #[test]
+    fn test_worker_reports_missing_dictionary() {
+        let worker = SpellWorker::new(&WorkerPool::spawn());
+        worker.request_check(SpellCheckRequest {
+            rev: 1,
+            rope: Rope::from("text\n"),
+            dictionary: PathBuf::from("/nonexistent/xx.dic"),
+            words: Vec::new(),
+        });
+
+        let result = worker.queue.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert!(result.outcome.is_err());
+    }
+}
@@ -0,0 +1,93 @@
+//! Status bar templates (`[statusbar]` in the config)
+//!
+//! Each side of the status bar is a template with `{token}` placeholders.
+//! The values come from the UI; this module only expands and lays out.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Replace each `{token}` in `template` with `value(token)`. Unknown tokens
+/// are kept as written. A token that expands to nothing also drops the
+/// spaces before it, so optional indicators don't leave gaps.
+pub fn expand(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let token = &after[..close];
+        match value(token) {
+            Some(text) if text.is_empty() => {
+                let kept = out.trim_end_matches(' ').len();
+                out.truncate(kept);
+            }
+            Some(text) => out.push_str(&text),
+            None => out.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A `width`-column line with `left` at the start and `right` at the end.
+/// `left` is cut short when both don't fit; `right` is dropped when it
+/// doesn't fit by itself.
+pub fn layout(left: &str, right: &str, width: usize) -> String {
+    let right = if right.width() <= width { right } else { "" };
+    let room = width - right.width();
+    let left = if left.width() <= room {
+        left.to_string()
+    } else if room == 0 {
+        String::new()
+    } else {
+        // Mark the cut with an ellipsis
+        let mut used = 0;
+        let mut cut: String = left
+            .chars()
+            .take_while(|&ch| {
+                used += UnicodeWidthChar::width(ch).unwrap_or(0);
+                used < room
+            })
+            .collect();
+        cut.push('…');
+        cut
+    };
+    let pad = room.saturating_sub(left.width());
+    format!("{}{}{}", left, " ".repeat(pad), right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(token: &str) -> Option<String> {
+        match token {
+            "file" => Some("notes.md".to_string()),
+            "modified" | "search" => Some(String::new()),
+            "line" => Some("12".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_tokens() {
+        assert_eq!(
+            expand(" {file}{modified}  {search} L{line}", values),
+            " notes.md L12"
+        );
+        // Unknown tokens and stray braces stay as written
+        assert_eq!(expand("{nope} {file", values), "{nope} {file");
+    }
+
+    #[test]
+    fn test_layout_aligns_and_truncates() {
+        assert_eq!(layout("left", "right", 12), "left   right");
+        assert_eq!(layout("a long left part", "right", 12), "a long…right");
+        // The right side is dropped when it can't fit at all
+        assert_eq!(layout("ab", "too wide for it", 6), "ab    ");
+    }
+}
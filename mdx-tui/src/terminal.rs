@@ -2,21 +2,61 @@
 
 use anyhow::{Context, Result};
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
 
+/// Whether `init` asks for the kitty keyboard protocol (`input.enhanced_keys`)
+static REQUEST_ENHANCED_KEYS: AtomicBool = AtomicBool::new(true);
+
+/// Whether the terminal took the protocol, so `restore` pops it again
+static ENHANCED_KEYS: AtomicBool = AtomicBool::new(false);
+
+/// Set whether later `init` calls ask for the kitty keyboard protocol
+pub fn request_enhanced_keys(enabled: bool) {
+    REQUEST_ENHANCED_KEYS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether keys are currently reported with the kitty keyboard protocol.
+/// Without it Ctrl+I arrives as Tab and Shift+Enter as Enter.
+pub fn enhanced_keys() -> bool {
+    ENHANCED_KEYS.load(Ordering::Relaxed)
+}
+
+/// Whether the terminal advertises 24-bit color (`COLORTERM=truecolor`).
+/// Without it, colors that have a 256-color stand-in use that instead.
+pub fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| matches!(value.as_str(), "truecolor" | "24bit"))
+}
+
 /// Initialize the terminal
 pub fn init() -> Result<Tui> {
     enable_raw_mode().context("Failed to enable raw mode")?;
     execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
         .context("Failed to enter alternate screen")?;
 
+    // The query needs raw mode; terminals that don't answer get legacy keys
+    if REQUEST_ENHANCED_KEYS.load(Ordering::Relaxed)
+        && crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false)
+    {
+        execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )
+        .context("Failed to enable enhanced keys")?;
+        ENHANCED_KEYS.store(true, Ordering::Relaxed);
+        tracing::debug!("Kitty keyboard protocol enabled");
+    }
+
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend).context("Failed to create terminal")?;
 
@@ -25,8 +65,34 @@ pub fn init() -> Result<Tui> {
 
 /// Restore the terminal to its original state
 pub fn restore() -> Result<()> {
+    if ENHANCED_KEYS.swap(false, Ordering::Relaxed) {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)
+            .context("Failed to disable enhanced keys")?;
+    }
     disable_raw_mode().context("Failed to disable raw mode")?;
     execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)
         .context("Failed to leave alternate screen")?;
     Ok(())
 }
+
+/// Flag raised when the process is sent SIGTSTP from outside. In raw mode
+/// the terminal delivers Ctrl+Z as a key rather than as this signal.
+#[cfg(unix)]
+pub fn suspend_signal() -> Result<std::sync::Arc<std::sync::atomic::AtomicBool>> {
+    let flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGTSTP, flag.clone())
+        .context("Failed to watch for SIGTSTP")?;
+    Ok(flag)
+}
+
+/// Stop like a shell job: hand the terminal back to the shell, stop until
+/// `fg` sends SIGCONT, then take the terminal over again
+#[cfg(unix)]
+pub fn suspend(terminal: &mut Tui) -> Result<()> {
+    restore().context("Failed to restore terminal for suspend")?;
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)
+        .context("Failed to suspend")?;
+    *terminal = init().context("Failed to reinitialize terminal after resume")?;
+    terminal.clear().context("Failed to clear terminal after resume")?;
+    Ok(())
+}
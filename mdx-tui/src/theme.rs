@@ -1,6 +1,7 @@
-//! Theme system for dark/light modes
+//! Built-in themes: dark, light, and accessible variants
 
 use mdx_core::config::ThemeVariant;
+use mdx_core::render::{Role, SpanStyle};
 use ratatui::style::{Color, Modifier, Style};
 
 /// Theme definition
@@ -10,6 +11,11 @@ pub struct Theme {
     pub heading: [Style; 6],
     pub code: Style,
     pub code_block_bg: Color,
+    /// Stand-in for `code_block_bg` on terminals without 24-bit color,
+    /// which would otherwise approximate it unpredictably
+    pub code_block_bg_256: Color,
+    /// Columns of code background between the gutter and the code
+    pub code_padding: usize,
     pub link: Style,
     pub quote: Style,
     pub list_marker: Style,
@@ -21,6 +27,10 @@ pub struct Theme {
     pub status_bar_bg: Color,
     pub collapsed_block_bg: Color,
     pub collapsed_indicator_fg: Color,
+    /// Background of our side of a merge conflict (`<<<<<<<` to `=======`)
+    pub conflict_ours_bg: Color,
+    /// Background of their side (`=======` to `>>>>>>>`)
+    pub conflict_theirs_bg: Color,
     pub border: Color,
     pub border_focused: Color,
     pub scrollbar_track: Color,
@@ -41,9 +51,28 @@ impl Theme {
         match variant {
             ThemeVariant::Dark => Self::dark(),
             ThemeVariant::Light => Self::light(),
+            ThemeVariant::HighContrast => Self::high_contrast(),
+            ThemeVariant::Solarized => Self::solarized(),
+            ThemeVariant::Monochrome => Self::monochrome(),
+            ThemeVariant::DeuteranopiaSafe => Self::deuteranopia_safe(),
         }
     }
 
+    /// The theme for `variant` as configured: `render.code_padding`, and
+    /// the 256-color code background unless the terminal has 24-bit color
+    pub fn for_config(
+        variant: ThemeVariant,
+        render: &mdx_core::config::RenderConfig,
+        truecolor: bool,
+    ) -> Self {
+        let mut theme = Self::for_variant(variant);
+        theme.code_padding = render.code_padding;
+        if !truecolor {
+            theme.code_block_bg = theme.code_block_bg_256;
+        }
+        theme
+    }
+
     /// Dark theme — Cyberpunk neon palette
     ///
     /// Near-black background with high-saturation neon accents. The
@@ -84,6 +113,8 @@ impl Theme {
             ],
             code: Style::default().fg(Color::Rgb(255, 210, 0)), // neon amber / gold
             code_block_bg: Color::Rgb(5, 5, 20),                // deeper blue-black for blocks
+            code_block_bg_256: Color::Indexed(233),             // #121212
+            code_padding: 1,
             link: Style::default()
                 .fg(Color::Rgb(0, 180, 255)) // electric sky-blue
                 .add_modifier(Modifier::UNDERLINED),
@@ -102,6 +133,8 @@ impl Theme {
             status_bar_bg: Color::Rgb(0, 229, 255), // electric cyan bar
             collapsed_block_bg: Color::Rgb(15, 15, 35),
             collapsed_indicator_fg: Color::Rgb(0, 229, 255),
+            conflict_ours_bg: Color::Rgb(10, 42, 28),
+            conflict_theirs_bg: Color::Rgb(12, 26, 60),
             border: Color::Rgb(40, 40, 80),
             border_focused: Color::Rgb(255, 45, 120), // hot pink focused border
             scrollbar_track: Color::Rgb(25, 25, 50),
@@ -158,6 +191,8 @@ impl Theme {
                 .fg(Color::Rgb(175, 30, 60)) // deep crimson — visible on white
                 .bg(Color::Rgb(255, 245, 248)), // faint pink tint for inline code
             code_block_bg: Color::Rgb(245, 247, 250), // very light blue-gray for blocks
+            code_block_bg_256: Color::Indexed(255),   // #eeeeee
+            code_padding: 1,
             link: Style::default()
                 .fg(Color::Rgb(3, 102, 214))
                 .add_modifier(Modifier::UNDERLINED),
@@ -176,6 +211,8 @@ impl Theme {
             status_bar_bg: Color::Rgb(36, 41, 46), // dark status bar keeps the contrast frame
             collapsed_block_bg: Color::Rgb(232, 238, 245),
             collapsed_indicator_fg: Color::Rgb(0, 112, 120),
+            conflict_ours_bg: Color::Rgb(222, 245, 228),
+            conflict_theirs_bg: Color::Rgb(222, 234, 252),
             border: Color::Rgb(208, 215, 222),
             border_focused: Color::Rgb(3, 102, 214),
             scrollbar_track: Color::Rgb(220, 228, 236),
@@ -190,4 +227,265 @@ impl Theme {
             diff_mod: Style::default().fg(Color::Rgb(210, 90, 0)),
         }
     }
+
+    /// High-contrast theme — white on pure black
+    ///
+    /// Every foreground is either white or a fully saturated bright color,
+    /// and the focus cues (cursor line, TOC entry, status bar) invert
+    /// rather than relying on subtle tints.
+    pub fn high_contrast() -> Self {
+        let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        Self {
+            base: Style::default().fg(Color::Rgb(255, 255, 255)).bg(Color::Rgb(0, 0, 0)),
+            heading: [
+                bold(Color::Rgb(255, 255, 0)).add_modifier(Modifier::UNDERLINED),
+                bold(Color::Rgb(0, 255, 255)),
+                bold(Color::Rgb(255, 255, 255)),
+                bold(Color::Rgb(255, 255, 0)),
+                bold(Color::Rgb(0, 255, 255)),
+                bold(Color::Rgb(255, 255, 255)),
+            ],
+            code: Style::default().fg(Color::Rgb(0, 255, 0)),
+            code_block_bg: Color::Rgb(0, 0, 0),
+            code_block_bg_256: Color::Indexed(16), // #000000
+            code_padding: 1,
+            link: bold(Color::Rgb(0, 255, 255)).add_modifier(Modifier::UNDERLINED),
+            quote: Style::default()
+                .fg(Color::Rgb(255, 255, 255))
+                .add_modifier(Modifier::ITALIC),
+            list_marker: bold(Color::Rgb(255, 255, 0)),
+            toc_bg: Color::Rgb(0, 0, 0),
+            toc_border: Color::Rgb(255, 255, 255),
+            toc_active: bold(Color::Rgb(0, 0, 0)).bg(Color::Rgb(255, 255, 0)),
+            cursor_line_bg: Color::Rgb(40, 40, 40),
+            status_bar_fg: Color::Rgb(0, 0, 0),
+            status_bar_bg: Color::Rgb(255, 255, 255),
+            collapsed_block_bg: Color::Rgb(0, 0, 0),
+            collapsed_indicator_fg: Color::Rgb(255, 255, 0),
+            conflict_ours_bg: Color::Rgb(0, 70, 0),
+            conflict_theirs_bg: Color::Rgb(0, 0, 110),
+            border: Color::Rgb(255, 255, 255),
+            border_focused: Color::Rgb(255, 255, 0),
+            scrollbar_track: Color::Rgb(60, 60, 60),
+            scrollbar_track_unfocused: Color::Rgb(30, 30, 30),
+            scrollbar_thumb: Color::Rgb(255, 255, 0),
+            scrollbar_thumb_unfocused: Color::Rgb(200, 200, 200),
+            #[cfg(feature = "git")]
+            diff_add: bold(Color::Rgb(0, 255, 255)),
+            #[cfg(feature = "git")]
+            diff_del: bold(Color::Rgb(255, 0, 255)),
+            #[cfg(feature = "git")]
+            diff_mod: bold(Color::Rgb(255, 255, 0)),
+        }
+    }
+
+    /// Solarized Dark — Ethan Schoonover's palette
+    pub fn solarized() -> Self {
+        const BASE03: Color = Color::Rgb(0, 43, 54);
+        const BASE02: Color = Color::Rgb(7, 54, 66);
+        const BASE01: Color = Color::Rgb(88, 110, 117);
+        const BASE0: Color = Color::Rgb(131, 148, 150);
+        const BASE1: Color = Color::Rgb(147, 161, 161);
+        const YELLOW: Color = Color::Rgb(181, 137, 0);
+        const ORANGE: Color = Color::Rgb(203, 75, 22);
+        const RED: Color = Color::Rgb(220, 50, 47);
+        const MAGENTA: Color = Color::Rgb(211, 54, 130);
+        const VIOLET: Color = Color::Rgb(108, 113, 196);
+        const BLUE: Color = Color::Rgb(38, 139, 210);
+        const CYAN: Color = Color::Rgb(42, 161, 152);
+        const GREEN: Color = Color::Rgb(133, 153, 0);
+        let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        Self {
+            base: Style::default().fg(BASE0).bg(BASE03),
+            heading: [
+                bold(ORANGE),
+                bold(BLUE),
+                bold(CYAN),
+                bold(VIOLET),
+                bold(MAGENTA),
+                bold(BASE1),
+            ],
+            code: Style::default().fg(YELLOW).bg(BASE02),
+            code_block_bg: BASE02,
+            code_block_bg_256: Color::Indexed(235), // #262626
+            code_padding: 1,
+            link: Style::default().fg(BLUE).add_modifier(Modifier::UNDERLINED),
+            quote: Style::default().fg(BASE01).add_modifier(Modifier::ITALIC),
+            list_marker: Style::default().fg(CYAN),
+            toc_bg: BASE03,
+            toc_border: BASE01,
+            toc_active: bold(BASE03).bg(BLUE),
+            cursor_line_bg: BASE02,
+            status_bar_fg: BASE03,
+            status_bar_bg: BASE1,
+            collapsed_block_bg: BASE02,
+            collapsed_indicator_fg: CYAN,
+            conflict_ours_bg: Color::Rgb(22, 62, 38),
+            conflict_theirs_bg: Color::Rgb(10, 50, 92),
+            border: BASE01,
+            border_focused: BLUE,
+            scrollbar_track: BASE02,
+            scrollbar_track_unfocused: BASE03,
+            scrollbar_thumb: BLUE,
+            scrollbar_thumb_unfocused: BASE01,
+            #[cfg(feature = "git")]
+            diff_add: Style::default().fg(GREEN),
+            #[cfg(feature = "git")]
+            diff_del: Style::default().fg(RED),
+            #[cfg(feature = "git")]
+            diff_mod: Style::default().fg(YELLOW),
+        }
+    }
+
+    /// Monochrome theme — grays only
+    ///
+    /// Headings, links, and code are told apart by weight, underline,
+    /// italics, and brightness, so nothing depends on seeing hue.
+    pub fn monochrome() -> Self {
+        let gray = |level| Color::Rgb(level, level, level);
+        let bold = |level| Style::default().fg(gray(level)).add_modifier(Modifier::BOLD);
+        Self {
+            base: Style::default().fg(gray(208)).bg(gray(16)),
+            heading: [
+                bold(255).add_modifier(Modifier::UNDERLINED),
+                bold(255),
+                bold(230),
+                bold(210).add_modifier(Modifier::ITALIC),
+                bold(190),
+                bold(170),
+            ],
+            code: Style::default().fg(gray(235)).bg(gray(45)),
+            code_block_bg: gray(30),
+            code_block_bg_256: Color::Indexed(234), // #1c1c1c
+            code_padding: 1,
+            link: Style::default().fg(gray(240)).add_modifier(Modifier::UNDERLINED),
+            quote: Style::default().fg(gray(150)).add_modifier(Modifier::ITALIC),
+            list_marker: bold(240),
+            toc_bg: gray(20),
+            toc_border: gray(90),
+            toc_active: bold(16).bg(gray(220)),
+            cursor_line_bg: gray(45),
+            status_bar_fg: gray(16),
+            status_bar_bg: gray(200),
+            collapsed_block_bg: gray(35),
+            collapsed_indicator_fg: gray(220),
+            conflict_ours_bg: gray(40),
+            conflict_theirs_bg: gray(62),
+            border: gray(80),
+            border_focused: gray(230),
+            scrollbar_track: gray(40),
+            scrollbar_track_unfocused: gray(28),
+            scrollbar_thumb: gray(200),
+            scrollbar_thumb_unfocused: gray(110),
+            #[cfg(feature = "git")]
+            diff_add: bold(255),
+            #[cfg(feature = "git")]
+            diff_del: bold(255).add_modifier(Modifier::REVERSED),
+            #[cfg(feature = "git")]
+            diff_mod: Style::default().fg(gray(150)),
+        }
+    }
+
+    /// Deuteranopia-safe theme — the Okabe–Ito palette on a dark background
+    ///
+    /// Red and green are the worst pairing for the most common forms of
+    /// colorblindness, so the diff gutter uses blue for added lines and
+    /// orange for deleted ones, with yellow for modified lines.
+    pub fn deuteranopia_safe() -> Self {
+        const ORANGE: Color = Color::Rgb(230, 159, 0);
+        const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+        const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+        const YELLOW: Color = Color::Rgb(240, 228, 66);
+        const VERMILLION: Color = Color::Rgb(213, 94, 0);
+        const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+        let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        Self {
+            base: Style::default()
+                .fg(Color::Rgb(225, 225, 230))
+                .bg(Color::Rgb(18, 18, 24)),
+            heading: [
+                bold(ORANGE),
+                bold(SKY_BLUE),
+                bold(YELLOW),
+                bold(REDDISH_PURPLE),
+                bold(BLUISH_GREEN),
+                bold(Color::Rgb(200, 200, 200)),
+            ],
+            code: Style::default().fg(YELLOW),
+            code_block_bg: Color::Rgb(28, 28, 38),
+            code_block_bg_256: Color::Indexed(234), // #1c1c1c
+            code_padding: 1,
+            link: Style::default().fg(SKY_BLUE).add_modifier(Modifier::UNDERLINED),
+            quote: Style::default()
+                .fg(Color::Rgb(160, 160, 175))
+                .add_modifier(Modifier::ITALIC),
+            list_marker: Style::default().fg(ORANGE),
+            toc_bg: Color::Rgb(16, 16, 22),
+            toc_border: Color::Rgb(70, 70, 90),
+            toc_active: bold(Color::Rgb(18, 18, 24)).bg(SKY_BLUE),
+            cursor_line_bg: Color::Rgb(35, 38, 55),
+            status_bar_fg: Color::Rgb(18, 18, 24),
+            status_bar_bg: SKY_BLUE,
+            collapsed_block_bg: Color::Rgb(28, 28, 40),
+            collapsed_indicator_fg: SKY_BLUE,
+            conflict_ours_bg: Color::Rgb(18, 44, 66),
+            conflict_theirs_bg: Color::Rgb(62, 40, 12),
+            border: Color::Rgb(70, 70, 90),
+            border_focused: ORANGE,
+            scrollbar_track: Color::Rgb(35, 35, 50),
+            scrollbar_track_unfocused: Color::Rgb(26, 26, 36),
+            scrollbar_thumb: ORANGE,
+            scrollbar_thumb_unfocused: Color::Rgb(100, 100, 125),
+            #[cfg(feature = "git")]
+            diff_add: Style::default().fg(SKY_BLUE),
+            #[cfg(feature = "git")]
+            diff_del: Style::default().fg(VERMILLION),
+            #[cfg(feature = "git")]
+            diff_mod: Style::default().fg(YELLOW),
+        }
+    }
+
+    /// The style for a span of `mdx_core::render` output
+    pub fn span_style(&self, style: SpanStyle) -> Style {
+        let base = match style.role {
+            Role::Text => self.base,
+            Role::Heading(level) => self.heading[usize::from(level.clamp(1, 6)) - 1],
+            Role::InlineCode => return self.code,
+            Role::CodeBlock | Role::Fence => self.base.bg(self.code_block_bg),
+            Role::ListMarker => self.list_marker,
+            Role::TableBorder => Style::default().fg(Color::Cyan),
+            Role::Rule | Role::DefinitionMarker => Style::default().fg(Color::DarkGray),
+            Role::QuoteMarker => self.quote,
+            Role::DefinitionTerm => self.base.add_modifier(Modifier::BOLD),
+            Role::Comment => return COMMENT_STYLE,
+        };
+        emphasize(base, style)
+    }
+}
+
+/// HTML comments shown with `C`, dimmed so they read as notes
+pub const COMMENT_STYLE: Style = Style::new()
+    .fg(Color::DarkGray)
+    .add_modifier(Modifier::ITALIC);
+
+/// Apply a span's strong, emphasis, and strikethrough flags on top of
+/// `base`, and underline URLs and paths written as plain text
+pub fn emphasize(base: Style, style: SpanStyle) -> Style {
+    let mut out = base;
+    if style.strong {
+        // Bright yellow stands out from every theme's body text
+        out = Style::default()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::BOLD);
+    }
+    if style.emphasis {
+        out = out.add_modifier(Modifier::ITALIC);
+    }
+    if style.strikethrough {
+        out = out.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if style.autolink {
+        out = out.add_modifier(Modifier::UNDERLINED);
+    }
+    out
 }
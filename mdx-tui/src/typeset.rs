@@ -0,0 +1,163 @@
+//! Hyphenation and full justification of wrapped prose
+//!
+//! Both are off by default and apply to paragraph rows only, never to code,
+//! tables, or headings. `render.hyphenate` lets the wrapper break a long
+//! word at a syllable boundary instead of pushing the whole word to the
+//! next row; it needs the `hyphenation` build feature, which embeds the
+//! US English patterns. `render.justify` widens the gaps between words so
+//! every row but the last of a paragraph line reaches the right edge.
+
+use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Where to break `word` so the head plus a hyphen fits in `room` columns.
+/// Returns the byte length of the head, or `None` when no syllable break
+/// fits (or hyphenation isn't built in).
+pub fn hyphen_break(word: &str, room: usize) -> Option<usize> {
+    #[cfg(feature = "hyphenation")]
+    {
+        use hyphenation::Hyphenator;
+
+        let breaks = dictionary()?.hyphenate(word).breaks;
+        breaks
+            .into_iter()
+            .rev()
+            .find(|&at| word[..at].width() < room)
+    }
+    #[cfg(not(feature = "hyphenation"))]
+    {
+        let _ = (word, room);
+        None
+    }
+}
+
+#[cfg(feature = "hyphenation")]
+fn dictionary() -> Option<&'static hyphenation::Standard> {
+    use hyphenation::{Language, Load, Standard};
+    use std::sync::OnceLock;
+
+    static DICTIONARY: OnceLock<Option<Standard>> = OnceLock::new();
+    DICTIONARY
+        .get_or_init(|| Standard::from_embedded(Language::EnglishUS).ok())
+        .as_ref()
+}
+
+/// Widen the gaps between words in `row` so it fills `width` columns. The
+/// first `skip` columns (line number, gutter, list marker) and any spaces
+/// right after them are left alone, as are rows without a gap to widen.
+pub fn justify_row(row: Line<'static>, skip: usize, width: usize) -> Line<'static> {
+    // Trailing spaces don't count as text that has to reach the edge
+    let content: String = row.spans.iter().map(|span| span.content.as_ref()).collect();
+    let text_width = content.trim_end().width();
+    if text_width >= width {
+        return row;
+    }
+
+    // Column of each gap between two words, past the skipped prefix
+    let mut gaps = Vec::new();
+    let mut column = 0;
+    let mut seen_word = false;
+    let mut in_gap = false;
+    for span in &row.spans {
+        for ch in span.content.chars() {
+            if column >= skip && column < text_width {
+                if ch == ' ' {
+                    if seen_word && !in_gap {
+                        gaps.push(column);
+                    }
+                    in_gap = true;
+                } else {
+                    seen_word = true;
+                    in_gap = false;
+                }
+            }
+            column += ch.width().unwrap_or(0);
+        }
+    }
+    if gaps.is_empty() {
+        return row;
+    }
+
+    // Spread the extra columns evenly, the leftmost gaps taking the rest
+    let extra = width - text_width;
+    let per_gap = extra / gaps.len();
+    let remainder = extra % gaps.len();
+    let widen = |gap: usize| per_gap + usize::from(gap < remainder);
+
+    let style = row.style;
+    let alignment = row.alignment;
+    let mut spans = Vec::with_capacity(row.spans.len());
+    let mut column = 0;
+    let mut next_gap = 0;
+    for span in row.spans {
+        if column >= text_width {
+            break;
+        }
+        let mut text = String::with_capacity(span.content.len());
+        for ch in span.content.chars() {
+            if column >= text_width {
+                break;
+            }
+            if gaps.get(next_gap) == Some(&column) {
+                text.push_str(&" ".repeat(widen(next_gap)));
+                next_gap += 1;
+            }
+            text.push(ch);
+            column += ch.width().unwrap_or(0);
+        }
+        spans.push(Span::styled(text, span.style));
+    }
+    let mut line = Line::from(spans).style(style);
+    line.alignment = alignment;
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Style};
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_justify_spreads_gaps_from_the_left() {
+        let row = Line::from("  1 a bb ccc");
+        let justified = justify_row(row, 4, 16);
+        assert_eq!(text(&justified), "  1 a   bb   ccc");
+    }
+
+    #[test]
+    fn test_justify_keeps_styles_and_drops_trailing_space() {
+        let bold = Style::default().fg(Color::Red);
+        let row = Line::from(vec![
+            Span::raw("one "),
+            Span::styled("two", bold),
+            Span::raw(" "),
+        ]);
+        let justified = justify_row(row, 0, 9);
+        assert_eq!(text(&justified), "one   two");
+        assert_eq!(justified.spans[1].style, bold);
+    }
+
+    #[test]
+    fn test_justify_leaves_single_words_alone() {
+        let row = Line::from("    indented");
+        assert_eq!(text(&justify_row(row, 0, 20)), "    indented");
+    }
+
+    #[cfg(feature = "hyphenation")]
+    #[test]
+    fn test_hyphen_break_fits_room() {
+        // hy-phen-a-tion
+        assert_eq!(hyphen_break("hyphenation", 8), Some(7));
+        assert_eq!(hyphen_break("hyphenation", 7), Some(6));
+        assert_eq!(hyphen_break("hyphenation", 4), Some(2));
+        assert_eq!(hyphen_break("hyphenation", 2), None);
+        assert_eq!(hyphen_break("the", 10), None);
+    }
+}
@@ -2,6 +2,11 @@
 
 use crate::app::App;
 use crate::collapse::{self, CollapseRange};
+use mdx_core::config::{CodeLabel, LineNumbers, MarkdownFlavor};
+use mdx_core::conflict::ConflictSide;
+use mdx_core::fence::{Fence, FenceLine};
+use mdx_core::list::{ListLine, ListMarker, ListTracker};
+use mdx_core::render::{definition_content, Role, LIST_BULLETS};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
@@ -9,37 +14,89 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Draw the UI
 pub fn draw(frame: &mut Frame, app: &mut App) {
+    if let Some(error) = &app.load_error {
+        render_load_error(frame, app, error);
+        return;
+    }
+
+    #[cfg(feature = "git")]
+    if app.diff_view.is_some() {
+        render_diff_view(frame, app);
+        return;
+    }
+
+    if app.presentation.is_some() {
+        render_presentation(frame, app);
+        return;
+    }
+
+    // Focus mode hides the status bar unless it has something to say
+    let status_height = if app.focus_mode && !app.status_bar_needed() {
+        0
+    } else {
+        1
+    };
+
     // Create base layout with optional security warnings pane
     let base_chunks = if !app.security_warnings.is_empty() && app.show_security_warnings {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(1),    // Main content area (TOC + panes)
-                Constraint::Length(4), // Security warnings pane
-                Constraint::Length(1), // Status bar
+                Constraint::Min(1),                // Main content area (TOC + panes)
+                Constraint::Length(4),             // Security warnings pane
+                Constraint::Length(status_height), // Status bar
             ])
             .split(frame.area());
 
         // Render security warnings pane
-        render_security_warnings(frame, chunks[1], &app.security_warnings, &app.theme);
+        render_security_warnings(frame, chunks[1], app);
 
         [chunks[0], chunks[2]] // Return [content_area, status_area]
     } else {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(1),    // Main content area
-                Constraint::Length(1), // Status bar
+                Constraint::Min(1),                  // Main content area
+                Constraint::Length(status_height), // Status bar
             ])
             .split(frame.area());
 
         [chunks[0], chunks[1]] // Return [content_area, status_area]
     };
 
+    // Tab bar above the TOC and panes when several files are open
+    let content_area = if app.tab_bar_visible() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(base_chunks[0]);
+        render_tab_bar(frame, app, chunks[0]);
+        chunks[1]
+    } else {
+        base_chunks[0]
+    };
+
+    // Banner for a file that wasn't plain UTF-8 text
+    let content_area = match app.doc.decoding.notice() {
+        Some(notice) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .split(content_area);
+            let banner = Paragraph::new(format!(" {}", notice))
+                .style(Style::default().fg(Color::Black).bg(Color::Yellow));
+            frame.render_widget(banner, chunks[0]);
+            chunks[1]
+        }
+        None => content_area,
+    };
+
     let pane_area = if app.show_toc {
+        #[allow(clippy::unnecessary_cast)]
         let toc_width = app.config.toc.width as u16;
         let main_chunks = if app.config.toc.side == mdx_core::config::TocSide::Left {
             Layout::default()
@@ -48,7 +105,7 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     Constraint::Length(toc_width), // TOC
                     Constraint::Min(1),            // Panes area
                 ])
-                .split(base_chunks[0])
+                .split(content_area)
         } else {
             Layout::default()
                 .direction(Direction::Horizontal)
@@ -56,19 +113,35 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
                     Constraint::Min(1),            // Panes area
                     Constraint::Length(toc_width), // TOC
                 ])
-                .split(base_chunks[0])
+                .split(content_area)
         };
 
+        // Section sizes are only worked out while they are shown
+        let stats = app.config.toc.stats.then(|| app.toc_stats.get(&app.doc));
+        let stats = stats.as_deref();
+
         // Render TOC based on position
         if app.config.toc.side == mdx_core::config::TocSide::Left {
-            render_toc(frame, app, main_chunks[0]);
+            render_toc(frame, app, main_chunks[0], stats);
             main_chunks[1]
         } else {
-            render_toc(frame, app, main_chunks[1]);
+            render_toc(frame, app, main_chunks[1], stats);
             main_chunks[0]
         }
     } else {
-        base_chunks[0]
+        content_area
+    };
+
+    // Code block output takes a split below the panes
+    let pane_area = if app.block_output.is_some() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(pane_area);
+        render_block_output(frame, app, chunks[1]);
+        chunks[0]
+    } else {
+        pane_area
     };
 
     // Compute layout for all panes and render them
@@ -79,7 +152,8 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     }
 
     // Render status bar
-    render_status_bar(frame, app, base_chunks[1]);
+    let position = app.scroll_position();
+    render_status_bar(frame, app, base_chunks[1], &position);
 
     // Render help popup if active
     if app.show_help {
@@ -93,7 +167,57 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
 
     // Render TOC dialog if active
     if app.show_toc_dialog {
-        render_toc_dialog(frame, app);
+        let stats = app.config.toc.stats.then(|| app.toc_stats.get(&app.doc));
+        render_toc_dialog(frame, app, stats.as_deref());
+    }
+
+    // Render breadcrumb popup if active
+    if app.show_breadcrumb_popup {
+        render_breadcrumb_popup(frame, app);
+    }
+
+    // Render buffer picker if active
+    if app.show_buffer_picker {
+        render_buffer_picker(frame, app);
+    }
+
+    // Render git log panel if active
+    #[cfg(feature = "git")]
+    if let Some(log) = &app.git_log {
+        render_git_log(frame, app, log);
+    }
+
+    // Render fuzzy finder if active
+    if let Some(finder) = &app.finder {
+        render_finder(frame, app, finder);
+    }
+
+    if app.show_image_cache_stats {
+        render_image_cache_stats(frame, app);
+    }
+
+    if let Some(scroll) = app.log_viewer {
+        render_log_viewer(frame, app, scroll);
+    }
+
+    // Render broken link diagnostics if active
+    if app.show_link_diagnostics {
+        render_link_diagnostics(frame, app);
+    }
+
+    // Render spelling suggestions if active
+    if app.show_spell_popup {
+        render_spell_popup(frame, app);
+    }
+
+    // Render the link or footnote preview if active
+    if let Some(preview) = &app.preview {
+        render_preview_popup(frame, app, preview);
+    }
+
+    // Render document statistics if active
+    if let Some(stats) = &app.stats_popup {
+        render_stats_popup(frame, app, stats);
     }
 
     if app.command_output.is_some() {
@@ -101,6 +225,16 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     }
 }
 
+/// Language label for a code block: the fence's info string, or "plain"
+fn fence_lang(fence: &Fence) -> String {
+    if fence.info.is_empty() {
+        "plain".to_string()
+    } else {
+        fence.info.clone()
+    }
+}
+
+#[allow(clippy::manual_range_contains)]
 fn sanitize_for_terminal(input: &str) -> String {
     input
         .chars()
@@ -112,46 +246,93 @@ fn sanitize_for_terminal(input: &str) -> String {
         .collect()
 }
 
+/// Digits of the line number column, or 0 with `line_numbers = "off"`
+fn line_number_width(app: &App, line_count: usize) -> usize {
+    match app.config.render.line_numbers {
+        LineNumbers::Off => 0,
+        _ => format!("{}", line_count).len().max(3),
+    }
+}
+
+/// Columns taken by the line number column and the space after it
+fn line_number_columns(line_num_width: usize) -> usize {
+    if line_num_width == 0 {
+        0
+    } else {
+        line_num_width + 1
+    }
+}
+
+/// Gutter number of `line_idx`, as `render.line_numbers` counts it. In
+/// hybrid mode the cursor line shows its own number, left-aligned like vim.
+fn line_number_span(
+    app: &App,
+    line_idx: usize,
+    cursor: usize,
+    is_focused: bool,
+    line_num_width: usize,
+) -> Span<'static> {
+    if line_num_width == 0 {
+        return Span::raw("");
+    }
+    let distance = line_idx.abs_diff(cursor);
+    let text = match app.config.render.line_numbers {
+        LineNumbers::Relative => format!("{:>width$} ", distance, width = line_num_width),
+        LineNumbers::Hybrid if distance == 0 => {
+            format!("{:<width$} ", line_idx + 1, width = line_num_width)
+        }
+        LineNumbers::Hybrid => format!("{:>width$} ", distance, width = line_num_width),
+        LineNumbers::Absolute | LineNumbers::Off => {
+            format!("{:>width$} ", line_idx + 1, width = line_num_width)
+        }
+    };
+    let color = if is_focused && distance == 0 {
+        Color::White
+    } else {
+        Color::DarkGray
+    };
+    Span::styled(text, Style::default().fg(color))
+}
+
 /// Render a collapsed block summary line
 ///
 /// Returns a styled Line showing the collapse indicator, heading text, and line count
 fn render_collapsed_summary(
     range: &CollapseRange,
-    line_num_width: usize,
+    line_number: Span<'static>,
     theme: &crate::theme::Theme,
     is_focused: bool,
     is_cursor: bool,
     content_width: usize,
+    a11y: bool,
 ) -> Line<'static> {
     let mut spans = Vec::new();
 
     // Add line number
-    let line_num = format!("{:>width$} ", range.start + 1, width = line_num_width);
-    let line_num_color = if is_focused && is_cursor {
-        Color::White
-    } else {
-        Color::DarkGray
-    };
-    spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
+    spans.push(line_number);
 
     // Add gutter spacing (2 chars for diff gutter)
     spans.push(Span::raw("  "));
 
     // Add collapse indicator (▶)
     spans.push(Span::styled(
-        "▶ ",
+        if a11y { "Folded: " } else { "▶ " },
         Style::default().fg(theme.collapsed_indicator_fg),
     ));
 
     // Add heading marks based on level
     if let Some(level) = range.level {
-        let marks = "#".repeat(level as usize);
+        let marks = if a11y {
+            mdx_core::render::heading_label(level)
+        } else {
+            format!("{} ", "#".repeat(level as usize))
+        };
         let heading_style = theme
             .heading
             .get(level as usize - 1)
             .copied()
             .unwrap_or(theme.base);
-        spans.push(Span::styled(format!("{} ", marks), heading_style));
+        spans.push(Span::styled(marks, heading_style));
     }
 
     // Add heading text (truncated)
@@ -174,7 +355,7 @@ fn render_collapsed_summary(
     ));
 
     // Calculate current width and pad to content width
-    let current_width: usize = spans.iter().map(|s| s.content.chars().count()).sum();
+    let current_width: usize = spans.iter().map(|s| s.content.width()).sum();
 
     if current_width < content_width {
         let padding = " ".repeat(content_width - current_width);
@@ -201,24 +382,24 @@ fn render_collapsed_summary(
 }
 
 /// Render security warnings pane
-fn render_security_warnings(
-    frame: &mut Frame,
-    area: ratatui::layout::Rect,
-    warnings: &[mdx_core::SecurityEvent],
-    theme: &crate::theme::Theme,
-) {
+fn render_security_warnings(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
     // Build warning items - show most recent first, limit to 100
-    let items: Vec<ListItem> = warnings
+    let items: Vec<ListItem> = app
+        .security_warnings
         .iter()
         .rev()
         .take(100)
         .map(|w| {
-            let color = match w.level {
-                mdx_core::SecurityEventLevel::Error => Color::Red,
-                mdx_core::SecurityEventLevel::Warning => Color::Yellow,
-                mdx_core::SecurityEventLevel::Info => Color::Cyan,
+            let (color, level) = match w.level {
+                mdx_core::SecurityEventLevel::Error => (Color::Red, "Error"),
+                mdx_core::SecurityEventLevel::Warning => (Color::Yellow, "Warning"),
+                mdx_core::SecurityEventLevel::Info => (Color::Cyan, "Info"),
+            };
+            let text = if app.config.render.a11y {
+                format!("{}: [{}] {}", level, w.source, w.message)
+            } else {
+                format!("[{}] {}", w.source, w.message)
             };
-            let text = format!("[{}] {}", w.source, w.message);
             ListItem::new(text).style(Style::default().fg(color))
         })
         .collect();
@@ -227,17 +408,16 @@ fn render_security_warnings(
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
                 .title(" Security Warnings (W to toggle) "),
         )
-        .style(theme.base);
+        .style(app.theme.base);
 
     frame.render_widget(list, area);
 }
 
 fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect, pane_id: usize) {
-    use ratatui::text::Span;
-
     // Split area for breadcrumb and content
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -269,8 +449,13 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         None
     };
 
-    // Render breadcrumb
-    render_breadcrumb(frame, app, breadcrumb_area, pane_id);
+    // Reading mode: narrow and center the content column
+    content_area = centered_content_area(content_area, app.config.render.max_width);
+
+    // Render breadcrumb (hidden in focus mode)
+    if !app.focus_mode {
+        render_breadcrumb(frame, app, breadcrumb_area, pane_id);
+    }
 
     // Get the pane's view state
     let pane = match app.panes.panes.get(&pane_id) {
@@ -290,10 +475,10 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
     };
 
     let line_count = app.doc.line_count();
-    let front_matter = app.front_matter;
 
-    // If in raw mode, render plain text without markdown processing
-    if pane.view.show_raw {
+    // If in raw mode, render plain text without markdown processing.
+    // Insert mode edits the source, so it always shows raw text.
+    if pane.view.show_raw || pane.view.mode == crate::app::Mode::Insert {
         render_raw_text(
             frame,
             app,
@@ -319,8 +504,80 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         return;
     }
 
+    let view = DocumentView {
+        area: content_area,
+        scroll,
+        height: content_area.height.saturating_sub(2) as usize,
+        cursor,
+        is_focused,
+        selection_range,
+        collapsed_ranges: pane.view.collapsed_ranges(&app.doc),
+        margin: !app.focus_mode,
+    };
+    let wrapped_lines = document_rows(app, &view);
+
+    // Add border to pane with focus highlight
+    let border_style = if is_focused {
+        Style::default().fg(app.theme.toc_active.bg.unwrap_or(Color::LightCyan))
+    } else {
+        Style::default().fg(app.theme.toc_border)
+    };
+
+    let paragraph = Paragraph::new(wrapped_lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(border_style)
+                .title(pane_badges(app, "")),
+        )
+        .style(app.theme.base);
+
+    frame.render_widget(paragraph, content_area);
+
+    // Render scrollbar if enabled
+    if let Some(scrollbar_area) = scrollbar_area {
+        render_scrollbar(
+            frame,
+            app,
+            scrollbar_area,
+            pane_id,
+            doc_line_count,
+            viewport_height,
+        );
+    }
+}
+
+/// The part of the document a pane shows, and the pane state that decides
+/// how its rows are styled
+struct DocumentView {
+    /// Bordered area the rows are laid out in
+    area: ratatui::layout::Rect,
+    /// First source line shown
+    scroll: usize,
+    /// Rows to fill, starting at `scroll`
+    height: usize,
+    cursor: usize,
+    is_focused: bool,
+    selection_range: Option<(usize, usize)>,
+    collapsed_ranges: Vec<CollapseRange>,
+    /// Line numbers and diff gutter in front of each row
+    margin: bool,
+}
+
+/// Style and wrap the document rows `view` shows: front matter, folds and
+/// hidden comments left out, conflicts banded, code and tables laid out,
+/// and wrapped lines indented under their list item
+fn document_rows(app: &mut App, view: &DocumentView) -> Vec<Line<'static>> {
+    use ratatui::text::Span;
+
+    let line_count = app.doc.line_count();
+    let front_matter = app.front_matter;
+    let (scroll, cursor, is_focused) = (view.scroll, view.cursor, view.is_focused);
+    let selection_range = view.selection_range;
+
     // Get search query for highlighting (clone to avoid borrow issues)
-    let search_query = if !app.search_query.is_empty() {
+    let search_query = if app.search_highlight && !app.search_query.is_empty() {
         Some(app.search_query.clone())
     } else {
         None
@@ -328,49 +585,46 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
 
     // Determine if we're in a code block at the scroll position
     // by quickly scanning lines before the viewport
-    let mut in_code_block = false;
-    let mut code_block_lang = String::new();
-    let mut code_block_indent = 0; // Track indentation of code block for list items
+    let mut fences = mdx_core::fence::FenceTracker::new();
+    let mut lists = ListTracker::new();
+    let mut code_block_start = 0;
     for line_idx in 0..scroll.min(line_count) {
         let line_text: String = app.doc.rope.line(line_idx).chunks().collect();
-        let trimmed = line_text.trim_end();
-        let trimmed_start = trimmed.trim_start();
-        if trimmed_start.starts_with("```") {
-            if !in_code_block {
-                // Opening fence - extract language and indentation
-                let indent = trimmed.len() - trimmed_start.len();
-                code_block_indent = indent;
-                let lang = trimmed_start.strip_prefix("```").unwrap_or("").trim();
-                code_block_lang = if lang.is_empty() {
-                    "plain".to_string()
-                } else {
-                    lang.to_string()
-                };
-            }
-            in_code_block = !in_code_block;
-            if !in_code_block {
-                code_block_lang.clear();
-                code_block_indent = 0;
-            }
+        if !fences.in_code_block() {
+            lists.next_line(&line_text);
+        }
+        if fences.next_line(line_text.trim_end()) == FenceLine::Open {
+            code_block_start = line_idx;
         }
     }
+    let mut in_code_block = fences.in_code_block();
+    let mut code_block_lang = fences.open_fence().map(fence_lang).unwrap_or_default();
+    // Track indentation of code block for list items
+    let mut code_block_indent = fences.open_fence().map_or(0, |fence| fence.indent);
+
+    // With `render.commonmark`, a full parse decides what each line is
+    let commonmark_lines = app
+        .config
+        .render
+        .commonmark
+        .then(|| app.commonmark_lines.get(&app.doc, &app.config.render));
 
     // Calculate left margin width for line numbers and gutter
-    let line_num_width = format!("{}", line_count).len().max(3);
+    let line_num_width = line_number_width(app, line_count);
     let gutter_width = 2; // Git gutter or spacing
-    let left_margin_width = (line_num_width + 1 + gutter_width) as u16; // +1 for space after line number
+    let left_margin_width = (line_number_columns(line_num_width) + gutter_width) as u16;
 
-    // Compute collapsed ranges for this pane
-    let collapsed_ranges =
-        collapse::compute_all_collapsed_ranges(&pane.view.collapsed_headings, &app.doc);
+    let collapsed_ranges = &view.collapsed_ranges;
+    let heading_numbers = heading_numbers(app);
 
     // Build only visible lines
-    let mut styled_lines: Vec<Line> = Vec::new();
+    let mut styled_lines: Vec<Line<'static>> = Vec::new();
     let mut is_table_row_flags: Vec<bool> = Vec::new();
+    let mut line_sources: Vec<usize> = Vec::new(); // Source line of each styled line
     let mut list_item_indents: Vec<Option<usize>> = Vec::new(); // Track list item continuation indent
-                                                                // Account for borders (top and bottom borders take 2 lines)
-    let content_height = content_area.height.saturating_sub(2) as usize;
-    let mut visible_end = (scroll + content_height).min(line_count);
+    let mut prose_lines: Vec<bool> = Vec::new(); // Paragraph text that may be hyphenated and justified
+    let mut selected_rows: Vec<bool> = Vec::new(); // Whether each styled line is in the visual selection
+    let mut visible_end = (scroll + view.height).min(line_count);
     let mut is_first_code_line = false;
 
     let mut line_idx = scroll;
@@ -388,22 +642,26 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         }
 
         // Check if this line is the start of a collapsed range
-        if let Some(range) = collapse::find_range_at_line(&collapsed_ranges, line_idx) {
+        if let Some(range) = collapse::find_range_at_line(collapsed_ranges, line_idx) {
             // Render collapsed summary
-            let content_width = content_area.width.saturating_sub(2) as usize;
+            let content_width = view.area.width.saturating_sub(2) as usize;
             let is_cursor = is_focused && cursor == line_idx;
             let summary_line = render_collapsed_summary(
                 range,
-                line_num_width,
+                line_number_span(app, line_idx, cursor, is_focused, line_num_width),
                 &app.theme,
                 is_focused,
                 is_cursor,
                 content_width,
+                app.config.render.a11y,
             );
 
             styled_lines.push(summary_line);
+            line_sources.push(line_idx);
             is_table_row_flags.push(false);
             list_item_indents.push(None);
+            prose_lines.push(false);
+            selected_rows.push(false);
 
             // Skip to the end of the collapsed range
             let skipped_lines = range.end.saturating_sub(range.start);
@@ -415,7 +673,7 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         }
 
         // Check if this line is inside a collapsed range (but not the start)
-        if collapse::find_range_containing_line(&collapsed_ranges, line_idx).is_some() {
+        if collapse::find_range_containing_line(collapsed_ranges, line_idx).is_some() {
             // Skip this line - it's hidden inside a collapsed block
             line_idx += 1;
             // Expand visible_end to compensate for skipped line
@@ -424,7 +682,19 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
             }
             continue;
         }
+
+        // HTML comments take no rows while hidden (toggled with `C`)
+        let is_comment = app.doc.comments.iter().any(|range| range.contains(&line_idx));
+        if is_hidden_comment(app, &app.doc, line_idx) {
+            line_idx += 1;
+            if visible_end < line_count {
+                visible_end += 1;
+            }
+            continue;
+        }
         let mut line_spans: Vec<Span> = Vec::new();
+        // A scoped search only highlights inside its scope
+        let search_query = search_query.clone().filter(|_| app.search_in_scope(line_idx));
 
         // Get line text first to check if it's a fence
         let line_text: String = if line_idx < line_count {
@@ -436,34 +706,62 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         // Remove trailing newline for styling
         let line_text = sanitize_for_terminal(line_text.trim_end_matches('\n'));
 
-        // Table detection: header row followed by a separator row
-        if !in_code_block && line_idx + 1 < line_count {
-            let next_line: String = app.doc.rope.line(line_idx + 1).chunks().collect();
-            let next_line = sanitize_for_terminal(next_line.trim_end_matches('\n'));
-            if is_table_row(&line_text) && is_table_separator_row(&next_line) {
-                let (table_lines, consumed) = render_table_block(
-                    app,
-                    content_area,
-                    line_idx,
-                    visible_end,
-                    line_count,
-                    line_num_width,
-                    is_focused,
-                    cursor,
-                    selection_range,
-                    left_margin_width,
-                    search_query.as_deref(),
-                );
+        // Merge conflicts: each side gets its own background, and the
+        // marker lines are shown as they are rather than as Markdown
+        let (is_conflict_marker, conflict_bg) = conflict_style(app, line_idx);
 
-                for line in table_lines {
-                    styled_lines.push(line);
-                    is_table_row_flags.push(true);
-                    list_item_indents.push(None); // Tables are not list items
-                }
+        let list_line = if in_code_block || is_comment || is_conflict_marker {
+            ListLine::Text
+        } else {
+            lists.next_line(&line_text)
+        };
+
+        // A table laid out as a whole from its header row
+        let table = table_at(app, line_idx).filter(|_| !in_code_block);
+        // A fenced `csv` or `tsv` block shows as a table in place of its code
+        let table_block = (!in_code_block)
+            .then(|| csv_block_rows(app, line_idx))
+            .flatten()
+            .map(|(rows, next_line)| (rows, usize::MAX, next_line))
+            .or_else(|| {
+                let table = table.clone().filter(|table| table.start == line_idx)?;
+                let rows = source_table_rows(app, line_idx, table.end);
+                Some((rows, visible_end.saturating_sub(line_idx), table.end))
+            });
+        if let Some((rows, max_rows, next_line)) = table_block {
+            let (table_lines, consumed) = render_table_block(
+                app,
+                view.area,
+                &rows,
+                max_rows,
+                line_num_width,
+                is_focused,
+                cursor,
+                selection_range,
+                left_margin_width,
+                search_query.as_deref(),
+            );
+
+            for line in table_lines {
+                styled_lines.push(line);
+                line_sources.push(line_idx);
+                is_table_row_flags.push(true);
+                list_item_indents.push(None); // Tables are not list items
+                prose_lines.push(false);
+                selected_rows.push(false);
+            }
 
+            if consumed < rows.len() {
                 line_idx = line_idx.saturating_add(consumed);
                 continue;
             }
+            // The fences of a csv block leave more source lines than rows
+            let hidden = (next_line - line_idx).saturating_sub(rows.len());
+            if visible_end < line_count {
+                visible_end = (visible_end + hidden).min(line_count);
+            }
+            line_idx = next_line;
+            continue;
         }
 
         // Check for image rendering
@@ -474,15 +772,30 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                 .doc
                 .images
                 .iter()
-                .find(|img| img.source_line == line_idx)
+                .find(|img| img.source_line == line_idx && img.standalone)
                 .cloned();
 
             if let Some(image) = image_opt {
+                // Keep the list bullet or quote marker in front of the image
+                let raw_line: String = app.doc.rope.line(line_idx).chunks().collect();
+                let marker = sanitize_for_terminal(raw_line.get(..image.column).unwrap_or(""));
+                let prefix = if marker.trim().is_empty() {
+                    vec![Span::raw(marker)]
+                } else {
+                    style_markdown_line(
+                        &marker,
+                        &app.theme,
+                        &app.config.render,
+                        &app.doc.link_definitions,
+                        None,
+                    )
+                };
                 let (image_lines, _consumed) = render_image(
                     app,
-                    content_area,
+                    view.area,
                     line_idx,
                     &image,
+                    prefix,
                     line_num_width,
                     is_focused,
                     cursor,
@@ -492,8 +805,11 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
 
                 for line in image_lines {
                     styled_lines.push(line);
+                    line_sources.push(line_idx);
                     is_table_row_flags.push(false);
                     list_item_indents.push(None); // Images are not list items
+                    prose_lines.push(false);
+                    selected_rows.push(false);
                 }
 
                 line_idx += 1;
@@ -501,30 +817,29 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
             }
         }
 
-        // Track if this is a table row (before styling splits the pipes)
-        let is_table_row = line_text.contains('|');
+        // Rows of a table the view starts partway through
+        let is_table_row = table.is_some();
 
-        // Check for code block fence markers (including indented ones) - skip rendering them
-        let trimmed = line_text.trim_end();
-        let trimmed_start = trimmed.trim_start();
-        if trimmed_start.starts_with("```") {
-            if !in_code_block {
+        // Check for code block fence markers (including indented ones) - skip rendering them,
+        // except the opening fence that becomes the header row of a left label
+        let fence_line = fences.next_line(line_text.trim_end());
+        let is_fence_header =
+            fence_line == FenceLine::Open && app.config.render.code_label == CodeLabel::Left;
+        if matches!(fence_line, FenceLine::Open | FenceLine::Close) {
+            if let Some(fence) = fences.open_fence() {
                 // Opening fence - extract language and indentation
-                let indent = trimmed.len() - trimmed_start.len();
-                code_block_indent = indent;
-                let lang = trimmed_start.strip_prefix("```").unwrap_or("").trim();
-                code_block_lang = if lang.is_empty() {
-                    "plain".to_string()
-                } else {
-                    lang.to_string()
-                };
+                code_block_indent = fence.indent;
+                code_block_lang = fence_lang(fence);
+                code_block_start = line_idx;
                 is_first_code_line = true;
             } else {
                 // Closing fence - clear language
                 code_block_lang.clear();
                 code_block_indent = 0;
             }
-            in_code_block = !in_code_block;
+            in_code_block = fences.in_code_block();
+        }
+        if matches!(fence_line, FenceLine::Open | FenceLine::Close) && !is_fence_header {
             // Skip this line entirely (don't render fence markers).
             // Expand visible range so skipped fences don't leave empty space.
             if visible_end < line_count {
@@ -535,124 +850,204 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         }
 
         // Add line number
-        let line_num = format!("{:>width$} ", line_idx + 1, width = line_num_width);
-        let line_num_color = if is_focused && line_idx == cursor {
-            Color::White
-        } else {
-            Color::DarkGray
-        };
-        line_spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
-
-        // Add diff gutter with vertical bars
-        #[cfg(feature = "git")]
-        if app.config.git.diff {
-            use mdx_core::diff::DiffMark;
-            let mark = app.doc.diff_gutter.get(line_idx);
-            let gutter = match mark {
-                DiffMark::None => "  ",
-                DiffMark::Added => "│ ",
-                DiffMark::Modified => "│ ",
-                DiffMark::DeletedAfter(_) => "│ ",
-            };
-            let gutter_color = match mark {
-                DiffMark::None => Color::DarkGray,
-                DiffMark::Added => Color::Green,
-                DiffMark::Modified => Color::Yellow,
-                DiffMark::DeletedAfter(_) => Color::Red,
-            };
-            line_spans.push(Span::styled(gutter, Style::default().fg(gutter_color)));
-        } else {
-            line_spans.push(Span::raw("  "));
-        }
-        #[cfg(not(feature = "git"))]
-        line_spans.push(Span::raw("  "));
+        line_spans.push(line_number_span(app, line_idx, cursor, is_focused, line_num_width));
+
+        // Add diff gutter
+        line_spans.extend(diff_gutter_spans(app, line_idx));
+
+        let commonmark_line = commonmark_lines
+            .as_ref()
+            .and_then(|lines| lines.get(line_idx)?.as_ref())
+            .filter(|_| !in_code_block);
+        // Code the line-by-line fence tracking can't see: indented code
+        // blocks, and fenced blocks inside quotes
+        let commonmark_code = commonmark_line.and_then(|line| match &line.block {
+            mdx_core::render::Block::Code { info } if info.is_empty() => Some("plain".to_string()),
+            mdx_core::render::Block::Code { info } => Some(info.clone()),
+            _ => None,
+        });
 
         // Track if this is a code block line for background styling
-        let is_code_block_line;
-
-        if in_code_block {
-            // Inside code block - render with syntax highlighting and different background
-            // For indented code blocks (in list items), preserve the indentation
-            if code_block_indent > 0 {
-                // Add the indentation as plain text
-                let indent_str = " ".repeat(code_block_indent);
-                line_spans.push(Span::raw(indent_str));
-
+        let is_code_block_line = in_code_block || commonmark_code.is_some();
+
+        // A plain line directly followed by `: description` is a definition term
+        let is_definition_term = !in_code_block
+            && line_idx + 1 < line_count
+            && is_definition_term(&line_text)
+            && definition_content(&app.doc.rope.line(line_idx + 1).to_string()).is_some();
+
+        // Styled spans only depend on the line text and the cache key, so
+        // reuse them across frames instead of re-running the styler.
+        let styled_key = crate::render::StyledLineKey {
+            line_idx,
+            doc_rev: app.doc.rev,
+            theme: app.theme_variant,
+            use_utf8_graphics: app.config.render.use_utf8_graphics,
+            smart_punctuation: app.config.render.smart_punctuation,
+            strip_html: app.config.render.strip_html,
+            search_query: search_query.clone(),
+            width: view.area.width,
+            code_indent: in_code_block.then_some(code_block_indent),
+            list: list_line,
+        };
+        let theme = &app.theme;
+        let render_config = &app.config.render;
+        let styled_spans = app.styled_line_cache.get_or_insert_with(styled_key, || {
+            if is_comment {
+                vec![Span::styled(line_text.clone(), crate::theme::COMMENT_STYLE)]
+            } else if is_conflict_marker {
+                vec![Span::styled(
+                    line_text.clone(),
+                    theme.base.add_modifier(Modifier::BOLD),
+                )]
+            } else if is_fence_header {
+                // Header row: the label goes after the padding, as it can change
+                let mut spans = vec![Span::raw(" ".repeat(code_block_indent))];
+                spans.push(code_padding(theme));
+                spans
+            } else if in_code_block {
+                // Inside code block - render with syntax highlighting and different background
+                // For indented code blocks (in list items), preserve the indentation
+                let mut spans = Vec::new();
+                if code_block_indent > 0 {
+                    // Add the indentation as plain text
+                    spans.push(Span::raw(" ".repeat(code_block_indent)));
+                }
+                spans.push(code_padding(theme));
                 // Render the code content (strip the indent from the line)
-                let code_content = if line_text.len() >= code_block_indent {
-                    &line_text[code_block_indent..]
-                } else {
-                    &line_text
-                };
-                line_spans.extend(render_code_line(
+                let code_content = line_text.get(code_block_indent..).unwrap_or(&line_text);
+                spans.extend(render_code_line(
                     code_content,
-                    &app.theme,
+                    &code_block_lang,
+                    theme,
                     search_query.as_deref(),
                 ));
+                spans
+            } else if let Some(line) = commonmark_line {
+                // Container prefixes (quote bars, list indent) come before
+                // the code, which is the last span
+                let styled = crate::render::styled_line(line, theme).spans;
+                match &commonmark_code {
+                    Some(lang) => {
+                        let mut spans = styled[..styled.len().saturating_sub(1)].to_vec();
+                        spans.push(code_padding(theme));
+                        let code = line.spans.last().map_or("", |span| span.text.as_str());
+                        spans.extend(render_code_line(code, lang, theme, search_query.as_deref()));
+                        spans
+                    }
+                    None => match search_query.as_deref() {
+                        Some(query) if !query.is_empty() => {
+                            apply_search_highlighting_to_spans(styled, query)
+                        }
+                        _ => styled,
+                    },
+                }
+            } else if is_table_row {
+                style_table_row(
+                    &line_text,
+                    theme,
+                    render_config,
+                    &app.doc.link_definitions,
+                    search_query.as_deref(),
+                )
+            } else if is_definition_term {
+                style_definition_term(
+                    &line_text,
+                    theme,
+                    render_config,
+                    &app.doc.link_definitions,
+                    search_query.as_deref(),
+                )
             } else {
-                line_spans.extend(render_code_line(
+                style_prose_line(
                     &line_text,
-                    &app.theme,
+                    list_line,
+                    theme,
+                    render_config,
+                    &app.doc.link_definitions,
                     search_query.as_deref(),
-                ));
+                )
             }
-            is_code_block_line = true;
-        } else {
-            // Apply markdown styling to the line
-            line_spans.extend(style_markdown_line(
-                &line_text,
-                &app.theme,
-                &app.config.render,
-                search_query.as_deref(),
-            ));
-            is_code_block_line = false;
+        });
+        let mut styled_spans = patch_diff_words(app, line_idx, styled_spans);
+        if !in_code_block {
+            styled_spans = patch_inline_images(app, line_idx, styled_spans);
+            for broken in app.broken_links_on_line(line_idx) {
+                styled_spans = patch_text_style(
+                    styled_spans,
+                    &broken.link.text,
+                    Style::default()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            let misspelled: Vec<&str> = app
+                .misspellings_on_line(line_idx)
+                .map(|w| w.word.as_str())
+                .collect();
+            for (i, word) in misspelled.iter().enumerate() {
+                // Repeats of a word on one line mark successive occurrences
+                let nth = misspelled[..i].iter().filter(|w| *w == word).count();
+                styled_spans = patch_word_style(
+                    styled_spans,
+                    word,
+                    nth,
+                    Style::default()
+                        .add_modifier(Modifier::UNDERLINED)
+                        .underline_color(Color::LightRed),
+                );
+            }
+        }
+        if let Some(numbers) = &heading_numbers {
+            styled_spans = patch_heading_number(app, numbers, line_idx, styled_spans);
         }
+        line_spans.extend(styled_spans);
 
         // For code blocks, pad to full viewport width and add language label on first line
         if is_code_block_line {
             let line_visual_width: usize = line_spans
                 .iter()
-                .map(|span| span.content.chars().count())
+                .map(|span| span.content.width())
                 .sum();
-            // Calculate available width (content_area width - borders)
-            let available_width = content_area.width.saturating_sub(2) as usize;
-
-            if is_first_code_line && !code_block_lang.is_empty() {
-                // Add language label on the right side of the first line
-                let lang_label = format!(" {} ", code_block_lang);
-                let lang_width = lang_label.chars().count();
-                let remaining_width = available_width.saturating_sub(line_visual_width);
-
-                if remaining_width > lang_width {
-                    // Add padding before the label
-                    let padding_before = " ".repeat(remaining_width - lang_width);
-                    line_spans.push(Span::styled(
-                        padding_before,
-                        Style::default().bg(app.theme.code_block_bg),
-                    ));
-                    // Add the language label
-                    line_spans.push(Span::styled(
-                        lang_label,
-                        Style::default()
-                            .fg(Color::Rgb(120, 120, 120))
-                            .bg(app.theme.code_block_bg),
-                    ));
-                } else {
-                    // Not enough space for label, just pad
-                    let padding = " ".repeat(remaining_width);
-                    line_spans.push(Span::styled(
-                        padding,
-                        Style::default().bg(app.theme.code_block_bg),
-                    ));
+            // Calculate available width (area width - borders)
+            let available_width = view.area.width.saturating_sub(2) as usize;
+
+            // The label goes on the header row, or else right-aligned on
+            // the first code row
+            let label = if is_fence_header
+                || (is_first_code_line && app.config.render.code_label != CodeLabel::Left)
+            {
+                code_block_label(app, code_block_start, &code_block_lang)
+            } else {
+                None
+            };
+            is_first_code_line = false;
+            let remaining_width = available_width.saturating_sub(line_visual_width);
+            let padding = |width: usize| {
+                Span::styled(" ".repeat(width), Style::default().bg(app.theme.code_block_bg))
+            };
+            match label {
+                Some(label) if remaining_width > label.width() => {
+                    let padding = padding(remaining_width - label.width());
+                    if is_fence_header {
+                        line_spans.extend([label, padding]);
+                    } else {
+                        line_spans.extend([padding, label]);
+                    }
                 }
-                is_first_code_line = false;
-            } else if line_visual_width < available_width {
-                // Regular code block line - just pad
-                let padding = " ".repeat(available_width - line_visual_width);
-                line_spans.push(Span::styled(
-                    padding,
-                    Style::default().bg(app.theme.code_block_bg),
-                ));
+                // Not enough space for the label, just pad
+                _ if remaining_width > 0 => line_spans.push(padding(remaining_width)),
+                _ => {}
+            }
+        }
+
+        // Conflict sides are banded across the whole row
+        if let Some(bg) = conflict_bg.filter(|_| !is_code_block_line) {
+            let line_visual_width: usize = line_spans.iter().map(|span| span.content.width()).sum();
+            let available_width = view.area.width.saturating_sub(2) as usize;
+            let remaining_width = available_width.saturating_sub(line_visual_width);
+            if remaining_width > 0 {
+                line_spans.push(Span::styled(" ".repeat(remaining_width), Style::default().bg(bg)));
             }
         }
 
@@ -669,8 +1064,7 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
             line_spans = line_spans
                 .into_iter()
                 .map(|mut span| {
-                    let new_style = span.style.bg(Color::Cyan).fg(Color::Black);
-                    span.style = new_style;
+                    span.style = span.style.patch(SELECTION_STYLE);
                     span
                 })
                 .collect();
@@ -696,36 +1090,70 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                     span
                 })
                 .collect();
+        } else if let Some(bg) = conflict_bg {
+            line_spans = band_conflict_side(line_spans, bg, &app.theme);
         }
 
         let line = Line::from(line_spans);
 
-        // Detect if this is a list item and calculate continuation indent
-        let list_indent = if !in_code_block {
-            detect_list_item_indent(&line_text)
-        } else {
-            None
+        // Plain paragraph text, in or out of a list
+        let is_prose = !is_code_block_line
+            && !is_table_row
+            && !is_definition_term
+            && !line_text.trim_start().starts_with(['#', '>', '<']);
+
+        // List items and their continuation lines wrap under the item's content
+        let list_indent = match list_line {
+            _ if commonmark_line.is_some() => {
+                commonmark_line.map(|line| line.indent).filter(|&indent| indent > 0)
+            }
+            ListLine::Item { content_indent, .. } | ListLine::Continuation { content_indent } => {
+                Some(content_indent)
+            }
+            _ if !in_code_block => definition_content(&line_text).map(|_| DEFINITION_INDENT),
+            _ => None,
         };
 
         styled_lines.push(line);
+        line_sources.push(line_idx);
         is_table_row_flags.push(is_table_row);
         list_item_indents.push(list_indent);
+        prose_lines.push(is_prose);
+        selected_rows.push(is_focused && is_selected);
+        let virtual_lines = deleted_virtual_lines(app, line_idx, line_num_width)
+            .into_iter()
+            .chain(transcluded_virtual_lines(app, line_idx, line_num_width));
+        for virtual_line in virtual_lines {
+            styled_lines.push(virtual_line);
+            line_sources.push(line_idx);
+            is_table_row_flags.push(false);
+            list_item_indents.push(None);
+            prose_lines.push(false);
+            selected_rows.push(false);
+        }
         line_idx += 1;
     }
 
-    // Add border to pane with focus highlight
-    let border_style = if is_focused {
-        Style::default().fg(app.theme.toc_active.bg.unwrap_or(Color::LightCyan))
-    } else {
-        Style::default().fg(app.theme.toc_border)
-    };
+    // Without a margin, drop line numbers and gutters; focus mode also
+    // dims everything outside the cursor's section
+    if !view.margin {
+        let section = app.focus_mode.then(|| app.section_range_at(cursor));
+        for (line, &source) in styled_lines.iter_mut().zip(&line_sources) {
+            let stripped = drop_leading_columns(std::mem::take(line), left_margin_width as usize);
+            *line = match &section {
+                Some(section) if !section.contains(&source) => dim_line(stripped),
+                _ => stripped,
+            };
+        }
+    }
+    let left_margin_width = if view.margin { left_margin_width } else { 0 };
 
     // Manual wrapping to indent continuation lines
-    let available_width = content_area.width.saturating_sub(2) as usize; // -2 for borders
+    let available_width = view.area.width.saturating_sub(2) as usize; // -2 for borders
     let content_start = left_margin_width as usize;
     let content_width = available_width.saturating_sub(content_start);
 
-    let mut wrapped_lines: Vec<Line> = Vec::new();
+    let mut wrapped_lines: Vec<Line<'static>> = Vec::new();
 
     for (idx, line) in styled_lines.into_iter().enumerate() {
         // Check if this is a table row - if so, don't wrap it
@@ -737,8 +1165,12 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
             continue;
         }
 
+        let first_wrapped = wrapped_lines.len();
+
         // Check if this is a list item and get the continuation indent
         let list_continuation_indent = list_item_indents.get(idx).copied().flatten();
+        let is_prose = prose_lines.get(idx).copied().unwrap_or(false);
+        let hyphenate = is_prose && app.config.render.hyphenate;
 
         // Calculate the visual width of the line
         let mut current_width = 0;
@@ -748,16 +1180,16 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
 
         for span in line.spans {
             let span_text = span.content.to_string();
-            let span_width = span_text.chars().count();
+            let span_width = span_text.width();
 
             // Detect if this span is a bullet marker
             let is_bullet_span = list_continuation_indent.is_some() &&
                 span_width <= 5 && // Bullets are short: "• ", "- ", "1. ", "100. " etc.
-                (span_text.starts_with('•') ||
+                (span_text.starts_with(LIST_BULLETS) ||
                  span_text.starts_with('-') ||
                  span_text.starts_with('*') ||
                  span_text.starts_with('+') ||
-                 span_text.chars().next().map_or(false, |c| c.is_ascii_digit()));
+                 span_text.chars().next().is_some_and(|c| c.is_ascii_digit()));
 
             if current_width + span_width <= available_width {
                 // Fits on current line
@@ -784,11 +1216,7 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                 } else if !current_line_spans.is_empty() {
                     // If the span is short and we have little content, try to keep them together
                     // by not wrapping yet (let the span overflow and wrap within itself)
-                    if span_is_short && current_content_width < 20 {
-                        false
-                    } else {
-                        true
-                    }
+                    !(span_is_short && current_content_width < 20)
                 } else {
                     false
                 };
@@ -820,7 +1248,7 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                         content_width.saturating_sub(extra_indent)
                     };
 
-                    let remaining_len = remaining.chars().count();
+                    let remaining_len = remaining.width();
 
                     if remaining_len <= available {
                         // Entire remaining text fits
@@ -831,12 +1259,15 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                         // Need to wrap - find word boundary
                         let mut split_at = 0;
                         let mut last_word_end = None;
-                        let mut char_count = 0;
-
+                        let mut used_width = 0;
                         for (byte_idx, ch) in remaining.char_indices() {
-                            if char_count >= available {
+                            // Measure in terminal columns so wide CJK/emoji
+                            // glyphs never overflow the pane.
+                            let ch_width = ch.width().unwrap_or(0);
+                            if used_width + ch_width > available {
                                 break;
                             }
+                            used_width += ch_width;
 
                             // Track word boundaries (space, tab, or punctuation followed by space)
                             if ch.is_whitespace() {
@@ -844,7 +1275,6 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                             }
 
                             split_at = byte_idx + ch.len_utf8();
-                            char_count += 1;
                         }
 
                         // Prefer splitting at word boundary if we found one
@@ -852,8 +1282,7 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                             // Split at the word boundary, but skip the trailing whitespace
                             let after_space = remaining[word_end..]
                                 .char_indices()
-                                .skip_while(|(_, c)| c.is_whitespace())
-                                .next()
+                                .find(|(_, c)| !c.is_whitespace())
                                 .map(|(i, _)| word_end + i)
                                 .unwrap_or(word_end);
                             (word_end, after_space)
@@ -875,11 +1304,36 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
                             split_pos
                         };
 
-                        let (chunk, rest) = remaining.split_at(safe_split_pos.0);
-                        let rest = &rest[safe_split_pos.1 - safe_split_pos.0..];
+                        // Break the word that didn't fit at a syllable if its
+                        // head and a hyphen fit on this row
+                        let word_start = last_word_end.map_or(0, |_| split_pos.1);
+                        let hyphen_at = hyphenate
+                            .then(|| {
+                                let word = remaining[word_start..]
+                                    .split(char::is_whitespace)
+                                    .next()
+                                    .unwrap_or("");
+                                let room = available.saturating_sub(remaining[..word_start].width());
+                                crate::typeset::hyphen_break(word, room)
+                            })
+                            .flatten()
+                            .map(|at| word_start + at);
+
+                        let (chunk, rest) = match hyphen_at {
+                            Some(at) => remaining.split_at(at),
+                            None => {
+                                let (chunk, rest) = remaining.split_at(safe_split_pos.0);
+                                (chunk, &rest[safe_split_pos.1 - safe_split_pos.0..])
+                            }
+                        };
 
                         if !chunk.is_empty() {
-                            current_line_spans.push(Span::styled(chunk.to_string(), span.style));
+                            let chunk = if hyphen_at.is_some() {
+                                format!("{}-", chunk)
+                            } else {
+                                chunk.to_string()
+                            };
+                            current_line_spans.push(Span::styled(chunk, span.style));
                             wrapped_lines.push(Line::from(current_line_spans.clone()));
                             current_line_spans.clear();
 
@@ -902,32 +1356,595 @@ fn render_markdown(frame: &mut Frame, app: &mut App, area: ratatui::layout::Rect
         if !current_line_spans.is_empty() {
             wrapped_lines.push(Line::from(current_line_spans));
         }
+
+        // Every row but the last of a wrapped paragraph reaches the edge
+        if is_prose && app.config.render.justify && wrapped_lines.len() > first_wrapped + 1 {
+            let skip = content_start + list_continuation_indent.unwrap_or(0);
+            let last = wrapped_lines.len() - 1;
+            for row in wrapped_lines[first_wrapped..last].iter_mut() {
+                *row = crate::typeset::justify_row(std::mem::take(row), skip, available_width);
+            }
+        }
+
+        // A selected line is highlighted as a solid block on every rendered
+        // row, continuation indent and trailing space included
+        if selected_rows.get(idx).copied().unwrap_or(false) {
+            for row in wrapped_lines[first_wrapped..].iter_mut() {
+                *row = fill_row(std::mem::take(row), available_width, SELECTION_STYLE);
+            }
+        }
+
+        // Put RTL text into visual order, one rendered row at a time
+        if app.config.render.bidi {
+            for row in wrapped_lines[first_wrapped..].iter_mut() {
+                let row_line = std::mem::take(row);
+                *row = crate::bidi::reorder_line(row_line, content_start, available_width);
+            }
+        }
     }
 
-    let paragraph = Paragraph::new(wrapped_lines)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(border_style),
+    wrapped_lines
+}
+
+/// Whether `line_idx` is a merge conflict marker, and the background of
+/// the conflict side it is on, if any
+fn conflict_style(app: &App, line_idx: usize) -> (bool, Option<Color>) {
+    let conflict = app.doc.conflicts.iter().find(|c| c.contains(line_idx));
+    let is_marker = conflict.is_some_and(|c| c.is_marker(line_idx));
+    let bg = conflict.and_then(|c| c.side(line_idx)).map(|side| match side {
+        ConflictSide::Ours => app.theme.conflict_ours_bg,
+        ConflictSide::Base => app.theme.collapsed_block_bg,
+        ConflictSide::Theirs => app.theme.conflict_theirs_bg,
+    });
+    (is_marker, bg)
+}
+
+/// Merge conflict: the side's background in place of the page's
+fn band_conflict_side(
+    spans: Vec<Span<'static>>,
+    bg: Color,
+    theme: &crate::theme::Theme,
+) -> Vec<Span<'static>> {
+    spans
+        .into_iter()
+        .map(|mut span| {
+            if span.style.bg.is_none() || span.style.bg == theme.base.bg {
+                span.style = span.style.bg(bg);
+            }
+            span
+        })
+        .collect()
+}
+
+/// Whether `line_idx` of `doc` is in an HTML comment, which takes no rows
+/// while comments are hidden (toggled with `C`)
+fn is_hidden_comment(app: &App, doc: &mdx_core::Document, line_idx: usize) -> bool {
+    !app.config.render.show_comments && doc.comments.iter().any(|range| range.contains(&line_idx))
+}
+
+/// Box sides drawn as blanks, keeping the layout of a bordered box
+const BLANK_BORDER: ratatui::symbols::border::Set = ratatui::symbols::border::Set {
+    top_left: " ",
+    top_right: " ",
+    bottom_left: " ",
+    bottom_right: " ",
+    vertical_left: " ",
+    vertical_right: " ",
+    horizontal_top: " ",
+    horizontal_bottom: " ",
+};
+
+/// Border symbols for boxes: blank in `render.a11y` mode, so screen
+/// readers don't read out a box-drawing character at each end of a row
+fn border_set(app: &App) -> ratatui::symbols::border::Set<'static> {
+    if app.config.render.a11y {
+        BLANK_BORDER
+    } else {
+        ratatui::symbols::border::PLAIN
+    }
+}
+
+/// Render breadcrumb bar with heading hierarchy and git status
+/// Remove the first `columns` display columns of a line, splitting a span
+/// that straddles the cut
+fn drop_leading_columns(line: Line<'static>, columns: usize) -> Line<'static> {
+    let mut remaining = columns;
+    let mut spans = Vec::with_capacity(line.spans.len());
+    for span in line.spans {
+        if remaining == 0 {
+            spans.push(span);
+            continue;
+        }
+        let width = span.content.width();
+        if width <= remaining {
+            remaining -= width;
+            continue;
+        }
+        let mut cut = 0;
+        let mut dropped = 0;
+        for (idx, ch) in span.content.char_indices() {
+            if dropped >= remaining {
+                cut = idx;
+                break;
+            }
+            dropped += ch.width().unwrap_or(0);
+            cut = idx + ch.len_utf8();
+        }
+        remaining = 0;
+        spans.push(Span::styled(span.content[cut..].to_string(), span.style));
+    }
+    Line::from(spans).style(line.style)
+}
+
+/// Fade a line that is outside the focused section
+fn dim_line(line: Line<'static>) -> Line<'static> {
+    let spans: Vec<Span<'static>> = line
+        .spans
+        .into_iter()
+        .map(|span| {
+            let style = span
+                .style
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM)
+                .remove_modifier(Modifier::BOLD);
+            Span::styled(span.content, style)
+        })
+        .collect();
+    Line::from(spans).style(line.style)
+}
+
+/// Label of the code block opened at `fence_line`: "copied!" just after
+/// `yc`, otherwise its language unless labels are hidden
+fn code_block_label(app: &App, fence_line: usize, lang: &str) -> Option<Span<'static>> {
+    let bg = app.theme.code_block_bg;
+    if app.code_block_copied(fence_line) {
+        return Some(Span::styled(
+            " copied! ",
+            Style::default()
+                .fg(Color::Green)
+                .bg(bg)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    (app.config.render.code_label != CodeLabel::Hidden && !lang.is_empty()).then(|| {
+        Span::styled(
+            format!(" {} ", lang),
+            Style::default().fg(Color::Rgb(120, 120, 120)).bg(bg),
         )
-        .style(app.theme.base);
+    })
+}
 
-    frame.render_widget(paragraph, content_area);
+/// Drop the line number and gutter spans that `render_table_block` puts
+/// before a table row's first border
+fn strip_table_margin(line: Line<'static>, border: &str) -> Line<'static> {
+    let spans: Vec<Span<'static>> = line
+        .spans
+        .into_iter()
+        .skip_while(|span| span.content != border)
+        .collect();
+    Line::from(spans)
+}
 
-    // Render scrollbar if enabled
-    if let Some(scrollbar_area) = scrollbar_area {
-        render_scrollbar(
-            frame,
-            app,
-            scrollbar_area,
-            pane_id,
-            doc_line_count,
-            viewport_height,
+/// Style the lines of one slide (or any line range), skipping fences
+fn build_slide_lines(
+    app: &App,
+    slide: std::ops::Range<usize>,
+    area: ratatui::layout::Rect,
+) -> Vec<Line<'static>> {
+    let theme = &app.theme;
+    let render_config = &app.config.render;
+    let links = &app.doc.link_definitions;
+    let border = if render_config.use_utf8_graphics {
+        "│"
+    } else {
+        "|"
+    };
+
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    let mut fences = mdx_core::fence::FenceTracker::new();
+    let mut lists = ListTracker::new();
+    let mut lang = String::new();
+    let mut line_idx = slide.start;
+    while line_idx < slide.end {
+        if is_hidden_comment(app, &app.doc, line_idx) {
+            line_idx += 1;
+            continue;
+        }
+        let raw: String = app.doc.rope.line(line_idx).chunks().collect();
+        let line_text = sanitize_for_terminal(raw.trim_end_matches(['\n', '\r']));
+
+        // Merge conflict markers are shown as they are, not as Markdown
+        let (is_conflict_marker, conflict_bg) = conflict_style(app, line_idx);
+        if is_conflict_marker {
+            let style = theme.base.add_modifier(Modifier::BOLD);
+            lines.push(Line::from(Span::styled(line_text, style)));
+            line_idx += 1;
+            continue;
+        }
+        let band = |spans: Vec<Span<'static>>| match conflict_bg {
+            Some(bg) => band_conflict_side(spans, bg, theme),
+            None => spans,
+        };
+
+        let fence_line = fences.next_line(&line_text);
+        if matches!(fence_line, FenceLine::Open | FenceLine::Close) {
+            lang = fences.open_fence().map(fence_lang).unwrap_or_default();
+            line_idx += 1;
+            continue;
+        }
+        if fence_line == FenceLine::Code {
+            let mut spans = vec![
+                Span::styled("  ", Style::default().bg(theme.code_block_bg)),
+                code_padding(theme),
+            ];
+            spans.extend(render_code_line(&line_text, &lang, theme, None));
+            lines.push(Line::from(band(spans)));
+            line_idx += 1;
+            continue;
+        }
+        let list_line = lists.next_line(&line_text);
+
+        let next_line = (line_idx + 1 < slide.end).then(|| {
+            let next: String = app.doc.rope.line(line_idx + 1).chunks().collect();
+            sanitize_for_terminal(next.trim_end_matches(['\n', '\r']))
+        });
+        if let Some(table) = table_at(app, line_idx).filter(|table| table.start == line_idx) {
+            let table_end = table.end.min(slide.end);
+            let rows = source_table_rows(app, line_idx, table_end);
+            let (table_lines, consumed) =
+                render_table_block(app, area, &rows, usize::MAX, 0, false, 0, None, 0, None);
+            lines.extend(
+                table_lines
+                    .into_iter()
+                    .map(|line| strip_table_margin(line, border)),
+            );
+            line_idx += consumed.max(1);
+            continue;
+        }
+
+        let spans = if next_line.as_deref().is_some_and(|n| definition_content(n).is_some())
+            && is_definition_term(&line_text)
+        {
+            style_definition_term(&line_text, theme, render_config, links, None)
+        } else {
+            style_prose_line(&line_text, list_line, theme, render_config, links, None)
+        };
+        lines.push(Line::from(band(spans)));
+        line_idx += 1;
+    }
+
+    // Blank lines around the content would throw off vertical centering
+    while lines.first().is_some_and(|l| l.width() == 0) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|l| l.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Rows of the whole document as the viewer lays them out, `width`
+/// columns wide with no line numbers, cursor or selection, as used for
+/// non-terminal output
+pub(crate) fn document_lines(app: &mut App, width: u16) -> Vec<Line<'static>> {
+    let line_count = app.doc.line_count();
+    let collapsed_ranges = app
+        .panes
+        .panes
+        .get(&app.panes.focused)
+        .map(|pane| pane.view.collapsed_ranges(&app.doc))
+        .unwrap_or_default();
+    let view = DocumentView {
+        // As if inside the pane's borders
+        area: ratatui::layout::Rect::new(0, 0, width.saturating_add(2), 1),
+        scroll: 0,
+        height: line_count,
+        cursor: 0,
+        is_focused: false,
+        selection_range: None,
+        collapsed_ranges,
+        margin: false,
+    };
+    let mut rows = document_rows(app, &view);
+    // A final newline ends the last line rather than starting an empty one
+    let ends_with_newline = app.doc.rope.len_chars() > 0
+        && app.doc.rope.line(line_count - 1).len_chars() == 0;
+    if ends_with_newline && rows.last().is_some_and(|row| row.width() == 0) {
+        rows.pop();
+    }
+    rows
+}
+
+/// Full-screen error for a document that couldn't be loaded: the error
+/// chain and the keys to retry, open another file, or quit
+fn render_load_error(frame: &mut Frame, app: &App, error: &crate::app::LoadError) {
+    use ratatui::widgets::Wrap;
+
+    let key_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  {}", error.path.display()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for (depth, cause) in error.chain.iter().enumerate() {
+        let (label, style) = if depth == 0 {
+            ("  Error: ", Style::default().fg(Color::Red))
+        } else {
+            ("    caused by: ", Style::default().fg(Color::DarkGray))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(label, style),
+            Span::raw(cause.clone()),
+        ]));
+    }
+    lines.push(Line::from(""));
+
+    let mut keys = vec![("r", "Retry"), ("o", "Open another file")];
+    if app.doc.loaded_mtime.is_some() {
+        keys.push(("Esc", "Back to the last loaded copy"));
+    }
+    keys.push(("q", "Quit"));
+    for (key, action) in keys {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<5}", key), key_style),
+            Span::raw(action),
+        ]));
+    }
+    if let Some(prompt) = &error.prompt {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("  Open: ", key_style),
+            Span::raw(format!("{}_", prompt)),
+        ]));
+    }
+    if let Some(prompt) = &app.confirm {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ", prompt.question), key_style),
+            Span::raw(prompt.action.choices()),
+        ]));
+    }
+    if let Some((message, _)) = &app.status_message {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  {}", message),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let title = format!(" Couldn't open {} ", crate::buffers::display_name(&app.doc));
+    let screen = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_set(border_set(app))
+            .border_style(Style::default().fg(Color::Red))
+            .title(title)
+            .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+    );
+    frame.render_widget(screen, frame.area());
+}
+
+/// Full-screen slide view: the current slide centered, with a progress
+/// footer
+fn render_presentation(frame: &mut Frame, app: &App) {
+    use ratatui::widgets::Wrap;
+
+    let (Some(slides), Some(slide)) = (&app.presentation, app.current_slide()) else {
+        return;
+    };
+    let area = frame.area();
+    frame.render_widget(Block::default().style(app.theme.base), area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(area);
+    let (body, footer) = (chunks[0], chunks[1]);
+
+    // Content column: at most render.max_width (or 100) columns wide
+    let max_width = match app.config.render.max_width {
+        0 => 100,
+        width => width,
+    };
+    let column_width = (body.width.saturating_sub(4) as usize).min(max_width) as u16;
+    let column = ratatui::layout::Rect {
+        x: body.x + (body.width.saturating_sub(column_width)) / 2,
+        width: column_width,
+        ..body
+    };
+
+    let lines = build_slide_lines(app, slide, column);
+    // Shrink narrow slides to their widest line so they sit centered
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .max()
+        .unwrap_or(0)
+        .clamp(1, column_width.max(1) as usize) as u16;
+    let height: usize = lines
+        .iter()
+        .map(|l| l.width().max(1).div_ceil(content_width as usize))
+        .sum();
+    let height = (height as u16).min(body.height);
+    let slide_area = ratatui::layout::Rect {
+        x: body.x + (body.width.saturating_sub(content_width)) / 2,
+        y: body.y + (body.height.saturating_sub(height)) / 2,
+        width: content_width,
+        height,
+    };
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }),
+        slide_area,
+    );
+
+    // Footer: slide counter, progress bar, and key hints
+    let counter = format!(" {}/{} ", app.presentation_slide + 1, slides.len());
+    let hints = " ←/→ navigate  q exit ";
+    let bar_width = (footer.width as usize).saturating_sub(counter.width() + hints.width() + 2);
+    let filled = if slides.len() <= 1 {
+        bar_width
+    } else {
+        bar_width * app.presentation_slide / (slides.len() - 1)
+    };
+    let (done, todo) = if app.config.render.use_utf8_graphics {
+        ("━", "─")
+    } else {
+        ("=", "-")
+    };
+    let footer_line = Line::from(vec![
+        Span::styled(counter, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(" "),
+        Span::styled(done.repeat(filled), Style::default().fg(Color::Cyan)),
+        Span::styled(todo.repeat(bar_width - filled), Style::default().fg(Color::DarkGray)),
+        Span::raw(" "),
+        Span::styled(hints, Style::default().fg(Color::DarkGray)),
+    ]);
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
+
+/// Render `mdx --diff`: the old file on the left, the new file on the
+/// right, with a summary and key hints below
+#[cfg(feature = "git")]
+fn render_diff_view(frame: &mut Frame, app: &mut App) {
+    use mdx_core::diff::{RowKind, RowSide};
+    use ratatui::layout::Rect;
+
+    let theme_base = app.theme.base;
+    let Some(view) = app.diff_view.as_mut() else {
+        return;
+    };
+    let area = frame.area();
+    frame.render_widget(Block::default().style(theme_base), area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(area);
+    let (header, body, footer) = (chunks[0], chunks[1], chunks[2]);
+    let half = body.width.saturating_sub(1) / 2;
+    let left_area = Rect { width: half, ..body };
+    let separator = Rect {
+        x: body.x + half,
+        width: 1,
+        ..body
+    };
+    let right_area = Rect {
+        x: separator.x + 1,
+        width: body.width.saturating_sub(half + 1),
+        ..body
+    };
+
+    view.page_height = body.height as usize;
+    view.scroll = view.scroll.min(view.rows.len().saturating_sub(view.page_height.max(1)));
+
+    let header_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    for (path, rect) in [(&view.old_path, left_area), (&view.new_path, right_area)] {
+        let title = sanitize_for_terminal(&path.display().to_string());
+        frame.render_widget(
+            Paragraph::new(Span::styled(format!(" {}", title), header_style)),
+            Rect { y: header.y, height: 1, ..rect },
         );
     }
+
+    let last_line = view
+        .rows
+        .iter()
+        .flat_map(|row| [&row.left, &row.right])
+        .flatten()
+        .map(|side| side.line + 1)
+        .max()
+        .unwrap_or(1);
+    let number_width = last_line.to_string().len();
+
+    // Row background, and the stronger shade for changed words
+    let shades = |kind: RowKind, old_side: bool| -> (Style, Style) {
+        let (row, word) = match (kind, old_side) {
+            (RowKind::Equal, _) => return (theme_base, theme_base),
+            // Only the old side of a removed row (and the new side of an
+            // added one) has text
+            (RowKind::Removed, _) => (Color::Rgb(60, 20, 20), Color::Rgb(60, 20, 20)),
+            (RowKind::Added, _) => (Color::Rgb(20, 50, 20), Color::Rgb(20, 50, 20)),
+            (RowKind::Changed, true) => (Color::Rgb(45, 25, 25), Color::Rgb(110, 35, 35)),
+            (RowKind::Changed, false) => (Color::Rgb(25, 45, 25), Color::Rgb(35, 95, 35)),
+        };
+        (theme_base.bg(row), theme_base.bg(word))
+    };
+    let side_line = |side: Option<&RowSide>, kind: RowKind, old_side: bool, width: u16| {
+        let Some(side) = side else {
+            let filler = Style::default().fg(Color::Rgb(50, 50, 50));
+            return Line::from(Span::styled("╱".repeat(width as usize), filler));
+        };
+        let (row_style, word_style) = shades(kind, old_side);
+        let mut spans = vec![Span::styled(
+            format!("{:>number_width$} ", side.line + 1),
+            Style::default().fg(Color::DarkGray),
+        )];
+        let mut pos = 0;
+        for range in &side.changes {
+            let before = side.text.get(pos..range.start).unwrap_or("");
+            let changed = side.text.get(range.clone()).unwrap_or("");
+            spans.push(Span::styled(diff_view_text(before), row_style));
+            spans.push(Span::styled(diff_view_text(changed), word_style));
+            pos = range.end;
+        }
+        spans.push(Span::styled(
+            diff_view_text(side.text.get(pos..).unwrap_or("")),
+            row_style,
+        ));
+        Line::from(spans).style(row_style)
+    };
+
+    let visible = view.rows.iter().skip(view.scroll).take(body.height as usize);
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for row in visible {
+        left.push(side_line(row.left.as_ref(), row.kind, true, left_area.width));
+        right.push(side_line(row.right.as_ref(), row.kind, false, right_area.width));
+    }
+    frame.render_widget(Paragraph::new(left), left_area);
+    frame.render_widget(Paragraph::new(right), right_area);
+    let bar = vec![Line::from("│"); body.height as usize];
+    frame.render_widget(
+        Paragraph::new(bar).style(Style::default().fg(Color::DarkGray)),
+        separator,
+    );
+
+    let (added, removed, changed) = view.counts();
+    let total = view.change_starts().len();
+    let position = match (view.current_change(), total) {
+        (_, 0) => "no differences".to_string(),
+        (Some(current), _) => format!("change {}/{}", current, total),
+        (None, _) => format!("{} changes", total),
+    };
+    let hint = match &app.status_message {
+        Some((message, _)) => format!("  {}", message),
+        None if view.quit_on_close => "  n/N next/prev change  q quit".to_string(),
+        None => "  n/N next/prev change  q close".to_string(),
+    };
+    let footer_line = Line::from(vec![
+        Span::styled(format!(" +{}", added), Style::default().fg(Color::Green)),
+        Span::styled(format!(" -{}", removed), Style::default().fg(Color::Red)),
+        Span::styled(format!(" ~{}", changed), Style::default().fg(Color::Yellow)),
+        Span::raw(format!("  {}", position)),
+        Span::styled(hint, Style::default().fg(Color::DarkGray)),
+    ]);
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
+
+/// Diff view text with control characters removed and tabs expanded
+#[cfg(feature = "git")]
+fn diff_view_text(text: &str) -> String {
+    sanitize_for_terminal(text).replace('\t', "    ")
 }
 
-/// Render breadcrumb bar with heading hierarchy and git status
 fn render_breadcrumb(frame: &mut Frame, app: &App, area: ratatui::layout::Rect, pane_id: usize) {
     use ratatui::text::Span;
 
@@ -953,24 +1970,21 @@ fn render_breadcrumb(frame: &mut Frame, app: &App, area: ratatui::layout::Rect,
         if idx > 0 {
             // Add separator
             let sep = " › ";
-            if current_width + sep.len() >= max_breadcrumb_width {
+            if current_width + sep.width() >= max_breadcrumb_width {
                 spans.push(Span::styled("…", Style::default().fg(Color::DarkGray)));
                 break;
             }
             spans.push(Span::styled(sep, Style::default().fg(Color::DarkGray)));
-            current_width += sep.len();
+            current_width += sep.width();
         }
 
         // Truncate crumb if needed
-        let crumb_text = if current_width + crumb.len() > max_breadcrumb_width {
+        let crumb_text = if current_width + crumb.width() > max_breadcrumb_width {
             let available = max_breadcrumb_width
                 .saturating_sub(current_width)
                 .saturating_sub(1);
             if available > 3 {
-                format!(
-                    "{}…",
-                    &crumb.chars().take(available - 1).collect::<String>()
-                )
+                format!("{}…", truncate_to_width(crumb, available - 1))
             } else {
                 "…".to_string()
             }
@@ -978,7 +1992,7 @@ fn render_breadcrumb(frame: &mut Frame, app: &App, area: ratatui::layout::Rect,
             crumb.clone()
         };
 
-        current_width += crumb_text.len();
+        current_width += crumb_text.width();
 
         // Style the breadcrumb
         let crumb_style = if is_focused {
@@ -1006,7 +2020,7 @@ fn render_breadcrumb(frame: &mut Frame, app: &App, area: ratatui::layout::Rect,
         // Add spacing before status
         let padding_width = area
             .width
-            .saturating_sub(current_width as u16 + status_text.len() as u16 + 2);
+            .saturating_sub(current_width as u16 + status_text.width() as u16 + 2);
         if padding_width > 0 {
             spans.push(Span::raw(" ".repeat(padding_width as usize)));
         }
@@ -1123,7 +2137,20 @@ fn render_scrollbar(
     frame.render_widget(paragraph, area);
 }
 
+/// Border title of a pane: its mode badge, if any, then `[DIRTY]` while
+/// the file has changed on disk since it was loaded
+fn pane_badges(app: &App, mode: &str) -> String {
+    let dirty = if app.doc.dirty_on_disk { "[DIRTY]" } else { "" };
+    let badges: Vec<&str> = [mode, dirty].into_iter().filter(|b| !b.is_empty()).collect();
+    if badges.is_empty() {
+        String::new()
+    } else {
+        format!(" {} ", badges.join(" "))
+    }
+}
+
 /// Render raw text without markdown processing
+#[allow(clippy::too_many_arguments)]
 fn render_raw_text(
     frame: &mut Frame,
     app: &App,
@@ -1138,8 +2165,13 @@ fn render_raw_text(
     use ratatui::text::Span;
 
     // Calculate left margin width for line numbers and gutter
-    let line_num_width = format!("{}", line_count).len().max(3);
-    let _gutter_width = 2; // Git gutter or spacing
+    let line_num_width = line_number_width(app, line_count);
+
+    let editing = is_focused
+        && app
+            .panes
+            .focused_pane()
+            .is_some_and(|p| p.view.mode == crate::app::Mode::Insert);
 
     // Build only visible lines
     let mut lines: Vec<Line> = Vec::new();
@@ -1160,39 +2192,17 @@ fn render_raw_text(
         let line_text = sanitize_for_terminal(line_text.trim_end_matches('\n'));
 
         // Add line number
-        let line_num = format!("{:>width$} ", line_idx + 1, width = line_num_width);
-        let line_num_color = if is_focused && line_idx == cursor {
-            Color::White
-        } else {
-            Color::DarkGray
-        };
-        line_spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
-
-        // Add diff gutter with vertical bars
-        #[cfg(feature = "git")]
-        if app.config.git.diff {
-            use mdx_core::diff::DiffMark;
-            let gutter = match app.doc.diff_gutter.get(line_idx) {
-                DiffMark::None => "  ",
-                DiffMark::Added => "│ ",
-                DiffMark::Modified => "│ ",
-                DiffMark::DeletedAfter(_) => "│ ",
-            };
-            let gutter_color = match app.doc.diff_gutter.get(line_idx) {
-                DiffMark::None => Color::DarkGray,
-                DiffMark::Added => Color::Green,
-                DiffMark::Modified => Color::Yellow,
-                DiffMark::DeletedAfter(_) => Color::Red,
-            };
-            line_spans.push(Span::styled(gutter, Style::default().fg(gutter_color)));
-        } else {
-            line_spans.push(Span::raw("  "));
-        }
-        #[cfg(not(feature = "git"))]
-        line_spans.push(Span::raw("  "));
+        line_spans.push(line_number_span(app, line_idx, cursor, is_focused, line_num_width));
+
+        // Add diff gutter
+        line_spans.extend(diff_gutter_spans(app, line_idx));
 
         // Add raw text content
-        line_spans.push(Span::styled(line_text.to_string(), app.theme.base));
+        line_spans.extend(patch_diff_words(
+            app,
+            line_idx,
+            vec![Span::styled(line_text.to_string(), app.theme.base)],
+        ));
 
         // Check if this line is selected or cursor
         let is_selected = if let Some((start, end)) = selection_range {
@@ -1226,6 +2236,10 @@ fn render_raw_text(
 
         let line = Line::from(line_spans);
         lines.push(line);
+        // Virtual lines would shift the edit cursor off its source line
+        if !editing {
+            lines.extend(deleted_virtual_lines(app, line_idx, line_num_width));
+        }
     }
 
     // Create border style
@@ -1239,20 +2253,67 @@ fn render_raw_text(
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(border_style)
-                .title(" Raw "),
+                .title(pane_badges(app, if editing { "Insert" } else { "[RAW]" })),
         )
         .style(app.theme.base);
 
     frame.render_widget(paragraph, area);
+
+    // Show the terminal cursor at the edit position
+    if let Some(pane) = app.panes.focused_pane().filter(|_| editing) {
+        if (scroll..visible_end).contains(&cursor) {
+            let line_text: String = app.doc.rope.line(cursor).chars().take(pane.view.edit_col).collect();
+            let prefix_width = sanitize_for_terminal(&line_text).width() as u16;
+            let margin = (line_number_columns(line_num_width) + 2) as u16;
+            let x = (area.x + 1 + margin + prefix_width).min(area.right().saturating_sub(2));
+            let y = area.y + 1 + (cursor - scroll) as u16;
+            frame.set_cursor_position((x, y));
+        }
+    }
+}
+
+/// Colors of lines in the visual line selection
+const SELECTION_STYLE: Style = Style::new().bg(Color::Cyan).fg(Color::Black);
+
+/// Give every span of a rendered row `style`'s colors and pad the row to
+/// `width` columns with it
+fn fill_row(row: Line<'static>, width: usize, style: Style) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = row
+        .spans
+        .into_iter()
+        .map(|span| {
+            let patched = span.style.patch(style);
+            span.style(patched)
+        })
+        .collect();
+    let used: usize = spans.iter().map(|span| span.content.width()).sum();
+    if used < width {
+        spans.push(Span::styled(" ".repeat(width - used), style));
+    }
+    Line::from(spans)
+}
+
+/// Background-colored space between the gutter and a code block's text
+fn code_padding(theme: &crate::theme::Theme) -> Span<'static> {
+    Span::styled(
+        " ".repeat(theme.code_padding),
+        Style::default().bg(theme.code_block_bg),
+    )
 }
 
 /// Render a code block line with syntax highlighting
 fn render_code_line(
     text: &str,
+    lang: &str,
     theme: &crate::theme::Theme,
     search_query: Option<&str>,
 ) -> Vec<Span<'static>> {
+    if is_diff_lang(lang) {
+        return render_diff_line(text, theme, search_query);
+    }
+
     // Code block background color from theme
     let code_bg = theme.code_block_bg;
 
@@ -1417,6 +2478,45 @@ fn render_code_line(
     spans
 }
 
+/// Whether a fence's info string marks a unified diff (```` ```diff ````,
+/// ```` ```patch ````)
+fn is_diff_lang(lang: &str) -> bool {
+    lang.split_whitespace()
+        .next()
+        .is_some_and(|word| word.eq_ignore_ascii_case("diff") || word.eq_ignore_ascii_case("patch"))
+}
+
+/// Color a line of a diff block by its first character: additions green,
+/// removals red, hunk headers cyan, and file headers bold
+fn render_diff_line(
+    text: &str,
+    theme: &crate::theme::Theme,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    let base = Style::default()
+        .fg(theme.code.fg.unwrap_or(Color::White))
+        .bg(theme.code_block_bg);
+    let style = if ["+++ ", "--- ", "diff ", "index "]
+        .iter()
+        .any(|header| text.starts_with(header))
+    {
+        base.add_modifier(Modifier::BOLD)
+    } else if text.starts_with("@@") {
+        base.fg(Color::Cyan)
+    } else if text.starts_with('+') {
+        base.fg(Color::Green)
+    } else if text.starts_with('-') {
+        base.fg(Color::Red)
+    } else {
+        base
+    };
+    let span = Span::styled(text.to_string(), style);
+    match search_query {
+        Some(query) if !query.is_empty() => apply_search_highlighting_to_spans(vec![span], query),
+        _ => vec![span],
+    }
+}
+
 /// Apply search highlighting on top of existing styled spans
 /// Preserves the original foreground color but adds yellow background for matches
 fn apply_search_highlighting_to_spans(
@@ -1510,30 +2610,24 @@ fn highlight_text_matches(text: &str, query: &str, base_style: Style) -> Vec<Spa
     spans
 }
 
-fn is_table_row(line: &str) -> bool {
-    !line.trim().is_empty() && line.contains('|')
+/// Source lines of the table `line` belongs to, if the Markdown flavor
+/// has tables
+fn table_at(app: &App, line: usize) -> Option<std::ops::Range<usize>> {
+    if app.config.render.markdown.flavor != MarkdownFlavor::Gfm {
+        return None;
+    }
+    app.doc
+        .tables
+        .iter()
+        .find(|table| table.contains(&line))
+        .cloned()
 }
 
 fn split_table_cells(line: &str) -> Vec<String> {
-    let mut trimmed = line.trim();
-    if trimmed.starts_with('|') {
-        trimmed = &trimmed[1..];
-    }
-    if trimmed.ends_with('|') && trimmed.len() > 1 {
-        trimmed = &trimmed[..trimmed.len() - 1];
-    }
-    trimmed
-        .split('|')
-        .map(|cell| cell.trim().to_string())
-        .collect()
+    mdx_core::format::split_cells(line)
 }
 
-fn is_table_separator_row(line: &str) -> bool {
-    if !is_table_row(line) {
-        return false;
-    }
-
-    let cells = split_table_cells(line);
+fn is_separator_cells(cells: &[String]) -> bool {
     if cells.is_empty() {
         return false;
     }
@@ -1565,7 +2659,7 @@ fn wrap_cell_text(text: &str, width: usize) -> Vec<String> {
     let mut current_len = 0;
 
     for word in text.split_whitespace() {
-        let word_len = word.chars().count();
+        let word_len = word.width();
         if current_len == 0 {
             current.push_str(word);
             current_len = word_len;
@@ -1588,22 +2682,40 @@ fn wrap_cell_text(text: &str, width: usize) -> Vec<String> {
 
     let mut wrapped: Vec<String> = Vec::new();
     for line in lines {
-        let mut start = 0;
-        let chars: Vec<char> = line.chars().collect();
-        if chars.is_empty() {
+        if line.is_empty() {
             wrapped.push(String::new());
             continue;
         }
-        while start < chars.len() {
-            let end = (start + width).min(chars.len());
-            wrapped.push(chars[start..end].iter().collect());
-            start = end;
+        // Hard-split words wider than the column, by display width
+        let mut remaining = line.as_str();
+        while !remaining.is_empty() {
+            let chunk = truncate_to_width(remaining, width);
+            let chunk_len = if chunk.is_empty() {
+                remaining.chars().next().map_or(0, char::len_utf8)
+            } else {
+                chunk.len()
+            };
+            wrapped.push(remaining[..chunk_len].to_string());
+            remaining = &remaining[chunk_len..];
         }
     }
 
     wrapped
 }
 
+/// Longest prefix of `text` that fits in `max_width` terminal columns
+fn truncate_to_width(text: &str, max_width: usize) -> &str {
+    let mut used = 0;
+    for (byte_idx, ch) in text.char_indices() {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width > max_width {
+            return &text[..byte_idx];
+        }
+        used += ch_width;
+    }
+    text
+}
+
 fn compute_table_widths(rows: &[Vec<String>], content_width: usize) -> Vec<usize> {
     let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
     if col_count == 0 {
@@ -1613,7 +2725,7 @@ fn compute_table_widths(rows: &[Vec<String>], content_width: usize) -> Vec<usize
     let mut widths = vec![0usize; col_count];
     for row in rows {
         for (idx, cell) in row.iter().enumerate() {
-            let width = cell.chars().count();
+            let width = cell.width();
             if width > widths[idx] {
                 widths[idx] = width;
             }
@@ -1675,15 +2787,68 @@ fn build_table_separator_cell(width: usize, raw: &str, use_utf8: bool) -> String
 }
 
 fn spans_visual_width(spans: &[Span<'static>]) -> usize {
-    spans.iter().map(|span| span.content.chars().count()).sum()
+    spans.iter().map(|span| span.content.width()).sum()
+}
+
+/// A table row laid out by `render_table_block`: its source line and cells
+type TableRow = (usize, Vec<String>);
+
+/// Cells of the table rows on source lines `start..end`, each with its line
+fn source_table_rows(app: &App, start: usize, end: usize) -> Vec<TableRow> {
+    (start..end.min(app.doc.line_count()))
+        .map(|idx| {
+            let line_text: String = app.doc.rope.line(idx).chunks().collect();
+            let line_text = sanitize_for_terminal(line_text.trim_end_matches('\n'));
+            (idx, split_table_cells(&line_text))
+        })
+        .collect()
+}
+
+/// Cells of a fenced `csv` or `tsv` block opened at `fence_line`, laid out
+/// as a table with its first record as the header, and the line after the
+/// closing fence. `None` if the block isn't one or is never closed.
+fn csv_block_rows(app: &App, fence_line: usize) -> Option<(Vec<TableRow>, usize)> {
+    let mut fences = mdx_core::fence::FenceTracker::new();
+    let open: String = app.doc.rope.line(fence_line).chunks().collect();
+    fences.next_line(open.trim_end());
+    let format = mdx_core::doc::csv::fence_format(&fences.open_fence()?.info)?;
+
+    let mut body = String::new();
+    let mut close = fence_line + 1;
+    loop {
+        if close >= app.doc.line_count() {
+            return None;
+        }
+        let line: String = app.doc.rope.line(close).chunks().collect();
+        if fences.next_line(line.trim_end()) == FenceLine::Close {
+            break;
+        }
+        body.push_str(&sanitize_for_terminal(line.trim_end_matches(['\n', '\r'])));
+        body.push('\n');
+        close += 1;
+    }
+
+    let records = mdx_core::doc::csv::records(&body, format);
+    let columns = records.iter().map(|record| record.fields.len()).max()?;
+    let mut rows: Vec<TableRow> = records
+        .iter()
+        .map(|record| {
+            let cells = record.fields.iter().map(|field| mdx_core::doc::csv::cell(field));
+            (fence_line + 1 + record.line, cells.collect())
+        })
+        .collect();
+    rows.insert(1, (rows[0].0, vec!["---".to_string(); columns]));
+    Some((rows, close + 1))
 }
 
+/// Lay out table `rows` (source line and cells, header first) for as many
+/// of them as `max_rows` allows. Returns the lines and the rows laid out.
+#[allow(clippy::too_many_arguments)]
 fn render_table_block(
     app: &App,
     area: ratatui::layout::Rect,
-    start_idx: usize,
-    visible_end: usize,
-    line_count: usize,
+    table_rows: &[TableRow],
+    max_rows: usize,
     line_num_width: usize,
     is_focused: bool,
     cursor: usize,
@@ -1691,25 +2856,10 @@ fn render_table_block(
     left_margin_width: u16,
     search_query: Option<&str>,
 ) -> (Vec<Line<'static>>, usize) {
-    let mut table_rows: Vec<(usize, String)> = Vec::new();
-    let mut idx = start_idx;
-    while idx < line_count {
-        let line_text: String = app.doc.rope.line(idx).chunks().collect();
-        let line_text = sanitize_for_terminal(line_text.trim_end_matches('\n'));
-        if !is_table_row(&line_text) {
-            break;
-        }
-        table_rows.push((idx, line_text));
-        idx += 1;
-    }
-
     let table_rows_len = table_rows.len();
-    let consumed = visible_end.saturating_sub(start_idx).min(table_rows_len);
+    let consumed = max_rows.min(table_rows_len);
 
-    let mut cell_rows: Vec<Vec<String>> = Vec::new();
-    for (_, row_text) in &table_rows {
-        cell_rows.push(split_table_cells(row_text));
-    }
+    let cell_rows: Vec<Vec<String>> = table_rows.iter().map(|(_, cells)| cells.clone()).collect();
 
     let content_width = area.width.saturating_sub(2) as usize;
     let content_width = content_width.saturating_sub(left_margin_width as usize);
@@ -1718,9 +2868,8 @@ fn render_table_block(
     let mut rendered: Vec<Line> = Vec::new();
     let indent_str = " ".repeat(left_margin_width as usize);
 
-    for (row_idx, (source_idx, row_text)) in table_rows.iter().enumerate().take(consumed) {
-        let cells = split_table_cells(row_text);
-        let is_separator = row_idx == 1 && is_table_separator_row(row_text);
+    for (row_idx, (source_idx, cells)) in table_rows.iter().enumerate().take(consumed) {
+        let is_separator = row_idx == 1 && is_separator_cells(cells);
 
         let mut padded_cells = cells.clone();
         while padded_cells.len() < widths.len() {
@@ -1740,43 +2889,21 @@ fn render_table_block(
             wrapped_cells.iter().map(|c| c.len()).max().unwrap_or(1)
         };
 
+        // Only the first row from a source line is numbered, like the first
+        // line of a wrapped row
+        let numbered = row_idx == 0 || table_rows[row_idx - 1].0 != *source_idx;
         for line_offset in 0..row_height {
             let mut line_spans: Vec<Span> = Vec::new();
 
-            if line_offset == 0 {
-                let line_num = format!("{:>width$} ", source_idx + 1, width = line_num_width);
-                let line_num_color = if is_focused && *source_idx == cursor {
-                    Color::White
-                } else {
-                    Color::DarkGray
-                };
-                line_spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
-
-                #[cfg(feature = "git")]
-                if app.config.git.diff {
-                    use mdx_core::diff::DiffMark;
-                    let gutter = match app.doc.diff_gutter.get(*source_idx) {
-                        DiffMark::None => "  ",
-                        DiffMark::Added => "│ ",
-                        DiffMark::Modified => "│ ",
-                        DiffMark::DeletedAfter(_) => "│ ",
-                    };
-                    let gutter_color = match app.doc.diff_gutter.get(*source_idx) {
-                        DiffMark::None => Color::DarkGray,
-                        DiffMark::Added => Color::Green,
-                        DiffMark::Modified => Color::Yellow,
-                        DiffMark::DeletedAfter(_) => Color::Red,
-                    };
-                    line_spans.push(Span::styled(gutter, Style::default().fg(gutter_color)));
-                } else {
-                    line_spans.push(Span::raw("  "));
-                }
-                #[cfg(not(feature = "git"))]
-                line_spans.push(Span::raw("  "));
+            if line_offset == 0 && numbered {
+                line_spans.push(line_number_span(app, *source_idx, cursor, is_focused, line_num_width));
+
+                line_spans.extend(diff_gutter_spans(app, *source_idx));
             } else {
                 line_spans.push(Span::raw(indent_str.clone()));
             }
 
+            let gutter_len = line_spans.len();
             let separator_char = if app.config.render.use_utf8_graphics {
                 "│"
             } else {
@@ -1809,8 +2936,11 @@ fn render_table_block(
                         cell_line,
                         app.theme.base,
                         app.theme.code,
-                        search_query.as_deref(),
+                        &app.config.render,
+                        &app.doc.link_definitions,
+                        search_query,
                     );
+                    cell_spans = patch_inline_images(app, *source_idx, cell_spans);
 
                     let cell_width = spans_visual_width(&cell_spans);
                     if cell_width < *width {
@@ -1827,6 +2957,22 @@ fn render_table_block(
                 ));
             }
 
+            // Screen readers hear the table's shape instead of a row of dashes
+            if is_separator && app.config.render.a11y {
+                line_spans.truncate(gutter_len);
+                let count = |n: usize, noun: &str| {
+                    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+                };
+                line_spans.push(Span::styled(
+                    format!(
+                        "Table with {} and {}",
+                        count(widths.len(), "column"),
+                        count(table_rows_len.saturating_sub(2), "row")
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
             let is_selected = if let Some((start, end)) = selection_range {
                 *source_idx >= start && *source_idx <= end
             } else {
@@ -1864,42 +3010,472 @@ fn render_table_block(
     (rendered, consumed)
 }
 
-/// Style a single line of markdown text
+/// Patch the style of the first occurrence of `needle` in a line of spans,
+/// splitting spans at the match boundaries
+fn patch_text_style(spans: Vec<Span<'static>>, needle: &str, patch: Style) -> Vec<Span<'static>> {
+    if needle.is_empty() {
+        return spans;
+    }
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    match text.find(needle) {
+        Some(start) => patch_range_style(spans, start..start + needle.len(), patch),
+        None => spans,
+    }
+}
+
+/// Mark the alt text of images that share their line with other content
+/// (inside a sentence, list item, or table cell) as image placeholders
+fn patch_inline_images(app: &App, line_idx: usize, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    #[cfg(feature = "images")]
+    if app.config.images.enabled && !app.config.security.safe_mode {
+        let style = Style::default()
+            .fg(Color::Rgb(100, 200, 255))
+            .bg(Color::Rgb(30, 40, 50));
+        return app
+            .doc
+            .images
+            .iter()
+            .filter(|img| img.source_line == line_idx && !img.standalone)
+            .fold(spans, |spans, img| {
+                patch_text_style(spans, &sanitize_for_terminal(&img.alt), style)
+            });
+    }
+    #[cfg(not(feature = "images"))]
+    let _ = (app, line_idx);
+    spans
+}
+
+/// Section numbers of the document's headings, when `render.number_headings`
+/// is on
+fn heading_numbers(app: &App) -> Option<Vec<String>> {
+    app.config
+        .render
+        .number_headings
+        .then(|| mdx_core::toc::section_numbers(&app.doc.headings))
+}
+
+/// Insert the section number of a heading line after its `#` marks
+fn patch_heading_number(
+    app: &App,
+    numbers: &[String],
+    line_idx: usize,
+    mut spans: Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    let headings = &app.doc.headings;
+    let Ok(idx) = headings.binary_search_by_key(&line_idx, |h| h.line) else {
+        return spans;
+    };
+    let (Some(number), Some(style)) = (
+        numbers.get(idx),
+        app.theme.heading.get(usize::from(headings[idx].level).saturating_sub(1)),
+    ) else {
+        return spans;
+    };
+    let at = usize::from(spans.first().is_some_and(|span| span.content.starts_with('#')));
+    spans.insert(at, Span::styled(format!("{} ", number), *style));
+    spans
+}
+
+/// The two gutter columns: the git mark (`git.diff`), then a mark for
+/// in-app edits that aren't saved yet
+fn diff_gutter_spans(app: &App, line_idx: usize) -> Vec<Span<'static>> {
+    #[cfg(feature = "git")]
+    {
+        use mdx_core::diff::{DiffLayer, DiffMark};
+        let git_mark = match app.doc.diff_gutter.layer(DiffLayer::Git, line_idx) {
+            _ if !app.config.git.diff => None,
+            DiffMark::None => None,
+            DiffMark::Added => Some(("+", app.theme.diff_add)),
+            DiffMark::Modified => Some(("~", app.theme.diff_mod)),
+            DiffMark::DeletedAfter(_) => Some(("_", app.theme.diff_del)),
+        };
+        let git = match git_mark {
+            // The kind of change can't be told by color alone in a11y mode
+            Some((mark, style)) if app.config.render.a11y => Span::styled(mark, style),
+            Some((_, style)) => Span::styled("│", style),
+            None => Span::raw(" "),
+        };
+        let saved = match app.doc.diff_gutter.layer(DiffLayer::Saved, line_idx) {
+            _ if !app.doc.modified => " ",
+            DiffMark::None => " ",
+            DiffMark::Added => "+",
+            DiffMark::Modified => "~",
+            DiffMark::DeletedAfter(_) => "_",
+        };
+        vec![git, Span::styled(saved, Style::default().fg(Color::Magenta))]
+    }
+    #[cfg(not(feature = "git"))]
+    {
+        let _ = (app, line_idx);
+        vec![Span::raw("  ")]
+    }
+}
+
+/// Base lines removed after `line_idx`, as red virtual lines without a
+/// line number (toggled with `D`)
+fn deleted_virtual_lines(app: &App, line_idx: usize, line_num_width: usize) -> Vec<Line<'static>> {
+    #[cfg(feature = "git")]
+    if app.config.git.diff && app.show_deleted_lines {
+        let style = Style::default()
+            .fg(Color::Rgb(230, 110, 110))
+            .bg(Color::Rgb(45, 20, 20));
+        return app
+            .doc
+            .diff_gutter
+            .deleted_lines(line_idx)
+            .iter()
+            .map(|text| {
+                Line::from(vec![
+                    Span::raw(" ".repeat(line_number_columns(line_num_width))),
+                    Span::styled("- ", app.theme.diff_del),
+                    Span::styled(sanitize_for_terminal(text), style),
+                ])
+            })
+            .collect();
+    }
+    #[cfg(not(feature = "git"))]
+    let _ = (app, line_idx, line_num_width);
+    Vec::new()
+}
+
+/// Rows showing what an include line (`![[file#section]]`) pulls in from
+/// another file, marked off by a bar so they don't pass for the document's
+/// own text
+fn transcluded_virtual_lines(app: &App, line_idx: usize, line_num_width: usize) -> Vec<Line<'static>> {
+    use mdx_core::transclude::IncludedLine;
+
+    let Some(included) = app.transclusions.get(&line_idx) else {
+        return Vec::new();
+    };
+    let bar = Span::styled("│ ", Style::default().fg(Color::DarkGray));
+    included
+        .iter()
+        .map(|line| {
+            let mut spans = vec![Span::raw(" ".repeat(line_number_columns(line_num_width))), bar.clone()];
+            match line {
+                IncludedLine::Text(text) => spans.extend(style_markdown_line(
+                    &sanitize_for_terminal(text),
+                    &app.theme,
+                    &app.config.render,
+                    &app.doc.link_definitions,
+                    None,
+                )),
+                IncludedLine::Error(problem) => spans.push(Span::styled(
+                    format!("include: {}", sanitize_for_terminal(problem)),
+                    Style::default().fg(Color::Red),
+                )),
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Tint the words that changed on a modified line (`git.diff`), so small
+/// edits stand out and not just the gutter mark
+fn patch_diff_words(app: &App, line_idx: usize, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    #[cfg(feature = "git")]
+    if app.config.git.diff && line_idx < app.doc.line_count() {
+        let changes = app.doc.diff_gutter.changes(line_idx);
+        if changes.is_empty() {
+            return spans;
+        }
+        let raw: String = app.doc.rope.line(line_idx).chunks().collect();
+        let tint = Style::default().bg(Color::Rgb(80, 70, 20));
+        return changes.iter().fold(spans, |spans, range| {
+            patch_changed_text(spans, &raw, range.clone(), tint)
+        });
+    }
+    #[cfg(not(feature = "git"))]
+    let _ = (app, line_idx);
+    spans
+}
+
+/// Patch the style of the text of `raw[range]` where it shows up in the
+/// rendered spans. Markup at the edges of the change is not rendered, so
+/// it is trimmed off; if the rest still doesn't appear as a whole (markup
+/// inside it), each word is patched on its own.
+#[cfg(feature = "git")]
+fn patch_changed_text(
+    spans: Vec<Span<'static>>,
+    raw: &str,
+    range: std::ops::Range<usize>,
+    patch: Style,
+) -> Vec<Span<'static>> {
+    let is_markup = |c: char| c.is_whitespace() || "*_`~[]()<>#|".contains(c);
+    let Some(changed) = raw.get(range.clone()) else {
+        return spans;
+    };
+    let trimmed = changed.trim_start_matches(is_markup);
+    let start = range.start + (changed.len() - trimmed.len());
+    let fragment = trimmed.trim_end_matches(is_markup);
+    if fragment.is_empty() {
+        return spans;
+    }
+
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    // The nth occurrence in the raw line is taken to be the nth rendered one
+    let find = |piece: &str, at: usize| {
+        let piece = sanitize_for_terminal(piece);
+        let nth = raw[..at].matches(piece.as_str()).count();
+        text.match_indices(piece.as_str())
+            .nth(nth)
+            .map(|(pos, _)| pos..pos + piece.len())
+    };
+    if let Some(found) = find(fragment, start) {
+        return patch_range_style(spans, found, patch);
+    }
+    let mut spans = spans;
+    let mut offset = 0;
+    for word in fragment.split_whitespace() {
+        let at = offset + fragment[offset..].find(word).unwrap_or(0);
+        offset = at + word.len();
+        let word = word.trim_matches(is_markup);
+        if word.is_empty() {
+            continue;
+        }
+        if let Some(found) = find(word, start + at) {
+            spans = patch_range_style(spans, found, patch);
+        }
+    }
+    spans
+}
+
+/// Patch the style of the `nth` (0-based) whole-word occurrence of `word`
+/// in a line of spans
+fn patch_word_style(
+    spans: Vec<Span<'static>>,
+    word: &str,
+    nth: usize,
+    patch: Style,
+) -> Vec<Span<'static>> {
+    if word.is_empty() {
+        return spans;
+    }
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '\'');
+    let found = text
+        .match_indices(word)
+        .map(|(start, _)| start)
+        .filter(|&start| {
+            !is_word_char(text[..start].chars().next_back())
+                && !is_word_char(text[start + word.len()..].chars().next())
+        })
+        .nth(nth);
+    match found {
+        Some(start) => patch_range_style(spans, start..start + word.len(), patch),
+        None => spans,
+    }
+}
+
+/// Patch the style of a byte range of the text of a line of spans,
+/// splitting spans at the range boundaries
+fn patch_range_style(
+    spans: Vec<Span<'static>>,
+    range: std::ops::Range<usize>,
+    patch: Style,
+) -> Vec<Span<'static>> {
+    let (match_start, match_end) = (range.start, range.end);
+    let mut out = Vec::with_capacity(spans.len() + 2);
+    let mut offset = 0;
+    for span in spans {
+        let len = span.content.len();
+        let (start, end) = (offset, offset + len);
+        offset = end;
+        if end <= match_start || start >= match_end {
+            out.push(span);
+            continue;
+        }
+        // Split this span into before / matched / after parts
+        let cut_start = match_start.saturating_sub(start);
+        let cut_end = (match_end - start).min(len);
+        let content = span.content.as_ref();
+        if cut_start > 0 {
+            out.push(Span::styled(content[..cut_start].to_string(), span.style));
+        }
+        out.push(Span::styled(
+            content[cut_start..cut_end].to_string(),
+            span.style.patch(patch),
+        ));
+        if cut_end < len {
+            out.push(Span::styled(content[cut_end..].to_string(), span.style));
+        }
+    }
+    out
+}
+
+/// Narrow a bordered content area so its inner width is at most
+/// `max_width` columns, centered horizontally. 0 leaves the area as is.
+fn centered_content_area(area: ratatui::layout::Rect, max_width: usize) -> ratatui::layout::Rect {
+    let inner_width = area.width.saturating_sub(2) as usize;
+    if max_width == 0 || inner_width <= max_width {
+        return area;
+    }
+    let width = (max_width + 2) as u16;
+    ratatui::layout::Rect {
+        x: area.x + (area.width - width) / 2,
+        width,
+        ..area
+    }
+}
+
+/// Rendered indent of a definition list description
+const DEFINITION_INDENT: usize = 4;
+
+/// Whether a line can be the term of a definition list: non-blank text that
+/// is not itself a heading, list item, table row, or description
+fn is_definition_term(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty()
+        && !trimmed.starts_with('#')
+        && !line.contains('|')
+        && detect_list_item_indent(line).is_none()
+        && definition_content(line).is_none()
+}
+
+/// Style a definition list term in bold
+fn style_definition_term(
+    line: &str,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    style_inline_markdown(
+        line.trim(),
+        theme.base.add_modifier(Modifier::BOLD),
+        theme.code,
+        render_config,
+        links,
+        search_query,
+    )
+}
+
 /// Detect if a line is a list item and calculate the indent for continuation lines
 /// Returns Some(indent_width) if it's a list item, None otherwise
 fn detect_list_item_indent(line: &str) -> Option<usize> {
-    let trimmed_start = line.trim_start();
-    let leading_spaces = line.len() - trimmed_start.len();
+    ListMarker::parse(line).map(|marker| marker.content_indent)
+}
 
-    // Check for unordered list (-, *, +)
-    if let Some(_rest) = trimmed_start.strip_prefix("- ") {
-        return Some(leading_spaces + 2); // "- " is 2 chars
-    } else if let Some(_rest) = trimmed_start.strip_prefix("* ") {
-        return Some(leading_spaces + 2); // "* " is 2 chars
-    } else if let Some(_rest) = trimmed_start.strip_prefix("+ ") {
-        return Some(leading_spaces + 2); // "+ " is 2 chars
+/// Style a prose line given its place in a list: items get the bullet for
+/// their depth, and continuation lines start under the item's content
+fn style_prose_line(
+    line: &str,
+    list_line: ListLine,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    match list_line {
+        ListLine::Item { depth, number, .. } => {
+            if let Some(marker) = ListMarker::parse(line) {
+                return style_list_item(
+                    line,
+                    &marker,
+                    depth,
+                    number,
+                    theme,
+                    render_config,
+                    links,
+                    search_query,
+                );
+            }
+        }
+        ListLine::Continuation { content_indent } => {
+            let mut spans = vec![Span::raw(" ".repeat(content_indent))];
+            spans.extend(style_text_line(
+                line.trim_start(),
+                theme,
+                render_config,
+                links,
+                search_query,
+            ));
+            return spans;
+        }
+        _ => {}
     }
+    style_text_line(line, theme, render_config, links, search_query)
+}
 
-    // Check for ordered list (number followed by . or ))
-    if let Some(pos) = trimmed_start.find(|c| c == '.' || c == ')') {
-        let prefix = &trimmed_start[..pos];
-        if prefix.chars().all(|c| c.is_ascii_digit()) && trimmed_start.len() > pos + 1 {
-            // Marker is like "1. " or "1) " - pos + 2 chars
-            return Some(leading_spaces + pos + 2);
-        }
+/// Style a list item: the marker becomes a bullet for its nesting level
+/// (or a normalized number), padded so the content starts at the same
+/// column as in the source. `sequence` is the item's number counted from
+/// the start of its list, shown instead of the source number when
+/// `render.renumber_lists` is on.
+#[allow(clippy::too_many_arguments)]
+fn style_list_item(
+    line: &str,
+    marker: &ListMarker,
+    depth: usize,
+    sequence: Option<u64>,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    if marker.indent > 0 {
+        spans.push(Span::raw(" ".repeat(marker.indent)));
     }
 
-    None
+    let display_marker = mdx_core::render::list_marker(marker, depth, sequence, render_config);
+    // Deeper levels fade so the outline's structure stands out
+    let marker_style = if depth == 0 {
+        theme.list_marker
+    } else {
+        theme.list_marker.add_modifier(Modifier::DIM)
+    };
+    // A marker wider than the source one ("viii." for "1.") still gets a
+    // space before the content
+    let width = (marker.content_indent - marker.indent).max(display_marker.width() + 1);
+    spans.push(Span::styled(
+        format!("{:<width$}", display_marker, width = width),
+        marker_style,
+    ));
+
+    let content = line[marker.content_indent.min(line.len())..].trim_start();
+    spans.extend(style_inline_markdown(
+        content,
+        theme.base,
+        theme.code,
+        render_config,
+        links,
+        search_query,
+    ));
+    spans
 }
 
+/// Style a line on its own. Without the document around it, a line with
+/// a `|` is taken to be a table row.
 fn style_markdown_line(
     line: &str,
     theme: &crate::theme::Theme,
     render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    let html = render_config.strip_html && mdx_core::html::is_html_line(line);
+    if !html && line.contains('|') && render_config.markdown.flavor == MarkdownFlavor::Gfm {
+        return style_table_row(line, theme, render_config, links, search_query);
+    }
+    style_text_line(line, theme, render_config, links, search_query)
+}
+
+/// Style a line known not to be a table row
+fn style_text_line(
+    line: &str,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
     search_query: Option<&str>,
 ) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
 
+    // Raw HTML: show the text content rather than literal tags
+    if render_config.strip_html && mdx_core::html::is_html_line(line) {
+        return style_html_line(line, theme, render_config, links, search_query);
+    }
+
     // Check for horizontal rule
     let trimmed = line.trim();
     if (trimmed.chars().all(|c| c == '-') && trimmed.len() >= 3)
@@ -1919,135 +3495,44 @@ fn style_markdown_line(
         return spans;
     }
 
-    // Check for table row (contains |)
-    if line.contains('|') {
-        // Simple table rendering - split by | and style each cell
-        let parts: Vec<&str> = line.split('|').collect();
-        for (i, part) in parts.iter().enumerate() {
-            if i > 0 {
-                let separator = if render_config.use_utf8_graphics {
-                    "│" // UTF-8 box-drawing vertical line
-                } else {
-                    "|"
-                };
-                spans.push(Span::styled(
-                    separator.to_string(),
-                    Style::default().fg(Color::Cyan),
-                ));
-            }
-            // Check if this is a separator row (contains only -, :, and spaces)
-            let is_separator = part
-                .trim()
-                .chars()
-                .all(|c| c == '-' || c == ':' || c == ' ');
-            if is_separator && !part.trim().is_empty() {
-                let separator_text = if render_config.use_utf8_graphics {
-                    // Convert alignment markers to UTF-8 table separators
-                    let trimmed = part.trim();
-                    let left_align = trimmed.starts_with(':');
-                    let right_align = trimmed.ends_with(':');
-
-                    let leading_spaces = part.len() - part.trim_start().len();
-                    let trailing_spaces = part.len().saturating_sub(leading_spaces + trimmed.len());
-
-                    let mut result = String::new();
-                    if leading_spaces > 0 {
-                        result.push_str(&" ".repeat(leading_spaces));
-                    }
-
-                    if left_align && right_align {
-                        result.push(':');
-                        result.push_str(&"─".repeat(trimmed.len().saturating_sub(2)));
-                        result.push(':');
-                    } else if left_align {
-                        result.push(':');
-                        result.push_str(&"─".repeat(trimmed.len().saturating_sub(1)));
-                    } else if right_align {
-                        result.push_str(&"─".repeat(trimmed.len().saturating_sub(1)));
-                        result.push(':');
-                    } else {
-                        result.push_str(&"─".repeat(trimmed.len()));
-                    }
-
-                    if trailing_spaces > 0 {
-                        result.push_str(&" ".repeat(trailing_spaces));
-                    }
-                    result
-                } else {
-                    part.to_string()
-                };
-                spans.push(Span::styled(
-                    separator_text,
-                    Style::default().fg(Color::DarkGray),
-                ));
-            } else {
-                // Style content within cell
-                spans.extend(style_inline_markdown(
-                    part,
-                    theme.base,
-                    theme.code,
-                    search_query,
-                ));
-            }
-        }
-        return spans;
-    }
-
-    // Check for list item (unordered: -, *, +)
-    let list_pattern = if let Some(rest) = line.trim_start().strip_prefix("- ") {
-        Some(("- ", rest, line.len() - line.trim_start().len()))
-    } else if let Some(rest) = line.trim_start().strip_prefix("* ") {
-        Some(("* ", rest, line.len() - line.trim_start().len()))
-    } else if let Some(rest) = line.trim_start().strip_prefix("+ ") {
-        Some(("+ ", rest, line.len() - line.trim_start().len()))
-    } else {
-        // Check for ordered list (number followed by . or ))
-        let trimmed_start = line.trim_start();
-        if let Some(pos) = trimmed_start.find(|c| c == '.' || c == ')') {
-            let prefix = &trimmed_start[..pos];
-            if prefix.chars().all(|c| c.is_ascii_digit()) && trimmed_start.len() > pos + 1 {
-                let rest = &trimmed_start[pos + 2..]; // Skip ". " or ") "
-                let marker = &trimmed_start[..pos + 2];
-                Some((marker, rest, line.len() - line.trim_start().len()))
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    };
-
-    if let Some((marker, content, indent)) = list_pattern {
-        // Add indentation
-        if indent > 0 {
-            spans.push(Span::raw(" ".repeat(indent)));
-        }
-        // Add list marker with special color
-        let display_marker = if render_config.use_utf8_graphics {
-            // Use UTF-8 bullets for unordered lists
-            if marker.starts_with('-') || marker.starts_with('*') || marker.starts_with('+') {
-                "• ".to_string() // UTF-8 bullet point
-            } else {
-                // Keep numbered list markers as-is
-                marker.to_string()
-            }
+    // Definition list description: indent under the term
+    if let Some(content) = definition_content(line) {
+        let marker = if render_config.use_utf8_graphics {
+            "  ▸ "
         } else {
-            marker.to_string()
+            "  : "
         };
         spans.push(Span::styled(
-            display_marker,
-            Style::default().fg(Color::Yellow),
+            marker.to_string(),
+            Style::default().fg(Color::DarkGray),
         ));
-        // Style the rest as inline markdown
         spans.extend(style_inline_markdown(
             content,
             theme.base,
             theme.code,
+            render_config,
+            links,
             search_query,
         ));
         return spans;
     }
 
+    // Check for list item (unordered: -, *, +)
+    // Without the lines above for context, guess the depth from the indent
+    if let Some(marker) = ListMarker::parse(line) {
+        let depth = marker.indent / 2;
+        return style_list_item(
+            line,
+            &marker,
+            depth,
+            None,
+            theme,
+            render_config,
+            links,
+            search_query,
+        );
+    }
+
     // Check for heading
     let (is_heading, heading_level, content) = if let Some(stripped) = line.strip_prefix("# ") {
         (true, 1, stripped)
@@ -2065,13 +3550,15 @@ fn style_markdown_line(
         (false, 0, line)
     };
 
-    // If it's a heading, show the ## prefix with heading style
+    // If it's a heading, show the ## prefix with heading style, or say
+    // the level in words for screen readers
     if is_heading {
-        let prefix = &line[..(line.len() - content.len())];
-        spans.push(Span::styled(
-            prefix.to_string(),
-            theme.heading[heading_level - 1],
-        ));
+        let prefix = if render_config.a11y {
+            mdx_core::render::heading_label(heading_level as u8)
+        } else {
+            line[..(line.len() - content.len())].to_string()
+        };
+        spans.push(Span::styled(prefix, theme.heading[heading_level - 1]));
     }
 
     // For headings or regular text, parse inline markdown
@@ -2085,6 +3572,8 @@ fn style_markdown_line(
         content,
         base_style,
         theme.code,
+        render_config,
+        links,
         search_query,
     ));
 
@@ -2100,77 +3589,168 @@ fn style_markdown_line(
     spans
 }
 
-/// Style inline markdown (bold, italic, code) within text
-fn style_inline_markdown(
-    text: &str,
-    base_style: Style,
-    code_style: Style,
+/// Style a table row cell by cell, for rows not laid out as a whole table
+/// (the view starts partway through it)
+fn style_table_row(
+    line: &str,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
     search_query: Option<&str>,
 ) -> Vec<Span<'static>> {
-    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
-
     let mut spans = Vec::new();
-    let parser = Parser::new(text);
-    let mut in_bold = false;
-    let mut in_italic = false;
-
-    for event in parser {
-        match event {
-            Event::Start(Tag::Strong) => {
-                in_bold = true;
-            }
-            Event::End(TagEnd::Strong) => {
-                in_bold = false;
-            }
-            Event::Start(Tag::Emphasis) => {
-                in_italic = true;
-            }
-            Event::End(TagEnd::Emphasis) => {
-                in_italic = false;
-            }
-            Event::Text(content) => {
-                let mut style = base_style;
-                if in_bold {
-                    // Make bold text bright yellow for better visibility
-                    style = Style::default()
-                        .fg(Color::LightYellow)
-                        .add_modifier(Modifier::BOLD);
-                }
-                if in_italic {
-                    style = style.add_modifier(Modifier::ITALIC);
+    // Simple table rendering - split by | and style each cell
+    let parts: Vec<&str> = line.split('|').collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            let separator = if render_config.use_utf8_graphics {
+                "│" // UTF-8 box-drawing vertical line
+            } else {
+                "|"
+            };
+            spans.push(Span::styled(
+                separator.to_string(),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        // Check if this is a separator row (contains only -, :, and spaces)
+        let is_separator = part
+            .trim()
+            .chars()
+            .all(|c| c == '-' || c == ':' || c == ' ');
+        if is_separator && !part.trim().is_empty() {
+            let separator_text = if render_config.use_utf8_graphics {
+                // Convert alignment markers to UTF-8 table separators
+                let trimmed = part.trim();
+                let left_align = trimmed.starts_with(':');
+                let right_align = trimmed.ends_with(':');
+
+                let leading_spaces = part.len() - part.trim_start().len();
+                let trailing_spaces = part.len().saturating_sub(leading_spaces + trimmed.len());
+
+                let mut result = String::new();
+                if leading_spaces > 0 {
+                    result.push_str(&" ".repeat(leading_spaces));
                 }
 
-                // Apply search highlighting if query present
-                if let Some(query) = search_query {
-                    spans.extend(highlight_text_matches(&content, query, style));
+                if left_align && right_align {
+                    result.push(':');
+                    result.push_str(&"─".repeat(trimmed.len().saturating_sub(2)));
+                    result.push(':');
+                } else if left_align {
+                    result.push(':');
+                    result.push_str(&"─".repeat(trimmed.len().saturating_sub(1)));
+                } else if right_align {
+                    result.push_str(&"─".repeat(trimmed.len().saturating_sub(1)));
+                    result.push(':');
                 } else {
-                    spans.push(Span::styled(content.to_string(), style));
+                    result.push_str(&"─".repeat(trimmed.len()));
                 }
-            }
-            Event::Code(code) => {
-                // Apply search highlighting to code if query present
-                if let Some(query) = search_query {
-                    spans.extend(highlight_text_matches(&code, query, code_style));
-                } else {
-                    spans.push(Span::styled(code.to_string(), code_style));
+
+                if trailing_spaces > 0 {
+                    result.push_str(&" ".repeat(trailing_spaces));
                 }
-            }
-            _ => {}
+                result
+            } else {
+                part.to_string()
+            };
+            spans.push(Span::styled(
+                separator_text,
+                Style::default().fg(Color::DarkGray),
+            ));
+        } else {
+            // Style content within cell
+            spans.extend(style_inline_markdown(
+                part,
+                theme.base,
+                theme.code,
+                render_config,
+                links,
+                search_query,
+            ));
         }
     }
+    spans
+}
+
+/// Style a raw HTML line as its text content. `<summary>` lines get an
+/// expanded-fold marker since their `<details>` block is open.
+fn style_html_line(
+    line: &str,
+    theme: &crate::theme::Theme,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    let text = mdx_core::html::strip_tags(line);
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let mut spans = Vec::new();
+    let base_style = if line.to_ascii_lowercase().contains("<summary") {
+        spans.push(Span::styled(
+            "▼ ",
+            Style::default().fg(theme.collapsed_indicator_fg),
+        ));
+        theme.base.add_modifier(Modifier::BOLD)
+    } else {
+        theme.base
+    };
+    spans.extend(style_inline_markdown(
+        text,
+        base_style,
+        theme.code,
+        render_config,
+        links,
+        search_query,
+    ));
+    spans
+}
 
+/// Style inline markdown (bold, italic, code) within text
+fn style_inline_markdown(
+    text: &str,
+    base_style: Style,
+    code_style: Style,
+    render_config: &mdx_core::config::RenderConfig,
+    links: &mdx_core::links::LinkDefinitions,
+    search_query: Option<&str>,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for span in mdx_core::render::inline(text, Role::Text, render_config, links) {
+        let style = match span.style.role {
+            Role::InlineCode => code_style,
+            Role::Comment => crate::theme::COMMENT_STYLE,
+            _ => crate::theme::emphasize(base_style, span.style),
+        };
+        // Apply search highlighting if query present
+        if let Some(query) = search_query {
+            spans.extend(highlight_text_matches(&span.text, query, style));
+        } else {
+            spans.push(Span::styled(span.text, style));
+        }
+    }
     spans
 }
 
-fn render_toc(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+fn render_toc(
+    frame: &mut Frame,
+    app: &App,
+    area: ratatui::layout::Rect,
+    stats: Option<&mdx_core::stats::DocumentStats>,
+) {
     // Get current heading index to highlight
     let current_heading = app.current_heading_index();
 
-    // Calculate visible TOC height (account for borders)
+    // Calculate visible TOC height and width (account for borders)
     let toc_height = area.height.saturating_sub(2) as usize;
+    let toc_width = area.width.saturating_sub(2) as usize;
     let scroll = app.toc_scroll;
 
     // Build visible TOC lines with indentation based on heading level
+    let numbers = heading_numbers(app);
     let toc_lines: Vec<Line> = app
         .doc
         .headings
@@ -2181,7 +3761,11 @@ fn render_toc(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .map(|(idx, heading)| {
             // Indent based on level (2 spaces per level, starting from level 1)
             let indent = "  ".repeat((heading.level as usize).saturating_sub(1));
-            let text = format!("{}{}", indent, heading.text);
+            let number = numbers.as_ref().map_or(String::new(), |n| format!("{} ", n[idx]));
+            let mut text = format!("{}{}{}", indent, number, heading.text);
+            if let Some(section) = stats.and_then(|stats| stats.section(idx)) {
+                text = with_section_size(&text, section, toc_width);
+            }
 
             // Highlight selected or current heading
             if app.toc_focus && idx == app.toc_selected {
@@ -2217,6 +3801,7 @@ fn render_toc(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(border_style)
                 .title(title),
         )
@@ -2225,7 +3810,49 @@ fn render_toc(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     frame.render_widget(toc_widget, area);
 }
 
-fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+/// A TOC entry with its section's size right-aligned after it, e.g.
+/// `Setup      40L 1.2kW 2C`, shortening the heading to make room
+fn with_section_size(
+    entry: &str,
+    section: &mdx_core::stats::SectionStats,
+    width: usize,
+) -> String {
+    let count = |n: usize| match n {
+        0..=999 => n.to_string(),
+        _ => format!("{:.1}k", n as f64 / 1000.0),
+    };
+    let mut size = format!("{}L {}W", count(section.total_lines), count(section.total_words));
+    if section.total_code_blocks > 0 {
+        size.push_str(&format!(" {}C", section.total_code_blocks));
+    }
+    let room = width.saturating_sub(size.width() + 1);
+    let entry = if entry.width() > room {
+        format!("{}…", truncate_to_width(entry, room.saturating_sub(1)))
+    } else {
+        entry.to_string()
+    };
+    let pad = width.saturating_sub(entry.width() + size.width()).max(1);
+    format!("{}{}{}", entry, " ".repeat(pad), size)
+}
+
+fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect, position: &str) {
+    // A pending question replaces everything else until it is answered
+    if let Some(prompt) = &app.confirm {
+        let style = Style::default()
+            .fg(app.theme.status_bar_fg)
+            .bg(app.theme.status_bar_bg);
+        let status = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{} ", prompt.question),
+                style.fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(prompt.action.choices(), style),
+        ]))
+        .style(style);
+        frame.render_widget(status, area);
+        return;
+    }
+
     // Check if we're in visual command mode
     let in_visual_command_mode = if let Some(pane) = app.panes.focused_pane() {
         pane.view.mode == crate::app::Mode::VisualCommand
@@ -2254,6 +3881,23 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
         return;
     }
 
+    // `:` command line
+    if app
+        .panes
+        .focused_pane()
+        .is_some_and(|p| p.view.mode == crate::app::Mode::Command)
+    {
+        let status = Paragraph::new(Line::from(vec![Span::styled(
+            format!(":{}", app.command_line),
+            Style::default()
+                .fg(app.theme.status_bar_fg)
+                .bg(app.theme.status_bar_bg)
+                .add_modifier(Modifier::BOLD),
+        )]));
+        frame.render_widget(status, area);
+        return;
+    }
+
     // Check if we're in search mode
     let in_search_mode = if let Some(pane) = app.panes.focused_pane() {
         pane.view.mode == crate::app::Mode::Search
@@ -2263,21 +3907,29 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
 
     // In search mode, show search input
     if in_search_mode {
+        let delimiter = if app.search_backward { '?' } else { '/' };
         let search_prompt = if !app.search_matches.is_empty() {
             if let Some(current_idx) = app.search_current_match {
                 format!(
-                    "/{} [{}/{}] ",
-                    app.search_query,
+                    "{}{} [{}/{}]{} ",
+                    delimiter,
+                    app.search_input,
                     current_idx + 1,
-                    app.search_matches.len()
+                    app.search_match_count(),
+                    app.search_scope_badge()
                 )
             } else {
-                format!("/{} ", app.search_query)
+                format!("{}{}{} ", delimiter, app.search_input, app.search_scope_badge())
             }
         } else if !app.search_query.is_empty() {
-            format!("/{} [no matches] ", app.search_query)
+            format!(
+                "{}{} [no matches]{} ",
+                delimiter,
+                app.search_input,
+                app.search_scope_badge()
+            )
         } else {
-            "/".to_string()
+            format!("{}{}{}", delimiter, app.search_input, app.search_scope_badge())
         };
 
         let status = Paragraph::new(Line::from(vec![Span::styled(
@@ -2299,6 +3951,11 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("untitled");
+    // A git revision opened with `--rev` is named after it
+    let filename = match &app.doc.revision {
+        Some(rev) => format!("{}@{}", filename, rev),
+        None => filename.to_string(),
+    };
 
     let line_count = app.doc.line_count();
     let heading_count = app.doc.headings.len();
@@ -2322,88 +3979,14 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
                 ("CMD", count)
             }
             crate::app::Mode::Search => ("SEARCH", None),
+            crate::app::Mode::Insert => ("INSERT", None),
+            crate::app::Mode::Command => ("COMMAND", None),
         };
         (line, mode, sel_count)
     } else {
         (1, "NORMAL", None)
     };
 
-    let toc_indicator = if app.show_toc {
-        if app.toc_focus {
-            " [TOC*]"
-        } else {
-            " [TOC]"
-        }
-    } else {
-        ""
-    };
-
-    let theme_str = match app.theme_variant {
-        mdx_core::config::ThemeVariant::Dark => "DARK",
-        mdx_core::config::ThemeVariant::Light => "LIGHT",
-    };
-
-    let prefix_str = match app.key_prefix {
-        crate::app::KeyPrefix::None => "",
-        crate::app::KeyPrefix::CtrlW => "  ^W-",
-        crate::app::KeyPrefix::Z => "  z-",
-        crate::app::KeyPrefix::G => "  g-",
-    };
-
-    let fold_indicator = if app.is_cursor_under_collapsed_heading() {
-        "  [COLLAPSED]"
-    } else if app.is_cursor_on_heading() {
-        "  [FOLDABLE]"
-    } else {
-        // Check if cursor is anywhere under a foldable section
-        if let Some(pane) = app.panes.focused_pane() {
-            let cursor_line = pane.view.cursor_line;
-            // Find nearest heading above
-            let has_heading_above = app.doc.headings.iter().any(|h| h.line <= cursor_line);
-            if has_heading_above {
-                "  [IN SECTION]"
-            } else {
-                ""
-            }
-        } else {
-            ""
-        }
-    };
-
-    let selection_str = if let Some(count) = selection_count {
-        format!(" ({} lines)", count)
-    } else {
-        String::new()
-    };
-
-    #[cfg(feature = "watch")]
-    let watch_str = if app.watcher.is_some() {
-        if app.doc.dirty_on_disk {
-            "  [DIRTY]"
-        } else {
-            "  [WATCH]"
-        }
-    } else {
-        ""
-    };
-    #[cfg(not(feature = "watch"))]
-    let watch_str = "";
-
-    let search_str = if !app.search_query.is_empty() {
-        if let Some(current_idx) = app.search_current_match {
-            format!(
-                "  /{} ({}/{})",
-                app.search_query,
-                current_idx + 1,
-                app.search_matches.len()
-            )
-        } else {
-            format!("  /{} (no matches)", app.search_query)
-        }
-    } else {
-        String::new()
-    };
-
     // If there's a status message, display it prominently
     if let Some((message, kind)) = &app.status_message {
         use ratatui::style::Color;
@@ -2427,23 +4010,132 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect)
         return;
     }
 
-    // Normal status bar
-    let status_text = format!(
-        " mdx  {}  {} lines  {} headings  {}:{}/{}  [{}{}]{}  [{}]{}{}{}{}",
-        filename,
-        line_count,
-        heading_count,
-        filename,
-        current_line,
-        line_count,
-        mode_str,
-        selection_str,
-        toc_indicator,
-        theme_str,
-        prefix_str,
-        watch_str,
-        search_str,
-        fold_indicator
+    // Normal status bar, laid out by the `[statusbar]` templates
+    let column = match app.panes.focused_pane() {
+        Some(pane) if pane.view.mode == crate::app::Mode::Insert => pane.view.edit_col + 1,
+        _ => 1,
+    };
+    let token = |name: &str| -> Option<String> {
+        Some(match name {
+            "file" => filename.to_string(),
+            "modified" if app.doc.revision.is_some() => "[RO]".to_string(),
+            "modified" => if app.doc.modified { "[+]" } else { "" }.to_string(),
+            "encoding" => match app.doc.decoding.label() {
+                "" => String::new(),
+                label => format!("[{}]", label),
+            },
+            "buffer" if app.buffer_count() > 1 && !app.tab_bar_visible() => {
+                format!("({}/{})", app.active_buffer + 1, app.buffer_count())
+            }
+            "buffer" => String::new(),
+            "line" => current_line.to_string(),
+            "lines" => line_count.to_string(),
+            "column" => column.to_string(),
+            "percent" => format!("{}%", current_line * 100 / line_count.max(1)),
+            "position" => position.to_string(),
+            "mode" => mode_str.to_string(),
+            "selection" => selection_count.map_or(String::new(), |n| format!("({} lines)", n)),
+            "headings" => heading_count.to_string(),
+            "theme" => app.theme_variant.name().to_uppercase(),
+            "toc" => match (app.show_toc, app.toc_focus) {
+                (false, _) => "",
+                (true, false) => "[TOC]",
+                (true, true) => "[TOC*]",
+            }
+            .to_string(),
+            "prefix" => {
+                let prefix = match app.key_prefix {
+                    crate::app::KeyPrefix::None => "",
+                    crate::app::KeyPrefix::CtrlW => "^W-",
+                    crate::app::KeyPrefix::Z => "z-",
+                    crate::app::KeyPrefix::G => "g-",
+                    crate::app::KeyPrefix::LeftBracket => "[-",
+                    crate::app::KeyPrefix::RightBracket => "]-",
+                    crate::app::KeyPrefix::Around => "a-",
+                    crate::app::KeyPrefix::Inner => "i-",
+                    crate::app::KeyPrefix::Y => "y-",
+                };
+                // A pending count goes in front, like vim's showcmd: `3]-`
+                app.pending_count.map_or(String::new(), |n| n.to_string()) + prefix
+            }
+            #[cfg(feature = "watch")]
+            "watch" => match (app.watcher.is_some(), app.doc.dirty_on_disk) {
+                (false, _) => "",
+                (true, false) => "[WATCH]",
+                (true, true) => "[DIRTY]",
+            }
+            .to_string(),
+            #[cfg(not(feature = "watch"))]
+            "watch" => String::new(),
+            "search" if app.search_query.is_empty() => String::new(),
+            "search" => {
+                let delimiter = if app.search_backward { '?' } else { '/' };
+                match app.search_current_match {
+                    Some(current_idx) => format!(
+                        "{}{} ({}/{}){}",
+                        delimiter,
+                        app.search_query,
+                        current_idx + 1,
+                        app.search_match_count(),
+                        app.search_scope_badge()
+                    ),
+                    None => format!(
+                        "{}{} (no matches){}",
+                        delimiter,
+                        app.search_query,
+                        app.search_scope_badge()
+                    ),
+                }
+            }
+            "fold" => {
+                let cursor_line = app.panes.focused_pane().map(|p| p.view.cursor_line);
+                if app.is_cursor_under_collapsed_heading() {
+                    "[COLLAPSED]"
+                } else if app.is_cursor_on_heading() {
+                    "[FOLDABLE]"
+                } else if cursor_line
+                    .is_some_and(|line| app.doc.headings.iter().any(|h| h.line <= line))
+                {
+                    "[IN SECTION]"
+                } else {
+                    ""
+                }
+                .to_string()
+            }
+            "links" => match app.broken_links.len() {
+                0 => String::new(),
+                1 => "[1 BROKEN LINK]".to_string(),
+                n => format!("[{} BROKEN LINKS]", n),
+            },
+            "spell" => match app.misspellings.len() {
+                0 => String::new(),
+                1 => "[1 MISSPELLING]".to_string(),
+                n => format!("[{} MISSPELLINGS]", n),
+            },
+            #[cfg(feature = "git")]
+            "git" => match app.hunk_position() {
+                Some((0, count)) => format!("[{} HUNK{}]", count, if count == 1 { "" } else { "S" }),
+                Some((current, count)) => format!("[HUNK {}/{}]", current, count),
+                None => String::new(),
+            },
+            #[cfg(not(feature = "git"))]
+            "git" => String::new(),
+            _ => return None,
+        })
+    };
+    let statusbar = &app.config.statusbar;
+    let mut right = crate::statusbar::expand(&statusbar.right, token);
+    if statusbar.ruler {
+        right.push_str(&format!(
+            "{:<14}{:>4} ",
+            format!("{},{}", current_line, column),
+            position
+        ));
+    }
+    let status_text = crate::statusbar::layout(
+        &crate::statusbar::expand(&statusbar.left, token),
+        &right,
+        area.width as usize,
     );
 
     let status = Paragraph::new(Line::from(vec![Span::styled(
@@ -2485,6 +4177,7 @@ fn render_command_output(frame: &mut Frame, app: &App) {
 
         let block = Block::default()
             .borders(Borders::ALL)
+            .border_set(border_set(app))
             .border_style(Style::default().fg(Color::LightBlue))
             .title(" Command Output ");
 
@@ -2495,7 +4188,90 @@ fn render_command_output(frame: &mut Frame, app: &App) {
     }
 }
 
-fn render_help_popup(frame: &mut Frame, _app: &App) {
+fn render_block_output(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let Some(output) = &app.block_output else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    match &output.result {
+        None => lines.push(Line::from(Span::styled(
+            "Running…",
+            Style::default().fg(Color::DarkGray),
+        ))),
+        Some(Ok(result)) => {
+            for line in result.stdout.lines() {
+                lines.push(Line::from(sanitize_for_terminal(line)));
+            }
+            if !result.stderr.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "--- stderr ---",
+                    Style::default().fg(Color::DarkGray),
+                )));
+                for line in result.stderr.lines() {
+                    lines.push(Line::from(Span::styled(
+                        sanitize_for_terminal(line),
+                        Style::default().fg(Color::LightRed),
+                    )));
+                }
+            }
+            if result.stdout.is_empty() && result.stderr.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "<no output>",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+        Some(Err(e)) => lines.push(Line::from(Span::styled(
+            e.clone(),
+            Style::default().fg(Color::LightRed),
+        ))),
+    }
+
+    let status = match &output.result {
+        None => Span::styled(" running ", Style::default().fg(Color::Yellow)),
+        Some(Ok(result)) if result.timed_out => Span::styled(
+            format!(" killed after {}s ", crate::app::RUN_BLOCK_TIMEOUT.as_secs()),
+            Style::default().fg(Color::LightRed),
+        ),
+        Some(Ok(result)) => match result.code {
+            Some(0) => Span::styled(" exit 0 ", Style::default().fg(Color::Green)),
+            Some(code) => Span::styled(
+                format!(" exit {} ", code),
+                Style::default().fg(Color::LightRed),
+            ),
+            None => Span::styled(" killed by signal ", Style::default().fg(Color::LightRed)),
+        },
+        Some(Err(_)) => Span::styled(" failed to start ", Style::default().fg(Color::LightRed)),
+    };
+
+    // Show the end of long output, where errors usually are
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let skip = lines.len().saturating_sub(inner_height);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(Color::LightBlue))
+        .title(format!(
+            " {} block at line {} ",
+            output.lang,
+            output.start_line + 1
+        ))
+        .title_bottom(Line::from(vec![
+            status,
+            Span::styled(" X run block · Esc close ", Style::default().fg(Color::DarkGray)),
+        ]));
+
+    let paragraph = Paragraph::new(lines.split_off(skip))
+        .block(block)
+        .style(app.theme.base);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+fn render_help_popup(frame: &mut Frame, app: &mut App) {
     use ratatui::widgets::{Clear, Paragraph};
 
     // Create a centered popup area
@@ -2510,136 +4286,953 @@ fn render_help_popup(frame: &mut Frame, _app: &App) {
         height: popup_height,
     };
 
-    // Help text content
-    let help_lines = vec![
-        Line::from(vec![Span::styled(
-            "MDX - Keyboard Commands",
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Navigation",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  j/k, ↓/↑          Move cursor down/up"),
-        Line::from("  Ctrl+d/u          Scroll half page down/up"),
-        Line::from("  Space, PgDn       Scroll full page down"),
-        Line::from("  PgUp              Scroll full page up"),
-        Line::from("  g, Home           Go to top"),
-        Line::from("  G, End            Go to bottom"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Search",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  /                 Start search"),
-        Line::from("  n                 Next match"),
-        Line::from("  N                 Previous match"),
-        Line::from("  Esc               Cancel search"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Visual Mode",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  V                 Enter visual line mode"),
-        Line::from("  Y                 Yank (copy) selected lines"),
-        Line::from("  Esc               Exit visual mode"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Folding",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  ←                 Collapse current section"),
-        Line::from("  →                 Expand current section"),
-        Line::from("  za                Toggle fold of current section"),
-        Line::from("  zo                Open fold of current section"),
-        Line::from("  zc                Close fold of current section"),
-        Line::from("  zM                Close all folds"),
-        Line::from("  zR                Open all folds"),
-        Line::from("  Note: Works on heading or anywhere in section"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Panes",
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  Ctrl+w s          Split horizontally"),
-        Line::from("  Ctrl+w v          Split vertically"),
-        Line::from("  Ctrl+w hjkl/↑↓←→  Move focus between panes"),
-        Line::from("  Ctrl+↑↓←→         Move focus between panes"),
-        Line::from("  q                 Close pane (quit if last)"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Mouse",
+    // Help text content, narrowed by the search query
+    let mut help_lines = vec![Line::from(vec![Span::styled(
+        "MDX - Keyboard Commands",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )])];
+    if app.help_searching || !app.help_query.is_empty() {
+        let cursor = if app.help_searching { "_" } else { "" };
+        help_lines.push(Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}{}", app.help_query, cursor)),
+        ]));
+    }
+    let sections = crate::help::matching_sections(&app.help_query);
+    if sections.is_empty() {
+        help_lines.push(Line::from(""));
+        help_lines.push(Line::from(Span::styled(
+            "  No matching keys",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (section, entries) in sections {
+        help_lines.push(Line::from(""));
+        help_lines.push(Line::from(vec![Span::styled(
+            section.title,
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  Click pane        Focus pane and move cursor"),
-        Line::from("  Click+drag        Select text (line-based)"),
-        Line::from("  Ctrl+Shift+C      Copy selection to clipboard"),
-        Line::from("  Click TOC         Jump to heading"),
-        Line::from("  Scroll wheel      Scroll pane or TOC"),
-        Line::from("  Drag border       Resize split panes"),
-        Line::from(""),
-        Line::from(vec![Span::styled(
-            "Other",
+        )]));
+        for entry in &entries {
+            let keys = entry.keys.text();
+            let pad = crate::help::KEY_COLUMN_WIDTH
+                .saturating_sub(keys.width())
+                .max(1);
+            help_lines.push(Line::from(format!(
+                "  {}{}{}",
+                keys,
+                " ".repeat(pad),
+                entry.description
+            )));
+        }
+        if let Some(note) = section.note.filter(|_| entries.len() == section.entries.len()) {
+            help_lines.push(Line::from(format!("  Note: {}", note)));
+        }
+    }
+
+    // Keep the last page in view when scrolled past the end
+    let inner_height = popup_height.saturating_sub(2) as usize;
+    app.help_scroll = app
+        .help_scroll
+        .min(help_lines.len().saturating_sub(inner_height));
+    let scroll = app.help_scroll;
+    let title = if app.help_searching {
+        " Help - type to filter, Enter to keep, Esc to clear "
+    } else if help_lines.len() > inner_height {
+        " Help - j/k to scroll, / to search, F1 or Esc to close "
+    } else {
+        " Help - / to search, F1 or Esc to close "
+    };
+
+    // Clear the background
+    frame.render_widget(Clear, popup_area);
+
+    // Render the popup
+    let popup = Paragraph::new(help_lines)
+        .scroll((scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_toc_dialog(
+    frame: &mut Frame,
+    app: &App,
+    stats: Option<&mdx_core::stats::DocumentStats>,
+) {
+    use ratatui::widgets::Clear;
+
+    // Create a full-screen popup area with small margins
+    let area = frame.area();
+    let popup_width = area.width.saturating_sub(4);
+    let popup_height = area.height.saturating_sub(4);
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    let title = if app.toc_dialog_filtering {
+        " Table of Contents - type to filter, Enter to keep, Esc to clear "
+    } else {
+        " Table of Contents - j/k to navigate, / to filter, Enter to jump, T/Esc to close "
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(title)
+        .title_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
-        )]),
-        Line::from("  t                 Toggle TOC sidebar"),
-        Line::from("  T                 Open TOC dialog (full screen)"),
-        Line::from("  m                 Toggle theme (dark/light)"),
-        Line::from("  O                 Open options dialog"),
-        Line::from("  W                 Toggle security warnings pane"),
-        Line::from("  e                 Open in $EDITOR"),
-        Line::from("  r                 Toggle raw/rendered mode"),
-        Line::from("  R                 Reload document"),
-        Line::from("  Ctrl+L            Redraw/refresh screen"),
-        Line::from("  ?                 Toggle this help"),
-        Line::from("  Ctrl+C            Force quit"),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+    let inner = block.inner(popup_area);
+
+    // Clear the background
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(block, popup_area);
+
+    // The filter takes a row above the list while one is set
+    let list_area = if app.toc_dialog_filtering || !app.toc_dialog_query.is_empty() {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(inner);
+        let cursor = if app.toc_dialog_filtering { "_" } else { "" };
+        let query = Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}{}", app.toc_dialog_query, cursor)),
+        ]);
+        frame.render_widget(Paragraph::new(query), rows[0]);
+        rows[1]
+    } else {
+        inner
+    };
+
+    // Preview only when both columns stay readable
+    let columns = if list_area.width >= 60 {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(list_area)
+            .to_vec()
+    } else {
+        vec![list_area]
+    };
+
+    // Build visible TOC lines with indentation based on heading level
+    let toc_height = columns[0].height as usize;
+    let list_width = columns[0].width as usize;
+    let scroll = app.toc_dialog_scroll;
+    let numbers = heading_numbers(app);
+    let headings = app.toc_dialog_headings();
+    let mut toc_lines: Vec<Line> = headings
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(toc_height)
+        .map(|(pos, &idx)| {
+            let heading = &app.doc.headings[idx];
+            // Indent based on level (2 spaces per level, starting from level 1)
+            let indent = "  ".repeat((heading.level as usize).saturating_sub(1));
+            let number = numbers.as_ref().map_or(String::new(), |n| format!("{} ", n[idx]));
+            let mut text = format!("{}{}{}", indent, number, heading.text);
+            if let Some(section) = stats.and_then(|stats| stats.section(idx)) {
+                text = with_section_size(&text, section, list_width);
+            }
+
+            // Highlight selected item
+            if pos == app.toc_dialog_selected {
+                Line::from(text).style(
+                    Style::default()
+                        .bg(Color::Cyan)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Line::from(text).style(Style::default().fg(Color::White))
+            }
+        })
+        .collect();
+    if headings.is_empty() {
+        toc_lines.push(Line::from(Span::styled(
+            "  No matching headings",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    frame.render_widget(Paragraph::new(toc_lines), columns[0]);
+
+    // Preview the opening lines of the selected heading's section
+    let selected = headings.get(app.toc_dialog_selected);
+    let (Some(preview_area), Some(&idx)) = (columns.get(1), selected) else {
+        return;
+    };
+    let section = app.section_range_at(app.doc.headings[idx].line);
+    let preview: Vec<Line> = section
+        .filter(|&line| !is_hidden_comment(app, &app.doc, line))
+        .take(preview_area.height as usize)
+        .map(|line| {
+            let text = app.doc.rope.line(line).to_string();
+            Line::from(style_markdown_line(
+                text.trim_end_matches(['\n', '\r']),
+                &app.theme,
+                &app.config.render,
+                &app.doc.link_definitions,
+                None,
+            ))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(preview).block(
+            Block::default()
+                .borders(Borders::LEFT)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        *preview_area,
+    );
+}
+
+fn render_breadcrumb_popup(frame: &mut Frame, app: &App) {
+    use ratatui::widgets::Clear;
+
+    let path = app.breadcrumb_heading_indices(app.panes.focused);
+    if path.is_empty() {
+        return;
+    }
+
+    // Build one line per ancestor, indented by depth in the path
+    let lines: Vec<Line> = path
+        .iter()
+        .enumerate()
+        .map(|(depth, &heading_idx)| {
+            let heading = &app.doc.headings[heading_idx];
+            let text = format!(
+                " {}{} {}",
+                "  ".repeat(depth),
+                "#".repeat(heading.level as usize),
+                heading.text
+            );
+            if depth == app.breadcrumb_popup_selected {
+                Line::from(text).style(
+                    Style::default()
+                        .bg(Color::Cyan)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Line::from(text).style(Style::default().fg(Color::White))
+            }
+        })
+        .collect();
+
+    // Size the popup to its content, centered on screen
+    let area = frame.area();
+    let title = " Sections - Enter to jump, Esc to close ";
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .chain(std::iter::once(title.width()))
+        .max()
+        .unwrap_or(0);
+    let popup_width = (content_width as u16 + 3).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+/// Tabs that fit in `width` columns, as (buffer index, columns, label).
+/// The row scrolls so the shown document's tab is always on screen.
+pub(crate) fn tab_layout(app: &App, width: u16) -> Vec<(usize, std::ops::Range<u16>, String)> {
+    let labels: Vec<String> = app
+        .buffer_docs()
+        .enumerate()
+        .map(|(idx, doc)| {
+            format!(
+                " {} {}{} ",
+                idx + 1,
+                app.buffer_name(doc),
+                buffer_marks(app, idx, doc)
+            )
+        })
+        .collect();
+    // Each tab is followed by a one-column gap
+    let widths: Vec<usize> = labels.iter().map(|label| label.width() + 1).collect();
+
+    let mut first = 0;
+    while first < app.active_buffer
+        && widths[first..=app.active_buffer].iter().sum::<usize>() > width as usize
+    {
+        first += 1;
+    }
+
+    let mut tabs = Vec::new();
+    let mut x = 0usize;
+    for (idx, label) in labels.into_iter().enumerate().skip(first) {
+        if x >= width as usize {
+            break;
+        }
+        let end = (x + label.width()).min(width as usize);
+        tabs.push((idx, x as u16..end as u16, label));
+        x += widths[idx];
+    }
+    tabs
+}
+
+/// Suffix flagging unsaved edits (`[+]`) and changes on disk since the
+/// buffer was last shown (`*`)
+fn buffer_marks(app: &App, index: usize, doc: &mdx_core::Document) -> &'static str {
+    match (doc.modified, app.buffer_changed(index)) {
+        (true, true) => " [+]*",
+        (true, false) => " [+]",
+        (false, true) => " *",
+        (false, false) => "",
+    }
+}
+
+fn render_tab_bar(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let inactive = Style::default()
+        .fg(app.theme.status_bar_fg)
+        .bg(app.theme.status_bar_bg);
+    let active = app.theme.toc_active.add_modifier(Modifier::BOLD);
+
+    let mut spans = Vec::new();
+    let mut x = 0;
+    for (idx, columns, label) in tab_layout(app, area.width) {
+        if columns.start > x {
+            spans.push(Span::raw(" ".repeat((columns.start - x) as usize)));
+        }
+        let style = if idx == app.active_buffer { active } else { inactive };
+        spans.push(Span::styled(label, style));
+        x = columns.end;
+    }
+
+    let bar = Paragraph::new(Line::from(spans)).style(Style::default().bg(app.theme.toc_bg));
+    frame.render_widget(bar, area);
+}
+
+fn render_buffer_picker(frame: &mut Frame, app: &App) {
+    use ratatui::widgets::Clear;
+
+    let lines: Vec<Line> = app
+        .buffer_docs()
+        .enumerate()
+        .map(|(idx, doc)| {
+            let marker = if idx == app.active_buffer { '%' } else { ' ' };
+            let text = format!(
+                " {:>2} {} {}{} ",
+                idx + 1,
+                marker,
+                app.buffer_name(doc),
+                buffer_marks(app, idx, doc)
+            );
+            if idx == app.buffer_picker_selected {
+                Line::from(text).style(
+                    Style::default()
+                        .bg(Color::Cyan)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Line::from(text).style(Style::default().fg(Color::White))
+            }
+        })
+        .collect();
+
+    // Size the popup to its content, centered on screen
+    let area = frame.area();
+    let title = " Buffers - Enter to open, d to close file, Esc ";
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .chain(std::iter::once(title.width()))
+        .max()
+        .unwrap_or(0);
+    let popup_width = (content_width as u16 + 3).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    // Keep the selection visible in long lists
+    let visible = popup_height.saturating_sub(2) as usize;
+    let scroll = app.buffer_picker_selected.saturating_sub(visible.saturating_sub(1));
+
+    let popup = Paragraph::new(lines)
+        .scroll((scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+/// Commits that touched the document: id, date, author, and subject
+#[cfg(feature = "git")]
+fn render_git_log(frame: &mut Frame, app: &App, log: &crate::git_log::GitLog) {
+    use ratatui::widgets::Clear;
+
+    let area = frame.area();
+    let author_width = log
+        .commits
+        .iter()
+        .map(|commit| commit.author.width())
+        .max()
+        .unwrap_or(0)
+        .min(20);
+    let lines: Vec<Line> = log
+        .commits
+        .iter()
+        .enumerate()
+        .map(|(idx, commit)| {
+            let author: String = commit.author.chars().take(author_width).collect();
+            let text = format!(
+                " {} {} {:<author_width$}  {} ",
+                commit.short_id(),
+                commit.date,
+                author,
+                commit.summary
+            );
+            let text = sanitize_for_terminal(&text);
+            if idx == log.selected {
+                Line::from(text).style(
+                    Style::default()
+                        .bg(Color::Cyan)
+                        .fg(Color::Black)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Line::from(text).style(Style::default().fg(Color::White))
+            }
+        })
+        .collect();
+
+    let title = format!(
+        " Git log: {} - Enter to compare, Esc ",
+        app.doc
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    );
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .chain(std::iter::once(title.width()))
+        .max()
+        .unwrap_or(0);
+    let popup_width = (content_width as u16 + 3).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    // Keep the selection visible in long lists
+    let visible = popup_height.saturating_sub(2) as usize;
+    let scroll = log.selected.saturating_sub(visible.saturating_sub(1));
+
+    let popup = Paragraph::new(lines)
+        .scroll((scroll as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(sanitize_for_terminal(&title))
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+/// Fuzzy finder: query, ranked matches, and a preview of the selected
+/// entry's destination when the screen is wide enough
+fn render_finder(frame: &mut Frame, app: &App, finder: &crate::finder::Finder) {
+    use ratatui::widgets::Clear;
+
+    let area = frame.area();
+    let popup_width = (area.width * 4 / 5).min(120).min(area.width.saturating_sub(4));
+    let popup_height = (area.height * 7 / 10).max(6).min(area.height.saturating_sub(2));
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+    frame.render_widget(Clear, popup_area);
+
+    let (name, verb) = if finder.is_palette() {
+        ("Commands", "run")
+    } else {
+        ("Find", "jump")
+    };
+    let title = format!(
+        " {} - {}/{} - Enter to {}, Esc ",
+        name,
+        finder.matches.len(),
+        finder.items.len(),
+        verb
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border_set(app))
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(title)
+        .title_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(inner);
+    let query = Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan)),
+        Span::styled(finder.query.clone(), Style::default().fg(Color::White)),
+        Span::styled(" ", Style::default().bg(Color::White)),
+    ]);
+    frame.render_widget(Paragraph::new(query), rows[0]);
+
+    // Preview only when both columns stay readable
+    let columns = if rows[1].width >= 60 && !finder.is_palette() {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(rows[1])
+            .to_vec()
+    } else {
+        vec![rows[1]]
+    };
+
+    let lines: Vec<Line> = finder
+        .matches
+        .iter()
+        .enumerate()
+        .map(|(idx, m)| {
+            let item = &finder.items[m.item];
+            let selected = idx == finder.selected;
+            let base = if selected {
+                Style::default()
+                    .bg(Color::Cyan)
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let hit = if selected {
+                base.fg(Color::Red)
+            } else {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            };
+            let mut spans = vec![Span::styled(
+                format!(" {} ", item.kind.tag()),
+                if selected {
+                    base
+                } else {
+                    Style::default().fg(Color::Cyan)
+                },
+            )];
+            spans.extend(item.label.chars().enumerate().map(|(pos, c)| {
+                let style = if m.positions.contains(&pos) { hit } else { base };
+                Span::styled(c.to_string(), style)
+            }));
+            if !item.detail.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", item.detail),
+                    if selected {
+                        base
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    },
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    // Keep the selection visible in long lists
+    let visible = columns[0].height as usize;
+    let scroll = finder.selected.saturating_sub(visible.saturating_sub(1));
+    frame.render_widget(
+        Paragraph::new(lines).scroll((scroll as u16, 0)),
+        columns[0],
+    );
+
+    let (Some(preview_area), Some(item)) = (columns.get(1), finder.selected_item()) else {
+        return;
+    };
+    let Some(doc) = app.buffer_docs().nth(item.buffer) else {
+        return;
+    };
+    let start = item.line.unwrap_or(0);
+    let preview: Vec<Line> = (start..doc.line_count())
+        .filter(|&idx| !is_hidden_comment(app, doc, idx))
+        .take(preview_area.height as usize)
+        .map(|idx| {
+            let text = doc.rope.line(idx).to_string();
+            Line::from(style_markdown_line(
+                text.trim_end_matches(['\n', '\r']),
+                &app.theme,
+                &app.config.render,
+                &doc.link_definitions,
+                None,
+            ))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(preview).block(
+            Block::default()
+                .borders(Borders::LEFT)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::DarkGray)),
+        ),
+        *preview_area,
+    );
+}
+
+/// Counters of the image metadata cache, for checking the memory budget
+fn render_image_cache_stats(frame: &mut Frame, app: &App) {
+    use ratatui::widgets::Clear;
+
+    #[cfg(feature = "images")]
+    let rows = {
+        let cache = &app.image_cache;
+        let stats = cache.stats();
+        let lookups = stats.hits + stats.misses;
+        let hit_rate = if lookups > 0 {
+            format!("{:.0}%", stats.hits as f64 * 100.0 / lookups as f64)
+        } else {
+            "-".to_string()
+        };
+        let mib = |bytes: u64| format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0));
+        vec![
+            ("Images", cache.len().to_string()),
+            ("Decoded size", mib(cache.used_bytes())),
+            ("Budget", mib(cache.budget_bytes())),
+            ("Hits", stats.hits.to_string()),
+            ("Misses", stats.misses.to_string()),
+            ("Hit rate", hit_rate),
+            ("Evictions", stats.evictions.to_string()),
+        ]
+    };
+    #[cfg(not(feature = "images"))]
+    let rows = {
+        let _ = app;
+        vec![("Images", "not built with image support".to_string())]
+    };
+
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!(" {:<14}", label), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!("{} ", value),
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                ),
+            ])
+        })
+        .collect();
+
+    let area = frame.area();
+    let title = " Image cache - any key to close ";
+    let content_width = lines
+        .iter()
+        .map(|l| l.width())
+        .chain(std::iter::once(title.width()))
+        .max()
+        .unwrap_or(0);
+    let popup_width = (content_width as u16 + 3).min(area.width.saturating_sub(4));
+    let popup_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_log_viewer(frame: &mut Frame, app: &App, scroll: usize) {
+    use ratatui::widgets::Clear;
+    use tracing::Level;
+
+    let events = app
+        .log_buffer
+        .as_ref()
+        .map(|buffer| buffer.snapshot())
+        .unwrap_or_default();
+
+    let area = frame.area();
+    let popup_width = area.width.saturating_sub(4);
+    let popup_height = area.height.saturating_sub(4);
+    let inner_width = popup_width.saturating_sub(2) as usize;
+    let visible = popup_height.saturating_sub(2) as usize;
+
+    // Newest events at the bottom, `scroll` events back from the end,
+    // keeping the popup full when scrolled back to the oldest
+    let end = events
+        .len()
+        .saturating_sub(scroll)
+        .max(visible.min(events.len()));
+    let start = end.saturating_sub(visible);
+    let mut lines: Vec<Line> = events[start..end]
+        .iter()
+        .map(|event| {
+            let level_color = match event.level {
+                Level::ERROR => Color::Red,
+                Level::WARN => Color::Yellow,
+                Level::INFO => Color::Green,
+                Level::DEBUG => Color::Blue,
+                Level::TRACE => Color::DarkGray,
+            };
+            let time = format!(" {:>9.3} ", event.elapsed.as_secs_f64());
+            let level = format!("{:<5} ", event.level.as_str());
+            let text = format!("{} {}", event.target, sanitize_for_terminal(&event.message));
+            let text_width = inner_width.saturating_sub(time.width() + level.width() + 1);
+            Line::from(vec![
+                Span::styled(time, Style::default().fg(Color::DarkGray)),
+                Span::styled(level, Style::default().fg(level_color)),
+                Span::styled(
+                    truncate_to_width(&text, text_width).to_string(),
+                    Style::default().fg(Color::White),
+                ),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " No events yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let title = format!(" Log ({} events) - j/k scroll, q to close ", events.len());
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title)
+                .title_style(
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_stats_popup(frame: &mut Frame, app: &App, stats: &mdx_core::stats::DocumentStats) {
+    use ratatui::widgets::Clear;
+
+    let area = frame.area();
+    let popup_width = 64.min(area.width.saturating_sub(4));
+    let inner_width = popup_width.saturating_sub(2) as usize;
+    let label_style = Style::default().fg(Color::DarkGray);
+    let value_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(" Words       ", label_style),
+            Span::styled(format!("{:<10}", stats.words), value_style),
+            Span::styled("Reading time  ", label_style),
+            Span::styled(format!("~{} min", stats.reading_minutes()), value_style),
+        ]),
+        Line::from(vec![
+            Span::styled(" Characters  ", label_style),
+            Span::styled(format!("{:<10}", stats.characters), value_style),
+            Span::styled("Code blocks   ", label_style),
+            Span::styled(stats.code_blocks.to_string(), value_style),
+        ]),
     ];
+    if stats.preamble_words > 0 {
+        lines.push(Line::from(vec![
+            Span::styled(" Before first heading  ", label_style),
+            Span::styled(stats.preamble_words.to_string(), value_style),
+        ]));
+    }
+    let header_lines = lines.len() + 2;
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(" {:<w$}{:>7}{:>7}", "Section", "Words", "Total", w = inner_width.saturating_sub(15)),
+        label_style,
+    )));
+
+    // Keep the selection in view below the fixed header
+    let popup_height = (header_lines + stats.sections.len() + 2)
+        .min(area.height.saturating_sub(4) as usize)
+        .max(header_lines + 3) as u16;
+    let list_height = (popup_height as usize).saturating_sub(header_lines + 2).max(1);
+    let scroll = app
+        .stats_popup_selected
+        .saturating_sub(list_height.saturating_sub(1));
+
+    let name_width = inner_width.saturating_sub(15);
+    for (idx, section) in stats.sections.iter().enumerate().skip(scroll).take(list_height) {
+        let heading = &app.doc.headings[section.heading];
+        let mut name = format!(
+            "{}{} {}",
+            "  ".repeat((heading.level as usize).saturating_sub(1)),
+            "#".repeat(heading.level as usize),
+            sanitize_for_terminal(&heading.text)
+        );
+        if name.width() > name_width.saturating_sub(1) {
+            name = format!("{}…", truncate_to_width(&name, name_width.saturating_sub(2)));
+        }
+        let pad = name_width.saturating_sub(name.width());
+        let text = format!(
+            " {}{}{:>7}{:>7}",
+            name,
+            " ".repeat(pad),
+            section.words,
+            section.total_words
+        );
+        lines.push(if idx == app.stats_popup_selected {
+            Line::from(text).style(
+                Style::default()
+                    .bg(Color::Cyan)
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Line::from(text).style(Style::default().fg(Color::White))
+        });
+    }
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height.min(area.height),
+    };
 
-    // Clear the background
     frame.render_widget(Clear, popup_area);
 
-    // Render the popup
-    let popup = Paragraph::new(help_lines)
+    let popup = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(Color::Cyan))
-                .title(" Help - Press ? or Esc to close ")
-                .title_style(
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                ),
+                .title(" Statistics - Enter to jump, Esc to close ")
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         )
         .style(Style::default().bg(Color::Rgb(30, 34, 42)));
 
     frame.render_widget(popup, popup_area);
 }
 
-fn render_toc_dialog(frame: &mut Frame, app: &App) {
+fn render_link_diagnostics(frame: &mut Frame, app: &App) {
     use ratatui::widgets::Clear;
 
-    // Create a full-screen popup area with small margins
     let area = frame.area();
-    let popup_width = area.width.saturating_sub(4);
-    let popup_height = area.height.saturating_sub(4);
+    let popup_width = 90.min(area.width.saturating_sub(4));
+    let popup_height =
+        (app.broken_links.len() as u16 + 2).min(area.height.saturating_sub(4).max(3));
 
     let popup_area = ratatui::layout::Rect {
         x: (area.width.saturating_sub(popup_width)) / 2,
@@ -2648,25 +5241,28 @@ fn render_toc_dialog(frame: &mut Frame, app: &App) {
         height: popup_height,
     };
 
-    // Calculate visible TOC height (account for borders and title)
-    let toc_height = popup_height.saturating_sub(2) as usize;
-    let scroll = app.toc_dialog_scroll;
+    // Keep the selection in view
+    let list_height = popup_height.saturating_sub(2) as usize;
+    let scroll = app
+        .link_diagnostics_selected
+        .saturating_sub(list_height.saturating_sub(1));
 
-    // Build visible TOC lines with indentation based on heading level
-    let toc_lines: Vec<Line> = app
-        .doc
-        .headings
+    let lines: Vec<Line> = app
+        .broken_links
         .iter()
         .enumerate()
         .skip(scroll)
-        .take(toc_height)
-        .map(|(idx, heading)| {
-            // Indent based on level (2 spaces per level, starting from level 1)
-            let indent = "  ".repeat((heading.level as usize).saturating_sub(1));
-            let text = format!("{}{}", indent, heading.text);
-
-            // Highlight selected item
-            if idx == app.toc_dialog_selected {
+        .take(list_height)
+        .map(|(idx, broken)| {
+            let kind = if broken.link.is_image { "image" } else { "link" };
+            let text = format!(
+                " {:>5}  {} {}: {}",
+                broken.link.line + 1,
+                kind,
+                sanitize_for_terminal(&broken.link.dest),
+                sanitize_for_terminal(&broken.problem)
+            );
+            if idx == app.link_diagnostics_selected {
                 Line::from(text).style(
                     Style::default()
                         .bg(Color::Cyan)
@@ -2679,21 +5275,121 @@ fn render_toc_dialog(frame: &mut Frame, app: &App) {
         })
         .collect();
 
-    // Clear the background
     frame.render_widget(Clear, popup_area);
 
-    // Render the TOC dialog
-    let popup = Paragraph::new(toc_lines)
+    let popup = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Cyan))
-                .title(" Table of Contents - j/k to navigate, Enter to jump, T/Esc to close ")
-                .title_style(
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Red))
+                .title(" Broken Links - Enter to jump, L/Esc to close ")
+                .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_spell_popup(frame: &mut Frame, app: &App) {
+    use ratatui::widgets::Clear;
+
+    let entries = app.spell_suggestions_at_cursor();
+    let area = frame.area();
+    let popup_width = 70.min(area.width.saturating_sub(4));
+    let popup_height = (entries.len() as u16 + 2).min(area.height.saturating_sub(4).max(3));
+
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    let lines: Vec<Line> = entries
+        .iter()
+        .map(|(word, suggestions)| {
+            let suggestions = if suggestions.is_empty() {
+                Span::styled("(no suggestions)", Style::default().fg(Color::DarkGray))
+            } else {
+                Span::styled(suggestions.join(", "), Style::default().fg(Color::White))
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(" {}", sanitize_for_terminal(word)),
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(Color::LightRed)
                         .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(" → ", Style::default().fg(Color::DarkGray)),
+                suggestions,
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(" Spelling - any key to close ")
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        )
+        .style(Style::default().bg(Color::Rgb(30, 34, 42)));
+
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_preview_popup(frame: &mut Frame, app: &App, preview: &crate::preview::Preview) {
+    use ratatui::widgets::{Clear, Wrap};
+
+    let lines: Vec<Line> = preview
+        .lines
+        .iter()
+        .map(|line| {
+            let mut spans = vec![Span::raw(" ")];
+            spans.extend(style_markdown_line(
+                &sanitize_for_terminal(line),
+                &app.theme,
+                &app.config.render,
+                &app.doc.link_definitions,
+                None,
+            ));
+            Line::from(spans)
+        })
+        .collect();
+
+    let area = frame.area();
+    let popup_width = 70.min(area.width.saturating_sub(4));
+    let inner_width = (popup_width as usize).saturating_sub(2).max(1);
+    let rows: usize = lines
+        .iter()
+        .map(|line| line.width().div_ceil(inner_width).max(1))
+        .sum();
+    let popup_height = (rows as u16 + 2).min(area.height.saturating_sub(4).max(3));
+    let popup_area = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let title = sanitize_for_terminal(&preview.title);
+    let title = truncate_to_width(&title, (popup_width as usize).saturating_sub(24));
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_set(border_set(app))
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(format!(" {} - any key to close ", title))
+                .title_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         )
         .style(Style::default().bg(Color::Rgb(30, 34, 42)));
 
@@ -2824,6 +5520,7 @@ fn render_options_dialog(frame: &mut Frame, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
+                .border_set(border_set(app))
                 .border_style(Style::default().fg(Color::Cyan))
                 .title(" Options - Press O or Esc to close ")
                 .title_style(
@@ -2839,21 +5536,32 @@ fn render_options_dialog(frame: &mut Frame, app: &App) {
 
 /// Render image (metadata placeholder)
 #[cfg(feature = "images")]
+#[allow(clippy::too_many_arguments)]
 fn render_image(
-    app: &App,
+    app: &mut App,
     content_area: ratatui::layout::Rect,
     source_line: usize,
     image: &mdx_core::image::ImageNode,
+    prefix: Vec<Span<'static>>,
     line_num_width: usize,
     is_focused: bool,
     cursor: usize,
     selection_range: Option<(usize, usize)>,
     left_margin_width: u16,
 ) -> (Vec<Line<'static>>, usize) {
-    // Try to read image metadata
-    let metadata_result = try_load_image(app, image, content_area);
+    let alt_only = app.show_image_alt_text;
+    let indent = line_number_columns(line_num_width)
+        + 2
+        + prefix.iter().map(|span| span.width()).sum::<usize>();
+
+    // Alt-text mode doesn't read the file at all
+    let metadata_result = if alt_only {
+        Ok(None)
+    } else {
+        try_load_image(app, image, content_area)
+    };
 
-    match metadata_result {
+    let (mut lines, consumed) = match metadata_result {
         Ok(Some(metadata)) => {
             // Successfully read - show placeholder with image info
             render_image_info_placeholder(
@@ -2861,6 +5569,7 @@ fn render_image(
                 image,
                 &metadata,
                 source_line,
+                prefix,
                 line_num_width,
                 is_focused,
                 cursor,
@@ -2869,12 +5578,13 @@ fn render_image(
             )
         }
         _ => {
-            // Failed to read - show placeholder
+            // Failed to read, or not read - show placeholder
             render_image_placeholder(
                 app,
                 content_area,
                 source_line,
                 image,
+                prefix,
                 line_num_width,
                 is_focused,
                 cursor,
@@ -2882,13 +5592,28 @@ fn render_image(
                 left_margin_width,
             )
         }
+    };
+
+    // The alt text is on the image line already in alt-text mode
+    let caption = image
+        .caption()
+        .filter(|caption| app.config.images.caption && !(alt_only && *caption == image.alt));
+    if let Some(caption) = caption {
+        lines.push(Line::from(vec![
+            Span::raw(" ".repeat(indent)),
+            Span::styled(
+                sanitize_for_terminal(caption),
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            ),
+        ]));
     }
+    (lines, consumed)
 }
 
-/// Try to read image metadata
+/// Image metadata, once the worker pool has read it
 #[cfg(feature = "images")]
 fn try_load_image(
-    app: &App,
+    app: &mut App,
     image: &mdx_core::image::ImageNode,
     _content_area: ratatui::layout::Rect,
 ) -> anyhow::Result<Option<crate::image_cache::ImageMetadata>> {
@@ -2914,7 +5639,11 @@ fn try_load_image(
                     }
                 }
             }
-            crate::image_cache::ImageMetadata::from_path(&path)?
+            match app.image_cache.get(&path) {
+                Some(metadata) => metadata,
+                // Still being read, or unreadable
+                None => return Ok(None),
+            }
         }
         ImageSource::Remote(_url) => {
             // Don't fetch remote images
@@ -2927,11 +5656,13 @@ fn try_load_image(
 
 /// Render placeholder with image information
 #[cfg(feature = "images")]
+#[allow(clippy::too_many_arguments)]
 fn render_image_info_placeholder(
     app: &App,
     image: &mdx_core::image::ImageNode,
     metadata: &crate::image_cache::ImageMetadata,
     source_line: usize,
+    prefix: Vec<Span<'static>>,
     line_num_width: usize,
     is_focused: bool,
     cursor: usize,
@@ -2961,36 +5692,12 @@ fn render_image_info_placeholder(
     let mut line_spans: Vec<Span> = Vec::new();
 
     // Line number
-    let line_num = format!("{:>width$} ", source_line + 1, width = line_num_width);
-    let line_num_color = if is_focused && source_line == cursor {
-        Color::White
-    } else {
-        Color::DarkGray
-    };
-    line_spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
+    line_spans.push(line_number_span(app, source_line, cursor, is_focused, line_num_width));
 
     // Git diff gutter
-    #[cfg(feature = "git")]
-    if app.config.git.diff {
-        use mdx_core::diff::DiffMark;
-        let gutter = match app.doc.diff_gutter.get(source_line) {
-            DiffMark::None => "  ",
-            DiffMark::Added => "│ ",
-            DiffMark::Modified => "│ ",
-            DiffMark::DeletedAfter(_) => "│ ",
-        };
-        let gutter_color = match app.doc.diff_gutter.get(source_line) {
-            DiffMark::None => Color::DarkGray,
-            DiffMark::Added => Color::Green,
-            DiffMark::Modified => Color::Yellow,
-            DiffMark::DeletedAfter(_) => Color::Red,
-        };
-        line_spans.push(Span::styled(gutter, Style::default().fg(gutter_color)));
-    } else {
-        line_spans.push(Span::raw("  "));
-    }
-    #[cfg(not(feature = "git"))]
-    line_spans.push(Span::raw("  "));
+    line_spans.extend(diff_gutter_spans(app, source_line));
+
+    line_spans.extend(prefix);
 
     // Add placeholder content - just the info text without borders
     line_spans.push(Span::styled(
@@ -3030,13 +5737,16 @@ fn render_image_info_placeholder(
     (lines, 1)
 }
 
-/// Render image placeholder when image cannot be loaded
+/// Render image placeholder when image cannot be loaded, or as its alt
+/// text only
 #[cfg(feature = "images")]
+#[allow(clippy::too_many_arguments)]
 fn render_image_placeholder(
     app: &App,
     _content_area: ratatui::layout::Rect,
     source_line: usize,
     image: &mdx_core::image::ImageNode,
+    prefix: Vec<Span<'static>>,
     line_num_width: usize,
     is_focused: bool,
     cursor: usize,
@@ -3052,8 +5762,20 @@ fn render_image_placeholder(
     };
     let alt_text = sanitize_for_terminal(alt_text);
 
-    // Format error message
-    let info_text = format!("🖼  {} | [unable to read]", alt_text);
+    // Alt-text mode shows just the description, in the placeholder colors
+    let (info_text, style) = if app.show_image_alt_text {
+        (
+            format!("[image: {}]", alt_text),
+            Style::default()
+                .fg(Color::Rgb(100, 200, 255))
+                .bg(Color::Rgb(30, 40, 50)),
+        )
+    } else {
+        (
+            format!("🖼  {} | [unable to read]", alt_text),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )
+    };
 
     // Check if this line is selected
     let is_selected = if let Some((start, end)) = selection_range {
@@ -3066,42 +5788,15 @@ fn render_image_placeholder(
     let mut line_spans: Vec<Span> = Vec::new();
 
     // Line number
-    let line_num = format!("{:>width$} ", source_line + 1, width = line_num_width);
-    let line_num_color = if is_focused && source_line == cursor {
-        Color::White
-    } else {
-        Color::DarkGray
-    };
-    line_spans.push(Span::styled(line_num, Style::default().fg(line_num_color)));
+    line_spans.push(line_number_span(app, source_line, cursor, is_focused, line_num_width));
 
     // Git diff gutter
-    #[cfg(feature = "git")]
-    if app.config.git.diff {
-        use mdx_core::diff::DiffMark;
-        let gutter = match app.doc.diff_gutter.get(source_line) {
-            DiffMark::None => "  ",
-            DiffMark::Added => "│ ",
-            DiffMark::Modified => "│ ",
-            DiffMark::DeletedAfter(_) => "│ ",
-        };
-        let gutter_color = match app.doc.diff_gutter.get(source_line) {
-            DiffMark::None => Color::DarkGray,
-            DiffMark::Added => Color::Green,
-            DiffMark::Modified => Color::Yellow,
-            DiffMark::DeletedAfter(_) => Color::Red,
-        };
-        line_spans.push(Span::styled(gutter, Style::default().fg(gutter_color)));
-    } else {
-        line_spans.push(Span::raw("  "));
-    }
-    #[cfg(not(feature = "git"))]
-    line_spans.push(Span::raw("  "));
+    line_spans.extend(diff_gutter_spans(app, source_line));
+
+    line_spans.extend(prefix);
 
     // Add error placeholder
-    line_spans.push(Span::styled(
-        info_text,
-        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-    ));
+    line_spans.push(Span::styled(info_text, style));
 
     // Apply highlighting directly to spans - priority order: selection > cursor
     if is_focused && is_selected {
@@ -3132,9 +5827,43 @@ fn render_image_placeholder(
     (lines, 1)
 }
 
+/// Entry points for the render benchmarks (`cargo bench -p mdx-tui`).
+/// Not a stable API.
+#[doc(hidden)]
+pub mod bench {
+    use super::*;
+
+    /// Style one source line the way the document view does
+    pub fn style_line(app: &App, line: &str) -> Vec<Span<'static>> {
+        style_markdown_line(
+            line,
+            &app.theme,
+            &app.config.render,
+            &app.doc.link_definitions,
+            None,
+        )
+    }
+
+    /// Lay out the table starting at `start` for a pane `width` columns wide
+    pub fn table_lines(app: &App, start: usize, width: u16) -> Vec<Line<'static>> {
+        let area = ratatui::layout::Rect::new(0, 0, width, u16::MAX);
+        let end = table_at(app, start).map_or(start, |table| table.end);
+        let rows = source_table_rows(app, start, end);
+        render_table_block(app, area, &rows, usize::MAX, 0, false, 0, None, 0, None).0
+    }
+}
+
 #[cfg(test)]
 mod security_tests {
     use super::sanitize_for_terminal;
+    #[cfg(feature = "images")]
+    use crate::App;
+    #[cfg(feature = "images")]
+    use mdx_core::{Config, Document};
+    #[cfg(feature = "images")]
+    use std::io::Write;
+    #[cfg(feature = "images")]
+    use tempfile::NamedTempFile;
 
     #[test]
     fn security_sanitises_control_characters() {
@@ -3174,9 +5903,10 @@ mod security_tests {
         config.images.allow_absolute = true;
         config.images.max_bytes = 1;
 
-        let app = App::new(config, doc, vec![]);
-        let image = app.doc.images.first().unwrap();
-        let result = super::try_load_image(&app, image, ratatui::layout::Rect::default()).unwrap();
+        let mut app = App::new(config, doc, vec![]);
+        let image = app.doc.images.first().unwrap().clone();
+        let result =
+            super::try_load_image(&mut app, &image, ratatui::layout::Rect::default()).unwrap();
 
         assert!(result.is_none());
     }
@@ -3187,6 +5917,7 @@ mod utf8_rendering_tests {
     use super::style_markdown_line;
     use crate::theme::Theme;
     use mdx_core::config::Config;
+    use mdx_core::links::LinkDefinitions;
     use ratatui::style::Color;
 
     fn get_text_from_spans(spans: &[ratatui::text::Span]) -> String {
@@ -3200,7 +5931,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = true;
 
         let line = "---";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should be UTF-8 horizontal lines
@@ -3214,7 +5945,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = false;
 
         let line = "---";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should remain as ASCII
@@ -3228,7 +5959,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = true;
 
         let line = "| Header 1 | Header 2 |";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should use UTF-8 vertical bars
@@ -3243,7 +5974,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = false;
 
         let line = "| Header 1 | Header 2 |";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should remain as ASCII pipe
@@ -3258,7 +5989,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = true;
 
         let line = "|:---|---:|:---:|";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should use UTF-8 horizontal lines for separators
@@ -3275,7 +6006,7 @@ mod utf8_rendering_tests {
         let test_cases = vec!["- Item 1", "* Item 2", "+ Item 3"];
 
         for line in test_cases {
-            let spans = style_markdown_line(line, &theme, &config.render, None);
+            let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
             let output = get_text_from_spans(&spans);
 
             // Should use UTF-8 bullet point
@@ -3295,7 +6026,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = false;
 
         let line = "- Item 1";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Should remain as ASCII
@@ -3303,6 +6034,61 @@ mod utf8_rendering_tests {
         assert!(!output.contains('•'));
     }
 
+    #[test]
+    fn test_nested_list_bullets() {
+        use super::style_prose_line;
+        use mdx_core::list::ListLine;
+
+        let theme = Theme::dark();
+        let config = Config::default();
+        let links = LinkDefinitions::new();
+        let render = |line: &str, list_line: ListLine| {
+            let spans = style_prose_line(line, list_line, &theme, &config.render, &links, None);
+            get_text_from_spans(&spans)
+        };
+
+        let item = |depth| ListLine::Item {
+            depth,
+            content_indent: 4,
+            number: None,
+        };
+        assert_eq!(render("  - b", item(1)), "  ◦ b");
+        assert_eq!(render("  * c", item(2)), "  ▪ c");
+        assert_eq!(render("  + d", item(3)), "  • d");
+        assert_eq!(render("  2) e", item(1)), "  2. e");
+        // Continuation text starts under the item's content
+        assert_eq!(
+            render("lazy text", ListLine::Continuation { content_indent: 4 }),
+            "    lazy text"
+        );
+    }
+
+    #[test]
+    fn test_renumbered_list_markers() {
+        use super::style_prose_line;
+        use mdx_core::list::ListLine;
+
+        let theme = Theme::dark();
+        let mut config = Config::default();
+        let links = LinkDefinitions::new();
+        let item = |depth| ListLine::Item {
+            depth,
+            content_indent: depth * 3 + 3,
+            number: Some(2),
+        };
+        let render = |config: &Config, line: &str, list_line: ListLine| {
+            let spans = style_prose_line(line, list_line, &theme, &config.render, &links, None);
+            get_text_from_spans(&spans)
+        };
+
+        // Off by default: the source number is kept
+        assert_eq!(render(&config, "1. b", item(0)), "1. b");
+        config.render.renumber_lists = true;
+        assert_eq!(render(&config, "1. b", item(0)), "2. b");
+        assert_eq!(render(&config, "   1. b", item(1)), "   ii. b");
+        assert_eq!(render(&config, "      1. b", item(2)), "      b. b");
+    }
+
     #[test]
     fn test_ordered_list_unchanged() {
         let theme = Theme::dark();
@@ -3310,7 +6096,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = true;
 
         let line = "1. First item";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // Ordered lists should keep their numbers
@@ -3332,7 +6118,7 @@ mod utf8_rendering_tests {
         config.render.use_utf8_graphics = true;
 
         let line = "| Header 1 | Header 2 |";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
 
         // Verify we have multiple spans (content + separators)
         assert!(spans.len() > 1);
@@ -3350,7 +6136,7 @@ mod utf8_rendering_tests {
 
         let theme = Theme::dark();
         let line = "| Col1 | Col2 |";
-        let spans = style_markdown_line(line, &theme, &config.render, None);
+        let spans = style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None);
         let output = get_text_from_spans(&spans);
 
         // With default config (UTF-8 enabled), should have UTF-8 chars
@@ -3361,3 +6147,264 @@ mod utf8_rendering_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod display_width_tests {
+    use super::{compute_table_widths, truncate_to_width, wrap_cell_text};
+    use unicode_width::UnicodeWidthStr;
+
+    #[test]
+    fn test_patch_word_style_whole_words() {
+        use ratatui::style::{Modifier, Style};
+        use ratatui::text::Span;
+
+        let patch = Style::default().add_modifier(Modifier::UNDERLINED);
+        let spans = vec![Span::raw("teh tehx teh")];
+        let out = super::patch_word_style(spans, "teh", 1, patch);
+        let parts: Vec<(&str, bool)> = out
+            .iter()
+            .map(|s| {
+                (
+                    s.content.as_ref(),
+                    s.style.add_modifier.contains(Modifier::UNDERLINED),
+                )
+            })
+            .collect();
+        assert_eq!(parts, vec![("teh tehx ", false), ("teh", true)]);
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_patch_changed_text_skips_markup() {
+        use ratatui::style::{Color, Style};
+        use ratatui::text::Span;
+
+        let tint = Style::default().bg(Color::Red);
+        let tinted = |out: Vec<Span<'static>>| -> Vec<String> {
+            out.iter()
+                .filter(|s| s.style.bg == Some(Color::Red))
+                .map(|s| s.content.to_string())
+                .collect()
+        };
+
+        // `**` around the change is not rendered; the second "a" changed
+        let raw = "a b **a c**";
+        let spans = vec![Span::raw("a b "), Span::raw("a c")];
+        let out = super::patch_changed_text(spans, raw, 4..11, tint);
+        assert_eq!(tinted(out), vec!["a c"]);
+
+        // Markup inside the change: each word is found on its own
+        let raw = "one *two* three";
+        let spans = vec![Span::raw("one "), Span::raw("two"), Span::raw(" three")];
+        let out = super::patch_changed_text(spans, raw, 0..15, tint);
+        assert_eq!(tinted(out), vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn test_patch_text_style_splits_spans() {
+        use ratatui::style::{Color, Style};
+        use ratatui::text::Span;
+
+        let spans = vec![
+            Span::raw("see the "),
+            Span::raw("guide page"),
+            Span::raw(" now"),
+        ];
+        let patch = Style::default().fg(Color::Red);
+        let out = super::patch_text_style(spans, "the guide", patch);
+        let parts: Vec<(&str, bool)> = out
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style.fg == Some(Color::Red)))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("see ", false),
+                ("the ", true),
+                ("guide", true),
+                (" page", false),
+                (" now", false)
+            ]
+        );
+
+        // No match leaves the spans untouched
+        let out = super::patch_text_style(vec![Span::raw("abc")], "xyz", patch);
+        assert_eq!(out.len(), 1);
+    }
+
+    #[test]
+    fn test_centered_content_area() {
+        use ratatui::layout::Rect;
+        let area = Rect::new(10, 1, 122, 30);
+        assert_eq!(super::centered_content_area(area, 0), area);
+        assert_eq!(super::centered_content_area(area, 200), area);
+        let narrowed = super::centered_content_area(area, 80);
+        assert_eq!(narrowed.width, 82);
+        assert_eq!(narrowed.x, 30);
+        assert_eq!((narrowed.y, narrowed.height), (1, 30));
+    }
+
+    #[test]
+    fn test_truncate_to_width_respects_wide_chars() {
+        assert_eq!(truncate_to_width("日本語テキスト", 5), "日本");
+        assert_eq!(truncate_to_width("abc", 10), "abc");
+        assert_eq!(truncate_to_width("😀x", 1), "");
+    }
+
+    #[test]
+    fn test_wrap_cell_text_splits_cjk_by_columns() {
+        let wrapped = wrap_cell_text("日本語テキスト", 4);
+        assert_eq!(wrapped, vec!["日本", "語テ", "キス", "ト"]);
+        assert!(wrapped.iter().all(|line| line.width() <= 4));
+    }
+
+    #[test]
+    fn test_table_widths_use_display_width() {
+        let rows = vec![
+            vec!["名前".to_string(), "x".to_string()],
+            vec!["a".to_string(), "😀😀".to_string()],
+        ];
+        let widths = compute_table_widths(&rows, 80);
+        assert_eq!(widths, vec![4, 4]);
+    }
+}
+
+#[cfg(test)]
+mod inline_text_tests {
+    use super::style_markdown_line;
+    use crate::theme::Theme;
+    use mdx_core::config::Config;
+    use mdx_core::links::LinkDefinitions;
+
+    fn render(line: &str, smart_punctuation: bool) -> String {
+        let theme = Theme::dark();
+        let mut config = Config::default();
+        config.render.smart_punctuation = smart_punctuation;
+        style_markdown_line(line, &theme, &config.render, &LinkDefinitions::new(), None)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_reference_links_resolved_from_document() {
+        let (doc, _file) = {
+            use std::io::Write as _;
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(
+                b"[![CI][ci-badge]][ci] and [docs] or [the guide][Guide]\n\n\
+                  [ci]: https://ci.example.com\n\
+                  [ci-badge]: https://ci.example.com/badge.svg\n\
+                  [docs]: https://docs.example.com\n\
+                  [guide]: ./guide.md\n",
+            )
+            .unwrap();
+            (mdx_core::Document::load(file.path()).unwrap().0, file)
+        };
+        assert_eq!(doc.link_definitions.len(), 4);
+
+        let theme = Theme::dark();
+        let config = Config::default();
+        let line: String = doc.rope.line(0).chunks().collect();
+        let text: String = style_markdown_line(
+            line.trim_end(),
+            &theme,
+            &config.render,
+            &doc.link_definitions,
+            None,
+        )
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+        assert_eq!(text, "CI and docs or the guide");
+
+        // Without definitions the brackets stay, as CommonMark requires
+        assert_eq!(render("[text][missing]", false), "[text][missing]");
+    }
+
+    #[test]
+    fn test_entities_decoded() {
+        assert_eq!(
+            render("Fish &amp; chips&nbsp;today", false),
+            "Fish & chips\u{a0}today"
+        );
+        assert_eq!(render("| A &lt; B |", false), "│A < B│");
+    }
+
+    #[test]
+    fn test_html_lines_show_text_content() {
+        assert_eq!(render("<p align=\"center\">Hello <b>there</b></p>", false), "Hello there");
+        assert_eq!(render("<details>", false), "");
+        assert_eq!(render("<summary>More info</summary>", false), "▼ More info");
+        // Autolinks are not HTML
+        assert_eq!(render("<https://example.com>", false), "https://example.com");
+    }
+
+    #[test]
+    fn test_definition_description_indented() {
+        assert_eq!(render(": A *short* meaning", false), "  ▸ A short meaning");
+        // Four spaces of indent is an indented code block, not a description
+        assert!(mdx_core::render::definition_content("    : too deep").is_none());
+    }
+
+    #[test]
+    fn test_definition_term_detection() {
+        assert!(super::is_definition_term("Glossary term"));
+        assert!(!super::is_definition_term("# Heading"));
+        assert!(!super::is_definition_term("- item"));
+        assert!(!super::is_definition_term(": description"));
+        assert!(!super::is_definition_term("   "));
+    }
+
+    #[test]
+    fn test_definition_term_is_bold() {
+        let theme = Theme::dark();
+        let config = Config::default();
+        let spans = super::style_definition_term(
+            "Term",
+            &theme,
+            &config.render,
+            &LinkDefinitions::new(),
+            None,
+        );
+        assert_eq!(spans[0].content, "Term");
+        assert!(spans[0]
+            .style
+            .add_modifier
+            .contains(ratatui::style::Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_diff_fence_lines_colored_by_prefix() {
+        use ratatui::style::{Color, Modifier};
+
+        let theme = Theme::dark();
+        let fg = |line: &str, lang: &str| {
+            super::render_code_line(line, lang, &theme, None)[0].style.fg
+        };
+
+        assert_eq!(fg("+let x = 1;", "diff"), Some(Color::Green));
+        assert_eq!(fg("-let x = 0;", "patch"), Some(Color::Red));
+        assert_eq!(fg("@@ -1,3 +1,3 @@", "Diff"), Some(Color::Cyan));
+        assert_eq!(fg(" unchanged", "diff"), theme.code.fg);
+        let header = super::render_code_line("--- a/src/lib.rs", "diff", &theme, None);
+        assert_eq!(header.len(), 1);
+        assert!(header[0].style.add_modifier.contains(Modifier::BOLD));
+        // Other languages keep the keyword highlighter
+        assert_ne!(fg("-x", "rust"), Some(Color::Red));
+    }
+
+    #[test]
+    fn test_smart_punctuation_off_by_default() {
+        assert!(!Config::default().render.smart_punctuation);
+        assert_eq!(render("a -- b \"quoted\"", false), "a -- b \"quoted\"");
+    }
+
+    #[test]
+    fn test_smart_punctuation_converts_dashes_and_quotes() {
+        assert_eq!(
+            render("a -- b --- c \"quoted\" it's", true),
+            "a \u{2013} b \u{2014} c \u{201c}quoted\u{201d} it\u{2019}s"
+        );
+    }
+}
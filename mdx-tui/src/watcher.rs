@@ -3,6 +3,7 @@
 use anyhow::{Context, Result};
 use crossbeam_channel::Receiver;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
@@ -30,6 +31,7 @@ impl FileWatcher {
         // Create the watcher
         let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
             if let Ok(event) = res {
+                tracing::trace!(kind = ?event.kind, paths = ?event.paths, "Watch event");
                 // Only care about modification events
                 if matches!(
                     event.kind,
@@ -95,6 +97,78 @@ impl FileWatcher {
     }
 }
 
+/// Watcher for a whole directory tree in directory mode. Reports the
+/// Markdown files that were created or modified.
+pub struct DirWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+    root: PathBuf,
+    pending: BTreeSet<PathBuf>,
+    last_event: Option<Instant>,
+}
+
+impl DirWatcher {
+    /// Create a recursive watcher for `root`
+    pub fn new(root: &Path) -> Result<Self> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let root = root.to_path_buf();
+        let root_clone = root.clone();
+
+        let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+            if let Ok(event) = res {
+                tracing::trace!(kind = ?event.kind, paths = ?event.paths, "Watch event");
+                if matches!(
+                    event.kind,
+                    notify::EventKind::Modify(_) | notify::EventKind::Create(_)
+                ) {
+                    for path in event.paths {
+                        if crate::buffers::is_markdown_path(&path)
+                            && !crate::buffers::is_hidden_below(&root_clone, &path)
+                        {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+            }
+        })
+        .context("Failed to create directory watcher")?;
+
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch directory: {}", root.display()))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver: rx,
+            root,
+            pending: BTreeSet::new(),
+            last_event: None,
+        })
+    }
+
+    /// Files changed since the last call, once no new event has arrived for
+    /// the debounce period. Empty while events are still coming in.
+    pub fn changed_paths(&mut self, debounce_ms: u64) -> Vec<PathBuf> {
+        while let Ok(path) = self.receiver.try_recv() {
+            self.pending.insert(path);
+            self.last_event = Some(Instant::now());
+        }
+
+        match self.last_event {
+            Some(last) if last.elapsed() >= Duration::from_millis(debounce_ms) => {
+                self.last_event = None;
+                std::mem::take(&mut self.pending).into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the watched directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +313,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_dir_watcher_reports_new_markdown_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().canonicalize()?;
+        std::fs::create_dir(root.join("sub"))?;
+        let mut watcher = DirWatcher::new(&root)?;
+        assert_eq!(watcher.root(), root);
+
+        std::fs::write(root.join("sub/new.md"), "# New\n")?;
+        std::fs::write(root.join("sub/ignored.txt"), "text\n")?;
+
+        let mut changed = Vec::new();
+        for _ in 0..20 {
+            thread::sleep(Duration::from_millis(100));
+            changed.extend(watcher.changed_paths(0));
+            if !changed.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(changed, vec![root.join("sub/new.md")]);
+
+        Ok(())
+    }
 }
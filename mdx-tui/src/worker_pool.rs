@@ -0,0 +1,327 @@
+//! Thread pool shared by the background workers
+//!
+//! The app makes one [`WorkerPool`], and the diff, spell check, link
+//! check, code block and image workers each run their requests on it
+//! through a [`Queue`]. Requests are tagged with a document revision,
+//! where only the newest request matters: a queue keeps the newest
+//! pending request per key (a document, say) and runs up to its limit of
+//! them at a time on the pool. Each running request has a handler to
+//! itself, so handlers can keep caches without locking. Results of
+//! requests overtaken by a newer one for the same key are dropped.
+
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fewest threads in a pool: enough that a slow link check or code block
+/// leaves room for diffs and images
+const MIN_THREADS: usize = 4;
+
+/// Most threads in a pool
+const MAX_THREADS: usize = 8;
+
+/// A request for a queue
+pub trait Task: Send + 'static {
+    /// Requests with the same key replace each other while queued
+    fn key(&self) -> usize {
+        0
+    }
+
+    /// Revision the request was made for; a result is only delivered if no
+    /// other revision was requested for the key since. Revisions needn't
+    /// grow: a new document starts over.
+    fn rev(&self) -> u64;
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Handle to the worker threads; clones share the same threads, which
+/// exit once every handle is gone
+#[derive(Clone)]
+pub struct WorkerPool {
+    job_tx: Sender<Job>,
+}
+
+impl WorkerPool {
+    /// Spawn a thread per core, within [`MIN_THREADS`]..=[`MAX_THREADS`]
+    pub fn spawn() -> Self {
+        let threads = thread::available_parallelism()
+            .map_or(MIN_THREADS, |n| n.get())
+            .clamp(MIN_THREADS, MAX_THREADS);
+        let (job_tx, job_rx) = crossbeam_channel::unbounded::<Job>();
+        for i in 0..threads {
+            let job_rx = job_rx.clone();
+            thread::Builder::new()
+                .name(format!("mdx-worker-{}", i))
+                .spawn(move || {
+                    for job in job_rx {
+                        job();
+                    }
+                })
+                .expect("failed to spawn worker thread");
+        }
+        Self { job_tx }
+    }
+
+    fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        let _ = self.job_tx.send(Box::new(job));
+    }
+}
+
+/// How a queue runs its requests
+#[derive(Debug, Clone, Copy)]
+pub struct QueueOptions {
+    /// Most requests running at once
+    pub limit: usize,
+    /// How long requests must stop coming before queued ones run, so a
+    /// burst of edits is worked on once
+    pub debounce: Duration,
+}
+
+/// A result with the key and revision of its request
+type Tagged<R> = (usize, u64, R);
+
+type Handler<T, R> = Box<dyn FnMut(T) -> Option<R> + Send>;
+
+/// Queue state shared with the requests running on the pool
+struct Shared<T, R> {
+    state: Mutex<State<T, R>>,
+    result_tx: Sender<Tagged<R>>,
+    debounce: Duration,
+}
+
+struct State<T, R> {
+    /// Newest queued request per key
+    pending: HashMap<usize, T>,
+    /// Jobs on the pool working through `pending`
+    running: usize,
+    last_request: Instant,
+    /// Handlers not in use by a running request
+    handlers: Vec<Handler<T, R>>,
+}
+
+impl<T, R> Shared<T, R> {
+    fn lock(&self) -> MutexGuard<'_, State<T, R>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A worker's requests, run on a shared [`WorkerPool`]
+pub struct Queue<T: Task, R: Send + 'static> {
+    pool: WorkerPool,
+    shared: Arc<Shared<T, R>>,
+    limit: usize,
+    result_rx: Receiver<Tagged<R>>,
+    /// Last revision requested per key
+    latest: Mutex<HashMap<usize, u64>>,
+}
+
+impl<T: Task, R: Send + 'static> Queue<T, R> {
+    /// A queue running up to `options.limit` requests at once on `pool`,
+    /// each with a handler from `make_handler`. A handler returning `None`
+    /// sends no result.
+    pub fn new<H>(
+        pool: &WorkerPool,
+        options: QueueOptions,
+        mut make_handler: impl FnMut() -> H,
+    ) -> Self
+    where
+        H: FnMut(T) -> Option<R> + Send + 'static,
+    {
+        let limit = options.limit.max(1);
+        let handlers = (0..limit)
+            .map(|_| Box::new(make_handler()) as Handler<T, R>)
+            .collect();
+        let (result_tx, result_rx) = crossbeam_channel::unbounded();
+        let shared = Shared {
+            state: Mutex::new(State {
+                pending: HashMap::new(),
+                running: 0,
+                last_request: Instant::now(),
+                handlers,
+            }),
+            result_tx,
+            debounce: options.debounce,
+        };
+
+        Self {
+            pool: pool.clone(),
+            shared: Arc::new(shared),
+            limit,
+            result_rx,
+            latest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queue a request, replacing any queued one with the same key
+    pub fn submit(&self, task: T) {
+        if let Ok(mut latest) = self.latest.lock() {
+            latest.insert(task.key(), task.rev());
+        }
+        let start = {
+            let mut state = self.shared.lock();
+            state.pending.insert(task.key(), task);
+            state.last_request = Instant::now();
+            let start = state.running < self.limit.min(state.pending.len());
+            if start {
+                state.running += 1;
+            }
+            start
+        };
+        if start {
+            let shared = Arc::clone(&self.shared);
+            self.pool.execute(move || run_pending(&shared));
+        }
+    }
+
+    /// Try to receive a result (non-blocking), skipping outdated ones
+    pub fn try_recv(&self) -> Option<R> {
+        loop {
+            let tagged = self.result_rx.try_recv().ok()?;
+            if let Some(result) = self.current(tagged) {
+                return Some(result);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for a current result
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<R> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let tagged = self.result_rx.recv_deadline(deadline).ok()?;
+            if let Some(result) = self.current(tagged) {
+                return Some(result);
+            }
+        }
+    }
+
+    /// The result, unless another revision was requested for its key
+    fn current(&self, (key, rev, result): Tagged<R>) -> Option<R> {
+        let latest = self.latest.lock().ok()?;
+        (latest.get(&key) == Some(&rev)).then_some(result)
+    }
+}
+
+/// Pool job: run pending requests of a queue once requests have stopped
+/// for its debounce, until none are left
+fn run_pending<T: Task, R: Send + 'static>(shared: &Shared<T, R>) {
+    loop {
+        let (task, mut handler) = {
+            let mut state = shared.lock();
+            let Some(&key) = state.pending.keys().next() else {
+                state.running -= 1;
+                return;
+            };
+            let wait = shared.debounce.saturating_sub(state.last_request.elapsed());
+            if !wait.is_zero() {
+                drop(state);
+                thread::sleep(wait);
+                continue;
+            }
+            let task = state.pending.remove(&key).expect("pending key");
+            let handler = state.handlers.pop().expect("a handler per running job");
+            (task, handler)
+        };
+
+        let (key, rev) = (task.key(), task.rev());
+        let result = handler(task);
+        shared.lock().handlers.push(handler);
+        if let Some(result) = result {
+            // The queue may be gone; its results with it
+            let _ = shared.result_tx.send((key, rev, result));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Job {
+        key: usize,
+        rev: u64,
+    }
+
+    impl Task for Job {
+        fn key(&self) -> usize {
+            self.key
+        }
+
+        fn rev(&self) -> u64 {
+            self.rev
+        }
+    }
+
+    #[test]
+    fn test_pool_coalesces_and_drops_outdated_results() {
+        let pool = WorkerPool::spawn();
+        let options = QueueOptions {
+            limit: 2,
+            debounce: Duration::from_millis(30),
+        };
+        let queue = Queue::new(&pool, options, || |job: Job| Some((job.key, job.rev)));
+
+        for rev in 1..=5 {
+            queue.submit(Job { key: 0, rev });
+        }
+        queue.submit(Job { key: 1, rev: 9 });
+
+        let mut results = vec![
+            queue.recv_timeout(Duration::from_secs(2)).unwrap(),
+            queue.recv_timeout(Duration::from_secs(2)).unwrap(),
+        ];
+        results.sort();
+        assert_eq!(results, vec![(0, 5), (1, 9)]);
+        assert_eq!(queue.recv_timeout(Duration::from_millis(100)), None);
+
+        // A result for a revision that has since been overtaken is dropped
+        let queue = Queue::new(&pool, options, || {
+            |job: Job| {
+                if job.rev == 1 {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Some(job.rev)
+            }
+        });
+        queue.submit(Job { key: 0, rev: 1 });
+        thread::sleep(Duration::from_millis(60));
+        queue.submit(Job { key: 0, rev: 2 });
+        assert_eq!(queue.recv_timeout(Duration::from_secs(2)), Some(2));
+        assert_eq!(queue.recv_timeout(Duration::from_millis(200)), None);
+    }
+
+    #[test]
+    fn test_queue_limits_running_requests() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let pool = WorkerPool::spawn();
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let options = QueueOptions {
+            limit: 1,
+            debounce: Duration::ZERO,
+        };
+        let queue = Queue::new(&pool, options, || {
+            let (running, most) = (Arc::clone(&running), Arc::clone(&most));
+            move |job: Job| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Some(job.key)
+            }
+        });
+
+        for key in 0..3 {
+            queue.submit(Job { key, rev: 0 });
+        }
+        let mut keys: Vec<usize> = (0..3)
+            .filter_map(|_| queue.recv_timeout(Duration::from_secs(2)))
+            .collect();
+        keys.sort();
+        assert_eq!(keys, [0, 1, 2]);
+        assert_eq!(most.load(Ordering::SeqCst), 1);
+    }
+}
@@ -77,7 +77,7 @@ fn integration_search_functionality() {
     assert_eq!(app.search_query, "test");
 
     // Should find matches
-    assert!(app.search_matches.len() > 0);
+    assert!(!app.search_matches.is_empty());
     assert_eq!(app.search_matches.len(), 2);
 }
 
@@ -234,7 +234,7 @@ fn integration_configuration() {
 
     let app = App::new(config.clone(), doc, vec![]);
 
-    assert_eq!(app.show_toc, true);
+    assert!(app.show_toc);
     assert_eq!(app.theme_variant, mdx_core::config::ThemeVariant::Light);
     assert_eq!(app.config.theme, mdx_core::config::ThemeVariant::Light);
 }
@@ -333,14 +333,47 @@ fn integration_theme_toggle() {
     let content = "# Test\n";
     let (mut app, _file) = create_test_app(content);
 
+    // `m` cycles through every built-in theme and back to the first
     let initial_theme = app.theme_variant;
-    app.toggle_theme();
-    assert_ne!(app.theme_variant, initial_theme);
-
+    let mut seen = vec![initial_theme];
+    for _ in 1..mdx_core::config::ThemeVariant::ALL.len() {
+        app.toggle_theme();
+        assert!(!seen.contains(&app.theme_variant));
+        seen.push(app.theme_variant);
+    }
     app.toggle_theme();
     assert_eq!(app.theme_variant, initial_theme);
 }
 
+#[cfg(feature = "git")]
+#[test]
+fn integration_colorblind_theme_diff_gutter() {
+    use mdx_core::config::ThemeVariant;
+    use mdx_tui::theme::Theme;
+    use ratatui::style::Color;
+
+    // Added and deleted lines must not be a green/red pair
+    let theme = Theme::for_variant(ThemeVariant::DeuteranopiaSafe);
+    for style in [theme.diff_add, theme.diff_del] {
+        assert!(!matches!(style.fg, Some(Color::Green | Color::Red)));
+    }
+    let (Some(Color::Rgb(ar, ag, ab)), Some(Color::Rgb(dr, _, db))) =
+        (theme.diff_add.fg, theme.diff_del.fg)
+    else {
+        panic!("diff colors should be RGB");
+    };
+    assert!(ab > ar && ab > ag, "added lines should be blue");
+    assert!(dr > db, "deleted lines should be orange");
+
+    // The monochrome theme has no hue at all
+    let mono = Theme::for_variant(ThemeVariant::Monochrome);
+    for style in [mono.base, mono.link, mono.heading[0], mono.diff_add, mono.diff_del] {
+        if let Some(Color::Rgb(r, g, b)) = style.fg {
+            assert!(r == g && g == b);
+        }
+    }
+}
+
 #[test]
 fn integration_multi_pane_independence() {
     let content = "# Test\nLine 2\nLine 3\n";
@@ -382,7 +415,7 @@ fn integration_render_fills_viewport_when_skipping_fences() {
 
     let mut row_has_digit = false;
     for x in 0..width {
-        let symbol = buffer.get(x, last_content_row).symbol();
+        let symbol = buffer[(x, last_content_row)].symbol();
         if symbol.chars().any(|c| c.is_ascii_digit()) {
             row_has_digit = true;
             break;
@@ -452,3 +485,2430 @@ fn integration_q_does_not_quit_in_visual_line_mode() {
         "app should not be marked for quit in visual line mode"
     );
 }
+
+#[test]
+fn integration_breadcrumb_navigation_keys() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Top\n\n## Middle\n\n### Leaf\n\ntext\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, c: char| {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        handle_input(app, key, &ctx).expect("handle_input failed");
+    };
+
+    app.jump_to_line(6);
+    assert_eq!(app.get_breadcrumb_path(app.panes.focused), vec!["Top", "Middle", "Leaf"]);
+
+    // [h jumps to the parent of the current section
+    press(&mut app, '[');
+    press(&mut app, 'h');
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 2);
+
+    // gb opens the popup with the parent preselected; k then Enter jumps to the root
+    press(&mut app, 'g');
+    press(&mut app, 'b');
+    assert!(app.show_breadcrumb_popup);
+    assert_eq!(app.breadcrumb_popup_selected, 0);
+    let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+    handle_input(&mut app, enter, &ctx).expect("handle_input failed");
+    assert!(!app.show_breadcrumb_popup);
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+
+    // At the top level there is no parent to jump to
+    press(&mut app, '[');
+    press(&mut app, 'h');
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+}
+
+#[test]
+fn integration_stats_popup_keys() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Intro\n\nOne two three.\n\n## Details\n\nFour five.\n\n```\nnot counted\n```\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+
+    // g Ctrl+g opens the popup with the cursor's section selected
+    app.jump_to_line(6);
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::CONTROL);
+    let stats = app.stats_popup.as_ref().expect("stats popup open");
+    assert_eq!(stats.words, 7);
+    assert_eq!(stats.code_blocks, 1);
+    assert_eq!(app.stats_popup_selected, 1);
+
+    // k then Enter jumps to the first section and closes the popup
+    send(&mut app, KeyCode::Char('k'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.stats_popup.is_none());
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+}
+
+#[test]
+fn integration_presentation_mode() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Welcome\n\nFirst slide\n\n---\n\nMiddle slide\n\n# Last\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed")
+    };
+    let screen = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..12)
+            .map(|y| (0..60).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>())
+            .collect::<Vec<_>>()
+    };
+
+    send(&mut app, KeyCode::Char('P'), KeyModifiers::SHIFT);
+    assert_eq!(app.presentation.as_ref().map(Vec::len), Some(3));
+    let rows = screen(&mut app);
+    assert!(rows.iter().any(|r| r.contains("First slide")));
+    assert!(!rows.iter().any(|r| r.contains("Middle slide")));
+    assert!(rows[11].contains("1/3"));
+
+    send(&mut app, KeyCode::Right, KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char(' '), KeyModifiers::NONE);
+    assert_eq!(app.presentation_slide, 2);
+    let rows = screen(&mut app);
+    assert!(rows[11].contains("3/3"));
+    // Tables render without line numbers
+    assert!(rows.iter().any(|r| r.trim_start().starts_with("│ 1")), "{:#?}", rows);
+
+    // Leaving puts the cursor on the slide that was shown
+    send(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.presentation.is_none());
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 8);
+    assert!(!app.should_quit);
+
+    // `mdx present` quits when the presentation ends
+    app.quit_after_presentation = true;
+    app.start_presentation();
+    assert_eq!(app.presentation_slide, 2);
+    assert_eq!(send(&mut app, KeyCode::Char('q'), KeyModifiers::NONE), mdx_tui::input::Action::Quit);
+}
+
+#[test]
+fn integration_presentation_hides_comments() {
+    let content = "# Slide\n\n<!--\nspeaker notes\n-->\n\nShown text\n";
+    let (mut app, _file) = create_test_app(content);
+    app.start_presentation();
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..12)
+        .map(|y| (0..60).map(|x| buffer[(x, y)].symbol().to_string()).collect())
+        .collect();
+    assert!(rows.iter().any(|r| r.contains("Shown text")), "{:#?}", rows);
+    assert!(!rows.iter().any(|r| r.contains("speaker notes")), "{:#?}", rows);
+}
+
+#[test]
+fn integration_presentation_shows_conflict_markers() {
+    // A blank line before the separator keeps `ours` from reading as a
+    // setext heading, which would start a slide of its own
+    let content = "# Slide\n\n<<<<<<< HEAD\nours\n\n=======\ntheirs\n>>>>>>> b\n";
+    let (mut app, _file) = create_test_app(content);
+    app.start_presentation();
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..12)
+        .map(|y| (0..60).map(|x| buffer[(x, y)].symbol().to_string()).collect())
+        .collect();
+    assert!(rows.iter().any(|r| r.contains(">>>>>>> b")), "{:#?}", rows);
+    assert!(rows.iter().any(|r| r.contains("<<<<<<< HEAD")), "{:#?}", rows);
+    let ours = rows.iter().position(|r| r.contains("ours")).expect("ours side");
+    let x = rows[ours].find("ours").unwrap() as u16;
+    assert_eq!(buffer[(x, ours as u16)].bg, app.theme.conflict_ours_bg);
+}
+
+#[test]
+fn integration_focus_mode_dims_other_sections() {
+    use ratatui::style::Color;
+
+    let content = "# First\n\nalpha text\n\n# Second\n\nbeta text\n";
+    let (mut app, _file) = create_test_app(content);
+    app.jump_to_line(6);
+    app.toggle_focus_mode();
+    assert_eq!(app.section_range_at(6), 4..app.doc.line_count());
+
+    let (width, height) = (50u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row_text = |y: u16| (0..width).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>();
+    let find_row = |needle: &str| (0..height).find(|&y| row_text(y).contains(needle));
+
+    // Text starts right after the border: no line numbers or gutter
+    let alpha = find_row("alpha").expect("alpha rendered");
+    assert!(row_text(alpha).starts_with("│alpha"), "{:?}", row_text(alpha));
+
+    // The other section is dimmed, the current one is not
+    let alpha_x = row_text(alpha).find("alpha").unwrap() as u16;
+    assert_eq!(buffer[(alpha_x, alpha)].fg, Color::DarkGray);
+    let beta = find_row("beta").expect("beta rendered");
+    let beta_x = row_text(beta).chars().position(|c| c == 'b').unwrap() as u16;
+    assert_ne!(buffer[(beta_x, beta)].fg, Color::DarkGray);
+
+    // No status bar
+    assert!(find_row(" mdx ").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn integration_run_block_output_split() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Runbook\n\n```sh\necho ran-ok\n```\n";
+    let (mut app, _file) = create_test_app(content);
+    app.config.security.no_exec = false;
+    app.jump_to_line(3);
+
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+
+    // Refused without --insecure
+    send(&mut app, KeyCode::Char('X'), KeyModifiers::SHIFT);
+    assert!(app.block_output.is_none());
+
+    app.insecure = true;
+    send(&mut app, KeyCode::Char('X'), KeyModifiers::SHIFT);
+    assert!(app.block_output.is_none(), "runs only once confirmed");
+
+    let (width, height) = (50u16, 16u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let status = (0..width).map(|x| buffer[(x, height - 1)].symbol()).collect::<String>();
+    assert!(status.starts_with("Run sh block at line 3? [y]es [n]o"), "{}", status);
+
+    // Keys other than the answers leave the question up
+    send(&mut app, KeyCode::Char('j'), KeyModifiers::NONE);
+    assert!(app.confirm.is_some());
+    send(&mut app, KeyCode::Char('y'), KeyModifiers::NONE);
+    assert!(app.block_output.is_some());
+
+    // The block runs in the background; wait for its output
+    let result = app
+        .exec_worker
+        .as_ref()
+        .and_then(|w| {
+            (0..500).find_map(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                w.try_recv_result()
+            })
+        })
+        .expect("exec worker result");
+    app.apply_block_result(result);
+
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row_text = |y: u16| (0..width).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>();
+    let title = (0..height).find(|&y| row_text(y).contains("sh block at line 3"));
+    let output = (0..height).find(|&y| row_text(y).starts_with("│ran-ok"));
+    assert!(title.is_some() && output > title, "output split not rendered");
+
+    send(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.block_output.is_none());
+}
+
+#[test]
+fn integration_insert_mode_and_write() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Notes\n\nA tpyo here\n";
+    let (mut app, file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+
+    app.jump_to_line(2);
+    send(&mut app, KeyCode::Char('i'), KeyModifiers::NONE);
+    for _ in 0..8 {
+        send(&mut app, KeyCode::Left, KeyModifiers::NONE);
+    }
+    send(&mut app, KeyCode::Delete, KeyModifiers::NONE);
+    send(&mut app, KeyCode::Delete, KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('y'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('p'), KeyModifiers::NONE);
+    assert!(!app.should_quit);
+    assert_eq!(app.doc.rope.line(2).to_string(), "A typo here\n");
+
+    let (width, height) = (50u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert!(screen.contains(" Insert "));
+    assert!(screen.contains("[+]"));
+
+    send(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    for c in ":w".chars() {
+        send(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(!app.doc.modified);
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "# Notes\n\nA typo here\n");
+}
+
+#[test]
+fn integration_help_popup_scrolls_to_last_page() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("# Doc\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode| {
+        handle_input(app, KeyEvent::new(code, KeyModifiers::NONE), &ctx).expect("handle_input failed");
+    };
+
+    send(&mut app, KeyCode::F(1));
+    for _ in 0..500 {
+        send(&mut app, KeyCode::Char('j'));
+    }
+
+    let (width, height) = (80u16, 24u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    // The last entry of the reference is on screen and k scrolls right back
+    assert!(screen.contains("Force quit"));
+    let last_page = app.help_scroll;
+    send(&mut app, KeyCode::Char('k'));
+    assert_eq!(app.help_scroll, last_page - 1);
+}
+
+#[test]
+fn integration_buffers_cycle_with_gt_and_picker() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _first) = create_test_app("# First\n");
+    let (second, _second) = create_test_doc("# Second\n");
+    let (third, _third) = create_test_doc("# Third\n");
+    app.add_buffer(second);
+    app.add_buffer(third);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('t'), KeyModifiers::NONE);
+    assert_eq!(app.doc.headings[0].text, "Second");
+    assert!(!app.show_toc, "gt must not toggle the TOC");
+
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('T'), KeyModifiers::SHIFT);
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('T'), KeyModifiers::SHIFT);
+    assert_eq!(app.doc.headings[0].text, "Third");
+    assert!(!app.show_toc_dialog);
+
+    send(&mut app, KeyCode::Char('B'), KeyModifiers::SHIFT);
+    let (width, height) = (80u16, 24u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert!(screen.contains(" Buffers "));
+    // With several files open the tab bar replaces the (i/n) status
+    let tab_row: String = (0..width).map(|x| buffer[(x, 0)].symbol().to_string()).collect();
+    assert!(tab_row.starts_with(" 1 "), "{tab_row:?}");
+    assert!(tab_row.contains(" 3 "), "{tab_row:?}");
+    assert!(!screen.contains("(3/3)"));
+
+    send(&mut app, KeyCode::Char('k'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('k'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.doc.headings[0].text, "First");
+
+    send(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('q'), KeyModifiers::NONE);
+    assert_eq!(app.buffer_count(), 2);
+    assert_eq!(app.doc.headings[0].text, "Second");
+    assert!(!app.should_quit);
+}
+
+#[cfg(feature = "images")]
+#[test]
+fn integration_images_in_lists_and_tables() {
+    use ratatui::style::Color;
+
+    let (doc, _file) = create_test_doc(
+        "- ![solo](missing.png)\n- see ![inline](missing.png) here\n\n| Icon | Name |\n|------|------|\n| ![cell](missing.png) | x |\n",
+    );
+    let mut config = Config::default();
+    config.images.enabled = true;
+    config.security.safe_mode = false;
+    let mut app = App::new(config, doc, vec![]);
+
+    let (width, height) = (60u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row = |y: u16| -> String { (0..width).map(|x| buffer[(x, y)].symbol()).collect() };
+    let placeholder_cells = |y: u16, alt: &str| {
+        let text = row(y);
+        let start = text.find(alt).expect("alt text shown");
+        let x = text[..start].chars().count() as u16;
+        buffer[(x, y)].bg == Color::Rgb(30, 40, 50)
+    };
+
+    // An image alone in a list item keeps its bullet
+    let screen: Vec<String> = (0..height).map(row).collect();
+    let solo = screen.iter().position(|r| r.contains("solo")).expect("solo row");
+    assert!(screen[solo].contains("• 🖼"), "{:?}", screen[solo]);
+    assert!(screen[solo].contains("[unable to read]"));
+
+    // Images next to text keep the text, with the alt text marked
+    let inline = screen.iter().position(|r| r.contains("inline")).expect("inline row");
+    assert!(screen[inline].contains("see inline here"), "{:?}", screen[inline]);
+    assert!(placeholder_cells(inline as u16, "inline"));
+
+    let cell = screen.iter().position(|r| r.contains("cell")).expect("table row");
+    assert!(placeholder_cells(cell as u16, "cell"));
+}
+
+#[test]
+fn integration_a11y_mode_spells_out_structure() {
+    let (doc, _file) = create_test_doc(
+        "# Guide\n\n- item\n\n| Name | Size |\n|------|------|\n| a | 1 |\n| b | 2 |\n\n---\n",
+    );
+    let mut config = Config::default();
+    config.render.a11y = true;
+    let mut app = App::new(config, doc, vec![]);
+    assert!(!app.config.render.use_utf8_graphics && !app.config.render.show_scrollbar);
+
+    let (width, height) = (60u16, 16u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    let screen = rows.join("\n");
+
+    assert!(screen.contains("Heading level 1: Guide"), "{}", screen);
+    assert!(screen.contains("Table with 2 columns and 2 rows"), "{}", screen);
+    let drawing = |c: char| ('\u{2500}'..='\u{257f}').contains(&c) || "•▶█".contains(c);
+    assert!(!screen.chars().any(drawing), "box drawing left in:\n{}", screen);
+}
+
+#[cfg(feature = "images")]
+#[test]
+fn integration_image_caption_and_alt_text_mode() {
+    let (doc, _file) = create_test_doc(
+        "# Pictures\n\n![A red barn](barn.png \"The barn in 1950\")\n\n![Just alt](other.png)\n\nafter\n",
+    );
+    let mut config = Config::default();
+    config.images.enabled = true;
+    config.images.caption = true;
+    config.security.safe_mode = false;
+    let mut app = App::new(config, doc, vec![]);
+
+    let (width, height) = (60u16, 14u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    let mut screen = |app: &mut App| -> Vec<String> {
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+
+    // The title goes under the image, or the alt text without one
+    let rows = screen(&mut app);
+    let barn = rows.iter().position(|r| r.contains("A red barn")).expect("image row");
+    assert!(rows[barn].contains("[unable to read]"), "{:?}", rows[barn]);
+    assert!(rows[barn + 1].contains("The barn in 1950"), "{:?}", rows[barn + 1]);
+    let other = rows.iter().position(|r| r.contains("🖼  Just alt")).expect("second image");
+    assert!(rows[other + 1].contains("Just alt"), "{:?}", rows[other + 1]);
+
+    // Alt-text mode shows the description alone and doesn't repeat it
+    app.toggle_image_alt_text();
+    let rows = screen(&mut app);
+    let barn = rows.iter().position(|r| r.contains("[image: A red barn]")).expect("alt row");
+    assert!(rows[barn + 1].contains("The barn in 1950"));
+    let other = rows.iter().position(|r| r.contains("[image: Just alt]")).expect("alt row");
+    assert!(!rows[other + 1].contains("Just alt"), "{:?}", rows[other + 1]);
+    assert!(!rows.iter().any(|r| r.contains("unable to read")));
+}
+
+#[test]
+fn integration_html_comments_hidden_or_dimmed() {
+    let content =
+        "# Notes\n\nbefore <!-- aside --> after\n<!--\nreview: fix this\n-->\nlast line\n";
+    let (mut app, _file) = create_test_app(content);
+
+    let (width, height) = (60u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    let mut screen = |app: &mut App| -> Vec<String> {
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+
+    // Hidden by default, and the cursor steps over them
+    let rows = screen(&mut app);
+    assert!(rows.iter().any(|r| r.contains("before  after")));
+    assert!(!rows.iter().any(|r| r.contains("aside") || r.contains("review")));
+    let last = rows.iter().position(|r| r.contains("last line")).expect("last line");
+    let before = rows.iter().position(|r| r.contains("before")).expect("before");
+    assert_eq!(last, before + 1);
+    app.jump_to_line(2);
+    app.move_cursor_down(1);
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 6);
+    app.move_cursor_up(1);
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 2);
+
+    // Search skips them unless `search.include_comments` is set
+    app.search("review");
+    assert!(app.search_matches.is_empty());
+    app.search("aside");
+    assert!(app.search_matches.is_empty());
+    app.config.search.include_comments = true;
+    app.search("review");
+    assert_eq!(app.search_matches, vec![4]);
+    app.clear_search();
+
+    // `C` shows them dimmed
+    app.toggle_comments();
+    let rows = screen(&mut app);
+    assert!(rows.iter().any(|r| r.contains("before <!-- aside --> after")));
+    assert!(rows.iter().any(|r| r.contains("review: fix this")));
+}
+
+#[test]
+fn integration_image_cache_stats_popup() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("# Title\n");
+    app.command_line = "imagecache".to_string();
+    app.run_command_line();
+    assert!(app.show_image_cache_stats);
+
+    let (width, height) = (80u16, 24u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert!(screen.contains(" Image cache "));
+
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 80,
+        term_height: 24,
+    };
+    handle_input(&mut app, KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE), &ctx)
+        .expect("handle_input failed");
+    assert!(!app.show_image_cache_stats);
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn integration_diff_view_side_by_side() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::diff_view::DiffView;
+    use mdx_tui::input::{handle_input, Action};
+
+    let (old, _old_file) = create_test_doc("# Notes\n\nkeep\nold text\ngone\nend\n");
+    let (new, _new_file) = create_test_doc("# Notes\n\nkeep\nnew text\nend\nadded\n");
+    let (mut app, _file) = create_test_app("# Notes\n");
+    app.diff_view = Some(DiffView::new(&old, &new));
+
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+
+    // Replaced lines share a row; a removed line leaves a gap on the right
+    assert!(rows[4].contains("old text") && rows[4].contains("new text"));
+    assert!(rows[5].contains("gone") && rows[5].contains("╱"));
+    assert!(rows[9].contains("+1 -1 ~1"));
+    assert_eq!(app.diff_view.as_ref().unwrap().page_height, 8);
+
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 8, content_width: 60 }),
+        term_width: 60,
+        term_height: 10,
+    };
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    handle_input(&mut app, key(KeyCode::Char('n')), &ctx).expect("handle_input failed");
+    assert_eq!(app.diff_view.as_ref().unwrap().scroll, 3);
+    let action = handle_input(&mut app, key(KeyCode::Char('q')), &ctx).expect("handle_input failed");
+    assert_eq!(action, Action::Quit);
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn integration_git_log_compares_revision() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::{handle_input, Action};
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("run git");
+        assert!(output.status.success(), "git {:?}", args);
+    };
+    let path = dir.path().join("notes.md");
+    git(&["init", "-q"]);
+    std::fs::write(&path, "# Notes\n\nold text\n").unwrap();
+    git(&["add", "notes.md"]);
+    git(&["commit", "-q", "-m", "First draft"]);
+    std::fs::write(&path, "# Notes\n\nnew text\n").unwrap();
+    git(&["commit", "-q", "-am", "Second draft"]);
+
+    let (doc, _warnings) = Document::load(&path).expect("load");
+    let mut app = App::new(Config::default(), doc, vec![]);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 8, content_width: 60 }),
+        term_width: 60,
+        term_height: 10,
+    };
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    for code in [KeyCode::Char('g'), KeyCode::Char('l')] {
+        handle_input(&mut app, key(code), &ctx).expect("handle_input failed");
+    }
+    let log = app.git_log.as_ref().expect("git log open");
+    let summaries: Vec<&str> = log.commits.iter().map(|c| c.summary.as_str()).collect();
+    assert_eq!(summaries, ["Second draft", "First draft"]);
+
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert!(screen.contains("Ada  First draft"));
+
+    // The first draft sits on the left, the document on the right
+    handle_input(&mut app, key(KeyCode::Char('j')), &ctx).expect("handle_input failed");
+    handle_input(&mut app, key(KeyCode::Enter), &ctx).expect("handle_input failed");
+    assert!(app.git_log.is_none());
+    let view = app.diff_view.as_ref().expect("diff view open");
+    assert!(view.old_path.to_string_lossy().starts_with("notes.md@"));
+    assert_eq!(view.counts(), (0, 0, 1));
+
+    // q returns to the document instead of quitting
+    let action = handle_input(&mut app, key(KeyCode::Char('q')), &ctx).expect("handle_input failed");
+    assert_eq!(action, Action::Continue);
+    assert!(app.diff_view.is_none());
+    assert!(!app.should_quit);
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn integration_open_file_at_revision() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("run git");
+        assert!(output.status.success(), "git {:?}", args);
+    };
+    let path = dir.path().join("spec.md");
+    git(&["init", "-q"]);
+    std::fs::write(&path, "# Spec\n\nold text\n").unwrap();
+    git(&["add", "spec.md"]);
+    git(&["commit", "-q", "-m", "First draft"]);
+    std::fs::write(&path, "# Spec\n\nnew text\n").unwrap();
+    git(&["commit", "-q", "-am", "Second draft"]);
+    std::fs::write(&path, "# Spec\n\nunsaved text\n").unwrap();
+
+    let doc = Document::from_revision(&path, "HEAD~1", 0).expect("load revision");
+    assert_eq!(doc.rope.to_string(), "# Spec\n\nold text\n");
+    let mut app = App::new(Config::default(), doc, vec![]);
+
+    let (width, height) = (60u16, 6u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let status: String = (0..width).map(|x| buffer[(x, height - 1)].symbol()).collect();
+    assert!(status.contains("spec.md@HEAD~1[RO]"), "{}", status);
+
+    // Nothing edits or overwrites the working tree file
+    app.enter_insert_mode();
+    assert_eq!(app.panes.focused_pane().unwrap().view.mode, mdx_tui::app::Mode::Normal);
+    assert!(!app.save_document());
+    assert!(app.open_in_editor().is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Spec\n\nunsaved text\n");
+    assert!(Document::from_revision(&path, "no-such-rev", 0).is_err());
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn integration_deleted_lines_toggle() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("first\nsecond\nthird\n");
+    app.doc.diff_gutter = mdx_core::diff::diff_gutter_from_text(
+        "first\nremoved line\nsecond\nthird\n",
+        "first\nsecond\nthird\n",
+    );
+
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    let mut screen_rows = |app: &mut App| -> Vec<String> {
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+    assert!(!screen_rows(&mut app).iter().any(|row| row.contains("removed line")));
+
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 8, content_width: 60 }),
+        term_width: 60,
+        term_height: 10,
+    };
+    handle_input(&mut app, KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT), &ctx)
+        .expect("handle_input failed");
+    assert!(app.show_deleted_lines);
+
+    // The removed line sits between its neighbours, without a line number
+    let rows = screen_rows(&mut app);
+    let first = rows.iter().position(|row| row.contains("first")).expect("first");
+    assert!(rows[first + 1].contains("- removed line"));
+    assert!(!rows[first + 1].contains('2'));
+    assert!(rows[first + 2].contains("second"));
+}
+
+#[test]
+#[cfg(feature = "git")]
+fn integration_unsaved_edits_marked_in_gutter() {
+    let (mut app, _file) = create_test_app("first\nsecond\nthird\n");
+    app.config.git.diff = false;
+
+    app.enter_insert_mode();
+    app.edit_cursor_to_line_edge(true);
+    app.insert_text(" edited");
+    app.move_edit_line(true);
+    app.move_edit_line(true);
+    app.edit_cursor_to_line_edge(true);
+    app.insert_text("\nnew line");
+
+    // The diff worker compares the edits with the text as loaded
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    loop {
+        if let Some(result) = app.diff_worker.try_recv_result() {
+            if result.rev == app.doc.rev {
+                app.doc.diff_gutter = result.gutter;
+                break;
+            }
+        }
+        assert!(std::time::Instant::now() < deadline, "no diff result");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    let mut gutter_of = |app: &mut App, text: &str| -> String {
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        let rows: Vec<String> = (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        let row = rows.iter().find(|row| row.contains(text)).expect("row");
+        let before: Vec<char> = row[..row.find(text).unwrap()].chars().collect();
+        before[before.len() - 2..].iter().collect()
+    };
+    // Only the second column marks edits; the git column stays blank
+    assert_eq!(gutter_of(&mut app, "first edited"), " ~");
+    assert_eq!(gutter_of(&mut app, "new line"), " +");
+    assert_eq!(gutter_of(&mut app, "second"), "  ");
+
+    app.exit_insert_mode();
+    assert!(app.save_document());
+    assert_eq!(gutter_of(&mut app, "first edited"), "  ");
+}
+
+#[test]
+fn integration_merge_conflicts() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app(
+        "# Notes\n\n<<<<<<< HEAD\nour words\n=======\ntheir words\n>>>>>>> feature\n\n\
+         <<<<<<< HEAD\nmore\n=======\nless\n>>>>>>> feature\n",
+    );
+    assert_eq!(app.doc.conflicts.len(), 2);
+
+    let (width, height) = (60u16, 16u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    let row_of = |text: &str| rows.iter().position(|row| row.contains(text)).expect(text) as u16;
+
+    // Each side has its own background, right across the row
+    let ours = row_of("our words");
+    let theirs = row_of("their words");
+    assert_eq!(buffer[(40, ours)].bg, app.theme.conflict_ours_bg);
+    assert_eq!(buffer[(40, theirs)].bg, app.theme.conflict_theirs_bg);
+    assert_ne!(app.theme.conflict_ours_bg, app.theme.conflict_theirs_bg);
+    // Markers are shown as written, not as a quote or a heading underline
+    assert!(rows[row_of(">>>>>>> feature") as usize].contains(">>>>>>> feature"));
+    assert!(rows[row_of("=======") as usize].contains("======="));
+
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 14, content_width: 60 }),
+        term_width: 60,
+        term_height: 16,
+    };
+    let press = |app: &mut App, c| {
+        handle_input(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &ctx)
+            .expect("handle_input failed");
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+    press(&mut app, ']');
+    press(&mut app, 'x');
+    assert_eq!(cursor(&app), 2);
+    press(&mut app, ']');
+    press(&mut app, 'x');
+    assert_eq!(cursor(&app), 8);
+    // Wraps around at the end
+    press(&mut app, ']');
+    press(&mut app, 'x');
+    assert_eq!(cursor(&app), 2);
+    press(&mut app, '[');
+    press(&mut app, 'x');
+    assert_eq!(cursor(&app), 8);
+}
+
+#[test]
+fn integration_csv_shown_as_table() {
+    let draw = |app: &mut App| -> Vec<String> {
+        let (width, height) = (60u16, 16u16);
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+
+    // A .csv file opens as a Markdown table, and can't be saved over
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("sales.csv");
+    std::fs::write(&path, "region,total\nnorth,\"1,200\"\nsouth,a|b\n").expect("write");
+    let (mut doc, _warnings) = Document::load(&path).expect("load");
+    assert_eq!(doc.tables, vec![0..4]);
+    assert!(doc.save().is_err());
+    let mut app = App::new(Config::default(), doc, vec![]);
+    let rows = draw(&mut app);
+    let row = |text: &str| rows.iter().find(|row| row.contains(text)).expect(text).clone();
+    assert!(row("region").contains("│ total"));
+    assert!(row("1,200").contains("│ north"));
+    assert!(row("a|b").contains("│ south"));
+
+    // So does a fenced csv block, in place of its code
+    let (mut app, _file) = create_test_app(
+        "# Data\n\n```csv\nname,score\nAda,\"9|10\"\n```\n\nAfter\n",
+    );
+    let rows = draw(&mut app);
+    let row = |text: &str| rows.iter().find(|row| row.contains(text)).expect(text).clone();
+    assert!(row("name").contains("│ score"));
+    assert!(row("9|10").contains("│ Ada"));
+    assert!(!rows.iter().any(|row| row.contains("name,score")));
+    assert!(rows.iter().any(|row| row.contains("After")));
+}
+
+#[test]
+fn integration_preview_footnote_without_jumping() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app(
+        "# Spec\n\nThe limit is soft.[^why]\n\n## Later\n\n[^why]: Old clients retry.\n",
+    );
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 14, content_width: 60 }),
+        term_width: 60,
+        term_height: 16,
+    };
+    let press = |app: &mut App, c| {
+        handle_input(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &ctx)
+            .expect("handle_input failed");
+    };
+    press(&mut app, 'j');
+    press(&mut app, 'j');
+    press(&mut app, 'K');
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 2);
+
+    let (width, height) = (60u16, 16u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+        .collect();
+    assert!(screen.contains("Footnote [^why]"));
+    assert!(screen.contains("Old clients retry."));
+
+    // Any key closes the preview and does nothing else
+    press(&mut app, 'j');
+    assert!(app.preview.is_none());
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 2);
+}
+
+#[test]
+fn integration_numbered_headings() {
+    let (doc, _file) = create_test_doc("# Spec\n\n## Scope\n\ntext\n\n## Terms\n\n### Words\n");
+    let mut config = Config::default();
+    config.render.number_headings = true;
+    let mut app = App::new(config, doc, vec![]);
+    app.show_toc = true;
+
+    let (width, height) = (80u16, 14u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+
+    // Content pane keeps the `#` marks in front of the number
+    assert!(screen.contains("## 1.1 Scope"));
+    assert!(screen.contains("### 1.2.1 Words"));
+    // TOC entries are numbered too
+    assert!(screen.contains("  1.2 Terms"));
+}
+
+#[test]
+fn integration_fuzzy_finder_jumps_to_heading() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Guide\n\nSee [setup](#installation).\n\n## Usage\n\ntext\n\n## Installation\n\nrun it\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 100,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+
+    send(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL);
+    let finder = app.finder.as_ref().expect("finder open");
+    // Three headings and the link
+    assert_eq!(finder.items.len(), 4);
+    for c in "instal".chars() {
+        send(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+
+    let (width, height) = (100u16, 23u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert!(screen.contains("> instal"));
+    assert!(screen.contains("head Installation"));
+    // The preview shows the destination's text
+    assert!(screen.contains("run it"));
+
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.finder.is_none());
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 8);
+}
+
+#[test]
+fn integration_visual_text_objects() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Top\n\n## Setup\n\nSteps:\n\n```sh\nmake\nmake install\n```\n\n## Usage\n\ntext\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, c: char| {
+        let modifiers = if c.is_uppercase() { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+        let key = KeyEvent::new(KeyCode::Char(c), modifiers);
+        handle_input(app, key, &ctx).expect("handle_input failed");
+    };
+    let selection = |app: &App| app.panes.focused_pane().unwrap().view.selection.unwrap().range();
+
+    app.jump_to_line(7);
+    press(&mut app, 'V');
+    press(&mut app, 'i');
+    press(&mut app, 'c');
+    assert_eq!(selection(&app), (7, 8));
+    press(&mut app, 'a');
+    press(&mut app, 'c');
+    assert_eq!(selection(&app), (6, 9));
+    press(&mut app, 'a');
+    press(&mut app, 's');
+    assert_eq!(selection(&app), (2, 10));
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 10);
+
+    // Still in visual mode, so `i` did not start editing
+    assert_eq!(app.panes.focused_pane().unwrap().view.mode, mdx_tui::app::Mode::VisualLine);
+}
+
+#[test]
+fn integration_wrapped_selection_fills_rows() {
+    use ratatui::style::Color;
+
+    let long = "word ".repeat(30);
+    let (mut app, _file) = create_test_app(&format!("{}\nshort\n", long.trim_end()));
+    app.enter_visual_line_mode();
+
+    let (width, height) = (40u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let selected_rows: Vec<u16> = (0..height)
+        .filter(|&y| (0..width).any(|x| buffer[(x, y)].bg == Color::Cyan))
+        .collect();
+
+    // The first line wraps onto several rows, and each is highlighted
+    // edge to edge inside the pane border
+    assert!(selected_rows.len() >= 3, "rows: {:?}", selected_rows);
+    for &y in &selected_rows {
+        assert!(
+            (1..width - 1).all(|x| buffer[(x, y)].bg == Color::Cyan),
+            "row {} is not filled",
+            y
+        );
+    }
+    // The unselected line after it is not highlighted
+    let next = selected_rows.last().unwrap() + 1;
+    assert!((1..width - 1).all(|x| buffer[(x, next)].bg != Color::Cyan));
+}
+
+#[test]
+fn integration_status_bar_template() {
+    let (mut app, _file) = create_test_app("# Title\n\nline\nline\n");
+    app.config.statusbar.left = " <{mode}>{nosuch}{toc}".to_string();
+    app.config.statusbar.right = "{line}/{lines} ".to_string();
+
+    let (width, height) = (30u16, 8u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row: String = (0..width)
+        .map(|x| buffer[(x, height - 1)].symbol().to_string())
+        .collect();
+
+    // Unknown tokens are left as typed, empty ones vanish, and the right
+    // side is pushed flush against the edge
+    assert!(row.starts_with(" <NORMAL>{nosuch}"), "row: {:?}", row);
+    assert!(row.ends_with("1/5 "), "row: {:?}", row);
+}
+
+#[test]
+fn integration_help_search() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("# Test Document\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        let key = KeyEvent::new(code, KeyModifiers::NONE);
+        handle_input(app, key, &ctx).expect("handle_input failed");
+    };
+    let render = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..30)
+            .map(|y| (0..80).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    app.toggle_help();
+    press(&mut app, KeyCode::Char('/'));
+    for c in "fold".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    press(&mut app, KeyCode::Enter);
+    let screen = render(&mut app);
+    assert!(screen.contains("/fold"));
+    assert!(screen.contains("Close all folds"));
+    assert!(!screen.contains("Move cursor down"));
+
+    // Esc clears the search first, then closes the popup
+    press(&mut app, KeyCode::Esc);
+    assert!(app.show_help);
+    assert!(render(&mut app).contains("Move cursor down"));
+    press(&mut app, KeyCode::Esc);
+    assert!(!app.show_help);
+}
+
+#[test]
+fn integration_load_error_screen() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{LoadError, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let missing = dir.path().join("gone.md");
+    let error = Document::load(&missing).expect_err("file is missing");
+    let mut app = App::new(Config::default(), Document::unloaded(&missing, 0), vec![]);
+    app.load_error = Some(LoadError::new(&missing, &error));
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("Couldn't open gone.md"));
+    assert!(screen.contains("Error: Failed to canonicalize path"));
+    assert!(screen.contains("Retry"));
+
+    // o opens another file in its place
+    let other = dir.path().join("other.md");
+    std::fs::write(&other, "# Other\n").expect("write");
+    let ctx = ScrollContext {
+        viewport: None,
+        term_width: 80,
+        term_height: 20,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        handle_input(app, KeyEvent::new(code, KeyModifiers::NONE), &ctx).expect("handle_input")
+    };
+    press(&mut app, KeyCode::Char('o'));
+    let prompt = app.load_error.as_ref().and_then(|e| e.prompt.clone());
+    for _ in 0..prompt.map_or(0, |p| p.chars().count()) {
+        press(&mut app, KeyCode::Backspace);
+    }
+    for c in other.display().to_string().chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    press(&mut app, KeyCode::Enter);
+    assert!(app.load_error.is_none());
+    assert_eq!(app.doc.headings[0].text, "Other");
+}
+
+#[test]
+fn integration_invalid_utf8_banner() {
+    let mut file = NamedTempFile::new().expect("temp file");
+    file.write_all(b"# Caf\xc3\xa9 \xe9\n\ntext\n").expect("write");
+    let (doc, _warnings) = Document::load(file.path()).expect("lossy load");
+    let mut app = App::new(Config::default(), doc, vec![]);
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 12)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row = |y: u16| -> String { (0..100).map(|x| buffer[(x, y)].symbol()).collect() };
+    assert!(row(0).starts_with(" Not valid UTF-8: 1 invalid sequence shown as"));
+    assert!((1..12).any(|y| row(y).contains("Caf\u{e9} \u{FFFD}")));
+}
+
+#[test]
+fn integration_utf16_encoding_in_status_bar() {
+    let bytes: Vec<u8> = "# Notes\n\ntext\n"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let mut file = NamedTempFile::new().expect("temp file");
+    file.write_all(&bytes).expect("write");
+    let (doc, _warnings) = Document::load(file.path()).expect("utf-16 load");
+    let mut app = App::new(Config::default(), doc, vec![]);
+    assert_eq!(app.doc.headings[0].text, "Notes");
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 10)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let status: String = (0..100).map(|x| buffer[(x, 9)].symbol()).collect();
+    assert!(status.contains(" [UTF-16LE] "), "status: {:?}", status);
+}
+
+#[test]
+fn integration_ctrl_z_suspends() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::{handle_input, Action};
+
+    let (mut app, _file) = create_test_app("# Test Document\n\nBody\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+
+    let key = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+    let action = handle_input(&mut app, key, &ctx).expect("handle_input failed");
+    assert_eq!(action, Action::Suspend);
+
+    // A plain z still starts the fold prefix
+    let key = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE);
+    let action = handle_input(&mut app, key, &ctx).expect("handle_input failed");
+    assert_eq!(action, Action::Continue);
+}
+
+#[test]
+fn integration_log_viewer() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+    use mdx_tui::logging::LogBuffer;
+    use tracing::Level;
+
+    let (mut app, _file) = create_test_app("# Test Document\n\nBody\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let render = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..24)
+            .map(|y| (0..80).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // Without --verbose there is nothing to show
+    app.log_buffer = None;
+    app.command_line = "log".to_string();
+    app.run_command_line();
+    assert!(app.log_viewer.is_none());
+
+    let buffer = LogBuffer::new(100);
+    for i in 0..30 {
+        buffer.push(Level::DEBUG, "mdx_tui::diff_worker", format!("Diff computed {}", i));
+    }
+    buffer.push(Level::WARN, "mdx_tui::watcher", "last event".to_string());
+    app.log_buffer = Some(buffer);
+    app.command_line = "log".to_string();
+    app.run_command_line();
+    assert_eq!(app.log_viewer, Some(0));
+
+    let screen = render(&mut app);
+    assert!(screen.contains("Log (31 events)"));
+    assert!(screen.contains("WARN  mdx_tui::watcher last event"));
+    assert!(!screen.contains("Diff computed 0 "));
+
+    // Scrolling back reaches the oldest event and stops there
+    let press = |app: &mut App, code: KeyCode| {
+        handle_input(app, KeyEvent::new(code, KeyModifiers::NONE), &ctx).expect("handle_input failed");
+    };
+    press(&mut app, KeyCode::Char('g'));
+    assert_eq!(app.log_viewer, Some(30));
+    assert!(render(&mut app).contains("Diff computed 0 "));
+    press(&mut app, KeyCode::Char('j'));
+    assert_eq!(app.log_viewer, Some(29));
+    press(&mut app, KeyCode::Char('G'));
+    assert_eq!(app.log_viewer, Some(0));
+    press(&mut app, KeyCode::Char('q'));
+    press(&mut app, KeyCode::Char('q'));
+    assert!(app.log_viewer.is_none());
+}
+
+#[test]
+fn integration_nested_fences_stay_code() {
+    let content = "# Guide\n\n````markdown\n```sh\n# install step\n```\n````\n\n## Usage\n";
+    let (mut app, _file) = create_test_app(content);
+
+    let headings: Vec<_> = app.doc.headings.iter().map(|h| h.text.as_str()).collect();
+    assert_eq!(headings, ["Guide", "Usage"]);
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 16)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..16)
+        .map(|y| (0..60).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    // The inner fence is content of the outer block, not a fence of its own
+    assert!(rows.iter().any(|row| row.contains("```sh")));
+    assert!(rows.iter().any(|row| row.contains("# install step")));
+    assert!(!rows.iter().any(|row| row.contains("````")));
+}
+
+#[test]
+fn integration_follow_anchor_link() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Spec\n\nSee [the API](#api-reference) and [setup](setup.md).\n\nText\n\n## API Reference\n\nDetails\n\n## API Reference\n\nMore\n\n[dup](#api-reference-1)\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    app.jump_to_line(2);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 6);
+
+    // Ctrl+O returns to the link
+    press(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+    assert_eq!(cursor(&app), 2);
+
+    // gf follows the numbered anchor of the second heading
+    app.jump_to_line(14);
+    press(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+    press(&mut app, KeyCode::Char('f'), KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 10);
+
+    // A line without links leaves the cursor alone
+    app.jump_to_line(4);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 4);
+    assert!(app.status_message.as_ref().is_some_and(|(m, _)| m.contains("No link")));
+}
+
+#[test]
+fn integration_nested_list_hanging_indent() {
+    let content = "- Top\n  - Nested item with enough words to wrap onto a second row\n    1) Third level\n\
+                   lazy continuation of the third item\n";
+    let (mut app, _file) = create_test_app(content);
+
+    let mut terminal = Terminal::new(TestBackend::new(50, 12)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..12)
+        .map(|y| (0..50).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+    let column = |row: &str, needle: &str| row[..row.find(needle).unwrap()].chars().count();
+
+    // Each level gets its own bullet; ordered markers are normalized
+    let top = rows.iter().position(|row| row.contains("• Top")).expect("top bullet");
+    let nested = &rows[top + 1];
+    assert!(nested.contains("◦ Nested"), "{:?}", rows);
+    assert!(rows[top + 3].contains("1. Third"), "{:?}", rows);
+
+    // The wrapped row starts under the item's text, not under the bullet
+    let text_column = column(nested, "Nested");
+    let wrapped = &rows[top + 2];
+    let first_char = wrapped.chars().skip(1).position(|c| c != ' ').unwrap() + 1;
+    assert_eq!(first_char, text_column, "{:?}", rows);
+
+    // A lazy continuation line lines up with the third item's text
+    let lazy = rows.iter().find(|row| row.contains("lazy")).expect("lazy line");
+    assert_eq!(column(lazy, "lazy"), column(&rows[top + 3], "Third"), "{:?}", rows);
+}
+
+#[test]
+fn integration_renumbered_lists() {
+    let content = "1. Plan\n1. Build\n   1. Parser\n   1. Renderer\n1. Ship\n";
+    let (doc, _file) = create_test_doc(content);
+    let mut config = Config::default();
+    config.render.renumber_lists = true;
+    let mut app = App::new(config, doc, vec![]);
+
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+
+    assert!(screen.contains("2. Build"));
+    assert!(screen.contains("3. Ship"));
+    // Nested ordered lists use roman numerals, as on GitHub
+    assert!(screen.contains("i. Parser"));
+    assert!(screen.contains("ii. Renderer"));
+    // The source is untouched
+    assert_eq!(app.doc.rope.line(1).to_string(), "1. Build\n");
+}
+
+#[test]
+fn integration_justified_hyphenated_paragraph() {
+    let content = "# Notes\n\nCharacterization of internationalization requirements demonstrates \
+                   extraordinary responsibilities across organizations.\n";
+    let (doc, _file) = create_test_doc(content);
+    let mut config = Config::default();
+    config.render.justify = true;
+    config.render.hyphenate = cfg!(feature = "hyphenation");
+    let mut app = App::new(config, doc, vec![]);
+
+    let (width, height) = (40u16, 14u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let rows: Vec<String> = (0..height)
+        .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+        .collect();
+
+    let first = rows.iter().position(|row| row.contains("Character")).expect("paragraph");
+    let last = rows.iter().position(|row| row.contains("organizations")).expect("last row");
+    assert!(last > first + 1, "{:?}", rows);
+    for row in &rows[first..last] {
+        // Text runs right up to the pane border
+        let inner: String = row.chars().take(width as usize - 1).collect();
+        assert!(!inner.ends_with(' '), "{:?}", rows);
+    }
+    if cfg!(feature = "hyphenation") {
+        let hyphenated = |row: &String| row.trim_end_matches(['│', ' ']).ends_with('-');
+        assert!(rows[first..last].iter().any(hyphenated), "{:?}", rows);
+    }
+}
+
+#[test]
+fn integration_tab_jumps_forward_without_enhanced_keys() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Top\n\nSee [end](#end).\n\ntext\n\n## End\n\ndone\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    app.jump_to_line(2);
+    press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 6);
+    press(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+    assert_eq!(cursor(&app), 2);
+
+    // Legacy terminals send Tab for Ctrl+I
+    press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 6);
+
+    // With the kitty protocol the two are distinct keys
+    app.enhanced_keys = true;
+    press(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+    press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+    assert_eq!(cursor(&app), 2);
+    press(&mut app, KeyCode::Char('i'), KeyModifiers::CONTROL);
+    assert_eq!(cursor(&app), 6);
+}
+
+#[test]
+fn integration_code_label_positions() {
+    use mdx_core::config::CodeLabel;
+
+    let content = "# Run\n\n```sh\nmake install\n```\n\nDone.\n";
+    let (width, height) = (50u16, 10u16);
+    let screen = |app: &mut App| -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+    let row_of = |rows: &[String], text: &str| rows.iter().position(|row| row.contains(text));
+
+    // Right: on the first code row, the fence takes no row
+    let (mut app, _file) = create_test_app(content);
+    let rows = screen(&mut app);
+    let code = row_of(&rows, "make install").expect("code row");
+    assert!(rows[code].contains(" sh │"), "{:?}", rows);
+    assert_eq!(row_of(&rows, "Done."), Some(code + 2));
+
+    // Left: a header row in place of the opening fence
+    let mut config = Config::default();
+    config.render.code_label = CodeLabel::Left;
+    app.set_config(config.clone());
+    let rows = screen(&mut app);
+    let code = row_of(&rows, "make install").expect("code row");
+    assert!(rows[code - 1].contains("3     sh"), "{:?}", rows);
+    assert!(!rows[code].contains(" sh "));
+
+    // Hidden: no label, but a copied block still flashes
+    config.render.code_label = CodeLabel::Hidden;
+    app.set_config(config);
+    let rows = screen(&mut app);
+    assert!(!rows.iter().any(|row| row.contains(" sh ")), "{:?}", rows);
+    app.copied_code_block = Some((2, std::time::Instant::now()));
+    let rows = screen(&mut app);
+    let code = row_of(&rows, "make install").expect("code row");
+    assert!(rows[code].contains("copied!"), "{:?}", rows);
+}
+
+#[test]
+fn integration_commonmark_mode_renders_document_structure() {
+    let content = "# Notes\n\n> quoted\nlazy line\n\nText:\n\n    let x = 1;\n";
+    let (width, height) = (50u16, 12u16);
+    let screen = |app: &mut App| -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+    let row_with = |rows: &[String], text: &str| {
+        rows.iter()
+            .find(|row| row.contains(text))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    // Line by line, the lazy line leaves the quote
+    let (mut app, _file) = create_test_app(content);
+    let rows = screen(&mut app);
+    assert!(!row_with(&rows, "lazy line").contains("│ lazy"), "{:?}", rows);
+
+    let mut config = Config::default();
+    config.render.commonmark = true;
+    app.set_config(config);
+    let rows = screen(&mut app);
+    assert!(row_with(&rows, "lazy line").contains("│ lazy line"), "{:?}", rows);
+    // Indented code loses its indent: it starts one padding column right
+    // of the paragraph text, on the code background
+    let column = |text: &str| {
+        rows.iter()
+            .find_map(|row| Some(row[..row.find(text)?].chars().count()))
+            .expect("text on screen")
+    };
+    assert_eq!(column("let x = 1;"), column("Text:") + 1, "{:?}", rows);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let y = rows.iter().position(|row| row.contains("let x")).unwrap();
+    let cell = &terminal.backend().buffer()[(column("let x") as u16, y as u16)];
+    assert_eq!(cell.bg, app.theme.code_block_bg);
+}
+
+#[test]
+fn integration_gfm_tables_and_strikethrough_follow_flavor() {
+    use mdx_core::config::MarkdownFlavor;
+    use ratatui::style::Modifier;
+
+    let content = "Pipe it: ls | grep md | wc -l and more words to wrap the line\n\n~~gone~~\n\n| A | B |\n|---|---|\n| 1 | 2 |\n";
+    let (width, height) = (40u16, 14u16);
+    let draw = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        terminal.backend().buffer().clone()
+    };
+    let rows = |buffer: &ratatui::buffer::Buffer| -> Vec<String> {
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+
+    let (mut app, _file) = create_test_app(content);
+    let buffer = draw(&mut app);
+    let screen = rows(&buffer);
+    // The pipes in prose stay pipes, and the line wraps like prose
+    assert!(screen.iter().any(|row| row.contains("ls | grep md")), "{:?}", screen);
+    assert!(screen.iter().any(|row| row.contains("wrap the line")), "{:?}", screen);
+    assert!(screen.iter().any(|row| row.contains("│ A ")), "{:?}", screen);
+    let y = screen.iter().position(|row| row.contains("gone")).unwrap();
+    let x = screen[y][..screen[y].find("gone").unwrap()].chars().count();
+    let cell = &buffer[(x as u16, y as u16)];
+    assert!(cell.modifier.contains(Modifier::CROSSED_OUT));
+
+    // CommonMark has neither tables nor strikethrough
+    let mut config = Config::default();
+    config.render.markdown.flavor = MarkdownFlavor::CommonMark;
+    app.set_config(config);
+    let buffer = draw(&mut app);
+    let screen = rows(&buffer);
+    assert!(screen.iter().any(|row| row.contains("| A | B |")), "{:?}", screen);
+    assert!(screen.iter().any(|row| row.contains("~~gone~~")), "{:?}", screen);
+}
+
+#[test]
+fn integration_yc_outside_code_block() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{KeyPrefix, PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("# Run\n\n```sh\nmake\n```\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, c: char| {
+        handle_input(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &ctx)
+            .expect("handle_input failed");
+    };
+
+    press(&mut app, 'y');
+    assert_eq!(app.key_prefix, KeyPrefix::Y);
+    press(&mut app, 'c');
+    assert_eq!(app.key_prefix, KeyPrefix::None);
+    let (message, _) = app.status_message.clone().expect("message");
+    assert_eq!(message, "Not in a code block");
+    assert!(app.copied_code_block.is_none());
+}
+
+#[test]
+fn integration_line_number_modes() {
+    use mdx_core::config::LineNumbers;
+
+    let content = "alpha\nbravo\ncharlie\ndelta\n";
+    let (width, height) = (40u16, 10u16);
+    let screen = |app: &mut App| -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+    // The gutter text in front of each word
+    let numbers = |rows: &[String]| -> Vec<String> {
+        ["alpha", "bravo", "charlie", "delta"]
+            .iter()
+            .map(|word| {
+                let row = rows.iter().find(|row| row.contains(word)).expect("word");
+                row[..row.find(word).unwrap()].trim_matches([' ', '│']).to_string()
+            })
+            .collect()
+    };
+
+    let (mut app, _file) = create_test_app(content);
+    app.move_cursor_down(2);
+    assert_eq!(numbers(&screen(&mut app)), ["1", "2", "3", "4"]);
+
+    let set_mode = |app: &mut App, mode| {
+        let mut config = Config::default();
+        config.render.line_numbers = mode;
+        app.set_config(config);
+        screen(app)
+    };
+    assert_eq!(
+        numbers(&set_mode(&mut app, LineNumbers::Relative)),
+        ["2", "1", "0", "1"]
+    );
+    assert_eq!(
+        numbers(&set_mode(&mut app, LineNumbers::Hybrid)),
+        ["2", "1", "3", "1"]
+    );
+
+    // Off drops the number column, so text starts further left
+    let absolute = set_mode(&mut app, LineNumbers::Absolute);
+    let off = set_mode(&mut app, LineNumbers::Off);
+    assert_eq!(numbers(&off), ["", "", "", ""]);
+    let column = |rows: &[String]| rows.iter().find_map(|row| row.find("alpha")).unwrap();
+    assert_eq!(column(&absolute) - column(&off), 4);
+}
+
+#[test]
+fn integration_count_prefixes_repeat_motions() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{KeyPrefix, PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# One\n\ntext\n\n# Two\n\n# Three\n\n# Four\n".to_string() + &"text\n".repeat(20);
+    let (mut app, _file) = create_test_app(&content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, keys: &str| {
+        for c in keys.chars() {
+            let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+            handle_input(app, key, &ctx).expect("handle_input failed");
+        }
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    press(&mut app, "3j");
+    assert_eq!(cursor(&app), 3);
+    assert_eq!(app.pending_count, None);
+    press(&mut app, "2k");
+    assert_eq!(cursor(&app), 1);
+
+    // The count waits through the `]` prefix and shows in the status bar
+    press(&mut app, "2]");
+    assert_eq!((app.pending_count, app.key_prefix), (Some(2), KeyPrefix::RightBracket));
+    press(&mut app, "]");
+    assert_eq!(cursor(&app), 6);
+    press(&mut app, "[[");
+    assert_eq!(cursor(&app), 4);
+
+    // An unused count is dropped by the next command, and 0 alone isn't one
+    press(&mut app, "5m");
+    assert_eq!(app.pending_count, None);
+    press(&mut app, "0");
+    assert_eq!(app.pending_count, None);
+    press(&mut app, "10");
+    assert_eq!(app.pending_count, Some(10));
+    press(&mut app, "j");
+    assert_eq!(cursor(&app), 14);
+}
+
+#[test]
+fn integration_counted_goto_and_search_jumps() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Top\n".to_string() + &"text\n".repeat(20) + "needle\n";
+    let (mut app, _file) = create_test_app(&content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 10, content_width: 80 }),
+        term_width: 82,
+        term_height: 13,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        let modifiers = match code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+    let keys = |app: &mut App, keys: &str| keys.chars().for_each(|c| press(app, KeyCode::Char(c)));
+    let back = |app: &mut App| {
+        let key = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+        handle_input(app, key, &ctx).expect("handle_input failed");
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    keys(&mut app, "12G");
+    assert_eq!(cursor(&app), 11);
+    keys(&mut app, "5gg");
+    assert_eq!(cursor(&app), 4);
+    keys(&mut app, "G");
+    assert_eq!(cursor(&app), 22);
+    keys(&mut app, "gg");
+    assert_eq!(cursor(&app), 0);
+
+    // A search that moves the cursor as you type is a jump too
+    keys(&mut app, "/needle");
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(cursor(&app), 21);
+    back(&mut app);
+    assert_eq!(cursor(&app), 0);
+    back(&mut app);
+    assert_eq!(cursor(&app), 22);
+}
+
+#[test]
+fn integration_incremental_search_previews_and_reverts() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "apple\n".to_string() + &"text\n".repeat(30) + "apricot\n" + &"text\n".repeat(30);
+    let (mut app, _file) = create_test_app(&content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 10, content_width: 80 }),
+        term_width: 82,
+        term_height: 13,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        handle_input(app, KeyEvent::new(code, KeyModifiers::NONE), &ctx).expect("handle_input failed");
+    };
+    let keys = |app: &mut App, keys: &str| keys.chars().for_each(|c| press(app, KeyCode::Char(c)));
+    let view = |app: &App| {
+        let view = &app.panes.focused_pane().unwrap().view;
+        (view.cursor_line, view.scroll_line())
+    };
+
+    app.jump_to_line(10);
+    let start = view(&app);
+
+    // The first match after the cursor is previewed while typing, and the
+    // match list follows the query
+    keys(&mut app, "/ap");
+    assert_eq!(app.search_matches, vec![0, 31]);
+    assert_eq!(view(&app).0, 31);
+    keys(&mut app, "x");
+    assert!(app.search_matches.is_empty());
+    assert_eq!(view(&app), start);
+    press(&mut app, KeyCode::Backspace);
+    assert_eq!(view(&app).0, 31);
+
+    // Esc goes back to where the search started
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(view(&app), start);
+    assert!(app.search_query.is_empty());
+
+    // Without incremental search the cursor stays put until Enter
+    let mut config = Config::default();
+    config.search.incremental = false;
+    app.set_config(config);
+    keys(&mut app, "/ap");
+    assert_eq!(app.search_matches, vec![0, 31]);
+    assert_eq!(view(&app), start);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(view(&app).0, 31);
+    assert_eq!(app.search_current_match, Some(1));
+}
+
+#[test]
+fn integration_backward_search_and_offsets() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "mark\none\ntwo\nmark\none\ntwo\nmark\none\ntwo\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        let modifiers = match code {
+            KeyCode::Char('N') => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed");
+    };
+    let keys = |app: &mut App, keys: &str| keys.chars().for_each(|c| press(app, KeyCode::Char(c)));
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    // ? finds the nearest match above, and n keeps going up
+    app.jump_to_line(5);
+    keys(&mut app, "?mark");
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(cursor(&app), 3);
+    keys(&mut app, "n");
+    assert_eq!(cursor(&app), 0);
+    keys(&mut app, "N");
+    assert_eq!(cursor(&app), 3);
+
+    // An offset moves off the match, for n too
+    keys(&mut app, "/mark/+2");
+    assert_eq!(app.search_query, "mark");
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(cursor(&app), 5);
+    keys(&mut app, "n");
+    assert_eq!(cursor(&app), 8);
+}
+
+#[test]
+fn integration_search_match_cap_and_noh() {
+    let content = "hit\nmiss\n".repeat(10);
+    let (mut app, _file) = create_test_app(&content);
+    let mut config = Config::default();
+    config.search.max_matches = 3;
+    app.set_config(config);
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    // Only the first matches are found up front
+    app.search("hit");
+    assert_eq!(app.search_matches, vec![0, 2, 4]);
+    assert_eq!(app.search_match_count(), "3+");
+
+    // Stepping past them finds more, and wrapping back finds the last
+    for _ in 0..3 {
+        app.next_search_match(20);
+    }
+    assert_eq!(cursor(&app), 6);
+    assert_eq!(app.search_current_match, Some(3));
+    app.jump_to_line(0);
+    app.search("hit");
+    assert_eq!(app.search_matches, vec![0, 2, 4]);
+    app.prev_search_match(20);
+    assert_eq!(cursor(&app), 18);
+    assert_eq!(app.search_match_count(), "10");
+
+    // A preview past the first matches scans ahead to reach it
+    app.jump_to_line(13);
+    app.enter_search_mode();
+    "hit".chars().for_each(|c| app.search_add_char(c));
+    assert_eq!(cursor(&app), 14);
+    app.confirm_search();
+
+    // :noh hides the highlights but keeps the query for n
+    app.command_line = "noh".to_string();
+    app.run_command_line();
+    assert!(!app.search_highlight);
+    assert_eq!(app.search_query, "hit");
+    app.repeat_search(false, 20);
+    assert!(app.search_highlight);
+    assert_eq!(cursor(&app), 16);
+}
+
+#[test]
+fn integration_toc_section_sizes() {
+    let content =
+        "# Intro\n\nSome words here.\n\n```\ncode\n```\n\n## Details\n\nMore text.\n";
+    let (doc, _file) = create_test_doc(content);
+    let mut config = Config::default();
+    config.toc.enabled = true;
+    config.toc.stats = true;
+    let mut app = App::new(config, doc, vec![]);
+
+    let (width, height) = (80u16, 12u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    let mut screen = |app: &mut App| -> Vec<String> {
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    };
+
+    // Sizes include subsections and are right-aligned in the sidebar
+    let rows = screen(&mut app);
+    let toc: Vec<&str> = rows.iter().map(|r| r.split('│').nth(1).unwrap_or("")).collect();
+    assert!(toc.iter().any(|r| r.starts_with("Intro") && r.ends_with("11L 7W 1C")), "{:?}", toc);
+    assert!(toc.iter().any(|r| r.starts_with("  Details") && r.ends_with("3L 3W")));
+
+    // Recomputed once the document changes
+    app.doc.edit(8..8, "one two\n");
+    let rows = screen(&mut app);
+    assert!(rows.iter().any(|r| r.contains("Intro") && r.contains("12L 9W 1C")));
+
+    app.config.toc.stats = false;
+    let rows = screen(&mut app);
+    assert!(!rows.iter().any(|r| r.contains("3L 3W")));
+}
+
+#[test]
+fn integration_command_palette() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::commands::Command;
+    use mdx_tui::finder::FinderKind;
+    use mdx_tui::input::handle_input;
+
+    let (mut app, _file) = create_test_app("# Intro\n\nText\n");
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed")
+    };
+
+    // Keys still reach their commands through the keymap
+    let shown = app.show_toc;
+    send(&mut app, KeyCode::Char('t'), KeyModifiers::NONE);
+    assert_eq!(app.show_toc, !shown);
+
+    // Ctrl+Shift+P lists every command; typing narrows and Enter runs it
+    send(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+    let finder = app.finder.as_ref().expect("palette open");
+    assert!(finder.is_palette());
+    assert_eq!(finder.items.len(), Command::ALL.len());
+    for c in "split vert".chars() {
+        send(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    let selected = app.finder.as_ref().and_then(|f| f.selected_item()).expect("match");
+    assert_eq!(selected.kind, FinderKind::Command(Command::SplitV));
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(app.finder.is_none());
+    assert_eq!(app.panes.panes.len(), 2);
+
+    // '>' at the start of the fuzzy finder switches to the commands
+    send(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL);
+    assert!(!app.finder.as_ref().unwrap().is_palette());
+    send(&mut app, KeyCode::Char('>'), KeyModifiers::SHIFT);
+    assert!(app.finder.as_ref().unwrap().is_palette());
+    for c in "close pane".chars() {
+        send(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+    }
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.panes.panes.len(), 1);
+    assert!(!app.should_quit);
+}
+
+#[test]
+fn integration_toc_picker_filters_and_previews() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# Intro\n\nWelcome text\n\n## Setup\n\nInstall steps here\n\n## Usage\n\nRun the tool\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+        handle_input(app, KeyEvent::new(code, modifiers), &ctx).expect("handle_input failed")
+    };
+    let screen = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(80, 16)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..16)
+            .map(|y| (0..80).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>())
+            .collect::<Vec<_>>()
+    };
+
+    // The selected heading's section shows beside the list
+    send(&mut app, KeyCode::Char('T'), KeyModifiers::SHIFT);
+    assert!(app.show_toc_dialog);
+    assert_eq!(app.toc_dialog_headings(), vec![0, 1, 2]);
+    assert!(screen(&mut app).iter().any(|r| r.contains("Welcome text")));
+
+    // Typing narrows the list and moves the preview to the first match
+    send(&mut app, KeyCode::Char('/'), KeyModifiers::NONE);
+    for c in "USA".chars() {
+        send(&mut app, KeyCode::Char(c), KeyModifiers::SHIFT);
+    }
+    assert_eq!(app.toc_dialog_headings(), vec![2]);
+    let rows = screen(&mut app);
+    assert!(rows.iter().any(|r| r.contains("/USA_")));
+    assert!(rows.iter().any(|r| r.contains("Run the tool")));
+    assert!(!rows.iter().any(|r| r.contains("Setup")));
+
+    // A filter with no hits says so
+    send(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+    assert!(screen(&mut app).iter().any(|r| r.contains("No matching headings")));
+    send(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+
+    // Enter keeps the filter; Enter again jumps to the filtered heading
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(!app.toc_dialog_filtering);
+    assert!(app.show_toc_dialog);
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert!(!app.show_toc_dialog);
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 8);
+
+    // Esc clears a kept filter before closing, and reopening starts afresh
+    send(&mut app, KeyCode::Char('T'), KeyModifiers::SHIFT);
+    send(&mut app, KeyCode::Char('/'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Char('s'), KeyModifiers::NONE);
+    send(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(app.toc_dialog_headings(), vec![1, 2]);
+    send(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(app.show_toc_dialog);
+    assert_eq!(app.toc_dialog_headings().len(), 3);
+    send(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+    assert!(!app.show_toc_dialog);
+}
+
+#[test]
+fn integration_reload_anchors_other_panes_and_shows_badges() {
+    let content: String = (0..40).map(|n| format!("line {}\n", n)).collect();
+    let (mut app, file) = create_test_app(&content);
+    app.split_focused(mdx_tui::panes::SplitDir::Vertical);
+    let focused = app.panes.focused;
+    let other = *app.panes.panes.keys().find(|&&id| id != focused).unwrap();
+    {
+        let view = &mut app.panes.panes.get_mut(&other).unwrap().view;
+        view.set_scroll_line(20);
+        view.cursor_line = 22;
+    }
+    app.panes.focused_pane_mut().unwrap().view.show_raw = true;
+
+    let screen = |app: &mut App| {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).expect("terminal");
+        terminal.draw(|frame| ui::draw(frame, app)).expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (0..30)
+            .map(|y| (0..100).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // Only the raw pane carries the badge
+    let text = screen(&mut app);
+    assert_eq!(text.matches("[RAW]").count(), 1);
+    assert!(!text.contains("[DIRTY]"));
+
+    // A change on disk not yet reloaded flags every pane's border
+    app.doc.dirty_on_disk = true;
+    let text = screen(&mut app);
+    let borders = text.lines().find(|row| row.contains("[RAW]")).unwrap();
+    assert_eq!(borders.matches("[DIRTY]").count(), 2);
+
+    // Lines inserted above keep the other pane on the text it showed
+    std::fs::write(file.path(), format!("new\nnew\nnew\n{}", content)).unwrap();
+    app.reload_document().unwrap();
+    let view = &app.panes.panes[&other].view;
+    assert_eq!((view.scroll_line(), view.cursor_line), (23, 25));
+    assert_eq!(app.panes.focused_pane().unwrap().view.scroll_line(), 0);
+    assert!(!screen(&mut app).contains("[DIRTY]"));
+}
+
+#[test]
+fn integration_fmt_command_normalizes_as_an_edit() {
+    let content = "Title\n=====\n\n\n* one\n* two\n\n|a|b|\n|-|-|\n|1|22|\n";
+    let (mut app, file) = create_test_app(content);
+    app.panes.focused_pane_mut().unwrap().view.cursor_line = 7;
+
+    app.command_line = "fmt".to_string();
+    app.run_command_line();
+    assert_eq!(
+        app.doc.rope.to_string(),
+        "# Title\n\n- one\n- two\n\n| a   | b   |\n| --- | --- |\n| 1   | 22  |\n"
+    );
+    assert!(app.doc.modified);
+    // Nothing survived unchanged around the cursor, so it keeps its line
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 7);
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), content);
+
+    app.command_line = "fmt".to_string();
+    app.run_command_line();
+    assert_eq!(
+        app.status_message.as_ref().map(|(text, _)| text.as_str()),
+        Some("Already formatted")
+    );
+}
+
+#[test]
+fn integration_outline_reorganizes_sections_on_disk() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# A\n\na\n\n## A1\n\n# B\n\nb\n";
+    let (mut app, file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let send = |app: &mut App, c: char| {
+        handle_input(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), &ctx)
+            .expect("handle_input failed")
+    };
+    let on_disk = || std::fs::read_to_string(file.path()).unwrap();
+    app.show_toc = true;
+    app.toc_focus = true;
+    app.toc_selected = 2;
+
+    // B moves above A and its subsection, and stays selected
+    send(&mut app, 'K');
+    assert_eq!(on_disk(), "# B\n\nb\n\n# A\n\na\n\n## A1\n");
+    assert_eq!(app.toc_selected, 0);
+    assert_eq!(app.panes.focused_pane().unwrap().view.cursor_line, 0);
+    assert!(!app.doc.modified);
+
+    // Demoting A takes A1 along
+    app.toc_selected = 1;
+    send(&mut app, '>');
+    assert_eq!(on_disk(), "# B\n\nb\n\n## A\n\na\n\n### A1\n");
+    send(&mut app, 'J');
+    assert!(app.status_message.as_ref().is_some_and(|(m, _)| m.contains("No section below")));
+
+    // Undo steps back through both changes
+    send(&mut app, 'u');
+    assert_eq!(on_disk(), "# B\n\nb\n\n# A\n\na\n\n## A1\n");
+    send(&mut app, 'u');
+    assert_eq!(on_disk(), content);
+    send(&mut app, 'u');
+    assert!(app.status_message.as_ref().is_some_and(|(m, _)| m == "Nothing to undo"));
+
+    // Unsaved in-app edits would be written too, so nothing happens
+    app.doc.modified = true;
+    app.toc_selected = 2;
+    send(&mut app, 'K');
+    assert_eq!(on_disk(), content);
+
+    // Neither is a change made on disk by another program thrown away
+    app.doc.modified = false;
+    std::fs::write(file.path(), "# Theirs\n").unwrap();
+    app.doc.dirty_on_disk = true;
+    send(&mut app, 'K');
+    assert_eq!(on_disk(), "# Theirs\n");
+    assert_eq!(app.doc.rope.to_string(), content);
+    assert!(app.status_message.as_ref().is_some_and(|(m, _)| m.contains("changed on disk")));
+}
+
+#[test]
+fn integration_transclusion_shows_included_section() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(
+        dir.path().join("part.md"),
+        "# Part\n\n## Usage\n\nincluded words\n\n## Other\n\nleft out\n",
+    )
+    .unwrap();
+    let main = dir.path().join("main.md");
+    std::fs::write(&main, "# Main\n\nbefore\n\n![[part#usage]]\n\n![[missing]]\n\nafter\n").unwrap();
+    let (doc, _warnings) = Document::load(&main).expect("load");
+    let mut config = Config::default();
+    config.security.safe_mode = false;
+    let mut app = App::new(config, doc, vec![]);
+    assert_eq!(app.transclusions.len(), 2);
+
+    let (width, height) = (60u16, 20u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let row_text = |y: u16| (0..width).map(|x| buffer[(x, y)].symbol().to_string()).collect::<String>();
+    let find_row = |needle: &str| (0..height).find(|&y| row_text(y).contains(needle));
+
+    let included = find_row("included words").expect("included text rendered");
+    assert!(row_text(included).contains("│ included words"), "{:?}", row_text(included));
+    assert!(find_row("left out").is_none());
+    assert!(find_row("include: missing.md: file not found").is_some());
+    assert!(find_row("after").unwrap() > included);
+
+    // Turned off, only the include lines themselves are left
+    app.config.render.transclude = false;
+    app.refresh_transclusions();
+    assert!(app.transclusions.is_empty());
+
+    // Safe mode (the default) reads no other files
+    app.config.render.transclude = true;
+    app.config.security.safe_mode = true;
+    app.refresh_transclusions();
+    assert!(app.transclusions.is_empty());
+}
+
+#[test]
+fn integration_wiki_links_open_pages_and_headings() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(
+        dir.path().join("Other Page.md"),
+        "# Other\n\ntext\n\n## Second Part\n\nhere\n",
+    )
+    .unwrap();
+    let main = dir.path().join("main.md");
+    std::fs::write(
+        &main,
+        "# Main\n\n[[Other Page#second part|more]]\n\n[[Missing]]\n\n## End\n\n[[#Main]]\n",
+    )
+    .unwrap();
+    let (doc, _warnings) = Document::load(&main).expect("load");
+    let mut app = App::new(Config::default(), doc, vec![]);
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    // Only the missing page is reported broken, once the link worker has
+    // looked for the pages
+    assert!(app.broken_links.is_empty());
+    let result = app
+        .link_worker
+        .as_ref()
+        .and_then(|w| {
+            (0..500).find_map(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                w.try_recv_result()
+            })
+        })
+        .expect("link worker result");
+    app.apply_link_results(result);
+    assert_eq!(app.broken_links.len(), 1);
+    assert_eq!(app.broken_links[0].link.dest, "Missing");
+
+    app.jump_to_line(8);
+    app.follow_link_at_cursor();
+    assert_eq!(cursor(&app), 0);
+    assert_eq!(app.doc.path, main);
+
+    app.jump_to_line(4);
+    app.follow_link_at_cursor();
+    assert!(app
+        .status_message
+        .as_ref()
+        .is_some_and(|(m, _)| m == "No page named 'Missing'"));
+
+    app.jump_to_line(2);
+    app.follow_link_at_cursor();
+    assert!(app.doc.path.ends_with("Other Page.md"));
+    assert_eq!(app.buffer_count(), 2);
+    assert_eq!(cursor(&app), 4);
+}
+
+#[test]
+fn integration_plain_text_urls_and_paths() {
+    use ratatui::style::Modifier;
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    std::fs::write(dir.path().join("notes.md"), "# Notes\n\none\n\n## Part\n\ntwo\n").unwrap();
+    let main = dir.path().join("main.md");
+    std::fs::write(
+        &main,
+        "# Log\n\nSee ./notes.md:3 today\n\nAt https://example.com\n\n[guide](notes.md#part)\n",
+    )
+    .unwrap();
+    let (doc, _warnings) = Document::load(&main).expect("load");
+    let mut app = App::new(Config::default(), doc, vec![]);
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    // Detected paths are underlined like links
+    let (width, height) = (60u16, 10u16);
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let underlined: String = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&pos| buffer[pos].modifier.contains(Modifier::UNDERLINED))
+        .map(|pos| buffer[pos].symbol().to_string())
+        .collect();
+    assert_eq!(underlined, "./notes.md:3https://example.com");
+
+    // Web links need external commands, which safe mode refuses
+    app.jump_to_line(4);
+    app.follow_link_at_cursor();
+    assert!(app
+        .status_message
+        .as_ref()
+        .is_some_and(|(m, _)| m == "Not opening https://example.com (security.safe_mode = true)"));
+
+    // A path opens its file at the line
+    app.jump_to_line(2);
+    app.follow_link_at_cursor();
+    assert!(app.doc.path.ends_with("notes.md"));
+    assert_eq!(cursor(&app), 2);
+
+    // Links to files open them too, at the heading
+    app.switch_buffer(0);
+    app.jump_to_line(6);
+    app.follow_link_at_cursor();
+    assert!(app.doc.path.ends_with("notes.md"));
+    assert_eq!(app.buffer_count(), 2);
+    assert_eq!(cursor(&app), 4);
+}
+
+#[test]
+fn integration_scoped_search_stays_in_section_or_selection() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use mdx_tui::app::{PaneViewport, ScrollContext};
+    use mdx_tui::input::handle_input;
+
+    let content = "# A\n\nfoo a\n\n## A1\n\nfoo a1\n\n# B\n\nfoo b\n";
+    let (mut app, _file) = create_test_app(content);
+    let ctx = ScrollContext {
+        viewport: Some(PaneViewport { visible_height: 20, content_width: 80 }),
+        term_width: 82,
+        term_height: 23,
+    };
+    let press = |app: &mut App, code: KeyCode| {
+        handle_input(app, KeyEvent::new(code, KeyModifiers::NONE), &ctx).expect("handle_input failed")
+    };
+    let type_text = |app: &mut App, text: &str| {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    };
+    let cursor = |app: &App| app.panes.focused_pane().unwrap().view.cursor_line;
+
+    // g/ searches section A with its subsection, not B
+    app.jump_to_line(2);
+    type_text(&mut app, "g/foo");
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.search_matches, vec![2, 6]);
+    assert_eq!(app.search_scope_badge(), " [SECTION]");
+    type_text(&mut app, "nn");
+    assert_eq!(cursor(&app), 2);
+
+    // / alone searches everything again
+    type_text(&mut app, "/foo");
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.search_matches, vec![2, 6, 10]);
+    assert_eq!(app.search_scope_badge(), "");
+
+    // / in visual mode searches the selection, shown in the prompt
+    app.jump_to_line(6);
+    handle_input(&mut app, KeyEvent::new(KeyCode::Char('V'), KeyModifiers::SHIFT), &ctx).unwrap();
+    type_text(&mut app, "j/fo");
+    assert_eq!(app.search_matches, vec![6]);
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).expect("terminal");
+    terminal.draw(|frame| ui::draw(frame, &mut app)).expect("draw");
+    let buffer = terminal.backend().buffer().clone();
+    let status: String = (0..60).map(|x| buffer[(x, 11)].symbol().to_string()).collect();
+    assert!(status.starts_with("/fo [1/1] [SELECTION]"), "{:?}", status);
+
+    press(&mut app, KeyCode::Esc);
+    assert!(app.search_scope.is_none());
+}
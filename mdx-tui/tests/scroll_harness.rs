@@ -382,3 +382,34 @@ fn harness_empty_doc_is_not_panicking_on_pgdn_resize() {
     // Reaches here without panic → pass.
     assert!(app.doc.line_count() >= 1);
 }
+
+#[test]
+fn harness_resize_keeps_wrapped_cursor_on_screen() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    // Prose lines that fit one row when wide and wrap to two when narrow
+    let content: String = (1..=60)
+        .map(|i| format!("Line {:04} {}\n", i, "word ".repeat(14).trim_end()))
+        .collect();
+    let (mut app, _f) = new_app_with(&content);
+    app.config.toc.enabled = false;
+    app.show_toc = false;
+    app.on_resize(200, 30);
+    app.jump_to_line(19);
+    app.auto_scroll(27);
+    assert_eq!(focused_scroll(&app), 0);
+
+    // At 60 columns every line above the cursor takes two rows
+    app.on_resize(60, 30);
+    let scroll = focused_scroll(&app);
+    assert!(scroll > 0 && scroll <= 19, "scroll={scroll}");
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 30)).unwrap();
+    terminal
+        .draw(|frame| mdx_tui::ui::draw(frame, &mut app))
+        .unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(screen.contains("Line 0020"), "cursor line went off screen");
+}
@@ -0,0 +1,96 @@
+//! Golden-file rendering tests.
+//!
+//! Every `tests/snapshots/*.md` document is rendered at a few fixed widths
+//! to a text-with-style dump (see `mdx_tui::print::render_style_dump`) and
+//! compared against `tests/snapshots/<name>.<width>.txt`. The dump is laid
+//! out by the same row pipeline as the viewer's panes, so comments, folds,
+//! conflicts and list wrapping show up as the viewer draws them. A rendering
+//! change shows up as a diff of those files.
+//!
+//! After an intended change, regenerate the golden files and review the
+//! diff before committing:
+//!
+//! ```text
+//! UPDATE_SNAPSHOTS=1 cargo test -p mdx-tui --test snapshots
+//! ```
+
+use mdx_core::{Config, Document};
+use mdx_tui::App;
+use std::path::{Path, PathBuf};
+
+const WIDTHS: [u16; 2] = [40, 80];
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(snapshot_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn render(path: &Path, width: u16) -> String {
+    let (doc, _warnings) = Document::load(path).unwrap();
+    let mut app = App::new(Config::default(), doc, vec![]);
+    mdx_tui::print::render_style_dump(&mut app, width)
+}
+
+/// First differing line of two dumps, for the failure message
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e == a => continue,
+            (e, a) => {
+                return format!(
+                    "line {}:\n  expected: {:?}\n  actual:   {:?}",
+                    number,
+                    e.unwrap_or("<end>"),
+                    a.unwrap_or("<end>")
+                )
+            }
+        }
+    }
+    "no line differs (trailing newline?)".to_string()
+}
+
+#[test]
+fn snapshots_match_golden_files() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no fixtures in {:?}", snapshot_dir());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        for width in WIDTHS {
+            let golden = fixture.with_extension(format!("{}.txt", width));
+            let actual = render(fixture, width);
+            if update {
+                std::fs::write(&golden, &actual).unwrap();
+                continue;
+            }
+            let name = golden.file_name().unwrap().to_string_lossy().into_owned();
+            match std::fs::read_to_string(&golden) {
+                Ok(expected) if expected == actual => {}
+                Ok(expected) => failures.push(format!(
+                    "{}: {}",
+                    name,
+                    first_difference(&expected, &actual)
+                )),
+                Err(_) => failures.push(format!("{}: missing golden file", name)),
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "rendering differs from the golden files (rerun with UPDATE_SNAPSHOTS=1 if intended):\n{}",
+        failures.join("\n")
+    );
+}
@@ -1,16 +1,22 @@
 //! MDX - A fast TUI Markdown viewer with Vim-style navigation
 
+mod man;
+
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use mdx_core::{Config, Document};
+use clap::{CommandFactory, Parser, Subcommand};
+use mdx_core::{config::ThemeVariant, Config, Document};
+use mdx_tui::profile::StartupProfile;
 use mdx_tui::App;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 /// A fast TUI Markdown viewer
 #[derive(Parser, Debug)]
 #[command(name = "mdx")]
 #[command(author, version, about, long_about = None)]
 #[command(args_conflicts_with_subcommands = true)]
+#[command(disable_help_subcommand = true)]
+#[command(after_help = "Press F1 in the viewer or run `mdx help` for keybindings")]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -21,25 +27,125 @@ struct Cli {
 
 #[derive(Parser, Debug)]
 struct ViewArgs {
-    /// Path to markdown file (reads from stdin if not provided)
+    /// Markdown files to open, each optionally followed by +LINE; FILE:LINE
+    /// and FILE#anchor also work. A single directory opens every Markdown
+    /// file below it (reads from stdin if none are given)
     #[arg(value_name = "FILE")]
-    file: Option<PathBuf>,
+    files: Vec<PathBuf>,
 
     /// Disable security restrictions (use for trusted content only)
     #[arg(long)]
     insecure: bool,
+
+    /// Print a breakdown of startup timings (config, document, first draw) on exit
+    #[arg(long)]
+    profile_startup: bool,
+
+    /// Log debug events to mdx.log in the state directory and keep them for
+    /// the in-app log viewer (`:log`)
+    #[arg(long)]
+    verbose: bool,
+
+    /// Draw N frames off screen while paging through the document and
+    /// print draw-time statistics
+    #[arg(long, hide = true, value_name = "N")]
+    profile_frames: Option<usize>,
+
+    /// Print without ANSI colors when stdout is not a terminal
+    #[arg(long)]
+    no_color: bool,
+
+    /// Accept JSON-RPC requests (scrollTo, reload, open, quit) from an
+    /// editor on this Unix socket, one JSON object per line
+    #[arg(long, value_name = "SOCKET")]
+    listen: Option<PathBuf>,
+
+    /// Compare two files side by side instead of opening them
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with = "files")]
+    diff: Option<Vec<PathBuf>>,
+
+    /// Open the files as of this git revision (a commit, HEAD~3, a branch
+    /// or tag) instead of the working tree, read-only
+    #[arg(long, value_name = "REV", conflicts_with = "diff")]
+    rev: Option<String>,
+
+    /// Reopen the files that were open when mdx last saved its session
+    #[arg(long, conflicts_with = "files")]
+    restore: bool,
+
+    /// Output for terminal screen readers: no box-drawing borders, and
+    /// headings and tables announced in words (same as `render.a11y`)
+    #[arg(long)]
+    a11y: bool,
+
+    /// Color theme: dark, light, high-contrast, solarized, monochrome, or
+    /// deuteranopia-safe (overrides `theme` in the config)
+    #[arg(long, value_name = "NAME", value_parser = parse_theme)]
+    theme: Option<ThemeVariant>,
+}
+
+fn parse_theme(name: &str) -> Result<ThemeVariant, String> {
+    ThemeVariant::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = ThemeVariant::ALL.iter().map(|v| v.name()).collect();
+        format!("unknown theme; expected one of: {}", names.join(", "))
+    })
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Initialize default configuration file
     InitConfig,
+    /// Check Markdown files for structural problems (exits nonzero on errors)
+    Lint {
+        /// Markdown files to check
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Normalize Markdown: heading spacing, list markers, table padding,
+    /// and optionally paragraph wrapping. Prints the result unless --write
+    /// or --check is given
+    Fmt {
+        /// Markdown files to format
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Rewrite the files in place
+        #[arg(short, long, conflicts_with = "check")]
+        write: bool,
+
+        /// List files that aren't formatted and exit nonzero if there are any
+        #[arg(long)]
+        check: bool,
+
+        /// Wrap paragraphs at this column (0 keeps line breaks); overrides
+        /// `format.width`
+        #[arg(long, value_name = "COLUMNS")]
+        width: Option<usize>,
+    },
+    /// Print the keybinding reference
+    Help {
+        /// Print a man page (roff) instead, e.g. `mdx help --man | man -l -`
+        #[arg(long)]
+        man: bool,
+    },
+    /// Present a Markdown file as slides, split at `---` and top-level headings
+    Present {
+        /// Path to markdown file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Disable security restrictions (use for trusted content only)
+        #[arg(long)]
+        insecure: bool,
+    },
 }
 
 fn main() -> Result<()> {
-    let _ = env_logger::try_init();
+    let profile = StartupProfile::start();
 
     let cli = Cli::parse();
+    let verbose = cli.view.as_ref().is_some_and(|view| view.verbose);
+    mdx_tui::logging::init(verbose).context("Failed to set up logging")?;
 
     // Handle subcommands
     if let Some(command) = cli.command {
@@ -50,42 +156,375 @@ fn main() -> Result<()> {
                 println!("Created default config file at: {}", config_path.display());
                 return Ok(());
             }
+            Commands::Help { man } => {
+                if man {
+                    // Building adds the generated --help and --version flags
+                    let mut cmd = Cli::command();
+                    cmd.build();
+                    print!("{}", man::man_page(&cmd));
+                } else {
+                    print!("{}", mdx_tui::help::plain_text());
+                }
+                return Ok(());
+            }
+            Commands::Lint { files } => {
+                let has_errors = run_lint(&files)?;
+                std::process::exit(if has_errors { 1 } else { 0 });
+            }
+            Commands::Fmt {
+                files,
+                write,
+                check,
+                width,
+            } => {
+                let unformatted = run_fmt(&files, write, check, width)?;
+                std::process::exit(if check && unformatted { 1 } else { 0 });
+            }
+            Commands::Present { file, insecure } => {
+                let view_args = ViewArgs {
+                    files: vec![file],
+                    insecure,
+                    profile_startup: false,
+                    profile_frames: None,
+                    verbose: false,
+                    no_color: false,
+                    listen: None,
+                    diff: None,
+                    rev: None,
+                    restore: false,
+                    a11y: false,
+                    theme: None,
+                };
+                return run_viewer(view_args, profile, true);
+            }
         }
     }
 
     // Default behavior: open markdown file or read from stdin
     let view_args = cli.view.unwrap_or(ViewArgs {
-        file: None,
+        files: Vec::new(),
         insecure: false,
+        profile_startup: false,
+        profile_frames: None,
+        verbose: false,
+        no_color: false,
+        listen: None,
+        diff: None,
+        rev: None,
+        restore: false,
+        a11y: false,
+        theme: None,
     });
+    if let Some([old, new]) = view_args.diff.as_deref() {
+        return run_diff(old, new, view_args.insecure);
+    }
+    run_viewer(view_args, profile, false)
+}
+
+/// Load the configuration and documents and run the TUI, starting in
+/// presentation mode when `present` is set
+fn run_viewer(view_args: ViewArgs, mut profile: StartupProfile, present: bool) -> Result<()> {
+    use mdx_tui::location::{parse_line_arg, split_file_arg, Location};
+
+    // Each file may carry a start location: `FILE:LINE`, `FILE#anchor`, or
+    // a `+LINE` argument after it
+    let mut targets: Vec<(PathBuf, Option<Location>)> = Vec::new();
+    for arg in view_args.files {
+        let line = arg
+            .to_str()
+            .filter(|_| !arg.exists())
+            .and_then(parse_line_arg);
+        match (line, targets.last_mut()) {
+            (Some(line), Some((_, location))) => *location = Some(line),
+            (Some(_), None) => anyhow::bail!("{} must follow a file", arg.display()),
+            (None, _) => targets.push(split_file_arg(&arg)),
+        }
+    }
+
+    let mut session_corrupt = false;
+    if view_args.restore {
+        let state = Config::session_path()
+            .map(|path| mdx_core::session::SessionState::load(&path))
+            .transpose();
+        match state {
+            Ok(state) => {
+                let open = state.unwrap_or_default().open;
+                targets = open.into_iter().map(|file| (file, None)).collect();
+                if targets.is_empty() {
+                    anyhow::bail!("No files to restore from the last session");
+                }
+            }
+            // The corrupt file was moved aside; open the current directory
+            // instead, as `mdx .` would
+            Err(e) => {
+                tracing::warn!("{:#}", e);
+                session_corrupt = true;
+                targets = vec![(PathBuf::from("."), None)];
+            }
+        }
+    }
+
+    // A single directory opens every Markdown file below it
+    let directory = match targets.as_slice() {
+        [(dir, None)] if dir.is_dir() => {
+            let dir = dir
+                .canonicalize()
+                .with_context(|| format!("Failed to open directory: {}", dir.display()))?;
+            targets = mdx_tui::buffers::markdown_files(&dir)
+                .into_iter()
+                .map(|file| (file, None))
+                .collect();
+            if targets.is_empty() {
+                anyhow::bail!("No Markdown files in {}", dir.display());
+            }
+            Some(dir)
+        }
+        _ => None,
+    };
 
     // Load configuration
-    let (mut config, mut warnings) = Config::load().context("Failed to load configuration")?;
+    let first_file = targets.first().map(|(file, _)| file.as_path());
+    let (mut config, mut warnings) = profile
+        .measure("config load", || Config::load_for(first_file))
+        .context("Failed to load configuration")?;
 
-    // Override security settings if --insecure flag is set
+    // --insecure lifts safe mode; `no_exec` stays as configured, so code
+    // blocks only run when the config allows it too
     if view_args.insecure {
         config.security.safe_mode = false;
-        config.security.no_exec = false;
         // Clear security warnings when using --insecure
         warnings.clear();
     }
+    if view_args.a11y {
+        config.render.a11y = true;
+    }
+    if let Some(theme) = view_args.theme {
+        config.theme = theme;
+    }
 
-    // Load document from file or stdin
-    let (doc, doc_warnings) = if let Some(file_path) = view_args.file {
-        Document::load(&file_path)
-            .with_context(|| format!("Failed to load document: {}", file_path.display()))?
-    } else {
-        Document::from_stdin().context("Failed to read document from stdin")?
-    };
+    // Load documents from files or stdin
+    let max_file_bytes = config.document.max_file_bytes;
+    let mut docs = Vec::new();
+    if targets.is_empty() {
+        if view_args.rev.is_some() {
+            anyhow::bail!("--rev needs a file to read from git");
+        }
+        let (doc, doc_warnings) = profile
+            .measure("document load", || {
+                Document::from_stdin_with_limit(max_file_bytes)
+            })
+            .context("Failed to read document from stdin")?;
+        docs.push(doc);
+        warnings.extend(doc_warnings);
+    }
+    // In the viewer a file that fails to load gets an error screen with a
+    // retry; redirected output has nobody to retry, so it stops here
+    let interactive = std::io::stdout().is_terminal();
+    let mut load_errors = Vec::new();
+    for (index, (file_path, _)) in targets.iter().enumerate() {
+        let result = profile
+            .measure("document load", || match view_args.rev.as_deref() {
+                Some(rev) => load_revision(file_path, rev, max_file_bytes),
+                None => Document::load_with_limit(file_path, max_file_bytes),
+            })
+            .with_context(|| format!("Failed to load document: {}", file_path.display()));
+        match result {
+            Ok((doc, doc_warnings)) => {
+                docs.push(doc);
+                // Combine warnings from config and documents
+                warnings.extend(doc_warnings);
+            }
+            // Retrying would read the working tree, not the revision
+            Err(e) if interactive && view_args.rev.is_none() => {
+                docs.push(Document::unloaded(file_path, max_file_bytes));
+                load_errors.push((index, e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let mut docs = docs.into_iter();
+    let doc = docs.next().context("No document to open")?;
 
-    // Combine warnings from config and document
-    warnings.extend(doc_warnings);
+    // Redirected output: print the rendered documents like `bat` would
+    // instead of starting the TUI
+    if !interactive && view_args.profile_frames.is_none() {
+        let width = mdx_tui::print::output_width(config.render.max_width);
+        let color = !view_args.no_color && std::env::var_os("NO_COLOR").is_none();
+        let mut app = App::new(config, doc, warnings);
+        docs.for_each(|doc| app.add_buffer(doc));
+        app.directory = directory;
+        for index in 0..app.buffer_count() {
+            app.switch_buffer(index);
+            mdx_tui::print::print_document(&mut app, width, color)
+                .context("Failed to write rendered document")?;
+        }
+        return Ok(());
+    }
 
     // Create app with warnings
-    let app = App::new(config, doc, warnings);
+    let mut app = profile.measure("app init", || App::new(config, doc, warnings));
+    docs.for_each(|doc| app.add_buffer(doc));
+    if let Some(dir) = directory {
+        app.set_directory(dir);
+    }
+    app.insecure = view_args.insecure;
+    if view_args.profile_frames.is_none() {
+        app.session_path = Config::session_path();
+        app.restore_session();
+    }
+    if session_corrupt {
+        app.set_error_message("Session file was corrupt; nothing to restore");
+    }
+    if view_args.profile_startup {
+        app.startup_profile = Some(profile);
+    }
+    for (index, error) in load_errors {
+        app.switch_buffer(index);
+        app.load_error = Some(mdx_tui::app::LoadError::new(&app.doc.path, &error));
+    }
+    for (index, (_, location)) in targets.iter().enumerate().rev() {
+        if let Some(location) = location {
+            app.switch_buffer(index);
+            app.open_at(location);
+        }
+    }
+    app.switch_buffer(0);
+    if let Some(frames) = view_args.profile_frames {
+        let (width, height) = mdx_tui::profile::frame_size();
+        let frame_profile = mdx_tui::profile::profile_frames(&mut app, frames, width, height)
+            .context("Failed to profile frames")?;
+        print!("{}", frame_profile.report());
+        return Ok(());
+    }
+    if let Some(socket) = &view_args.listen {
+        app.listener = Some(mdx_tui::listen::Listener::bind(socket)?);
+    }
+    if present {
+        app.quit_after_presentation = true;
+        app.start_presentation();
+    }
 
     // Run TUI
     mdx_tui::run(app).context("TUI application error")?;
 
     Ok(())
 }
+
+/// Load a file as of a git revision (`mdx --rev REV FILE`)
+#[cfg(feature = "git")]
+fn load_revision(
+    path: &Path,
+    rev: &str,
+    max_file_bytes: u64,
+) -> Result<(Document, Vec<mdx_core::SecurityEvent>)> {
+    Ok((Document::from_revision(path, rev, max_file_bytes)?, Vec::new()))
+}
+
+#[cfg(not(feature = "git"))]
+fn load_revision(
+    _path: &Path,
+    _rev: &str,
+    _max_file_bytes: u64,
+) -> Result<(Document, Vec<mdx_core::SecurityEvent>)> {
+    anyhow::bail!("--rev needs mdx built with the git feature")
+}
+
+/// Compare two files side by side (`mdx --diff OLD NEW`). Redirected
+/// output gets a plain unified listing instead.
+#[cfg(feature = "git")]
+fn run_diff(old: &Path, new: &Path, insecure: bool) -> Result<()> {
+    let (mut config, mut warnings) =
+        Config::load_for(Some(new)).context("Failed to load configuration")?;
+    if insecure {
+        config.security.safe_mode = false;
+        warnings.clear();
+    }
+
+    let max_file_bytes = config.document.max_file_bytes;
+    let mut load = |path: &Path| -> Result<Document> {
+        let (doc, doc_warnings) = Document::load_with_limit(path, max_file_bytes)
+            .with_context(|| format!("Failed to load document: {}", path.display()))?;
+        warnings.extend(doc_warnings);
+        Ok(doc)
+    };
+    let old_doc = load(old)?;
+    let new_doc = load(new)?;
+    let view = mdx_tui::diff_view::DiffView::new(&old_doc, &new_doc);
+
+    if !std::io::stdout().is_terminal() {
+        print!("{}", view.plain_text());
+        return Ok(());
+    }
+
+    let mut app = App::new(config, new_doc, warnings);
+    app.insecure = insecure;
+    app.diff_view = Some(view);
+    mdx_tui::run(app).context("TUI application error")?;
+    Ok(())
+}
+
+#[cfg(not(feature = "git"))]
+fn run_diff(_old: &Path, _new: &Path, _insecure: bool) -> Result<()> {
+    anyhow::bail!("--diff needs mdx built with the git feature")
+}
+
+/// Lint each file, printing `file:line: severity[rule]: message` lines.
+/// Returns whether any error-level issue was found.
+/// Format each file, printing it, rewriting it (`write`), or naming it if
+/// it would change (`check`). Returns whether any file wasn't formatted.
+fn run_fmt(files: &[PathBuf], write: bool, check: bool, width: Option<usize>) -> Result<bool> {
+    use mdx_core::format::{format_markdown, FormatOptions};
+
+    let (config, _warnings) = Config::load().context("Failed to load configuration")?;
+    let options = FormatOptions {
+        width: width.unwrap_or(config.format.width),
+        flavor: config.render.markdown.flavor,
+    };
+    let mut unformatted = false;
+
+    for file in files {
+        let text = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let formatted = format_markdown(&text, &options);
+        let changed = formatted != text;
+        unformatted |= changed;
+        if check {
+            if changed {
+                println!("{}", file.display());
+            }
+        } else if write {
+            if changed {
+                std::fs::write(file, &formatted)
+                    .with_context(|| format!("Failed to write {}", file.display()))?;
+                eprintln!("Formatted {}", file.display());
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    Ok(unformatted)
+}
+
+fn run_lint(files: &[PathBuf]) -> Result<bool> {
+    use mdx_core::lint::{lint, Severity};
+
+    let (config, _warnings) = Config::load().context("Failed to load configuration")?;
+    let mut errors = 0;
+    let mut warnings = 0;
+
+    for file in files {
+        let (doc, _doc_warnings) =
+            Document::load_with_limit(file, config.document.max_file_bytes)
+                .with_context(|| format!("Failed to load document: {}", file.display()))?;
+        for issue in lint(&doc, config.links.vault_root.as_deref()) {
+            match issue.severity {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+            println!("{}:{}", file.display(), issue);
+        }
+    }
+
+    eprintln!("{} error(s), {} warning(s)", errors, warnings);
+    Ok(errors > 0)
+}
@@ -0,0 +1,67 @@
+//! Man page generation for `mdx help --man`
+
+use clap::Command;
+use mdx_tui::help::roff_escape;
+
+/// Render a roff man page for the CLI, with the keybinding reference
+pub fn man_page(cmd: &Command) -> String {
+    let name = cmd.get_name();
+    let version = cmd.get_version().unwrap_or("");
+    let about = cmd.get_about().map(|a| a.to_string()).unwrap_or_default();
+
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        name.to_uppercase(),
+        name,
+        version
+    );
+    out.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        name,
+        roff_escape(&about)
+    ));
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n.B {}\n[\\fIOPTIONS\\fR] [\\fIFILE\\fR]\n.br\n.B {}\n\\fICOMMAND\\fR [\\fIARGS\\fR]\n",
+        name, name
+    ));
+    out.push_str(
+        ".SH DESCRIPTION\n\
+         Opens a Markdown file, or standard input, in a terminal viewer with\n\
+         Vim\\-style navigation. When standard output is not a terminal the\n\
+         rendered document is printed instead.\n",
+    );
+
+    out.push_str(".SH OPTIONS\n");
+    for arg in cmd.get_arguments().filter(|a| !a.is_positional()) {
+        let mut flags = Vec::new();
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("\\-{}", short));
+        }
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("\\-\\-{}", roff_escape(long)));
+        }
+        let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+        out.push_str(&format!(".TP\n.B {}\n{}\n", flags.join(", "), roff_escape(&help)));
+    }
+
+    out.push_str(".SH COMMANDS\n");
+    for sub in cmd.get_subcommands() {
+        let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+        out.push_str(&format!(
+            ".TP\n.B {} {}\n{}\n",
+            name,
+            roff_escape(sub.get_name()),
+            roff_escape(&about)
+        ));
+    }
+
+    out.push_str(&mdx_tui::help::man_keys_section());
+
+    out.push_str(
+        ".SH FILES\n\
+         Configuration is read from \\fImdx/mdx.toml\\fR in the platform config\n\
+         directory (for example \\fI~/.config/mdx/mdx.toml\\fR on Linux).\n\
+         Run \\fBmdx init\\-config\\fR to create it.\n",
+    );
+    out
+}